@@ -0,0 +1,116 @@
+use field_type::FieldType;
+use proc_macro2::TokenStream;
+use query::QueryContext;
+
+/// The `#[proptest]` attribute of a field, when deriving `Arbitrary` needs help with its type.
+///
+/// Custom scalars are aliases of types defined by the caller, so they are generated from their `Default` implementation instead of requiring an `Arbitrary` implementation. Input fields referring to their own type are always empty, to keep the generated values finite.
+pub(crate) fn field_attribute(
+    context: &QueryContext,
+    field_type: &FieldType,
+    is_recursive: bool,
+) -> TokenStream {
+    if !context.arbitrary {
+        return quote!();
+    }
+
+    if is_recursive {
+        let empty = match field_type {
+            FieldType::Optional(_) => "::std::option::Option::None",
+            FieldType::Vector(_) => "::std::vec::Vec::new()",
+            FieldType::Named(_) => return quote!(),
+        };
+        // Non-list recursive fields are boxed.
+        let value = if field_type.is_indirected() {
+            empty.to_string()
+        } else {
+            format!("::std::boxed::Box::new({})", empty)
+        };
+        return quote!(#[proptest(value = #value)]);
+    }
+
+    if !context
+        .schema
+        .scalars
+        .contains_key(field_type.inner_name_str())
+    {
+        return quote!();
+    }
+
+    let strategy = strategy(context, field_type);
+    quote!(#[proptest(strategy = #strategy)])
+}
+
+fn strategy(context: &QueryContext, field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Named(name) => match context.big_number_strategy(name) {
+            Some(strategy) => format!(
+                "::proptest::strategy::LazyJust::new(|| {})",
+                strategy.default_value()
+            ),
+            None => {
+                "::proptest::strategy::LazyJust::new(::std::default::Default::default)".to_string()
+            }
+        },
+        FieldType::Optional(inner) => {
+            format!("::proptest::option::of({})", strategy(context, inner))
+        }
+        FieldType::Vector(inner) => format!(
+            "::proptest::collection::vec({}, 0..4)",
+            strategy(context, inner)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scalars::Scalar;
+    use schema::Schema;
+    use std::cell::Cell;
+
+    #[test]
+    fn custom_scalars_are_generated_from_their_default_value() {
+        let mut schema = Schema::new();
+        schema.scalars.insert(
+            "DateTime",
+            Scalar {
+                name: "DateTime",
+                description: None,
+                specified_by: None,
+                is_required: Cell::new(false),
+            },
+        );
+        let mut context = QueryContext::new_empty(&schema);
+        let field_type = FieldType::Optional(Box::new(FieldType::Vector(Box::new(
+            FieldType::Named("DateTime"),
+        ))));
+
+        assert!(field_attribute(&context, &field_type, false).is_empty());
+
+        context.arbitrary = true;
+        assert_eq!(
+            field_attribute(&context, &field_type, false).to_string(),
+            "# [ proptest ( strategy = \"::proptest::option::of(::proptest::collection::vec(::proptest::strategy::LazyJust::new(::std::default::Default::default), 0..4))\" ) ]"
+        );
+        assert!(field_attribute(&context, &FieldType::Named("String"), false).is_empty());
+        assert_eq!(
+            field_attribute(
+                &context,
+                &FieldType::Optional(Box::new(FieldType::Named("Node"))),
+                true
+            )
+            .to_string(),
+            "# [ proptest ( value = \"::std::boxed::Box::new(::std::option::Option::None)\" ) ]"
+        );
+        assert_eq!(
+            field_attribute(
+                &context,
+                &FieldType::Vector(Box::new(FieldType::Named("Node"))),
+                true
+            )
+            .to_string(),
+            "# [ proptest ( value = \"::std::vec::Vec::new()\" ) ]"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+// The base64 helpers of the bytes scalars. Not a module of the crate: `bytes::base64_helpers` includes the source in the generated code, and the tests of `bytes` include it to run it.
+
+fn base64_encode(bytes: &[u8]) -> ::std::string::String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = ::std::string::String::with_capacity(bytes.len() / 3 * 4 + 4);
+
+    for chunk in bytes.chunks(3) {
+        let buffer = (<u32 as ::std::convert::From<u8>>::from(chunk[0]) << 16)
+            | (<u32 as ::std::convert::From<u8>>::from(*chunk.get(1).unwrap_or(&0)) << 8)
+            | <u32 as ::std::convert::From<u8>>::from(*chunk.get(2).unwrap_or(&0));
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(buffer >> (18 - 6 * index) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+fn base64_decode(
+    encoded: &str,
+) -> ::std::result::Result<::std::vec::Vec<u8>, ::std::string::String> {
+    let unpadded = encoded.trim_end_matches('=');
+    let padding = encoded.len() - unpadded.len();
+    // A lone character has fewer bits than a byte, and padding completes a group of four. Written without `div_ceil` and `is_multiple_of`, which are too recent for some of the crates this is generated in.
+    if unpadded.len() % 4 == 1 || (padding > 0 && (padding > 2 || unpadded.len() % 4 + padding != 4))
+    {
+        return ::std::result::Result::Err(::std::format!(
+            "invalid base64 length: {}",
+            encoded.len()
+        ));
+    }
+
+    let mut bytes = ::std::vec::Vec::with_capacity(unpadded.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for c in unpadded.chars() {
+        let value = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' | '-' => 62,
+            '/' | '_' => 63,
+            _ => {
+                return ::std::result::Result::Err(::std::format!(
+                    "invalid base64 character: `{}`",
+                    c
+                ))
+            }
+        };
+        buffer = (buffer << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    ::std::result::Result::Ok(bytes)
+}
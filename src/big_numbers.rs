@@ -0,0 +1,139 @@
+use failure;
+use proc_macro2::{Ident, TokenStream};
+
+/// How a big-number scalar, like `BigInt` or `Long`, is represented in the generated code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BigNumberStrategy {
+    /// An alias of `i128`.
+    I128,
+    /// An alias of `u64`.
+    U64,
+    /// A newtype around the digits, deserialized from JSON strings as well as numbers.
+    String,
+    /// An alias of `serde_json::Number`, which keeps every digit when serde_json's `arbitrary_precision` feature is enabled.
+    Number,
+}
+
+impl ::std::str::FromStr for BigNumberStrategy {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "i128" => Ok(BigNumberStrategy::I128),
+            "u64" => Ok(BigNumberStrategy::U64),
+            "string" => Ok(BigNumberStrategy::String),
+            "number" => Ok(BigNumberStrategy::Number),
+            other => Err(format_err!(
+                "Invalid big number representation: `{}`. Valid values are `i128`, `u64`, `string` and `number`.",
+                other
+            )),
+        }
+    }
+}
+
+impl BigNumberStrategy {
+    /// The type the scalar is an alias of, or the scalar itself for the newtype.
+    pub(crate) fn rust_type(self, name: &Ident) -> TokenStream {
+        match self {
+            BigNumberStrategy::I128 => quote!(i128),
+            BigNumberStrategy::U64 => quote!(u64),
+            BigNumberStrategy::String => quote!(#name),
+            BigNumberStrategy::Number => quote!(::serde_json::Number),
+        }
+    }
+
+    /// The value of the scalar in mocks and generated test values. `serde_json::Number` has no `Default` implementation.
+    pub(crate) fn default_value(self) -> TokenStream {
+        match self {
+            BigNumberStrategy::Number => quote!(::serde_json::Number::from(0u8)),
+            _ => quote!(::std::default::Default::default()),
+        }
+    }
+
+    /// The definition of the scalar, with its doc attributes.
+    pub(crate) fn scalar_definition(self, name: &Ident, docs: TokenStream) -> TokenStream {
+        if self != BigNumberStrategy::String {
+            let ty = self.rust_type(name);
+            return quote!(#docs type #name = #ty;);
+        }
+
+        quote! {
+            #docs
+            #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::cmp::PartialEq, ::std::cmp::Eq, ::std::hash::Hash, ::std::default::Default, ::serde_derive::Serialize)]
+            pub struct #name(pub ::std::string::String);
+
+            impl<'de> ::serde::Deserialize<'de> for #name {
+                fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+                    struct DigitsVisitor;
+
+                    impl<'de> ::serde::de::Visitor<'de> for DigitsVisitor {
+                        type Value = ::std::string::String;
+
+                        fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                            f.write_str("a number, or a string of digits")
+                        }
+
+                        fn visit_str<E: ::serde::de::Error>(self, value: &str) -> ::std::result::Result<::std::string::String, E> {
+                            ::std::result::Result::Ok(::std::string::ToString::to_string(&value))
+                        }
+
+                        fn visit_i64<E: ::serde::de::Error>(self, value: i64) -> ::std::result::Result<::std::string::String, E> {
+                            ::std::result::Result::Ok(::std::string::ToString::to_string(&value))
+                        }
+
+                        fn visit_u64<E: ::serde::de::Error>(self, value: u64) -> ::std::result::Result<::std::string::String, E> {
+                            ::std::result::Result::Ok(::std::string::ToString::to_string(&value))
+                        }
+
+                        fn visit_i128<E: ::serde::de::Error>(self, value: i128) -> ::std::result::Result<::std::string::String, E> {
+                            ::std::result::Result::Ok(::std::string::ToString::to_string(&value))
+                        }
+
+                        fn visit_u128<E: ::serde::de::Error>(self, value: u128) -> ::std::result::Result<::std::string::String, E> {
+                            ::std::result::Result::Ok(::std::string::ToString::to_string(&value))
+                        }
+                    }
+
+                    deserializer.deserialize_any(DigitsVisitor).map(#name)
+                }
+            }
+
+            impl ::std::fmt::Display for #name {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                    f.write_str(&self.0)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::Span;
+
+    #[test]
+    fn big_number_strategies_parse() {
+        assert_eq!(
+            "i128".parse::<BigNumberStrategy>().unwrap(),
+            BigNumberStrategy::I128
+        );
+        assert_eq!(
+            " number ".parse::<BigNumberStrategy>().unwrap(),
+            BigNumberStrategy::Number
+        );
+        assert!("f64".parse::<BigNumberStrategy>().is_err());
+
+        let name = Ident::new("BigInt", Span::call_site());
+        assert_eq!(
+            BigNumberStrategy::U64
+                .scalar_definition(&name, quote!())
+                .to_string(),
+            "type BigInt = u64 ;"
+        );
+        assert!(BigNumberStrategy::String
+            .scalar_definition(&name, quote!())
+            .to_string()
+            .contains("pub struct BigInt ( pub :: std :: string :: String ) ;"));
+    }
+}
@@ -0,0 +1,103 @@
+use failure;
+
+/// Command line arguments for a subcommand: positional arguments, `--name value` (or `--name=value`) options, and boolean `--flags`.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    /// Every `--name` is expected to be followed by a value, except for the names in `boolean_flags`.
+    pub(crate) fn parse(args: Vec<String>, boolean_flags: &[&str]) -> Result<Args, failure::Error> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg);
+                continue;
+            }
+
+            let name = &arg[2..];
+
+            if boolean_flags.contains(&name) {
+                parsed.flags.push(name.to_owned());
+            } else if let Some(idx) = name.find('=') {
+                parsed
+                    .options
+                    .push((name[..idx].to_owned(), name[idx + 1..].to_owned()));
+            } else {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format_err!("Missing value for --{}", name))?;
+                parsed.options.push((name.to_owned(), value));
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    pub(crate) fn positional(&self, idx: usize) -> Option<&str> {
+        self.positional.get(idx).map(String::as_str)
+    }
+
+    pub(crate) fn positionals(&self) -> &[String] {
+        &self.positional
+    }
+
+    /// The last value given for the `name` option.
+    pub(crate) fn value(&self, name: &str) -> Option<&str> {
+        self.values(name).pop()
+    }
+
+    /// All the values given for the `name` option, in order.
+    pub(crate) fn values(&self, name: &str) -> Vec<&str> {
+        self.options
+            .iter()
+            .filter(|(option, _)| option == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub(crate) fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn args_parsing_works() {
+        let parsed = Args::parse(
+            args(&[
+                "http://example.com",
+                "--header",
+                "A: b",
+                "--verbose",
+                "--header=C: d",
+            ]),
+            &["verbose"],
+        )
+        .unwrap();
+
+        assert_eq!(parsed.positional(0), Some("http://example.com"));
+        assert_eq!(parsed.positional(1), None);
+        assert_eq!(parsed.values("header"), vec!["A: b", "C: d"]);
+        assert_eq!(parsed.value("header"), Some("C: d"));
+        assert!(parsed.flag("verbose"));
+        assert!(!parsed.flag("header"));
+    }
+
+    #[test]
+    fn args_parsing_fails_on_missing_value() {
+        assert!(Args::parse(args(&["--output"]), &[]).is_err());
+    }
+}
@@ -0,0 +1,37 @@
+use args::Args;
+use failure;
+use graphql_client_codegen::validation::validate;
+use std::path::Path;
+
+/// `check <query_path>... --schema-path <path>`
+///
+/// Prints every problem found in the query files as `path:line:column: message`, and fails if there is any.
+pub(crate) fn run(args: Vec<String>) -> Result<(), failure::Error> {
+    let args = Args::parse(args, &[])?;
+    let schema_path = Path::new(
+        args.value("schema-path")
+            .ok_or_else(|| format_err!("check: missing --schema-path"))?,
+    );
+
+    if args.positionals().is_empty() {
+        return Err(format_err!("check: missing the path of the query file(s)"));
+    }
+
+    let mut problems = 0;
+
+    for query_path in args.positionals() {
+        let query = ::std::fs::read_to_string(query_path)
+            .map_err(|err| format_err!("Could not read {}: {}", query_path, err))?;
+
+        for diagnostic in validate(&query, schema_path) {
+            problems += 1;
+            println!("{}:{}", query_path, diagnostic);
+        }
+    }
+
+    match problems {
+        0 => Ok(()),
+        1 => Err(format_err!("Found 1 problem.")),
+        n => Err(format_err!("Found {} problems.", n)),
+    }
+}
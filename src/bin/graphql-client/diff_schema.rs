@@ -0,0 +1,34 @@
+use args::Args;
+use failure;
+use graphql_client_codegen::schema::{diff_files, Criticality};
+use std::path::Path;
+
+/// `diff-schema <old_schema_path> <new_schema_path>`
+///
+/// Prints every change as `criticality: message`, and fails if there is any breaking change, so it can guard CI jobs.
+pub(crate) fn run(args: Vec<String>) -> Result<(), failure::Error> {
+    let args = Args::parse(args, &[])?;
+    let (old, new) = match (args.positional(0), args.positional(1)) {
+        (Some(old), Some(new)) => (Path::new(old), Path::new(new)),
+        _ => {
+            return Err(format_err!(
+                "diff-schema: expected the paths of the old and the new schema"
+            ))
+        }
+    };
+
+    let changes = diff_files(old, new)?;
+    for change in &changes {
+        println!("{}", change);
+    }
+
+    match changes
+        .iter()
+        .filter(|change| change.criticality == Criticality::Breaking)
+        .count()
+    {
+        0 => Ok(()),
+        1 => Err(format_err!("Found 1 breaking change.")),
+        n => Err(format_err!("Found {} breaking changes.", n)),
+    }
+}
@@ -0,0 +1,29 @@
+use args::Args;
+use failure;
+use generate::{generate_code, options_from_args, OPTIONS_FLAGS};
+use graphql_client_codegen::dump::format_rust_code;
+use std::path::Path;
+
+/// `expand <query_path> --schema-path <path> [options]`
+///
+/// Prints the formatted code the derive generates for a query file, to debug type mismatches without expanding the whole crate. Takes the same options as `generate`.
+pub(crate) fn run(args: Vec<String>) -> Result<(), failure::Error> {
+    let args = Args::parse(args, OPTIONS_FLAGS)?;
+    let schema_path = Path::new(
+        args.value("schema-path")
+            .ok_or_else(|| format_err!("expand: missing --schema-path"))?,
+    );
+    let query_path = args
+        .positional(0)
+        .ok_or_else(|| format_err!("expand: missing the path of the query file"))?;
+
+    let (_, code) = generate_code(
+        Path::new(query_path),
+        schema_path,
+        options_from_args(&args)?,
+    )?;
+
+    print!("{}", format_rust_code(&code));
+
+    Ok(())
+}
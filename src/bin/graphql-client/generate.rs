@@ -0,0 +1,410 @@
+use args::Args;
+use failure;
+use graphql_client_codegen::deprecation::DeprecationStrategy;
+use graphql_client_codegen::derive_profile::DeriveProfile;
+use graphql_client_codegen::indirection::Indirection;
+use graphql_client_codegen::namespace::Namespace;
+use graphql_client_codegen::operation_features::OperationFeatures;
+use graphql_client_codegen::query_text::QueryText;
+use graphql_client_codegen::schema_format::SchemaFormat;
+use graphql_client_codegen::union_mode::UnionMode;
+use graphql_client_codegen::{generate_module_token_stream, GraphQLClientDeriveOptions};
+use graphql_parser;
+use heck::SnakeCase;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use syn;
+
+/// `generate <query_path>... --schema-path <path> [options]`
+///
+/// Writes one `.rs` file per query file, containing the same code the derive would generate, plus the structs the `GraphQLQuery` impls are attached to. A query file with several operations and no `--operation-name` becomes a single module with all of them, and their `Operations` and `OperationVariables` enums.
+pub(crate) fn run(args: Vec<String>) -> Result<(), failure::Error> {
+    let flags: Vec<&str> = OPTIONS_FLAGS
+        .iter()
+        .cloned()
+        .chain(Some("no-formatting"))
+        .collect();
+    let args = Args::parse(args, &flags)?;
+    let schema_path = Path::new(
+        args.value("schema-path")
+            .ok_or_else(|| format_err!("generate: missing --schema-path"))?,
+    );
+
+    if args.positionals().is_empty() {
+        return Err(format_err!(
+            "generate: missing the path of the query file(s)"
+        ));
+    }
+
+    for query_path in args.positionals() {
+        let query_path = Path::new(query_path);
+        let options = options_from_args(&args)?;
+        let output_directory = args
+            .value("output-directory")
+            .map(Path::new)
+            .or_else(|| query_path.parent())
+            .unwrap_or_else(|| Path::new("."));
+
+        let output_path = generate_file(query_path, schema_path, output_directory, options)?;
+
+        if !args.flag("no-formatting") {
+            format_file(&output_path);
+        }
+
+        println!("Wrote {}", output_path.display());
+    }
+
+    Ok(())
+}
+
+fn generate_file(
+    query_path: &Path,
+    schema_path: &Path,
+    output_directory: &Path,
+    options: GraphQLClientDeriveOptions,
+) -> Result<PathBuf, failure::Error> {
+    let (module_name, code) = generate_code(query_path, schema_path, options)?;
+    ::std::fs::create_dir_all(output_directory).map_err(|err| {
+        format_err!(
+            "Could not create the output directory {}: {}",
+            output_directory.display(),
+            err
+        )
+    })?;
+    let output_path = output_directory.join(format!("{}.rs", module_name));
+    ::std::fs::write(
+        &output_path,
+        format!(
+            "// Generated by graphql-client from {}. Do not edit.\n\n{}",
+            query_path.display(),
+            code
+        ),
+    )
+    .map_err(|err| format_err!("Could not write {}: {}", output_path.display(), err))?;
+
+    Ok(output_path)
+}
+
+/// The name of the generated module, and its unformatted code.
+pub(crate) fn generate_code(
+    query_path: &Path,
+    schema_path: &Path,
+    mut options: GraphQLClientDeriveOptions,
+) -> Result<(String, String), failure::Error> {
+    let query_string = ::std::fs::read_to_string(query_path)
+        .map_err(|err| format_err!("Could not read {}: {}", query_path.display(), err))?;
+    let operation_names = operation_names(&query_string, options.operation_name.as_ref())?;
+
+    // Without an operation name, the module is named after the query file.
+    let module_name = match options.operation_name {
+        Some(ref operation_name) => operation_name.to_snake_case(),
+        None => query_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| format_err!("Invalid query path: {}", query_path.display()))?
+            .to_snake_case(),
+    };
+    options.module_name = Some(module_name.clone());
+    // The file and the structs are named like the items the namespace prefixes.
+    let namespaced_module_name = match options.namespace {
+        Some(ref namespace) => namespace.module_name(None, &module_name)?,
+        None => module_name,
+    };
+    let struct_names: Vec<(String, Option<&str>)> = operation_names
+        .iter()
+        .map(|name| {
+            let struct_name = options
+                .namespace
+                .as_ref()
+                .and_then(|namespace| namespace.struct_name(name))
+                .unwrap_or_else(|| name.clone());
+            let feature = options
+                .operation_features
+                .as_ref()
+                .and_then(|features| features.feature(name));
+            (struct_name, feature)
+        })
+        .collect();
+    let cfg_attribute = match options.cfg {
+        Some(ref predicate) => format!("#[cfg({})]\n", predicate),
+        None => String::new(),
+    };
+
+    let structs: String = struct_names
+        .iter()
+        .map(|(name, feature)| {
+            let feature_attribute = match feature {
+                Some(feature) => format!("#[cfg(feature = {:?})]\n", feature),
+                None => String::new(),
+            };
+            format!(
+                "{}{}pub struct {};\n",
+                cfg_attribute, feature_attribute, name
+            )
+        })
+        .collect();
+    let tokens =
+        generate_module_token_stream(query_path.to_path_buf(), schema_path, Some(options))?;
+
+    Ok((namespaced_module_name, format!("{}\n{}\n", structs, tokens)))
+}
+
+/// The flags among the code generation options, which take no value.
+pub(crate) const OPTIONS_FLAGS: &[&str] = &[
+    "input-builders",
+    "sse-subscriptions",
+    "get-requests",
+    "graphql-values",
+    "cassettes",
+    "mocks",
+    "default-impls",
+    "list-iterators",
+    "arbitrary",
+    "specta",
+    "json-validation",
+    "query-extensions",
+    "variable-specs",
+    "partial-data",
+    "error-paths",
+    "pretty-print",
+    "lenient-scalars",
+    "nullable-defaults",
+    "deny-unknown-fields",
+    "typename-enums",
+    "borrowed-variables",
+    "no-std",
+    "federation-representations",
+    "refetch-queries",
+    "selection-metadata",
+];
+
+/// The code generation options shared by the commands that generate code.
+pub(crate) fn options_from_args(args: &Args) -> Result<GraphQLClientDeriveOptions, failure::Error> {
+    let deprecation_strategy = match args.value("deprecation-strategy") {
+        Some(strategy) => Some(strategy.parse::<DeprecationStrategy>()?),
+        None => None,
+    };
+
+    let derive_profile = match args.value("derive-profile") {
+        Some(profile) => profile.parse::<DeriveProfile>()?,
+        None => DeriveProfile::default(),
+    };
+
+    let namespace = match args.value("namespace") {
+        Some(namespace) => Some(namespace.parse::<Namespace>()?),
+        None => None,
+    };
+
+    let operation_features = match args.value("operation-features") {
+        Some(features) => Some(features.parse::<OperationFeatures>()?),
+        None => None,
+    };
+
+    let nested_indirection = match args.value("nested-indirection") {
+        Some(indirection) => Some(indirection.parse::<Indirection>()?),
+        None => None,
+    };
+
+    let union_mode = match args.value("union-mode") {
+        Some(mode) => mode.parse::<UnionMode>()?,
+        None => UnionMode::default(),
+    };
+
+    let query_text = match args.value("query-text") {
+        Some(query_text) => query_text.parse::<QueryText>()?,
+        None => QueryText::default(),
+    };
+
+    let schema_format = match args.value("schema-format") {
+        Some(format) => Some(format.parse::<SchemaFormat>()?),
+        None => None,
+    };
+
+    let max_selection_depth = match args.value("max-selection-depth") {
+        Some(depth) => Some(depth.parse::<usize>().map_err(|_| {
+            format_err!(
+                "Invalid selection depth: `{}`. Expected a positive number.",
+                depth
+            )
+        })?),
+        None => None,
+    };
+
+    Ok(GraphQLClientDeriveOptions {
+        operation_name: args.value("operation-name").map(String::from),
+        struct_name: None,
+        module_name: None,
+        additional_derives: args.value("additional-derives").map(String::from),
+        derive_profile,
+        deprecation_strategy,
+        deprecation_overrides: args.value("deprecation-overrides").map(String::from),
+        specified_by_overrides: args.value("specified-by-overrides").map(String::from),
+        big_number_scalars: args.value("big-number-scalars").map(String::from),
+        bytes_scalars: args.value("bytes-scalars").map(String::from),
+        module_visibility: syn::parse_str(args.value("module-visibility").unwrap_or("pub"))?,
+        input_builders: args.flag("input-builders"),
+        client_schema_path: args.value("client-schema-path").map(PathBuf::from),
+        sse_subscriptions: args.flag("sse-subscriptions"),
+        get_requests: args.flag("get-requests"),
+        graphql_values: args.flag("graphql-values"),
+        cassettes: args.flag("cassettes"),
+        mocks: args.flag("mocks"),
+        default_impls: args.flag("default-impls"),
+        default_variants: args.value("default-variants").map(String::from),
+        list_iterators: args.flag("list-iterators"),
+        arbitrary: args.flag("arbitrary"),
+        specta: args.flag("specta"),
+        json_validation: args.flag("json-validation"),
+        query_extensions: args.flag("query-extensions"),
+        variable_specs: args.flag("variable-specs"),
+        partial_data: args.flag("partial-data"),
+        error_paths: args.flag("error-paths"),
+        pretty_print: args.flag("pretty-print"),
+        nested_indirection,
+        response_string_type: args.value("response-string-type").map(String::from),
+        serde_adapters: args.value("serde-adapters").map(String::from),
+        lenient_scalars: args.flag("lenient-scalars"),
+        nullable_defaults: args.flag("nullable-defaults"),
+        deny_unknown_fields: args.flag("deny-unknown-fields"),
+        sensitive_fields: args.value("sensitive-fields").map(String::from),
+        union_mode,
+        typename_enums: args.flag("typename-enums"),
+        borrowed_variables: args.flag("borrowed-variables"),
+        codegen_stats: args.value("codegen-stats").map(String::from),
+        cfg: args.value("cfg").map(String::from),
+        no_std: args.flag("no-std"),
+        span: None,
+        namespace,
+        operation_features,
+        additional_query_paths: args
+            .values("additional-query-path")
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        fragments_path: args.value("fragments-path").map(String::from),
+        track_files: false,
+        schema_format,
+        max_selection_depth,
+        hooks: None,
+        json_schema_directory: args.value("json-schema-directory").map(PathBuf::from),
+        federation_representations: args.flag("federation-representations"),
+        refetch_queries: args.flag("refetch-queries"),
+        selection_metadata: args.flag("selection-metadata"),
+        operation_lock: args.value("operation-lock").map(PathBuf::from),
+        call_hook: args.value("call-hook").map(String::from),
+        query_text,
+    })
+}
+
+/// The names of the operations code will be generated for. Mirrors the derive: the operation named `operation_name`, or the first one if there is no such operation, or all of them when no operation name is given.
+fn operation_names(
+    query_string: &str,
+    operation_name: Option<&String>,
+) -> Result<Vec<String>, failure::Error> {
+    use graphql_parser::query::{Definition, OperationDefinition};
+
+    let query = graphql_parser::parse_query(query_string)?;
+    let names: Vec<String> = query
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Operation(OperationDefinition::Query(q)) => q.name.clone(),
+            Definition::Operation(OperationDefinition::Mutation(m)) => m.name.clone(),
+            Definition::Operation(OperationDefinition::Subscription(s)) => s.name.clone(),
+            _ => None,
+        })
+        .collect();
+
+    match operation_name {
+        Some(operation_name) if names.contains(operation_name) => Ok(vec![operation_name.clone()]),
+        Some(_) => Ok(names.into_iter().take(1).collect()),
+        None => Ok(names),
+    }
+}
+
+/// Format the file with rustfmt, if it is installed. Unformatted code is still valid, so failures are only reported.
+fn format_file(path: &Path) {
+    match Command::new("rustfmt")
+        .arg("--edition")
+        .arg("2018")
+        .arg(path)
+        .status()
+    {
+        Ok(ref status) if status.success() => (),
+        Ok(_) => eprintln!("Warning: rustfmt failed on {}", path.display()),
+        Err(err) => eprintln!(
+            "Warning: could not run rustfmt ({}), {} is not formatted",
+            err,
+            path.display()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUERY: &str = r#"
+        query Heroes { hero { name } }
+        mutation CreateReview { createReview { stars } }
+    "#;
+
+    #[test]
+    fn operation_names_works() {
+        assert_eq!(
+            operation_names(QUERY, None).unwrap(),
+            vec!["Heroes".to_string(), "CreateReview".to_string()]
+        );
+        assert_eq!(
+            operation_names(QUERY, Some(&"CreateReview".to_string())).unwrap(),
+            vec!["CreateReview".to_string()]
+        );
+        assert_eq!(
+            operation_names(QUERY, Some(&"Unknown".to_string())).unwrap(),
+            vec!["Heroes".to_string()]
+        );
+    }
+
+    #[test]
+    fn generate_file_creates_the_output_directory() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+        let output_directory = ::std::env::temp_dir().join("graphql_client_cli_generate/nested");
+        let _ = ::std::fs::remove_dir_all(output_directory.parent().unwrap());
+
+        let output_path = generate_file(
+            &root.join("star_wars_query.graphql"),
+            &root.join("star_wars_schema.graphql"),
+            &output_directory,
+            GraphQLClientDeriveOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(output_path, output_directory.join("star_wars_query.rs"));
+        assert!(output_path.is_file());
+    }
+    #[test]
+    fn documents_with_several_operations_get_one_module() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+        let directory = ::std::env::temp_dir().join("graphql_client_cli_operations");
+        ::std::fs::create_dir_all(&directory).unwrap();
+        let query_path = directory.join("reviews.graphql");
+        ::std::fs::write(
+            &query_path,
+            "query Heroes { hero { __typename name } }\nmutation CreateReview($review: ReviewInput!) { createReview(review: $review) { stars } }",
+        )
+        .unwrap();
+
+        let (module_name, code) = generate_code(
+            &query_path,
+            &root.join("star_wars_schema.graphql"),
+            GraphQLClientDeriveOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(module_name, "reviews");
+        assert!(code.starts_with("pub struct Heroes;\npub struct CreateReview;\n"));
+        assert!(code.contains("pub const HEROES_OPERATION_NAME"));
+        assert!(code.contains("pub const CREATE_REVIEW_OPERATION_NAME"));
+        assert!(code.contains("pub enum OperationVariables"));
+    }
+}
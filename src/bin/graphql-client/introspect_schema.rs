@@ -0,0 +1,136 @@
+use args::Args;
+use failure;
+use graphql_client_codegen::introspection_response::IntrospectionResponse;
+use graphql_client_codegen::INTROSPECTION_QUERY;
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT};
+use serde_json;
+
+/// `introspect-schema <url> [--header 'Name: value']... [--output <path>]`
+pub(crate) fn run(args: Vec<String>) -> Result<(), failure::Error> {
+    let args = Args::parse(args, &[])?;
+    let url = args
+        .positional(0)
+        .ok_or_else(|| format_err!("introspect-schema: missing the URL of the GraphQL server"))?;
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+    for header in args.values("header") {
+        let (name, value) = parse_header(header)?;
+        headers.append(name, value);
+    }
+
+    let body = json!({
+        "query": INTROSPECTION_QUERY,
+        "operationName": "IntrospectionQuery",
+    });
+
+    let request_error =
+        |err: reqwest::Error| format_err!("The introspection request to {} failed: {}", url, err);
+    let response = Client::new()
+        .post(url)
+        .headers(headers)
+        .json(&body)
+        .send()
+        .and_then(reqwest::blocking::Response::error_for_status)
+        .and_then(reqwest::blocking::Response::bytes)
+        .map_err(request_error)?;
+
+    let response = check_response(&response)?;
+    let schema = serde_json::to_string_pretty(&response)?;
+
+    match args.value("output") {
+        Some(path) => ::std::fs::write(path, schema + "\n")?,
+        None => println!("{}", schema),
+    }
+
+    Ok(())
+}
+
+/// The name and value of a `Name: value` header.
+fn parse_header(header: &str) -> Result<(HeaderName, HeaderValue), failure::Error> {
+    let invalid = || {
+        format_err!(
+            "Invalid header: `{}`. Headers should look like `Name: value`.",
+            header
+        )
+    };
+    let idx = header.find(':').ok_or_else(invalid)?;
+    let name = HeaderName::from_bytes(header[..idx].trim().as_bytes()).map_err(|_| invalid())?;
+    let value = HeaderValue::from_str(header[idx + 1..].trim()).map_err(|_| invalid())?;
+    Ok((name, value))
+}
+
+/// The errors of a response.
+#[derive(Deserialize)]
+struct ResponseErrors {
+    #[serde(default)]
+    errors: Option<Vec<ResponseError>>,
+}
+
+#[derive(Deserialize)]
+struct ResponseError {
+    message: Option<String>,
+}
+
+/// The introspection response, unless the server responded with errors or without a schema.
+fn check_response(body: &[u8]) -> Result<IntrospectionResponse, failure::Error> {
+    let errors: ResponseErrors = serde_json::from_slice(body)
+        .map_err(|err| format_err!("The server did not respond with JSON: {}", err))?;
+    let errors = errors.errors.unwrap_or_default();
+    if !errors.is_empty() {
+        let messages: Vec<&str> = errors
+            .iter()
+            .filter_map(|error| error.message.as_deref())
+            .collect();
+        return Err(format_err!(
+            "The server responded with errors:\n{}",
+            messages.join("\n")
+        ));
+    }
+
+    let response: IntrospectionResponse = serde_json::from_slice(body)
+        .map_err(|err| format_err!("The server did not respond with a schema: {}", err))?;
+    match response {
+        IntrospectionResponse::FullResponse(ref full_response)
+            if full_response.data.schema.is_some() =>
+        {
+            Ok(response)
+        }
+        _ => Err(format_err!("The response does not contain data.__schema")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_header_works() {
+        let (name, value) = parse_header("Authorization: Bearer abcd").unwrap();
+        assert_eq!(
+            (name.as_str(), value.to_str().unwrap()),
+            ("authorization", "Bearer abcd")
+        );
+        assert!(parse_header("Authorization").is_err());
+        assert!(parse_header(": value").is_err());
+        assert!(parse_header("Authorization: a\nX-Other: b").is_err());
+    }
+
+    #[test]
+    fn check_response_works() {
+        assert!(check_response(br#"{ "data": { "__schema": { "types": [] } } }"#).is_ok());
+        assert!(check_response(br#"{ "data": { "__schema": null } }"#).is_err());
+        assert!(
+            check_response(br#"{ "errors": null, "data": { "__schema": { "types": [] } } }"#)
+                .is_ok()
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                check_response(br#"{ "errors": [{ "message": "Unauthorized" }], "data": null }"#)
+                    .unwrap_err()
+            ),
+            "The server responded with errors:\nUnauthorized"
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Command line companion to graphql_client: fetch schemas from servers and work with query files without going through the derive.
+
+#[macro_use]
+extern crate failure;
+extern crate graphql_client_codegen;
+extern crate graphql_parser;
+extern crate heck;
+extern crate reqwest;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+extern crate syn;
+
+mod args;
+mod check;
+mod diff_schema;
+mod expand;
+mod generate;
+mod introspect_schema;
+mod server_stubs;
+
+const USAGE: &str = r#"graphql-client
+
+USAGE:
+    graphql-client <COMMAND> [ARGS]
+
+COMMANDS:
+    introspect-schema <url> [--header 'Name: value']... [--output <path>]
+        Run the introspection query against a GraphQL server and write the resulting schema.json.
+
+    check <query_path>... --schema-path <path>
+        Report all the problems in the query files that would prevent code generation.
+
+    diff-schema <old_schema_path> <new_schema_path>
+        Print the changes between two versions of a schema, and fail if any of them is breaking.
+
+    expand <query_path> --schema-path <path> [generate options]
+        Print the formatted code generated for a query file.
+
+    generate <query_path>... --schema-path <path> [--schema-format sdl|json]
+             [--output-directory <path>] [--operation-name <name>]
+             [--additional-derives <derives>] [--deprecation-strategy allow|deny|warn|warn_always]
+             [--deprecation-overrides 'Type.field=strategy,...'] [--derive-profile minimal|standard|full]
+             [--specified-by-overrides 'url=RustType,...'] [--big-number-scalars 'Scalar=i128|u64|string|number,...']
+             [--bytes-scalars 'Scalar,...']
+             [--module-visibility <visibility>] [--input-builders] [--client-schema-path <path>]
+             [--sse-subscriptions] [--get-requests] [--graphql-values] [--cassettes]
+             [--mocks] [--default-impls]
+             [--default-variants 'Type=Variant,...'] [--list-iterators] [--arbitrary] [--specta]
+             [--json-validation] [--pretty-print] [--borrowed-variables] [--codegen-stats stderr|<path>]
+             [--error-paths] [--partial-data] [--variable-specs] [--query-extensions]
+             [--cfg <predicate>] [--no-std] [--namespace <prefix>]
+             [--operation-features 'Operation=feature,...']
+             [--additional-query-path <path or pattern>]... [--fragments-path <pattern>]
+             [--max-selection-depth <depth>] [--json-schema-directory <path>]
+             [--federation-representations] [--refetch-queries] [--selection-metadata]
+             [--operation-lock <path>] [--call-hook <path>] [--nested-indirection box|arc[:<min fields>]]
+             [--response-string-type <RustType>] [--serde-adapters 'Scalar=path,Type.field=path,...']
+             [--lenient-scalars] [--nullable-defaults] [--deny-unknown-fields]
+             [--union-mode exhaustive|non-exhaustive] [--query-text embedded|compressed|<loader path>]
+             [--typename-enums] [--sensitive-fields 'Type,Type.field,...'] [--no-formatting]
+        Write the code the derive would generate for each query file to a .rs file, in a single
+        module for all the operations of the file unless --operation-name picks one of them.
+
+    server-stubs <schema_path> [--module-name <name>] [--output <path>]
+        Print the resolver traits of a server for the schema, one per object type, or write them to a file.
+
+    help
+        Print this message.
+"#;
+
+fn main() {
+    if let Err(err) = run(::std::env::args().skip(1).collect()) {
+        eprintln!("{}", err);
+        ::std::process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), failure::Error> {
+    let mut args = args.into_iter();
+
+    match args.next().as_deref() {
+        Some("check") => check::run(args.collect()),
+        Some("diff-schema") => diff_schema::run(args.collect()),
+        Some("expand") => expand::run(args.collect()),
+        Some("generate") => generate::run(args.collect()),
+        Some("introspect-schema") => introspect_schema::run(args.collect()),
+        Some("server-stubs") => server_stubs::run(args.collect()),
+        Some("help") | Some("--help") | Some("-h") | None => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => Err(format_err!("Unknown command: {}\n\n{}", other, USAGE)),
+    }
+}
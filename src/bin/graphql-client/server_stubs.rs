@@ -0,0 +1,35 @@
+use args::Args;
+use failure;
+use graphql_client_codegen::dump::format_rust_code;
+use graphql_client_codegen::server_stubs::generate_server_stubs;
+use std::path::Path;
+
+/// `server-stubs <schema_path> [--module-name <name>] [--output <path>]`
+///
+/// Prints the formatted resolver traits of a server for the schema, or writes them to the output file.
+pub(crate) fn run(args: Vec<String>) -> Result<(), failure::Error> {
+    let args = Args::parse(args, &[])?;
+    let schema_path = args
+        .positional(0)
+        .ok_or_else(|| format_err!("server-stubs: missing the path of the schema"))?;
+    let module_name = args.value("module-name").unwrap_or("server");
+
+    let code =
+        format_rust_code(&generate_server_stubs(Path::new(schema_path), module_name)?.to_string());
+
+    match args.value("output") {
+        Some(output) => {
+            ::std::fs::write(
+                output,
+                format!(
+                    "// Generated by graphql-client from {}. Do not edit.\n\n{}",
+                    schema_path, code
+                ),
+            )?;
+            println!("Wrote {}", output);
+        }
+        None => print!("{}", code),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use proc_macro2::{Ident, TokenStream};
+
+/// The definition of a scalar holding base64-encoded binary data: a newtype around `Vec<u8>`, (de)serialized from base64 strings with the helpers from [base64_helpers].
+pub(crate) fn bytes_scalar_definition(name: &Ident, docs: TokenStream) -> TokenStream {
+    quote! {
+        #docs
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::cmp::PartialEq, ::std::cmp::Eq, ::std::hash::Hash, ::std::default::Default)]
+        pub struct #name(pub ::std::vec::Vec<u8>);
+
+        impl ::serde::Serialize for #name {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_str(&base64_encode(&self.0))
+            }
+        }
+
+        impl<'de> ::serde::Deserialize<'de> for #name {
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+                let encoded = <::std::string::String as ::serde::Deserialize>::deserialize(deserializer)?;
+                base64_decode(&encoded)
+                    .map(#name)
+                    .map_err(<D::Error as ::serde::de::Error>::custom)
+            }
+        }
+
+        impl ::std::ops::Deref for #name {
+            type Target = ::std::vec::Vec<u8>;
+
+            fn deref(&self) -> &::std::vec::Vec<u8> {
+                &self.0
+            }
+        }
+
+        impl ::std::convert::From<::std::vec::Vec<u8>> for #name {
+            fn from(bytes: ::std::vec::Vec<u8>) -> Self {
+                #name(bytes)
+            }
+        }
+    }
+}
+
+/// The base64 encoding and decoding functions used by the bytes scalars, so the generated code does not depend on a base64 crate. Generated once per module.
+///
+/// Decoding accepts the standard and URL-safe alphabets, with or without padding.
+pub(crate) fn base64_helpers() -> TokenStream {
+    include_str!("base64_helpers.rs")
+        .parse()
+        .expect("the base64 helpers are valid Rust")
+}
+
+#[cfg(test)]
+mod tests {
+    include!("base64_helpers.rs");
+
+    /// The test vectors of RFC 4648.
+    const VECTORS: &[(&str, &str)] = &[
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn base64_helpers_round_trip() {
+        for (decoded, encoded) in VECTORS {
+            assert_eq!(base64_encode(decoded.as_bytes()), *encoded);
+            assert_eq!(base64_decode(encoded).unwrap(), decoded.as_bytes());
+            assert_eq!(
+                base64_decode(encoded.trim_end_matches('=')).unwrap(),
+                decoded.as_bytes()
+            );
+        }
+        assert_eq!(
+            base64_decode("-_8=").unwrap(),
+            base64_decode("+/8=").unwrap()
+        );
+    }
+
+    #[test]
+    fn base64_decode_rejects_invalid_input() {
+        for encoded in &[
+            "Z", "Zm9vY", "Zg=", "Zm8==", "Zm9v=", "Zm9v==", "Z===", "Zm9v!",
+        ] {
+            assert!(base64_decode(encoded).is_err(), "{}", encoded);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use operations::Operation;
+use proc_macro2::{Ident, Span, TokenStream};
+use query_text::QueryText;
+
+/// The `request_hash` function of the cassettes, FNV-1a like the hashes of the operation lock file. Generated once per module.
+pub(crate) fn request_hash_helper() -> TokenStream {
+    quote! {
+        /// The FNV-1a hash of a request body, in hexadecimal.
+        fn request_hash(body: &str) -> ::std::string::String {
+            let hash = ::std::iter::Iterator::fold(body.bytes(), 0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+                (hash ^ <u64 as ::std::convert::From<u8>>::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+            ::std::format!("{:016x}", hash)
+        }
+    }
+}
+
+/// The `Cassette` of an operation: a response of the server recorded with the hash and the variables of the request it answered, to serialize to a file and replay in tests.
+///
+/// The response is recorded from the body the server sent, as the response types only derive `Deserialize`.
+pub(crate) fn cassette_type(
+    operation: &Operation,
+    multiple_operations: bool,
+    query_text: &QueryText,
+) -> TokenStream {
+    let prefix = if multiple_operations {
+        operation.name.as_str()
+    } else {
+        ""
+    };
+    let cassette_name = Ident::new(&format!("{}Cassette", prefix), Span::call_site());
+    let variables_struct_name = Ident::new(&format!("{}Variables", prefix), Span::call_site());
+    let response_data_struct_name =
+        Ident::new(&format!("{}ResponseData", prefix), Span::call_site());
+    let query = query_text.expression();
+    let operation_name = &operation.name;
+
+    quote! {
+        /// A response of the server to the operation, recorded with the request it answered, to replay in tests.
+        #[derive(::serde_derive::Serialize, ::serde_derive::Deserialize, ::std::fmt::Debug, ::std::clone::Clone, ::std::cmp::PartialEq)]
+        pub struct #cassette_name {
+            /// The FNV-1a hash of the body of the request, in hexadecimal.
+            pub request_hash: ::std::string::String,
+            /// The variables of the request.
+            pub variables: ::serde_json::Value,
+            /// The body of the response.
+            pub response: ::serde_json::Value,
+        }
+
+        impl #cassette_name {
+            /// The hash of the body of the request running the operation with these variables.
+            pub fn request_hash(variables: &#variables_struct_name) -> ::std::result::Result<::std::string::String, ::serde_json::Error> {
+                let body: ::graphql_client::QueryBody<&#variables_struct_name> = ::graphql_client::QueryBody {
+                    variables,
+                    query: #query,
+                    operation_name: #operation_name,
+                };
+                ::std::result::Result::Ok(request_hash(&::serde_json::to_string(&body)?))
+            }
+
+            /// Record the body of the response of the server to the request with these variables. It must deserialize to the response types.
+            pub fn record(variables: &#variables_struct_name, response: &str) -> ::std::result::Result<Self, ::serde_json::Error> {
+                let response: ::serde_json::Value = ::serde_json::from_str(response)?;
+                ::serde_json::from_value::<::graphql_client::Response<#response_data_struct_name>>(::std::clone::Clone::clone(&response))?;
+                ::std::result::Result::Ok(#cassette_name {
+                    request_hash: Self::request_hash(variables)?,
+                    variables: ::serde_json::to_value(variables)?,
+                    response,
+                })
+            }
+
+            /// Whether the cassette answers the request with these variables.
+            pub fn matches(&self, variables: &#variables_struct_name) -> bool {
+                Self::request_hash(variables)
+                    .map(|hash| hash == self.request_hash)
+                    .unwrap_or(false)
+            }
+
+            /// The cassette answering the request with these variables, among the cassettes of a file.
+            pub fn find<'a>(cassettes: &'a [Self], variables: &#variables_struct_name) -> ::std::option::Option<&'a Self> {
+                ::std::iter::Iterator::find(&mut cassettes.iter(), |cassette| cassette.matches(variables))
+            }
+
+            /// The recorded response.
+            pub fn replay(&self) -> ::std::result::Result<::graphql_client::Response<#response_data_struct_name>, ::serde_json::Error> {
+                ::serde_json::from_value(::std::clone::Clone::clone(&self.response))
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use constants::{CLIENT_DIRECTIVE, MOCK_DIRECTIVE, SENSITIVE_DIRECTIVE};
+use graphql_parser::query::*;
+use std::collections::BTreeSet;
+
+pub(crate) fn is_client_field(directives: &[Directive]) -> bool {
+    directives
+        .iter()
+        .any(|directive| directive.name == CLIENT_DIRECTIVE)
+}
+
+/// Whether the field is marked `@sensitive`, a directive only the generated code knows about.
+pub(crate) fn is_sensitive_field(directives: &[Directive]) -> bool {
+    directives
+        .iter()
+        .any(|directive| directive.name == SENSITIVE_DIRECTIVE)
+}
+
+/// Whether the field has `@mock` hints for the response fixtures, which the server does not know about.
+fn has_mock_hints(directives: &[Directive]) -> bool {
+    directives
+        .iter()
+        .any(|directive| directive.name == MOCK_DIRECTIVE)
+}
+
+/// The query to send to the server: `query_string` itself, or `document` printed without its `@client` fields and `@sensitive` and `@mock` directives if it has some.
+pub(crate) fn server_query(query_string: &str, document: &Document) -> String {
+    if !has_client_fields(document) {
+        return query_string.to_owned();
+    }
+
+    ::string_literals::print_query(&strip_client_fields(document))
+}
+
+fn has_client_fields(document: &Document) -> bool {
+    document
+        .definitions
+        .iter()
+        .any(|definition| match definition {
+            Definition::Operation(operation) => {
+                selection_set_has_client_fields(operation_selection_set(operation))
+            }
+            Definition::Fragment(fragment) => {
+                selection_set_has_client_fields(&fragment.selection_set)
+            }
+        })
+}
+
+fn selection_set_has_client_fields(selection_set: &SelectionSet) -> bool {
+    selection_set.items.iter().any(|item| match item {
+        Selection::Field(field) => {
+            is_client_field(&field.directives)
+                || is_sensitive_field(&field.directives)
+                || has_mock_hints(&field.directives)
+                || selection_set_has_client_fields(&field.selection_set)
+        }
+        Selection::InlineFragment(inline) => selection_set_has_client_fields(&inline.selection_set),
+        Selection::FragmentSpread(_) => false,
+    })
+}
+
+/// Remove the `@client` fields from `document`, along with the selections and fragments left empty, and the `@sensitive` and `@mock` directives.
+pub(crate) fn strip_client_fields(document: &Document) -> Document {
+    let mut document = document.clone();
+    let mut empty_fragments = BTreeSet::new();
+
+    // Emptying a fragment empties the selections only spreading it, so we strip until nothing changes.
+    loop {
+        let empty_fragments_count = empty_fragments.len();
+        for definition in &mut document.definitions {
+            if let Definition::Fragment(fragment) = definition {
+                strip_selection_set(&mut fragment.selection_set, &empty_fragments);
+                if fragment.selection_set.items.is_empty() {
+                    empty_fragments.insert(fragment.name.clone());
+                }
+            }
+        }
+        if empty_fragments.len() == empty_fragments_count {
+            break;
+        }
+    }
+
+    for definition in &mut document.definitions {
+        if let Definition::Operation(operation) = definition {
+            strip_selection_set(operation_selection_set_mut(operation), &empty_fragments);
+        }
+    }
+
+    document.definitions.retain(|definition| match definition {
+        Definition::Fragment(fragment) => !empty_fragments.contains(&fragment.name),
+        Definition::Operation(_) => true,
+    });
+
+    document
+}
+
+fn strip_selection_set(selection_set: &mut SelectionSet, empty_fragments: &BTreeSet<String>) {
+    let items = ::std::mem::take(&mut selection_set.items);
+    selection_set.items = items
+        .into_iter()
+        .filter_map(|item| match item {
+            Selection::Field(mut field) => {
+                if is_client_field(&field.directives) {
+                    return None;
+                }
+                field.directives.retain(|directive| {
+                    directive.name != SENSITIVE_DIRECTIVE && directive.name != MOCK_DIRECTIVE
+                });
+                let is_composite = !field.selection_set.items.is_empty();
+                strip_selection_set(&mut field.selection_set, empty_fragments);
+                if is_composite && field.selection_set.items.is_empty() {
+                    None
+                } else {
+                    Some(Selection::Field(field))
+                }
+            }
+            Selection::FragmentSpread(spread) => {
+                if empty_fragments.contains(&spread.fragment_name) {
+                    None
+                } else {
+                    Some(Selection::FragmentSpread(spread))
+                }
+            }
+            Selection::InlineFragment(mut inline) => {
+                strip_selection_set(&mut inline.selection_set, empty_fragments);
+                if inline.selection_set.items.is_empty() {
+                    None
+                } else {
+                    Some(Selection::InlineFragment(inline))
+                }
+            }
+        })
+        .collect();
+}
+
+pub(crate) fn operation_selection_set(operation: &OperationDefinition) -> &SelectionSet {
+    match operation {
+        OperationDefinition::SelectionSet(selection_set) => selection_set,
+        OperationDefinition::Query(query) => &query.selection_set,
+        OperationDefinition::Mutation(mutation) => &mutation.selection_set,
+        OperationDefinition::Subscription(subscription) => &subscription.selection_set,
+    }
+}
+
+fn operation_selection_set_mut(operation: &mut OperationDefinition) -> &mut SelectionSet {
+    match operation {
+        OperationDefinition::SelectionSet(selection_set) => selection_set,
+        OperationDefinition::Query(query) => &mut query.selection_set,
+        OperationDefinition::Mutation(mutation) => &mut mutation.selection_set,
+        OperationDefinition::Subscription(subscription) => &mut subscription.selection_set,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphql_parser;
+
+    #[test]
+    fn client_fields_are_not_sent_to_the_server() {
+        let query_string = r#"
+            query Profile {
+              viewer {
+                login
+                isSelected @client
+                preferences @client { theme }
+                ...LocalState
+              }
+            }
+
+            fragment LocalState on User {
+              cartItems @client
+            }
+        "#;
+        let document = graphql_parser::parse_query(query_string).unwrap();
+
+        assert_eq!(
+            server_query(query_string, &document),
+            "query Profile {\n  viewer {\n    login\n  }\n}\n"
+        );
+    }
+
+    #[test]
+    fn directives_on_operations_and_fragments_are_kept() {
+        let query_string = r#"
+            query Profile @cached(ttl: 60) {
+              viewer {
+                isSelected @client
+                ...Login
+              }
+            }
+
+            fragment Login on User @tag(name: "auth") {
+              login
+            }
+        "#;
+        let document = graphql_parser::parse_query(query_string).unwrap();
+
+        assert_eq!(
+            server_query(query_string, &document),
+            "query Profile @cached(ttl: 60) {\n  viewer {\n    ...Login\n  }\n}\n\nfragment Login on User @tag(name: \"auth\") {\n  login\n}\n"
+        );
+    }
+
+    #[test]
+    fn mock_hints_are_not_sent_to_the_server() {
+        let query_string = r#"query Profile { viewer { login @mock(value: "alice") } }"#;
+        let document = graphql_parser::parse_query(query_string).unwrap();
+
+        assert_eq!(
+            server_query(query_string, &document),
+            "query Profile {\n  viewer {\n    login\n  }\n}\n"
+        );
+    }
+
+    #[test]
+    fn queries_without_client_fields_are_sent_verbatim() {
+        let query_string = "query Profile { viewer { login } }";
+        let document = graphql_parser::parse_query(query_string).unwrap();
+
+        assert_eq!(server_query(query_string, &document), query_string);
+    }
+}
@@ -1,12 +1,22 @@
-use deprecation;
+use enums::ENUMS_PREFIX;
+use error_paths;
 use failure;
+use federation;
 use fragments::GqlFragment;
 use graphql_parser::query;
+use hooks::ItemKind;
+use incremental;
+use json_validation;
+use mocks;
 use operations::Operation;
 use proc_macro2::{Ident, Span, TokenStream};
 use query::QueryContext;
 use schema;
 use selection::Selection;
+use shared;
+use sse;
+use std::collections::BTreeSet;
+use GraphQLClientDeriveOptions;
 
 /// Selects the first operation matching `struct_name` or the first one. Returns `None` when the query document defines no operation.
 pub(crate) fn select_operation<'query>(
@@ -33,19 +43,97 @@ pub(crate) fn all_operations(query: &query::Document) -> Vec<Operation> {
     operations
 }
 
+/// The code generated for an operation: the items the other operations of a module can share, like enums and input objects, each with a key naming it, and the items of the operation itself.
+pub(crate) struct OperationItems {
+    pub(crate) shared: Vec<(String, TokenStream)>,
+    pub(crate) own: TokenStream,
+}
+
 /// The main code generation function.
 pub(crate) fn response_for_query(
     schema: &schema::Schema,
     query: &query::Document,
     operation: &Operation,
-    additional_derives: Option<String>,
-    deprecation_strategy: deprecation::DeprecationStrategy,
+    options: &GraphQLClientDeriveOptions,
     multiple_operation: bool,
 ) -> Result<TokenStream, failure::Error> {
+    let items = operation_items(schema, query, operation, options, multiple_operation)?;
+    let shared = items.shared.into_iter().map(|(_, tokens)| tokens);
+    let own = items.own;
+    Ok(quote!(#(#shared)* #own))
+}
+
+/// The code of an operation, with the items it can share with the other operations of the module set apart.
+pub(crate) fn operation_items(
+    schema: &schema::Schema,
+    query: &query::Document,
+    operation: &Operation,
+    options: &GraphQLClientDeriveOptions,
+    multiple_operation: bool,
+) -> Result<OperationItems, failure::Error> {
+    let operation = &operation.with_root_fragments_flattened(schema);
+    let incremental = incremental::uses_incremental_delivery(query, &operation.name)?;
+    // The user can determine what to do about deprecations.
+    let deprecation_strategy = options.deprecation_strategy.clone().unwrap_or_default();
     let mut context = QueryContext::new(schema, deprecation_strategy);
+    context.input_builders = options.input_builders;
+    context.graphql_values = options.graphql_values;
+    context.mocks = options.mocks;
+    context.default_impls = options.default_impls;
+    context.list_iterators = options.list_iterators;
+    context.partial_data = options.partial_data;
+    context.error_paths = options.error_paths;
+    context.arbitrary = options.arbitrary;
+    context.specta = options.specta;
+    context.json_validation = options.json_validation;
+    context.pretty_print = options.pretty_print;
+    context.nested_indirection = options.nested_indirection.clone();
+    context.lenient_scalars = options.lenient_scalars;
+    context.nullable_defaults = options.nullable_defaults;
+    context.deny_unknown_fields = options.deny_unknown_fields;
+    if let Some(ref sensitive_fields) = options.sensitive_fields {
+        context.sensitive_fields = ::redaction::SensitiveFields::parse(sensitive_fields);
+    }
+    context.union_mode = options.union_mode;
+    if let Some(depth) = options.max_selection_depth {
+        context.max_selection_depth = depth;
+    }
+    context.hooks = options.hooks.clone();
+
+    if let Some(ref derives) = options.additional_derives {
+        context.ingest_additional_derives(derives).unwrap();
+    }
+
+    context.ingest_derive_profile(&options.derive_profile);
+
+    if let Some(ref overrides) = options.deprecation_overrides {
+        context.ingest_deprecation_overrides(overrides)?;
+    }
 
-    if let Some(derives) = additional_derives {
-        context.ingest_additional_derives(&derives).unwrap();
+    if let Some(ref overrides) = options.specified_by_overrides {
+        context.ingest_specified_by_overrides(overrides)?;
+    }
+
+    if let Some(ref ty) = options.response_string_type {
+        context.ingest_response_string_type(ty)?;
+    }
+
+    if let Some(ref variants) = options.default_variants {
+        let variants = ::defaults::DefaultVariants::parse(variants)?;
+        variants.check(context.schema)?;
+        context.default_variants = variants;
+    }
+
+    if let Some(ref adapters) = options.serde_adapters {
+        context.serde_adapters = ::serde_adapters::SerdeAdapters::parse(adapters)?;
+    }
+
+    if let Some(ref scalars) = options.big_number_scalars {
+        context.ingest_big_number_scalars(scalars)?;
+    }
+
+    if let Some(ref scalars) = options.bytes_scalars {
+        context.ingest_bytes_scalars(scalars);
     }
 
     let mut definitions = Vec::new();
@@ -55,12 +143,18 @@ pub(crate) fn response_for_query(
             query::Definition::Operation(_op) => (),
             query::Definition::Fragment(fragment) => {
                 let &query::TypeCondition::On(ref on) = &fragment.type_condition;
+                let mut selection = Selection::from(&fragment.selection_set);
+                // Fragments on the root types are objects like any other, with inline fragments on the root type flattened like in the operations.
+                if schema.is_root_type(on) {
+                    selection = selection.flatten_inline_fragments_on(on);
+                }
                 context.fragments.insert(
                     &fragment.name,
                     GqlFragment {
                         name: &fragment.name,
-                        selection: Selection::from(&fragment.selection_set),
+                        selection,
                         on,
+                        directives: &fragment.directives,
                         is_required: false.into(),
                     },
                 );
@@ -68,60 +162,140 @@ pub(crate) fn response_for_query(
         }
     }
 
+    let mut root_path_enum = quote!();
     let response_data_fields = {
-        let root_name = operation.root_name(&context.schema);
-        let opt_definition = context.schema.objects.get(&root_name);
-        let definition = if let Some(definition) = opt_definition {
-            definition
-        } else {
-            panic!(
-                "operation type '{:?}' not in schema",
-                operation.operation_type
-            );
-        };
+        let root_name = operation.checked_root_name(&context.schema)?;
+        let definition = &context.schema.objects[root_name];
         let prefix = &operation.name;
-        let selection = &operation.selection;
+        let selection = &operation
+            .selection
+            .without_fragment_fields(&context, root_name)?;
 
-        if operation.is_subscription() && selection.0.len() > 1 {
+        // A single fragment spread can still select several root fields.
+        if operation.is_subscription()
+            && (selection.0.len() > 1
+                || error_paths::root_response_keys(&context, definition, selection).len() > 1)
+        {
             Err(format_err!(
                 "{}",
                 ::constants::MULTIPLE_SUBSCRIPTION_FIELDS_ERROR
             ))?
         }
 
+        let operation_prefix = if multiple_operation {
+            operation.name.as_str()
+        } else {
+            ""
+        };
+        root_path_enum = error_paths::root_path_enum(
+            &context,
+            definition,
+            selection,
+            &Ident::new(
+                &format!("{}ResponseDataPath", operation_prefix),
+                Span::call_site(),
+            ),
+            &Ident::new(
+                &format!("{}PartialData", operation_prefix),
+                Span::call_site(),
+            ),
+        );
+
         definitions.extend(definition.field_impls_for_selection(&context, &selection, &prefix)?);
         definition.response_fields_for_selection(&context, &selection, &prefix)?
     };
 
     let enum_definitions = context.schema.enums.values().filter_map(|enm| {
         if enm.is_required.get() {
-            Some(enm.to_rust(&context))
+            let rust_name = format!("{}{}", ENUMS_PREFIX, enm.name);
+            Some((
+                format!("enum {}", enm.name),
+                context.hooked(ItemKind::Enum, enm.name, &rust_name, enm.to_rust(&context)),
+            ))
         } else {
             None
         }
     });
-    let fragment_definitions: Result<Vec<TokenStream>, _> = context
-        .fragments
-        .values()
-        .filter_map(|fragment| {
-            if fragment.is_required.get() {
-                Some(fragment.to_rust(&context))
-            } else {
-                None
-            }
-        })
-        .collect();
-    let fragment_definitions = fragment_definitions?;
-    let variables_struct =
-        operation.expand_variables(&context, &operation.name, multiple_operation);
+    // Generating a fragment marks the fragments it spreads as required, so we generate until no new fragment is required.
+    let mut fragment_definitions: Vec<(String, TokenStream)> = Vec::new();
+    let mut generated_fragments = BTreeSet::new();
+    loop {
+        let required: Vec<&GqlFragment> = context
+            .fragments
+            .values()
+            .filter(|fragment| {
+                fragment.is_required.get() && !generated_fragments.contains(fragment.name)
+            })
+            .collect();
+        if required.is_empty() {
+            break;
+        }
+        for fragment in required {
+            generated_fragments.insert(fragment.name);
+            fragment_definitions.push((
+                format!("fragment {}", fragment.name),
+                fragment.to_rust(&context)?,
+            ));
+        }
+    }
+    let operation_prefix = if multiple_operation {
+        operation.name.as_str()
+    } else {
+        ""
+    };
+    let variables_struct = context.hooked_with_directives(
+        ItemKind::Variables,
+        &operation.name,
+        &format!("{}Variables", operation_prefix),
+        operation.directives,
+        operation.expand_variables(&context, &operation.name, multiple_operation),
+    );
+    let variable_specs = if options.variable_specs {
+        ::variables::variable_specs(&operation.variables, operation_prefix)
+    } else {
+        quote!()
+    };
+    let borrowed_variables_struct = if options.borrowed_variables {
+        context.hooked_with_directives(
+            ItemKind::Variables,
+            &operation.name,
+            &format!("{}VariablesRef", operation_prefix),
+            operation.directives,
+            operation.expand_borrowed_variables(
+                &context,
+                &operation.name,
+                multiple_operation,
+                &options.query_text,
+            ),
+        )
+    } else {
+        quote!()
+    };
+
+    let representations = if options.federation_representations {
+        federation::representations(&context, operation, operation_prefix)?
+    } else {
+        quote!()
+    };
+
+    let selection_constant = if options.selection_metadata {
+        ::selection_metadata::selection_constant(&context, operation, operation_prefix)
+    } else {
+        quote!()
+    };
 
-    let input_object_definitions: Result<Vec<TokenStream>, _> = context
+    let input_object_definitions: Result<Vec<(String, TokenStream)>, _> = context
         .schema
         .inputs
         .values()
         .filter_map(|i| {
             if i.is_required.get() {
-                Some(i.to_rust(&context))
+                Some(i.to_rust(&context).map(|tokens| {
+                    (
+                        format!("input {}", i.name),
+                        context.hooked(ItemKind::InputObject, i.name, i.name, tokens),
+                    )
+                }))
             } else {
                 None
             }
@@ -129,20 +303,95 @@ pub(crate) fn response_for_query(
         .collect();
     let input_object_definitions = input_object_definitions?;
 
-    let scalar_definitions: Vec<TokenStream> = context
+    // Several scalars can stand for the same Rust type, which must only implement the traits once.
+    let mut implemented_types: BTreeSet<String> = ["bool", "i64", "f64", "String"]
+        .iter()
+        .map(|ty| ty.to_string())
+        .collect();
+    let string_type_impls = match context.response_string_type() {
+        Some(ty) if implemented_types.insert(quote!(#ty).to_string()) => {
+            let ty = quote!(#ty);
+            let mock = mocks::string_type_mock_impl(&context, &ty);
+            let validation = json_validation::string_type_validation_impl(&context, &ty);
+            let pretty_print = ::pretty_print::string_type_pretty_print_impl(&context, &ty);
+            quote!(#mock #validation #pretty_print)
+        }
+        _ => quote!(),
+    };
+    let scalar_definitions: Vec<(String, TokenStream)> = context
         .schema
         .scalars
         .values()
         .filter_map(|s| {
             if s.is_required.get() {
-                Some(s.to_rust())
+                let definition = s.to_rust(&context);
+                let tokens = if implemented_types.insert(s.rust_type(&context).to_string()) {
+                    let mock = mocks::scalar_mock_impl(&context, s.name);
+                    let validation = json_validation::scalar_validation_impl(&context, s.name);
+                    let pretty_print = ::pretty_print::scalar_pretty_print_impl(&context, s.name);
+                    quote!(#definition #mock #validation #pretty_print)
+                } else {
+                    definition
+                };
+                Some((
+                    format!("scalar {}", s.name),
+                    context.hooked(ItemKind::Scalar, s.name, s.name, tokens),
+                ))
             } else {
                 None
             }
         })
         .collect();
+    let base64_helpers = if context
+        .schema
+        .scalars
+        .values()
+        .any(|s| s.is_required.get() && context.is_bytes_scalar(s.name))
+    {
+        Some(::bytes::base64_helpers())
+    } else {
+        None
+    };
+
+    let lenient_helpers = if context.lenient_scalars {
+        Some(::lenient::lenient_helpers())
+    } else {
+        None
+    };
 
     let response_derives = context.response_derives();
+    let deprecation_warnings = context.deprecation_warnings(&operation.name);
+    let get_query_string = if options.get_requests {
+        Some(::get_requests::get_query_string_impl(
+            operation,
+            multiple_operation,
+            &options.query_text,
+        ))
+    } else {
+        None
+    };
+    let cassette_type = if options.cassettes {
+        Some(::cassettes::cassette_type(
+            operation,
+            multiple_operation,
+            &options.query_text,
+        ))
+    } else {
+        None
+    };
+    let sse_event_type = if options.sse_subscriptions && operation.is_subscription() {
+        Some(sse::sse_event_type(&operation.name, multiple_operation))
+    } else {
+        None
+    };
+    let incremental_types = if incremental {
+        Some(incremental::incremental_types(
+            &operation.name,
+            multiple_operation,
+        ))
+    } else {
+        None
+    };
 
     let respons_data_struct_name = if multiple_operation {
         Ident::new(
@@ -153,34 +402,189 @@ pub(crate) fn response_for_query(
         Ident::new("ResponseData", Span::call_site())
     };
 
-    Ok(quote! {
-        use serde_derive::*;
+    // The root fields are recorded under the operation name, the prefix of the nested structs.
+    for field_name in context.redacted_fields(&operation.name) {
+        context.record_redacted_field(&respons_data_struct_name.to_string(), &field_name);
+    }
+    let response_data = quote! {
+        #response_derives
+        pub struct #respons_data_struct_name {
+            #(#response_data_fields,)*
+        }
+    };
+    let response_data = ::strict_mode::strict_definition(&context, response_data);
+    let response_data_mock = mocks::mock_impl(&context, &response_data);
+    let response_data_list_iterators =
+        ::list_iterators::list_iterators_impl(&context, &response_data);
+    let response_data_default = ::defaults::default_impl(
+        &context,
+        &response_data,
+        operation.root_name(context.schema),
+    );
+    let response_data_fragment_conversions =
+        shared::fragment_conversions(&respons_data_struct_name, &operation.selection);
+    let response_data_validation = json_validation::validation_impl(&context, &response_data);
+    let validate_json =
+        json_validation::response_data_validation(&context, &respons_data_struct_name);
+    let response_data_pretty_print = ::pretty_print::pretty_print_impl(&context, &response_data);
+    let display = ::pretty_print::response_data_display(&context, &respons_data_struct_name);
+    let response_data = ::redaction::redacted_debug(&context, response_data);
+    let response_data = context.hooked_with_directives(
+        ItemKind::Object,
+        operation.root_name(context.schema),
+        &respons_data_struct_name.to_string(),
+        operation.directives,
+        quote! {
+            #response_data
+
+            #response_data_fragment_conversions
+
+            #response_data_mock
+
+            #response_data_default
 
-        #[allow(dead_code)]
-        type Boolean = bool;
-        #[allow(dead_code)]
-        type Float = f64;
-        #[allow(dead_code)]
-        type Int = i64;
-        #[allow(dead_code)]
-        type ID = String;
+            #response_data_list_iterators
 
-        #(#scalar_definitions)*
+            #response_data_validation
 
-        #(#input_object_definitions)*
+            #validate_json
 
-        #(#enum_definitions)*
+            #response_data_pretty_print
 
-        #(#fragment_definitions)*
+            #display
+        },
+    );
+    let mut shared = vec![(
+        "builtin scalars".to_string(),
+        quote! {
+            #[allow(dead_code)]
+            type Boolean = bool;
+            #[allow(dead_code)]
+            type Float = f64;
+            #[allow(dead_code)]
+            type Int = i64;
+            #[allow(dead_code)]
+            type ID = ::std::string::String;
+        },
+    )];
+    shared.extend(scalar_definitions);
+    shared.push(("string type impls".to_string(), string_type_impls));
+    shared.extend(base64_helpers.map(|helpers| ("base64 helpers".to_string(), helpers)));
+    shared.extend(lenient_helpers.map(|helpers| ("lenient helpers".to_string(), helpers)));
+    shared.extend(input_object_definitions);
+    shared.extend(enum_definitions);
+    shared.extend(fragment_definitions);
 
+    let own = quote! {
         #(#definitions)*
 
         #variables_struct
 
-        #response_derives
-        pub struct #respons_data_struct_name {
-            #(#response_data_fields,)*
-        }
+        #variable_specs
+
+        #borrowed_variables_struct
+
+        #get_query_string
+
+        #representations
 
-    })
+        #cassette_type
+
+        #deprecation_warnings
+
+        #incremental_types
+
+        #sse_event_type
+
+        #response_data
+
+        #root_path_enum
+
+        #selection_constant
+    };
+
+    Ok(OperationItems { shared, own })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deprecation::DeprecationStrategy;
+    use graphql_parser;
+
+    #[test]
+    fn warn_always_reports_selected_deprecated_fields() {
+        let schema = graphql_parser::parse_schema(
+            r#"
+            type Query {
+              user: User
+            }
+
+            type User {
+              name: String @deprecated(reason: "Use `fullName`")
+              login: String @deprecated
+              fullName: String
+            }
+            "#,
+        )
+        .unwrap();
+        let schema = schema::Schema::from(&schema);
+        let query =
+            graphql_parser::parse_query("query MyQuery { user { name login fullName } }").unwrap();
+        let operations = all_operations(&query);
+
+        let generate = |strategy, overrides| {
+            let options = GraphQLClientDeriveOptions {
+                deprecation_strategy: Some(strategy),
+                deprecation_overrides: overrides,
+                ..Default::default()
+            };
+            response_for_query(&schema, &query, &operations[0], &options, false)
+                .unwrap()
+                .to_string()
+        };
+
+        let warn_always = generate(DeprecationStrategy::WarnAlways, None);
+        assert!(warn_always.contains("fn deprecated_fields_in_MyQuery"));
+        assert!(warn_always
+            .contains("# [ deprecated ( note = \"Use `fullName`\" ) ] struct User_name ;"));
+        assert!(warn_always.contains("# [ deprecated ] struct User_login ;"));
+        assert!(!warn_always.contains("User_fullName"));
+
+        let warn = generate(DeprecationStrategy::Warn, None);
+        assert!(!warn.contains("deprecated_fields_in_MyQuery"));
+    }
+
+    #[test]
+    fn deprecation_overrides_take_precedence() {
+        let schema = graphql_parser::parse_schema(
+            r#"
+            type Query {
+              user: User
+            }
+
+            type User {
+              legacyId: String @deprecated
+              login: String @deprecated
+            }
+            "#,
+        )
+        .unwrap();
+        let schema = schema::Schema::from(&schema);
+        let query =
+            graphql_parser::parse_query("query MyQuery { user { legacyId login } }").unwrap();
+        let operations = all_operations(&query);
+
+        let options = GraphQLClientDeriveOptions {
+            deprecation_strategy: Some(DeprecationStrategy::Deny),
+            deprecation_overrides: Some("User.legacyId=allow".to_owned()),
+            ..Default::default()
+        };
+        let generated = response_for_query(&schema, &query, &operations[0], &options, false)
+            .unwrap()
+            .to_string();
+
+        assert!(generated.contains("pub legacy_id"));
+        assert!(!generated.contains("pub login"));
+    }
 }
@@ -0,0 +1,180 @@
+use deprecation::DeprecationStrategy;
+use derive_profile::DeriveProfile;
+use failure;
+use graphql_parser::query::{Definition, Document, OperationDefinition};
+use graphql_parser::Pos;
+use std::collections::BTreeMap;
+use syn;
+use GraphQLClientDeriveOptions;
+
+/// The prefix of the comments setting options.
+const COMMENT_PREFIX: &str = "graphql-client:";
+
+/// The options set for an operation by the comments right above it in the query file, like `# graphql-client: derive=Clone,Copy`, one option per comment. They take precedence over the options of the derive or the CLI.
+#[derive(Default)]
+pub(crate) struct CommentOptions {
+    /// The traits derived on top of the derive profile, replacing `additional_derives`.
+    additional_derives: Option<String>,
+    derive_profile: Option<DeriveProfile>,
+    deprecation_strategy: Option<DeprecationStrategy>,
+    /// The visibility of the module, the same for all the operations of the query file.
+    module_visibility: Option<syn::Visibility>,
+}
+
+impl CommentOptions {
+    fn set(&mut self, key: &str, value: &str) -> Result<(), failure::Error> {
+        match key {
+            "derive" => self.additional_derives = Some(value.to_owned()),
+            "derive_profile" => self.derive_profile = Some(value.parse()?),
+            "deprecation_strategy" => self.deprecation_strategy = Some(value.parse()?),
+            "module_visibility" => {
+                self.module_visibility = Some(syn::parse_str(value).map_err(|err| {
+                    format_err!("Invalid module visibility: `{}`: {}", value, err)
+                })?)
+            }
+            other => Err(format_err!(
+                "Unknown option: `{}`. Valid options are `derive`, `derive_profile`, `deprecation_strategy` and `module_visibility`.",
+                other
+            ))?,
+        }
+        Ok(())
+    }
+
+    /// The options for the operation.
+    pub(crate) fn apply(&self, options: &GraphQLClientDeriveOptions) -> GraphQLClientDeriveOptions {
+        let mut options = options.clone();
+        if let Some(ref derives) = self.additional_derives {
+            options.additional_derives = Some(derives.clone());
+        }
+        if let Some(ref profile) = self.derive_profile {
+            options.derive_profile = profile.clone();
+        }
+        if let Some(ref strategy) = self.deprecation_strategy {
+            options.deprecation_strategy = Some(strategy.clone());
+        }
+        options
+    }
+}
+
+/// The options set by comments in the query file, by operation name.
+pub(crate) fn operation_comment_options(
+    query_string: &str,
+    query: &Document,
+) -> Result<BTreeMap<String, CommentOptions>, failure::Error> {
+    let lines: Vec<&str> = query_string.lines().collect();
+    let mut options = BTreeMap::new();
+
+    for definition in &query.definitions {
+        let (name, position) = match definition {
+            Definition::Operation(OperationDefinition::Query(q)) => (&q.name, q.position),
+            Definition::Operation(OperationDefinition::Mutation(m)) => (&m.name, m.position),
+            Definition::Operation(OperationDefinition::Subscription(s)) => (&s.name, s.position),
+            _ => continue,
+        };
+        let name = match name {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let mut operation_options = CommentOptions::default();
+        let mut is_set = false;
+        for (key, value) in comments_above(&lines, position) {
+            operation_options
+                .set(key, value)
+                .map_err(|err| format_err!("In the comments of the {} operation: {}", name, err))?;
+            is_set = true;
+        }
+        if is_set {
+            options.insert(name.clone(), operation_options);
+        }
+    }
+
+    Ok(options)
+}
+
+/// The `key=value` options of the `# graphql-client:` comments in the block of comment lines right above `position`, top to bottom. Other comments in the block are skipped.
+fn comments_above<'a>(lines: &[&'a str], position: Pos) -> Vec<(&'a str, &'a str)> {
+    let mut options = Vec::new();
+    for line in lines[..position.line.saturating_sub(1)].iter().rev() {
+        let comment = match line.trim().strip_prefix('#') {
+            Some(comment) => comment.trim(),
+            None => break,
+        };
+        if let Some(option) = comment.strip_prefix(COMMENT_PREFIX) {
+            let mut parts = option.splitn(2, '=');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts.next().unwrap_or("").trim();
+            options.push((key, value));
+        }
+    }
+    options.reverse();
+    options
+}
+
+/// The visibility of the module, set by the comments of its operations or else by the options. The operations generated in the module must agree on it.
+pub(crate) fn module_visibility<'a>(
+    comment_options: &'a BTreeMap<String, CommentOptions>,
+    operation_names: &[&str],
+    default: &'a syn::Visibility,
+) -> Result<&'a syn::Visibility, failure::Error> {
+    let mut visibilities = operation_names.iter().filter_map(|name| {
+        comment_options
+            .get(*name)
+            .and_then(|options| options.module_visibility.as_ref())
+            .map(|visibility| (*name, visibility))
+    });
+    let (first_name, first) = match visibilities.next() {
+        Some(first) => first,
+        None => return Ok(default),
+    };
+    for (name, visibility) in visibilities {
+        if quote!(#visibility).to_string() != quote!(#first).to_string() {
+            return Err(format_err!(
+                "The {} and {} operations set different module visibilities, but are generated in the same module.",
+                first_name,
+                name
+            ));
+        }
+    }
+    Ok(first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphql_parser::parse_query;
+
+    #[test]
+    fn comments_right_above_an_operation_set_its_options() {
+        let query_string = r#"
+# graphql-client: derive=Clone, PartialEq
+# Fetches the hero.
+# graphql-client: module_visibility = pub(crate)
+query Hero { hero { name } }
+
+# graphql-client: derive=Debug
+
+fragment Name on Character { name }
+
+query Droid { droid { name } }
+"#;
+        let query = parse_query(query_string).unwrap();
+
+        let options = operation_comment_options(query_string, &query).unwrap();
+
+        assert_eq!(options.keys().collect::<Vec<_>>(), vec!["Hero"]);
+        let hero = &options["Hero"];
+        assert_eq!(
+            hero.apply(&GraphQLClientDeriveOptions::default())
+                .additional_derives,
+            Some("Clone, PartialEq".to_string())
+        );
+        let default = syn::Visibility::Inherited;
+        let visibility = module_visibility(&options, &["Hero", "Droid"], &default).unwrap();
+        assert_eq!(quote!(#visibility).to_string(), "pub ( crate )");
+
+        let query_string = "# graphql-client: derives=Clone\nquery Hero { hero { name } }";
+        let query = parse_query(query_string).unwrap();
+        assert!(operation_comment_options(query_string, &query).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use failure;
+use flate2;
+use ruzstd::decoding::errors::{FrameDecoderError, ReadFrameHeaderError};
+use ruzstd::decoding::StreamingDecoder;
+use std::io::Read;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Decompress `data` if it is gzip or zstd compressed, recognized by its magic bytes whatever the file extension. Other data is returned as is.
+pub(crate) fn decompress(data: Vec<u8>) -> Result<Vec<u8>, failure::Error> {
+    if data.starts_with(GZIP_MAGIC) {
+        gunzip(&data)
+    } else if data.starts_with(ZSTD_MAGIC) {
+        unzstd(&data)
+    } else {
+        Ok(data)
+    }
+}
+
+/// Decompress the gzip members of `data`, checking their CRC.
+fn gunzip(data: &[u8]) -> Result<Vec<u8>, failure::Error> {
+    let mut inflated = Vec::new();
+    flate2::read::MultiGzDecoder::new(data)
+        .read_to_end(&mut inflated)
+        .map_err(|err| format_err!("Corrupt gzip data: {}", err))?;
+    Ok(inflated)
+}
+
+/// Decompress the zstd frames of `data`, skipping the skippable frames and checking the checksums of the frames that have one.
+fn unzstd(mut data: &[u8]) -> Result<Vec<u8>, failure::Error> {
+    let mut output = Vec::new();
+    while !data.is_empty() {
+        let mut decoder = match StreamingDecoder::new(&mut data) {
+            Ok(decoder) => decoder,
+            Err(FrameDecoderError::ReadFrameHeaderError(ReadFrameHeaderError::SkipFrame {
+                length,
+                ..
+            })) => {
+                data = data
+                    .get(length as usize..)
+                    .ok_or_else(|| format_err!("Corrupt zstd data: truncated skippable frame"))?;
+                continue;
+            }
+            Err(err) => return Err(format_err!("Corrupt zstd data: {}", err)),
+        };
+        decoder
+            .read_to_end(&mut output)
+            .map_err(|err| format_err!("Corrupt zstd data: {}", err))?;
+
+        let frame = decoder.into_frame_decoder();
+        if let Some(checksum) = frame.get_checksum_from_data() {
+            if frame.get_calculated_checksum() != Some(checksum) {
+                return Err(format_err!("Corrupt zstd data: checksum mismatch"));
+            }
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &[u8] = include_bytes!("../tests/star_wars_schema.graphql");
+
+    #[test]
+    fn compressed_data_is_decompressed() {
+        let gzipped = include_bytes!("../tests/star_wars_schema.graphql.gz");
+        let zstd_compressed = include_bytes!("../tests/star_wars_schema.graphql.zst");
+        assert_eq!(decompress(gzipped.to_vec()).unwrap(), SCHEMA);
+        assert_eq!(decompress(zstd_compressed.to_vec()).unwrap(), SCHEMA);
+        assert_eq!(decompress(SCHEMA.to_vec()).unwrap(), SCHEMA);
+
+        for compressed in &[&gzipped[..], &zstd_compressed[..]] {
+            let mut corrupted = compressed.to_vec();
+            corrupted[100] ^= 1;
+            assert!(decompress(corrupted).is_err());
+        }
+    }
+
+    #[test]
+    fn every_frame_is_decompressed() {
+        let gzipped = include_bytes!("../tests/star_wars_schema.graphql.gz");
+        let zstd_compressed = include_bytes!("../tests/star_wars_schema.graphql.zst");
+        let twice = [SCHEMA, SCHEMA].concat();
+
+        assert_eq!(
+            decompress([&gzipped[..], &gzipped[..]].concat()).unwrap(),
+            twice
+        );
+
+        let skippable_frame = [0x50, 0x2a, 0x4d, 0x18, 3, 0, 0, 0, 1, 2, 3];
+        assert_eq!(
+            decompress(
+                [
+                    &zstd_compressed[..],
+                    &skippable_frame[..],
+                    &zstd_compressed[..]
+                ]
+                .concat()
+            )
+            .unwrap(),
+            twice
+        );
+    }
+}
@@ -4,6 +4,28 @@ use objects::GqlObjectField;
 
 pub(crate) const TYPENAME_FIELD: &str = "__typename";
 
+/// Marks the fields resolved on the client, which are not sent to the server.
+pub(crate) const CLIENT_DIRECTIVE: &str = "client";
+
+/// Marks the fields whose values are masked in `Debug` and pretty-printed output. Not sent to the server.
+pub(crate) const SENSITIVE_DIRECTIVE: &str = "sensitive";
+
+/// Hints for the values of the field in response fixtures, like `@mock(value: "Alice")`. Not sent to the server.
+pub(crate) const MOCK_DIRECTIVE: &str = "mock";
+
+/// Incremental delivery directives: the fragment or the list items come in subsequent payloads.
+pub(crate) const DEFER_DIRECTIVE: &str = "defer";
+pub(crate) const STREAM_DIRECTIVE: &str = "stream";
+
+/// Stands for the `implements` clause of interfaces in parsed SDL documents, which graphql_parser does not support.
+pub(crate) const IMPLEMENTS_INTERFACES_DIRECTIVE: &str = "implementsInterfaces";
+
+/// Stands for the `repeatable` keyword of directive definitions in parsed SDL documents, as an extra argument. Names starting with `__` are reserved, so it cannot clash with a real argument.
+pub(crate) const REPEATABLE_ARGUMENT: &str = "__repeatable";
+
+/// How deeply selection sets may be nested when `max_selection_depth` is not set.
+pub(crate) const DEFAULT_MAX_SELECTION_DEPTH: usize = 64;
+
 pub(crate) fn string_type() -> &'static str {
     "String"
 }
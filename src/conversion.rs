@@ -0,0 +1,901 @@
+use failure;
+use graphql_parser::query;
+use graphql_parser::schema::*;
+use graphql_parser::Pos;
+use introspection_response::{self as introspection, __TypeKind, IntrospectionResponse};
+use schema::{BUILTIN_DIRECTIVES, DEFAULT_SCALARS};
+use serde_json;
+use std::collections::BTreeMap;
+
+const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+/// Convert an introspection response (with or without the `data` envelope) to SDL.
+///
+/// Built-in scalars, built-in directives and the introspection types are left out, since every GraphQL server defines them.
+pub fn introspection_to_sdl(json: &str) -> Result<String, failure::Error> {
+    let response: IntrospectionResponse = serde_json::from_str(json)?;
+    let schema = response
+        .as_schema()
+        .schema
+        .as_ref()
+        .ok_or_else(|| format_err!("The introspection response has no __schema"))?;
+
+    let mut document = Document {
+        definitions: vec![Definition::SchemaDefinition(SchemaDefinition {
+            query: schema.query_type.as_ref().and_then(|ty| ty.name.clone()),
+            mutation: schema.mutation_type.as_ref().and_then(|ty| ty.name.clone()),
+            subscription: schema
+                .subscription_type
+                .as_ref()
+                .and_then(|ty| ty.name.clone()),
+            ..Default::default()
+        })],
+    };
+
+    for ty in flatten(&schema.types).map(|ty| &ty.full_type) {
+        let name = ty
+            .name
+            .clone()
+            .ok_or_else(|| format_err!("Unnamed type in the introspection response"))?;
+        if name.starts_with("__") || DEFAULT_SCALARS.contains(&name.as_str()) {
+            continue;
+        }
+
+        let definition = match ty.kind {
+            Some(__TypeKind::SCALAR) => TypeDefinition::Scalar(ScalarType {
+                description: ty.description.clone(),
+                directives: specified_by_directive(&ty.specified_by_url),
+                ..ScalarType::new(name)
+            }),
+            Some(__TypeKind::OBJECT) => TypeDefinition::Object(ObjectType {
+                description: ty.description.clone(),
+                implements_interfaces: flatten(&ty.interfaces)
+                    .filter_map(|iface| iface.type_ref.name.clone())
+                    .collect(),
+                fields: sdl_fields(&ty.fields)?,
+                ..ObjectType::new(name)
+            }),
+            Some(__TypeKind::INTERFACE) => {
+                let implemented: Vec<String> = flatten(&ty.interfaces)
+                    .filter_map(|iface| iface.type_ref.name.clone())
+                    .collect();
+                TypeDefinition::Interface(InterfaceType {
+                    description: ty.description.clone(),
+                    directives: if implemented.is_empty() {
+                        Vec::new()
+                    } else {
+                        vec![::sdl::implements_interfaces_directive(implemented)]
+                    },
+                    fields: sdl_fields(&ty.fields)?,
+                    ..InterfaceType::new(name)
+                })
+            }
+            Some(__TypeKind::UNION) => TypeDefinition::Union(UnionType {
+                description: ty.description.clone(),
+                types: flatten(&ty.possible_types)
+                    .filter_map(|variant| variant.type_ref.name.clone())
+                    .collect(),
+                ..UnionType::new(name)
+            }),
+            Some(__TypeKind::ENUM) => TypeDefinition::Enum(EnumType {
+                description: ty.description.clone(),
+                values: flatten(&ty.enum_values)
+                    .filter_map(|value| {
+                        Some(EnumValue {
+                            description: value.description.clone(),
+                            directives: deprecation_directive(
+                                value.is_deprecated,
+                                &value.deprecation_reason,
+                            ),
+                            ..EnumValue::new(value.name.clone()?)
+                        })
+                    })
+                    .collect(),
+                ..EnumType::new(name)
+            }),
+            Some(__TypeKind::INPUT_OBJECT) => TypeDefinition::InputObject(InputObjectType {
+                description: ty.description.clone(),
+                fields: flatten(&ty.input_fields)
+                    .map(|field| sdl_input_value(&field.input_value))
+                    .collect::<Result<_, _>>()?,
+                ..InputObjectType::new(name)
+            }),
+            ref kind => {
+                return Err(format_err!(
+                    "Unexpected kind for the `{}` type: {:?}",
+                    name,
+                    kind
+                ))
+            }
+        };
+        document
+            .definitions
+            .push(Definition::TypeDefinition(definition));
+    }
+
+    for directive in flatten(&schema.directives) {
+        let name = match directive.name {
+            Some(ref name) if !BUILTIN_DIRECTIVES.contains(&name.as_str()) => name.clone(),
+            _ => continue,
+        };
+        let locations = flatten(&directive.locations)
+            .map(|location| {
+                location
+                    .as_str()
+                    .parse()
+                    .map_err(|_| format_err!("Unknown directive location: {}", location.as_str()))
+            })
+            .collect::<Result<_, failure::Error>>()?;
+
+        let mut arguments: Vec<InputValue> = flatten(&directive.args)
+            .map(|arg| sdl_input_value(&arg.input_value))
+            .collect::<Result<_, _>>()?;
+        if directive.is_repeatable.unwrap_or(false) {
+            arguments.push(::sdl::repeatable_argument());
+        }
+
+        document
+            .definitions
+            .push(Definition::DirectiveDefinition(DirectiveDefinition {
+                description: directive.description.clone(),
+                arguments,
+                locations,
+                ..DirectiveDefinition::new(name)
+            }));
+    }
+
+    let mut placeholders = Placeholders::default();
+    placeholders.protect_document(&mut document);
+
+    Ok(placeholders.restore(::sdl::print_schema(&document)))
+}
+
+/// Convert an SDL schema to the JSON document a server would return for the introspection query, including the `data` envelope.
+///
+/// The built-in scalars are included, but not the introspection types themselves. When the SDL has no `schema` definition, the root types are looked up by their conventional names.
+pub fn sdl_to_introspection(sdl: &str) -> Result<serde_json::Value, failure::Error> {
+    let document = ::sdl::parse_schema(sdl)?;
+
+    let mut types: BTreeMap<&str, TypeDefinition> = BTreeMap::new();
+    let mut extensions = Vec::new();
+    let mut directives = Vec::new();
+    let mut schema_definition = None;
+
+    for definition in &document.definitions {
+        match definition {
+            Definition::SchemaDefinition(definition) => schema_definition = Some(definition),
+            Definition::TypeDefinition(definition) => {
+                types.insert(type_definition_name(definition), definition.clone());
+            }
+            Definition::TypeExtension(extension) => extensions.push(extension),
+            Definition::DirectiveDefinition(directive) => directives.push(directive),
+        }
+    }
+
+    for extension in extensions {
+        apply_extension(&mut types, extension)?;
+    }
+
+    let kinds: BTreeMap<&str, &str> = types
+        .iter()
+        .map(|(name, definition)| (*name, type_definition_kind(definition)))
+        .chain(DEFAULT_SCALARS.iter().map(|name| (*name, "SCALAR")))
+        .collect();
+
+    let root_type = |explicit: Option<&String>, conventional: &str| {
+        let name = match schema_definition {
+            Some(_) => explicit.map(String::as_str),
+            None if kinds.contains_key(conventional) => Some(conventional),
+            None => None,
+        };
+        name.map(|name| json!({ "name": name }))
+    };
+
+    let mut introspected_types: Vec<serde_json::Value> = DEFAULT_SCALARS
+        .iter()
+        .filter(|name| !types.contains_key(*name))
+        .map(|name| introspected_type("SCALAR", name, &None))
+        .collect();
+
+    for (name, definition) in &types {
+        let mut ty = introspected_type(
+            type_definition_kind(definition),
+            name,
+            type_definition_description(definition),
+        );
+
+        match definition {
+            TypeDefinition::Scalar(scalar) => {
+                if let Some(url) = ::scalars::specified_by_url(&scalar.directives) {
+                    ty["specifiedByURL"] = json!(url);
+                }
+            }
+            TypeDefinition::Object(object) => {
+                ty["fields"] = introspected_fields(&object.fields, &kinds)?;
+                ty["interfaces"] = object
+                    .implements_interfaces
+                    .iter()
+                    .map(|iface| introspected_type_ref(&Type::NamedType(iface.clone()), &kinds))
+                    .collect::<Result<_, _>>()?;
+            }
+            TypeDefinition::Interface(interface) => {
+                ty["fields"] = introspected_fields(&interface.fields, &kinds)?;
+                ty["interfaces"] = ::sdl::implemented_interfaces(&interface.directives)
+                    .into_iter()
+                    .map(|iface| introspected_type_ref(&Type::NamedType(iface.to_string()), &kinds))
+                    .collect::<Result<_, _>>()?;
+                ty["possibleTypes"] = types
+                    .values()
+                    .filter_map(|definition| match definition {
+                        TypeDefinition::Object(object)
+                            if object.implements_interfaces.contains(&interface.name) =>
+                        {
+                            Some(json!({ "kind": "OBJECT", "name": object.name, "ofType": null }))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+            }
+            TypeDefinition::Union(union) => {
+                ty["possibleTypes"] = union
+                    .types
+                    .iter()
+                    .map(|variant| introspected_type_ref(&Type::NamedType(variant.clone()), &kinds))
+                    .collect::<Result<_, _>>()?;
+            }
+            TypeDefinition::Enum(enm) => {
+                ty["enumValues"] = enm
+                    .values
+                    .iter()
+                    .map(|value| {
+                        let (is_deprecated, deprecation_reason) =
+                            deprecation_status(&value.directives);
+                        json!({
+                            "name": value.name,
+                            "description": value.description,
+                            "isDeprecated": is_deprecated,
+                            "deprecationReason": deprecation_reason,
+                        })
+                    })
+                    .collect();
+            }
+            TypeDefinition::InputObject(input) => {
+                ty["inputFields"] = introspected_input_values(&input.fields, &kinds)?;
+            }
+        }
+
+        introspected_types.push(ty);
+    }
+
+    let introspected_directives = directives
+        .iter()
+        .map(|directive| {
+            let arguments: Vec<InputValue> = ::sdl::directive_arguments(directive)
+                .into_iter()
+                .cloned()
+                .collect();
+            Ok(json!({
+                "name": directive.name,
+                "description": directive.description,
+                "locations": directive
+                    .locations
+                    .iter()
+                    .map(DirectiveLocation::as_str)
+                    .collect::<Vec<_>>(),
+                "args": introspected_input_values(&arguments, &kinds)?,
+                "isRepeatable": ::sdl::is_repeatable(directive),
+            }))
+        })
+        .collect::<Result<Vec<_>, failure::Error>>()?;
+
+    let explicit_roots = schema_definition.cloned().unwrap_or_default();
+
+    Ok(json!({
+        "data": {
+            "__schema": {
+                "queryType": root_type(explicit_roots.query.as_ref(), "Query"),
+                "mutationType": root_type(explicit_roots.mutation.as_ref(), "Mutation"),
+                "subscriptionType": root_type(explicit_roots.subscription.as_ref(), "Subscription"),
+                "types": introspected_types,
+                "directives": introspected_directives,
+            }
+        }
+    }))
+}
+
+fn flatten<T>(items: &Option<Vec<Option<T>>>) -> impl Iterator<Item = &T> {
+    items
+        .iter()
+        .flat_map(|items| items.iter())
+        .filter_map(Option::as_ref)
+}
+
+fn sdl_fields(
+    fields: &Option<Vec<Option<introspection::FullTypeFields>>>,
+) -> Result<Vec<Field>, failure::Error> {
+    flatten(fields)
+        .map(|field| {
+            let type_ref = field
+                .type_
+                .as_ref()
+                .map(|type_| &type_.type_ref)
+                .ok_or_else(|| format_err!("Field without a type: {:?}", field.name))?;
+            Ok(Field {
+                position: Pos::default(),
+                description: field.description.clone(),
+                name: field
+                    .name
+                    .clone()
+                    .ok_or_else(|| format_err!("Unnamed field in the introspection response"))?,
+                arguments: flatten(&field.args)
+                    .map(|arg| sdl_input_value(&arg.input_value))
+                    .collect::<Result<_, _>>()?,
+                field_type: sdl_type(type_ref)?,
+                directives: deprecation_directive(field.is_deprecated, &field.deprecation_reason),
+            })
+        })
+        .collect()
+}
+
+fn sdl_input_value(input_value: &introspection::InputValue) -> Result<InputValue, failure::Error> {
+    let name = input_value
+        .name
+        .clone()
+        .ok_or_else(|| format_err!("Unnamed input value in the introspection response"))?;
+    let type_ref = input_value
+        .type_
+        .as_ref()
+        .map(|type_| &type_.type_ref)
+        .ok_or_else(|| format_err!("Input value without a type: {}", name))?;
+
+    Ok(InputValue {
+        position: Pos::default(),
+        description: input_value.description.clone(),
+        value_type: sdl_type(type_ref)?,
+        default_value: input_value
+            .default_value
+            .as_ref()
+            .map(|value| sdl_value(value)),
+        directives: deprecation_directive(
+            input_value.is_deprecated,
+            &input_value.deprecation_reason,
+        ),
+        name,
+    })
+}
+
+fn sdl_type(type_ref: &introspection::TypeRef) -> Result<Type, failure::Error> {
+    let of_type = || {
+        type_ref
+            .of_type
+            .as_ref()
+            .ok_or_else(|| format_err!("Wrapping type without an inner type"))
+    };
+
+    match type_ref.kind {
+        Some(__TypeKind::NON_NULL) => Ok(Type::NonNullType(Box::new(sdl_type(of_type()?)?))),
+        Some(__TypeKind::LIST) => Ok(Type::ListType(Box::new(sdl_type(of_type()?)?))),
+        _ => type_ref
+            .name
+            .clone()
+            .map(Type::NamedType)
+            .ok_or_else(|| format_err!("Unnamed type reference")),
+    }
+}
+
+/// Default values are GraphQL literals in introspection responses, so we parse them with the query parser. Anything it rejects is kept verbatim.
+fn sdl_value(literal: &str) -> Value {
+    parse_value_literal(literal).unwrap_or_else(|| Value::Enum(literal.to_owned()))
+}
+
+/// Parse a GraphQL value literal, like the `defaultValue` of an introspected input value.
+pub(crate) fn parse_value_literal(literal: &str) -> Option<Value> {
+    query::parse_query(&format!("{{ f(a: {}) }}", literal))
+        .ok()
+        .and_then(|document| match document.definitions.into_iter().next() {
+            Some(query::Definition::Operation(query::OperationDefinition::SelectionSet(
+                selection_set,
+            ))) => match selection_set.items.into_iter().next() {
+                Some(query::Selection::Field(field)) => {
+                    field.arguments.into_iter().next().map(|(_, value)| value)
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
+fn specified_by_directive(url: &Option<String>) -> Vec<Directive> {
+    url.iter()
+        .map(|url| Directive {
+            position: Pos::default(),
+            name: "specifiedBy".to_string(),
+            arguments: vec![("url".to_string(), Value::String(url.clone()))],
+        })
+        .collect()
+}
+
+fn deprecation_directive(is_deprecated: Option<bool>, reason: &Option<String>) -> Vec<Directive> {
+    if is_deprecated != Some(true) {
+        return Vec::new();
+    }
+
+    let arguments = match reason {
+        Some(reason) if reason != DEFAULT_DEPRECATION_REASON => {
+            vec![("reason".to_owned(), Value::String(reason.clone()))]
+        }
+        _ => Vec::new(),
+    };
+
+    vec![Directive {
+        position: Pos::default(),
+        name: "deprecated".to_owned(),
+        arguments,
+    }]
+}
+
+fn deprecation_status(directives: &[Directive]) -> (bool, Option<String>) {
+    match directives.iter().find(|d| d.name == "deprecated") {
+        Some(directive) => {
+            let reason = directive
+                .arguments
+                .iter()
+                .find(|(name, _)| name == "reason")
+                .and_then(|(_, value)| match value {
+                    Value::String(reason) => Some(reason.clone()),
+                    _ => None,
+                })
+                .unwrap_or_else(|| DEFAULT_DEPRECATION_REASON.to_owned());
+            (true, Some(reason))
+        }
+        None => (false, None),
+    }
+}
+
+/// The graphql_parser printer mangles characters outside the basic multilingual plane (like emoji in descriptions) and most control characters, so strings containing them are printed as placeholders, then escaped by hand.
+#[derive(Default)]
+pub(crate) struct Placeholders {
+    strings: Vec<String>,
+}
+
+impl Placeholders {
+    const PREFIX: &'static str = "graphql-client-placeholder-";
+
+    fn protect(&mut self, s: &mut String) {
+        let mangled = s
+            .chars()
+            .any(|c| c > '\u{FFFF}' || (c < ' ' && !['\n', '\r', '\t'].contains(&c)));
+        if mangled {
+            self.replace(s);
+        }
+    }
+
+    /// Values with line breaks are protected too: unlike descriptions, they must not be printed as block strings, which lose their indentation and blank lines.
+    fn protect_string_value(&mut self, s: &mut String) {
+        if s.contains('\n') {
+            self.replace(s);
+        } else {
+            self.protect(s);
+        }
+    }
+
+    fn replace(&mut self, s: &mut String) {
+        let placeholder = format!("{}{}", Self::PREFIX, self.strings.len());
+        self.strings.push(::std::mem::replace(s, placeholder));
+    }
+
+    fn protect_description(&mut self, description: &mut Option<String>) {
+        if let Some(description) = description {
+            self.protect(description);
+        }
+    }
+
+    fn protect_value(&mut self, value: &mut Value) {
+        match value {
+            Value::String(s) => self.protect_string_value(s),
+            Value::List(values) => values.iter_mut().for_each(|v| self.protect_value(v)),
+            Value::Object(fields) => fields.values_mut().for_each(|v| self.protect_value(v)),
+            _ => (),
+        }
+    }
+
+    fn protect_directives(&mut self, directives: &mut [Directive]) {
+        for directive in directives {
+            for (_, value) in &mut directive.arguments {
+                self.protect_value(value);
+            }
+        }
+    }
+
+    fn protect_input_values(&mut self, input_values: &mut [InputValue]) {
+        for input_value in input_values {
+            self.protect_description(&mut input_value.description);
+            self.protect_directives(&mut input_value.directives);
+            if let Some(default_value) = &mut input_value.default_value {
+                self.protect_value(default_value);
+            }
+        }
+    }
+
+    fn protect_fields(&mut self, fields: &mut [Field]) {
+        for field in fields {
+            self.protect_description(&mut field.description);
+            self.protect_input_values(&mut field.arguments);
+            self.protect_directives(&mut field.directives);
+        }
+    }
+
+    fn protect_document(&mut self, document: &mut Document) {
+        for definition in &mut document.definitions {
+            match definition {
+                Definition::TypeDefinition(TypeDefinition::Scalar(ty)) => {
+                    self.protect_description(&mut ty.description);
+                }
+                Definition::TypeDefinition(TypeDefinition::Object(ty)) => {
+                    self.protect_description(&mut ty.description);
+                    self.protect_fields(&mut ty.fields);
+                }
+                Definition::TypeDefinition(TypeDefinition::Interface(ty)) => {
+                    self.protect_description(&mut ty.description);
+                    self.protect_fields(&mut ty.fields);
+                }
+                Definition::TypeDefinition(TypeDefinition::Union(ty)) => {
+                    self.protect_description(&mut ty.description);
+                }
+                Definition::TypeDefinition(TypeDefinition::Enum(ty)) => {
+                    self.protect_description(&mut ty.description);
+                    for value in &mut ty.values {
+                        self.protect_description(&mut value.description);
+                        self.protect_directives(&mut value.directives);
+                    }
+                }
+                Definition::TypeDefinition(TypeDefinition::InputObject(ty)) => {
+                    self.protect_description(&mut ty.description);
+                    self.protect_input_values(&mut ty.fields);
+                }
+                Definition::DirectiveDefinition(directive) => {
+                    self.protect_description(&mut directive.description);
+                    self.protect_input_values(&mut directive.arguments);
+                }
+                Definition::SchemaDefinition(_) | Definition::TypeExtension(_) => (),
+            }
+        }
+    }
+
+    pub(crate) fn protect_query_document(&mut self, document: &mut query::Document) {
+        for definition in &mut document.definitions {
+            match definition {
+                query::Definition::Operation(query::OperationDefinition::SelectionSet(
+                    selection_set,
+                )) => self.protect_selection_set(selection_set),
+                query::Definition::Operation(query::OperationDefinition::Query(query)) => {
+                    self.protect_variable_definitions(&mut query.variable_definitions);
+                    self.protect_directives(&mut query.directives);
+                    self.protect_selection_set(&mut query.selection_set);
+                }
+                query::Definition::Operation(query::OperationDefinition::Mutation(mutation)) => {
+                    self.protect_variable_definitions(&mut mutation.variable_definitions);
+                    self.protect_directives(&mut mutation.directives);
+                    self.protect_selection_set(&mut mutation.selection_set);
+                }
+                query::Definition::Operation(query::OperationDefinition::Subscription(
+                    subscription,
+                )) => {
+                    self.protect_variable_definitions(&mut subscription.variable_definitions);
+                    self.protect_directives(&mut subscription.directives);
+                    self.protect_selection_set(&mut subscription.selection_set);
+                }
+                query::Definition::Fragment(fragment) => {
+                    self.protect_directives(&mut fragment.directives);
+                    self.protect_selection_set(&mut fragment.selection_set);
+                }
+            }
+        }
+    }
+
+    fn protect_variable_definitions(&mut self, variables: &mut [query::VariableDefinition]) {
+        for variable in variables {
+            if let Some(default_value) = &mut variable.default_value {
+                self.protect_value(default_value);
+            }
+        }
+    }
+
+    fn protect_selection_set(&mut self, selection_set: &mut query::SelectionSet) {
+        for item in &mut selection_set.items {
+            match item {
+                query::Selection::Field(field) => {
+                    for (_, value) in &mut field.arguments {
+                        self.protect_value(value);
+                    }
+                    self.protect_directives(&mut field.directives);
+                    self.protect_selection_set(&mut field.selection_set);
+                }
+                query::Selection::FragmentSpread(spread) => {
+                    self.protect_directives(&mut spread.directives)
+                }
+                query::Selection::InlineFragment(inline) => {
+                    self.protect_directives(&mut inline.directives);
+                    self.protect_selection_set(&mut inline.selection_set);
+                }
+            }
+        }
+    }
+
+    /// Replace the quoted placeholders in the printed document with the escaped original strings.
+    pub(crate) fn restore(&self, mut printed: String) -> String {
+        for (idx, original) in self.strings.iter().enumerate() {
+            let placeholder = format!("\"{}{}\"", Self::PREFIX, idx);
+            printed = printed.replacen(&placeholder, &quote_string(original), 1);
+        }
+        printed
+    }
+}
+
+fn quote_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c < ' ' => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn type_definition_name(definition: &TypeDefinition) -> &str {
+    match definition {
+        TypeDefinition::Scalar(ty) => &ty.name,
+        TypeDefinition::Object(ty) => &ty.name,
+        TypeDefinition::Interface(ty) => &ty.name,
+        TypeDefinition::Union(ty) => &ty.name,
+        TypeDefinition::Enum(ty) => &ty.name,
+        TypeDefinition::InputObject(ty) => &ty.name,
+    }
+}
+
+fn type_definition_kind(definition: &TypeDefinition) -> &'static str {
+    match definition {
+        TypeDefinition::Scalar(_) => "SCALAR",
+        TypeDefinition::Object(_) => "OBJECT",
+        TypeDefinition::Interface(_) => "INTERFACE",
+        TypeDefinition::Union(_) => "UNION",
+        TypeDefinition::Enum(_) => "ENUM",
+        TypeDefinition::InputObject(_) => "INPUT_OBJECT",
+    }
+}
+
+fn type_definition_description(definition: &TypeDefinition) -> &Option<String> {
+    match definition {
+        TypeDefinition::Scalar(ty) => &ty.description,
+        TypeDefinition::Object(ty) => &ty.description,
+        TypeDefinition::Interface(ty) => &ty.description,
+        TypeDefinition::Union(ty) => &ty.description,
+        TypeDefinition::Enum(ty) => &ty.description,
+        TypeDefinition::InputObject(ty) => &ty.description,
+    }
+}
+
+fn apply_extension(
+    types: &mut BTreeMap<&str, TypeDefinition>,
+    extension: &TypeExtension,
+) -> Result<(), failure::Error> {
+    let name = match extension {
+        TypeExtension::Scalar(ext) => &ext.name,
+        TypeExtension::Object(ext) => &ext.name,
+        TypeExtension::Interface(ext) => &ext.name,
+        TypeExtension::Union(ext) => &ext.name,
+        TypeExtension::Enum(ext) => &ext.name,
+        TypeExtension::InputObject(ext) => &ext.name,
+    };
+
+    match (types.get_mut(name.as_str()), extension) {
+        (Some(TypeDefinition::Scalar(ty)), TypeExtension::Scalar(ext)) => {
+            ty.directives.extend(ext.directives.iter().cloned());
+        }
+        (Some(TypeDefinition::Object(ty)), TypeExtension::Object(ext)) => {
+            ty.implements_interfaces
+                .extend(ext.implements_interfaces.iter().cloned());
+            ty.fields.extend(ext.fields.iter().cloned());
+        }
+        (Some(TypeDefinition::Interface(ty)), TypeExtension::Interface(ext)) => {
+            // Keeps the `implements` clause of the extension.
+            ty.directives.extend(ext.directives.iter().cloned());
+            ty.fields.extend(ext.fields.iter().cloned());
+        }
+        (Some(TypeDefinition::Union(ty)), TypeExtension::Union(ext)) => {
+            ty.types.extend(ext.types.iter().cloned());
+        }
+        (Some(TypeDefinition::Enum(ty)), TypeExtension::Enum(ext)) => {
+            ty.values.extend(ext.values.iter().cloned());
+        }
+        (Some(TypeDefinition::InputObject(ty)), TypeExtension::InputObject(ext)) => {
+            ty.fields.extend(ext.fields.iter().cloned());
+        }
+        _ => {
+            return Err(format_err!(
+                "The `{}` type extension does not match any type definition",
+                name
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+fn introspected_type(kind: &str, name: &str, description: &Option<String>) -> serde_json::Value {
+    json!({
+        "kind": kind,
+        "name": name,
+        "description": description,
+        "fields": null,
+        "inputFields": null,
+        "interfaces": null,
+        "enumValues": null,
+        "possibleTypes": null,
+    })
+}
+
+fn introspected_fields(
+    fields: &[Field],
+    kinds: &BTreeMap<&str, &str>,
+) -> Result<serde_json::Value, failure::Error> {
+    fields
+        .iter()
+        .map(|field| {
+            let (is_deprecated, deprecation_reason) = deprecation_status(&field.directives);
+            Ok(json!({
+                "name": field.name,
+                "description": field.description,
+                "args": introspected_input_values(&field.arguments, kinds)?,
+                "type": introspected_type_ref(&field.field_type, kinds)?,
+                "isDeprecated": is_deprecated,
+                "deprecationReason": deprecation_reason,
+            }))
+        })
+        .collect()
+}
+
+fn introspected_input_values(
+    input_values: &[InputValue],
+    kinds: &BTreeMap<&str, &str>,
+) -> Result<serde_json::Value, failure::Error> {
+    input_values
+        .iter()
+        .map(|input_value| {
+            let (is_deprecated, deprecation_reason) = deprecation_status(&input_value.directives);
+            Ok(json!({
+                "name": input_value.name,
+                "description": input_value.description,
+                "type": introspected_type_ref(&input_value.value_type, kinds)?,
+                "defaultValue": input_value.default_value.as_ref().map(Value::to_string),
+                "isDeprecated": is_deprecated,
+                "deprecationReason": deprecation_reason,
+            }))
+        })
+        .collect()
+}
+
+fn introspected_type_ref(
+    type_: &Type,
+    kinds: &BTreeMap<&str, &str>,
+) -> Result<serde_json::Value, failure::Error> {
+    Ok(match type_ {
+        Type::NamedType(name) => {
+            let kind = kinds
+                .get(name.as_str())
+                .ok_or_else(|| format_err!("Unknown type: {}", name))?;
+            json!({ "kind": kind, "name": name, "ofType": null })
+        }
+        Type::ListType(inner) => {
+            json!({ "kind": "LIST", "name": null, "ofType": introspected_type_ref(inner, kinds)? })
+        }
+        Type::NonNullType(inner) => {
+            json!({ "kind": "NON_NULL", "name": null, "ofType": introspected_type_ref(inner, kinds)? })
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema;
+
+    #[test]
+    fn sdl_round_trips_through_introspection() {
+        let sdl = include_str!("tests/star_wars_schema.graphql");
+        let json = sdl_to_introspection(sdl).unwrap().to_string();
+
+        let from_sdl = ::sdl::parse_schema(sdl).unwrap();
+        let from_json: IntrospectionResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            schema::Schema::from(&from_json),
+            schema::Schema::from(&from_sdl)
+        );
+
+        let printed = introspection_to_sdl(&json).unwrap();
+        assert_eq!(
+            schema::Schema::from(&::sdl::parse_schema(&printed).unwrap()),
+            schema::Schema::from(&from_sdl)
+        );
+    }
+
+    #[test]
+    fn introspection_round_trips_through_sdl() {
+        let json = include_str!("tests/github_schema.json");
+        let printed = introspection_to_sdl(json).unwrap();
+
+        let from_json: IntrospectionResponse = serde_json::from_str(json).unwrap();
+        let mut expected = schema::Schema::from(&from_json);
+        // The introspection types are not part of the SDL.
+        expected.enums.retain(|name, _| !name.starts_with("__"));
+        expected.objects.retain(|name, _| !name.starts_with("__"));
+        expected
+            .field_arguments
+            .retain(|coordinate, _| !coordinate.starts_with("__"));
+        expected
+            .field_positions
+            .retain(|name, _| !name.starts_with("__"));
+
+        let from_sdl = parse_schema(&printed).unwrap();
+        assert_eq!(schema::Schema::from(&from_sdl), expected);
+        assert!(printed.contains("\"Represents the 👍 emoji.\""));
+    }
+
+    #[test]
+    fn introspection_to_sdl_keeps_deprecations_and_defaults() {
+        let sdl = r#"
+            type Query {
+              users(first: Int = 10, order: Order = ASC, filter: Filter = {name: "x"}): [User!]!
+            }
+
+            type User {
+              name: String @deprecated(reason: "Use `fullName`")
+              fullName: String!
+            }
+
+            enum Order {
+              ASC
+              DESC @deprecated
+            }
+
+            input Filter {
+              name: String
+              nameLike: String @deprecated(reason: "Use `name`")
+            }
+
+            directive @cached(ttl: Int) on FIELD_DEFINITION | OBJECT
+            directive @tag(name: String) repeatable on OBJECT
+
+            scalar DateTime @specifiedBy(url: "https://tools.ietf.org/html/rfc3339")
+        "#;
+        let json = sdl_to_introspection(sdl).unwrap().to_string();
+        let printed = introspection_to_sdl(&json).unwrap();
+
+        assert!(printed.contains("query: Query"));
+        assert!(printed.contains(
+            "users(first: Int = 10, order: Order = ASC, filter: Filter = {name: \"x\"}): [User!]!"
+        ));
+        assert!(printed.contains("name: String @deprecated(reason: \"Use `fullName`\")"));
+        assert!(printed.contains("DESC @deprecated\n"));
+        assert!(printed.contains("nameLike: String @deprecated(reason: \"Use `name`\")"));
+        assert!(printed.contains("directive @cached(ttl: Int) on FIELD_DEFINITION | OBJECT"));
+        assert!(printed.contains("directive @tag(name: String) repeatable on OBJECT"));
+        assert!(printed.contains(
+            "scalar DateTime @specifiedBy(url: \"https://tools.ietf.org/html/rfc3339\")"
+        ));
+        assert!(!printed.contains("scalar String"));
+    }
+
+    #[test]
+    fn sdl_to_introspection_rejects_unknown_types() {
+        assert!(sdl_to_introspection("type Query { a: Missing }").is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use failure;
+use proc_macro2::TokenStream;
+use query::QueryContext;
+use schema::Schema;
+use std::collections::BTreeMap;
+use syn;
+
+/// The variant `Default` picks for an enum, by name of the GraphQL enum, union or interface. The other enums default to their first variant.
+#[derive(Debug, Default)]
+pub(crate) struct DefaultVariants(BTreeMap<String, String>);
+
+impl DefaultVariants {
+    /// Parse a list like `Episode=JEDI, SearchResult=Human`.
+    pub(crate) fn parse(attribute_value: &str) -> Result<Self, failure::Error> {
+        let mut variants = BTreeMap::new();
+        for item in attribute_value.split(',').filter(|s| !s.trim().is_empty()) {
+            match item
+                .splitn(2, '=')
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .as_slice()
+            {
+                [type_name, variant] if !type_name.is_empty() && !variant.is_empty() => {
+                    variants.insert((*type_name).to_owned(), (*variant).to_owned());
+                }
+                _ => {
+                    return Err(format_err!(
+                        "Invalid default variant: `{}`. Expected `Type=Variant`.",
+                        item.trim()
+                    ))
+                }
+            }
+        }
+        Ok(DefaultVariants(variants))
+    }
+
+    /// Check that every variant is a value of its enum, or a member of its union or interface.
+    pub(crate) fn check(&self, schema: &Schema) -> Result<(), failure::Error> {
+        for (type_name, variant) in &self.0 {
+            let candidates: Vec<&str> = if let Some(enm) = schema.enums.get(type_name.as_str()) {
+                enm.variants.iter().map(|v| v.name).collect()
+            } else if let Some(union) = schema.unions.get(type_name.as_str()) {
+                union.variants.iter().cloned().collect()
+            } else if let Some(iface) = schema.interfaces.get(type_name.as_str()) {
+                iface.implemented_by.iter().cloned().collect()
+            } else {
+                return Err(format_err!(
+                    "Default variant for `{}`, which is not an enum, union or interface of the schema.",
+                    type_name
+                ));
+            };
+            if !candidates.contains(&variant.as_str()) {
+                return Err(format_err!(
+                    "Default variant `{}` is not a variant of `{}`.",
+                    variant,
+                    type_name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `Default` implementation of a generated struct or enum, from its definition. Structs take the default of every field, enums the designated variant of `type_name`, the GraphQL type they stand for, or their first one.
+pub(crate) fn default_impl(
+    context: &QueryContext,
+    definition: &TokenStream,
+    type_name: &str,
+) -> TokenStream {
+    if !context.default_impls {
+        return quote!();
+    }
+
+    let definition: syn::DeriveInput =
+        syn::parse2(definition.clone()).expect("generated type definition");
+    let name = &definition.ident;
+    let value = match definition.data {
+        syn::Data::Struct(ref data) => {
+            let fields = data.fields.iter().map(|field| {
+                let field_name = &field.ident;
+                quote!(#field_name: ::std::default::Default::default())
+            });
+            quote!(#name { #(#fields,)* })
+        }
+        syn::Data::Enum(ref data) => {
+            let designated = context.default_variants.0.get(type_name);
+            let variant = data
+                .variants
+                .iter()
+                .find(|variant| Some(&variant.ident.to_string()) == designated)
+                .or_else(|| data.variants.iter().next());
+            match variant {
+                Some(variant) => {
+                    let variant_name = &variant.ident;
+                    match variant.fields {
+                        syn::Fields::Unit => quote!(#name::#variant_name),
+                        _ => quote!(#name::#variant_name(::std::default::Default::default())),
+                    }
+                }
+                None => return quote!(),
+            }
+        }
+        syn::Data::Union(_) => return quote!(),
+    };
+
+    quote! {
+        #[allow(deprecated)]
+        impl ::std::default::Default for #name {
+            fn default() -> Self {
+                #value
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_use_every_field_and_the_designated_variant() {
+        let schema = Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        let definition = quote! {
+            #[derive(Deserialize)]
+            pub struct Viewer {
+                pub name: Option<String>,
+                pub on: ViewerOn,
+            }
+        };
+        let on_enum = quote!(
+            pub enum ViewerOn {
+                User(ViewerOnUser),
+                Bot,
+            }
+        );
+
+        assert!(default_impl(&context, &definition, "Viewer").is_empty());
+
+        context.default_impls = true;
+        assert_eq!(
+            default_impl(&context, &definition, "Viewer").to_string(),
+            "# [ allow ( deprecated ) ] impl :: std :: default :: Default for Viewer { fn default ( ) -> Self { Viewer { name : :: std :: default :: Default :: default ( ) , on : :: std :: default :: Default :: default ( ) , } } }"
+        );
+        assert_eq!(
+            default_impl(&context, &on_enum, "Actor").to_string(),
+            "# [ allow ( deprecated ) ] impl :: std :: default :: Default for ViewerOn { fn default ( ) -> Self { ViewerOn :: User ( :: std :: default :: Default :: default ( ) ) } }"
+        );
+
+        context.default_variants = DefaultVariants::parse("Actor=Bot").unwrap();
+        assert_eq!(
+            default_impl(&context, &on_enum, "Actor").to_string(),
+            "# [ allow ( deprecated ) ] impl :: std :: default :: Default for ViewerOn { fn default ( ) -> Self { ViewerOn :: Bot } }"
+        );
+    }
+
+    #[test]
+    fn invalid_default_variants_are_reported() {
+        assert!(DefaultVariants::parse("Episode").is_err());
+
+        let schema = Schema::new();
+        let err = DefaultVariants::parse("Episode=JEDI")
+            .unwrap()
+            .check(&schema)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Default variant for `Episode`, which is not an enum, union or interface of the schema."
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use proc_macro2::TokenStream;
+
 /// Whether an item is deprecated, with context.
 #[derive(Debug, PartialEq, Hash, Clone)]
 pub enum DeprecationStatus {
@@ -16,6 +18,8 @@ pub enum DeprecationStrategy {
     Deny,
     /// Allow use of deprecated items in queries, but warn about them (default).
     Warn,
+    /// Like `Warn`, but also emit a compiler warning for every deprecated field selected in the query, even if the generated field is never read.
+    WarnAlways,
 }
 
 impl Default for DeprecationStrategy {
@@ -23,3 +27,71 @@ impl Default for DeprecationStrategy {
         DeprecationStrategy::Warn
     }
 }
+
+impl ::std::str::FromStr for DeprecationStrategy {
+    type Err = ::failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "allow" => Ok(DeprecationStrategy::Allow),
+            "deny" => Ok(DeprecationStrategy::Deny),
+            "warn" => Ok(DeprecationStrategy::Warn),
+            "warn_always" => Ok(DeprecationStrategy::WarnAlways),
+            other => Err(format_err!(
+                "Invalid deprecation strategy: `{}`. Valid values are `allow`, `deny`, `warn` and `warn_always`.",
+                other
+            )),
+        }
+    }
+}
+
+/// The attribute to put on a generated item with the given deprecation status, or `None` if the item should not be generated at all.
+pub(crate) fn deprecation_attribute(
+    status: &DeprecationStatus,
+    strategy: &DeprecationStrategy,
+) -> Option<TokenStream> {
+    match (status, strategy) {
+        // If the item is deprecated and we are denying usage, don't generate it at all.
+        (DeprecationStatus::Deprecated(_), DeprecationStrategy::Deny) => None,
+        // Everything is allowed so there is nothing to do.
+        (_, DeprecationStrategy::Allow) => Some(quote!()),
+        // Current so there is nothing to do.
+        (DeprecationStatus::Current, _) => Some(quote!()),
+        // A reason was provided, translate it to a note.
+        (
+            DeprecationStatus::Deprecated(Some(reason)),
+            DeprecationStrategy::Warn | DeprecationStrategy::WarnAlways,
+        ) => Some(quote!(#[deprecated(note = #reason)])),
+        // No reason provided, just mark as deprecated.
+        (
+            DeprecationStatus::Deprecated(None),
+            DeprecationStrategy::Warn | DeprecationStrategy::WarnAlways,
+        ) => Some(quote!(#[deprecated])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deprecation_strategy_from_str() {
+        assert_eq!(
+            "allow".parse::<DeprecationStrategy>().unwrap(),
+            DeprecationStrategy::Allow
+        );
+        assert_eq!(
+            "deny".parse::<DeprecationStrategy>().unwrap(),
+            DeprecationStrategy::Deny
+        );
+        assert_eq!(
+            " warn ".parse::<DeprecationStrategy>().unwrap(),
+            DeprecationStrategy::Warn
+        );
+        assert_eq!(
+            "warn_always".parse::<DeprecationStrategy>().unwrap(),
+            DeprecationStrategy::WarnAlways
+        );
+        assert!("Warn".parse::<DeprecationStrategy>().is_err());
+    }
+}
@@ -0,0 +1,65 @@
+/// Sets of traits derived on every generated type, on top of `additional_derives`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum DeriveProfile {
+    /// Only what (de)serialization needs: `Deserialize` on responses, `Serialize` on variables and inputs (default).
+    #[default]
+    Minimal,
+    /// `Debug`, `Clone` and `PartialEq` on every type.
+    Standard,
+    /// Like `Standard`, and every type can be both serialized and deserialized, to store and replay responses and variables.
+    Full,
+}
+
+impl ::std::str::FromStr for DeriveProfile {
+    type Err = ::failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "minimal" => Ok(DeriveProfile::Minimal),
+            "standard" => Ok(DeriveProfile::Standard),
+            "full" => Ok(DeriveProfile::Full),
+            other => Err(format_err!(
+                "Invalid derive profile: `{}`. Valid values are `minimal`, `standard` and `full`.",
+                other
+            )),
+        }
+    }
+}
+
+impl DeriveProfile {
+    /// The traits derived on the response types. Enums skip the serde traits, they have their own implementations.
+    pub(crate) fn response_derives(&self) -> &'static [&'static str] {
+        match self {
+            DeriveProfile::Minimal => &[],
+            DeriveProfile::Standard => &["Debug", "Clone", "PartialEq"],
+            DeriveProfile::Full => &["Debug", "Clone", "PartialEq", "Serialize"],
+        }
+    }
+
+    /// The traits derived on the variables and input types.
+    pub(crate) fn variables_derives(&self) -> &'static [&'static str] {
+        match self {
+            DeriveProfile::Minimal => &[],
+            DeriveProfile::Standard => &["Debug", "Clone", "PartialEq"],
+            DeriveProfile::Full => &["Debug", "Clone", "PartialEq", "Deserialize"],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derive_profiles_parse() {
+        assert_eq!(
+            "standard".parse::<DeriveProfile>().unwrap(),
+            DeriveProfile::Standard
+        );
+        assert_eq!(
+            " full ".parse::<DeriveProfile>().unwrap(),
+            DeriveProfile::Full
+        );
+        assert!("everything".parse::<DeriveProfile>().is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use field_type::FieldType;
+use graphql_parser::schema;
+use introspection_response::RustIntrospectionQuerySchemaDirectives;
+
+/// A custom directive defined by the schema (simplified schema representation).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GqlDirective<'schema> {
+    pub name: &'schema str,
+    pub description: Option<&'schema str>,
+    /// The arguments, with their types.
+    pub arguments: Vec<(&'schema str, FieldType<'schema>)>,
+    /// Where the directive can be used, like `FIELD` or `FIELD_DEFINITION`.
+    pub locations: Vec<&'schema str>,
+    /// Whether the directive can be used more than once at the same location.
+    pub is_repeatable: bool,
+}
+
+impl<'schema> GqlDirective<'schema> {
+    pub(crate) fn from_graphql_parser_directive(
+        directive: &'schema schema::DirectiveDefinition,
+    ) -> Self {
+        GqlDirective {
+            name: &directive.name,
+            description: directive.description.as_deref(),
+            arguments: ::sdl::directive_arguments(directive)
+                .into_iter()
+                .map(|argument| {
+                    (
+                        argument.name.as_str(),
+                        FieldType::from(&argument.value_type),
+                    )
+                })
+                .collect(),
+            locations: directive
+                .locations
+                .iter()
+                .map(schema::DirectiveLocation::as_str)
+                .collect(),
+            is_repeatable: ::sdl::is_repeatable(directive),
+        }
+    }
+
+    pub(crate) fn from_introspected_schema_json(
+        directive: &'schema RustIntrospectionQuerySchemaDirectives,
+    ) -> Self {
+        GqlDirective {
+            name: directive.name.as_ref().expect("directive name"),
+            description: directive.description.as_deref(),
+            arguments: directive
+                .args
+                .iter()
+                .flat_map(|args| args.iter())
+                .filter_map(|arg| arg.as_ref())
+                .map(|arg| {
+                    (
+                        arg.input_value
+                            .name
+                            .as_ref()
+                            .expect("directive argument name")
+                            .as_str(),
+                        arg.input_value
+                            .type_
+                            .as_ref()
+                            .map(FieldType::from)
+                            .expect("directive argument type"),
+                    )
+                })
+                .collect(),
+            locations: directive
+                .locations
+                .iter()
+                .flat_map(|locations| locations.iter())
+                .filter_map(|location| location.as_ref())
+                .map(|location| location.as_str())
+                .collect(),
+            is_repeatable: directive.is_repeatable.unwrap_or(false),
+        }
+    }
+}
+
+/// A custom directive used in the schema, on a type, field, argument or enum value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectiveUsage<'schema> {
+    pub name: &'schema str,
+    pub arguments: Vec<(&'schema str, &'schema schema::Value)>,
+}
+
+impl<'schema> ::std::convert::From<&'schema schema::Directive> for DirectiveUsage<'schema> {
+    fn from(directive: &'schema schema::Directive) -> Self {
+        DirectiveUsage {
+            name: &directive.name,
+            arguments: directive
+                .arguments
+                .iter()
+                .map(|(name, value)| (name.as_str(), value))
+                .collect(),
+        }
+    }
+}
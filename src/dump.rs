@@ -0,0 +1,91 @@
+use proc_macro2::{Ident, TokenStream};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Comma-separated list of the generated modules to dump, or `*` for all of them.
+pub const DUMP_ENV_VAR: &str = "GRAPHQL_CLIENT_DUMP";
+/// Directory to write the dumped modules to, as `<module_name>.rs`. They are printed to stderr when it is not set.
+pub const DUMP_DIR_ENV_VAR: &str = "GRAPHQL_CLIENT_DUMP_DIR";
+
+/// Format Rust code with rustfmt. The code is returned unchanged if rustfmt is not installed or fails.
+pub fn format_rust_code(code: &str) -> String {
+    let child = Command::new("rustfmt")
+        .args(["--edition", "2018", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(_) => return code.to_owned(),
+    };
+
+    let written = child
+        .stdin
+        .take()
+        .map(|mut stdin| stdin.write_all(code.as_bytes()).is_ok())
+        .unwrap_or(false);
+
+    match child.wait_with_output() {
+        Ok(ref output) if written && output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+        _ => code.to_owned(),
+    }
+}
+
+/// Dump the generated module if it was requested through the `GRAPHQL_CLIENT_DUMP` environment variable.
+///
+/// Note that cargo does not know about this variable, so the crate has to be touched for the derive to run again.
+pub(crate) fn dump_if_requested(module_name: &Ident, tokens: &TokenStream) {
+    let requested = match ::std::env::var(DUMP_ENV_VAR) {
+        Ok(requested) => requested,
+        Err(_) => return,
+    };
+    let module_name = module_name.to_string();
+
+    if !is_requested(&requested, &module_name) {
+        return;
+    }
+
+    let code = format_rust_code(&tokens.to_string());
+
+    match ::std::env::var_os(DUMP_DIR_ENV_VAR) {
+        Some(dir) => {
+            let path = Path::new(&dir).join(format!("{}.rs", module_name));
+            if let Err(err) = ::std::fs::write(&path, code) {
+                eprintln!(
+                    "graphql_client: could not write {}: {}",
+                    path.display(),
+                    err
+                );
+            }
+        }
+        None => eprintln!(
+            "// graphql_client: generated module `{}`\n{}",
+            module_name, code
+        ),
+    }
+}
+
+fn is_requested(requested: &str, module_name: &str) -> bool {
+    requested
+        .split(',')
+        .map(str::trim)
+        .any(|name| name == "*" || name == module_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_requested_works() {
+        assert!(is_requested("*", "star_wars_query"));
+        assert!(is_requested("other, star_wars_query", "star_wars_query"));
+        assert!(!is_requested("other", "star_wars_query"));
+        assert!(!is_requested("", "star_wars_query"));
+    }
+}
@@ -1,3 +1,4 @@
+use deprecation::{deprecation_attribute, DeprecationStatus};
 use proc_macro2::{Ident, Span, TokenStream};
 use std::cell::Cell;
 
@@ -7,6 +8,7 @@ pub const ENUMS_PREFIX: &str = "";
 pub struct EnumVariant<'schema> {
     pub description: Option<&'schema str>,
     pub name: &'schema str,
+    pub deprecation: DeprecationStatus,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -20,41 +22,77 @@ pub struct GqlEnum<'schema> {
 impl<'schema> GqlEnum<'schema> {
     pub(crate) fn to_rust(&self, query_context: &::query::QueryContext) -> TokenStream {
         let derives = query_context.response_enum_derives();
-        let variant_names: Vec<TokenStream> = self
+        // Denied variants are left out, so their values deserialize to `Other`.
+        let variants: Vec<(&EnumVariant, TokenStream)> = self
             .variants
             .iter()
-            .map(|v| {
+            .filter_map(|v| {
+                let strategy = query_context.deprecation_strategy_for(self.name, v.name);
+                deprecation_attribute(&v.deprecation, strategy).map(|attribute| (v, attribute))
+            })
+            .collect();
+        let variant_names: Vec<TokenStream> = variants
+            .iter()
+            .map(|(v, deprecation)| {
                 let name = Ident::new(&v.name, Span::call_site());
-                let description = &v.description;
-                let description = description.as_ref().map(|d| quote!(#[doc = #d]));
-                quote!(#description #name)
+                let description = ::shared::description_attributes(v.description);
+                quote!(#description #deprecation #name)
             })
             .collect();
         let variant_names = &variant_names;
         let name_ident = Ident::new(&format!("{}{}", ENUMS_PREFIX, self.name), Span::call_site());
-        let constructors: Vec<_> = self
-            .variants
+        let constructors: Vec<_> = variants
             .iter()
-            .map(|v| {
+            .map(|(v, _)| {
                 let v = Ident::new(&v.name, Span::call_site());
                 quote!(#name_ident::#v)
             })
             .collect();
         let constructors = &constructors;
-        let variant_str: Vec<&str> = self.variants.iter().map(|v| v.name).collect();
+        let variant_str: Vec<&str> = variants.iter().map(|(v, _)| v.name).collect();
         let variant_str = &variant_str;
 
         let name = name_ident.clone();
-
-        quote! {
+        let description = ::shared::description_attributes(self.description);
+        let definition = quote! {
             #derives
+            #description
             pub enum #name {
                 #(#variant_names,)*
-                Other(String),
+                Other(::std::string::String),
             }
+        };
+        let mock = ::mocks::mock_impl(query_context, &definition);
+        let default = ::defaults::default_impl(query_context, &definition, self.name);
+        let validation = ::json_validation::validation_impl(query_context, &definition);
+        let pretty_print = ::pretty_print::pretty_print_impl(query_context, &definition);
+        let graphql_value = if query_context.graphql_values {
+            Some(::graphql_values::enum_impl(
+                &name,
+                constructors,
+                variant_str,
+            ))
+        } else {
+            None
+        };
+
+        quote! {
+            #definition
+
+            #mock
+
+            #default
+
+            #validation
 
+
+            #pretty_print
+
+            #graphql_value
+
+            #[allow(deprecated)]
             impl ::serde::Serialize for #name {
-                fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+                fn serialize<S: ::serde::Serializer>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error> {
                     ser.serialize_str(match *self {
                         #(#constructors => #variant_str,)*
                         #name::Other(ref s) => &s,
@@ -62,16 +100,53 @@ impl<'schema> GqlEnum<'schema> {
                 }
             }
 
+            #[allow(deprecated)]
             impl<'de> ::serde::Deserialize<'de> for #name {
-                fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-                    let s = <String>::deserialize(deserializer)?;
+                fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+                    let s = <::std::string::String as ::serde::Deserialize>::deserialize(deserializer)?;
 
                     match s.as_str() {
-                        #(#variant_str => Ok(#constructors),)*
-                        _ => Ok(#name::Other(s)),
+                        #(#variant_str => ::std::result::Result::Ok(#constructors),)*
+                        _ => ::std::result::Result::Ok(#name::Other(s)),
                     }
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use deprecation::DeprecationStrategy;
+    use graphql_parser;
+    use query::QueryContext;
+    use schema::Schema;
+
+    #[test]
+    fn deprecated_enum_values_follow_the_strategy() {
+        let schema = graphql_parser::parse_schema(
+            r#"
+            enum Order {
+              ASC
+              DESC
+              RANDOM @deprecated(reason: "Sort client-side")
+            }
+            "#,
+        )
+        .unwrap();
+        let schema = Schema::from(&schema);
+
+        let generate = |strategy| {
+            let context = QueryContext::new(&schema, strategy);
+            context.schema.enums["Order"].to_rust(&context).to_string()
+        };
+
+        let warn = generate(DeprecationStrategy::Warn);
+        assert!(warn.contains("# [ deprecated ( note = \"Sort client-side\" ) ] RANDOM"));
+        assert!(warn.contains("# [ allow ( deprecated ) ] impl :: serde :: Serialize"));
+
+        let deny = generate(DeprecationStrategy::Deny);
+        assert!(!deny.contains("RANDOM"));
+        assert!(deny.contains("Order :: DESC"));
+    }
+}
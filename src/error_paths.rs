@@ -0,0 +1,257 @@
+use constants::TYPENAME_FIELD;
+use heck::CamelCase;
+use objects::GqlObject;
+use proc_macro2::{Ident, Span, TokenStream};
+use query::QueryContext;
+use selection::{Selection, SelectionItem};
+
+/// The enum of the top-level fields of an operation, to match the first segment of `errors[].path` in responses, and the struct classifying them in a response with errors if `partial_data` is set.
+pub(crate) fn root_path_enum(
+    context: &QueryContext,
+    root: &GqlObject,
+    selection: &Selection,
+    enum_name: &Ident,
+    partial_data_name: &Ident,
+) -> TokenStream {
+    if !context.error_paths && !context.partial_data {
+        return quote!();
+    }
+
+    let mut roots: Vec<(&str, bool)> = Vec::new();
+    collect_roots(context, root, selection, &mut roots);
+
+    if roots.is_empty() {
+        return quote!();
+    }
+
+    let variants: Vec<Ident> = roots
+        .iter()
+        .map(|(key, _)| Ident::new(&key.to_camel_case(), Span::call_site()))
+        .collect();
+    let keys: Vec<&str> = roots.iter().map(|(key, _)| *key).collect();
+    let nullable: Vec<bool> = roots.iter().map(|(_, nullable)| *nullable).collect();
+    let paths: Vec<TokenStream> = variants
+        .iter()
+        .map(|variant| quote!(#enum_name::#variant))
+        .collect();
+    let paths = &paths;
+    let keys = &keys;
+    let partial_data = if context.partial_data {
+        partial_data(enum_name, partial_data_name)
+    } else {
+        quote!()
+    };
+
+    quote! {
+        /// The top-level fields of the operation, as found at the start of `errors[].path`.
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::marker::Copy, ::std::cmp::PartialEq, ::std::cmp::Eq, ::std::hash::Hash)]
+        pub enum #enum_name {
+            #(#variants,)*
+        }
+
+        impl #enum_name {
+            /// Every top-level field, in selection order.
+            pub const ALL: &'static [#enum_name] = &[#(#paths,)*];
+
+            /// The key of the field in the response data.
+            pub fn response_key(&self) -> &'static str {
+                match self {
+                    #(#paths => #keys,)*
+                }
+            }
+
+            /// The field with the given key in the response data, if it is selected by the operation.
+            pub fn from_response_key(key: &str) -> ::std::option::Option<Self> {
+                match key {
+                    #(#keys => ::std::option::Option::Some(#paths),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            /// Whether the field is nullable, in which case an error on it leaves the field `None` instead of blanking the whole response data.
+            pub fn is_nullable(&self) -> bool {
+                match self {
+                    #(#paths => #nullable,)*
+                }
+            }
+        }
+
+        impl<'de> ::serde::Deserialize<'de> for #enum_name {
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+                let key = <::std::string::String as ::serde::Deserialize>::deserialize(deserializer)?;
+                #enum_name::from_response_key(&key).ok_or_else(|| {
+                    <D::Error as ::serde::de::Error>::unknown_variant(&key, &[#(#keys),*])
+                })
+            }
+        }
+
+        #partial_data
+    }
+}
+
+/// The classification of the top-level fields of a response with errors, so callers can retry or degrade per field.
+fn partial_data(enum_name: &Ident, struct_name: &Ident) -> TokenStream {
+    quote! {
+        /// The top-level fields of a response, classified by how the `errors` affected them.
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::cmp::PartialEq, ::std::default::Default)]
+        pub struct #struct_name {
+            /// Fields that are null or missing because of an error at or below them.
+            pub errored: ::std::vec::Vec<#enum_name>,
+            /// Fields that are present, with errors below them blanking some nullable fields.
+            pub partial: ::std::vec::Vec<#enum_name>,
+            /// Fields that are null without any error, as allowed by the schema.
+            pub null: ::std::vec::Vec<#enum_name>,
+        }
+
+        impl #struct_name {
+            /// Classify the top-level fields from a raw response, with its `data` and `errors`.
+            pub fn from_response(response: &::serde_json::Value) -> Self {
+                let errors = response
+                    .get("errors")
+                    .and_then(|errors| errors.as_array())
+                    .map(|errors| errors.as_slice())
+                    .unwrap_or(&[]);
+                let mut errored_keys: ::std::vec::Vec<&str> = ::std::vec::Vec::new();
+                for error in errors {
+                    let key = error.get("path").and_then(|path| path.get(0)).and_then(|key| key.as_str());
+                    if let ::std::option::Option::Some(key) = key {
+                        errored_keys.push(key);
+                    }
+                }
+                let mut classification = <Self as ::std::default::Default>::default();
+
+                let data = match response.get("data") {
+                    ::std::option::Option::Some(data) if !data.is_null() => data,
+                    // An error on a non-null field blanks the whole data.
+                    _ => {
+                        if !errors.is_empty() {
+                            classification.errored = #enum_name::ALL.to_vec();
+                        }
+                        return classification;
+                    }
+                };
+
+                for path in #enum_name::ALL {
+                    let is_null = data
+                        .get(path.response_key())
+                        .map(|value| value.is_null())
+                        .unwrap_or(true);
+                    let has_errors = errored_keys.contains(&path.response_key());
+                    match (is_null, has_errors) {
+                        (true, true) => classification.errored.push(*path),
+                        (false, true) => classification.partial.push(*path),
+                        (true, false) => classification.null.push(*path),
+                        (false, false) => (),
+                    }
+                }
+
+                classification
+            }
+
+            /// Whether no top-level field was affected by an error.
+            pub fn is_complete(&self) -> bool {
+                self.errored.is_empty() && self.partial.is_empty()
+            }
+        }
+    }
+}
+
+/// The response keys of the selected root fields, looking into fragments.
+pub(crate) fn root_response_keys<'a>(
+    context: &'a QueryContext,
+    root: &GqlObject,
+    selection: &'a Selection,
+) -> Vec<&'a str> {
+    let mut roots = Vec::new();
+    collect_roots(context, root, selection, &mut roots);
+    roots.into_iter().map(|(key, _)| key).collect()
+}
+
+/// The response keys of the selected root fields, with their nullability, looking into fragments. `__typename` and `@client` fields never appear in server errors.
+fn collect_roots<'a>(
+    context: &'a QueryContext,
+    root: &GqlObject,
+    selection: &'a Selection,
+    roots: &mut Vec<(&'a str, bool)>,
+) {
+    for item in &selection.0 {
+        match item {
+            SelectionItem::Field(field) => {
+                let key = field.alias.unwrap_or(field.name);
+                let schema_field = context.schema.field(root.name, field.name);
+                if let Some(schema_field) = schema_field {
+                    if field.name != TYPENAME_FIELD
+                        && !field.is_client
+                        && roots.iter().all(|(existing, _)| *existing != key)
+                    {
+                        roots.push((key, schema_field.type_.is_optional()));
+                    }
+                }
+            }
+            SelectionItem::FragmentSpread(spread) => {
+                if let Some(fragment) = context.fragments.get(spread.fragment_name) {
+                    collect_roots(context, root, &fragment.selection, roots);
+                }
+            }
+            SelectionItem::InlineFragment(fragment) => {
+                collect_roots(context, root, &fragment.fields, roots);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::Schema;
+
+    #[test]
+    fn root_path_enum_uses_response_keys() {
+        let schema_document = ::graphql_parser::parse_schema(
+            "type Query { viewer: User! repository(name: String!): Repository } type User { login: String } type Repository { name: String }",
+        )
+        .unwrap();
+        let schema = Schema::from(&schema_document);
+        let mut context = QueryContext::new_empty(&schema);
+        let query = ::graphql_parser::parse_query(
+            "query Q { viewer { login } repo: repository(name: \"x\") { name } __typename }",
+        )
+        .unwrap();
+        let selection = match &query.definitions[0] {
+            ::graphql_parser::query::Definition::Operation(
+                ::graphql_parser::query::OperationDefinition::Query(q),
+            ) => Selection::from(&q.selection_set),
+            _ => unreachable!(),
+        };
+
+        let generate = |context: &QueryContext| {
+            root_path_enum(
+                context,
+                &schema.objects["Query"],
+                &selection,
+                &Ident::new("ResponseDataPath", Span::call_site()),
+                &Ident::new("PartialData", Span::call_site()),
+            )
+            .to_string()
+        };
+
+        assert_eq!(generate(&context), "");
+
+        context.error_paths = true;
+        let generated = generate(&context);
+
+        assert!(generated.contains("pub enum ResponseDataPath { Viewer , Repo , }"));
+        assert!(generated.contains(
+            "ResponseDataPath :: Viewer => \"viewer\" , ResponseDataPath :: Repo => \"repo\" ,"
+        ));
+        assert!(generated
+            .contains("ResponseDataPath :: Viewer => false , ResponseDataPath :: Repo => true ,"));
+        assert!(generated.contains(
+            "pub const ALL : & 'static [ ResponseDataPath ] = & [ ResponseDataPath :: Viewer , ResponseDataPath :: Repo , ] ;"
+        ));
+        assert!(!generated.contains("PartialData"));
+
+        context.partial_data = true;
+        assert!(generate(&context).contains("pub struct PartialData { "));
+    }
+}
@@ -0,0 +1,125 @@
+use proc_macro2::{Ident, TokenStream};
+use syn;
+
+/// `Struct::execute`, sending the query with reqwest and deserializing the response, and reporting it to the `call_hook` function if there is one. Generated when the `reqwest` feature is enabled.
+pub(crate) fn reqwest_execute(
+    struct_name: &Ident,
+    module_name: &Ident,
+    variables_struct_name: &Ident,
+    response_data_struct_name: &Ident,
+    call_hook: Option<&str>,
+) -> TokenStream {
+    if !cfg!(feature = "reqwest") {
+        return quote!();
+    }
+    let result_type = quote!(::std::result::Result<::graphql_client::Response<#module_name::#response_data_struct_name>, ::reqwest::Error>);
+    let call = instrumented(quote!(
+        client.post(url).json(&body).send().await?.json().await
+    ));
+    let call = observed(call, &result_type, call_hook);
+
+    quote! {
+        impl #struct_name {
+            /// Post the query to `url` with reqwest, and deserialize the response.
+            pub async fn execute(
+                client: &::reqwest::Client,
+                url: &str,
+                variables: #module_name::#variables_struct_name,
+            ) -> #result_type {
+                let body = <Self as ::graphql_client::GraphQLQuery>::build_query(variables);
+                #call
+            }
+        }
+    }
+}
+
+/// The `call` of the executors, given the query `body`, in a `graphql` span with the operation name and the variables redacted. Left as is unless the `tracing` feature is enabled.
+fn instrumented(call: TokenStream) -> TokenStream {
+    if !cfg!(feature = "tracing") {
+        return call;
+    }
+
+    quote! {
+        let span = ::tracing::info_span!(
+            "graphql",
+            graphql.operation = body.operation_name,
+            graphql.variables = "[redacted]",
+        );
+        ::tracing::Instrument::instrument(async { #call }, span).await
+    }
+}
+
+/// The `call` of the executors, followed by a call of `call_hook` with the operation name, the time the call took and whether it succeeded. Left as is when there is no hook.
+fn observed(call: TokenStream, result_type: &TokenStream, call_hook: Option<&str>) -> TokenStream {
+    let call_hook: syn::Path = match call_hook {
+        Some(call_hook) => syn::parse_str(call_hook).expect("validated call hook"),
+        None => return call,
+    };
+
+    quote! {
+        let started = ::std::time::Instant::now();
+        let result: #result_type = async { #call }.await;
+        #call_hook(body.operation_name, started.elapsed(), ::std::result::Result::is_ok(&result));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::Span;
+
+    #[test]
+    fn reqwest_execute_follows_the_feature() {
+        let generated = reqwest_execute(
+            &Ident::new("MyQuery", Span::call_site()),
+            &Ident::new("my_query", Span::call_site()),
+            &Ident::new("Variables", Span::call_site()),
+            &Ident::new("ResponseData", Span::call_site()),
+            None,
+        )
+        .to_string();
+
+        if cfg!(feature = "reqwest") {
+            assert!(generated.contains(
+                "pub async fn execute ( client : & :: reqwest :: Client , url : & str , variables : my_query :: Variables , ) -> :: std :: result :: Result < :: graphql_client :: Response < my_query :: ResponseData > , :: reqwest :: Error >"
+            ));
+        } else {
+            assert!(generated.is_empty());
+        }
+    }
+
+    #[test]
+    fn instrumentation_follows_the_feature() {
+        let generated = instrumented(quote!(executor.execute(&body).await)).to_string();
+
+        if cfg!(feature = "tracing") {
+            assert!(generated.contains(
+                ":: tracing :: Instrument :: instrument ( async { executor . execute ( & body ) . await } , span ) . await"
+            ));
+            assert!(generated.contains("graphql . variables = \"[redacted]\""));
+        } else {
+            assert_eq!(generated, "executor . execute ( & body ) . await");
+        }
+    }
+
+    #[test]
+    fn calls_are_reported_to_the_call_hook() {
+        let result_type = quote!(::std::result::Result<u8, ()>);
+        let call = quote!(executor.execute(&body).await);
+
+        assert_eq!(
+            observed(call.clone(), &result_type, None).to_string(),
+            call.to_string()
+        );
+
+        let generated =
+            observed(call, &result_type, Some("crate::metrics::record_call")).to_string();
+        assert!(generated.contains(
+            "let result : :: std :: result :: Result < u8 , ( ) > = async { executor . execute ( & body ) . await } . await ;"
+        ));
+        assert!(generated.contains(
+            "crate :: metrics :: record_call ( body . operation_name , started . elapsed ( ) , :: std :: result :: Result :: is_ok ( & result ) ) ;"
+        ));
+    }
+}
@@ -0,0 +1,302 @@
+use deprecation::DeprecationStatus;
+use directives::DirectiveUsage;
+use failure;
+use field_type::FieldType;
+use graphql_parser::schema::{Definition, Directive, TypeDefinition, TypeExtension, Value};
+use graphql_parser::{self, query, schema};
+use heck::{CamelCase, SnakeCase};
+use hooks::ItemKind;
+use objects::{GqlArgument, GqlObject, GqlObjectField};
+use operations::{Operation, OperationType};
+use proc_macro2::{Ident, Span, TokenStream};
+use query::QueryContext;
+use scalars::Scalar;
+use schema::Schema;
+use selection::SelectionItem;
+use std::collections::BTreeSet;
+use unions::GqlUnion;
+
+/// The directive marking the entities, with the fields identifying them. Federation 2 subgraphs can import it under its namespaced name.
+const KEY_DIRECTIVES: &[&str] = &["key", "federation__key"];
+
+pub(crate) const ANY_SCALAR: &str = "_Any";
+pub(crate) const ENTITY_UNION: &str = "_Entity";
+pub(crate) const SERVICE_TYPE: &str = "_Service";
+pub(crate) const ENTITIES_FIELD: &str = "_entities";
+pub(crate) const SERVICE_FIELD: &str = "_service";
+
+impl<'schema> Schema<'schema> {
+    /// Add what a federation subgraph serves without declaring it in its SDL: the `_Any` scalar, the `_Entity` union of the types with a `@key`, the `_Service` type, and the `_entities` and `_service` fields of the query type.
+    pub(crate) fn ingest_federation(&mut self, ast: &'schema schema::Document) {
+        let entities = entity_names(ast);
+        if entities.is_empty() {
+            return;
+        }
+
+        self.scalars.entry(ANY_SCALAR).or_insert_with(|| Scalar {
+            name: ANY_SCALAR,
+            description: None,
+            specified_by: None,
+            is_required: false.into(),
+        });
+        self.objects.entry(SERVICE_TYPE).or_insert_with(|| {
+            let mut service = GqlObject::new(SERVICE_TYPE, None);
+            service.fields.push(field(
+                "sdl",
+                FieldType::Optional(Box::new(FieldType::Named("String"))),
+            ));
+            service
+        });
+        self.unions
+            .entry(ENTITY_UNION)
+            .or_insert_with(|| GqlUnion {
+                name: ENTITY_UNION,
+                description: None,
+                variants: BTreeSet::new(),
+                is_required: false.into(),
+            })
+            .variants
+            .extend(entities);
+
+        let query_type = self
+            .root_type(&OperationType::Query)
+            .unwrap_or_else(|| OperationType::Query.conventional_root_name());
+        let query = self
+            .objects
+            .entry(query_type)
+            .or_insert_with(|| GqlObject::new(query_type, None));
+        if !query
+            .fields
+            .iter()
+            .any(|field| field.name == ENTITIES_FIELD)
+        {
+            query.fields.push(field(
+                ENTITIES_FIELD,
+                FieldType::Vector(Box::new(FieldType::Optional(Box::new(FieldType::Named(
+                    ENTITY_UNION,
+                ))))),
+            ));
+            self.field_arguments.insert(
+                format!("{}.{}", query_type, ENTITIES_FIELD),
+                vec![GqlArgument {
+                    name: "representations",
+                    type_: FieldType::Vector(Box::new(FieldType::Named(ANY_SCALAR))),
+                    has_default: false,
+                }],
+            );
+        }
+        if !query.fields.iter().any(|field| field.name == SERVICE_FIELD) {
+            query
+                .fields
+                .push(field(SERVICE_FIELD, FieldType::Named(SERVICE_TYPE)));
+        }
+    }
+}
+
+/// The object types of an SDL document with a `@key`, defined or extended.
+pub(crate) fn entity_names(ast: &schema::Document) -> Vec<&str> {
+    let is_entity = |directives: &[Directive]| {
+        directives
+            .iter()
+            .any(|d| KEY_DIRECTIVES.contains(&d.name.as_str()))
+    };
+
+    ast.definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::TypeDefinition(TypeDefinition::Object(obj))
+                if is_entity(&obj.directives) =>
+            {
+                Some(obj.name.as_str())
+            }
+            Definition::TypeExtension(TypeExtension::Object(extension))
+                if is_entity(&extension.directives) =>
+            {
+                Some(extension.name.as_str())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn field(name: &'static str, type_: FieldType<'static>) -> GqlObjectField<'static> {
+    GqlObjectField {
+        description: None,
+        name,
+        type_,
+        deprecation: DeprecationStatus::Current,
+    }
+}
+
+/// The fields of the first resolvable `@key` of an entity, like `id organization { id }`. Keys are only known for SDL schemas.
+fn key_fields<'schema>(schema: &Schema<'schema>, entity: &str) -> Option<&'schema str> {
+    first_resolvable_key(schema.directive_usages.get(entity)?)
+}
+
+/// The fields of the first resolvable `@key` among the directives of an object type definition or extension.
+pub(crate) fn declared_key_fields(directives: &[Directive]) -> Option<&str> {
+    let usages: Vec<DirectiveUsage> = directives.iter().map(DirectiveUsage::from).collect();
+    first_resolvable_key(&usages)
+}
+
+fn first_resolvable_key<'schema>(usages: &[DirectiveUsage<'schema>]) -> Option<&'schema str> {
+    usages
+        .iter()
+        .filter(|usage| KEY_DIRECTIVES.contains(&usage.name))
+        .filter(|usage| {
+            !usage
+                .arguments
+                .iter()
+                .any(|(name, value)| *name == "resolvable" && **value == Value::Boolean(false))
+        })
+        .filter_map(|usage| usage.arguments.iter().find(|(name, _)| *name == "fields"))
+        .filter_map(|(_, value)| match value {
+            Value::String(fields) => Some(fields.as_str()),
+            _ => None,
+        })
+        .next()
+}
+
+/// The key fields as a selection set.
+pub(crate) fn parse_key(fields: &str) -> Result<query::SelectionSet, failure::Error> {
+    let document = graphql_parser::parse_query(&format!("{{ {} }}", fields))?;
+    match document.definitions.into_iter().next() {
+        Some(query::Definition::Operation(query::OperationDefinition::SelectionSet(
+            selection_set,
+        ))) => Ok(selection_set),
+        _ => Err(format_err!("Invalid key fields: `{}`", fields)),
+    }
+}
+
+/// The representations of the entities the operation fetches with `_entities`, named like `ProductRepresentation`. They serialize to the `__typename` and the key fields of the entity, as the `representations` argument expects.
+pub(crate) fn representations(
+    context: &QueryContext,
+    operation: &Operation,
+    prefix: &str,
+) -> Result<TokenStream, failure::Error> {
+    let mut entities = BTreeSet::new();
+    for item in &operation.selection.0 {
+        let entities_selection = match item {
+            SelectionItem::Field(field) if field.name == ENTITIES_FIELD => &field.fields,
+            _ => continue,
+        };
+        for item in &entities_selection.0 {
+            match item {
+                SelectionItem::InlineFragment(inline) => {
+                    entities.insert(inline.on);
+                }
+                SelectionItem::FragmentSpread(spread) => {
+                    if let Some(fragment) = context.fragments.get(spread.fragment_name) {
+                        entities.insert(fragment.on);
+                    }
+                }
+                SelectionItem::Field(_) => (),
+            }
+        }
+    }
+
+    let mut representations = Vec::with_capacity(entities.len());
+    for entity in entities {
+        let fields = key_fields(context.schema, entity).ok_or_else(|| {
+            format_err!(
+                "`{}` is fetched with `{}` but has no `@key` in the schema.",
+                entity,
+                ENTITIES_FIELD
+            )
+        })?;
+        let rust_name = format!("{}{}Representation", prefix, entity);
+        let tokens =
+            representation_structs(context, entity, &rust_name, &parse_key(fields)?, true)?;
+        representations.push(context.hooked(ItemKind::InputObject, entity, &rust_name, tokens));
+    }
+
+    Ok(quote!(#(#representations)*))
+}
+
+/// The struct for the key fields selected on `type_name`, and the structs of the nested selections. Entity representations are tagged with their `__typename` and have a constructor taking the key fields.
+fn representation_structs(
+    context: &QueryContext,
+    type_name: &str,
+    rust_name: &str,
+    selection_set: &query::SelectionSet,
+    is_entity: bool,
+) -> Result<TokenStream, failure::Error> {
+    let mut nested = Vec::new();
+    let mut fields = Vec::new();
+    let mut names = Vec::new();
+    let mut types = Vec::new();
+    for item in &selection_set.items {
+        let field = match item {
+            query::Selection::Field(field) => field,
+            _ => {
+                return Err(format_err!(
+                    "Key fields of `{}` cannot use fragments.",
+                    type_name
+                ))
+            }
+        };
+        let schema_field = context
+            .schema
+            .field(type_name, &field.name)
+            .ok_or_else(|| format_err!("Unknown key field `{}` on `{}`.", field.name, type_name))?;
+
+        let ty = if field.selection_set.items.is_empty() {
+            schema_field.type_.to_rust(context, "")
+        } else {
+            let nested_name = format!("{}{}", rust_name, field.name.to_camel_case());
+            nested.push(representation_structs(
+                context,
+                schema_field.type_.inner_name_str(),
+                &nested_name,
+                &field.selection_set,
+                false,
+            )?);
+            schema_field.type_.to_rust(context, &nested_name)
+        };
+        let snake_case_name = field.name.to_snake_case();
+        let rename = ::shared::field_rename_annotation(&field.name, &snake_case_name);
+        let strategy = ::arbitrary::field_attribute(context, &schema_field.type_, false);
+        let adapter = ::serde_adapters::field_attribute(
+            context,
+            Some(type_name),
+            &field.name,
+            &schema_field.type_,
+        );
+        let name = Ident::new(&snake_case_name, Span::call_site());
+        fields.push(quote!(#rename #strategy #adapter pub #name: #ty));
+        names.push(name);
+        types.push(ty);
+    }
+
+    let variables_derives = context.variables_derives();
+    let name = Ident::new(rust_name, Span::call_site());
+    let representation = if is_entity {
+        let names = &names;
+        quote! {
+            #variables_derives
+            #[serde(tag = "__typename", rename = #type_name)]
+            pub struct #name {
+                #(#fields,)*
+            }
+
+            impl #name {
+                pub fn new(#(#names: #types),*) -> Self {
+                    #name { #(#names),* }
+                }
+            }
+        }
+    } else {
+        quote! {
+            #variables_derives
+            pub struct #name {
+                #(#fields,)*
+            }
+        }
+    };
+
+    Ok(quote! {
+        #representation
+
+        #(#nested)*
+    })
+}
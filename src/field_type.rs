@@ -1,5 +1,6 @@
 use enums::ENUMS_PREFIX;
 use graphql_parser;
+use indirection::Pointer;
 use introspection_response;
 use proc_macro2::{Ident, Span, TokenStream};
 use query::QueryContext;
@@ -21,6 +22,7 @@ impl<'a> FieldType<'a> {
             prefix
         };
         match &self {
+            FieldType::Named("String") => quote!(::std::string::String),
             FieldType::Named(ref name) => {
                 let full_name = if context
                     .schema
@@ -51,11 +53,70 @@ impl<'a> FieldType<'a> {
             }
             FieldType::Optional(inner) => {
                 let inner = inner.to_rust(context, &prefix);
-                quote!( Option<#inner>)
+                quote!(::std::option::Option<#inner>)
             }
             FieldType::Vector(inner) => {
                 let inner = inner.to_rust(context, &prefix);
-                quote!( Vec<#inner>)
+                quote!(::std::vec::Vec<#inner>)
+            }
+        }
+    }
+
+    /// Like `to_rust`, with the string type of the response fields in place of `String`.
+    pub(crate) fn to_response_rust(&self, context: &QueryContext, prefix: &str) -> TokenStream {
+        match (self, context.response_string_type()) {
+            (FieldType::Named("String"), Some(ty)) => quote!(#ty),
+            (FieldType::Optional(inner), Some(_)) => {
+                let inner = inner.to_response_rust(context, prefix);
+                quote!(::std::option::Option<#inner>)
+            }
+            (FieldType::Vector(inner), Some(_)) => {
+                let inner = inner.to_response_rust(context, prefix);
+                quote!(::std::vec::Vec<#inner>)
+            }
+            _ => self.to_rust(context, prefix),
+        }
+    }
+
+    /// Like `to_rust`, with the named type held by `pointer`. Boxes are left out of lists, which already hold their items on the heap.
+    pub(crate) fn to_rust_behind(
+        &self,
+        context: &QueryContext,
+        prefix: &str,
+        pointer: Pointer,
+    ) -> TokenStream {
+        match self {
+            FieldType::Named(_) => pointer.wrap(&self.to_rust(context, prefix)),
+            FieldType::Optional(inner) => {
+                let inner = inner.to_rust_behind(context, prefix, pointer);
+                quote!(::std::option::Option<#inner>)
+            }
+            FieldType::Vector(_) if pointer == Pointer::Box => self.to_rust(context, prefix),
+            FieldType::Vector(inner) => {
+                let inner = inner.to_rust_behind(context, prefix, pointer);
+                quote!(::std::vec::Vec<#inner>)
+            }
+        }
+    }
+
+    /// The type of a variable borrowing its value with the lifetime `'a`: strings as `&'a str`, lists as slices, and other non-`Copy` types by reference. Also returns whether anything is borrowed.
+    pub(crate) fn to_borrowed_rust(&self, context: &QueryContext) -> (TokenStream, bool) {
+        match self {
+            FieldType::Named(name) if ["Int", "Float", "Boolean"].contains(name) => {
+                (self.to_rust(context, ""), false)
+            }
+            FieldType::Named(name) if ["String", "ID"].contains(name) => (quote!(&'a str), true),
+            FieldType::Named(_) => {
+                let ty = self.to_rust(context, "");
+                (quote!(&'a #ty), true)
+            }
+            FieldType::Optional(inner) => {
+                let (inner, borrows) = inner.to_borrowed_rust(context);
+                (quote!(::std::option::Option<#inner>), borrows)
+            }
+            FieldType::Vector(inner) => {
+                let inner = inner.to_rust(context, "");
+                (quote!(&'a [#inner]), true)
             }
         }
     }
@@ -76,6 +137,15 @@ impl<'a> FieldType<'a> {
         }
     }
 
+    /// The type in GraphQL syntax, like `[Episode!]`.
+    pub(crate) fn to_graphql(&self) -> String {
+        match self {
+            FieldType::Named(name) => format!("{}!", name),
+            FieldType::Optional(inner) => inner.to_graphql().trim_end_matches('!').to_string(),
+            FieldType::Vector(inner) => format!("[{}]!", inner.to_graphql()),
+        }
+    }
+
     /// A type is indirected if it is a (flat or nested) list type, optional or not.
     ///
     /// We use this to determine whether a type needs to be boxed for recursion.
@@ -170,6 +240,29 @@ mod tests {
     use graphql_parser::schema::Type as GqlParserType;
     use introspection_response::{FullTypeFieldsType, TypeRef, __TypeKind};
 
+    #[test]
+    fn borrowed_types_borrow_strings_and_lists() {
+        let schema = ::schema::Schema::new();
+        let context = QueryContext::new_empty(&schema);
+        let borrowed = |ty: FieldType| {
+            let (ty, borrows) = ty.to_borrowed_rust(&context);
+            (ty.to_string(), borrows)
+        };
+
+        assert_eq!(
+            borrowed(FieldType::Named("Int")),
+            ("Int".to_string(), false)
+        );
+        assert_eq!(
+            borrowed(FieldType::Optional(Box::new(FieldType::Named("ID")))),
+            (":: std :: option :: Option < & 'a str >".to_string(), true)
+        );
+        assert_eq!(
+            borrowed(FieldType::Vector(Box::new(FieldType::Named("String")))),
+            ("& 'a [ :: std :: string :: String ]".to_string(), true)
+        );
+    }
+
     #[test]
     fn field_type_from_graphql_parser_schema_type_works() {
         let ty = GqlParserType::NamedType("Cat".to_owned());
@@ -183,6 +276,15 @@ mod tests {
         assert_eq!(FieldType::from(&ty), FieldType::Named("Cat"));
     }
 
+    #[test]
+    fn field_types_print_as_graphql() {
+        let ty = FieldType::Optional(Box::new(FieldType::Vector(Box::new(FieldType::Named(
+            "Episode",
+        )))));
+        assert_eq!(ty.to_graphql(), "[Episode!]");
+        assert_eq!(FieldType::Named("Int").to_graphql(), "Int!");
+    }
+
     #[test]
     fn field_type_from_introspection_response_works() {
         let ty = FullTypeFieldsType {
@@ -0,0 +1,573 @@
+use failure;
+use field_type::FieldType;
+use graphql_parser::query::{
+    self, Definition, Document, Field, OperationDefinition, Selection, SelectionSet, TypeCondition,
+};
+use operations::OperationType;
+use schema::Schema;
+use serde_json::{Map, Value};
+use std::cell::Cell;
+use std::path::Path;
+
+/// The number of items of the lists of the fixtures.
+const LIST_LENGTH: usize = 2;
+
+/// Values of the custom scalars in fixtures, as the server would send them.
+pub trait FixtureHooks {
+    /// The value of the custom `scalar` for a field, by schema coordinate like `Human.birthday`. `None` falls back to a string naming the scalar.
+    fn custom_scalar(&self, _scalar: &str, _coordinate: &str) -> Option<Value> {
+        None
+    }
+}
+
+/// No hooks: custom scalars are strings.
+impl FixtureHooks for () {}
+
+/// A plausible JSON response of an operation of `query`, the `data` a server following the schema at `schema_path` could send, to serve from stub servers in integration tests.
+///
+/// Nullable fields are present, lists have two items, enums have their first value, and abstract types resolve to their first possible type spread in the selection. Scalars are numbered so the items of lists differ. The operation is the one named `operation_name`, or the first one of the document.
+///
+/// The `@mock` directives of the fields of the query are hints for their values, so fixtures look realistic. They are not sent to the server.
+///
+/// - `@mock(value: "Alice")` is the value of the field, any literal without variables.
+/// - `@mock(min: 18, max: 99)` is the range of the numbers of `Int` and `Float` fields. Either bound can be left out.
+/// - `@mock(length: 5)` is the number of items of the lists of the field.
+pub fn response_fixture(
+    query: &str,
+    schema_path: &Path,
+    operation_name: Option<&str>,
+    hooks: &dyn FixtureHooks,
+) -> Result<Value, failure::Error> {
+    let document = ::string_literals::parse_query(query)?;
+    let parsed_schema = ::load_schema(schema_path, None)?;
+    let schema = Schema::from(&*parsed_schema);
+
+    let (operation_type, selection_set) = select_operation(&document, operation_name)?;
+    let root_name = match schema.root_type(&operation_type) {
+        Some(root_name) if schema.objects.contains_key(root_name) => root_name,
+        _ => {
+            return Err(format_err!(
+                "The schema has no {} type.",
+                operation_type.keyword()
+            ))
+        }
+    };
+
+    let generator = FixtureGenerator {
+        schema: &schema,
+        document: &document,
+        hooks,
+        counter: Cell::new(0),
+    };
+    let data = generator.object(root_name, &[selection_set])?;
+    let mut response = Map::new();
+    response.insert("data".to_owned(), data);
+    Ok(Value::Object(response))
+}
+
+/// The type and the selection of the operation named `operation_name`, or of the first operation.
+fn select_operation<'doc>(
+    document: &'doc Document,
+    operation_name: Option<&str>,
+) -> Result<(OperationType, &'doc SelectionSet), failure::Error> {
+    let mut operations = document
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Operation(OperationDefinition::Query(query)) => Some((
+                query.name.as_deref(),
+                OperationType::Query,
+                &query.selection_set,
+            )),
+            Definition::Operation(OperationDefinition::Mutation(mutation)) => Some((
+                mutation.name.as_deref(),
+                OperationType::Mutation,
+                &mutation.selection_set,
+            )),
+            Definition::Operation(OperationDefinition::Subscription(subscription)) => Some((
+                subscription.name.as_deref(),
+                OperationType::Subscription,
+                &subscription.selection_set,
+            )),
+            Definition::Operation(OperationDefinition::SelectionSet(selection_set)) => {
+                Some((None, OperationType::Query, selection_set))
+            }
+            Definition::Fragment(_) => None,
+        });
+
+    let operation = match operation_name {
+        Some(operation_name) => operations.find(|(name, _, _)| *name == Some(operation_name)),
+        None => operations.next(),
+    };
+    match (operation, operation_name) {
+        (Some((_, operation_type, selection_set)), _) => Ok((operation_type, selection_set)),
+        (None, Some(operation_name)) => Err(format_err!(
+            "The document has no operation named `{}`.",
+            operation_name
+        )),
+        (None, None) => Err(format_err!("The document has no operation.")),
+    }
+}
+
+struct FixtureGenerator<'a, 'schema: 'a> {
+    schema: &'a Schema<'schema>,
+    document: &'a Document,
+    hooks: &'a dyn FixtureHooks,
+    /// Numbers the scalars, so the values of the fixture differ.
+    counter: Cell<usize>,
+}
+
+impl<'a, 'schema> FixtureGenerator<'a, 'schema> {
+    fn next_number(&self) -> usize {
+        self.counter.set(self.counter.get() + 1);
+        self.counter.get()
+    }
+
+    /// The object of a concrete type, with the fields of the selection sets merged by response key.
+    fn object(
+        &self,
+        type_name: &str,
+        selection_sets: &[&'a SelectionSet],
+    ) -> Result<Value, failure::Error> {
+        let mut fields: Vec<(&'a str, Vec<&'a Field>)> = Vec::new();
+        for selection_set in selection_sets {
+            self.collect_fields(type_name, selection_set, &mut fields);
+        }
+
+        let mut object = Map::new();
+        for (response_key, fields) in fields {
+            let field = fields[0];
+            let value = if field.name == ::constants::TYPENAME_FIELD {
+                Value::String(type_name.to_owned())
+            } else {
+                let definition = self.schema.field(type_name, &field.name).ok_or_else(|| {
+                    format_err!("No field `{}` on the type `{}`.", field.name, type_name)
+                })?;
+                let coordinate = format!("{}.{}", type_name, field.name);
+                let hints = MockHints::of(&fields)
+                    .map_err(|err| format_err!("Invalid @mock on `{}`: {}", coordinate, err))?;
+                match hints.value {
+                    Some(Value::Null) if !definition.type_.is_optional() => {
+                        return Err(format_err!(
+                            "Invalid @mock on `{}`: the field is not nullable.",
+                            coordinate
+                        ))
+                    }
+                    Some(ref value) => value.clone(),
+                    None => {
+                        let selection_sets: Vec<&'a SelectionSet> =
+                            fields.iter().map(|field| &field.selection_set).collect();
+                        self.value(&coordinate, &definition.type_, &selection_sets, &hints)?
+                    }
+                }
+            };
+            object.insert(response_key.to_owned(), value);
+        }
+        Ok(Value::Object(object))
+    }
+
+    /// The fields the selection set selects on the type, by response key, following the fragments that apply to it.
+    fn collect_fields(
+        &self,
+        type_name: &str,
+        selection_set: &'a SelectionSet,
+        fields: &mut Vec<(&'a str, Vec<&'a Field>)>,
+    ) {
+        for item in &selection_set.items {
+            match item {
+                Selection::Field(field) if ::client_fields::is_client_field(&field.directives) => {}
+                Selection::Field(field) => {
+                    let response_key = field.alias.as_ref().unwrap_or(&field.name).as_str();
+                    match fields.iter_mut().find(|(key, _)| *key == response_key) {
+                        Some((_, same_key)) => same_key.push(field),
+                        None => fields.push((response_key, vec![field])),
+                    }
+                }
+                Selection::InlineFragment(fragment) => {
+                    let applies = match fragment.type_condition {
+                        Some(TypeCondition::On(ref on)) => self.applies_to(on, type_name),
+                        None => true,
+                    };
+                    if applies {
+                        self.collect_fields(type_name, &fragment.selection_set, fields);
+                    }
+                }
+                Selection::FragmentSpread(spread) => {
+                    if let Some((on, selection_set)) = self.fragment(&spread.fragment_name) {
+                        if self.applies_to(on, type_name) {
+                            self.collect_fields(type_name, selection_set, fields);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The type condition and the selection set of a fragment of the document.
+    fn fragment(&self, name: &str) -> Option<(&'a str, &'a SelectionSet)> {
+        self.document
+            .definitions
+            .iter()
+            .find_map(|definition| match definition {
+                Definition::Fragment(fragment) if fragment.name == name => {
+                    let TypeCondition::On(ref on) = fragment.type_condition;
+                    Some((on.as_str(), &fragment.selection_set))
+                }
+                _ => None,
+            })
+    }
+
+    /// Whether a fragment on `on` applies to the object type: it is that type, or an interface or union the type belongs to.
+    fn applies_to(&self, on: &str, type_name: &str) -> bool {
+        on == type_name || self.possible_types(on).contains(&type_name)
+    }
+
+    /// The object types of an interface or union, sorted by name.
+    fn possible_types(&self, type_name: &str) -> Vec<&'schema str> {
+        let mut possible_types: Vec<&'schema str> = match (
+            self.schema.interfaces.get(type_name),
+            self.schema.unions.get(type_name),
+        ) {
+            (Some(interface), _) => interface.implemented_by.iter().cloned().collect(),
+            (_, Some(union)) => union.variants.iter().cloned().collect(),
+            _ => Vec::new(),
+        };
+        possible_types.sort_unstable();
+        possible_types
+    }
+
+    /// The concrete type an interface or union resolves to: the first of its possible types a fragment of the selection is on, or else its first possible type.
+    fn resolve_type(
+        &self,
+        type_name: &str,
+        selection_sets: &[&'a SelectionSet],
+    ) -> Result<&'schema str, failure::Error> {
+        let possible_types = self.possible_types(type_name);
+        let mut conditions = Vec::new();
+        for selection_set in selection_sets {
+            self.type_conditions(selection_set, &mut conditions);
+        }
+
+        possible_types
+            .iter()
+            .find(|possible_type| conditions.contains(possible_type))
+            .or_else(|| possible_types.first())
+            .cloned()
+            .ok_or_else(|| format_err!("The type `{}` has no possible type.", type_name))
+    }
+
+    /// The type conditions of the fragments of a selection set.
+    fn type_conditions(&self, selection_set: &'a SelectionSet, conditions: &mut Vec<&'a str>) {
+        for item in &selection_set.items {
+            match item {
+                Selection::Field(_) => (),
+                Selection::InlineFragment(fragment) => {
+                    if let Some(TypeCondition::On(ref on)) = fragment.type_condition {
+                        conditions.push(on);
+                    }
+                    self.type_conditions(&fragment.selection_set, conditions);
+                }
+                Selection::FragmentSpread(spread) => {
+                    if let Some((on, selection_set)) = self.fragment(&spread.fragment_name) {
+                        conditions.push(on);
+                        self.type_conditions(selection_set, conditions);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The value of a field of type `ty`, at the schema coordinate `coordinate`.
+    fn value(
+        &self,
+        coordinate: &str,
+        ty: &FieldType,
+        selection_sets: &[&'a SelectionSet],
+        hints: &MockHints,
+    ) -> Result<Value, failure::Error> {
+        match ty {
+            FieldType::Optional(inner) => self.value(coordinate, inner, selection_sets, hints),
+            FieldType::Vector(inner) => (0..hints.length.unwrap_or(LIST_LENGTH))
+                .map(|_| self.value(coordinate, inner, selection_sets, hints))
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            FieldType::Named(name) => self.named_value(coordinate, name, selection_sets, hints),
+        }
+    }
+
+    fn named_value(
+        &self,
+        coordinate: &str,
+        type_name: &str,
+        selection_sets: &[&'a SelectionSet],
+        hints: &MockHints,
+    ) -> Result<Value, failure::Error> {
+        if self.schema.objects.contains_key(type_name) {
+            return self.object(type_name, selection_sets);
+        }
+        if self.schema.interfaces.contains_key(type_name)
+            || self.schema.unions.contains_key(type_name)
+        {
+            let concrete_type = self.resolve_type(type_name, selection_sets)?;
+            return self.object(concrete_type, selection_sets);
+        }
+        if let Some(enm) = self.schema.enums.get(type_name) {
+            return Ok(enm
+                .variants
+                .first()
+                .map(|variant| Value::String(variant.name.to_owned()))
+                .unwrap_or(Value::Null));
+        }
+
+        let number = self.next_number();
+        let field_name = coordinate.rsplit('.').next().unwrap_or(coordinate);
+        Ok(match type_name {
+            "Boolean" => Value::Bool(number % 2 == 1),
+            "Int" => match hints.range() {
+                Some((min, max)) => {
+                    let (min, max) = (min.ceil() as i64, max.floor() as i64);
+                    Value::from(min + number as i64 % (max - min + 1).max(1))
+                }
+                None => Value::from(number),
+            },
+            "Float" => match hints.range() {
+                Some((min, max)) => Value::from(min + (max - min) * (number % 11) as f64 / 10.0),
+                None => Value::from(number as f64 + 0.5),
+            },
+            "ID" => Value::String(number.to_string()),
+            "String" => Value::String(format!("{} {}", field_name, number)),
+            scalar => self
+                .hooks
+                .custom_scalar(scalar, coordinate)
+                .unwrap_or_else(|| Value::String(format!("{} {}", scalar, number))),
+        })
+    }
+}
+
+/// The `@mock` hints of a field.
+#[derive(Debug, Default)]
+struct MockHints {
+    value: Option<Value>,
+    min: Option<f64>,
+    max: Option<f64>,
+    length: Option<usize>,
+}
+
+impl MockHints {
+    /// The hints of the first `@mock` directive of the fields selected under the same response key.
+    fn of(fields: &[&Field]) -> Result<MockHints, failure::Error> {
+        let directive = fields
+            .iter()
+            .flat_map(|field| field.directives.iter())
+            .find(|directive| directive.name == ::constants::MOCK_DIRECTIVE);
+        let directive = match directive {
+            Some(directive) => directive,
+            None => return Ok(MockHints::default()),
+        };
+
+        let mut hints = MockHints::default();
+        for (name, value) in &directive.arguments {
+            match (name.as_str(), value) {
+                ("value", value) => hints.value = Some(json_value(value)?),
+                ("min", value) => hints.min = Some(number(name, value)?),
+                ("max", value) => hints.max = Some(number(name, value)?),
+                ("length", query::Value::Int(length)) if length.as_i64().unwrap_or(-1) >= 0 => {
+                    hints.length = length.as_i64().map(|length| length as usize)
+                }
+                ("length", _) => return Err(format_err!("`length` is not a positive integer.")),
+                (other, _) => return Err(format_err!("unknown argument `{}`.", other)),
+            }
+        }
+        if let (Some(min), Some(max)) = (hints.min, hints.max) {
+            if min > max {
+                return Err(format_err!("`min` is greater than `max`."));
+            }
+        }
+        Ok(hints)
+    }
+
+    /// The range of the numbers, when a bound is set. The other bound is 100 away.
+    fn range(&self) -> Option<(f64, f64)> {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => Some((min, max)),
+            (Some(min), None) => Some((min, min + 100.0)),
+            (None, Some(max)) => Some((max - 100.0, max)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// The number of a `min` or `max` argument.
+fn number(name: &str, value: &query::Value) -> Result<f64, failure::Error> {
+    match value {
+        query::Value::Int(number) => number
+            .as_i64()
+            .map(|number| number as f64)
+            .ok_or_else(|| format_err!("`{}` is out of range.", name)),
+        query::Value::Float(number) => Ok(*number),
+        _ => Err(format_err!("`{}` is not a number.", name)),
+    }
+}
+
+/// The JSON value of a literal of the document.
+fn json_value(value: &query::Value) -> Result<Value, failure::Error> {
+    Ok(match value {
+        query::Value::Variable(name) => {
+            return Err(format_err!(
+                "the variable `${}` has no value in fixtures.",
+                name
+            ))
+        }
+        query::Value::Int(number) => number.as_i64().map(Value::from).unwrap_or(Value::Null),
+        query::Value::Float(number) => Value::from(*number),
+        query::Value::String(string) => Value::String(string.clone()),
+        query::Value::Boolean(boolean) => Value::Bool(*boolean),
+        query::Value::Null => Value::Null,
+        query::Value::Enum(name) => Value::String(name.clone()),
+        query::Value::List(items) => {
+            Value::Array(items.iter().map(json_value).collect::<Result<_, _>>()?)
+        }
+        query::Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), json_value(value)?)))
+                .collect::<Result<_, failure::Error>>()?,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Dates;
+
+    impl FixtureHooks for Dates {
+        fn custom_scalar(&self, scalar: &str, _coordinate: &str) -> Option<Value> {
+            match scalar {
+                "Date" => Some(Value::String("2019-01-01".to_owned())),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn fixtures_follow_the_schema() {
+        let dir = ::std::env::temp_dir().join("graphql_client_fixtures");
+        ::std::fs::create_dir_all(&dir).unwrap();
+        let schema_path = dir.join("schema.graphql");
+        ::std::fs::write(
+            &schema_path,
+            r#"
+            scalar Date
+            scalar Url
+            enum Episode { NEWHOPE, EMPIRE }
+            interface Character { name: String! }
+            type Droid implements Character { name: String!, primaryFunction: String }
+            type Human implements Character { name: String!, born: Date, homepage: Url, friends: [Character!]! }
+            type Query { hero(episode: Episode): Character, episodes: [Episode!] }
+            "#,
+        )
+        .unwrap();
+
+        let query = r#"
+            query Hero {
+              hero {
+                __typename
+                name
+                ... on Human { born homepage friends { name } }
+                ...DroidFields
+              }
+              favorite: episodes
+            }
+            fragment DroidFields on Droid { primaryFunction }
+        "#;
+
+        assert_eq!(
+            response_fixture(query, &schema_path, Some("Hero"), &Dates).unwrap(),
+            json!({
+                "data": {
+                    "hero": {
+                        "__typename": "Droid",
+                        "name": "name 1",
+                        "primaryFunction": "primaryFunction 2",
+                    },
+                    "favorite": ["NEWHOPE", "NEWHOPE"],
+                }
+            })
+        );
+
+        let human = "{ hero { ... on Human { name born homepage friends { name } } } }";
+        assert_eq!(
+            response_fixture(human, &schema_path, None, &Dates).unwrap(),
+            json!({
+                "data": {
+                    "hero": {
+                        "name": "name 1",
+                        "born": "2019-01-01",
+                        "homepage": "Url 3",
+                        "friends": [{ "name": "name 4" }, { "name": "name 5" }],
+                    }
+                }
+            })
+        );
+        assert!(response_fixture(human, &schema_path, Some("Missing"), &()).is_err());
+    }
+
+    #[test]
+    fn mock_hints_shape_the_fixtures() {
+        let dir = ::std::env::temp_dir().join("graphql_client_fixtures_hints");
+        ::std::fs::create_dir_all(&dir).unwrap();
+        let schema_path = dir.join("schema.graphql");
+        ::std::fs::write(
+            &schema_path,
+            "type User { name: String!, age: Int, score: Float!, tags: [String!]!, friends: [User!] }
+            type Query { viewer: User! }",
+        )
+        .unwrap();
+
+        let query = r#"
+            query Viewer {
+              viewer {
+                name @mock(value: "Alice")
+                age @mock(min: 18, max: 20)
+                score @mock(min: 1.0, max: 2.0)
+                tags @mock(length: 3)
+                friends @mock(value: null)
+              }
+            }
+        "#;
+        assert_eq!(
+            response_fixture(query, &schema_path, None, &()).unwrap(),
+            json!({
+                "data": {
+                    "viewer": {
+                        "name": "Alice",
+                        "age": 19,
+                        "score": 1.2,
+                        "tags": ["tags 3", "tags 4", "tags 5"],
+                        "friends": null,
+                    }
+                }
+            })
+        );
+
+        let invalid = |query: &str| {
+            response_fixture(query, &schema_path, None, &())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            invalid("{ viewer { name @mock(value: null) } }"),
+            "Invalid @mock on `User.name`: the field is not nullable."
+        );
+        assert_eq!(
+            invalid("{ viewer { age @mock(min: 3, max: 1) } }"),
+            "Invalid @mock on `User.age`: `min` is greater than `max`."
+        );
+        assert_eq!(
+            invalid("{ viewer { tags @mock(size: 3) } }"),
+            "Invalid @mock on `User.tags`: unknown argument `size`."
+        );
+    }
+}
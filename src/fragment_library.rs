@@ -0,0 +1,97 @@
+use client_fields::operation_selection_set;
+use failure;
+use globs;
+use graphql_parser::query::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+/// Append to the document the fragments of the library files matching `pattern` it spreads, directly or through other library fragments. The fragments the document defines itself take precedence over the library ones.
+pub(crate) fn include_spread_fragments(
+    pattern: &str,
+    query_string: &str,
+    document: &Document,
+    tracked_files: &mut Vec<PathBuf>,
+) -> Result<(String, Document), failure::Error> {
+    let mut library: BTreeMap<String, (FragmentDefinition, String)> = BTreeMap::new();
+    for path in globs::expand(pattern)? {
+        let contents = ::read_file(&path)?;
+        let parsed = ::string_literals::parse_query(&contents)
+            .map_err(|err| format_err!("Could not parse {}: {}", path.display(), err))?;
+        for definition in parsed.definitions {
+            match definition {
+                Definition::Fragment(fragment) => {
+                    if let Some((_, other_path)) = library.get(&fragment.name) {
+                        return Err(format_err!(
+                            "Fragment `{}` is defined in both {} and {}",
+                            fragment.name,
+                            other_path,
+                            path.display()
+                        ));
+                    }
+                    library.insert(
+                        fragment.name.clone(),
+                        (fragment, path.display().to_string()),
+                    );
+                }
+                Definition::Operation(_) => {
+                    return Err(format_err!(
+                        "{} is in the fragment library but defines an operation",
+                        path.display()
+                    ))
+                }
+            }
+        }
+        tracked_files.push(path);
+    }
+
+    let mut defined = BTreeSet::new();
+    let mut pending = Vec::new();
+    for definition in &document.definitions {
+        match definition {
+            Definition::Fragment(fragment) => {
+                defined.insert(fragment.name.clone());
+                spreads(&fragment.selection_set, &mut pending);
+            }
+            Definition::Operation(operation) => {
+                spreads(operation_selection_set(operation), &mut pending);
+            }
+        }
+    }
+
+    let mut included = BTreeMap::new();
+    while let Some(name) = pending.pop() {
+        if defined.contains(&name) || included.contains_key(&name) {
+            continue;
+        }
+        // Unknown fragments are left for code generation to report.
+        if let Some((fragment, _)) = library.remove(&name) {
+            spreads(&fragment.selection_set, &mut pending);
+            included.insert(name, fragment);
+        }
+    }
+
+    if included.is_empty() {
+        return Ok((query_string.to_owned(), document.clone()));
+    }
+
+    let fragments = Document {
+        definitions: included.into_values().map(Definition::Fragment).collect(),
+    };
+    let query_string = format!(
+        "{}\n{}",
+        query_string,
+        ::string_literals::print_query(&fragments)
+    );
+    let document = ::string_literals::parse_query(&query_string)?;
+    Ok((query_string, document))
+}
+
+pub(crate) fn spreads(selection_set: &SelectionSet, names: &mut Vec<String>) {
+    for item in &selection_set.items {
+        match item {
+            Selection::Field(field) => spreads(&field.selection_set, names),
+            Selection::InlineFragment(inline) => spreads(&inline.selection_set, names),
+            Selection::FragmentSpread(spread) => names.push(spread.fragment_name.clone()),
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use proc_macro2::TokenStream;
+use graphql_parser::query::Directive;
+use heck::SnakeCase;
+use proc_macro2::{Ident, Span, TokenStream};
 use query::QueryContext;
 use selection::Selection;
 use std::cell::Cell;
@@ -12,6 +14,8 @@ pub(crate) struct GqlFragment<'query> {
     pub on: &'query str,
     /// The selected fields.
     pub selection: Selection<'query>,
+    /// The directives on the fragment definition.
+    pub directives: &'query [Directive],
     /// Whether the fragment is used in the current query
     pub is_required: Cell<bool>,
 }
@@ -19,16 +23,52 @@ pub(crate) struct GqlFragment<'query> {
 impl<'query> GqlFragment<'query> {
     /// Generate all the Rust code required by the fragment's object selection.
     pub(crate) fn to_rust(&self, context: &QueryContext) -> Result<TokenStream, ::failure::Error> {
-        if let Some(obj) = context.schema.objects.get(&self.on) {
-            obj.response_for_selection(context, &self.selection, &self.name)
+        let definition = if let Some(obj) = context.schema.objects.get(&self.on) {
+            obj.response_for_selection(context, &self.selection, &self.name)?
         } else if let Some(iface) = context.schema.interfaces.get(&self.on) {
-            iface.response_for_selection(context, &self.selection, &self.name)
+            iface.response_for_selection(context, &self.selection, &self.name)?
         } else {
             Err(format_err!(
                 "Fragment {} is defined on unknown type: {}",
                 self.name,
                 self.on
             ))?
+        };
+        let carrier_trait = self.carrier_trait();
+
+        Ok(quote! {
+            #definition
+
+            #carrier_trait
+        })
+    }
+
+    /// The `Has<Fragment>` trait, implemented by the fragment and every struct spreading it, so generic code can accept any of them.
+    fn carrier_trait(&self) -> TokenStream {
+        let name = Ident::new(self.name, Span::call_site());
+        let trait_name = carrier_trait_name(self.name);
+        let method_name = Ident::new(&self.name.to_snake_case(), Span::call_site());
+        let doc = format!(
+            "Implemented by the types containing the `{}` fragment.",
+            self.name
+        );
+
+        quote! {
+            #[doc = #doc]
+            pub trait #trait_name {
+                fn #method_name(&self) -> &#name;
+            }
+
+            impl #trait_name for #name {
+                fn #method_name(&self) -> &#name {
+                    self
+                }
+            }
         }
     }
 }
+
+/// The name of the trait implemented by the types containing a fragment.
+pub(crate) fn carrier_trait_name(fragment_name: &str) -> Ident {
+    Ident::new(&format!("Has{}", fragment_name), Span::call_site())
+}
@@ -0,0 +1,69 @@
+use operations::{Operation, OperationType};
+use proc_macro2::{Ident, Span, TokenStream};
+use query_text::QueryText;
+
+/// The `url_encode` function of the GET request helpers, percent-encoding everything but the unreserved characters. Generated once per module.
+pub(crate) fn url_encode_helper() -> TokenStream {
+    quote! {
+        /// Append `value` to `encoded`, percent-encoded for a URL query string.
+        fn url_encode(value: &str, encoded: &mut ::std::string::String) {
+            for byte in value.bytes() {
+                match byte {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                        encoded.push(byte as char)
+                    }
+                    _ => {
+                        const HEX: &[u8; 16] = b"0123456789ABCDEF";
+                        encoded.push('%');
+                        encoded.push(HEX[(byte >> 4) as usize] as char);
+                        encoded.push(HEX[(byte & 0xF) as usize] as char);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `to_get_query_string()` on the variables of a query, the query string of a GET request running it as in the GraphQL over HTTP spec, so responses can be cached by CDNs. Mutations and subscriptions must not be sent with GET, they get none.
+pub(crate) fn get_query_string_impl(
+    operation: &Operation,
+    multiple_operations: bool,
+    query_text: &QueryText,
+) -> TokenStream {
+    match operation.operation_type {
+        OperationType::Query => (),
+        OperationType::Mutation | OperationType::Subscription => return quote!(),
+    }
+
+    let variables_struct_name = if multiple_operations {
+        format!("{}Variables", operation.name)
+    } else {
+        "Variables".to_owned()
+    };
+    let variables_struct_name = Ident::new(&variables_struct_name, Span::call_site());
+    let query = query_text.expression();
+    // Names are made of letters, digits and underscores, which need no encoding.
+    let operation_name = format!("&operationName={}", operation.name);
+    // Operations without variables leave them out.
+    let variables = if operation.variables.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            query_string.push_str("&variables=");
+            url_encode(&::serde_json::to_string(self)?, &mut query_string);
+        }
+    };
+
+    quote! {
+        impl #variables_struct_name {
+            /// The query string of a GET request running the operation with these variables: `query`, `operationName` and `variables`, URL-encoded.
+            pub fn to_get_query_string(&self) -> ::std::result::Result<::std::string::String, ::serde_json::Error> {
+                let mut query_string = <::std::string::String as ::std::convert::From<&str>>::from("query=");
+                url_encode(#query, &mut query_string);
+                query_string.push_str(#operation_name);
+                #variables
+                ::std::result::Result::Ok(query_string)
+            }
+        }
+    }
+}
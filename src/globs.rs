@@ -0,0 +1,199 @@
+use failure;
+use globset::{GlobBuilder, GlobMatcher};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+/// A segment of a path pattern.
+enum Segment {
+    /// A file or directory name.
+    Literal(String),
+    /// A segment with wildcards, like `*.graphql`.
+    Wildcard(GlobMatcher),
+    /// `**`: any number of directories.
+    AnyDirectories,
+}
+
+/// The files matching a path pattern, sorted. `*` and `?` match within a path segment, and a `**` segment matches any number of directories, following symbolic links without looping on them. A pattern without wildcards is returned as is, whether the file exists or not.
+///
+/// The matched files are tracked like the query file, but cargo cannot watch the directories: a new file matching the pattern is picked up once another tracked file changes, or on `cargo clean`.
+pub(crate) fn expand(pattern: &str) -> Result<Vec<PathBuf>, failure::Error> {
+    if !pattern.contains(&['*', '?'][..]) {
+        return Ok(vec![PathBuf::from(pattern)]);
+    }
+
+    let pattern_path = Path::new(pattern);
+    let mut root = PathBuf::new();
+    let mut segments = Vec::new();
+    for component in pattern_path.components() {
+        let segment = match component {
+            Component::Normal(segment) => segment.to_string_lossy().into_owned(),
+            other if segments.is_empty() => {
+                root.push(other.as_os_str());
+                continue;
+            }
+            other => other.as_os_str().to_string_lossy().into_owned(),
+        };
+        segments.push(if segment == "**" {
+            Segment::AnyDirectories
+        } else if segment.contains(&['*', '?'][..]) {
+            // Glob matchers are regular expressions, so they never backtrack.
+            let glob = GlobBuilder::new(&segment)
+                .literal_separator(true)
+                .build()
+                .map_err(|err| format_err!("Invalid pattern `{}`: {}", pattern, err))?;
+            Segment::Wildcard(glob.compile_matcher())
+        } else {
+            Segment::Literal(segment)
+        });
+    }
+    if root.as_os_str().is_empty() {
+        root.push(".");
+    }
+
+    let mut matches = Vec::new();
+    walk(&root, &segments, &mut HashSet::new(), &mut matches);
+    matches.sort();
+    matches.dedup();
+
+    if matches.is_empty() {
+        return Err(format_err!("No file matches `{}`", pattern));
+    }
+    Ok(matches
+        .into_iter()
+        .map(|path| {
+            path.strip_prefix(".")
+                .map(Path::to_path_buf)
+                .unwrap_or(path)
+        })
+        .collect())
+}
+
+/// Add the files under `dir` matching `segments` to `matches`. `walked` holds the directories `**` was expanded in, with the number of segments left, so symbolic links back to a parent directory are only walked once.
+fn walk(
+    dir: &Path,
+    segments: &[Segment],
+    walked: &mut HashSet<(PathBuf, usize)>,
+    matches: &mut Vec<PathBuf>,
+) {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => {
+            if dir.is_file() {
+                matches.push(dir.to_path_buf());
+            }
+            return;
+        }
+    };
+
+    match segment {
+        Segment::AnyDirectories => {
+            let canonical = match dir.canonicalize() {
+                Ok(canonical) => canonical,
+                Err(_) => return,
+            };
+            if !walked.insert((canonical, segments.len())) {
+                return;
+            }
+            walk(dir, rest, walked, matches);
+            for entry in entries(dir) {
+                if entry.is_dir() {
+                    walk(&entry, segments, walked, matches);
+                }
+            }
+        }
+        Segment::Wildcard(matcher) => {
+            for entry in entries(dir) {
+                let matched = entry
+                    .file_name()
+                    .map(|name| matcher.is_match(name))
+                    .unwrap_or(false);
+                if matched {
+                    walk(&entry, rest, walked, matches);
+                }
+            }
+        }
+        Segment::Literal(name) => walk(&dir.join(name), rest, walked, matches),
+    }
+}
+
+fn entries(dir: &Path) -> Vec<PathBuf> {
+    ::std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_match_files_in_nested_directories() {
+        let root = ::std::env::temp_dir().join("graphql_client_globs");
+        let _ = ::std::fs::remove_dir_all(&root);
+        for file in &[
+            "a.graphql",
+            "b.json",
+            "users/user.graphql",
+            "users/deep/avatar.graphql",
+        ] {
+            let path = root.join(file);
+            ::std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            ::std::fs::write(path, "").unwrap();
+        }
+        let root_pattern = root.display().to_string();
+
+        assert_eq!(
+            expand(&format!("{}/*.graphql", root_pattern)).unwrap(),
+            vec![root.join("a.graphql")]
+        );
+        assert_eq!(
+            expand(&format!("{}/**/*.graphql", root_pattern)).unwrap(),
+            vec![
+                root.join("a.graphql"),
+                root.join("users/deep/avatar.graphql"),
+                root.join("users/user.graphql"),
+            ]
+        );
+        assert_eq!(
+            expand(&format!("{}/users/?ser.graphql", root_pattern)).unwrap(),
+            vec![root.join("users/user.graphql")]
+        );
+        assert!(expand(&format!("{}/*.gql", root_pattern)).is_err());
+        assert_eq!(
+            expand("src/query.graphql").unwrap(),
+            vec![PathBuf::from("src/query.graphql")]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symbolic_link_cycles_are_walked_once() {
+        let root = ::std::env::temp_dir().join("graphql_client_globs_cycles");
+        let _ = ::std::fs::remove_dir_all(&root);
+        ::std::fs::create_dir_all(root.join("users")).unwrap();
+        ::std::fs::write(root.join("users/user.graphql"), "").unwrap();
+        ::std::os::unix::fs::symlink(&root, root.join("users/root")).unwrap();
+
+        assert_eq!(
+            expand(&format!("{}/**/*.graphql", root.display())).unwrap(),
+            vec![root.join("users/user.graphql")]
+        );
+    }
+
+    #[test]
+    fn wildcards_do_not_backtrack() {
+        let root = ::std::env::temp_dir().join("graphql_client_globs_backtracking");
+        let _ = ::std::fs::remove_dir_all(&root);
+        ::std::fs::create_dir_all(&root).unwrap();
+        ::std::fs::write(root.join("a".repeat(100)), "").unwrap();
+
+        let started = ::std::time::Instant::now();
+        assert!(expand(&format!("{}/{}b", root.display(), "*a".repeat(30))).is_err());
+        assert!(started.elapsed() < ::std::time::Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,138 @@
+use field_type::FieldType;
+use proc_macro2::{Ident, TokenStream};
+use query::QueryContext;
+
+/// The `scalar_graphql_value` function turning scalars into GraphQL values through their JSON serialization. Generated once per module.
+pub(crate) fn scalar_helper() -> TokenStream {
+    quote! {
+        /// The GraphQL value of a scalar, from its JSON serialization. Numbers out of the range of `Int` become floats.
+        fn scalar_graphql_value<T: ::serde::Serialize + ?::std::marker::Sized>(value: &T) -> ::graphql_parser::query::Value {
+            fn from_json(value: ::serde_json::Value) -> ::graphql_parser::query::Value {
+                match value {
+                    ::serde_json::Value::Null => ::graphql_parser::query::Value::Null,
+                    ::serde_json::Value::Bool(value) => ::graphql_parser::query::Value::Boolean(value),
+                    ::serde_json::Value::Number(number) => {
+                        match number.as_i64().filter(|number| {
+                            *number >= <i64 as ::std::convert::From<i32>>::from(::std::i32::MIN)
+                                && *number <= <i64 as ::std::convert::From<i32>>::from(::std::i32::MAX)
+                        }) {
+                            ::std::option::Option::Some(number) => {
+                                ::graphql_parser::query::Value::Int(::std::convert::From::from(number as i32))
+                            }
+                            ::std::option::Option::None => match number.as_f64() {
+                                ::std::option::Option::Some(number) => {
+                                    ::graphql_parser::query::Value::Float(number)
+                                }
+                                ::std::option::Option::None => {
+                                    ::graphql_parser::query::Value::String(::std::string::ToString::to_string(&number))
+                                }
+                            },
+                        }
+                    }
+                    ::serde_json::Value::String(value) => ::graphql_parser::query::Value::String(value),
+                    ::serde_json::Value::Array(items) => ::graphql_parser::query::Value::List(
+                        ::std::iter::Iterator::collect(::std::iter::Iterator::map(
+                            ::std::iter::IntoIterator::into_iter(items),
+                            from_json,
+                        )),
+                    ),
+                    ::serde_json::Value::Object(fields) => ::graphql_parser::query::Value::Object(
+                        ::std::iter::Iterator::collect(::std::iter::Iterator::map(
+                            ::std::iter::IntoIterator::into_iter(fields),
+                            |(name, value)| (name, from_json(value)),
+                        )),
+                    ),
+                }
+            }
+
+            from_json(::serde_json::to_value(value).expect("scalars serialize to JSON"))
+        }
+    }
+}
+
+/// The expression of the GraphQL value of `value`, a reference to a value of type `ty`. Enums become enum values and input objects objects, with their `to_graphql_value()` methods, and scalars go through their JSON serialization.
+fn value_expression(context: &QueryContext, ty: &FieldType) -> TokenStream {
+    match ty {
+        FieldType::Optional(inner) => {
+            let inner = value_expression(context, inner);
+            quote! {
+                match value {
+                    ::std::option::Option::Some(value) => #inner,
+                    ::std::option::Option::None => ::graphql_parser::query::Value::Null,
+                }
+            }
+        }
+        FieldType::Vector(inner) => {
+            let inner = value_expression(context, inner);
+            quote! {
+                ::graphql_parser::query::Value::List(::std::iter::Iterator::collect(
+                    ::std::iter::Iterator::map(value.iter(), |value| #inner),
+                ))
+            }
+        }
+        FieldType::Named(name)
+            if context.schema.enums.contains_key(name)
+                || context.schema.inputs.contains_key(name) =>
+        {
+            quote!(value.to_graphql_value())
+        }
+        FieldType::Named(_) => quote!(scalar_graphql_value(value)),
+    }
+}
+
+/// `to_graphql_value()` on a struct of variables or an input object: an object with the GraphQL names of its fields. `fields` are the GraphQL name, the Rust name and the type of each field, and whether it is boxed.
+pub(crate) fn object_impl(
+    context: &QueryContext,
+    struct_name: &Ident,
+    description: &str,
+    fields: &[(&str, Ident, &FieldType, bool)],
+) -> TokenStream {
+    let entries = fields.iter().map(|(name, rust_name, ty, boxed)| {
+        let value = if *boxed {
+            quote!(&*self.#rust_name)
+        } else {
+            quote!(&self.#rust_name)
+        };
+        let expression = value_expression(context, ty);
+        quote! {
+            (<::std::string::String as ::std::convert::From<&str>>::from(#name), {
+                let value = #value;
+                #expression
+            })
+        }
+    });
+
+    quote! {
+        #[allow(deprecated)]
+        impl #struct_name {
+            #[doc = #description]
+            pub fn to_graphql_value(&self) -> ::graphql_parser::query::Value {
+                ::graphql_parser::query::Value::Object(
+                    ::std::iter::Iterator::collect(::std::iter::IntoIterator::into_iter(
+                        ::std::vec![#(#entries,)*],
+                    ))
+                )
+            }
+        }
+    }
+}
+
+/// `to_graphql_value()` on an enum: the enum value, to put it unquoted in a document.
+pub(crate) fn enum_impl(
+    enum_name: &Ident,
+    constructors: &[TokenStream],
+    variant_str: &[&str],
+) -> TokenStream {
+    quote! {
+        #[allow(deprecated)]
+        impl #enum_name {
+            /// The GraphQL enum value, for documents built programmatically.
+            pub fn to_graphql_value(&self) -> ::graphql_parser::query::Value {
+                ::graphql_parser::query::Value::Enum(match *self {
+                    #(#constructors => <::std::string::String as ::std::convert::From<&str>>::from(#variant_str),)*
+                    #enum_name::Other(ref other) => ::std::clone::Clone::clone(other),
+                })
+            }
+        }
+    }
+}
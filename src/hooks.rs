@@ -0,0 +1,41 @@
+use graphql_parser::query::Directive;
+use proc_macro2::TokenStream;
+
+/// What a [GeneratedItem] is generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    /// The struct for a selection on an object type, like the fields of an object, a fragment or the response data.
+    Object,
+    /// The struct and its `on` enum for a selection on an interface.
+    Interface,
+    /// The enum for a selection on a union.
+    Union,
+    /// An enum of the schema.
+    Enum,
+    /// An input object of the schema.
+    InputObject,
+    /// A custom scalar of the schema.
+    Scalar,
+    /// The variables of an operation.
+    Variables,
+}
+
+/// A type generated for the schema or the query, with its trait implementations.
+pub struct GeneratedItem<'a> {
+    /// What the item is generated for.
+    pub kind: ItemKind,
+    /// The name of the GraphQL type, or of the operation for [ItemKind::Variables].
+    pub graphql_type: &'a str,
+    /// The name of the generated Rust type.
+    pub rust_name: &'a str,
+    /// The directives on the operation the item is generated for, for the response data and the variables, or on the fragment, for the struct of a fragment. Empty for the other items.
+    pub directives: &'a [Directive],
+    /// The code of the item. Changing it changes the generated code, leaving it empty leaves the item out.
+    pub tokens: TokenStream,
+}
+
+/// Callbacks to inspect or change the generated code before it is assembled into the module, so macros wrapping the derive can add impls or attributes, or leave types out, without forking the code generation.
+pub trait CodegenHooks: Send + Sync {
+    /// Called with every generated item. Does nothing by default.
+    fn generated_item(&self, _item: &mut GeneratedItem) {}
+}
@@ -0,0 +1,298 @@
+use constants::*;
+use failure;
+use graphql_parser::query::*;
+use proc_macro2::{Ident, Span, TokenStream};
+use std::collections::BTreeSet;
+
+/// Whether `directives` contain `name`, unless it is disabled with a literal `if: false`.
+pub(crate) fn has_directive(directives: &[Directive], name: &str) -> bool {
+    directives.iter().any(|directive| {
+        directive.name == name
+            && !directive
+                .arguments
+                .iter()
+                .any(|(argument, value)| argument == "if" && *value == Value::Boolean(false))
+    })
+}
+
+/// Whether the operation named `operation_name` uses `@defer` or `@stream`, directly or through its fragments.
+///
+/// Only fragment spreads can be deferred: the fields of an inline fragment are part of the struct of the selection around it, and required in the initial payload. An inline fragment with `@defer` is an error rather than a response failing to deserialize.
+pub(crate) fn uses_incremental_delivery(
+    query: &Document,
+    operation_name: &str,
+) -> Result<bool, failure::Error> {
+    let selection_set = query
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Operation(OperationDefinition::Query(query))
+                if query.name.as_deref() == Some(operation_name) =>
+            {
+                Some(&query.selection_set)
+            }
+            Definition::Operation(OperationDefinition::Mutation(mutation))
+                if mutation.name.as_deref() == Some(operation_name) =>
+            {
+                Some(&mutation.selection_set)
+            }
+            Definition::Operation(OperationDefinition::Subscription(subscription))
+                if subscription.name.as_deref() == Some(operation_name) =>
+            {
+                Some(&subscription.selection_set)
+            }
+            _ => None,
+        })
+        .next();
+
+    match selection_set {
+        Some(selection_set) => {
+            selection_set_is_incremental(query, selection_set, &mut BTreeSet::new())
+        }
+        None => Ok(false),
+    }
+}
+
+/// Whether the selection set uses `@defer` or `@stream`. Every item is visited, so deferred inline fragments are reported wherever they are.
+fn selection_set_is_incremental<'query>(
+    query: &'query Document,
+    selection_set: &'query SelectionSet,
+    visited_fragments: &mut BTreeSet<&'query str>,
+) -> Result<bool, failure::Error> {
+    let mut incremental = false;
+
+    for item in &selection_set.items {
+        let item_is_incremental = match item {
+            Selection::Field(field) => {
+                selection_set_is_incremental(query, &field.selection_set, visited_fragments)?
+                    || has_directive(&field.directives, STREAM_DIRECTIVE)
+            }
+            Selection::InlineFragment(inline) => {
+                if has_directive(&inline.directives, DEFER_DIRECTIVE) {
+                    let fragment = match inline.type_condition {
+                        Some(TypeCondition::On(ref on)) => format!("... on {} @defer", on),
+                        None => "... @defer".to_string(),
+                    };
+                    return Err(format_err!(
+                        "`@defer` is only supported on fragment spreads: move the fields of `{}` to a named fragment, and spread it with `...Name @defer`.",
+                        fragment
+                    ));
+                }
+                selection_set_is_incremental(query, &inline.selection_set, visited_fragments)?
+            }
+            Selection::FragmentSpread(spread) => {
+                if has_directive(&spread.directives, DEFER_DIRECTIVE) {
+                    true
+                } else if !visited_fragments.insert(&spread.fragment_name) {
+                    false
+                } else {
+                    match query
+                        .definitions
+                        .iter()
+                        .find_map(|definition| match definition {
+                            Definition::Fragment(fragment)
+                                if fragment.name == spread.fragment_name =>
+                            {
+                                Some(fragment)
+                            }
+                            _ => None,
+                        }) {
+                        Some(fragment) => selection_set_is_incremental(
+                            query,
+                            &fragment.selection_set,
+                            visited_fragments,
+                        )?,
+                        None => false,
+                    }
+                }
+            }
+        };
+        incremental |= item_is_incremental;
+    }
+
+    Ok(incremental)
+}
+
+/// The types to consume an incremental response: the subsequent payloads, and the response merging them into the initial one.
+///
+/// Deferred fragments are optional in `ResponseData`, so it deserializes the initial payload as well as the merged response. The payloads themselves are not typed: their `data` and `items` are JSON, since what they hold depends on the `path` they are merged at. They are typed once merged, by `response_data`.
+pub(crate) fn incremental_types(operation_name: &str, multiple_operations: bool) -> TokenStream {
+    let (response_data, patch, response) = if multiple_operations {
+        (
+            format!("{}ResponseData", operation_name),
+            format!("{}IncrementalPatch", operation_name),
+            format!("{}IncrementalResponse", operation_name),
+        )
+    } else {
+        (
+            "ResponseData".to_owned(),
+            "IncrementalPatch".to_owned(),
+            "IncrementalResponse".to_owned(),
+        )
+    };
+    let response_data = Ident::new(&response_data, Span::call_site());
+    let patch = Ident::new(&patch, Span::call_site());
+    let response = Ident::new(&response, Span::call_site());
+
+    quote! {
+        /// A subsequent payload of the incremental response, for `@defer` and `@stream`. Its `data` and `items` are JSON, typed once merged into the response.
+        #[derive(::serde_derive::Deserialize, ::std::fmt::Debug, ::std::clone::Clone, ::std::cmp::PartialEq)]
+        pub struct #patch {
+            /// Where the payload goes in the response: field names and list indices.
+            pub path: ::std::vec::Vec<::serde_json::Value>,
+            /// The label of the `@defer` or `@stream` directive.
+            pub label: ::std::option::Option<::std::string::String>,
+            /// The fields of a deferred fragment.
+            pub data: ::std::option::Option<::serde_json::Value>,
+            /// The next items of a streamed list.
+            pub items: ::std::option::Option<::std::vec::Vec<::serde_json::Value>>,
+            #[serde(rename = "hasNext", default)]
+            pub has_next: bool,
+        }
+
+        /// Merges the payloads of an incremental response into the full response data.
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::cmp::PartialEq)]
+        pub struct #response {
+            data: ::serde_json::Value,
+        }
+
+        impl #response {
+            /// Start from the `data` of the initial payload.
+            pub fn new(initial_data: ::serde_json::Value) -> Self {
+                #response { data: initial_data }
+            }
+
+            /// Merge a subsequent payload. Deferred fields are added to the object at `path`, streamed items are put in the list at `path`, starting at its last index.
+            pub fn apply(&mut self, patch: #patch) {
+                if let ::std::option::Option::Some(items) = patch.items {
+                    if let ::std::option::Option::Some((start, list_path)) = patch.path.split_last() {
+                        let start = start.as_u64().unwrap_or(0) as usize;
+                        if let ::std::option::Option::Some(::serde_json::Value::Array(list)) = Self::at_path(&mut self.data, list_path) {
+                            for (offset, item) in ::std::iter::Iterator::enumerate(::std::iter::IntoIterator::into_iter(items)) {
+                                if start + offset < list.len() {
+                                    list[start + offset] = item;
+                                } else {
+                                    list.push(item);
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if let ::std::option::Option::Some(data) = patch.data {
+                    if let ::std::option::Option::Some(target) = Self::at_path(&mut self.data, &patch.path) {
+                        Self::merge(target, data);
+                    }
+                }
+            }
+
+            /// The response data received so far.
+            pub fn response_data(&self) -> ::std::result::Result<#response_data, ::serde_json::Error> {
+                ::serde_json::from_value(::std::clone::Clone::clone(&self.data))
+            }
+
+            fn at_path<'a>(
+                mut value: &'a mut ::serde_json::Value,
+                path: &[::serde_json::Value],
+            ) -> ::std::option::Option<&'a mut ::serde_json::Value> {
+                for segment in path {
+                    value = match segment {
+                        ::serde_json::Value::String(key) => value.get_mut(key.as_str())?,
+                        ::serde_json::Value::Number(index) => value.get_mut(index.as_u64()? as usize)?,
+                        _ => return ::std::option::Option::None,
+                    };
+                }
+                ::std::option::Option::Some(value)
+            }
+
+            fn merge(target: &mut ::serde_json::Value, patch: ::serde_json::Value) {
+                match (target, patch) {
+                    (::serde_json::Value::Object(target), ::serde_json::Value::Object(patch)) => {
+                        for (key, value) in patch {
+                            if let ::std::option::Option::Some(existing) = target.get_mut(&key) {
+                                Self::merge(existing, value);
+                                continue;
+                            }
+                            target.insert(key, value);
+                        }
+                    }
+                    (target, patch) => *target = patch,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphql_parser;
+
+    #[test]
+    fn incremental_delivery_is_detected_through_fragments() {
+        let query = graphql_parser::parse_query(
+            r#"
+            query Deferred { viewer { ...Bio } }
+            query Streamed { viewer { films @stream(initialCount: 1) } }
+            query Disabled { viewer { ...Details @defer(if: false) } }
+            fragment Bio on User { ...Details @defer(label: "details") }
+            fragment Details on User { bio }
+            "#,
+        )
+        .unwrap();
+
+        assert!(uses_incremental_delivery(&query, "Deferred").unwrap());
+        assert!(uses_incremental_delivery(&query, "Streamed").unwrap());
+        assert!(!uses_incremental_delivery(&query, "Disabled").unwrap());
+    }
+
+    #[test]
+    fn deferred_inline_fragments_are_rejected() {
+        let query = graphql_parser::parse_query(
+            r#"
+            query Typed { hero { name ... on Human @defer { height } } }
+            query Untyped { hero { ...Details } }
+            fragment Details on Character { ... @defer(label: "name") { name } }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            uses_incremental_delivery(&query, "Typed").unwrap_err().to_string(),
+            "`@defer` is only supported on fragment spreads: move the fields of `... on Human @defer` to a named fragment, and spread it with `...Name @defer`."
+        );
+        assert_eq!(
+            uses_incremental_delivery(&query, "Untyped").unwrap_err().to_string(),
+            "`@defer` is only supported on fragment spreads: move the fields of `... @defer` to a named fragment, and spread it with `...Name @defer`."
+        );
+    }
+
+    #[test]
+    fn deferred_fragments_are_optional() {
+        use codegen::{all_operations, response_for_query};
+        use schema::Schema;
+
+        let schema = graphql_parser::parse_schema(
+            "type Query { viewer: User }\ntype User { login: String! bio: String! }",
+        )
+        .unwrap();
+        let schema = Schema::from(&schema);
+        let query = graphql_parser::parse_query(
+            "query Profile { viewer { login ...Bio @defer } }\nfragment Bio on User { bio }",
+        )
+        .unwrap();
+        let operations = all_operations(&query);
+
+        let generated =
+            response_for_query(&schema, &query, &operations[0], &Default::default(), false)
+                .unwrap()
+                .to_string();
+
+        assert!(generated
+            .contains("# [ serde ( flatten ) ] pub bio : :: std :: option :: Option < Bio >"));
+        assert!(generated.contains("pub struct IncrementalPatch"));
+        assert!(generated.contains(
+            "pub fn response_data ( & self ) -> :: std :: result :: Result < ResponseData , :: serde_json :: Error >"
+        ));
+    }
+}
@@ -0,0 +1,84 @@
+use proc_macro2::TokenStream;
+
+/// The smart pointer holding nested response structs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pointer {
+    /// `Box`, keeping the parent struct small so it is cheap to move.
+    Box,
+    /// `Arc`, to also share big subtrees across threads. Deserializing it needs the `rc` feature of serde.
+    Arc,
+}
+
+/// Which nested response structs are held by a pointer instead of inline, parsed from `box`, `arc`, or either with the minimum number of fields, like `box:8`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Indirection {
+    /// The pointer holding the structs.
+    pub pointer: Pointer,
+    /// How many fields the selection of a struct must have, counting those of its nested selections and fragments, to be held by the pointer. All of them by default.
+    pub min_fields: usize,
+}
+
+impl ::std::str::FromStr for Indirection {
+    type Err = ::failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(2, ':');
+        let pointer = match parts.next().map(str::trim) {
+            Some("box") => Pointer::Box,
+            Some("arc") => Pointer::Arc,
+            _ => {
+                return Err(format_err!(
+                    "Invalid nested indirection: `{}`. Valid values are `box` and `arc`, optionally followed by the minimum number of fields, like `box:8`.",
+                    s
+                ))
+            }
+        };
+        let min_fields = match parts.next() {
+            Some(min_fields) => min_fields.trim().parse().map_err(|_| {
+                format_err!(
+                    "Invalid minimum number of fields in nested indirection: `{}`.",
+                    s
+                )
+            })?,
+            None => 0,
+        };
+        Ok(Indirection {
+            pointer,
+            min_fields,
+        })
+    }
+}
+
+impl Pointer {
+    pub(crate) fn wrap(self, ty: &TokenStream) -> TokenStream {
+        match self {
+            Pointer::Box => quote!(::std::boxed::Box<#ty>),
+            Pointer::Arc => quote!(::std::sync::Arc<#ty>),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indirections_parse() {
+        assert_eq!(
+            "box".parse::<Indirection>().unwrap(),
+            Indirection {
+                pointer: Pointer::Box,
+                min_fields: 0,
+            }
+        );
+        assert_eq!(
+            " arc : 8 ".parse::<Indirection>().unwrap(),
+            Indirection {
+                pointer: Pointer::Arc,
+                min_fields: 8,
+            }
+        );
+        assert!("rc".parse::<Indirection>().is_err());
+        assert!("box:many".parse::<Indirection>().is_err());
+    }
+}
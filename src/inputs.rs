@@ -1,14 +1,17 @@
-use deprecation::DeprecationStatus;
+use conversion::parse_value_literal;
+use deprecation::{deprecation_attribute, DeprecationStatus};
 use failure;
+use field_type::FieldType;
 use graphql_parser;
+use graphql_parser::schema::Value;
 use heck::SnakeCase;
 use introspection_response;
-use objects::GqlObjectField;
+use objects::{parse_deprecation_directives, GqlObjectField};
 use proc_macro2::{Ident, Span, TokenStream};
 use query::QueryContext;
 use schema::Schema;
 use std::cell::Cell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Represents an input object type from a GraphQL schema
 #[derive(Debug, Clone, PartialEq)]
@@ -16,6 +19,8 @@ pub struct GqlInput<'schema> {
     pub description: Option<&'schema str>,
     pub name: &'schema str,
     pub fields: HashMap<&'schema str, GqlObjectField<'schema>>,
+    /// The default values declared in the schema, by field name.
+    pub default_values: HashMap<&'schema str, Value>,
     pub is_required: Cell<bool>,
 }
 
@@ -30,18 +35,39 @@ impl<'schema> GqlInput<'schema> {
         })
     }
 
-    pub(crate) fn to_rust(&self, context: &QueryContext) -> Result<TokenStream, failure::Error> {
-        let name = Ident::new(&self.name, Span::call_site());
+    /// The fields that are generated, sorted by name. Denied fields are left out, so they can not be sent.
+    fn rendered_fields(
+        &self,
+        context: &QueryContext,
+    ) -> Vec<(&GqlObjectField<'schema>, TokenStream)> {
         let mut fields: Vec<&GqlObjectField> = self.fields.values().collect();
         fields.sort_unstable_by(|a, b| a.name.cmp(&b.name));
-        let fields = fields.iter().map(|field| {
+        fields
+            .into_iter()
+            .filter_map(|field| {
+                let strategy = context.deprecation_strategy_for(self.name, field.name);
+                deprecation_attribute(&field.deprecation, strategy)
+                    .map(|deprecation| (field, deprecation))
+            })
+            .collect()
+    }
+
+    fn is_recursive_field(&self, field: &GqlObjectField) -> bool {
+        !field.type_.is_indirected() && field.type_.inner_name_str() == self.name
+    }
+
+    pub(crate) fn to_rust(&self, context: &QueryContext) -> Result<TokenStream, failure::Error> {
+        let name = Ident::new(&self.name, Span::call_site());
+        let fields = self.rendered_fields(context);
+        let fields = fields.iter().map(|(field, deprecation)| {
             let ty = field.type_.to_rust(&context, "");
 
             // If the type is recursive, we have to box it
-            let ty = if field.type_.is_indirected() || field.type_.inner_name_str() != self.name {
-                ty
+            let is_recursive = self.is_recursive_field(field);
+            let ty = if is_recursive {
+                quote! { ::std::boxed::Box<#ty> }
             } else {
-                quote! { Box<#ty> }
+                ty
             };
 
             context.schema.require(&field.type_.inner_name_str());
@@ -49,18 +75,313 @@ impl<'schema> GqlInput<'schema> {
             let snake_case_name = field.name.to_snake_case();
             let rename = ::shared::field_rename_annotation(&original_name, &snake_case_name);
             let name = Ident::new(&snake_case_name, Span::call_site());
+            let strategy = ::arbitrary::field_attribute(
+                context,
+                &field.type_,
+                field.type_.inner_name_str() == self.name,
+            );
+            let adapter = ::serde_adapters::field_attribute(
+                context,
+                Some(self.name),
+                field.name,
+                &field.type_,
+            );
+
+            let description = ::shared::description_attributes(field.description);
 
-            quote!(#rename pub #name: #ty)
+            quote!(#description #deprecation #rename #strategy #adapter pub #name: #ty)
         });
         let variables_derives = context.variables_derives();
+        let default_impl = self.default_impl(context);
+        let builder = if context.input_builders {
+            Some(self.builder(context))
+        } else {
+            None
+        };
+        let graphql_value = if context.graphql_values {
+            Some(self.graphql_value_impl(context))
+        } else {
+            None
+        };
+
+        let description = ::shared::description_attributes(self.description);
 
         Ok(quote! {
             #variables_derives
+            #description
             pub struct #name {
                 #(#fields,)*
             }
+
+            #default_impl
+
+            #builder
+
+            #graphql_value
         })
     }
+
+    /// `to_graphql_value()`, the input object as a GraphQL value.
+    fn graphql_value_impl(&self, context: &QueryContext) -> TokenStream {
+        let name = Ident::new(self.name, Span::call_site());
+        let fields: Vec<_> = self
+            .rendered_fields(context)
+            .into_iter()
+            .map(|(field, _)| {
+                (
+                    field.name,
+                    Ident::new(&field.name.to_snake_case(), Span::call_site()),
+                    &field.type_,
+                    self.is_recursive_field(field),
+                )
+            })
+            .collect();
+
+        ::graphql_values::object_impl(
+            context,
+            &name,
+            "The input object as a GraphQL value, for documents built programmatically.",
+            &fields,
+        )
+    }
+
+    /// A `Default` impl using the schema defaults, when the input declares some and every other field is optional.
+    fn default_impl(&self, context: &QueryContext) -> Option<TokenStream> {
+        if self.default_values.is_empty() || context.has_variables_derive("Default") {
+            return None;
+        }
+
+        let name = Ident::new(self.name, Span::call_site());
+        let literal = self.struct_literal(context, &BTreeMap::new())?;
+
+        Some(quote! {
+            #[allow(deprecated)]
+            impl ::std::default::Default for #name {
+                fn default() -> Self {
+                    #literal
+                }
+            }
+        })
+    }
+
+    /// A `<Name>Builder` with a setter per field, starting from the schema defaults. `build()` fails if a required field is not set.
+    fn builder(&self, context: &QueryContext) -> TokenStream {
+        let name = Ident::new(self.name, Span::call_site());
+        let builder_name = Ident::new(&format!("{}Builder", self.name), Span::call_site());
+        let mut builder_fields = Vec::new();
+        let mut initial_values = Vec::new();
+        let mut setters = Vec::new();
+        let mut built_fields = Vec::new();
+
+        for (field, deprecation) in self.rendered_fields(context) {
+            let field_name = Ident::new(&field.name.to_snake_case(), Span::call_site());
+            let is_recursive = self.is_recursive_field(field);
+            let box_expr = |expr: TokenStream| {
+                if is_recursive {
+                    quote!(::std::boxed::Box::new(#expr))
+                } else {
+                    expr
+                }
+            };
+            let ty = field.type_.to_rust(context, "");
+            let ty = if is_recursive {
+                quote!(::std::boxed::Box<#ty>)
+            } else {
+                ty
+            };
+            let default_value = self
+                .default_values
+                .get(field.name)
+                .and_then(|value| value_expr(context, &field.type_, value))
+                .map(&box_expr);
+
+            let (setter_type, value) = match field.type_ {
+                FieldType::Optional(ref inner) => (
+                    inner.to_rust(context, ""),
+                    box_expr(quote!(::std::option::Option::Some(
+                        ::std::convert::Into::into(value)
+                    ))),
+                ),
+                _ => (
+                    field.type_.to_rust(context, ""),
+                    box_expr(quote!(::std::convert::Into::into(value))),
+                ),
+            };
+
+            if field.type_.is_optional() {
+                let initial_value =
+                    default_value.unwrap_or_else(|| box_expr(quote!(::std::option::Option::None)));
+                builder_fields.push(quote!(#field_name: #ty));
+                initial_values.push(quote!(#field_name: #initial_value));
+                setters.push(quote! {
+                    #deprecation
+                    pub fn #field_name<V: ::std::convert::Into<#setter_type>>(mut self, value: V) -> Self {
+                        self.#field_name = #value;
+                        self
+                    }
+                });
+                built_fields.push(quote!(#field_name: self.#field_name));
+            } else {
+                let initial_value = match default_value {
+                    Some(value) => quote!(::std::option::Option::Some(#value)),
+                    None => quote!(::std::option::Option::None),
+                };
+                let missing = format!("Missing required field `{}` on `{}`", field.name, self.name);
+                builder_fields.push(quote!(#field_name: ::std::option::Option<#ty>));
+                initial_values.push(quote!(#field_name: #initial_value));
+                setters.push(quote! {
+                    #deprecation
+                    pub fn #field_name<V: ::std::convert::Into<#setter_type>>(mut self, value: V) -> Self {
+                        self.#field_name = ::std::option::Option::Some(#value);
+                        self
+                    }
+                });
+                built_fields.push(quote!(#field_name: self.#field_name.ok_or(#missing)?));
+            }
+        }
+
+        quote! {
+            pub struct #builder_name {
+                #(#builder_fields,)*
+            }
+
+            #[allow(deprecated)]
+            impl ::std::default::Default for #builder_name {
+                fn default() -> Self {
+                    #builder_name {
+                        #(#initial_values,)*
+                    }
+                }
+            }
+
+            impl #name {
+                pub fn builder() -> #builder_name {
+                    <#builder_name as ::std::default::Default>::default()
+                }
+            }
+
+            #[allow(deprecated)]
+            impl #builder_name {
+                #(#setters)*
+
+                pub fn build(self) -> ::std::result::Result<#name, &'static str> {
+                    ::std::result::Result::Ok(#name {
+                        #(#built_fields,)*
+                    })
+                }
+            }
+        }
+    }
+
+    /// Build a struct literal from the given values, falling back to the schema defaults, then to `None` for optional fields.
+    ///
+    /// Returns `None` if a required field has no value, or a value can not be expressed in Rust.
+    pub(crate) fn struct_literal(
+        &self,
+        context: &QueryContext,
+        values: &BTreeMap<String, Value>,
+    ) -> Option<TokenStream> {
+        let name = Ident::new(self.name, Span::call_site());
+        let mut fields = Vec::new();
+
+        for (field, _) in self.rendered_fields(context) {
+            let value = values
+                .get(field.name)
+                .or_else(|| self.default_values.get(field.name));
+            let expr = match value {
+                Some(value) => value_expr(context, &field.type_, value)?,
+                None if field.type_.is_optional() => quote!(::std::option::Option::None),
+                None => return None,
+            };
+            let expr = if self.is_recursive_field(field) {
+                quote!(::std::boxed::Box::new(#expr))
+            } else {
+                expr
+            };
+            let field_name = Ident::new(&field.name.to_snake_case(), Span::call_site());
+            fields.push(quote!(#field_name: #expr));
+        }
+
+        Some(quote!(#name { #(#fields,)* }))
+    }
+}
+
+/// The Rust expression for a GraphQL value of the given input type.
+pub(crate) fn value_expr(
+    context: &QueryContext,
+    type_: &FieldType,
+    value: &Value,
+) -> Option<TokenStream> {
+    match (type_, value) {
+        (FieldType::Optional(_), Value::Null) => Some(quote!(::std::option::Option::None)),
+        (FieldType::Optional(inner), value) => {
+            let expr = value_expr(context, inner, value)?;
+            Some(quote!(::std::option::Option::Some(#expr)))
+        }
+        (FieldType::Vector(inner), Value::List(items)) => {
+            let items: Option<Vec<TokenStream>> = items
+                .iter()
+                .map(|item| value_expr(context, inner, item))
+                .collect();
+            let items = items?;
+            Some(quote!(::std::vec![#(#items),*]))
+        }
+        // Input coercion accepts a single value where a list is expected.
+        (FieldType::Vector(inner), value) => {
+            let expr = value_expr(context, inner, value)?;
+            Some(quote!(::std::vec![#expr]))
+        }
+        (FieldType::Named(name), value) => named_value_expr(context, name, value),
+    }
+}
+
+fn named_value_expr(context: &QueryContext, type_name: &str, value: &Value) -> Option<TokenStream> {
+    match (type_name, value) {
+        ("Int", Value::Int(number)) => {
+            let number = number.as_i64()?;
+            Some(quote!(#number))
+        }
+        ("Float", Value::Int(number)) => {
+            let number = number.as_i64()? as f64;
+            Some(quote!(#number))
+        }
+        ("Float", Value::Float(number)) => Some(quote!(#number)),
+        ("String", Value::String(string)) | ("ID", Value::String(string)) => {
+            Some(quote!(::std::string::ToString::to_string(&#string)))
+        }
+        ("ID", Value::Int(number)) => {
+            let string = number.as_i64()?.to_string();
+            Some(quote!(::std::string::ToString::to_string(&#string)))
+        }
+        ("Boolean", Value::Boolean(boolean)) => Some(quote!(#boolean)),
+        (_, Value::Enum(variant)) => {
+            let enm = context.schema.enums.get(type_name)?;
+            let name = Ident::new(
+                &format!("{}{}", ::enums::ENUMS_PREFIX, enm.name),
+                Span::call_site(),
+            );
+            let is_generated = enm.variants.iter().any(|v| {
+                v.name == variant
+                    && deprecation_attribute(
+                        &v.deprecation,
+                        context.deprecation_strategy_for(enm.name, v.name),
+                    )
+                    .is_some()
+            });
+            if is_generated {
+                let variant = Ident::new(variant, Span::call_site());
+                Some(quote!(#name::#variant))
+            } else {
+                Some(quote!(#name::Other(::std::string::ToString::to_string(&#variant))))
+            }
+        }
+        (_, Value::Object(values)) => context
+            .schema
+            .inputs
+            .get(type_name)?
+            .struct_literal(context, values),
+        _ => None,
+    }
 }
 
 impl<'schema> ::std::convert::From<&'schema graphql_parser::schema::InputObjectType>
@@ -76,14 +397,24 @@ impl<'schema> ::std::convert::From<&'schema graphql_parser::schema::InputObjectT
                 .map(|field| {
                     let name = field.name.as_str();
                     let field = GqlObjectField {
-                        description: None,
+                        description: field.description.as_deref(),
                         name: &field.name,
                         type_: crate::field_type::FieldType::from(&field.value_type),
-                        deprecation: DeprecationStatus::Current,
+                        deprecation: parse_deprecation_directives(&field.directives),
                     };
                     (name, field)
                 })
                 .collect(),
+            default_values: schema_input
+                .fields
+                .iter()
+                .filter_map(|field| {
+                    field
+                        .default_value
+                        .clone()
+                        .map(|value| (field.name.as_str(), value))
+                })
+                .collect(),
             is_required: false.into(),
         }
     }
@@ -93,6 +424,13 @@ impl<'schema> ::std::convert::From<&'schema introspection_response::FullType>
     for GqlInput<'schema>
 {
     fn from(schema_input: &'schema introspection_response::FullType) -> GqlInput<'schema> {
+        let input_fields = schema_input
+            .input_fields
+            .as_ref()
+            .expect("fields on input object")
+            .iter()
+            .filter_map(|a| a.as_ref());
+
         GqlInput {
             description: schema_input.description.as_ref().map(String::as_str),
             name: schema_input
@@ -100,12 +438,8 @@ impl<'schema> ::std::convert::From<&'schema introspection_response::FullType>
                 .as_ref()
                 .map(String::as_str)
                 .expect("unnamed input object"),
-            fields: schema_input
-                .input_fields
-                .as_ref()
-                .expect("fields on input object")
-                .iter()
-                .filter_map(|a| a.as_ref())
+            fields: input_fields
+                .clone()
                 .map(|f| {
                     let name = f
                         .input_value
@@ -114,7 +448,7 @@ impl<'schema> ::std::convert::From<&'schema introspection_response::FullType>
                         .expect("unnamed input object field")
                         .as_str();
                     let field = GqlObjectField {
-                        description: None,
+                        description: f.input_value.description.as_deref(),
                         name: &name,
                         type_: f
                             .input_value
@@ -122,11 +456,22 @@ impl<'schema> ::std::convert::From<&'schema introspection_response::FullType>
                             .as_ref()
                             .map(|s| s.into())
                             .expect("type on input object field"),
-                        deprecation: DeprecationStatus::Current,
+                        deprecation: if f.input_value.is_deprecated.unwrap_or(false) {
+                            DeprecationStatus::Deprecated(f.input_value.deprecation_reason.clone())
+                        } else {
+                            DeprecationStatus::Current
+                        },
                     };
                     (name, field)
                 })
                 .collect(),
+            default_values: input_fields
+                .filter_map(|f| {
+                    let name = f.input_value.name.as_ref()?.as_str();
+                    let value = parse_value_literal(f.input_value.default_value.as_ref()?)?;
+                    Some((name, value))
+                })
+                .collect(),
             is_required: false.into(),
         }
     }
@@ -174,16 +519,17 @@ mod tests {
             ]
             .into_iter()
             .collect(),
+            default_values: HashMap::new(),
             is_required: false.into(),
         };
 
         let expected: String = vec![
-            "# [ derive ( Serialize , Clone ) ] ",
+            "# [ derive ( :: serde_derive :: Serialize , :: std :: clone :: Clone ) ] ",
             "pub struct Cat { ",
-            "pub offsprings : Vec < Cat > , ",
+            "pub offsprings : :: std :: vec :: Vec < Cat > , ",
             "# [ serde ( rename = \"pawsCount\" ) ] ",
             "pub paws_count : Float , ",
-            "pub requirements : Option < CatRequirements > , ",
+            "pub requirements : :: std :: option :: Option < CatRequirements > , ",
             "}",
         ]
         .into_iter()
@@ -202,4 +548,120 @@ mod tests {
             expected
         );
     }
+
+    #[test]
+    fn deprecated_input_fields_follow_the_strategy() {
+        use deprecation::DeprecationStrategy;
+        use graphql_parser;
+
+        let schema = graphql_parser::parse_schema(
+            r#"
+            input Filter {
+              name: String
+              nameLike: String @deprecated(reason: "Use `name`")
+            }
+            "#,
+        )
+        .unwrap();
+        let schema = Schema::from(&schema);
+
+        let generate = |strategy| {
+            let context = QueryContext::new(&schema, strategy);
+            context.schema.inputs["Filter"]
+                .to_rust(&context)
+                .unwrap()
+                .to_string()
+        };
+
+        assert!(generate(DeprecationStrategy::Warn).contains(
+            "# [ deprecated ( note = \"Use `name`\" ) ] # [ serde ( rename = \"nameLike\" ) ] pub name_like"
+        ));
+        assert!(!generate(DeprecationStrategy::Allow).contains("deprecated"));
+        assert!(!generate(DeprecationStrategy::Deny).contains("name_like"));
+    }
+
+    #[test]
+    fn schema_defaults_generate_a_default_impl() {
+        use graphql_parser;
+
+        let schema = graphql_parser::parse_schema(
+            r#"
+            enum Order { ASC DESC }
+
+            input Page {
+              first: Int = 10
+              order: Order = DESC
+              cursor: String
+            }
+
+            input Search {
+              query: String!
+              page: Page = { first: 20 }
+            }
+
+            input Filter {
+              tags: [String!] = "rust"
+              page: Page! = {}
+            }
+            "#,
+        )
+        .unwrap();
+        let schema = Schema::from(&schema);
+        let context = QueryContext::new_empty(&schema);
+        let generate = |name: &str| {
+            context.schema.inputs[name]
+                .to_rust(&context)
+                .unwrap()
+                .to_string()
+        };
+
+        assert!(generate("Page").contains(
+            "impl :: std :: default :: Default for Page { fn default ( ) -> Self { Page { cursor : :: std :: option :: Option :: None , first : :: std :: option :: Option :: Some ( 10i64 ) , order : :: std :: option :: Option :: Some ( Order :: DESC ) , } } }"
+        ));
+        assert!(generate("Filter").contains(
+            "Filter { page : Page { cursor : :: std :: option :: Option :: None , first : :: std :: option :: Option :: Some ( 10i64 ) , order : :: std :: option :: Option :: Some ( Order :: DESC ) , } , tags : :: std :: option :: Option :: Some ( :: std :: vec ! [ :: std :: string :: ToString :: to_string ( & \"rust\" ) ] ) , }"
+        ));
+        // `query` is required and has no default.
+        assert!(!generate("Search").contains("impl Default"));
+    }
+
+    #[test]
+    fn input_builders_enforce_required_fields() {
+        use graphql_parser;
+
+        let schema = graphql_parser::parse_schema(
+            r#"
+            input Search {
+              query: String!
+              first: Int = 10
+              cursor: String
+            }
+            "#,
+        )
+        .unwrap();
+        let schema = Schema::from(&schema);
+        let mut context = QueryContext::new_empty(&schema);
+        let generate = |context: &QueryContext| {
+            context.schema.inputs["Search"]
+                .to_rust(context)
+                .unwrap()
+                .to_string()
+        };
+
+        assert!(!generate(&context).contains("SearchBuilder"));
+
+        context.input_builders = true;
+        let generated = generate(&context);
+        assert!(generated.contains(
+            "pub struct SearchBuilder { cursor : :: std :: option :: Option < :: std :: string :: String > , first : :: std :: option :: Option < Int > , query : :: std :: option :: Option < :: std :: string :: String > , }"
+        ));
+        assert!(generated
+            .contains("SearchBuilder { cursor : :: std :: option :: Option :: None , first : :: std :: option :: Option :: Some ( 10i64 ) , query : :: std :: option :: Option :: None , }"));
+        assert!(generated.contains(
+            "pub fn query < V : :: std :: convert :: Into < :: std :: string :: String >> ( mut self , value : V ) -> Self { self . query = :: std :: option :: Option :: Some ( :: std :: convert :: Into :: into ( value ) ) ; self }"
+        ));
+        assert!(generated.contains(
+            "query : self . query . ok_or ( \"Missing required field `query` on `Search`\" ) ? ,"
+        ));
+    }
 }
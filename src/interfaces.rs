@@ -1,13 +1,15 @@
 use crate::constants::TYPENAME_FIELD;
 use failure;
+use hooks::ItemKind;
 use objects::GqlObjectField;
 use proc_macro2::{Ident, Span, TokenStream};
-use query::QueryContext;
+use query::{Expansion, Nested, QueryContext};
 use selection::{Selection, SelectionField, SelectionFragmentSpread, SelectionItem};
 use shared::*;
 use std::cell::Cell;
-use std::collections::HashSet;
-use unions::union_variants;
+use std::collections::{BTreeSet, HashSet};
+use union_mode::UnionMode;
+use unions::{fallback_enum, fallback_variant, union_variants, FALLBACK_VARIANT};
 
 /// A GraphQL interface (simplified schema representation).
 ///
@@ -18,6 +20,8 @@ pub struct GqlInterface<'schema> {
     pub description: Option<&'schema str>,
     /// The set of object types implementing this interface.
     pub implemented_by: HashSet<&'schema str>,
+    /// The interfaces this interface implements.
+    pub implements: Vec<&'schema str>,
     /// The name of the interface. Should match 1-to-1 to its name in the GraphQL schema.
     pub name: &'schema str,
     /// The interface's fields. Analogous to object fields.
@@ -31,7 +35,7 @@ impl<'schema> GqlInterface<'schema> {
     /// This does not include the __typename field because it is translated into the `on` enum.
     fn object_selection<'query>(
         &self,
-        selection: &'query Selection<'query>,
+        selection: &Selection<'query>,
         query_context: &QueryContext,
     ) -> Selection<'query> {
         Selection(
@@ -41,7 +45,10 @@ impl<'schema> GqlInterface<'schema> {
                 // Only keep what we can handle
                 .filter(|f| match f {
                     SelectionItem::Field(f) => f.name != TYPENAME_FIELD,
-                    SelectionItem::FragmentSpread(SelectionFragmentSpread { fragment_name }) => {
+                    SelectionItem::FragmentSpread(SelectionFragmentSpread {
+                        fragment_name,
+                        ..
+                    }) => {
                         // only if the fragment refers to the interface’s own fields (to take into account type-refining fragments)
                         let fragment = query_context
                             .fragments
@@ -61,7 +68,7 @@ impl<'schema> GqlInterface<'schema> {
 
     fn union_selection<'query>(
         &self,
-        selection: &'query Selection,
+        selection: &Selection<'query>,
         query_context: &QueryContext,
     ) -> Selection<'query> {
         Selection(
@@ -71,7 +78,10 @@ impl<'schema> GqlInterface<'schema> {
                 // Only keep what we can handle
                 .filter(|f| match f {
                     SelectionItem::InlineFragment(_) => true,
-                    SelectionItem::FragmentSpread(SelectionFragmentSpread { fragment_name }) => {
+                    SelectionItem::FragmentSpread(SelectionFragmentSpread {
+                        fragment_name,
+                        ..
+                    }) => {
                         let fragment = query_context
                             .fragments
                             .get(fragment_name)
@@ -98,20 +108,21 @@ impl<'schema> GqlInterface<'schema> {
             name,
             description,
             implemented_by: HashSet::new(),
+            implements: Vec::new(),
             fields: vec![],
             is_required: false.into(),
         }
     }
 
-    /// The generated code for each of the selected field's types. See [shared::field_impls_for_selection].
-    pub(crate) fn field_impls_for_selection(
+    /// The selections of the selected fields. See [shared::nested_selections].
+    fn nested_selections<'query>(
         &self,
         context: &QueryContext,
-        selection: &Selection,
+        selection: &Selection<'query>,
         prefix: &str,
-    ) -> Result<Vec<TokenStream>, failure::Error> {
-        ::shared::field_impls_for_selection(
-            &self.fields,
+    ) -> Result<Vec<Nested<'query>>, failure::Error> {
+        ::shared::nested_selections(
+            self.name,
             context,
             &self.object_selection(selection, context),
             prefix,
@@ -127,10 +138,11 @@ impl<'schema> GqlInterface<'schema> {
     ) -> Result<Vec<TokenStream>, failure::Error> {
         response_fields_for_selection(
             &self.name,
-            &self.fields,
             context,
             &self.object_selection(selection, context),
             prefix,
+            // The `__typename` is deserialized into the `on` field.
+            &["on"],
         )
     }
 
@@ -141,8 +153,22 @@ impl<'schema> GqlInterface<'schema> {
         selection: &Selection,
         prefix: &str,
     ) -> Result<TokenStream, failure::Error> {
+        query_context.expand_nested(
+            prefix,
+            self.expand_selection(query_context, selection, prefix)?,
+        )
+    }
+
+    /// The struct and `on` enum for the selection, and the selections of the fields and on the variants. See [QueryContext::expand_nested].
+    pub(crate) fn expand_selection<'query>(
+        &self,
+        query_context: &QueryContext<'query, '_>,
+        selection: &Selection<'query>,
+        prefix: &str,
+    ) -> Expansion<'query> {
         let name = Ident::new(&prefix, Span::call_site());
         let derives = query_context.response_derives();
+        let selection = &selection.without_fragment_fields(query_context, self.name)?;
 
         selection.extract_typename(query_context).ok_or_else(|| {
             format_err!(
@@ -155,30 +181,52 @@ impl<'schema> GqlInterface<'schema> {
         let object_fields =
             self.response_fields_for_selection(query_context, &selection, prefix)?;
 
-        let object_children = self.field_impls_for_selection(query_context, &selection, prefix)?;
+        let mut children = self.nested_selections(query_context, &selection, prefix)?;
 
         let union_selection = self.union_selection(&selection, &query_context);
 
         let (mut union_variants, union_children, used_variants) =
             union_variants(&union_selection, query_context, prefix, &self.name)?;
+        // The structs of the variants are flattened into the interface struct, with the `on` enum.
+        for child in &union_children {
+            if let Nested::Selection { prefix, .. } = child {
+                query_context.record_flattened_struct(prefix);
+            }
+        }
 
         // Add the non-selected variants to the generated enum's variants.
         union_variants.extend(
             self.implemented_by
                 .iter()
-                .filter(|obj| used_variants.iter().find(|v| v == obj).is_none())
+                .filter(|obj| !used_variants.contains(obj))
                 .map(|v| {
                     let v = Ident::new(v, Span::call_site());
                     quote!(#v)
                 }),
         );
 
+        let non_exhaustive = match query_context.union_mode {
+            UnionMode::Exhaustive => quote!(),
+            UnionMode::NonExhaustive => {
+                if self.implemented_by.contains(FALLBACK_VARIANT) {
+                    Err(format_err!(
+                        "The implementor {} of {} conflicts with the fallback variant of non-exhaustive interfaces",
+                        FALLBACK_VARIANT,
+                        self.name
+                    ))?;
+                }
+                union_variants.push(fallback_variant());
+                quote!(#[non_exhaustive])
+            }
+        };
+
         let attached_enum_name = Ident::new(&format!("{}On", name), Span::call_site());
         let (attached_enum, last_object_field) =
             if selection.extract_typename(query_context).is_some() {
                 let attached_enum = quote! {
                     #derives
                     #[serde(tag = "__typename")]
+                    #non_exhaustive
                     pub enum #attached_enum_name {
                         #(#union_variants,)*
                     }
@@ -189,20 +237,98 @@ impl<'schema> GqlInterface<'schema> {
                 (quote!(), quote!())
             };
 
-        Ok(quote! {
-
-            #(#object_children)*
-
-            #(#union_children)*
-
-            #attached_enum
-
+        let (
+            attached_enum_mock,
+            attached_enum_default,
+            attached_enum_validation,
+            attached_enum_pretty_print,
+        ) = if attached_enum.is_empty() {
+            (quote!(), quote!(), quote!(), quote!())
+        } else {
+            (
+                ::mocks::mock_impl(query_context, &attached_enum),
+                ::defaults::default_impl(query_context, &attached_enum, self.name),
+                ::json_validation::validation_impl(query_context, &attached_enum),
+                ::pretty_print::pretty_print_impl(query_context, &attached_enum),
+            )
+        };
+        let attached_enum = match query_context.union_mode {
+            UnionMode::NonExhaustive if !attached_enum.is_empty() => fallback_enum(&attached_enum),
+            _ => attached_enum,
+        };
+        let definition = quote! {
             #derives
             pub struct #name {
                 #(#object_fields,)*
                 #last_object_field
             }
-        })
+        };
+        let mock = ::mocks::mock_impl(query_context, &definition);
+        let default = ::defaults::default_impl(query_context, &definition, self.name);
+        let list_iterators = ::list_iterators::list_iterators_impl(query_context, &definition);
+        let validation = ::json_validation::validation_impl(query_context, &definition);
+        let pretty_print = ::pretty_print::pretty_print_impl(query_context, &definition);
+        let object_selection = self.object_selection(selection, query_context);
+        let fragment_conversions = ::shared::fragment_conversions(&name, &object_selection);
+        let mut typename_arms: Vec<TokenStream> = used_variants
+            .iter()
+            .chain(self.implemented_by.iter())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|variant| {
+                let variant_name = Ident::new(variant, Span::call_site());
+                quote!(#attached_enum_name::#variant_name { .. } => #variant)
+            })
+            .collect();
+        if query_context.union_mode == UnionMode::NonExhaustive {
+            let fallback = Ident::new(FALLBACK_VARIANT, Span::call_site());
+            typename_arms
+                .push(quote!(#attached_enum_name::#fallback { ref typename } => typename.as_str()));
+        }
+        // The `__typename` is only kept in the `on` enum.
+        let cache_key = if attached_enum.is_empty() {
+            quote!()
+        } else {
+            ::shared::cache_key_impl(
+                self.name,
+                query_context,
+                &object_selection,
+                &name,
+                &quote!(match self.on { #(#typename_arms,)* }),
+            )
+        };
+
+        children.extend(union_children);
+        let definition = ::redaction::redacted_debug(query_context, definition);
+        let tokens = quote! {
+            #attached_enum
+
+            #attached_enum_mock
+
+            #attached_enum_default
+
+            #attached_enum_validation
+
+            #attached_enum_pretty_print
+
+            #definition
+
+            #fragment_conversions
+
+            #cache_key
+
+            #mock
+
+            #default
+
+            #list_iterators
+
+            #validation
+
+            #pretty_print
+        };
+        let tokens = query_context.hooked(ItemKind::Interface, self.name, prefix, tokens);
+        Ok((tokens, children))
     }
 }
 
@@ -216,6 +342,7 @@ mod tests {
         let iface = GqlInterface {
             description: None,
             implemented_by: HashSet::new(),
+            implements: Vec::new(),
             name: "MyInterface",
             fields: vec![],
             is_required: Cell::new(true),
@@ -228,6 +355,8 @@ mod tests {
             alias: None,
             name: "__typename",
             fields: Selection(vec![]),
+            is_client: false,
+            is_sensitive: false,
         });
         let selection = Selection(vec![typename_field.clone()]);
 
@@ -243,6 +372,7 @@ mod tests {
         let iface = GqlInterface {
             description: None,
             implemented_by: HashSet::new(),
+            implements: Vec::new(),
             name: "MyInterface",
             fields: vec![],
             is_required: Cell::new(true),
@@ -255,6 +385,8 @@ mod tests {
             alias: None,
             name: "__typename",
             fields: Selection(vec![]),
+            is_client: false,
+            is_sensitive: false,
         });
         let selection = Selection(vec![typename_field]);
 
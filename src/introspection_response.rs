@@ -1,35 +1,58 @@
+//! The response to the [introspection query](../constant.INTROSPECTION_QUERY.html), with the names of the introspection schema. Every field is optional, as servers differ in what they report.
+
 #![allow(non_camel_case_types)]
 
 use serde;
 
 type Boolean = bool;
 
+/// Where a directive can be used.
 #[derive(Clone, Debug)]
 pub enum __DirectiveLocation {
+    /// On query operations.
     QUERY,
+    /// On mutation operations.
     MUTATION,
+    /// On subscription operations.
     SUBSCRIPTION,
+    /// On fields of the selections.
     FIELD,
+    /// On fragment definitions.
     FRAGMENT_DEFINITION,
+    /// On fragment spreads.
     FRAGMENT_SPREAD,
+    /// On inline fragments.
     INLINE_FRAGMENT,
+    /// On the schema definition.
     SCHEMA,
+    /// On scalar types.
     SCALAR,
+    /// On object types.
     OBJECT,
+    /// On the fields of object and interface types.
     FIELD_DEFINITION,
+    /// On arguments.
     ARGUMENT_DEFINITION,
+    /// On interface types.
     INTERFACE,
+    /// On union types.
     UNION,
+    /// On enum types.
     ENUM,
+    /// On enum values.
     ENUM_VALUE,
+    /// On input object types.
     INPUT_OBJECT,
+    /// On the fields of input object types.
     INPUT_FIELD_DEFINITION,
+    /// A location this crate does not know about.
     Other(String),
 }
 
-impl ::serde::Serialize for __DirectiveLocation {
-    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
-        ser.serialize_str(match *self {
+impl __DirectiveLocation {
+    /// The name of the location, like `FIELD_DEFINITION`.
+    pub fn as_str(&self) -> &str {
+        match *self {
             __DirectiveLocation::QUERY => "QUERY",
             __DirectiveLocation::MUTATION => "MUTATION",
             __DirectiveLocation::SUBSCRIPTION => "SUBSCRIPTION",
@@ -49,47 +72,83 @@ impl ::serde::Serialize for __DirectiveLocation {
             __DirectiveLocation::INPUT_OBJECT => "INPUT_OBJECT",
             __DirectiveLocation::INPUT_FIELD_DEFINITION => "INPUT_FIELD_DEFINITION",
             __DirectiveLocation::Other(ref s) => s.as_str(),
-        })
+        }
+    }
+}
+
+impl ::serde::Serialize for __DirectiveLocation {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_str(self.as_str())
     }
 }
 
 impl<'de> ::serde::Deserialize<'de> for __DirectiveLocation {
     fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let s = <&'de str>::deserialize(deserializer)?;
+        deserializer.deserialize_str(NameVisitor(__DirectiveLocation::from_name))
+    }
+}
+
+impl __DirectiveLocation {
+    fn from_name(s: &str) -> Self {
         match s {
-            "QUERY" => Ok(__DirectiveLocation::QUERY),
-            "MUTATION" => Ok(__DirectiveLocation::MUTATION),
-            "SUBSCRIPTION" => Ok(__DirectiveLocation::SUBSCRIPTION),
-            "FIELD" => Ok(__DirectiveLocation::FIELD),
-            "FRAGMENT_DEFINITION" => Ok(__DirectiveLocation::FRAGMENT_DEFINITION),
-            "FRAGMENT_SPREAD" => Ok(__DirectiveLocation::FRAGMENT_SPREAD),
-            "INLINE_FRAGMENT" => Ok(__DirectiveLocation::INLINE_FRAGMENT),
-            "SCHEMA" => Ok(__DirectiveLocation::SCHEMA),
-            "SCALAR" => Ok(__DirectiveLocation::SCALAR),
-            "OBJECT" => Ok(__DirectiveLocation::OBJECT),
-            "FIELD_DEFINITION" => Ok(__DirectiveLocation::FIELD_DEFINITION),
-            "ARGUMENT_DEFINITION" => Ok(__DirectiveLocation::ARGUMENT_DEFINITION),
-            "INTERFACE" => Ok(__DirectiveLocation::INTERFACE),
-            "UNION" => Ok(__DirectiveLocation::UNION),
-            "ENUM" => Ok(__DirectiveLocation::ENUM),
-            "ENUM_VALUE" => Ok(__DirectiveLocation::ENUM_VALUE),
-            "INPUT_OBJECT" => Ok(__DirectiveLocation::INPUT_OBJECT),
-            "INPUT_FIELD_DEFINITION" => Ok(__DirectiveLocation::INPUT_FIELD_DEFINITION),
-            _ => Ok(__DirectiveLocation::Other(s.to_string())),
+            "QUERY" => __DirectiveLocation::QUERY,
+            "MUTATION" => __DirectiveLocation::MUTATION,
+            "SUBSCRIPTION" => __DirectiveLocation::SUBSCRIPTION,
+            "FIELD" => __DirectiveLocation::FIELD,
+            "FRAGMENT_DEFINITION" => __DirectiveLocation::FRAGMENT_DEFINITION,
+            "FRAGMENT_SPREAD" => __DirectiveLocation::FRAGMENT_SPREAD,
+            "INLINE_FRAGMENT" => __DirectiveLocation::INLINE_FRAGMENT,
+            "SCHEMA" => __DirectiveLocation::SCHEMA,
+            "SCALAR" => __DirectiveLocation::SCALAR,
+            "OBJECT" => __DirectiveLocation::OBJECT,
+            "FIELD_DEFINITION" => __DirectiveLocation::FIELD_DEFINITION,
+            "ARGUMENT_DEFINITION" => __DirectiveLocation::ARGUMENT_DEFINITION,
+            "INTERFACE" => __DirectiveLocation::INTERFACE,
+            "UNION" => __DirectiveLocation::UNION,
+            "ENUM" => __DirectiveLocation::ENUM,
+            "ENUM_VALUE" => __DirectiveLocation::ENUM_VALUE,
+            "INPUT_OBJECT" => __DirectiveLocation::INPUT_OBJECT,
+            "INPUT_FIELD_DEFINITION" => __DirectiveLocation::INPUT_FIELD_DEFINITION,
+            _ => __DirectiveLocation::Other(s.to_string()),
         }
     }
 }
 
+/// Deserializes enum values from borrowed or transient strings, without allocating for the known names.
+struct NameVisitor<T>(fn(&str) -> T);
+
+impl<'de, T> ::serde::de::Visitor<'de> for NameVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        formatter.write_str("a name")
+    }
+
+    fn visit_str<E: ::serde::de::Error>(self, s: &str) -> Result<T, E> {
+        Ok((self.0)(s))
+    }
+}
+
+/// What kind of type a type is.
 #[derive(Clone, Debug)]
 pub enum __TypeKind {
+    /// A scalar type.
     SCALAR,
+    /// An object type.
     OBJECT,
+    /// An interface type.
     INTERFACE,
+    /// A union type.
     UNION,
+    /// An enum type.
     ENUM,
+    /// An input object type.
     INPUT_OBJECT,
+    /// A list of the type in `ofType`.
     LIST,
+    /// The type in `ofType`, not nullable.
     NON_NULL,
+    /// A kind this crate does not know about.
     Other(String),
 }
 
@@ -111,244 +170,458 @@ impl ::serde::Serialize for __TypeKind {
 
 impl<'de> ::serde::Deserialize<'de> for __TypeKind {
     fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let s = <&'de str>::deserialize(deserializer)?;
+        deserializer.deserialize_str(NameVisitor(__TypeKind::from_name))
+    }
+}
+
+impl __TypeKind {
+    fn from_name(s: &str) -> Self {
         match s {
-            "SCALAR" => Ok(__TypeKind::SCALAR),
-            "OBJECT" => Ok(__TypeKind::OBJECT),
-            "INTERFACE" => Ok(__TypeKind::INTERFACE),
-            "UNION" => Ok(__TypeKind::UNION),
-            "ENUM" => Ok(__TypeKind::ENUM),
-            "INPUT_OBJECT" => Ok(__TypeKind::INPUT_OBJECT),
-            "LIST" => Ok(__TypeKind::LIST),
-            "NON_NULL" => Ok(__TypeKind::NON_NULL),
-            _ => Ok(__TypeKind::Other(s.to_string())),
+            "SCALAR" => __TypeKind::SCALAR,
+            "OBJECT" => __TypeKind::OBJECT,
+            "INTERFACE" => __TypeKind::INTERFACE,
+            "UNION" => __TypeKind::UNION,
+            "ENUM" => __TypeKind::ENUM,
+            "INPUT_OBJECT" => __TypeKind::INPUT_OBJECT,
+            "LIST" => __TypeKind::LIST,
+            "NON_NULL" => __TypeKind::NON_NULL,
+            _ => __TypeKind::Other(s.to_string()),
         }
     }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// A type of the schema.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FullType {
+    /// What kind of type it is.
     pub kind: Option<__TypeKind>,
+    /// The name of the type.
     pub name: Option<String>,
+    /// The description of the type.
     pub description: Option<String>,
+    /// The fields of object and interface types.
     pub fields: Option<Vec<Option<FullTypeFields>>>,
+    /// The fields of input object types.
     pub input_fields: Option<Vec<Option<FullTypeInputFields>>>,
+    /// The interfaces object and interface types implement.
     pub interfaces: Option<Vec<Option<FullTypeInterfaces>>>,
+    /// The values of enum types.
     pub enum_values: Option<Vec<Option<FullTypeEnumValues>>>,
+    /// The object types of the values of interface and union types.
     pub possible_types: Option<Vec<Option<FullTypePossibleTypes>>>,
-}
-
-#[derive(Clone, Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+    /// Only reported by servers that support `@specifiedBy`, when the introspection query asks for it.
+    #[serde(
+        default,
+        rename = "specifiedByURL",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub specified_by_url: Option<String>,
+}
+
+/// An argument of a field.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
 pub struct FullTypeFieldsArgs {
-    #[serde(flatten)]
-    input_value: InputValue,
+    /// The argument.
+    pub input_value: InputValue,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// The type of a field.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
 pub struct FullTypeFieldsType {
-    #[serde(flatten)]
+    /// The type.
     pub type_ref: TypeRef,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// A field of an object or interface type.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FullTypeFields {
+    /// The name of the field.
     pub name: Option<String>,
+    /// The description of the field.
     pub description: Option<String>,
+    /// The arguments of the field.
     pub args: Option<Vec<Option<FullTypeFieldsArgs>>>,
+    /// The type of the values of the field.
     #[serde(rename = "type")]
     pub type_: Option<FullTypeFieldsType>,
+    /// Whether the field is deprecated.
     pub is_deprecated: Option<Boolean>,
+    /// Why the field is deprecated.
     pub deprecation_reason: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// A field of an input object type.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
 pub struct FullTypeInputFields {
-    #[serde(flatten)]
+    /// The field.
     pub input_value: InputValue,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// An interface implemented by an object or interface type.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
 pub struct FullTypeInterfaces {
-    #[serde(flatten)]
+    /// The interface.
     pub type_ref: TypeRef,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// A value of an enum type.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FullTypeEnumValues {
+    /// The name of the value.
     pub name: Option<String>,
+    /// The description of the value.
     pub description: Option<String>,
+    /// Whether the value is deprecated.
     pub is_deprecated: Option<Boolean>,
+    /// Why the value is deprecated.
     pub deprecation_reason: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// An object type of the values of an interface or union type.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
 pub struct FullTypePossibleTypes {
-    #[serde(flatten)]
+    /// The object type.
     pub type_ref: TypeRef,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// An argument, or a field of an input object type.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InputValue {
+    /// The name of the argument or field.
     pub name: Option<String>,
+    /// The description of the argument or field.
     pub description: Option<String>,
+    /// The type of the values of the argument or field.
     #[serde(rename = "type")]
     pub type_: Option<InputValueType>,
+    /// The default value, as a GraphQL literal.
     pub default_value: Option<String>,
+    /// Only reported by servers that support deprecating input values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_deprecated: Option<Boolean>,
+    /// Why the argument or field is deprecated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecation_reason: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// The type of an argument or input field.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
 pub struct InputValueType {
-    #[serde(flatten)]
+    /// The type.
     pub type_ref: TypeRef,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// A reference to a type, wrapped in lists and non-null types.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TypeRef {
+    /// What kind of type it is.
     pub kind: Option<__TypeKind>,
+    /// The name of named types, `None` for lists and non-null types.
     pub name: Option<String>,
+    /// The type wrapped in lists and non-null types.
     pub of_type: Option<Box<TypeRef>>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+// The nesting of `ofType` the introspection query asks for, which `TypeRef` reads recursively instead.
+#[allow(dead_code)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType {
-    pub kind: Option<__TypeKind>,
-    pub name: Option<String>,
+struct TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType {
+    kind: Option<__TypeKind>,
+    name: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[allow(dead_code)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfType {
-    pub kind: Option<__TypeKind>,
-    pub name: Option<String>,
-    pub of_type: Option<TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType>,
+struct TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfType {
+    kind: Option<__TypeKind>,
+    name: Option<String>,
+    of_type: Option<TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[allow(dead_code)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct TypeRefOfTypeOfTypeOfTypeOfTypeOfType {
-    pub kind: Option<__TypeKind>,
-    pub name: Option<String>,
-    pub of_type: Option<TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfType>,
+struct TypeRefOfTypeOfTypeOfTypeOfTypeOfType {
+    kind: Option<__TypeKind>,
+    name: Option<String>,
+    of_type: Option<TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfType>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[allow(dead_code)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct TypeRefOfTypeOfTypeOfTypeOfType {
-    pub kind: Option<__TypeKind>,
-    pub name: Option<String>,
-    pub of_type: Option<TypeRefOfTypeOfTypeOfTypeOfTypeOfType>,
+struct TypeRefOfTypeOfTypeOfTypeOfType {
+    kind: Option<__TypeKind>,
+    name: Option<String>,
+    of_type: Option<TypeRefOfTypeOfTypeOfTypeOfTypeOfType>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[allow(dead_code)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct TypeRefOfTypeOfTypeOfType {
-    pub kind: Option<__TypeKind>,
-    pub name: Option<String>,
-    pub of_type: Option<TypeRefOfTypeOfTypeOfTypeOfType>,
+struct TypeRefOfTypeOfTypeOfType {
+    kind: Option<__TypeKind>,
+    name: Option<String>,
+    of_type: Option<TypeRefOfTypeOfTypeOfTypeOfType>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[allow(dead_code)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct TypeRefOfTypeOfType {
-    pub kind: Option<__TypeKind>,
-    pub name: Option<String>,
-    pub of_type: Option<TypeRefOfTypeOfTypeOfType>,
+struct TypeRefOfTypeOfType {
+    kind: Option<__TypeKind>,
+    name: Option<String>,
+    of_type: Option<TypeRefOfTypeOfTypeOfType>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[allow(dead_code)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct TypeRefOfType {
-    pub kind: Option<__TypeKind>,
-    pub name: Option<String>,
-    pub of_type: Option<TypeRefOfTypeOfType>,
+struct TypeRefOfType {
+    kind: Option<__TypeKind>,
+    name: Option<String>,
+    of_type: Option<TypeRefOfTypeOfType>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// The query type of the schema.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RustIntrospectionQuerySchemaQueryType {
+    /// The name of the type.
     pub name: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// The mutation type of the schema.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RustIntrospectionQuerySchemaMutationType {
+    /// The name of the type.
     pub name: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// The subscription type of the schema.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RustIntrospectionQuerySchemaSubscriptionType {
+    /// The name of the type.
     pub name: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// A type of the schema.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
 pub struct RustIntrospectionQuerySchemaTypes {
-    #[serde(flatten)]
+    /// The type.
     pub full_type: FullType,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// An argument of a directive.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
 pub struct RustIntrospectionQuerySchemaDirectivesArgs {
-    #[serde(flatten)]
-    input_value: InputValue,
+    /// The argument.
+    pub input_value: InputValue,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// A directive of the schema.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RustIntrospectionQuerySchemaDirectives {
+    /// The name of the directive.
     pub name: Option<String>,
+    /// The description of the directive.
     pub description: Option<String>,
+    /// Where the directive can be used.
     pub locations: Option<Vec<Option<__DirectiveLocation>>>,
+    /// The arguments of the directive.
     pub args: Option<Vec<Option<RustIntrospectionQuerySchemaDirectivesArgs>>>,
+    /// Only reported by servers that support repeatable directives.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_repeatable: Option<Boolean>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// The `__schema` of the introspection response.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RustIntrospectionQuerySchema {
+    /// The root type of query operations.
     pub query_type: Option<RustIntrospectionQuerySchemaQueryType>,
+    /// The root type of mutation operations, if the schema has mutations.
     pub mutation_type: Option<RustIntrospectionQuerySchemaMutationType>,
+    /// The root type of subscription operations, if the schema has subscriptions.
     pub subscription_type: Option<RustIntrospectionQuerySchemaSubscriptionType>,
+    /// All the types of the schema, including the introspection types.
     pub types: Option<Vec<Option<RustIntrospectionQuerySchemaTypes>>>,
-    directives: Option<Vec<Option<RustIntrospectionQuerySchemaDirectives>>>,
+    /// The directives of the schema.
+    pub directives: Option<Vec<Option<RustIntrospectionQuerySchemaDirectives>>>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-pub(crate) struct Schema {
+/// The `data` of a response to the introspection query.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Schema {
+    /// The schema.
     #[serde(rename = "__schema")]
     pub schema: Option<RustIntrospectionQuerySchema>,
 }
 
-#[derive(Deserialize, Debug)]
-pub(crate) struct FullResponse<T> {
-    data: T,
+/// A GraphQL response, with its `data` envelope.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct FullResponse<T> {
+    /// The data of the response.
+    pub data: T,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-pub(crate) enum IntrospectionResponse {
+/// A response to the introspection query, either as the server sent it or without the `data` envelope.
+#[derive(Debug)]
+pub enum IntrospectionResponse {
+    /// The response as the server sent it.
     FullResponse(FullResponse<Schema>),
+    /// The response without the `data` envelope.
     Schema(Schema),
 }
 
+impl ::serde::Serialize for IntrospectionResponse {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        match self {
+            IntrospectionResponse::FullResponse(full_response) => full_response.serialize(ser),
+            IntrospectionResponse::Schema(schema) => schema.serialize(ser),
+        }
+    }
+}
+
+// Deserialized in a single pass over the map rather than as an untagged enum, which would first buffer the whole response, several times the size of the JSON for large schemas.
+impl<'de> ::serde::Deserialize<'de> for IntrospectionResponse {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(IntrospectionResponseVisitor)
+    }
+}
+
+struct IntrospectionResponseVisitor;
+
+impl<'de> ::serde::de::Visitor<'de> for IntrospectionResponseVisitor {
+    type Value = IntrospectionResponse;
+
+    fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        formatter.write_str("an introspection response")
+    }
+
+    fn visit_map<A: ::serde::de::MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> Result<IntrospectionResponse, A::Error> {
+        let mut data = None;
+        let mut schema = None;
+        while let Some(key) = map.next_key::<ResponseKey>()? {
+            match key {
+                ResponseKey::Data => data = Some(map.next_value::<Schema>()?),
+                ResponseKey::Schema => schema = map.next_value()?,
+                ResponseKey::Other => {
+                    map.next_value::<::serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(match data {
+            Some(data) => IntrospectionResponse::FullResponse(FullResponse { data }),
+            None => IntrospectionResponse::Schema(Schema { schema }),
+        })
+    }
+}
+
+enum ResponseKey {
+    Data,
+    Schema,
+    Other,
+}
+
+impl<'de> ::serde::Deserialize<'de> for ResponseKey {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_identifier(NameVisitor(|key| match key {
+            "data" => ResponseKey::Data,
+            "__schema" => ResponseKey::Schema,
+            _ => ResponseKey::Other,
+        }))
+    }
+}
+
 impl IntrospectionResponse {
-    pub(crate) fn as_schema(&self) -> &Schema {
+    /// The `data` of the response.
+    pub fn as_schema(&self) -> &Schema {
         match self {
             IntrospectionResponse::FullResponse(full_response) => &full_response.data,
-            IntrospectionResponse::Schema(schema) => &schema,
+            IntrospectionResponse::Schema(schema) => schema,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema;
+    use serde_json;
+
+    #[test]
+    fn introspection_response_round_trips_through_json() {
+        // Owned, so it can be read from any `io::Read` without keeping the JSON around.
+        let response: IntrospectionResponse =
+            serde_json::from_reader(&include_bytes!("tests/github_schema.json")[..]).unwrap();
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json["__schema"]["queryType"]["name"] == "Query");
+        let object = json["__schema"]["types"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|ty| ty["kind"] == "OBJECT")
+            .unwrap();
+        // The flattened type references serialize inline.
+        assert!(object["fields"][0]["type"]["kind"].is_string());
+
+        let reparsed: IntrospectionResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            schema::Schema::from(&reparsed),
+            schema::Schema::from(&response)
+        );
+    }
+
+    #[test]
+    fn responses_parse_with_and_without_the_data_envelope() {
+        let schema = r#"{"__schema": {"queryType": {"name": "Query"}, "types": [{"kind": "SCALAR", "name": "Date"}]}}"#;
+        let full = format!(
+            r#"{{"errors": [], "data": {}, "extensions": {{"cost": 1}}}}"#,
+            schema
+        );
+
+        match serde_json::from_str(&full).unwrap() {
+            IntrospectionResponse::FullResponse(response) => {
+                let types = response.data.schema.unwrap().types.unwrap();
+                match types[0].as_ref().unwrap().full_type.kind {
+                    Some(__TypeKind::SCALAR) => (),
+                    ref other => panic!("unexpected kind {:?}", other),
+                }
+            }
+            other => panic!("unexpected response {:?}", other),
+        }
+        match serde_json::from_str(schema).unwrap() {
+            IntrospectionResponse::Schema(schema) => {
+                assert_eq!(
+                    schema.schema.unwrap().query_type.unwrap().name.unwrap(),
+                    "Query"
+                )
+            }
+            other => panic!("unexpected response {:?}", other),
         }
     }
 }
@@ -0,0 +1,317 @@
+use constants::TYPENAME_FIELD;
+use failure;
+use field_type::FieldType;
+use graphql_parser::query;
+use operations::Operation;
+use schema::Schema;
+use selection::{Selection, SelectionItem};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Write the JSON Schema documents describing the variables and the response data of `operation` to `directory`, as `<operation>.variables.schema.json` and `<operation>.response.schema.json`.
+pub(crate) fn write_operation_schemas(
+    directory: &Path,
+    schema: &Schema,
+    query: &query::Document,
+    operation: &Operation,
+) -> Result<(), failure::Error> {
+    fs::create_dir_all(directory)
+        .map_err(|err| format_err!("Could not create {}: {}", directory.display(), err))?;
+
+    let documents = [
+        ("variables", variables_schema(schema, operation)),
+        ("response", response_schema(schema, query, operation)),
+    ];
+    for (kind, document) in &documents {
+        let path = directory.join(format!("{}.{}.schema.json", operation.name, kind));
+        let json = ::serde_json::to_string_pretty(document)?;
+        fs::write(&path, json + "\n")
+            .map_err(|err| format_err!("Could not write {}: {}", path.display(), err))?;
+    }
+    Ok(())
+}
+
+/// The schema of the variables: an object with a property per variable. Non-null variables without a default value are required.
+pub(crate) fn variables_schema(schema: &Schema, operation: &Operation) -> Value {
+    let mut builder = Builder::new(schema);
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for variable in &operation.variables {
+        properties.insert(variable.name.to_owned(), builder.type_schema(&variable.ty));
+        if !variable.ty.is_optional() && variable.default.is_none() {
+            required.push(Value::from(variable.name));
+        }
+    }
+
+    builder.document(
+        format!("{} variables", operation.name),
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        }),
+    )
+}
+
+/// The schema of the response data for the selection of the operation. Selections on interfaces and unions are one object schema per possible type.
+pub(crate) fn response_schema(
+    schema: &Schema,
+    query: &query::Document,
+    operation: &Operation,
+) -> Value {
+    let mut builder = Builder::new(schema);
+    for definition in &query.definitions {
+        if let query::Definition::Fragment(fragment) = definition {
+            let query::TypeCondition::On(ref on) = fragment.type_condition;
+            builder.fragments.insert(
+                &fragment.name,
+                (on, Selection::from(&fragment.selection_set)),
+            );
+        }
+    }
+
+    let data = builder.object_schema(operation.root_name(schema), &operation.selection);
+    builder.document(format!("{} response data", operation.name), data)
+}
+
+struct Builder<'a> {
+    schema: &'a Schema<'a>,
+    fragments: BTreeMap<&'a str, (&'a str, Selection<'a>)>,
+    /// The schemas of the enums, input objects and custom scalars, referred to by name.
+    defs: BTreeMap<String, Value>,
+}
+
+impl<'a> Builder<'a> {
+    fn new(schema: &'a Schema<'a>) -> Self {
+        Builder {
+            schema,
+            fragments: BTreeMap::new(),
+            defs: BTreeMap::new(),
+        }
+    }
+
+    fn document(self, title: String, root: Value) -> Value {
+        let mut document = json!({
+            "$schema": DRAFT,
+            "title": title,
+        });
+        let object = document.as_object_mut().expect("document is an object");
+        if let Value::Object(root) = root {
+            object.extend(root);
+        }
+        if !self.defs.is_empty() {
+            object.insert(
+                "$defs".to_owned(),
+                Value::Object(self.defs.into_iter().collect()),
+            );
+        }
+        document
+    }
+
+    fn type_schema(&mut self, ty: &FieldType) -> Value {
+        match ty {
+            FieldType::Named(name) => self.named_schema(name),
+            FieldType::Optional(inner) => {
+                json!({ "anyOf": [self.type_schema(inner), { "type": "null" }] })
+            }
+            FieldType::Vector(inner) => {
+                json!({ "type": "array", "items": self.type_schema(inner) })
+            }
+        }
+    }
+
+    /// The schema of a scalar, enum or input object, in `defs` unless it is a built-in scalar.
+    fn named_schema(&mut self, name: &str) -> Value {
+        let builtin = match name {
+            "Int" => Some("integer"),
+            "Float" => Some("number"),
+            "String" | "ID" => Some("string"),
+            "Boolean" => Some("boolean"),
+            _ => None,
+        };
+        if let Some(builtin) = builtin {
+            return json!({ "type": builtin });
+        }
+
+        let reference = json!({ "$ref": format!("#/$defs/{}", name) });
+        if self.defs.contains_key(name) {
+            return reference;
+        }
+        // Input objects can refer to themselves, so their entry exists before their fields are described.
+        self.defs.insert(name.to_owned(), Value::Null);
+        let def = self.def_schema(name);
+        self.defs.insert(name.to_owned(), def);
+        reference
+    }
+
+    fn def_schema(&mut self, name: &str) -> Value {
+        let schema = self.schema;
+        if let Some(enm) = schema.enums.get(name) {
+            let variants: Vec<&str> = enm.variants.iter().map(|variant| variant.name).collect();
+            with_description(json!({ "enum": variants }), enm.description)
+        } else if let Some(input) = schema.inputs.get(name) {
+            let mut fields: Vec<_> = input.fields.values().collect();
+            fields.sort_unstable_by_key(|field| field.name);
+            let mut properties = Map::new();
+            let mut required = Vec::new();
+            for field in fields {
+                let property = self.type_schema(&field.type_);
+                properties.insert(
+                    field.name.to_owned(),
+                    with_description(property, field.description),
+                );
+                if !field.type_.is_optional() && !input.default_values.contains_key(field.name) {
+                    required.push(Value::from(field.name));
+                }
+            }
+            with_description(
+                json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": required,
+                    "additionalProperties": false,
+                }),
+                input.description,
+            )
+        } else {
+            // Custom scalars can be anything, their format is up to the server.
+            let description = schema
+                .scalars
+                .get(name)
+                .and_then(|scalar| scalar.description);
+            with_description(json!({ "title": name }), description)
+        }
+    }
+
+    /// The schema of the value of a field of type `ty` with the selection `selection`.
+    fn field_schema(&mut self, ty: &FieldType, selection: &Selection) -> Value {
+        match ty {
+            FieldType::Named(name) if !selection.0.is_empty() => {
+                self.selection_schema(name, selection)
+            }
+            FieldType::Named(name) => self.named_schema(name),
+            FieldType::Optional(inner) => {
+                json!({ "anyOf": [self.field_schema(inner, selection), { "type": "null" }] })
+            }
+            FieldType::Vector(inner) => {
+                json!({ "type": "array", "items": self.field_schema(inner, selection) })
+            }
+        }
+    }
+
+    fn selection_schema(&mut self, type_name: &str, selection: &Selection) -> Value {
+        let schema = self.schema;
+        let mut possible_types: Vec<&str> = if let Some(iface) = schema.interfaces.get(type_name) {
+            iface.implemented_by.iter().cloned().collect()
+        } else if let Some(union) = schema.unions.get(type_name) {
+            union.variants.iter().cloned().collect()
+        } else {
+            vec![type_name]
+        };
+        possible_types.sort_unstable();
+        if possible_types.is_empty() {
+            possible_types.push(type_name);
+        }
+
+        let mut variants: Vec<Value> = Vec::with_capacity(possible_types.len());
+        for possible_type in possible_types {
+            let variant = self.object_schema(possible_type, selection);
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+        if variants.len() == 1 {
+            variants.remove(0)
+        } else {
+            json!({ "anyOf": variants })
+        }
+    }
+
+    /// The schema of an object of type `type_name`, with the fields of the selection and of the fragments applying to that type.
+    fn object_schema(&mut self, type_name: &str, selection: &Selection) -> Value {
+        let mut properties = Map::new();
+        self.collect_properties(type_name, selection, &mut properties);
+        let required: Vec<&String> = properties.keys().collect();
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+    }
+
+    fn collect_properties(
+        &mut self,
+        type_name: &str,
+        selection: &Selection,
+        properties: &mut Map<String, Value>,
+    ) {
+        for item in &selection.0 {
+            match item {
+                SelectionItem::Field(field) => {
+                    let key = field.alias.unwrap_or(field.name);
+                    if properties.contains_key(key) {
+                        continue;
+                    }
+                    let property = if field.name == TYPENAME_FIELD {
+                        if self.schema.objects.contains_key(type_name) {
+                            json!({ "const": type_name })
+                        } else {
+                            json!({ "type": "string" })
+                        }
+                    } else {
+                        match self.schema.field(type_name, field.name) {
+                            Some(schema_field) => {
+                                let value = self.field_schema(&schema_field.type_, &field.fields);
+                                with_description(value, schema_field.description)
+                            }
+                            // `@client` fields of a client schema that is not loaded.
+                            None => json!({}),
+                        }
+                    };
+                    properties.insert(key.to_owned(), property);
+                }
+                SelectionItem::InlineFragment(inline) => {
+                    if self.applies(inline.on, type_name) {
+                        self.collect_properties(type_name, &inline.fields, properties);
+                    }
+                }
+                SelectionItem::FragmentSpread(spread) => {
+                    let fragment = match self.fragments.get(spread.fragment_name) {
+                        Some((on, selection)) if self.applies(on, type_name) => selection.clone(),
+                        _ => continue,
+                    };
+                    self.collect_properties(type_name, &fragment, properties);
+                }
+            }
+        }
+    }
+
+    /// Whether a fragment on `condition` applies to values of type `type_name`.
+    fn applies(&self, condition: &str, type_name: &str) -> bool {
+        condition == type_name
+            || self
+                .schema
+                .interfaces
+                .get(condition)
+                .map(|iface| iface.implemented_by.contains(type_name))
+                .unwrap_or(false)
+            || self
+                .schema
+                .unions
+                .get(condition)
+                .map(|union| union.variants.contains(type_name))
+                .unwrap_or(false)
+    }
+}
+
+fn with_description(mut value: Value, description: Option<&str>) -> Value {
+    if let (Value::Object(object), Some(description)) = (&mut value, description) {
+        object.insert("description".to_owned(), Value::from(description));
+    }
+    value
+}
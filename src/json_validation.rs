@@ -0,0 +1,355 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use query::QueryContext;
+use syn;
+
+/// The `PathError` type and the `ValidateJson` trait, with its implementations for the built-in scalars and the containers. Generated once per module, when JSON validation is enabled.
+pub(crate) fn validation_trait() -> TokenStream {
+    let leaf_impls = [
+        quote!(bool),
+        quote!(i64),
+        quote!(f64),
+        quote!(::std::string::String),
+    ]
+    .iter()
+    .map(leaf_impl)
+    .collect::<Vec<_>>();
+
+    quote! {
+        /// A mismatch between a JSON document and the generated types.
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::cmp::PartialEq)]
+        pub struct PathError {
+            /// The JSON pointer to the mismatched value, like `/hero/friends/0/name`.
+            pub path: ::std::string::String,
+            pub message: ::std::string::String,
+        }
+
+        impl ::std::fmt::Display for PathError {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                ::std::write!(f, "{}: {}", self.path, self.message)
+            }
+        }
+
+        /// Checks a JSON document against a generated type, collecting every mismatch instead of stopping at the first one.
+        pub trait ValidateJson {
+            fn validate_json_at(value: &::serde_json::Value, path: &str, errors: &mut ::std::vec::Vec<PathError>);
+
+            fn validate_missing(path: &str, errors: &mut ::std::vec::Vec<PathError>) {
+                errors.push(PathError {
+                    path: ::std::string::ToString::to_string(&path),
+                    message: ::std::string::ToString::to_string(&"missing field"),
+                });
+            }
+        }
+
+        fn json_pointer(path: &str, segment: &str) -> ::std::string::String {
+            ::std::format!("{}/{}", path, segment.replace("~", "~0").replace("/", "~1"))
+        }
+
+        #(#leaf_impls)*
+
+        impl<T: ValidateJson> ValidateJson for ::std::option::Option<T> {
+            fn validate_json_at(value: &::serde_json::Value, path: &str, errors: &mut ::std::vec::Vec<PathError>) {
+                if !value.is_null() {
+                    T::validate_json_at(value, path, errors)
+                }
+            }
+
+            fn validate_missing(_path: &str, _errors: &mut ::std::vec::Vec<PathError>) {}
+        }
+
+        impl<T: ValidateJson> ValidateJson for ::std::vec::Vec<T> {
+            fn validate_json_at(value: &::serde_json::Value, path: &str, errors: &mut ::std::vec::Vec<PathError>) {
+                match value.as_array() {
+                    ::std::option::Option::Some(items) => for (index, item) in ::std::iter::Iterator::enumerate(items.iter()) {
+                        T::validate_json_at(item, &json_pointer(path, &::std::string::ToString::to_string(&index)), errors)
+                    },
+                    ::std::option::Option::None => errors.push(PathError {
+                        path: ::std::string::ToString::to_string(&path),
+                        message: ::std::format!("expected a list, found `{}`", value),
+                    }),
+                }
+            }
+        }
+
+        impl<T: ValidateJson> ValidateJson for ::std::boxed::Box<T> {
+            fn validate_json_at(value: &::serde_json::Value, path: &str, errors: &mut ::std::vec::Vec<PathError>) {
+                T::validate_json_at(value, path, errors)
+            }
+        }
+
+        impl<T: ValidateJson> ValidateJson for ::std::sync::Arc<T> {
+            fn validate_json_at(value: &::serde_json::Value, path: &str, errors: &mut ::std::vec::Vec<PathError>) {
+                T::validate_json_at(value, path, errors)
+            }
+        }
+    }
+}
+
+/// Values that are checked by deserializing them as a whole: scalars and enums.
+fn leaf_impl(ty: &TokenStream) -> TokenStream {
+    let check = leaf_check(ty);
+    quote! {
+        impl ValidateJson for #ty {
+            fn validate_json_at(value: &::serde_json::Value, path: &str, errors: &mut ::std::vec::Vec<PathError>) {
+                #check
+            }
+        }
+    }
+}
+
+fn leaf_check(ty: &TokenStream) -> TokenStream {
+    quote! {
+        if let ::std::result::Result::Err(err) = ::serde_json::from_value::<#ty>(::std::clone::Clone::clone(&value)) {
+            errors.push(PathError {
+                path: ::std::string::ToString::to_string(&path),
+                message: ::std::string::ToString::to_string(&err),
+            });
+        }
+    }
+}
+
+/// Union and interface variants are checked field by field, after looking them up by their tag.
+fn tagged_enum_impl(name: &Ident, tag: &str, data: &syn::DataEnum) -> TokenStream {
+    let variants = data.variants.iter().filter_map(|variant| {
+        let variant_name = variant.ident.to_string();
+        match variant.fields {
+            syn::Fields::Unnamed(ref fields) => {
+                let ty = &fields.unnamed.iter().next().expect("variant field").ty;
+                Some(quote!(::std::option::Option::Some(#variant_name) => <#ty as ValidateJson>::validate_json_at(value, path, errors),))
+            }
+            syn::Fields::Unit => Some(quote!(::std::option::Option::Some(#variant_name) => (),)),
+            // The fallback variant of non-exhaustive enums has no tag of its own, it is left to the deserialization check.
+            syn::Fields::Named(_) => None,
+        }
+    });
+    let fallback = leaf_check(&quote!(#name));
+
+    quote! {
+        impl ValidateJson for #name {
+            fn validate_json_at(value: &::serde_json::Value, path: &str, errors: &mut ::std::vec::Vec<PathError>) {
+                match value.get(#tag).and_then(|tag| tag.as_str()) {
+                    #(#variants)*
+                    _ => { #fallback }
+                }
+            }
+        }
+    }
+}
+
+/// The `ValidateJson` implementation of a generated struct or enum, from its definition. Struct fields are checked one by one, enums are deserialized as a whole.
+pub(crate) fn validation_impl(context: &QueryContext, definition: &TokenStream) -> TokenStream {
+    if !context.json_validation {
+        return quote!();
+    }
+
+    let definition: syn::DeriveInput =
+        syn::parse2(definition.clone()).expect("generated type definition");
+    let name = &definition.ident;
+    let data = match definition.data {
+        syn::Data::Struct(ref data) => data,
+        syn::Data::Enum(ref data) => {
+            return match SerdeAttributes::from_attributes(&definition.attrs).tag {
+                Some(tag) => tagged_enum_impl(name, &tag, data),
+                None => leaf_impl(&quote!(#name)),
+            }
+        }
+        syn::Data::Union(_) => return quote!(),
+    };
+
+    // Structs of fragment spreads only flatten every field and never read the object themselves.
+    let object = if data
+        .fields
+        .iter()
+        .all(|field| SerdeAttributes::from_attributes(&field.attrs).flatten)
+    {
+        quote!(_)
+    } else {
+        quote!(object)
+    };
+    let fields = data.fields.iter().map(|field| {
+        let ty = &field.ty;
+        let serde = SerdeAttributes::from_attributes(&field.attrs);
+        if serde.flatten {
+            return quote!(<#ty as ValidateJson>::validate_json_at(value, path, errors););
+        }
+
+        let json_name = serde
+            .rename
+            .unwrap_or_else(|| field.ident.as_ref().expect("named field").to_string());
+        quote! {
+            match object.get(#json_name) {
+                ::std::option::Option::Some(field) => <#ty as ValidateJson>::validate_json_at(
+                    field,
+                    &json_pointer(path, #json_name),
+                    errors,
+                ),
+                ::std::option::Option::None => <#ty as ValidateJson>::validate_missing(&json_pointer(path, #json_name), errors),
+            }
+        }
+    });
+
+    quote! {
+        #[allow(deprecated)]
+        impl ValidateJson for #name {
+            fn validate_json_at(value: &::serde_json::Value, path: &str, errors: &mut ::std::vec::Vec<PathError>) {
+                let #object = match value.as_object() {
+                    ::std::option::Option::Some(object) => object,
+                    ::std::option::Option::None => {
+                        errors.push(PathError {
+                            path: ::std::string::ToString::to_string(&path),
+                            message: ::std::format!("expected an object, found `{}`", value),
+                        });
+                        return;
+                    }
+                };
+
+                #(#fields)*
+            }
+        }
+    }
+}
+
+/// Custom scalars are checked by deserializing them.
+pub(crate) fn scalar_validation_impl(context: &QueryContext, scalar_name: &str) -> TokenStream {
+    if !context.json_validation {
+        return quote!();
+    }
+
+    let name = Ident::new(scalar_name, Span::call_site());
+    leaf_impl(&quote!(#name))
+}
+
+/// The string type of the response fields is checked by deserializing it.
+pub(crate) fn string_type_validation_impl(context: &QueryContext, ty: &TokenStream) -> TokenStream {
+    if !context.json_validation {
+        return quote!();
+    }
+
+    leaf_impl(ty)
+}
+
+/// The `validate_json` entry point on the response data.
+pub(crate) fn response_data_validation(
+    context: &QueryContext,
+    response_data_name: &Ident,
+) -> TokenStream {
+    if !context.json_validation {
+        return quote!();
+    }
+
+    quote! {
+        impl #response_data_name {
+            /// Check a JSON document, like a recorded server response, against the response data, reporting every mismatch with its JSON pointer.
+            pub fn validate_json(value: &::serde_json::Value) -> ::std::result::Result<(), ::std::vec::Vec<PathError>> {
+                let mut errors = ::std::vec::Vec::new();
+                <#response_data_name as ValidateJson>::validate_json_at(value, "", &mut errors);
+                if errors.is_empty() {
+                    ::std::result::Result::Ok(())
+                } else {
+                    ::std::result::Result::Err(errors)
+                }
+            }
+        }
+    }
+}
+
+/// The serde attributes affecting the JSON shape of a field or an enum.
+#[derive(Default)]
+pub(crate) struct SerdeAttributes {
+    pub(crate) rename: Option<String>,
+    pub(crate) flatten: bool,
+    pub(crate) tag: Option<String>,
+}
+
+impl SerdeAttributes {
+    pub(crate) fn from_attributes(attributes: &[syn::Attribute]) -> SerdeAttributes {
+        let mut serde = SerdeAttributes::default();
+        let items = attributes
+            .iter()
+            .filter_map(|attribute| attribute.interpret_meta())
+            .filter_map(|meta| match meta {
+                syn::Meta::List(ref list) if list.ident == "serde" => {
+                    Some(list.nested.clone().into_iter())
+                }
+                _ => None,
+            })
+            .flatten();
+
+        for item in items {
+            match item {
+                syn::NestedMeta::Meta(syn::Meta::Word(ref ident)) if ident == "flatten" => {
+                    serde.flatten = true
+                }
+                syn::NestedMeta::Meta(syn::Meta::NameValue(ref name_value)) => {
+                    if let syn::Lit::Str(ref value) = name_value.lit {
+                        if name_value.ident == "rename" {
+                            serde.rename = Some(value.value())
+                        } else if name_value.ident == "tag" {
+                            serde.tag = Some(value.value())
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        serde
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::Schema;
+
+    #[test]
+    fn struct_fields_are_validated_by_their_json_name() {
+        let schema = Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        let definition = quote! {
+            #[derive(Deserialize)]
+            pub struct Viewer {
+                #[serde(rename = "fullName")]
+                pub full_name: ::std::option::Option<::std::string::String>,
+                #[serde(rename = "type")]
+                pub type_: ::std::string::String,
+                #[serde(flatten)]
+                pub on: ViewerOn,
+            }
+        };
+
+        assert!(validation_impl(&context, &definition).is_empty());
+
+        context.json_validation = true;
+        let generated = validation_impl(&context, &definition).to_string();
+        assert!(generated.contains(
+            "match object . get ( \"fullName\" ) { :: std :: option :: Option :: Some ( field ) => < :: std :: option :: Option < :: std :: string :: String > as ValidateJson > :: validate_json_at"
+        ));
+        assert!(generated.contains("match object . get ( \"type\" )"));
+        assert!(generated.contains(
+            "< ViewerOn as ValidateJson > :: validate_json_at ( value , path , errors ) ;"
+        ));
+    }
+
+    #[test]
+    fn tagged_enums_are_validated_by_variant() {
+        let schema = Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        context.json_validation = true;
+        let definition = quote! {
+            #[derive(Deserialize)]
+            #[serde(tag = "__typename")]
+            pub enum ViewerOn {
+                User(ViewerOnUser),
+                Bot,
+            }
+        };
+
+        let generated = validation_impl(&context, &definition).to_string();
+        assert!(generated.contains("match value . get ( \"__typename\" )"));
+        assert!(generated.contains(
+            "Some ( \"User\" ) => < ViewerOnUser as ValidateJson > :: validate_json_at ( value , path , errors ) ,"
+        ));
+        assert!(generated.contains("Some ( \"Bot\" ) => ( ) ,"));
+    }
+}
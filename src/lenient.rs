@@ -0,0 +1,192 @@
+use field_type::FieldType;
+use proc_macro2::TokenStream;
+use query::QueryContext;
+
+/// The built-in scalars deserialized leniently.
+const LENIENT_SCALARS: &[&str] = &["Int", "Float", "Boolean", "ID"];
+
+/// The `#[serde(deserialize_with)]` attribute of a response field of a built-in scalar type, when lenient deserialization is enabled.
+pub(crate) fn field_attribute(context: &QueryContext, field_type: &FieldType) -> TokenStream {
+    if !context.lenient_scalars || !LENIENT_SCALARS.contains(&field_type.inner_name_str()) {
+        return quote!();
+    }
+
+    // Unlike plain options, options with a `deserialize_with` function are required unless they have a default.
+    if field_type.is_optional() {
+        quote!(#[serde(default, deserialize_with = "deserialize_lenient")])
+    } else {
+        quote!(#[serde(deserialize_with = "deserialize_lenient")])
+    }
+}
+
+/// The `deserialize_lenient` function of the fields of built-in scalar types, accepting numbers, booleans and IDs as well as their string form, as some gateways send them. Generated once per module.
+pub(crate) fn lenient_helpers() -> TokenStream {
+    quote! {
+        /// A JSON scalar, as read by the lenient deserializers.
+        enum LenientValue<'a> {
+            Bool(bool),
+            I64(i64),
+            U64(u64),
+            F64(f64),
+            Str(&'a str),
+        }
+
+        /// Built-in scalars accepting the string form of their values.
+        trait LenientScalar: ::std::marker::Sized {
+            const EXPECTING: &'static str;
+
+            fn from_lenient(value: LenientValue) -> ::std::option::Option<Self>;
+        }
+
+        impl LenientScalar for i64 {
+            const EXPECTING: &'static str = "an integer, or a string of one";
+
+            fn from_lenient(value: LenientValue) -> ::std::option::Option<Self> {
+                match value {
+                    LenientValue::I64(value) => ::std::option::Option::Some(value),
+                    LenientValue::U64(value) => ::std::convert::TryFrom::try_from(value).ok(),
+                    LenientValue::Str(value) => value.trim().parse().ok(),
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+
+        impl LenientScalar for f64 {
+            const EXPECTING: &'static str = "a number, or a string of one";
+
+            fn from_lenient(value: LenientValue) -> ::std::option::Option<Self> {
+                match value {
+                    LenientValue::I64(value) => ::std::option::Option::Some(value as f64),
+                    LenientValue::U64(value) => ::std::option::Option::Some(value as f64),
+                    LenientValue::F64(value) => ::std::option::Option::Some(value),
+                    LenientValue::Str(value) => value.trim().parse().ok(),
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+
+        impl LenientScalar for bool {
+            const EXPECTING: &'static str = "a boolean, or a string of one";
+
+            fn from_lenient(value: LenientValue) -> ::std::option::Option<Self> {
+                match value {
+                    LenientValue::Bool(value) => ::std::option::Option::Some(value),
+                    LenientValue::Str(value) => value.trim().parse().ok(),
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+
+        impl LenientScalar for ::std::string::String {
+            const EXPECTING: &'static str = "a string, or an integer";
+
+            fn from_lenient(value: LenientValue) -> ::std::option::Option<Self> {
+                match value {
+                    LenientValue::Str(value) => ::std::option::Option::Some(::std::string::ToString::to_string(value)),
+                    LenientValue::I64(value) => ::std::option::Option::Some(::std::string::ToString::to_string(&value)),
+                    LenientValue::U64(value) => ::std::option::Option::Some(::std::string::ToString::to_string(&value)),
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+
+        struct LenientVisitor<T>(::std::marker::PhantomData<T>);
+
+        impl<T: LenientScalar> LenientVisitor<T> {
+            fn value<E: ::serde::de::Error>(&self, value: LenientValue, unexpected: ::serde::de::Unexpected) -> ::std::result::Result<T, E> {
+                T::from_lenient(value).ok_or_else(|| E::invalid_value(unexpected, self))
+            }
+        }
+
+        impl<'de, T: LenientScalar> ::serde::de::Visitor<'de> for LenientVisitor<T> {
+            type Value = T;
+
+            fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.write_str(T::EXPECTING)
+            }
+
+            fn visit_bool<E: ::serde::de::Error>(self, value: bool) -> ::std::result::Result<T, E> {
+                self.value(LenientValue::Bool(value), ::serde::de::Unexpected::Bool(value))
+            }
+
+            fn visit_i64<E: ::serde::de::Error>(self, value: i64) -> ::std::result::Result<T, E> {
+                self.value(LenientValue::I64(value), ::serde::de::Unexpected::Signed(value))
+            }
+
+            fn visit_u64<E: ::serde::de::Error>(self, value: u64) -> ::std::result::Result<T, E> {
+                self.value(LenientValue::U64(value), ::serde::de::Unexpected::Unsigned(value))
+            }
+
+            fn visit_f64<E: ::serde::de::Error>(self, value: f64) -> ::std::result::Result<T, E> {
+                self.value(LenientValue::F64(value), ::serde::de::Unexpected::Float(value))
+            }
+
+            fn visit_str<E: ::serde::de::Error>(self, value: &str) -> ::std::result::Result<T, E> {
+                self.value(LenientValue::Str(value), ::serde::de::Unexpected::Str(value))
+            }
+        }
+
+        /// The types of the fields deserialized leniently: the built-in scalars, in options and lists.
+        trait LenientField: ::std::marker::Sized {
+            fn deserialize_lenient<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error>;
+        }
+
+        impl<T: LenientScalar> LenientField for T {
+            fn deserialize_lenient<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+                deserializer.deserialize_any(LenientVisitor(::std::marker::PhantomData))
+            }
+        }
+
+        impl<T: LenientField> LenientField for ::std::option::Option<T> {
+            fn deserialize_lenient<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+                <::std::option::Option<LenientFieldValue<T>> as ::serde::Deserialize>::deserialize(deserializer)
+                    .map(|value| value.map(|value| value.0))
+            }
+        }
+
+        impl<T: LenientField> LenientField for ::std::vec::Vec<T> {
+            fn deserialize_lenient<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+                <::std::vec::Vec<LenientFieldValue<T>> as ::serde::Deserialize>::deserialize(deserializer)
+                    .map(|values| ::std::iter::Iterator::collect(::std::iter::Iterator::map(::std::iter::IntoIterator::into_iter(values), |value| value.0)))
+            }
+        }
+
+        /// A value deserialized leniently, inside an option or a list.
+        struct LenientFieldValue<T>(T);
+
+        impl<'de, T: LenientField> ::serde::Deserialize<'de> for LenientFieldValue<T> {
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+                T::deserialize_lenient(deserializer).map(LenientFieldValue)
+            }
+        }
+
+        fn deserialize_lenient<'de, D: ::serde::Deserializer<'de>, T: LenientField>(deserializer: D) -> ::std::result::Result<T, D::Error> {
+            T::deserialize_lenient(deserializer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::Schema;
+
+    #[test]
+    fn built_in_scalar_fields_are_lenient() {
+        let schema = Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        let count = FieldType::Vector(Box::new(FieldType::Optional(Box::new(FieldType::Named(
+            "Int",
+        )))));
+
+        assert!(field_attribute(&context, &count).is_empty());
+
+        context.lenient_scalars = true;
+        assert_eq!(
+            field_attribute(&context, &count).to_string(),
+            "# [ serde ( deserialize_with = \"deserialize_lenient\" ) ]"
+        );
+        assert!(field_attribute(&context, &FieldType::Named("String")).is_empty());
+        assert!(field_attribute(&context, &FieldType::Named("DateTime")).is_empty());
+    }
+}
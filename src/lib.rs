@@ -7,16 +7,22 @@
 
 #[macro_use]
 extern crate failure;
+extern crate flate2;
+extern crate globset;
 extern crate graphql_parser;
 extern crate heck;
 extern crate itertools;
 #[macro_use]
 extern crate lazy_static;
+extern crate miniz_oxide;
 extern crate proc_macro;
 extern crate proc_macro2;
+extern crate reqwest;
+extern crate ruzstd;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+#[macro_use]
 extern crate serde_json;
 extern crate syn;
 #[macro_use]
@@ -26,28 +32,101 @@ use proc_macro2::TokenStream;
 use syn::Visibility;
 
 mod codegen;
+/// Converting schemas between SDL and introspection JSON.
+pub mod conversion;
+mod defaults;
 /// Deprecation-related code
 pub mod deprecation;
-mod introspection_response;
+/// The sets of traits derived on every generated type.
+pub mod derive_profile;
+/// Printing the generated code, for debugging.
+pub mod dump;
+/// Plausible JSON responses of operations, for stub servers in tests.
+pub mod fixtures;
+/// Callbacks to post-process the generated code.
+pub mod hooks;
+/// Holding nested response structs behind a `Box` or an `Arc`.
+pub mod indirection;
+/// The types of the response to the introspection query, to run it with your own HTTP client.
+pub mod introspection_response;
+/// Prefixing the generated names, for operations with the same name in different query files.
+pub mod namespace;
+/// Generating operations only when a cargo feature is enabled.
+pub mod operation_features;
+/// The lock file of the operations the crate can send.
+pub mod operation_lock;
 mod query;
+/// Embedding the document of the operations, compressed or not, or loading it at runtime.
+pub mod query_text;
+/// Named schemas, for crates that talk to several GraphQL APIs.
+pub mod registry;
 /// Contains the [Schema] type and its implementation.
 pub mod schema;
+/// Telling SDL schemas from introspection responses.
+pub mod schema_format;
+/// Resolver traits for the servers of a schema.
+pub mod server_stubs;
+/// Whether the enums of unions have a fallback for new members.
+pub mod union_mode;
 
+mod arbitrary;
+mod big_numbers;
+mod bytes;
+mod cassettes;
+mod client_fields;
+mod comment_options;
+mod compression;
 mod constants;
+mod directives;
 mod enums;
+mod error_paths;
+mod executors;
+mod federation;
 mod field_type;
+mod fragment_library;
 mod fragments;
+mod get_requests;
+mod globs;
+mod graphql_values;
+mod incremental;
 mod inputs;
 mod interfaces;
+mod json_schema;
+mod json_validation;
+mod lenient;
+mod list_iterators;
+mod mocks;
+mod no_std;
 mod objects;
 mod operations;
+mod pretty_print;
+mod pruning;
+mod redaction;
+mod refetch;
 mod scalars;
+mod schema_cache;
+mod schema_diff;
+mod sdl;
 mod selection;
+mod selection_metadata;
+mod serde_adapters;
 mod shared;
+mod spans;
+mod sse;
+/// Statistics about the generated code.
+pub mod stats;
+mod strict_mode;
+mod string_literals;
+mod tracking;
+mod typename_enums;
 mod unions;
+/// Query validation, reporting all the problems in a query document at once.
+pub mod validation;
 mod variables;
+/// Walking the operations and fragments of a query document, with the schema types of the selected fields.
+pub mod visitor;
 
-use heck::SnakeCase;
+use heck::{ShoutySnakeCase, SnakeCase};
 
 #[cfg(test)]
 mod tests;
@@ -57,26 +136,200 @@ type CacheMap<T> =
     ::std::sync::Mutex<::std::collections::hash_map::HashMap<::std::path::PathBuf, T>>;
 
 lazy_static! {
-    static ref SCHEMA_CACHE: CacheMap<String> = CacheMap::default();
+    static ref SCHEMA_CACHE: CacheMap<::std::sync::Arc<schema::ParsedSchema>> = CacheMap::default();
     static ref QUERY_CACHE: CacheMap<(String, graphql_parser::query::Document)> =
         CacheMap::default();
 }
 
+/// The standard introspection query. Its response deserializes to [introspection_response::IntrospectionResponse].
+pub const INTROSPECTION_QUERY: &str = include_str!("introspection_query.graphql");
+
 /// Used to configure code generation.
 #[derive(Clone)]
 pub struct GraphQLClientDeriveOptions {
     /// Name of the operation we want to generate code for. If it does not match, we use all queries.
     pub operation_name: Option<String>,
-    /// The name of implemention target struct.
+    /// The name of implemention target struct. Modules with several operations implement `GraphQLQuery` for a struct named after each operation instead.
     pub struct_name: Option<String>,
     /// The module that contains queries.
     pub module_name: Option<String>,
     /// Comma-separated list of additional traits we want to derive.
     pub additional_derives: Option<String>,
+    /// The traits derived on every generated type, on top of `additional_derives`.
+    pub derive_profile: derive_profile::DeriveProfile,
     /// The deprecation strategy to adopt.
     pub deprecation_strategy: Option<deprecation::DeprecationStrategy>,
+    /// Comma-separated list of per-field deprecation strategies overriding `deprecation_strategy`, like `User.legacyId=allow`.
+    pub deprecation_overrides: Option<String>,
+    /// Comma-separated list of Rust types for the custom scalars with a `@specifiedBy` directive, keyed by its URL, like `https://tools.ietf.org/html/rfc3339=chrono::DateTime<chrono::Utc>`. Other scalars are aliases of a type defined next to the query struct.
+    pub specified_by_overrides: Option<String>,
+    /// Comma-separated list of representations for big-number scalars, like `BigInt=i128, Long=u64`. The representations are `i128`, `u64`, `string` (a newtype around the digits, deserialized from JSON strings and numbers) and `number` (`serde_json::Number`, enable its `arbitrary_precision` feature to keep every digit).
+    pub big_number_scalars: Option<String>,
+    /// Comma-separated list of scalars holding base64-encoded binary data, like `Bytes, Base64`. They are generated as newtypes around `Vec<u8>`, (de)serialized from base64 strings.
+    pub bytes_scalars: Option<String>,
     /// target module visibility.
     pub module_visibility: Visibility,
+    /// Generate a `*Builder` for each input object.
+    pub input_builders: bool,
+    /// SDL file with the types of the `@client` fields, like `extend type Query { isLoggedIn: Boolean! }`.
+    pub client_schema_path: Option<std::path::PathBuf>,
+    /// Generate the GraphQL over Server-Sent Events envelope types for subscriptions.
+    pub sse_subscriptions: bool,
+    /// Generate `Variables::to_get_query_string()` for the queries, the URL-encoded query string of a GET request running them as in the GraphQL over HTTP spec, for responses CDNs can cache. Needs `serde_json` in the crate using the generated code.
+    pub get_requests: bool,
+    /// Generate `to_graphql_value()` on the variables, input objects and enums, their `graphql_parser::query::Value`, for tools building documents programmatically, like batching operations into one document. Needs `graphql_parser` and `serde_json` in the crate using the generated code. Custom scalars go through their `Serialize` implementation, without the `serde(with)` adapters of the fields.
+    pub graphql_values: bool,
+    /// Generate a `Cassette` type per operation, a response of the server recorded with the hash and the variables of the request, to write to files and replay in tests. Needs `serde_json` in the crate using the generated code.
+    pub cassettes: bool,
+    /// Generate a `Mock` trait implemented by the response types, to fabricate responses in tests. Custom scalars are mocked with their `Default` implementation.
+    pub mocks: bool,
+    /// Derive `proptest_derive::Arbitrary`, and the `Debug` it requires, for the response data, enums, inputs and variables, for property-based tests. Custom scalars are generated from their `Default` implementation.
+    pub arbitrary: bool,
+    /// Derive `specta::Type` for the response data, enums, inputs and variables, so frontends can get TypeScript definitions matching them. Custom scalars must implement it too.
+    pub specta: bool,
+    /// Implement `Default` for the response data and the structs and enums of the responses, to build placeholder values and test fixtures without spelling out whole trees. Custom scalars and the string type of the responses must implement `Default` too.
+    pub default_impls: bool,
+    /// Comma-separated list of the variants `Default` picks for enums, unions and interfaces, like `Episode=JEDI, SearchResult=Human`. The other enums default to their first variant.
+    pub default_variants: Option<String>,
+    /// Generate a `{field}_iter()` method next to each response field holding a nullable list or a list of nullable items, iterating over the items that are present, nested lists flattened.
+    pub list_iterators: bool,
+    /// Generate `ResponseData::validate_json`, reporting every mismatch between a JSON document and the response types with its JSON pointer.
+    pub json_validation: bool,
+    /// Generate `build_query_with_extensions` next to `build_query`, building a request body with `extensions` for the server, like tracing IDs or a persisted query hash.
+    pub query_extensions: bool,
+    /// Generate a `VARIABLES` constant describing the variables of each operation, with their names, GraphQL types and whether they have a default, for middleware and forms that don't know the operation.
+    pub variable_specs: bool,
+    /// Generate a `PartialData` struct for each operation, classifying the top-level fields of a raw response by how its `errors` affected them, with the `ResponseDataPath` enum it refers to. Needs `serde_json` in the crate using the generated code.
+    pub partial_data: bool,
+    /// Generate a `ResponseDataPath` enum of the top-level fields of each operation, to match the first segment of `errors[].path` in responses.
+    pub error_paths: bool,
+    /// Generate a `PrettyPrint` trait implemented by the response types, and `Display` for the response data with it, printing the responses nested like the selection, with the names of the fields. Custom scalars are printed with their `Debug` implementation.
+    pub pretty_print: bool,
+    /// Hold the structs of nested selections behind a `Box` or an `Arc`, so the response data stays small to move and big subtrees can be shared. Only the structs whose selection has at least `min_fields` fields, counting nested ones, are held this way.
+    pub nested_indirection: Option<indirection::Indirection>,
+    /// The Rust type of the `String` fields of the responses, like `::std::borrow::Cow<'static, str>`, `::std::sync::Arc<str>` or `::smol_str::SmolStr`, to cut allocations in applications that mostly read responses. It must deserialize from a JSON string and convert from `&str`.
+    pub response_string_type: Option<String>,
+    /// Comma-separated list of `serde(with)` modules for the fields of a scalar or enum type, or for one field, like `Timestamp=::serde_with::As::<Option<::serde_with::TimestampMilliSeconds>>, Order.total=crate::number_as_string`. The module (de)serializes the whole field, options and lists included, and the adapter of a field takes precedence over the one of its type. Fields of `VariablesRef` are not covered.
+    pub serde_adapters: Option<String>,
+    /// Deserialize the response fields of the `Int`, `Float`, `Boolean` and `ID` types from the string form of their values too, like `"42"` or `"true"`, and IDs from integers, for servers and gateways sending them that way.
+    pub lenient_scalars: bool,
+    /// Put `#[serde(default)]` on every nullable response field, so responses leaving out null fields deserialize even when the fields have a `serde(with)` adapter or are deserialized leniently, which otherwise makes them required.
+    pub nullable_defaults: bool,
+    /// Put `#[serde(deny_unknown_fields)]` on the response structs, so contract tests catch servers returning fields that were not selected. serde does not support it along with `flatten`, so the structs spreading fragments, the fragments themselves and the structs of interfaces are left lenient.
+    pub deny_unknown_fields: bool,
+    /// Comma-separated list of the types whose fields, or of the single fields, masked as `[redacted]` in `Debug` and pretty-printed output, like `AccessToken, User.email`, so tokens and personal data don't end up in logs. Fields can also be marked with the `@sensitive` directive in the query, which is not sent to the server.
+    pub sensitive_fields: Option<String>,
+    /// Whether the enums of unions and interfaces are exhaustive, or have an `Unknown` variant for the members added to the schema later and are `#[non_exhaustive]`.
+    pub union_mode: union_mode::UnionMode,
+    /// Generate a `{Type}Typename` enum of the possible `__typename` values of each interface and union used in the document, with `as_str()` and `FromStr`, to branch on type names without string literals.
+    pub typename_enums: bool,
+    /// Also generate a `VariablesRef<'a>` struct, borrowing strings, lists and other large values instead of owning them, to serialize the variables without cloning them.
+    pub borrowed_variables: bool,
+    /// Report statistics about the generated code, like the number of structs and tokens: `stderr`, or a file to append them to as JSON lines.
+    pub codegen_stats: Option<String>,
+    /// The predicate of a `cfg` attribute put on the generated module and trait implementations, like `feature = "admin-api"`.
+    pub cfg: Option<String>,
+    /// Generate code for `#![no_std]` crates, using `core` and `alloc` paths instead of `std` ones. The crate needs `extern crate alloc;`, and `serde_json` with its `alloc` feature.
+    pub no_std: bool,
+    /// The span of the derive attribute. Every generated item is given this span, so compiler errors and "go to definition" on generated types land on the attribute.
+    pub span: Option<Span>,
+    /// Make the generated code include the bytes of the query, schema and fragment files, so cargo rebuilds it when they are edited. The files are referred to by absolute path, so this is for the derive rather than generated files that are checked in.
+    pub track_files: bool,
+    /// The format of the schema file. By default it is detected from the contents.
+    pub schema_format: Option<schema_format::SchemaFormat>,
+    /// Prefix the generated module, and the query structs when there is no struct name, so operations with the same name in different query files can be generated side by side.
+    pub namespace: Option<namespace::Namespace>,
+    /// The cargo features of the crate using the generated code that the operations of the document are generated under, like `GetUser=users, ListPosts=posts`, so binaries using a few operations of a shared document only compile those. Operations left out are always generated.
+    pub operation_features: Option<operation_features::OperationFeatures>,
+    /// More query files, or patterns like `graphql/fragments/*.graphql`, appended to the document of the query file before code generation. Operations can then spread fragments defined in other files.
+    pub additional_query_paths: Vec<String>,
+    /// A pattern like `graphql/fragments/**/*.graphql` matching a library of fragment files. The fragments the query spreads are added to the document, the others are left out.
+    pub fragments_path: Option<String>,
+    /// How deeply selection sets may be nested, 64 by default. Deeper selections are reported with their path rather than generated.
+    pub max_selection_depth: Option<usize>,
+    /// Callbacks inspecting or changing each generated item before the module is assembled.
+    pub hooks: Option<::std::sync::Arc<dyn hooks::CodegenHooks>>,
+    /// A directory to write JSON Schema documents for the variables and the response data of each operation to, as `<operation>.variables.schema.json` and `<operation>.response.schema.json`.
+    pub json_schema_directory: Option<::std::path::PathBuf>,
+    /// Generate structs like `ProductRepresentation` for the entities the operations fetch with the `_entities` field of a federation subgraph, serializing to the representations it expects. The key fields come from the `@key` directives of an SDL schema.
+    pub federation_representations: bool,
+    /// Generate a Relay-style refetch operation, like `HeroNameRefetchQuery`, for each fragment on a type implementing the interface of a `node(id: ID!)` field of the query type. Each one has its own module next to the module of the query, like `hero_name_refetch_query`.
+    pub refetch_queries: bool,
+    /// Generate the `SELECTION` constant, describing the fields the operation selects with their aliases and types, for logging and metrics.
+    pub selection_metadata: bool,
+    /// A lock file recording the operations with a hash of their document. The build fails when it is out of date, unless `GRAPHQL_CLIENT_UPDATE_LOCK` is set to update it.
+    pub operation_lock: Option<::std::path::PathBuf>,
+    /// The path of a function the generated `execute` functions call once each request is done, like `crate::metrics::record_graphql_call`, so every GraphQL call of the application can be observed in one place. It is called with the operation name, the time the call took and whether it succeeded, as a `fn(&'static str, ::std::time::Duration, bool)`. Only used with the `reqwest` feature.
+    pub call_hook: Option<String>,
+    /// Whether the document of the operations is embedded as the `QUERY` constant, loaded at runtime by a function from its `QUERY_HASH` to keep it out of the binary, or compressed in the binary and decompressed at first use.
+    ///
+    /// The compressed document is decompressed by the generated code with `::miniz_oxide`, so the crate using it needs a `miniz_oxide = "0.8"` dependency of its own.
+    pub query_text: query_text::QueryText,
+}
+
+impl Default for GraphQLClientDeriveOptions {
+    fn default() -> Self {
+        GraphQLClientDeriveOptions {
+            operation_name: None,
+            struct_name: None,
+            module_name: None,
+            additional_derives: None,
+            derive_profile: derive_profile::DeriveProfile::default(),
+            deprecation_strategy: None,
+            deprecation_overrides: None,
+            specified_by_overrides: None,
+            big_number_scalars: None,
+            bytes_scalars: None,
+            module_visibility: Visibility::Inherited,
+            input_builders: false,
+            client_schema_path: None,
+            sse_subscriptions: false,
+            get_requests: false,
+            graphql_values: false,
+            cassettes: false,
+            mocks: false,
+            arbitrary: false,
+            specta: false,
+            default_impls: false,
+            default_variants: None,
+            list_iterators: false,
+            json_validation: false,
+            query_extensions: false,
+            variable_specs: false,
+            partial_data: false,
+            error_paths: false,
+            pretty_print: false,
+            nested_indirection: None,
+            response_string_type: None,
+            serde_adapters: None,
+            lenient_scalars: false,
+            nullable_defaults: false,
+            deny_unknown_fields: false,
+            sensitive_fields: None,
+            union_mode: union_mode::UnionMode::default(),
+            typename_enums: false,
+            borrowed_variables: false,
+            codegen_stats: None,
+            cfg: None,
+            no_std: false,
+            span: None,
+            track_files: true,
+            schema_format: None,
+            namespace: None,
+            operation_features: None,
+            additional_query_paths: Vec::new(),
+            fragments_path: None,
+            max_selection_depth: None,
+            hooks: None,
+            json_schema_directory: None,
+            federation_representations: false,
+            refetch_queries: false,
+            selection_metadata: false,
+            operation_lock: None,
+            call_hook: None,
+            query_text: query_text::QueryText::default(),
+        }
+    }
 }
 
 /// Generates the code for a Rust module given a query, a schema and options.
@@ -87,26 +340,23 @@ pub fn generate_module_token_stream(
 ) -> Result<TokenStream, failure::Error> {
     let options = options.unwrap();
 
-    let module_visibility = options.module_visibility.clone();
-    let response_derives = options.additional_derives.clone();
-
-    // The user can determine what to do about deprecations.
-    let deprecation_strategy = options.deprecation_strategy.clone().unwrap_or_default();
+    let query_path_display = query_path.display().to_string();
 
     // We need to qualify the query with the path to the crate it is part of
     let (query_string, query) = {
         let mut lock = QUERY_CACHE.lock().expect("query cache is poisoned");
-        match lock.entry(query_path) {
+        match lock.entry(query_path.clone()) {
             ::std::collections::hash_map::Entry::Occupied(o) => o.get().clone(),
             ::std::collections::hash_map::Entry::Vacant(v) => {
                 let query_string = read_file(v.key())?;
-                let query = graphql_parser::parse_query(&query_string)?;
+                let query = string_literals::parse_query(&query_string)?;
                 v.insert((query_string, query)).clone()
             }
         }
     };
 
     // Determine which operation we are generating code for. This will be used in operationName.
+    // Only the operations of the query file are generated, the additional files are for the fragments they define.
     let operations = if options.operation_name.is_some() {
         let op = codegen::select_operation(&query, &(options.operation_name.clone().unwrap()));
         if op.is_some() {
@@ -118,35 +368,113 @@ pub fn generate_module_token_stream(
         codegen::all_operations(&query)
     };
 
-    let schema_extension = schema_path
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("INVALID");
+    // Options set in the query file, next to the operations.
+    let comment_options = comment_options::operation_comment_options(&query_string, &query)?;
+    let operation_names: Vec<&str> = operations
+        .iter()
+        .map(|operation| operation.name.as_str())
+        .collect();
+    let module_visibility = comment_options::module_visibility(
+        &comment_options,
+        &operation_names,
+        &options.module_visibility,
+    )?
+    .clone();
 
-    // Check the schema cache.
-    let schema_string: String = {
-        let mut lock = SCHEMA_CACHE.lock().expect("schema cache is poisoned");
-        match lock.entry(schema_path.to_path_buf()) {
-            ::std::collections::hash_map::Entry::Occupied(o) => o.get().clone(),
-            ::std::collections::hash_map::Entry::Vacant(v) => {
-                let schema_string = read_file(v.key())?;
-                v.insert(schema_string).to_string()
-            }
+    let mut tracked_files = vec![query_path.clone()];
+    // The derive resolves the schema path against the crate root; it may be the name of a schema of the registry instead.
+    let crate_root = ::std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(std::path::PathBuf::from)
+        .map_or_else(::std::env::current_dir, Ok)?;
+    let schema_path = &registry::resolve_schema(schema_path, &crate_root, &mut tracked_files)?;
+    tracked_files.push(schema_path.clone());
+    let merged_query = if options.additional_query_paths.is_empty() {
+        None
+    } else {
+        Some(merge_query_files(
+            &query_path,
+            query_string.clone(),
+            &options.additional_query_paths,
+            &mut tracked_files,
+        )?)
+    };
+    let (query_string, query) = match merged_query {
+        Some((ref merged_string, ref merged)) => (merged_string, merged),
+        None => (&query_string, &query),
+    };
+    let with_library = match options.fragments_path {
+        Some(ref pattern) => Some(fragment_library::include_spread_fragments(
+            pattern,
+            query_string,
+            query,
+            &mut tracked_files,
+        )?),
+        None => None,
+    };
+    let (query_string, query) = match with_library {
+        Some((ref library_string, ref library_query)) => (library_string, library_query),
+        None => (query_string, query),
+    };
+
+    let parsed_schema = load_schema(schema_path, options.schema_format)?;
+    let client_schema = match options.client_schema_path {
+        Some(ref path) => {
+            tracked_files.push(path.clone());
+            Some(sdl::parse_schema(&read_file(path)?)?)
+        }
+        None => None,
+    };
+    // Only the types the query can reach are built, so huge schemas stay cheap.
+    let type_graph = pruning::TypeGraph::new(&parsed_schema, client_schema.as_ref());
+    let reachable = type_graph.reachable(
+        query,
+        pruning::Extent {
+            refetch_queries: options.refetch_queries,
+            possible_types: options.json_schema_directory.is_some(),
+        },
+    );
+    let mut schema = schema::Schema::reachable(&parsed_schema, &reachable.types);
+    if let Some(ref client_schema) = client_schema {
+        schema.ingest_reachable_document(client_schema, &reachable.types);
+    }
+    let types_pruned = type_graph
+        .type_count()
+        .saturating_sub(stats::type_count(&schema));
+    let argument_diagnostics = validation::argument_diagnostics(query, &schema);
+    if !argument_diagnostics.is_empty() {
+        return Err(format_err!(
+            "Invalid arguments in {}:\n{}",
+            query_path_display,
+            argument_diagnostics
+                .iter()
+                .map(|diagnostic| match diagnostic.position {
+                    Some(_) => format!("{}:{}", query_path_display, diagnostic),
+                    None => format!("{}: {}", query_path_display, diagnostic),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        ));
+    }
+
+    // The possible types of the interfaces are reachable when the JSON Schemas are written.
+    if let Some(ref directory) = options.json_schema_directory {
+        for operation in &operations {
+            json_schema::write_operation_schemas(directory, &schema, query, operation)?;
         }
+    }
+
+    let typename_enums = if options.typename_enums {
+        Some(typename_enums::typename_enums(&schema, query)?)
+    } else {
+        None
     };
 
-    let parsed_schema = match schema_extension {
-                        "graphql" | "gql" => {
-                            let s = graphql_parser::schema::parse_schema(&schema_string)?;
-                            schema::ParsedSchema::GraphQLParser(s)
-                        }
-                        "json" => {
-                            let parsed: introspection_response::IntrospectionResponse = ::serde_json::from_str(&schema_string)?;
-                            schema::ParsedSchema::Json(parsed)
-                        }
-                        extension => panic!("Unsupported extension for the GraphQL schema: {} (only .json and .graphql are supported)", extension)
-                    };
-    let schema = schema::Schema::from(&parsed_schema);
+    let refetch_queries = if options.refetch_queries {
+        refetch::refetch_queries(&schema, query)?
+    } else {
+        Vec::new()
+    };
+    let fragments_required = reachable.fragments_required;
 
     let struct_name = if options.struct_name.is_some() {
         Some(Ident::new(
@@ -157,94 +485,319 @@ pub fn generate_module_token_stream(
         None
     };
 
-    let module_name = Ident::new(
-        options
-            .module_name
-            .as_ref()
-            .unwrap_or_else(|| options.operation_name.as_ref().unwrap())
-            .to_snake_case()
-            .as_str(),
-        Span::call_site(),
-    );
+    let module_name = options
+        .module_name
+        .as_ref()
+        .unwrap_or_else(|| options.operation_name.as_ref().unwrap())
+        .to_snake_case();
+    let module_name = match options.namespace {
+        Some(ref namespace) => {
+            namespace.module_name(options.struct_name.as_deref(), &module_name)?
+        }
+        None => module_name,
+    };
+    let module_name = Ident::new(&module_name, Span::call_site());
+
+    let operation_features = options.operation_features.clone().unwrap_or_default();
+    operation_features.check(
+        &codegen::all_operations(query)
+            .iter()
+            .map(|operation| operation.name.as_str())
+            .collect::<Vec<_>>(),
+    )?;
 
     let operation_count = operations.len();
 
     let multiple_operations = operation_count > 1;
 
     let mut schema_and_operations = Vec::with_capacity(operation_count);
+    // The enums, input objects, scalars and fragments several operations use are generated once, as the first operation using them generates them.
+    let mut shared_items = quote!();
+    let mut shared_keys = ::std::collections::BTreeSet::new();
 
     for operation in &operations {
-        let schema_output = codegen::response_for_query(
+        let operation_options = match comment_options.get(&operation.name) {
+            Some(overrides) => overrides.apply(&options),
+            None => options.clone(),
+        };
+        let items = codegen::operation_items(
             &schema.clone(),
-            &query.clone(),
+            query,
             &operation,
-            response_derives.clone(),
-            deprecation_strategy.clone(),
+            &operation_options,
             multiple_operations,
         )?;
-        let operation_name = Ident::new(operation.name.as_str(), Span::call_site());
-        schema_and_operations.push((schema_output, operation_name, operation.name.as_str()));
+        for (key, tokens) in items.shared {
+            if shared_keys.insert(key) {
+                shared_items.extend(tokens);
+            }
+        }
+        let schema_output = items.own;
+        let namespaced_struct_name = options
+            .namespace
+            .as_ref()
+            .and_then(|namespace| namespace.struct_name(&operation.name))
+            .map(|name| Ident::new(&name, Span::call_site()));
+        // The operations of a module with several operations can't share a struct.
+        let operation_struct_name = struct_name
+            .clone()
+            .filter(|_| !multiple_operations)
+            .or(namespaced_struct_name)
+            .unwrap_or_else(|| Ident::new(&operation.name, Span::call_site()));
+        schema_and_operations.push((
+            schema_output,
+            operation_struct_name,
+            operation.name.as_str(),
+            operation_features.cfg_attribute(&operation.name),
+        ));
+    }
+
+    let server_query = client_fields::server_query(query_string, query);
+    if let Some(ref path) = options.operation_lock {
+        let locked = operations
+            .iter()
+            .map(|operation| (operation.name.as_str(), server_query.as_str()))
+            .chain(
+                refetch_queries
+                    .iter()
+                    .map(|refetch| (refetch.name.as_str(), refetch.query_string.as_str())),
+            )
+            .map(|(name, query)| {
+                (
+                    format!("{}::{}", module_name, name),
+                    operation_lock::LockedOperation::new(query),
+                )
+            })
+            .collect();
+        operation_lock::check_or_update(path, locked)?;
+        tracked_files.push(path.clone());
+    }
+
+    // Items shared by all the operations of the module.
+    let mut common_items = quote!();
+    if options.mocks {
+        common_items.extend(mocks::mock_trait());
+    }
+    if options.json_validation {
+        common_items.extend(json_validation::validation_trait());
+    }
+    if options.get_requests {
+        common_items.extend(get_requests::url_encode_helper());
+    }
+    if options.graphql_values {
+        common_items.extend(graphql_values::scalar_helper());
+    }
+    if options.cassettes {
+        common_items.extend(cassettes::request_hash_helper());
+    }
+    if options.pretty_print {
+        common_items.extend(pretty_print::pretty_print_trait());
+    }
+    if let Some(typename_enums) = typename_enums {
+        common_items.extend(typename_enums);
+    }
+    if multiple_operations {
+        common_items.extend(operations::operations_enum(
+            &operations,
+            &operation_features,
+            &options.query_text,
+        ));
+    }
+    if options.track_files {
+        common_items.extend(tracking::tracked_files(tracked_files)?);
+    }
+    common_items.extend(shared_items);
+
+    let cfg_attribute = match options.cfg {
+        Some(ref predicate) => {
+            let predicate: TokenStream = predicate
+                .parse()
+                .map_err(|_| format_err!("Invalid cfg predicate: `{}`", predicate))?;
+            quote!(#[cfg(#predicate)])
+        }
+        None => quote!(),
+    };
+    if let Some(ref call_hook) = options.call_hook {
+        syn::parse_str::<syn::Path>(call_hook).map_err(|_| {
+            format_err!(
+                "Invalid call hook: `{}`. Expected the path of a function, like `crate::metrics::record_graphql_call`.",
+                call_hook
+            )
+        })?;
     }
 
-    let result = build_module_token_stream(
+    let mut result = build_module_token_stream(
+        &cfg_attribute,
         &module_visibility,
         &module_name,
-        &struct_name,
-        &query_string,
+        &server_query,
+        &options,
+        common_items,
         schema_and_operations,
     );
+    for refetch_query in &refetch_queries {
+        result.extend(build_refetch_module(
+            &cfg_attribute,
+            &module_visibility,
+            &schema,
+            refetch_query,
+            &options,
+        )?);
+    }
+    if options.no_std {
+        if options.query_text == query_text::QueryText::Compressed {
+            return Err(format_err!(
+                "The compressed query text is decompressed with `std`, it is not available in no_std code"
+            ));
+        }
+        result = no_std::alloc_paths(result)?;
+    }
+    if let Some(span) = options.span {
+        result = spans::respan(result, span);
+    }
+
+    dump::dump_if_requested(&module_name, &result);
+    if let Some(ref destination) = options.codegen_stats {
+        stats::CodegenStats::new(
+            module_name.to_string(),
+            query_path_display,
+            &result,
+            fragments_required,
+            types_pruned,
+        )
+        .report(destination);
+    }
 
     Ok(result)
 }
 
 fn build_module_token_stream(
+    cfg_attribute: &TokenStream,
     module_visibility: &syn::Visibility,
     module_name: &Ident,
-    struct_name: &Option<Ident>,
     query_string: &str,
-    schema_and_operations: Vec<(TokenStream, Ident, &str)>,
+    options: &GraphQLClientDeriveOptions,
+    common_items: TokenStream,
+    schema_and_operations: Vec<(TokenStream, Ident, &str, TokenStream)>,
 ) -> TokenStream {
+    let mut common_items = common_items;
+    if options.query_extensions {
+        common_items.extend(query_body_with_extensions());
+    }
     let mut schema_token_streams = vec![];
     let mut trait_token_streams = vec![];
     let multiple_operations = schema_and_operations.len() > 1;
-    for (schema_output, operation_name, operation_name_literal) in schema_and_operations {
+    for (schema_output, struct_name, operation_name_literal, feature_attribute) in
+        schema_and_operations
+    {
         let (schema_token_stream, trait_token_stream) = build_query_struct_token_stream(
+            &quote!(#cfg_attribute #feature_attribute),
             &module_name,
-            struct_name.clone(),
+            struct_name,
             &schema_output,
-            &operation_name,
             operation_name_literal,
             multiple_operations,
+            options,
         );
-        schema_token_streams.push(schema_token_stream);
+        schema_token_streams.push(operation_features::gated_items(
+            &feature_attribute,
+            operation_name_literal,
+            schema_token_stream,
+        ));
         trait_token_streams.push(trait_token_stream);
     }
 
     merge_with_common_token_stream(
+        cfg_attribute,
         &module_visibility,
         &module_name,
-        query_string,
+        options.query_text.items(query_string),
+        common_items,
         schema_token_streams,
         trait_token_streams,
     )
 }
 
+/// The module of a refetch operation, next to the module of the query, and the struct implementing `GraphQLQuery` for it.
+fn build_refetch_module(
+    cfg_attribute: &TokenStream,
+    module_visibility: &syn::Visibility,
+    schema: &schema::Schema,
+    refetch_query: &refetch::RefetchQuery,
+    options: &GraphQLClientDeriveOptions,
+) -> Result<TokenStream, failure::Error> {
+    let operation = codegen::select_operation(&refetch_query.document, &refetch_query.name)
+        .expect("refetch operation");
+    let schema_output = codegen::response_for_query(
+        &schema.clone(),
+        &refetch_query.document,
+        &operation,
+        options,
+        false,
+    )?;
+
+    let module_name = match options.namespace {
+        Some(ref namespace) => namespace.module_name(
+            options.struct_name.as_deref(),
+            &refetch_query.name.to_snake_case(),
+        )?,
+        None => refetch_query.name.to_snake_case(),
+    };
+    let struct_name = options
+        .namespace
+        .as_ref()
+        .and_then(|namespace| namespace.struct_name(&refetch_query.name))
+        .unwrap_or_else(|| refetch_query.name.clone());
+    let struct_name = Ident::new(&struct_name, Span::call_site());
+    let mut common_items = quote!();
+    if options.graphql_values {
+        common_items.extend(graphql_values::scalar_helper());
+    }
+    if options.cassettes {
+        common_items.extend(cassettes::request_hash_helper());
+    }
+    let module = build_module_token_stream(
+        cfg_attribute,
+        module_visibility,
+        &Ident::new(&module_name, Span::call_site()),
+        &client_fields::server_query(&refetch_query.query_string, &refetch_query.document),
+        options,
+        common_items,
+        vec![(
+            schema_output,
+            struct_name.clone(),
+            &refetch_query.name,
+            quote!(),
+        )],
+    );
+
+    Ok(quote! {
+        #cfg_attribute
+        #module_visibility struct #struct_name;
+
+        #module
+    })
+}
+
 fn merge_with_common_token_stream(
+    cfg_attribute: &TokenStream,
     module_visibility: &syn::Visibility,
     module_name: &Ident,
-    query_string: &str,
+    query_items: TokenStream,
+    common_items: TokenStream,
     schema_token_streams: Vec<TokenStream>,
     trait_token_streams: Vec<TokenStream>,
 ) -> TokenStream {
     quote!(
+        #cfg_attribute
         #module_visibility mod #module_name {
             #![allow(non_camel_case_types)]
             #![allow(non_snake_case)]
             #![allow(dead_code)]
 
-            use serde;
 
-            pub const QUERY: &'static str = #query_string;
+            #query_items
+            #common_items
             #(#schema_token_streams)*
         }
         #(#trait_token_streams)*
@@ -252,19 +805,14 @@ fn merge_with_common_token_stream(
 }
 
 fn build_query_struct_token_stream(
+    cfg_attribute: &TokenStream,
     module_name: &Ident,
-    struct_name: Option<Ident>,
+    struct_name: Ident,
     schema_output: &TokenStream,
-    operation_name: &Ident,
     operation_name_literal: &str,
     multiple_operations: bool,
+    options: &GraphQLClientDeriveOptions,
 ) -> (TokenStream, TokenStream) {
-    let struct_name = if struct_name.is_some() {
-        struct_name.unwrap()
-    } else {
-        operation_name.clone()
-    };
-
     let (respons_data_struct_name, variables_struct_name) = if multiple_operations {
         (
             Ident::new(
@@ -272,7 +820,7 @@ fn build_query_struct_token_stream(
                 Span::call_site(),
             ),
             Ident::new(
-                format!("{}Variables", operation_name).as_str(),
+                format!("{}Variables", operation_name_literal).as_str(),
                 Span::call_site(),
             ),
         )
@@ -283,11 +831,78 @@ fn build_query_struct_token_stream(
         )
     };
 
+    let query = options.query_text.expression();
+    let operation_name_const = if multiple_operations {
+        Ident::new(
+            &format!(
+                "{}_OPERATION_NAME",
+                operation_name_literal.to_shouty_snake_case()
+            ),
+            Span::call_site(),
+        )
+    } else {
+        Ident::new("OPERATION_NAME", Span::call_site())
+    };
     let schema_token = quote!(
-        pub const OPERATION_NAME: &'static str = #operation_name_literal;
+        pub const #operation_name_const: &'static str = #operation_name_literal;
         #schema_output
     );
+    let execute = executors::reqwest_execute(
+        &struct_name,
+        module_name,
+        &variables_struct_name,
+        &respons_data_struct_name,
+        options.call_hook.as_deref(),
+    );
+    // The executor is empty when it is not enabled, it must not take the attribute of the next item.
+    let execute = if execute.is_empty() {
+        execute
+    } else {
+        quote!(#cfg_attribute #execute)
+    };
+    let variable_specs = if options.variable_specs {
+        let (variable_spec_name, variables_const_name) =
+            variables::variable_specs_names(if multiple_operations {
+                operation_name_literal
+            } else {
+                ""
+            });
+        quote! {
+            /// The variables of the operation, in declaration order.
+            pub const VARIABLES: &'static [#module_name::#variable_spec_name] = #module_name::#variables_const_name;
+        }
+    } else {
+        quote!()
+    };
+    let build_query_with_extensions = if options.query_extensions {
+        quote! {
+            /// The body of the request, with `extensions` for the server, like tracing IDs or a persisted query hash.
+            pub fn build_query_with_extensions<E: ::serde::Serialize>(
+                variables: #module_name::#variables_struct_name,
+                extensions: E,
+            ) -> #module_name::QueryBodyWithExtensions<#module_name::#variables_struct_name, E> {
+                #module_name::QueryBodyWithExtensions {
+                    variables,
+                    query: #module_name::#query,
+                    operation_name: #module_name::#operation_name_const,
+                    extensions,
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
     let trait_token = quote!(
+        #execute
+
+        #cfg_attribute
+        impl #struct_name {
+            #variable_specs
+
+            #build_query_with_extensions
+        }
+
+        #cfg_attribute
         impl ::graphql_client::GraphQLQuery for #struct_name {
             type Variables = #module_name::#variables_struct_name;
             type ResponseData = #module_name::#respons_data_struct_name;
@@ -295,8 +910,8 @@ fn build_query_struct_token_stream(
             fn build_query(variables: Self::Variables) -> ::graphql_client::QueryBody<Self::Variables> {
                 ::graphql_client::QueryBody {
                     variables,
-                    query: #module_name::QUERY,
-                    operation_name: #module_name::OPERATION_NAME,
+                    query: #module_name::#query,
+                    operation_name: #module_name::#operation_name_const,
                 }
 
             }
@@ -305,10 +920,93 @@ fn build_query_struct_token_stream(
     (schema_token, trait_token)
 }
 
+/// The body of the requests built by `build_query_with_extensions`, `graphql_client::QueryBody` having no extensions. Generated once per module, with the `query_extensions` option.
+fn query_body_with_extensions() -> TokenStream {
+    quote! {
+        /// The body of a request with `extensions` for the server.
+        #[derive(::serde_derive::Serialize)]
+        pub struct QueryBodyWithExtensions<V, E> {
+            /// The variables of the operation.
+            pub variables: V,
+            /// The document of the operation.
+            pub query: &'static str,
+            /// The name of the operation.
+            #[serde(rename = "operationName")]
+            pub operation_name: &'static str,
+            /// The extensions, like tracing IDs or a persisted query hash.
+            pub extensions: E,
+        }
+    }
+}
+
+/// Parse the schema at `schema_path`, or get it from the schema cache.
+fn load_schema(
+    schema_path: &std::path::Path,
+    format: Option<schema_format::SchemaFormat>,
+) -> Result<::std::sync::Arc<schema::ParsedSchema>, failure::Error> {
+    let mut lock = SCHEMA_CACHE.lock().expect("schema cache is poisoned");
+    match lock.entry(schema_path.to_path_buf()) {
+        ::std::collections::hash_map::Entry::Occupied(o) => Ok(o.get().clone()),
+        ::std::collections::hash_map::Entry::Vacant(v) => {
+            let parsed_schema = parse_schema(v.key(), format)?;
+            Ok(v.insert(::std::sync::Arc::new(parsed_schema)).clone())
+        }
+    }
+}
+
+fn parse_schema(
+    schema_path: &std::path::Path,
+    format: Option<schema_format::SchemaFormat>,
+) -> Result<schema::ParsedSchema, failure::Error> {
+    let schema_string = read_file(schema_path)?;
+    let format = format.unwrap_or_else(|| schema_format::SchemaFormat::detect(&schema_string));
+
+    if let Some(parsed_schema) = schema_cache::read(&schema_string, format) {
+        return Ok(parsed_schema);
+    }
+
+    let parsed_schema = match format {
+        schema_format::SchemaFormat::Sdl => {
+            schema::ParsedSchema::GraphQLParser(sdl::parse_schema(&schema_string)?)
+        }
+        schema_format::SchemaFormat::Json => {
+            schema::ParsedSchema::Json(::serde_json::from_str(&schema_string)?)
+        }
+    };
+    schema_cache::write(&schema_string, &parsed_schema);
+
+    Ok(parsed_schema)
+}
+
+/// Append the files matching `patterns` to the query document, skipping the query file itself. Each file is parsed on its own first, so syntax errors point at the right file.
+fn merge_query_files(
+    query_path: &::std::path::Path,
+    mut query_string: String,
+    patterns: &[String],
+    tracked_files: &mut Vec<::std::path::PathBuf>,
+) -> Result<(String, graphql_parser::query::Document), failure::Error> {
+    for pattern in patterns {
+        for path in globs::expand(pattern)? {
+            if path == query_path {
+                continue;
+            }
+            let contents = read_file(&path)?;
+            string_literals::parse_query(&contents)
+                .map_err(|err| format_err!("Could not parse {}: {}", path.display(), err))?;
+            query_string.push('\n');
+            query_string.push_str(&contents);
+            tracked_files.push(path);
+        }
+    }
+
+    let query = string_literals::parse_query(&query_string)?;
+    Ok((query_string, query))
+}
+
 fn read_file(path: &::std::path::Path) -> Result<String, failure::Error> {
     use std::io::prelude::*;
 
-    let mut out = String::new();
+    let mut out = Vec::new();
     let mut file = ::std::fs::File::open(path).map_err(|io_err| {
         let err: failure::Error = io_err.into();
         err.context(format!(
@@ -319,6 +1017,8 @@ fn read_file(path: &::std::path::Path) -> Result<String, failure::Error> {
             path.display()
         ))
     })?;
-    file.read_to_string(&mut out)?;
-    Ok(out)
+    file.read_to_end(&mut out)?;
+    let out = compression::decompress(out)
+        .map_err(|err| format_err!("Could not decompress {}: {}", path.display(), err))?;
+    Ok(String::from_utf8(out)?)
 }
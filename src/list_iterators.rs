@@ -0,0 +1,146 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use query::QueryContext;
+use syn;
+
+/// A `{field}_iter()` method for each field of a response struct holding a list with nullable items or itself nullable, iterating over the items that are present, nested lists flattened, so callers don't unwrap every level by hand.
+pub(crate) fn list_iterators_impl(context: &QueryContext, definition: &TokenStream) -> TokenStream {
+    if !context.list_iterators {
+        return quote!();
+    }
+
+    let definition: syn::DeriveInput =
+        syn::parse2(definition.clone()).expect("generated type definition");
+    let name = &definition.ident;
+    let fields = match definition.data {
+        syn::Data::Struct(ref data) => &data.fields,
+        _ => return quote!(),
+    };
+
+    let methods: Vec<TokenStream> = fields
+        .iter()
+        .filter_map(|field| {
+            let field_name = field.ident.as_ref().expect("named field");
+            let (item, iterator) = flatten(&field.ty, quote!(self.#field_name.iter()))?;
+            let method_name = Ident::new(
+                &format!("{}_iter", field_name.to_string().trim_end_matches('_')),
+                Span::call_site(),
+            );
+            let doc = format!(
+                "The items of `{}` that are present, nested lists flattened.",
+                field_name
+            );
+            Some(quote! {
+                #[doc = #doc]
+                pub fn #method_name(&self) -> impl ::std::iter::Iterator<Item = &#item> {
+                    #iterator
+                }
+            })
+        })
+        .collect();
+    if methods.is_empty() {
+        return quote!();
+    }
+
+    quote! {
+        #[allow(deprecated)]
+        impl #name {
+            #(#methods)*
+        }
+    }
+}
+
+/// The item type and the iterator over the items of a field of type `ty`, given `iterator` over the values wrapped by its outer `Option` or `Vec`. `None` for types that aren't lists, or lists that are never null and hold no null items.
+fn flatten(ty: &syn::Type, iterator: TokenStream) -> Option<(TokenStream, TokenStream)> {
+    let (wrapper, inner) = wrapped_type(ty)?;
+    let mut has_list = wrapper == "Vec";
+    let mut has_option = wrapper == "Option";
+    let mut item = inner;
+    let mut iterator = iterator;
+    while let Some((wrapper, inner)) = wrapped_type(item) {
+        match wrapper.as_str() {
+            "Vec" => has_list = true,
+            "Option" => has_option = true,
+            // Nested structs held behind a pointer are iterated over by reference.
+            _ => {
+                iterator = quote!(::std::iter::Iterator::map(#iterator, |item| &**item));
+                item = inner;
+                break;
+            }
+        }
+        iterator = quote!(::std::iter::Iterator::flat_map(#iterator, |item| item.iter()));
+        item = inner;
+    }
+
+    if has_list && has_option {
+        Some((quote!(#item), iterator))
+    } else {
+        None
+    }
+}
+
+/// The name of the last segment of a path type like `::std::option::Option<T>`, and `T`.
+fn wrapped_type(ty: &syn::Type) -> Option<(String, &syn::Type)> {
+    let segment = match ty {
+        syn::Type::Path(path) => path.path.segments.iter().last()?,
+        _ => return None,
+    };
+    let name = segment.ident.to_string();
+    if !["Option", "Vec", "Box", "Arc"].contains(&name.as_str()) {
+        return None;
+    }
+    match segment.arguments {
+        syn::PathArguments::AngleBracketed(ref arguments) => match arguments.args.iter().next() {
+            Some(syn::GenericArgument::Type(inner)) => Some((name, inner)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::Schema;
+
+    #[test]
+    fn nullable_lists_get_flattening_iterators() {
+        let schema = Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        let definition = quote! {
+            #[derive(Deserialize)]
+            pub struct Viewer {
+                pub friends: ::std::option::Option<::std::vec::Vec<::std::option::Option<ViewerFriends>>>,
+                pub tags: ::std::vec::Vec<::std::string::String>,
+                pub name: ::std::option::Option<::std::string::String>,
+                pub type_: ::std::vec::Vec<::std::option::Option<::std::sync::Arc<ViewerType>>>,
+            }
+        };
+
+        assert!(list_iterators_impl(&context, &definition).is_empty());
+
+        context.list_iterators = true;
+        assert_eq!(
+            list_iterators_impl(&context, &definition).to_string(),
+            quote! {
+                #[allow(deprecated)]
+                impl Viewer {
+                    #[doc = "The items of `friends` that are present, nested lists flattened."]
+                    pub fn friends_iter(&self) -> impl ::std::iter::Iterator<Item = &ViewerFriends> {
+                        ::std::iter::Iterator::flat_map(
+                            ::std::iter::Iterator::flat_map(self.friends.iter(), |item| item.iter()),
+                            |item| item.iter()
+                        )
+                    }
+                    #[doc = "The items of `type_` that are present, nested lists flattened."]
+                    pub fn type_iter(&self) -> impl ::std::iter::Iterator<Item = &ViewerType> {
+                        ::std::iter::Iterator::map(
+                            ::std::iter::Iterator::flat_map(self.type_.iter(), |item| item.iter()),
+                            |item| &**item
+                        )
+                    }
+                }
+            }
+            .to_string()
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use query::QueryContext;
+use syn;
+
+/// The `Mock` trait, with its implementations for the built-in scalars and the containers. Generated once per module, when mocks are enabled.
+pub(crate) fn mock_trait() -> TokenStream {
+    quote! {
+        /// Values to fabricate responses in tests: optional values are present, lists have one item, and the rest is the default value of the type.
+        pub trait Mock {
+            fn mock() -> Self;
+        }
+
+        impl Mock for bool {
+            fn mock() -> Self {
+                false
+            }
+        }
+
+        impl Mock for i64 {
+            fn mock() -> Self {
+                0
+            }
+        }
+
+        impl Mock for f64 {
+            fn mock() -> Self {
+                0.0
+            }
+        }
+
+        impl Mock for ::std::string::String {
+            fn mock() -> Self {
+                ::std::string::String::new()
+            }
+        }
+
+        impl<T: Mock> Mock for ::std::option::Option<T> {
+            fn mock() -> Self {
+                ::std::option::Option::Some(T::mock())
+            }
+        }
+
+        impl<T: Mock> Mock for ::std::vec::Vec<T> {
+            fn mock() -> Self {
+                ::std::vec![T::mock()]
+            }
+        }
+
+        impl<T: Mock> Mock for ::std::boxed::Box<T> {
+            fn mock() -> Self {
+                ::std::boxed::Box::new(T::mock())
+            }
+        }
+
+        impl<T: Mock> Mock for ::std::sync::Arc<T> {
+            fn mock() -> Self {
+                ::std::sync::Arc::new(T::mock())
+            }
+        }
+    }
+}
+
+/// The `Mock` implementation of a generated struct or enum, from its definition. Structs mock every field, enums use their first variant.
+pub(crate) fn mock_impl(context: &QueryContext, definition: &TokenStream) -> TokenStream {
+    if !context.mocks {
+        return quote!();
+    }
+
+    let definition: syn::DeriveInput =
+        syn::parse2(definition.clone()).expect("generated type definition");
+    let name = &definition.ident;
+    let value = match definition.data {
+        syn::Data::Struct(ref data) => {
+            let fields = data.fields.iter().map(|field| {
+                let field_name = &field.ident;
+                quote!(#field_name: Mock::mock())
+            });
+            quote!(#name { #(#fields,)* })
+        }
+        syn::Data::Enum(ref data) => match data.variants.iter().next() {
+            Some(variant) => {
+                let variant_name = &variant.ident;
+                match variant.fields {
+                    syn::Fields::Unit => quote!(#name::#variant_name),
+                    _ => quote!(#name::#variant_name(Mock::mock())),
+                }
+            }
+            None => return quote!(),
+        },
+        syn::Data::Union(_) => return quote!(),
+    };
+
+    quote! {
+        #[allow(deprecated)]
+        impl Mock for #name {
+            fn mock() -> Self {
+                #value
+            }
+        }
+    }
+}
+
+/// Custom scalars are mocked with their `Default` implementation, except for big numbers without one.
+pub(crate) fn scalar_mock_impl(context: &QueryContext, scalar_name: &str) -> TokenStream {
+    if !context.mocks {
+        return quote!();
+    }
+
+    let name = Ident::new(scalar_name, Span::call_site());
+    let value = match context.big_number_strategy(scalar_name) {
+        Some(strategy) => strategy.default_value(),
+        None => quote!(::std::default::Default::default()),
+    };
+    quote! {
+        impl Mock for #name {
+            fn mock() -> Self {
+                #value
+            }
+        }
+    }
+}
+
+/// The string type of the response fields mocks an empty string.
+pub(crate) fn string_type_mock_impl(context: &QueryContext, ty: &TokenStream) -> TokenStream {
+    if !context.mocks {
+        return quote!();
+    }
+
+    quote! {
+        impl Mock for #ty {
+            fn mock() -> Self {
+                ::std::convert::From::from("")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::Schema;
+
+    #[test]
+    fn mocks_use_every_field_and_the_first_variant() {
+        let schema = Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        let definition = quote! {
+            #[derive(Deserialize)]
+            pub struct Viewer {
+                #[serde(rename = "fullName")]
+                pub full_name: Option<String>,
+                pub on: ViewerOn,
+            }
+        };
+
+        assert!(mock_impl(&context, &definition).is_empty());
+
+        context.mocks = true;
+        assert_eq!(
+            mock_impl(&context, &definition).to_string(),
+            "# [ allow ( deprecated ) ] impl Mock for Viewer { fn mock ( ) -> Self { Viewer { full_name : Mock :: mock ( ) , on : Mock :: mock ( ) , } } }"
+        );
+        assert_eq!(
+            mock_impl(&context, &quote!(pub enum ViewerOn { User(ViewerOnUser), Bot })).to_string(),
+            "# [ allow ( deprecated ) ] impl Mock for ViewerOn { fn mock ( ) -> Self { ViewerOn :: User ( Mock :: mock ( ) ) } }"
+        );
+    }
+}
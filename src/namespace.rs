@@ -0,0 +1,102 @@
+use heck::{CamelCase, SnakeCase};
+
+/// A prefix for the generated module and query structs, so operations with the same name in different query files can be generated in the same module path.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Namespace {
+    /// The name of the struct the derive is on.
+    StructName,
+    /// An explicit prefix.
+    Prefix(String),
+}
+
+impl ::std::str::FromStr for Namespace {
+    type Err = ::failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "struct_name" => Ok(Namespace::StructName),
+            prefix if is_identifier(prefix) => Ok(Namespace::Prefix(prefix.to_string())),
+            other => Err(format_err!(
+                "Invalid namespace: `{}`. Valid values are `struct_name` and identifiers.",
+                other
+            )),
+        }
+    }
+}
+
+impl Namespace {
+    fn prefix<'a>(&'a self, struct_name: Option<&'a str>) -> Result<&'a str, ::failure::Error> {
+        match self {
+            Namespace::StructName => struct_name.ok_or_else(|| {
+                format_err!("The `struct_name` namespace needs the name of the query struct")
+            }),
+            Namespace::Prefix(prefix) => Ok(prefix),
+        }
+    }
+
+    /// The name of the module generated for `module_name`, like `github_repository_query`.
+    pub fn module_name(
+        &self,
+        struct_name: Option<&str>,
+        module_name: &str,
+    ) -> Result<String, ::failure::Error> {
+        let prefix = self.prefix(struct_name)?;
+        Ok(format!(
+            "{}_{}",
+            prefix.to_snake_case(),
+            module_name.to_snake_case()
+        ))
+    }
+
+    /// The name of the struct implementing `GraphQLQuery` for an operation when no struct name is given, like `GithubRepositoryQuery`. The `struct_name` namespace is only used with a struct name.
+    pub fn struct_name(&self, operation_name: &str) -> Option<String> {
+        match self {
+            Namespace::StructName => None,
+            Namespace::Prefix(prefix) => {
+                Some(format!("{}{}", prefix.to_camel_case(), operation_name))
+            }
+        }
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) if first.is_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespaces_prefix_modules_and_structs() {
+        assert_eq!(
+            "struct_name".parse::<Namespace>().unwrap(),
+            Namespace::StructName
+        );
+        assert!("github-api".parse::<Namespace>().is_err());
+
+        let github = "github".parse::<Namespace>().unwrap();
+        assert_eq!(
+            github.module_name(None, "RepositoryQuery").unwrap(),
+            "github_repository_query"
+        );
+        assert_eq!(
+            github.struct_name("RepositoryQuery"),
+            Some("GithubRepositoryQuery".to_string())
+        );
+
+        assert_eq!(
+            Namespace::StructName
+                .module_name(Some("GithubRepository"), "repository_query")
+                .unwrap(),
+            "github_repository_repository_query"
+        );
+        assert_eq!(Namespace::StructName.struct_name("RepositoryQuery"), None);
+    }
+}
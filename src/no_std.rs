@@ -0,0 +1,111 @@
+use failure;
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use syn;
+
+/// The `std` modules that are re-exports of `alloc` modules. The others used by the generated code are re-exports of `core` modules.
+const ALLOC_MODULES: &[&str] = &["boxed", "string", "vec", "format", "borrow", "sync"];
+
+/// The `std` modules that have no `core` or `alloc` equivalent.
+const STD_ONLY_MODULES: &[&str] = &[
+    "collections",
+    "env",
+    "fs",
+    "io",
+    "net",
+    "path",
+    "process",
+    "thread",
+    "time",
+];
+
+/// Replace the `::std::` paths of the generated code with their `::core::` or `::alloc::` equivalents, so it compiles in `#![no_std]` crates with `extern crate alloc;`.
+pub(crate) fn alloc_paths(tokens: TokenStream) -> Result<TokenStream, failure::Error> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut rewritten = Vec::with_capacity(tokens.len());
+
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Group(group) => {
+                let mut new_group =
+                    proc_macro2::Group::new(group.delimiter(), alloc_paths(group.stream())?);
+                new_group.set_span(group.span());
+                rewritten.push(TokenTree::Group(new_group));
+            }
+            TokenTree::Ident(ident) if ident == "std" && is_path_root(&tokens, index) => {
+                let module = match tokens.get(index + 3) {
+                    Some(TokenTree::Ident(module)) => module.to_string(),
+                    _ => return Err(format_err!("Unexpected `::std` path in no_std code")),
+                };
+                if STD_ONLY_MODULES.contains(&module.as_str()) {
+                    return Err(format_err!(
+                        "`::std::{}` is not available in no_std code",
+                        module
+                    ));
+                }
+                let krate = if ALLOC_MODULES.contains(&module.as_str()) {
+                    "alloc"
+                } else {
+                    "core"
+                };
+                rewritten.push(TokenTree::Ident(Ident::new(krate, Span::call_site())));
+            }
+            token => rewritten.push(token.clone()),
+        }
+    }
+
+    Ok(rewritten.into_iter().collect())
+}
+
+/// Whether the token at `index` comes right after a leading `::` and before another `::`.
+fn is_path_root(tokens: &[TokenTree], index: usize) -> bool {
+    let is_colon = |index: usize| match tokens.get(index) {
+        Some(TokenTree::Punct(punct)) => punct.as_char() == ':',
+        _ => false,
+    };
+    let after_path_segment = index >= 3
+        && match tokens[index - 3] {
+            // Keywords, like `impl ::std::fmt::Display`, are not path segments.
+            TokenTree::Ident(ref ident) => syn::parse_str::<Ident>(&ident.to_string()).is_ok(),
+            _ => false,
+        };
+
+    index >= 2
+        && is_colon(index - 1)
+        && is_colon(index - 2)
+        && !after_path_segment
+        && is_colon(index + 1)
+        && is_colon(index + 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn std_paths_are_replaced_by_core_and_alloc_paths() {
+        let tokens = quote! {
+            impl ::std::fmt::Display for Hero {}
+            pub struct Hero {
+                pub name: ::std::option::Option<::std::string::String>,
+                pub friends: ::std::vec::Vec<my::std::Friend>,
+            }
+            fn friends() -> ::std::vec::Vec<Hero> { ::std::vec![] }
+        };
+
+        assert_eq!(
+            alloc_paths(tokens).unwrap().to_string(),
+            "impl :: core :: fmt :: Display for Hero { } \
+             pub struct Hero { \
+             pub name : :: core :: option :: Option < :: alloc :: string :: String > , \
+             pub friends : :: alloc :: vec :: Vec < my :: std :: Friend > , \
+             } \
+             fn friends ( ) -> :: alloc :: vec :: Vec < Hero > { :: alloc :: vec ! [ ] }"
+        );
+        assert_eq!(
+            alloc_paths(quote!(::std::fs::read("query.graphql")))
+                .unwrap_err()
+                .to_string(),
+            "`::std::fs` is not available in no_std code"
+        );
+    }
+}
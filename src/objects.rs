@@ -3,11 +3,12 @@ use deprecation::DeprecationStatus;
 use failure;
 use field_type::FieldType;
 use graphql_parser::schema;
+use hooks::ItemKind;
 use proc_macro2::{Ident, Span, TokenStream};
-use query::QueryContext;
+use query::{Expansion, QueryContext};
 use schema::Schema;
 use selection::*;
-use shared::{field_impls_for_selection, response_fields_for_selection};
+use shared::{field_impls_for_selection, nested_selections, response_fields_for_selection};
 use std::cell::Cell;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -26,9 +27,62 @@ pub struct GqlObjectField<'schema> {
     pub deprecation: DeprecationStatus,
 }
 
+impl<'schema> ::std::convert::From<&'schema schema::Field> for GqlObjectField<'schema> {
+    fn from(field: &'schema schema::Field) -> GqlObjectField<'schema> {
+        GqlObjectField {
+            description: field.description.as_ref().map(String::as_str),
+            name: &field.name,
+            type_: FieldType::from(&field.field_type),
+            deprecation: parse_deprecation_info(field),
+        }
+    }
+}
+
+/// An argument of an object or interface field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GqlArgument<'schema> {
+    pub name: &'schema str,
+    pub type_: FieldType<'schema>,
+    /// Arguments with a default value can be omitted, even when their type is non-null.
+    pub has_default: bool,
+}
+
+impl<'schema> ::std::convert::From<&'schema schema::InputValue> for GqlArgument<'schema> {
+    fn from(argument: &'schema schema::InputValue) -> GqlArgument<'schema> {
+        GqlArgument {
+            name: &argument.name,
+            type_: FieldType::from(&argument.value_type),
+            has_default: argument.default_value.is_some(),
+        }
+    }
+}
+
+impl<'schema> ::std::convert::From<&'schema ::introspection_response::FullTypeFieldsArgs>
+    for GqlArgument<'schema>
+{
+    fn from(
+        argument: &'schema ::introspection_response::FullTypeFieldsArgs,
+    ) -> GqlArgument<'schema> {
+        let argument = &argument.input_value;
+        GqlArgument {
+            name: argument.name.as_ref().expect("argument name"),
+            type_: argument
+                .type_
+                .as_ref()
+                .map(FieldType::from)
+                .expect("argument type"),
+            has_default: argument.default_value.is_some(),
+        }
+    }
+}
+
 fn parse_deprecation_info(field: &schema::Field) -> DeprecationStatus {
-    let deprecated = field
-        .directives
+    parse_deprecation_directives(&field.directives)
+}
+
+/// The deprecation status of a schema item, from its `@deprecated` directive.
+pub(crate) fn parse_deprecation_directives(directives: &[schema::Directive]) -> DeprecationStatus {
+    let deprecated = directives
         .iter()
         .filter(|x| x.name.to_lowercase() == "deprecated")
         .nth(0);
@@ -69,15 +123,8 @@ impl<'schema> GqlObject<'schema> {
     pub fn from_graphql_parser_object(obj: &'schema schema::ObjectType) -> Self {
         let description = obj.description.as_ref().map(|s| s.as_str());
         let mut item = GqlObject::new(&obj.name, description);
-        item.fields.extend(obj.fields.iter().map(|f| {
-            let deprecation = parse_deprecation_info(&f);
-            GqlObjectField {
-                description: f.description.as_ref().map(String::as_str),
-                name: &f.name,
-                type_: FieldType::from(&f.field_type),
-                deprecation,
-            }
-        }));
+        item.fields
+            .extend(obj.fields.iter().map(GqlObjectField::from));
         item
     }
 
@@ -121,20 +168,66 @@ impl<'schema> GqlObject<'schema> {
         selection: &Selection,
         prefix: &str,
     ) -> Result<TokenStream, failure::Error> {
+        query_context.expand_nested(
+            prefix,
+            self.expand_selection(query_context, selection, prefix)?,
+        )
+    }
+
+    /// The struct for the selection, and the selections of its fields. See [QueryContext::expand_nested].
+    pub(crate) fn expand_selection<'query>(
+        &self,
+        query_context: &QueryContext,
+        selection: &Selection<'query>,
+        prefix: &str,
+    ) -> Expansion<'query> {
         let derives = query_context.response_derives();
         let name = Ident::new(prefix, Span::call_site());
+        let selection = &selection.without_fragment_fields(query_context, self.name)?;
         let fields = self.response_fields_for_selection(query_context, selection, prefix)?;
-        let field_impls = self.field_impls_for_selection(query_context, selection, &prefix)?;
-        let description = self.description.as_ref().map(|desc| quote!(#[doc = #desc]));
-        Ok(quote! {
-            #(#field_impls)*
-
+        let nested = nested_selections(self.name, query_context, selection, prefix)?;
+        let description = ::shared::description_attributes(self.description);
+        let definition = quote! {
             #derives
             #description
             pub struct #name {
                 #(#fields,)*
             }
-        })
+        };
+        let definition = ::strict_mode::strict_definition(query_context, definition);
+        let mock = ::mocks::mock_impl(query_context, &definition);
+        let default = ::defaults::default_impl(query_context, &definition, self.name);
+        let list_iterators = ::list_iterators::list_iterators_impl(query_context, &definition);
+        let validation = ::json_validation::validation_impl(query_context, &definition);
+        let pretty_print = ::pretty_print::pretty_print_impl(query_context, &definition);
+        let fragment_conversions = ::shared::fragment_conversions(&name, selection);
+        let cache_key = match ::shared::selected_typename(query_context, selection) {
+            Some(typename) => {
+                ::shared::cache_key_impl(self.name, query_context, selection, &name, &typename)
+            }
+            None => quote!(),
+        };
+        let definition = ::redaction::redacted_debug(query_context, definition);
+        let tokens = quote! {
+            #definition
+
+            #fragment_conversions
+
+            #cache_key
+
+            #mock
+
+            #default
+
+            #list_iterators
+
+            #validation
+
+
+            #pretty_print
+        };
+        let tokens = query_context.hooked(ItemKind::Object, self.name, prefix, tokens);
+        Ok((tokens, nested))
     }
 
     pub(crate) fn field_impls_for_selection(
@@ -143,7 +236,7 @@ impl<'schema> GqlObject<'schema> {
         selection: &Selection,
         prefix: &str,
     ) -> Result<Vec<TokenStream>, failure::Error> {
-        field_impls_for_selection(&self.fields, query_context, selection, prefix)
+        field_impls_for_selection(self.name, query_context, selection, prefix)
     }
 
     pub(crate) fn response_fields_for_selection(
@@ -152,7 +245,7 @@ impl<'schema> GqlObject<'schema> {
         selection: &Selection,
         prefix: &str,
     ) -> Result<Vec<TokenStream>, failure::Error> {
-        response_fields_for_selection(&self.name, &self.fields, query_context, selection, prefix)
+        response_fields_for_selection(&self.name, query_context, selection, prefix, &[])
     }
 }
 
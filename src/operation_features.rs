@@ -0,0 +1,124 @@
+use failure;
+use proc_macro2::{Ident, Span, TokenStream};
+use std::collections::BTreeMap;
+
+/// The cargo features the operations of a document are generated under, by operation name, like `GetUser=users, ListPosts=posts`. Operations left out are always generated.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OperationFeatures(BTreeMap<String, String>);
+
+impl ::std::str::FromStr for OperationFeatures {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut features = BTreeMap::new();
+        for item in s.split(',').filter(|item| !item.trim().is_empty()) {
+            match item
+                .splitn(2, '=')
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .as_slice()
+            {
+                [operation, feature] if !operation.is_empty() && !feature.is_empty() => {
+                    features.insert((*operation).to_owned(), (*feature).to_owned());
+                }
+                _ => {
+                    return Err(format_err!(
+                        "Invalid operation feature: `{}`. Expected `Operation=feature`.",
+                        item.trim()
+                    ))
+                }
+            }
+        }
+        Ok(OperationFeatures(features))
+    }
+}
+
+impl OperationFeatures {
+    /// Check that every operation is in the document.
+    pub(crate) fn check(&self, operation_names: &[&str]) -> Result<(), failure::Error> {
+        match self
+            .0
+            .keys()
+            .find(|operation| !operation_names.contains(&operation.as_str()))
+        {
+            Some(operation) => Err(format_err!(
+                "Feature for the operation `{}`, which is not in the document. Operations: `{}`.",
+                operation,
+                operation_names.join("`, `")
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// The feature of the operation, if it has one.
+    pub fn feature(&self, operation_name: &str) -> Option<&str> {
+        self.0.get(operation_name).map(String::as_str)
+    }
+
+    /// The `#[cfg(feature = "...")]` attribute of the items of the operation, if it has a feature.
+    pub(crate) fn cfg_attribute(&self, operation_name: &str) -> TokenStream {
+        match self.feature(operation_name) {
+            Some(feature) => quote!(#[cfg(feature = #feature)]),
+            None => quote!(),
+        }
+    }
+}
+
+/// The items of an operation inside the module, behind `cfg`: in a module of their own, re-exported, as the attribute applies to a single item.
+pub(crate) fn gated_items(
+    cfg_attribute: &TokenStream,
+    operation_name: &str,
+    items: TokenStream,
+) -> TokenStream {
+    if cfg_attribute.is_empty() {
+        return items;
+    }
+
+    let module_name = Ident::new(
+        &format!(
+            "{}_operation",
+            ::heck::SnakeCase::to_snake_case(operation_name)
+        ),
+        Span::call_site(),
+    );
+    quote! {
+        #cfg_attribute
+        mod #module_name {
+            use super::*;
+
+            #items
+        }
+
+        #cfg_attribute
+        pub use self::#module_name::*;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operation_features_gate_the_operations() {
+        let features = "GetUser=users, ListPosts = posts"
+            .parse::<OperationFeatures>()
+            .unwrap();
+
+        assert_eq!(
+            features.cfg_attribute("GetUser").to_string(),
+            "# [ cfg ( feature = \"users\" ) ]"
+        );
+        assert!(features.cfg_attribute("Other").is_empty());
+        assert!(features.check(&["GetUser", "ListPosts", "Other"]).is_ok());
+        assert_eq!(
+            features.check(&["GetUser"]).unwrap_err().to_string(),
+            "Feature for the operation `ListPosts`, which is not in the document. Operations: `GetUser`."
+        );
+        assert!("GetUser".parse::<OperationFeatures>().is_err());
+
+        assert_eq!(
+            gated_items(&features.cfg_attribute("GetUser"), "GetUser", quote!(pub struct GetUserVariables;)).to_string(),
+            "# [ cfg ( feature = \"users\" ) ] mod get_user_operation { use super :: * ; pub struct GetUserVariables ; } # [ cfg ( feature = \"users\" ) ] pub use self :: get_user_operation :: * ;"
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! A lock file recording every operation the crate can send, with a hash of its document, as a reviewable artifact of the queries in the binary.
+//!
+//! Each derive checks its operations against the lock file and fails when an entry is missing or out of date. Building with `GRAPHQL_CLIENT_UPDATE_LOCK=1` writes the entries instead. Entries of removed operations are not detected, since each derive only knows its own operations.
+
+use failure;
+use serde_json;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Set to anything but `0` to write the operations to the lock file instead of checking them.
+///
+/// Note that cargo does not know about this variable, so the crate has to be touched for the derive to run again.
+pub const UPDATE_LOCK_ENV_VAR: &str = "GRAPHQL_CLIENT_UPDATE_LOCK";
+
+/// An operation as recorded in the lock file, under the name of its module and its own name, like `hero_query::HeroQuery`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedOperation {
+    /// The FNV-1a hash of `query`, in hexadecimal.
+    pub hash: String,
+    /// The document sent with the operation.
+    pub query: String,
+}
+
+impl LockedOperation {
+    pub(crate) fn new(query: &str) -> Self {
+        LockedOperation {
+            hash: format!("{:016x}", ::schema_cache::content_hash(query.as_bytes())),
+            query: query.to_owned(),
+        }
+    }
+}
+
+/// Check `operations` against the lock file at `path`, or write them to it when `GRAPHQL_CLIENT_UPDATE_LOCK` is set.
+pub(crate) fn check_or_update(
+    path: &Path,
+    operations: BTreeMap<String, LockedOperation>,
+) -> Result<(), failure::Error> {
+    let update = ::std::env::var(UPDATE_LOCK_ENV_VAR)
+        .map(|value| value != "0")
+        .unwrap_or(false);
+
+    if update {
+        return update_lock_file(path, operations);
+    }
+
+    let locked = read(path)?;
+    let stale: Vec<&str> = operations
+        .iter()
+        .filter(|(name, operation)| locked.get(*name) != Some(operation))
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if stale.is_empty() {
+        Ok(())
+    } else {
+        Err(format_err!(
+            "The operation lock file {} is out of date for {}. Build with {}=1 to update it, and review the changes.",
+            path.display(),
+            stale.join(", "),
+            UPDATE_LOCK_ENV_VAR
+        ))
+    }
+}
+
+fn read(path: &Path) -> Result<BTreeMap<String, LockedOperation>, failure::Error> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let error = |err| format_err!("Could not read {}: {}", path.display(), err);
+    let mut file = File::open(path).map_err(error)?;
+    // Derives updating the lock file in parallel hold an exclusive lock while they write it.
+    file.lock_shared().map_err(error)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).map_err(error)?;
+    parse(path, &contents)
+}
+
+/// Add `operations` to the lock file at `path`, holding an exclusive lock on it from the read to the write so derives running in parallel keep the entries of each other.
+fn update_lock_file(
+    path: &Path,
+    operations: BTreeMap<String, LockedOperation>,
+) -> Result<(), failure::Error> {
+    let error = |err| format_err!("Could not write {}: {}", path.display(), err);
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(error)?;
+    file.lock().map_err(error)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).map_err(error)?;
+
+    let mut locked = parse(path, &contents)?;
+    let before = locked.clone();
+    locked.extend(operations);
+    if locked == before {
+        return Ok(());
+    }
+    let json = serde_json::to_string_pretty(&locked)? + "\n";
+    file.set_len(0)
+        .and_then(|()| file.seek(SeekFrom::Start(0)))
+        .and_then(|_| file.write_all(json.as_bytes()))
+        .map_err(error)
+}
+
+/// The entries of a lock file. A lock file just created by another derive is still empty.
+fn parse(path: &Path, contents: &str) -> Result<BTreeMap<String, LockedOperation>, failure::Error> {
+    if contents.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    serde_json::from_str(contents)
+        .map_err(|err| format_err!("Invalid operation lock file {}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::thread;
+
+    #[test]
+    fn parallel_updates_keep_every_entry() {
+        let dir = ::std::env::temp_dir().join("graphql_client_parallel_operation_lock");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("graphql.lock");
+
+        let updates: Vec<_> = (0..8)
+            .map(|index| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    let name = format!("query_{}::Query{}", index, index);
+                    let query = format!("query Query{} {{ viewer {{ id }} }}", index);
+                    let operations = vec![(name, LockedOperation::new(&query))];
+                    update_lock_file(&path, operations.into_iter().collect())
+                })
+            })
+            .collect();
+        for update in updates {
+            update.join().unwrap().unwrap();
+        }
+
+        assert_eq!(read(&path).unwrap().len(), 8);
+    }
+}
@@ -1,8 +1,11 @@
 use constants::*;
-use graphql_parser::query::OperationDefinition;
+use failure;
+use graphql_parser::query::{Directive, OperationDefinition};
 use heck::SnakeCase;
+use operation_features::OperationFeatures;
 use proc_macro2::{Span, TokenStream};
 use query::QueryContext;
+use query_text::QueryText;
 use selection::Selection;
 use syn::Ident;
 use variables::Variable;
@@ -14,20 +17,74 @@ pub enum OperationType {
     Subscription,
 }
 
+impl OperationType {
+    /// The name of the root type of the operations of the type in schemas that don't declare their root types.
+    pub(crate) fn conventional_root_name(&self) -> &'static str {
+        match *self {
+            OperationType::Query => "Query",
+            OperationType::Mutation => "Mutation",
+            OperationType::Subscription => "Subscription",
+        }
+    }
+
+    /// The keyword of the operations of the type in documents.
+    pub(crate) fn keyword(&self) -> &'static str {
+        match *self {
+            OperationType::Query => "query",
+            OperationType::Mutation => "mutation",
+            OperationType::Subscription => "subscription",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Operation<'query> {
     pub name: String,
     pub operation_type: OperationType,
     pub variables: Vec<Variable<'query>>,
     pub selection: Selection<'query>,
+    /// The directives on the operation itself, like `@cached(ttl: 60)`.
+    pub directives: &'query [Directive],
 }
 
 impl<'query> Operation<'query> {
     pub(crate) fn root_name<'schema>(&self, schema: &'schema ::schema::Schema) -> &'schema str {
-        match self.operation_type {
-            OperationType::Query => schema.query_type.unwrap_or("Query"),
-            OperationType::Mutation => schema.mutation_type.unwrap_or("Mutation"),
-            OperationType::Subscription => schema.subscription_type.unwrap_or("Subscription"),
+        schema
+            .root_type(&self.operation_type)
+            .unwrap_or_else(|| self.operation_type.conventional_root_name())
+    }
+
+    /// The root type of the operation, or an error when the schema has no root object for operations of its type.
+    pub(crate) fn checked_root_name<'schema>(
+        &self,
+        schema: &'schema ::schema::Schema,
+    ) -> Result<&'schema str, failure::Error> {
+        let keyword = self.operation_type.keyword();
+        match schema.root_type(&self.operation_type) {
+            Some(root_name) if schema.objects.contains_key(root_name) => Ok(root_name),
+            Some(root_name) => Err(format_err!(
+                "The schema declares `{}` as its {} type, but defines no such object; operation `{}` is a {}.",
+                root_name,
+                keyword,
+                self.name,
+                keyword
+            )),
+            None => Err(format_err!(
+                "The schema has no {} type; operation `{}` is a {}.",
+                keyword,
+                self.name,
+                keyword
+            )),
+        }
+    }
+
+    /// The operation with the inline fragments on its root type flattened into its selection, so their fields are generated on the response data like the other fields.
+    pub(crate) fn with_root_fragments_flattened(&self, schema: &::schema::Schema) -> Self {
+        Operation {
+            selection: self
+                .selection
+                .flatten_inline_fragments_on(self.root_name(schema)),
+            ..self.clone()
         }
     }
 
@@ -56,10 +113,33 @@ impl<'query> Operation<'query> {
         };
 
         let variables_derives = context.variables_derives();
+        let graphql_value = if context.graphql_values {
+            let fields: Vec<_> = variables
+                .iter()
+                .map(|variable| {
+                    (
+                        variable.name,
+                        Ident::new(&variable.name.to_snake_case(), Span::call_site()),
+                        &variable.ty,
+                        false,
+                    )
+                })
+                .collect();
+            Some(::graphql_values::object_impl(
+                context,
+                &variables_struct_name,
+                "The variables as a GraphQL object value, by variable name, for documents built programmatically.",
+                &fields,
+            ))
+        } else {
+            None
+        };
 
         if variables.is_empty() {
             return quote!(#variables_derives
-            pub struct #variables_struct_name;);
+            pub struct #variables_struct_name;
+
+            #graphql_value);
         }
 
         let fields = variables.iter().map(|variable| {
@@ -68,8 +148,11 @@ impl<'query> Operation<'query> {
             let snake_case_name = name.to_snake_case();
             let rename = ::shared::field_rename_annotation(&name, &snake_case_name);
             let name = Ident::new(&snake_case_name, Span::call_site());
+            let strategy = ::arbitrary::field_attribute(context, &variable.ty, false);
+            let adapter =
+                ::serde_adapters::field_attribute(context, None, variable.name, &variable.ty);
 
-            quote!(#rename pub #name: #ty)
+            quote!(#rename #strategy #adapter pub #name: #ty)
         });
 
         let default_constructors = variables
@@ -85,10 +168,208 @@ impl<'query> Operation<'query> {
             impl #variables_struct_name {
                 #(#default_constructors)*
             }
+
+            #graphql_value
         }
     }
 }
 
+impl<'query> Operation<'query> {
+    /// Generate the `VariablesRef` struct, borrowing the values of the variables so they are serialized without cloning them. Nothing is generated when no variable can be borrowed.
+    pub(crate) fn expand_borrowed_variables(
+        &self,
+        context: &QueryContext,
+        operation_name: &str,
+        multiple_operations: bool,
+        query_text: &QueryText,
+    ) -> TokenStream {
+        let struct_name = if multiple_operations {
+            Ident::new(
+                format!("{}VariablesRef", operation_name).as_str(),
+                Span::call_site(),
+            )
+        } else {
+            Ident::new("VariablesRef", Span::call_site())
+        };
+
+        let fields: Vec<(TokenStream, bool)> = self
+            .variables
+            .iter()
+            .map(|variable| {
+                let name = variable.name;
+                let (ty, borrows) = variable.ty.to_borrowed_rust(context);
+                let snake_case_name = name.to_snake_case();
+                let rename = ::shared::field_rename_annotation(name, &snake_case_name);
+                let name = Ident::new(&snake_case_name, Span::call_site());
+
+                (quote!(#rename pub #name: #ty), borrows)
+            })
+            .collect();
+
+        if !fields.iter().any(|(_, borrows)| *borrows) {
+            return quote!();
+        }
+
+        let fields = fields.iter().map(|(field, _)| field);
+        let query = query_text.expression();
+
+        quote! {
+            /// The variables of the operation, borrowing their values.
+            #[derive(::serde_derive::Serialize)]
+            pub struct #struct_name<'a> {
+                #(#fields,)*
+            }
+
+            impl<'a> #struct_name<'a> {
+                /// The body of the request, to serialize and send to the server.
+                pub fn into_query_body(self) -> ::graphql_client::QueryBody<Self> {
+                    ::graphql_client::QueryBody {
+                        variables: self,
+                        query: #query,
+                        operation_name: #operation_name,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The `Operations` enum of a module generated for several operations, to list them, and the `OperationVariables` enum building the request running any of them from its variables.
+pub(crate) fn operations_enum(
+    operations: &[Operation],
+    features: &OperationFeatures,
+    query_text: &QueryText,
+) -> TokenStream {
+    // The variants of operations behind a feature are gated too, in case they are looked up by name.
+    let variants: Vec<TokenStream> = operations
+        .iter()
+        .map(|operation| {
+            let variant = Ident::new(&operation.name, Span::call_site());
+            let cfg_attribute = features.cfg_attribute(&operation.name);
+            quote!(#cfg_attribute Operations::#variant)
+        })
+        .collect();
+    let names = operations.iter().map(|operation| operation.name.as_str());
+    let operation_types = operations
+        .iter()
+        .map(|operation| operation.operation_type.keyword());
+    let name_arms = variants
+        .iter()
+        .zip(names)
+        .map(|(variant, name)| quote!(#variant => #name));
+    let operation_type_arms = variants
+        .iter()
+        .zip(operation_types)
+        .map(|(variant, operation_type)| quote!(#variant => #operation_type));
+    let declarations = operations.iter().map(|operation| {
+        let variant = Ident::new(&operation.name, Span::call_site());
+        let cfg_attribute = features.cfg_attribute(&operation.name);
+        quote!(#cfg_attribute #variant)
+    });
+    let all = variants.iter();
+    let query = query_text.expression();
+    let variables_variants: Vec<TokenStream> = operations
+        .iter()
+        .map(|operation| {
+            let variant = Ident::new(&operation.name, Span::call_site());
+            let cfg_attribute = features.cfg_attribute(&operation.name);
+            quote!(#cfg_attribute OperationVariables::#variant)
+        })
+        .collect();
+    let variables_declarations = operations.iter().map(|operation| {
+        let variant = Ident::new(&operation.name, Span::call_site());
+        let variables_struct_name =
+            Ident::new(&format!("{}Variables", operation.name), Span::call_site());
+        let cfg_attribute = features.cfg_attribute(&operation.name);
+        quote!(#cfg_attribute #variant(#variables_struct_name))
+    });
+    let operation_arms =
+        variables_variants
+            .iter()
+            .zip(operations)
+            .map(|(variables_variant, operation)| {
+                let variant = Ident::new(&operation.name, Span::call_site());
+                quote!(#variables_variant(_) => Operations::#variant)
+            });
+    let serialize_arms = variables_variants.iter().map(|variables_variant| {
+        quote!(#variables_variant(ref variables) => ::serde::Serialize::serialize(variables, serializer))
+    });
+    let conversions = operations.iter().map(|operation| {
+        let variant = Ident::new(&operation.name, Span::call_site());
+        let variables_struct_name =
+            Ident::new(&format!("{}Variables", operation.name), Span::call_site());
+        let cfg_attribute = features.cfg_attribute(&operation.name);
+        quote! {
+            #cfg_attribute
+            impl ::std::convert::From<#variables_struct_name> for OperationVariables {
+                fn from(variables: #variables_struct_name) -> Self {
+                    OperationVariables::#variant(variables)
+                }
+            }
+        }
+    });
+
+    quote! {
+        /// The operations of the module.
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::marker::Copy, ::std::cmp::PartialEq, ::std::cmp::Eq, ::std::hash::Hash)]
+        pub enum Operations {
+            #(#declarations,)*
+        }
+
+        impl Operations {
+            /// Every operation of the module, in document order.
+            pub const ALL: &'static [Operations] = &[#(#all,)*];
+
+            /// The name of the operation, sent as `operationName`.
+            pub fn name(&self) -> &'static str {
+                match *self {
+                    #(#name_arms,)*
+                }
+            }
+
+            /// `query`, `mutation` or `subscription`.
+            pub fn operation_type(&self) -> &'static str {
+                match *self {
+                    #(#operation_type_arms,)*
+                }
+            }
+        }
+
+        /// The variables of one of the operations of the module, to build the request running it.
+        pub enum OperationVariables {
+            #(#variables_declarations,)*
+        }
+
+        impl OperationVariables {
+            /// The operation the variables are for.
+            pub fn operation(&self) -> Operations {
+                match *self {
+                    #(#operation_arms,)*
+                }
+            }
+
+            /// The body of the request running the operation with these variables.
+            pub fn into_query_body(self) -> ::graphql_client::QueryBody<Self> {
+                ::graphql_client::QueryBody {
+                    query: #query,
+                    operation_name: self.operation().name(),
+                    variables: self,
+                }
+            }
+        }
+
+        impl ::serde::Serialize for OperationVariables {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+                match *self {
+                    #(#serialize_arms,)*
+                }
+            }
+        }
+
+        #(#conversions)*
+    }
+}
+
 impl<'query> ::std::convert::From<&'query OperationDefinition> for Operation<'query> {
     fn from(definition: &'query OperationDefinition) -> Operation<'query> {
         match *definition {
@@ -97,20 +378,56 @@ impl<'query> ::std::convert::From<&'query OperationDefinition> for Operation<'qu
                 operation_type: OperationType::Query,
                 variables: q.variable_definitions.iter().map(|v| v.into()).collect(),
                 selection: (&q.selection_set).into(),
+                directives: &q.directives,
             },
             OperationDefinition::Mutation(ref m) => Operation {
                 name: m.name.clone().expect("unnamed operation"),
                 operation_type: OperationType::Mutation,
                 variables: m.variable_definitions.iter().map(|v| v.into()).collect(),
                 selection: (&m.selection_set).into(),
+                directives: &m.directives,
             },
             OperationDefinition::Subscription(ref s) => Operation {
                 name: s.name.clone().expect("unnamed operation"),
                 operation_type: OperationType::Subscription,
                 variables: s.variable_definitions.iter().map(|v| v.into()).collect(),
                 selection: (&s.selection_set).into(),
+                directives: &s.directives,
             },
             OperationDefinition::SelectionSet(_) => panic!(SELECTION_SET_AT_ROOT),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codegen::all_operations;
+    use graphql_parser;
+
+    #[test]
+    fn operations_enum_lists_every_operation() {
+        let query = graphql_parser::parse_query(
+            "query Hero { hero { name } }\nmutation CreateReview { createReview { stars } }",
+        )
+        .unwrap();
+
+        let generated = operations_enum(
+            &all_operations(&query),
+            &OperationFeatures::default(),
+            &QueryText::default(),
+        )
+        .to_string();
+
+        assert!(generated.contains("pub enum Operations { Hero , CreateReview , }"));
+        assert!(generated.contains(
+            "pub const ALL : & 'static [ Operations ] = & [ Operations :: Hero , Operations :: CreateReview , ] ;"
+        ));
+        assert!(generated.contains(
+            "match * self { Operations :: Hero => \"Hero\" , Operations :: CreateReview => \"CreateReview\" , }"
+        ));
+        assert!(generated.contains(
+            "match * self { Operations :: Hero => \"query\" , Operations :: CreateReview => \"mutation\" , }"
+        ));
+    }
+}
@@ -0,0 +1,305 @@
+use json_validation::SerdeAttributes;
+use proc_macro2::{Ident, Span, TokenStream};
+use query::QueryContext;
+use syn;
+
+/// The `PrettyPrint` trait, with its implementations for the built-in scalars and the containers. Generated once per module, when pretty printing is enabled.
+pub(crate) fn pretty_print_trait() -> TokenStream {
+    let leaf_impls = [quote!(bool), quote!(i64), quote!(f64)]
+        .iter()
+        .map(|ty| leaf_impl(ty, quote!(::std::fmt::Display::fmt(self, f))))
+        .collect::<Vec<_>>();
+    let string_impl = leaf_impl(
+        &quote!(::std::string::String),
+        quote!(::std::fmt::Debug::fmt(self, f)),
+    );
+
+    quote! {
+        /// Human-readable output of the response types, nested like the selection of the query, for logs and command line tools.
+        pub trait PrettyPrint {
+            /// Print the value, with nested lines indented `indent` levels.
+            fn pretty_print(&self, f: &mut ::std::fmt::Formatter, indent: usize) -> ::std::fmt::Result {
+                f.write_str("{\n")?;
+                self.pretty_print_fields(f, indent + 1)?;
+                pretty_print_indent(f, indent)?;
+                f.write_str("}")
+            }
+
+            /// Print the fields of an object, one per line, indented `indent` levels. Objects flattened into another one print their fields with its own.
+            fn pretty_print_fields(&self, _f: &mut ::std::fmt::Formatter, _indent: usize) -> ::std::fmt::Result {
+                ::std::result::Result::Ok(())
+            }
+        }
+
+        fn pretty_print_indent(f: &mut ::std::fmt::Formatter, indent: usize) -> ::std::fmt::Result {
+            for _ in 0..indent {
+                f.write_str("  ")?;
+            }
+            ::std::result::Result::Ok(())
+        }
+
+        #(#leaf_impls)*
+
+        #string_impl
+
+        impl<T: PrettyPrint> PrettyPrint for ::std::option::Option<T> {
+            fn pretty_print(&self, f: &mut ::std::fmt::Formatter, indent: usize) -> ::std::fmt::Result {
+                match self {
+                    ::std::option::Option::Some(value) => value.pretty_print(f, indent),
+                    ::std::option::Option::None => f.write_str("null"),
+                }
+            }
+
+            fn pretty_print_fields(&self, f: &mut ::std::fmt::Formatter, indent: usize) -> ::std::fmt::Result {
+                match self {
+                    ::std::option::Option::Some(value) => value.pretty_print_fields(f, indent),
+                    ::std::option::Option::None => ::std::result::Result::Ok(()),
+                }
+            }
+        }
+
+        impl<T: PrettyPrint> PrettyPrint for ::std::vec::Vec<T> {
+            fn pretty_print(&self, f: &mut ::std::fmt::Formatter, indent: usize) -> ::std::fmt::Result {
+                if self.is_empty() {
+                    return f.write_str("[]");
+                }
+                f.write_str("[\n")?;
+                for item in self {
+                    pretty_print_indent(f, indent + 1)?;
+                    item.pretty_print(f, indent + 1)?;
+                    f.write_str("\n")?;
+                }
+                pretty_print_indent(f, indent)?;
+                f.write_str("]")
+            }
+        }
+
+        impl<T: PrettyPrint> PrettyPrint for ::std::boxed::Box<T> {
+            fn pretty_print(&self, f: &mut ::std::fmt::Formatter, indent: usize) -> ::std::fmt::Result {
+                (**self).pretty_print(f, indent)
+            }
+
+            fn pretty_print_fields(&self, f: &mut ::std::fmt::Formatter, indent: usize) -> ::std::fmt::Result {
+                (**self).pretty_print_fields(f, indent)
+            }
+        }
+
+        impl<T: PrettyPrint> PrettyPrint for ::std::sync::Arc<T> {
+            fn pretty_print(&self, f: &mut ::std::fmt::Formatter, indent: usize) -> ::std::fmt::Result {
+                (**self).pretty_print(f, indent)
+            }
+
+            fn pretty_print_fields(&self, f: &mut ::std::fmt::Formatter, indent: usize) -> ::std::fmt::Result {
+                (**self).pretty_print_fields(f, indent)
+            }
+        }
+    }
+}
+
+fn leaf_impl(ty: &TokenStream, print: TokenStream) -> TokenStream {
+    quote! {
+        impl PrettyPrint for #ty {
+            fn pretty_print(&self, f: &mut ::std::fmt::Formatter, _indent: usize) -> ::std::fmt::Result {
+                #print
+            }
+        }
+    }
+}
+
+/// The `PrettyPrint` implementation of a generated struct or enum, from its definition. Struct fields are printed with their name in the response, enums on interfaces and unions print the `__typename` they are tagged with, and the other enums print their value.
+pub(crate) fn pretty_print_impl(context: &QueryContext, definition: &TokenStream) -> TokenStream {
+    if !context.pretty_print {
+        return quote!();
+    }
+
+    let definition: syn::DeriveInput =
+        syn::parse2(definition.clone()).expect("generated type definition");
+    let name = &definition.ident;
+    let data = match definition.data {
+        syn::Data::Struct(ref data) => data,
+        syn::Data::Enum(ref data) => {
+            return match SerdeAttributes::from_attributes(&definition.attrs).tag {
+                Some(tag) => tagged_enum_impl(name, &tag, data),
+                None => enum_impl(name, data),
+            }
+        }
+        syn::Data::Union(_) => return quote!(),
+    };
+
+    let redacted = context.redacted_fields(&name.to_string());
+    let fields = data.fields.iter().map(|field| {
+        let field_name = &field.ident;
+        let serde = SerdeAttributes::from_attributes(&field.attrs);
+        if serde.flatten {
+            return quote!(PrettyPrint::pretty_print_fields(&self.#field_name, f, indent)?;);
+        }
+
+        let json_name = serde
+            .rename
+            .unwrap_or_else(|| field.ident.as_ref().expect("named field").to_string());
+        let label = format!("{}: ", json_name);
+        if redacted.contains(&field_name.as_ref().expect("named field").to_string()) {
+            let label = format!("{}{}\n", label, ::redaction::REDACTED);
+            return quote! {
+                pretty_print_indent(f, indent)?;
+                f.write_str(#label)?;
+            };
+        }
+        quote! {
+            pretty_print_indent(f, indent)?;
+            f.write_str(#label)?;
+            PrettyPrint::pretty_print(&self.#field_name, f, indent)?;
+            f.write_str("\n")?;
+        }
+    });
+
+    quote! {
+        #[allow(deprecated)]
+        impl PrettyPrint for #name {
+            fn pretty_print_fields(&self, f: &mut ::std::fmt::Formatter, indent: usize) -> ::std::fmt::Result {
+                #(#fields)*
+                ::std::result::Result::Ok(())
+            }
+        }
+    }
+}
+
+fn tagged_enum_impl(name: &Ident, tag: &str, data: &syn::DataEnum) -> TokenStream {
+    let variants = data.variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+        let label = format!("{}: {:?}\n", tag, variant_name.to_string());
+        match variant.fields {
+            syn::Fields::Unit => quote! {
+                #name::#variant_name => {
+                    pretty_print_indent(f, indent)?;
+                    f.write_str(#label)
+                }
+            },
+            // The fallback variant of non-exhaustive enums holds the `__typename` of the member.
+            syn::Fields::Named(_) => {
+                let format = format!("{}: {{:?}}\n", tag);
+                quote! {
+                    #name::#variant_name { typename } => {
+                        pretty_print_indent(f, indent)?;
+                        f.write_fmt(::std::format_args!(#format, typename))
+                    }
+                }
+            }
+            syn::Fields::Unnamed(_) => quote! {
+                #name::#variant_name(value) => {
+                    pretty_print_indent(f, indent)?;
+                    f.write_str(#label)?;
+                    value.pretty_print_fields(f, indent)
+                }
+            },
+        }
+    });
+
+    quote! {
+        impl PrettyPrint for #name {
+            fn pretty_print_fields(&self, f: &mut ::std::fmt::Formatter, indent: usize) -> ::std::fmt::Result {
+                match self {
+                    #(#variants,)*
+                }
+            }
+        }
+    }
+}
+
+fn enum_impl(name: &Ident, data: &syn::DataEnum) -> TokenStream {
+    let variants = data.variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+        match variant.fields {
+            syn::Fields::Unit => {
+                let value = variant_name.to_string();
+                quote!(#name::#variant_name => f.write_str(#value))
+            }
+            _ => quote!(#name::#variant_name(value) => f.write_str(value)),
+        }
+    });
+
+    quote! {
+        #[allow(deprecated)]
+        impl PrettyPrint for #name {
+            fn pretty_print(&self, f: &mut ::std::fmt::Formatter, _indent: usize) -> ::std::fmt::Result {
+                match self {
+                    #(#variants,)*
+                }
+            }
+        }
+    }
+}
+
+/// Custom scalars are printed with their `Debug` implementation.
+pub(crate) fn scalar_pretty_print_impl(context: &QueryContext, scalar_name: &str) -> TokenStream {
+    if !context.pretty_print {
+        return quote!();
+    }
+
+    let name = Ident::new(scalar_name, Span::call_site());
+    leaf_impl(&quote!(#name), quote!(::std::fmt::Debug::fmt(self, f)))
+}
+
+/// The string type of the response fields is printed quoted, like `String`, with its `Debug` implementation.
+pub(crate) fn string_type_pretty_print_impl(
+    context: &QueryContext,
+    ty: &TokenStream,
+) -> TokenStream {
+    if !context.pretty_print {
+        return quote!();
+    }
+
+    leaf_impl(ty, quote!(::std::fmt::Debug::fmt(self, f)))
+}
+
+/// `Display` for the response data, with its pretty printer.
+pub(crate) fn response_data_display(
+    context: &QueryContext,
+    response_data_name: &Ident,
+) -> TokenStream {
+    if !context.pretty_print {
+        return quote!();
+    }
+
+    quote! {
+        impl ::std::fmt::Display for #response_data_name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                PrettyPrint::pretty_print(self, f, 0)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::Schema;
+
+    #[test]
+    fn structs_print_their_fields_by_json_name() {
+        let schema = Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        let definition = quote! {
+            #[derive(Deserialize)]
+            pub struct Viewer {
+                #[serde(rename = "fullName")]
+                pub full_name: Option<String>,
+                #[serde(flatten)]
+                pub on: ViewerOn,
+            }
+        };
+
+        assert!(pretty_print_impl(&context, &definition).is_empty());
+
+        context.pretty_print = true;
+        assert_eq!(
+            pretty_print_impl(&context, &definition).to_string(),
+            "# [ allow ( deprecated ) ] impl PrettyPrint for Viewer { \
+             fn pretty_print_fields ( & self , f : & mut :: std :: fmt :: Formatter , indent : usize ) -> :: std :: fmt :: Result { \
+             pretty_print_indent ( f , indent ) ? ; f . write_str ( \"fullName: \" ) ? ; \
+             PrettyPrint :: pretty_print ( & self . full_name , f , indent ) ? ; f . write_str ( \"\\n\" ) ? ; \
+             PrettyPrint :: pretty_print_fields ( & self . on , f , indent ) ? ; \
+             :: std :: result :: Result :: Ok ( ( ) ) } }"
+        );
+    }
+}
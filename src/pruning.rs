@@ -0,0 +1,635 @@
+use codegen;
+use federation;
+use graphql_parser::{query, schema};
+use introspection_response::{
+    __TypeKind, FullTypeFields, FullTypeInputFields, IntrospectionResponse, TypeRef,
+};
+use operations::OperationType;
+use refetch::NODE_FIELD;
+use schema::{ParsedSchema, DEFAULT_SCALARS};
+use selection::{Selection, SelectionItem};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// The fields of an object or interface, as they are in the parsed document.
+#[derive(Clone, Copy)]
+enum Fields<'schema> {
+    Sdl(&'schema [schema::Field]),
+    Json(&'schema [Option<FullTypeFields>]),
+}
+
+/// The fields of an input object, as they are in the parsed document.
+#[derive(Clone, Copy)]
+enum InputFields<'schema> {
+    Sdl(&'schema [schema::InputValue]),
+    Json(&'schema [Option<FullTypeInputFields>]),
+}
+
+/// The named types a field refers to: the type of its value, and the types of its arguments.
+struct FieldTypes<'schema> {
+    type_: &'schema str,
+    arguments: Vec<&'schema str>,
+}
+
+impl<'schema> Fields<'schema> {
+    fn field(self, name: &str) -> Option<FieldTypes<'schema>> {
+        match self {
+            Fields::Sdl(fields) => {
+                fields
+                    .iter()
+                    .find(|field| field.name == name)
+                    .map(|field| FieldTypes {
+                        type_: sdl_named_type(&field.field_type),
+                        arguments: field
+                            .arguments
+                            .iter()
+                            .map(|argument| sdl_named_type(&argument.value_type))
+                            .collect(),
+                    })
+            }
+            Fields::Json(fields) => fields
+                .iter()
+                .filter_map(Option::as_ref)
+                .find(|field| field.name.as_deref() == Some(name))
+                .and_then(|field| {
+                    Some(FieldTypes {
+                        type_: json_named_type(&field.type_.as_ref()?.type_ref)?,
+                        arguments: field
+                            .args
+                            .iter()
+                            .flatten()
+                            .filter_map(Option::as_ref)
+                            .filter_map(|argument| argument.input_value.type_.as_ref())
+                            .filter_map(|ty| json_named_type(&ty.type_ref))
+                            .collect(),
+                    })
+                }),
+        }
+    }
+}
+
+impl<'schema> InputFields<'schema> {
+    fn types(self) -> Vec<&'schema str> {
+        match self {
+            InputFields::Sdl(fields) => fields
+                .iter()
+                .map(|field| sdl_named_type(&field.value_type))
+                .collect(),
+            InputFields::Json(fields) => fields
+                .iter()
+                .filter_map(Option::as_ref)
+                .filter_map(|field| field.input_value.type_.as_ref())
+                .filter_map(|ty| json_named_type(&ty.type_ref))
+                .collect(),
+        }
+    }
+}
+
+fn sdl_named_type(ty: &schema::Type) -> &str {
+    match ty {
+        schema::Type::NamedType(name) => name,
+        schema::Type::ListType(inner) | schema::Type::NonNullType(inner) => sdl_named_type(inner),
+    }
+}
+
+fn json_named_type(ty: &TypeRef) -> Option<&str> {
+    match (&ty.kind, &ty.of_type) {
+        (Some(__TypeKind::LIST), Some(inner)) | (Some(__TypeKind::NON_NULL), Some(inner)) => {
+            json_named_type(inner)
+        }
+        _ => ty.name.as_deref(),
+    }
+}
+
+/// What reachability needs to know about a parsed schema: the names of the types its fields, their arguments and the input fields refer to.
+///
+/// It borrows the parsed documents and converts nothing, so the types the operations cannot reach are never built, which keeps huge schemas like GitHub's cheap.
+pub(crate) struct TypeGraph<'schema> {
+    /// The fields of the objects and interfaces, with those of their extensions.
+    fields: HashMap<&'schema str, Vec<Fields<'schema>>>,
+    input_fields: HashMap<&'schema str, InputFields<'schema>>,
+    /// The interfaces each interface implements, whose fields it inherits.
+    parent_interfaces: HashMap<&'schema str, Vec<&'schema str>>,
+    /// The members of the unions, and the objects implementing the interfaces.
+    possible_types: HashMap<&'schema str, Vec<&'schema str>>,
+    /// The types of the arguments of the directives.
+    directive_argument_types: Vec<&'schema str>,
+    /// The fields of the first resolvable `@key` of the federation entities.
+    keys: HashMap<&'schema str, &'schema str>,
+    /// Whether an SDL document is a federation subgraph, whose query type has the `_entities` and `_service` fields.
+    is_subgraph: bool,
+    query_type: Option<&'schema str>,
+    mutation_type: Option<&'schema str>,
+    subscription_type: Option<&'schema str>,
+    type_names: HashSet<&'schema str>,
+}
+
+/// The types to keep on top of those the operations select.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Extent {
+    /// The `node` field of the query type and the fragments the operations do not spread, for the refetch queries.
+    pub(crate) refetch_queries: bool,
+    /// The possible types of the interfaces and unions, with the fields selected on them, for the JSON Schemas of the responses.
+    pub(crate) possible_types: bool,
+}
+
+/// The types the operations of a query document can reach.
+pub(crate) struct Reachable {
+    pub(crate) types: BTreeSet<String>,
+    /// The number of fragments used by the operations.
+    pub(crate) fragments_required: usize,
+}
+
+impl<'schema> TypeGraph<'schema> {
+    /// The graph of the types of the schema, and of the local types of the client schema.
+    pub(crate) fn new(
+        parsed_schema: &'schema ParsedSchema,
+        client_schema: Option<&'schema schema::Document>,
+    ) -> Self {
+        let mut graph = TypeGraph {
+            fields: HashMap::new(),
+            input_fields: HashMap::new(),
+            parent_interfaces: HashMap::new(),
+            possible_types: HashMap::new(),
+            directive_argument_types: Vec::new(),
+            keys: HashMap::new(),
+            is_subgraph: false,
+            query_type: None,
+            mutation_type: None,
+            subscription_type: None,
+            type_names: HashSet::new(),
+        };
+        match parsed_schema {
+            ParsedSchema::GraphQLParser(document) => graph.ingest_document(document),
+            ParsedSchema::Json(response) => graph.ingest_introspection(response),
+        }
+        if let Some(client_schema) = client_schema {
+            graph.ingest_document(client_schema);
+        }
+        graph
+    }
+
+    /// The number of types of the schema built from all the type definitions.
+    pub(crate) fn type_count(&self) -> usize {
+        self.type_names.len()
+    }
+
+    fn ingest_document(&mut self, document: &'schema schema::Document) {
+        use graphql_parser::schema::{Definition, TypeDefinition, TypeExtension};
+
+        for definition in &document.definitions {
+            match definition {
+                Definition::TypeDefinition(TypeDefinition::Object(object)) => {
+                    self.add_fields(&object.name, Fields::Sdl(&object.fields));
+                    self.add_implementations(&object.name, &object.implements_interfaces);
+                    self.add_key(&object.name, &object.directives);
+                }
+                Definition::TypeExtension(TypeExtension::Object(extension)) => {
+                    self.add_fields(&extension.name, Fields::Sdl(&extension.fields));
+                    self.add_implementations(&extension.name, &extension.implements_interfaces);
+                    self.add_key(&extension.name, &extension.directives);
+                }
+                Definition::TypeDefinition(TypeDefinition::Interface(interface)) => {
+                    self.add_fields(&interface.name, Fields::Sdl(&interface.fields));
+                    self.parent_interfaces.insert(
+                        &interface.name,
+                        ::sdl::implemented_interfaces(&interface.directives),
+                    );
+                }
+                Definition::TypeDefinition(TypeDefinition::Union(union)) => {
+                    self.type_names.insert(&union.name);
+                    self.possible_types
+                        .entry(&union.name)
+                        .or_default()
+                        .extend(union.types.iter().map(String::as_str));
+                }
+                Definition::TypeDefinition(TypeDefinition::InputObject(input)) => {
+                    self.type_names.insert(&input.name);
+                    self.input_fields
+                        .insert(&input.name, InputFields::Sdl(&input.fields));
+                }
+                Definition::TypeDefinition(TypeDefinition::Enum(enm)) => {
+                    self.type_names.insert(&enm.name);
+                }
+                Definition::TypeDefinition(TypeDefinition::Scalar(scalar)) => {
+                    self.type_names.insert(&scalar.name);
+                }
+                Definition::DirectiveDefinition(directive) => {
+                    self.directive_argument_types.extend(
+                        directive
+                            .arguments
+                            .iter()
+                            .map(|argument| sdl_named_type(&argument.value_type)),
+                    );
+                }
+                Definition::SchemaDefinition(definition) => {
+                    self.query_type = definition.query.as_deref();
+                    self.mutation_type = definition.mutation.as_deref();
+                    self.subscription_type = definition.subscription.as_deref();
+                }
+                Definition::TypeExtension(_) => (),
+            }
+        }
+
+        // The types federation adds to subgraphs, see `Schema::ingest_federation`.
+        let entities = federation::entity_names(document);
+        if !entities.is_empty() {
+            self.is_subgraph = true;
+            self.type_names.extend(&[
+                federation::ANY_SCALAR,
+                federation::ENTITY_UNION,
+                federation::SERVICE_TYPE,
+            ]);
+            self.possible_types
+                .entry(federation::ENTITY_UNION)
+                .or_default()
+                .extend(entities);
+        }
+    }
+
+    fn ingest_introspection(&mut self, response: &'schema IntrospectionResponse) {
+        let root = match response.as_schema().schema {
+            Some(ref root) => root,
+            None => return,
+        };
+        self.query_type = root
+            .query_type
+            .as_ref()
+            .and_then(|ty| ty.name.as_ref())
+            .map(String::as_str);
+        self.mutation_type = root
+            .mutation_type
+            .as_ref()
+            .and_then(|ty| ty.name.as_ref())
+            .map(String::as_str);
+        self.subscription_type = root
+            .subscription_type
+            .as_ref()
+            .and_then(|ty| ty.name.as_ref())
+            .map(String::as_str);
+
+        for ty in root
+            .types
+            .iter()
+            .flatten()
+            .filter_map(|ty| ty.as_ref().map(|ty| &ty.full_type))
+        {
+            let name = match ty.name {
+                Some(ref name) => name.as_str(),
+                None => continue,
+            };
+            match ty.kind {
+                Some(__TypeKind::OBJECT) => {
+                    self.add_fields(name, Fields::Json(ty.fields.as_deref().unwrap_or(&[])));
+                }
+                Some(__TypeKind::INTERFACE) => {
+                    self.add_fields(name, Fields::Json(ty.fields.as_deref().unwrap_or(&[])));
+                    self.parent_interfaces.insert(
+                        name,
+                        ty.interfaces
+                            .iter()
+                            .flatten()
+                            .filter_map(Option::as_ref)
+                            .filter_map(|parent| parent.type_ref.name.as_ref())
+                            .map(String::as_str)
+                            .collect(),
+                    );
+                }
+                Some(__TypeKind::INPUT_OBJECT) => {
+                    self.type_names.insert(name);
+                    self.input_fields.insert(
+                        name,
+                        InputFields::Json(ty.input_fields.as_deref().unwrap_or(&[])),
+                    );
+                }
+                // The schema does not keep the built-in scalars.
+                Some(__TypeKind::SCALAR) if DEFAULT_SCALARS.contains(&name) => (),
+                _ => {
+                    self.type_names.insert(name);
+                }
+            }
+            if let Some(possible_types) = ty.possible_types.as_ref() {
+                self.possible_types.insert(
+                    name,
+                    possible_types
+                        .iter()
+                        .filter_map(Option::as_ref)
+                        .filter_map(|possible_type| possible_type.type_ref.name.as_ref())
+                        .map(String::as_str)
+                        .collect(),
+                );
+            }
+        }
+
+        for directive in root.directives.iter().flatten().filter_map(Option::as_ref) {
+            self.directive_argument_types.extend(
+                directive
+                    .args
+                    .iter()
+                    .flatten()
+                    .filter_map(Option::as_ref)
+                    .filter_map(|argument| argument.input_value.type_.as_ref())
+                    .filter_map(|ty| json_named_type(&ty.type_ref)),
+            );
+        }
+    }
+
+    fn add_fields(&mut self, type_name: &'schema str, fields: Fields<'schema>) {
+        self.type_names.insert(type_name);
+        self.fields.entry(type_name).or_default().push(fields);
+    }
+
+    fn add_implementations(&mut self, object: &'schema str, interfaces: &'schema [String]) {
+        for interface in interfaces {
+            self.possible_types
+                .entry(interface)
+                .or_default()
+                .push(object);
+        }
+    }
+
+    fn add_key(&mut self, entity: &'schema str, directives: &'schema [schema::Directive]) {
+        if let Some(fields) = federation::declared_key_fields(directives) {
+            self.keys.entry(entity).or_insert(fields);
+        }
+    }
+
+    /// The root type of the operations of the type, like [Operation::root_name](../operations/struct.Operation.html#method.root_name).
+    fn root_type(&self, operation_type: &OperationType) -> &'schema str {
+        let declared = match *operation_type {
+            OperationType::Query => self.query_type,
+            OperationType::Mutation => self.mutation_type,
+            OperationType::Subscription => self.subscription_type,
+        };
+        declared.unwrap_or_else(|| operation_type.conventional_root_name())
+    }
+
+    /// A field of an object or interface, or of the interfaces an interface inherits from.
+    fn field(&self, type_name: &str, field_name: &str) -> Option<FieldTypes<'schema>> {
+        if self.is_subgraph && type_name == self.root_type(&OperationType::Query) {
+            match field_name {
+                federation::ENTITIES_FIELD => {
+                    return Some(FieldTypes {
+                        type_: federation::ENTITY_UNION,
+                        arguments: vec![federation::ANY_SCALAR],
+                    })
+                }
+                federation::SERVICE_FIELD => {
+                    return Some(FieldTypes {
+                        type_: federation::SERVICE_TYPE,
+                        arguments: Vec::new(),
+                    })
+                }
+                _ => (),
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut types = vec![type_name];
+        while let Some(type_name) = types.pop() {
+            if !visited.insert(type_name) {
+                continue;
+            }
+            let found = self
+                .fields
+                .get(type_name)
+                .into_iter()
+                .flatten()
+                .find_map(|fields| fields.field(field_name));
+            if found.is_some() {
+                return found;
+            }
+            types.extend(self.parent_interfaces.get(type_name).into_iter().flatten());
+        }
+        None
+    }
+
+    /// The types the operations of `query` can reach, and the types of the `extent`.
+    ///
+    /// Types are reachable when they are the root type of an operation, the type of a selected field or of its arguments, the type condition of a fragment, or the type of a variable or of a directive argument (including the types of the fields of input objects, transitively).
+    pub(crate) fn reachable(&self, query: &query::Document, extent: Extent) -> Reachable {
+        let mut reachability = Reachability {
+            graph: self,
+            extent,
+            fragments: query
+                .definitions
+                .iter()
+                .filter_map(|definition| match definition {
+                    query::Definition::Fragment(fragment) => {
+                        let query::TypeCondition::On(ref on) = fragment.type_condition;
+                        Some((
+                            fragment.name.as_str(),
+                            (on.as_str(), Selection::from(&fragment.selection_set)),
+                        ))
+                    }
+                    query::Definition::Operation(_) => None,
+                })
+                .collect(),
+            visited_fragments: BTreeSet::new(),
+            reachable: BTreeSet::new(),
+        };
+
+        for type_name in &self.directive_argument_types {
+            reachability.mark_input(type_name);
+        }
+
+        for operation in codegen::all_operations(query) {
+            let root_name = self.root_type(&operation.operation_type);
+            reachability.mark(root_name);
+            reachability.walk_selection(root_name, &operation.selection);
+
+            for variable in &operation.variables {
+                reachability.mark_input(variable.ty.inner_name_str());
+            }
+        }
+        let fragments_required = reachability.visited_fragments.len();
+
+        if extent.refetch_queries {
+            let query_type = self.root_type(&OperationType::Query);
+            reachability.mark(query_type);
+            if let Some(node) = self.field(query_type, NODE_FIELD) {
+                reachability.mark(node.type_);
+                for argument in node.arguments {
+                    reachability.mark_input(argument);
+                }
+            }
+            let fragment_names: Vec<&str> = reachability.fragments.keys().cloned().collect();
+            for fragment_name in fragment_names {
+                reachability.walk_fragment(fragment_name);
+            }
+        }
+
+        // Federation entities keep the types of their key fields, which their representations are made of.
+        let reachable_types: Vec<String> = reachability.reachable.iter().cloned().collect();
+        for type_name in reachable_types {
+            if let Some(Ok(key)) = self
+                .keys
+                .get(type_name.as_str())
+                .map(|fields| federation::parse_key(fields))
+            {
+                reachability.walk_key(&type_name, &key);
+            }
+        }
+
+        Reachable {
+            types: reachability.reachable,
+            fragments_required,
+        }
+    }
+}
+
+/// Collects the names of all the schema types the query document can reach.
+struct Reachability<'a, 'schema: 'a, 'query> {
+    graph: &'a TypeGraph<'schema>,
+    extent: Extent,
+    fragments: BTreeMap<&'query str, (&'query str, Selection<'query>)>,
+    visited_fragments: BTreeSet<&'query str>,
+    reachable: BTreeSet<String>,
+}
+
+impl<'a, 'schema, 'query> Reachability<'a, 'schema, 'query> {
+    fn mark(&mut self, type_name: &str) -> bool {
+        self.reachable.insert(type_name.to_owned())
+    }
+
+    fn mark_input(&mut self, type_name: &str) {
+        if !self.mark(type_name) {
+            return;
+        }
+
+        let graph = self.graph;
+        if let Some(input_fields) = graph.input_fields.get(type_name) {
+            for field_type in input_fields.types() {
+                self.mark_input(field_type);
+            }
+        }
+    }
+
+    fn walk_selection(&mut self, on: &str, selection: &Selection<'query>) {
+        let graph = self.graph;
+
+        if self.extent.possible_types {
+            for possible_type in graph.possible_types.get(on).into_iter().flatten() {
+                if *possible_type != on {
+                    self.mark(possible_type);
+                    self.walk_selection(possible_type, selection);
+                }
+            }
+        }
+
+        for item in &selection.0 {
+            match item {
+                SelectionItem::Field(field) => {
+                    if let Some(schema_field) = graph.field(on, field.name) {
+                        self.mark(schema_field.type_);
+                        for argument in schema_field.arguments {
+                            self.mark_input(argument);
+                        }
+                        self.walk_selection(schema_field.type_, &field.fields);
+                    }
+                }
+                SelectionItem::InlineFragment(inline_fragment) => {
+                    self.mark(inline_fragment.on);
+                    self.walk_selection(inline_fragment.on, &inline_fragment.fields);
+                }
+                SelectionItem::FragmentSpread(spread) => self.walk_fragment(spread.fragment_name),
+            }
+        }
+    }
+
+    fn walk_fragment(&mut self, fragment_name: &'query str) {
+        if !self.visited_fragments.insert(fragment_name) {
+            return;
+        }
+
+        if let Some((fragment_on, fragment_selection)) = self.fragments.get(fragment_name).cloned()
+        {
+            self.mark(fragment_on);
+            self.walk_selection(fragment_on, &fragment_selection);
+        }
+    }
+
+    fn walk_key(&mut self, on: &str, selection_set: &query::SelectionSet) {
+        let graph = self.graph;
+
+        for item in &selection_set.items {
+            if let query::Selection::Field(field) = item {
+                if let Some(schema_field) = graph.field(on, &field.name) {
+                    self.mark(schema_field.type_);
+                    self.walk_key(schema_field.type_, &field.selection_set);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphql_parser;
+    use schema::Schema;
+
+    #[test]
+    fn only_reachable_types_are_built() {
+        let parsed = ParsedSchema::GraphQLParser(
+            graphql_parser::parse_schema(include_str!("tests/star_wars_schema.graphql")).unwrap(),
+        );
+        let query = graphql_parser::parse_query(
+            r#"
+            query HeroAndReview($episode: Episode!, $review: ReviewInput!) {
+              hero(episode: $episode) {
+                __typename
+                name
+                ...on Human {
+                  starships { name }
+                }
+              }
+            }
+            "#,
+        )
+        .unwrap();
+        let graph = TypeGraph::new(&parsed, None);
+
+        let reachable = graph.reachable(&query, Extent::default());
+        let schema = Schema::reachable(&parsed, &reachable.types);
+
+        assert_eq!(
+            schema.objects.keys().collect::<Vec<_>>(),
+            vec![&"Human", &"Query", &"Starship"]
+        );
+        assert_eq!(
+            schema.interfaces.keys().collect::<Vec<_>>(),
+            vec![&"Character"]
+        );
+        assert_eq!(schema.enums.keys().collect::<Vec<_>>(), vec![&"Episode"]);
+        assert_eq!(
+            schema.inputs.keys().collect::<Vec<_>>(),
+            vec![&"ColorInput", &"ReviewInput"]
+        );
+        assert!(schema.unions.is_empty());
+        assert!(graph.type_count() > ::stats::type_count(&schema));
+    }
+
+    #[test]
+    fn possible_types_are_reachable_in_their_extent() {
+        let parsed = ParsedSchema::GraphQLParser(
+            graphql_parser::parse_schema(include_str!("tests/star_wars_schema.graphql")).unwrap(),
+        );
+        let query = graphql_parser::parse_query("query Hero { hero { __typename name } }").unwrap();
+        let graph = TypeGraph::new(&parsed, None);
+
+        let selected = graph.reachable(&query, Extent::default()).types;
+        let with_possible_types = graph
+            .reachable(
+                &query,
+                Extent {
+                    possible_types: true,
+                    ..Extent::default()
+                },
+            )
+            .types;
+
+        assert!(!selected.contains("Droid"));
+        assert!(with_possible_types.contains("Droid"));
+        assert!(with_possible_types.contains("Human"));
+    }
+}
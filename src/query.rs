@@ -1,19 +1,108 @@
+use big_numbers::BigNumberStrategy;
+use constants::DEFAULT_MAX_SELECTION_DEPTH;
+use defaults::DefaultVariants;
 use deprecation::DeprecationStrategy;
+use derive_profile::DeriveProfile;
 use failure;
 use fragments::GqlFragment;
+use graphql_parser::query::Directive;
+use heck::CamelCase;
+use hooks::{CodegenHooks, GeneratedItem, ItemKind};
+use indirection::Indirection;
 use itertools::Itertools;
 use proc_macro2::Span;
 use proc_macro2::TokenStream;
+use redaction::SensitiveFields;
 use schema::Schema;
 use selection::Selection;
+use serde_adapters::SerdeAdapters;
+use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet};
-use syn::Ident;
+use std::sync::Arc;
+use syn::{self, Ident};
+use union_mode::UnionMode;
+
+/// The code generated for a type, and the selections nested in its selection, whose types are still to generate.
+pub(crate) type Expansion<'query> = Result<(TokenStream, Vec<Nested<'query>>), failure::Error>;
+
+/// A selection nested in another one, its types generated by [QueryContext::expand_nested] after those of the enclosing selection.
+pub(crate) enum Nested<'query> {
+    /// The selection on a field or union variant of type `on`, generating the type named `prefix`. `key` is the response key or type condition leading to it, for error messages.
+    Selection {
+        on: String,
+        selection: Selection<'query>,
+        prefix: String,
+        key: String,
+    },
+    /// Code that needs no further expansion, like the alias to the type generated for an identical selection.
+    Expanded(TokenStream),
+}
 
 /// This holds all the information we need during the code generation phase.
 pub(crate) struct QueryContext<'query, 'schema: 'query> {
     pub fragments: BTreeMap<&'query str, GqlFragment<'query>>,
     pub schema: &'schema Schema<'schema>,
     pub deprecation_strategy: DeprecationStrategy,
+    /// Per-field strategies, as `(type, field) -> strategy`, taking precedence over `deprecation_strategy`.
+    deprecation_overrides: BTreeMap<(String, String), DeprecationStrategy>,
+    /// The deprecated fields selected in the query, as `(type, field) -> reason`, for `DeprecationStrategy::WarnAlways`.
+    deprecated_fields: RefCell<BTreeMap<(String, String), Option<String>>>,
+    /// The names of the structs generated for the selections on fields, as `(enclosing struct, response key) -> name`. See [QueryContext::field_struct_name].
+    field_struct_names: RefCell<BTreeMap<(String, String), String>>,
+    /// The Rust types of the custom scalars, by the URL in their `@specifiedBy` directive.
+    specified_by_overrides: BTreeMap<String, syn::Type>,
+    /// The representations of the big-number scalars, by scalar name.
+    big_number_scalars: BTreeMap<String, BigNumberStrategy>,
+    /// The scalars holding base64-encoded binary data.
+    bytes_scalars: BTreeSet<String>,
+    /// Whether to generate a builder for each input object.
+    pub input_builders: bool,
+    /// Whether to generate `to_graphql_value()` on the variables, input objects and enums.
+    pub graphql_values: bool,
+    /// Whether to implement `Mock` for the response types.
+    pub mocks: bool,
+    /// Whether to implement `Default` for the response types.
+    pub default_impls: bool,
+    /// The variants `Default` picks for the enums of the responses.
+    pub default_variants: DefaultVariants,
+    /// Whether to generate iterators over the items of the nullable lists of the responses.
+    pub list_iterators: bool,
+    /// Whether to generate the classification of the top-level fields of responses with errors.
+    pub partial_data: bool,
+    /// Whether to generate the enum of the top-level fields of the operation.
+    pub error_paths: bool,
+    /// Whether to derive `proptest_derive::Arbitrary` for the generated types.
+    pub arbitrary: bool,
+    /// Whether to derive `specta::Type` for the generated types.
+    pub specta: bool,
+    /// Whether to implement `PrettyPrint` for the response types.
+    pub pretty_print: bool,
+    /// The pointer holding the nested response structs, and which ones.
+    pub nested_indirection: Option<Indirection>,
+    /// The Rust type of the `String` fields of the responses, if not `String`.
+    response_string_type: Option<syn::Type>,
+    /// The `serde(with)` modules of the fields.
+    pub serde_adapters: SerdeAdapters,
+    /// Whether to deserialize the built-in scalars of the responses from their string form too.
+    pub lenient_scalars: bool,
+    /// Whether the nullable response fields default to `None` when they are missing.
+    pub nullable_defaults: bool,
+    /// Whether the response structs deny unknown fields, where serde allows it.
+    pub deny_unknown_fields: bool,
+    /// The names of the structs flattened into others, which cannot deny unknown fields.
+    flattened_structs: RefCell<BTreeSet<String>>,
+    /// The fields masked in `Debug` and pretty-printed output, besides those marked `@sensitive`.
+    pub sensitive_fields: SensitiveFields,
+    /// The Rust names of the masked fields, by struct name.
+    redacted_fields: RefCell<BTreeMap<String, BTreeSet<String>>>,
+    /// Whether the enums of unions have a fallback variant.
+    pub union_mode: UnionMode,
+    /// Whether to implement `ValidateJson` for the response types.
+    pub json_validation: bool,
+    /// How deeply selection sets may be nested.
+    pub max_selection_depth: usize,
+    /// Callbacks to apply to the generated items.
+    pub hooks: Option<Arc<dyn CodegenHooks>>,
     variables_derives: Vec<Ident>,
     response_derives: Vec<Ident>,
 }
@@ -28,6 +117,36 @@ impl<'query, 'schema> QueryContext<'query, 'schema> {
             fragments: BTreeMap::new(),
             schema,
             deprecation_strategy,
+            deprecation_overrides: BTreeMap::new(),
+            deprecated_fields: RefCell::new(BTreeMap::new()),
+            field_struct_names: RefCell::new(BTreeMap::new()),
+            specified_by_overrides: BTreeMap::new(),
+            big_number_scalars: BTreeMap::new(),
+            bytes_scalars: BTreeSet::new(),
+            input_builders: false,
+            graphql_values: false,
+            mocks: false,
+            default_impls: false,
+            default_variants: DefaultVariants::default(),
+            list_iterators: false,
+            partial_data: false,
+            error_paths: false,
+            arbitrary: false,
+            specta: false,
+            pretty_print: false,
+            nested_indirection: None,
+            response_string_type: None,
+            serde_adapters: SerdeAdapters::default(),
+            lenient_scalars: false,
+            nullable_defaults: false,
+            deny_unknown_fields: false,
+            flattened_structs: RefCell::new(BTreeSet::new()),
+            sensitive_fields: SensitiveFields::default(),
+            redacted_fields: RefCell::new(BTreeMap::new()),
+            union_mode: UnionMode::default(),
+            json_validation: false,
+            max_selection_depth: DEFAULT_MAX_SELECTION_DEPTH,
+            hooks: None,
             variables_derives: vec![Ident::new("Serialize", Span::call_site())],
             response_derives: vec![Ident::new("Deserialize", Span::call_site())],
         }
@@ -40,6 +159,123 @@ impl<'query, 'schema> QueryContext<'query, 'schema> {
         }
     }
 
+    /// The deprecation strategy for a field, taking the overrides into account.
+    pub(crate) fn deprecation_strategy_for(
+        &self,
+        type_name: &str,
+        field_name: &str,
+    ) -> &DeprecationStrategy {
+        self.deprecation_overrides
+            .get(&(type_name.to_owned(), field_name.to_owned()))
+            .unwrap_or(&self.deprecation_strategy)
+    }
+
+    /// Remember that a deprecated field was selected, so `deprecation_warnings` can report it.
+    pub(crate) fn record_deprecated_field(
+        &self,
+        type_name: &str,
+        field_name: &str,
+        reason: Option<&String>,
+    ) {
+        if *self.deprecation_strategy_for(type_name, field_name) == DeprecationStrategy::WarnAlways
+        {
+            self.deprecated_fields.borrow_mut().insert(
+                (type_name.to_owned(), field_name.to_owned()),
+                reason.cloned(),
+            );
+        }
+    }
+
+    /// The name of the struct generated for the selection on the field with the given response key, in the struct named `prefix`: the two camel-cased and concatenated. Names like this can collide, as with `viewerFriends: viewer` and `viewer { friends }`, or aliases differing only in case, so a name already given to another field, or taken by a fragment, gets the smallest numeric suffix free, the first field in generation order keeping the plain name.
+    pub(crate) fn field_struct_name(&self, prefix: &str, response_key: &str) -> String {
+        let key = (prefix.to_owned(), response_key.to_owned());
+        let mut names = self.field_struct_names.borrow_mut();
+        if let Some(name) = names.get(&key) {
+            return name.clone();
+        }
+
+        let base = format!("{}{}", prefix.to_camel_case(), response_key.to_camel_case());
+        let is_taken = |name: &str| {
+            self.fragments.contains_key(name) || names.values().any(|taken| taken == name)
+        };
+        let name = if is_taken(&base) {
+            (2..)
+                .map(|suffix| format!("{}{}", base, suffix))
+                .find(|name| !is_taken(name))
+                .expect("free struct name")
+        } else {
+            base
+        };
+        names.insert(key, name.clone());
+        name
+    }
+
+    /// Remember that the struct named `struct_name` is flattened into another one.
+    pub(crate) fn record_flattened_struct(&self, struct_name: &str) {
+        self.flattened_structs
+            .borrow_mut()
+            .insert(struct_name.to_owned());
+    }
+
+    /// Whether the struct named `struct_name` is flattened into another one.
+    pub(crate) fn is_flattened_struct(&self, struct_name: &str) -> bool {
+        self.flattened_structs.borrow().contains(struct_name)
+    }
+
+    /// Remember that a field of the struct named `struct_name` is masked.
+    pub(crate) fn record_redacted_field(&self, struct_name: &str, field_name: &str) {
+        self.redacted_fields
+            .borrow_mut()
+            .entry(struct_name.to_owned())
+            .or_default()
+            .insert(field_name.to_owned());
+    }
+
+    /// The Rust names of the masked fields of the struct named `struct_name`.
+    pub(crate) fn redacted_fields(&self, struct_name: &str) -> BTreeSet<String> {
+        self.redacted_fields
+            .borrow()
+            .get(struct_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// A function using a deprecated marker item for every deprecated field selected in the operation, so rustc warns about them even if the fields are never read.
+    pub(crate) fn deprecation_warnings(&self, operation_name: &str) -> TokenStream {
+        let deprecated_fields = self.deprecated_fields.borrow();
+
+        if deprecated_fields.is_empty() {
+            return quote!();
+        }
+
+        let function_name = Ident::new(
+            &format!("deprecated_fields_in_{}", operation_name),
+            Span::call_site(),
+        );
+        let markers = deprecated_fields
+            .iter()
+            .map(|((type_name, field_name), reason)| {
+                let marker =
+                    Ident::new(&format!("{}_{}", type_name, field_name), Span::call_site());
+                let deprecated = match reason {
+                    Some(reason) => quote!(#[deprecated(note = #reason)]),
+                    None => quote!(#[deprecated]),
+                };
+                quote! {
+                    #deprecated
+                    struct #marker;
+                    let _ = #marker;
+                }
+            });
+
+        quote! {
+            #[allow(non_snake_case, non_camel_case_types)]
+            fn #function_name() {
+                #(#markers)*
+            }
+        }
+    }
+
     /// For testing only. creates an empty QueryContext with an empty Schema.
     #[cfg(test)]
     pub(crate) fn new_empty(schema: &'schema Schema) -> QueryContext<'query, 'schema> {
@@ -47,37 +283,165 @@ impl<'query, 'schema> QueryContext<'query, 'schema> {
             fragments: BTreeMap::new(),
             schema,
             deprecation_strategy: DeprecationStrategy::Allow,
+            deprecation_overrides: BTreeMap::new(),
+            deprecated_fields: RefCell::new(BTreeMap::new()),
+            field_struct_names: RefCell::new(BTreeMap::new()),
+            specified_by_overrides: BTreeMap::new(),
+            big_number_scalars: BTreeMap::new(),
+            bytes_scalars: BTreeSet::new(),
+            input_builders: false,
+            graphql_values: false,
+            mocks: false,
+            default_impls: false,
+            default_variants: DefaultVariants::default(),
+            list_iterators: false,
+            partial_data: false,
+            error_paths: false,
+            arbitrary: false,
+            specta: false,
+            pretty_print: false,
+            nested_indirection: None,
+            response_string_type: None,
+            serde_adapters: SerdeAdapters::default(),
+            lenient_scalars: false,
+            nullable_defaults: false,
+            deny_unknown_fields: false,
+            flattened_structs: RefCell::new(BTreeSet::new()),
+            sensitive_fields: SensitiveFields::default(),
+            redacted_fields: RefCell::new(BTreeMap::new()),
+            union_mode: UnionMode::default(),
+            json_validation: false,
+            max_selection_depth: DEFAULT_MAX_SELECTION_DEPTH,
+            hooks: None,
             variables_derives: vec![Ident::new("Serialize", Span::call_site())],
             response_derives: vec![Ident::new("Deserialize", Span::call_site())],
         }
     }
 
-    /// Expand the deserialization data structures for the given field.
-    pub(crate) fn maybe_expand_field(
+    /// The code of an item, after the hooks inspected or changed it.
+    pub(crate) fn hooked(
+        &self,
+        kind: ItemKind,
+        graphql_type: &str,
+        rust_name: &str,
+        tokens: TokenStream,
+    ) -> TokenStream {
+        // The struct of a fragment is the one named after it.
+        let directives = match self.fragments.get(rust_name) {
+            Some(fragment) if fragment.on == graphql_type => fragment.directives,
+            _ => &[],
+        };
+        self.hooked_with_directives(kind, graphql_type, rust_name, directives, tokens)
+    }
+
+    /// Like [QueryContext::hooked], for the items generated for an operation, with its directives.
+    pub(crate) fn hooked_with_directives(
+        &self,
+        kind: ItemKind,
+        graphql_type: &str,
+        rust_name: &str,
+        directives: &[Directive],
+        tokens: TokenStream,
+    ) -> TokenStream {
+        let hooks = match self.hooks {
+            Some(ref hooks) => hooks,
+            None => return tokens,
+        };
+        let mut item = GeneratedItem {
+            kind,
+            graphql_type,
+            rust_name,
+            directives,
+            tokens,
+        };
+        hooks.generated_item(&mut item);
+        item.tokens
+    }
+
+    /// Expand the deserialization data structures for the given field. The selections nested in it are returned rather than expanded, see [QueryContext::expand_nested].
+    pub(crate) fn maybe_expand_field<'s>(
         &self,
         ty: &str,
-        selection: &Selection,
+        selection: &Selection<'s>,
         prefix: &str,
-    ) -> Result<TokenStream, failure::Error> {
+    ) -> Expansion<'s>
+    where
+        'query: 's,
+    {
         if self.schema.contains_scalar(ty) {
-            Ok(quote!())
+            Ok((quote!(), Vec::new()))
         } else if let Some(enm) = self.schema.enums.get(ty) {
             enm.is_required.set(true);
-            Ok(quote!()) // we already expand enums separately
+            Ok((quote!(), Vec::new())) // we already expand enums separately
         } else if let Some(obj) = self.schema.objects.get(ty) {
             obj.is_required.set(true);
-            obj.response_for_selection(self, &selection, prefix)
+            obj.expand_selection(self, selection, prefix)
         } else if let Some(iface) = self.schema.interfaces.get(ty) {
             iface.is_required.set(true);
-            iface.response_for_selection(self, &selection, prefix)
+            iface.expand_selection(self, selection, prefix)
         } else if let Some(unn) = self.schema.unions.get(ty) {
             unn.is_required.set(true);
-            unn.response_for_selection(self, &selection, prefix)
+            unn.expand_selection(self, selection, prefix)
         } else {
             Err(format_err!("Unknown type: {}", ty))
         }
     }
 
+    /// The code of an expansion, preceded by the code for the selections nested in it and, in turn, in those. The nested selections are expanded with an explicit stack rather than recursion, so deeply nested queries cannot overflow the stack, and those more than `max_selection_depth` selection sets deep are reported with their path from `path`.
+    pub(crate) fn expand_nested<'s>(
+        &self,
+        path: &str,
+        (tokens, nested): (TokenStream, Vec<Nested<'s>>),
+    ) -> Result<TokenStream, failure::Error>
+    where
+        'query: 's,
+    {
+        // Popping the nested selections in reverse order and reversing the result puts the code of every type after that of its nested selections, in selection order.
+        let mut expanded = vec![tokens];
+        let mut pending: Vec<(Nested<'s>, usize, String)> = nested
+            .into_iter()
+            .map(|nested| (nested, 2, path.to_owned()))
+            .collect();
+
+        while let Some((nested, depth, parent_path)) = pending.pop() {
+            let (on, selection, prefix, key) = match nested {
+                Nested::Selection {
+                    on,
+                    selection,
+                    prefix,
+                    key,
+                } => (on, selection, prefix, key),
+                Nested::Expanded(tokens) => {
+                    expanded.push(tokens);
+                    continue;
+                }
+            };
+            // Type conditions read like `hero ... on Droid`.
+            let separator = if key.starts_with("...") { " " } else { "." };
+            let path = format!("{}{}{}", parent_path, separator, key);
+            if depth > self.max_selection_depth && !selection.0.is_empty() {
+                return Err(format_err!(
+                    "The selection on `{}` at `{}` is nested {} selection sets deep, more than the limit of {}. Raise `max_selection_depth` to generate it.",
+                    on,
+                    path,
+                    depth,
+                    self.max_selection_depth
+                ));
+            }
+
+            let (tokens, nested) = self.maybe_expand_field(&on, &selection, &prefix)?;
+            expanded.push(tokens);
+            pending.extend(
+                nested
+                    .into_iter()
+                    .map(|nested| (nested, depth + 1, path.clone())),
+            );
+        }
+
+        expanded.reverse();
+        Ok(quote!(#(#expanded)*))
+    }
+
     pub(crate) fn ingest_additional_derives(
         &mut self,
         attribute_value: &str,
@@ -103,8 +467,172 @@ impl<'query, 'schema> QueryContext<'query, 'schema> {
         Ok(())
     }
 
+    /// Derive the traits of the profile on every generated type, on top of the additional derives.
+    pub(crate) fn ingest_derive_profile(&mut self, profile: &DeriveProfile) {
+        self.variables_derives.extend(
+            profile
+                .variables_derives()
+                .iter()
+                .map(|s| Ident::new(s, Span::call_site())),
+        );
+        self.response_derives.extend(
+            profile
+                .response_derives()
+                .iter()
+                .map(|s| Ident::new(s, Span::call_site())),
+        );
+    }
+
+    /// Parse per-field deprecation strategies, like `User.legacyId=allow, Query.viewer=warn`.
+    pub(crate) fn ingest_deprecation_overrides(
+        &mut self,
+        attribute_value: &str,
+    ) -> Result<(), failure::Error> {
+        for item in attribute_value.split(',').filter(|s| !s.trim().is_empty()) {
+            let (field, strategy) = match item.splitn(2, '=').collect::<Vec<_>>().as_slice() {
+                [field, strategy] => (field.trim(), strategy.parse::<DeprecationStrategy>()?),
+                _ => {
+                    return Err(format_err!(
+                        "Invalid deprecation override: `{}`. Expected `Type.field=strategy`.",
+                        item.trim()
+                    ))
+                }
+            };
+            let (type_name, field_name) = match field.splitn(2, '.').collect::<Vec<_>>().as_slice()
+            {
+                [type_name, field_name] => (type_name.trim(), field_name.trim()),
+                _ => {
+                    return Err(format_err!(
+                        "Invalid deprecation override: `{}`. Expected `Type.field=strategy`.",
+                        item.trim()
+                    ))
+                }
+            };
+
+            self.deprecation_overrides
+                .insert((type_name.to_owned(), field_name.to_owned()), strategy);
+        }
+        Ok(())
+    }
+
+    /// Parse the Rust types of the custom scalars by specification URL, like `https://tools.ietf.org/html/rfc3339=chrono::DateTime<chrono::Utc>`.
+    pub(crate) fn ingest_specified_by_overrides(
+        &mut self,
+        attribute_value: &str,
+    ) -> Result<(), failure::Error> {
+        for item in attribute_value.split(',').filter(|s| !s.trim().is_empty()) {
+            // URLs may contain `=` in their query string, Rust types hardly ever do.
+            let (url, ty) = match item.rsplitn(2, '=').collect::<Vec<_>>().as_slice() {
+                [ty, url] => (url.trim(), ty.trim()),
+                _ => {
+                    return Err(format_err!(
+                        "Invalid scalar override: `{}`. Expected `url=RustType`.",
+                        item.trim()
+                    ))
+                }
+            };
+            let ty = syn::parse_str::<syn::Type>(ty)
+                .map_err(|err| format_err!("Invalid Rust type for `{}`: {}", url, err))?;
+
+            self.specified_by_overrides.insert(url.to_owned(), ty);
+        }
+        Ok(())
+    }
+
+    /// The Rust type for the custom scalars specified by `url`, if there is one in the overrides.
+    pub(crate) fn specified_by_override(&self, url: &str) -> Option<&syn::Type> {
+        self.specified_by_overrides.get(url)
+    }
+
+    /// Parse the Rust type of the `String` fields of the responses.
+    pub(crate) fn ingest_response_string_type(&mut self, ty: &str) -> Result<(), failure::Error> {
+        let ty = syn::parse_str::<syn::Type>(ty)
+            .map_err(|err| format_err!("Invalid response string type `{}`: {}", ty, err))?;
+        self.response_string_type = Some(ty);
+        Ok(())
+    }
+
+    /// The Rust type of the `String` fields of the responses, if not `String`.
+    pub(crate) fn response_string_type(&self) -> Option<&syn::Type> {
+        self.response_string_type.as_ref()
+    }
+
+    /// Parse the representations of big-number scalars, like `BigInt=i128, Long=string`.
+    pub(crate) fn ingest_big_number_scalars(
+        &mut self,
+        attribute_value: &str,
+    ) -> Result<(), failure::Error> {
+        for item in attribute_value.split(',').filter(|s| !s.trim().is_empty()) {
+            let (scalar, strategy) = match item.splitn(2, '=').collect::<Vec<_>>().as_slice() {
+                [scalar, strategy] => (scalar.trim(), strategy.parse::<BigNumberStrategy>()?),
+                _ => {
+                    return Err(format_err!(
+                        "Invalid big number scalar: `{}`. Expected `Scalar=representation`.",
+                        item.trim()
+                    ))
+                }
+            };
+
+            self.big_number_scalars.insert(scalar.to_owned(), strategy);
+        }
+        Ok(())
+    }
+
+    /// The representation of a big-number scalar, if it has one in the options.
+    pub(crate) fn big_number_strategy(&self, scalar_name: &str) -> Option<BigNumberStrategy> {
+        self.big_number_scalars.get(scalar_name).cloned()
+    }
+
+    /// Parse the names of the scalars holding base64-encoded binary data, like `Bytes, Base64`.
+    pub(crate) fn ingest_bytes_scalars(&mut self, attribute_value: &str) {
+        self.bytes_scalars.extend(
+            attribute_value
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(String::from),
+        );
+    }
+
+    /// Whether the scalar holds base64-encoded binary data.
+    pub(crate) fn is_bytes_scalar(&self, scalar_name: &str) -> bool {
+        self.bytes_scalars.contains(scalar_name)
+    }
+
+    /// `Arbitrary`, and `Debug` which proptest requires of the types it generates.
+    fn arbitrary_derives(&self) -> Vec<Ident> {
+        if self.arbitrary {
+            vec![
+                Ident::new("Debug", Span::call_site()),
+                Ident::new("Arbitrary", Span::call_site()),
+            ]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn specta_derive(&self) -> Option<Ident> {
+        if self.specta {
+            Some(Ident::new("Type", Span::call_site()))
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn has_variables_derive(&self, derive: &str) -> bool {
+        self.variables_derives.iter().any(|ident| ident == derive)
+    }
+
     pub(crate) fn variables_derives(&self) -> TokenStream {
-        let derives = self.variables_derives.iter().unique();
+        let arbitrary = self.arbitrary_derives();
+        let specta = self.specta_derive();
+        let derives = self
+            .variables_derives
+            .iter()
+            .chain(arbitrary.iter())
+            .chain(specta.iter())
+            .unique()
+            .map(derive_path);
 
         quote! {
             #[derive( #(#derives),* )]
@@ -112,7 +640,15 @@ impl<'query, 'schema> QueryContext<'query, 'schema> {
     }
 
     pub(crate) fn response_derives(&self) -> TokenStream {
-        let derives = self.response_derives.iter().unique();
+        let arbitrary = self.arbitrary_derives();
+        let specta = self.specta_derive();
+        let derives = self
+            .response_derives
+            .iter()
+            .chain(arbitrary.iter())
+            .chain(specta.iter())
+            .unique()
+            .map(derive_path);
 
         quote! {
             #[derive( #(#derives),* )]
@@ -124,6 +660,8 @@ impl<'query, 'schema> QueryContext<'query, 'schema> {
             Ident::new("Eq", Span::call_site()),
             Ident::new("PartialEq", Span::call_site()),
         ];
+        let arbitrary = self.arbitrary_derives();
+        let specta = self.specta_derive();
         let mut enum_derives: BTreeSet<_> = self
             .response_derives
             .iter()
@@ -133,12 +671,31 @@ impl<'query, 'schema> QueryContext<'query, 'schema> {
             })
             .collect();
         enum_derives.extend(always_derives.iter());
+        enum_derives.extend(arbitrary.iter());
+        enum_derives.extend(specta.iter());
+        let enum_derives = enum_derives.into_iter().map(derive_path);
         quote! {
             #[derive( #(#enum_derives),* )]
         }
     }
 }
 
+/// The path of a derive macro, so the generated code does not depend on the prelude or on `#[macro_use]`. Other derives are left as they are.
+pub(crate) fn derive_path(derive: &Ident) -> TokenStream {
+    match derive.to_string().as_str() {
+        "Clone" => quote!(::std::clone::Clone),
+        "Copy" => quote!(::std::marker::Copy),
+        "Debug" => quote!(::std::fmt::Debug),
+        "Default" => quote!(::std::default::Default),
+        "Eq" | "PartialEq" | "Ord" | "PartialOrd" => quote!(::std::cmp::#derive),
+        "Hash" => quote!(::std::hash::Hash),
+        "Serialize" | "Deserialize" => quote!(::serde_derive::#derive),
+        "Arbitrary" => quote!(::proptest_derive::Arbitrary),
+        "Type" => quote!(::specta::Type),
+        _ => quote!(#derive),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,7 +711,7 @@ mod tests {
 
         assert_eq!(
             context.response_derives().to_string(),
-            "# [ derive ( Deserialize , PartialEq , PartialOrd , Serialize ) ]"
+            "# [ derive ( :: serde_derive :: Deserialize , :: std :: cmp :: PartialEq , :: std :: cmp :: PartialOrd , :: serde_derive :: Serialize ) ]"
         );
     }
 
@@ -164,7 +721,7 @@ mod tests {
         let context = QueryContext::new_empty(&schema);
         assert_eq!(
             context.response_enum_derives().to_string(),
-            "# [ derive ( Eq , PartialEq ) ]"
+            "# [ derive ( :: std :: cmp :: Eq , :: std :: cmp :: PartialEq ) ]"
         );
     }
 
@@ -179,8 +736,134 @@ mod tests {
 
         assert_eq!(
             context.response_enum_derives().to_string(),
-            "# [ derive ( Eq , PartialEq , PartialOrd ) ]"
+            "# [ derive ( :: std :: cmp :: Eq , :: std :: cmp :: PartialEq , :: std :: cmp :: PartialOrd ) ]"
+        );
+    }
+
+    #[test]
+    fn derive_profile_applies_to_every_type() {
+        let schema = ::schema::Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+
+        context.ingest_additional_derives("PartialEq").unwrap();
+        context.ingest_derive_profile(&DeriveProfile::Full);
+
+        assert_eq!(
+            context.response_derives().to_string(),
+            "# [ derive ( :: serde_derive :: Deserialize , :: std :: cmp :: PartialEq , :: std :: fmt :: Debug , :: std :: clone :: Clone , :: serde_derive :: Serialize ) ]"
+        );
+        assert_eq!(
+            context.variables_derives().to_string(),
+            "# [ derive ( :: serde_derive :: Serialize , :: std :: cmp :: PartialEq , :: std :: fmt :: Debug , :: std :: clone :: Clone , :: serde_derive :: Deserialize ) ]"
+        );
+        assert_eq!(
+            context.response_enum_derives().to_string(),
+            "# [ derive ( :: std :: clone :: Clone , :: std :: fmt :: Debug , :: std :: cmp :: Eq , :: std :: cmp :: PartialEq ) ]"
+        );
+    }
+
+    #[test]
+    fn arbitrary_is_derived_for_every_type() {
+        let schema = ::schema::Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        context.arbitrary = true;
+
+        assert_eq!(
+            context.response_derives().to_string(),
+            "# [ derive ( :: serde_derive :: Deserialize , :: std :: fmt :: Debug , :: proptest_derive :: Arbitrary ) ]"
+        );
+        assert_eq!(
+            context.variables_derives().to_string(),
+            "# [ derive ( :: serde_derive :: Serialize , :: std :: fmt :: Debug , :: proptest_derive :: Arbitrary ) ]"
+        );
+        assert_eq!(
+            context.response_enum_derives().to_string(),
+            "# [ derive ( :: proptest_derive :: Arbitrary , :: std :: fmt :: Debug , :: std :: cmp :: Eq , :: std :: cmp :: PartialEq ) ]"
+        );
+    }
+
+    #[test]
+    fn specta_type_is_derived_for_every_type() {
+        let schema = ::schema::Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        context.specta = true;
+
+        assert_eq!(
+            context.response_derives().to_string(),
+            "# [ derive ( :: serde_derive :: Deserialize , :: specta :: Type ) ]"
+        );
+        assert_eq!(
+            context.variables_derives().to_string(),
+            "# [ derive ( :: serde_derive :: Serialize , :: specta :: Type ) ]"
+        );
+        assert_eq!(
+            context.response_enum_derives().to_string(),
+            "# [ derive ( :: std :: cmp :: Eq , :: std :: cmp :: PartialEq , :: specta :: Type ) ]"
+        );
+    }
+
+    #[test]
+    fn deprecation_overrides_ingestion_works() {
+        let schema = ::schema::Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+
+        context
+            .ingest_deprecation_overrides("User.legacyId=allow, Query.viewer = warn_always")
+            .unwrap();
+
+        assert_eq!(
+            context.deprecation_strategy_for("User", "legacyId"),
+            &DeprecationStrategy::Allow
+        );
+        assert_eq!(
+            context.deprecation_strategy_for("Query", "viewer"),
+            &DeprecationStrategy::WarnAlways
+        );
+        assert_eq!(
+            context.deprecation_strategy_for("User", "name"),
+            &context.deprecation_strategy
+        );
+        assert!(context
+            .ingest_deprecation_overrides("legacyId=allow")
+            .is_err());
+        assert!(context
+            .ingest_deprecation_overrides("User.legacyId")
+            .is_err());
+        assert!(context
+            .ingest_deprecation_overrides("User.legacyId=sometimes")
+            .is_err());
+    }
+
+    #[test]
+    fn colliding_field_struct_names_get_a_suffix() {
+        let schema = ::schema::Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        context.fragments.insert(
+            "QViewerTeam",
+            GqlFragment {
+                name: "QViewerTeam",
+                on: "Team",
+                selection: Selection::new_empty(),
+                directives: &[],
+                is_required: false.into(),
+            },
+        );
+
+        assert_eq!(
+            context.field_struct_name("Q", "viewerFriends"),
+            "QViewerFriends"
+        );
+        assert_eq!(
+            context.field_struct_name("QViewer", "friends"),
+            "QViewerFriends2"
+        );
+        assert_eq!(context.field_struct_name("QViewer", "team"), "QViewerTeam2");
+        // The same field always gets the same name.
+        assert_eq!(
+            context.field_struct_name("QViewer", "friends"),
+            "QViewerFriends2"
         );
+        assert_eq!(context.field_struct_name("Q", "viewer"), "QViewer");
     }
 
     #[test]
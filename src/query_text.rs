@@ -0,0 +1,138 @@
+use failure;
+use miniz_oxide;
+use proc_macro2::{Literal, TokenStream};
+use syn;
+
+/// How the generated code holds the document of its operations.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum QueryText {
+    /// The `QUERY` constant, embedded in the binary (default).
+    #[default]
+    Embedded,
+    /// Loaded at runtime by the function at this path, like `crate::queries::load`, for binary-size-sensitive targets. It is called with the `QUERY_HASH` of the module, the FNV-1a hash of the document recorded in the operation lock file, and returns the document: `fn(&'static str) -> &'static str`. The module has a `query()` function calling it instead of `QUERY`.
+    Loaded(String),
+    /// Compressed with DEFLATE in the binary, and decompressed at first use by the `query()` function of the module, for crates embedding many large documents. The crate using the generated code needs `std` and a `miniz_oxide = "0.8"` dependency of its own, as the runtime does not re-export it.
+    Compressed,
+}
+
+impl ::std::str::FromStr for QueryText {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "embedded" => Ok(QueryText::Embedded),
+            "compressed" => Ok(QueryText::Compressed),
+            loader if syn::parse_str::<syn::Path>(loader).is_ok() => {
+                Ok(QueryText::Loaded(loader.to_owned()))
+            }
+            other => Err(format_err!(
+                "Invalid query text: `{}`. Valid values are `embedded`, `compressed` and the path of a loader function.",
+                other
+            )),
+        }
+    }
+}
+
+impl QueryText {
+    /// The items of the module holding or getting the document.
+    pub(crate) fn items(&self, query_string: &str) -> TokenStream {
+        match self {
+            QueryText::Embedded => quote!(pub const QUERY: &'static str = #query_string;),
+            QueryText::Loaded(loader) => {
+                let loader: syn::Path = syn::parse_str(loader).expect("validated query loader");
+                let hash = ::operation_lock::LockedOperation::new(query_string).hash;
+                quote! {
+                    /// The FNV-1a hash of the document of the operations, in hexadecimal, as recorded in the operation lock file.
+                    pub const QUERY_HASH: &'static str = #hash;
+
+                    /// The document of the operations, from the query loader.
+                    pub fn query() -> &'static str {
+                        #loader(QUERY_HASH)
+                    }
+                }
+            }
+            QueryText::Compressed => {
+                let deflated = Literal::byte_string(&miniz_oxide::deflate::compress_to_vec(
+                    query_string.as_bytes(),
+                    10,
+                ));
+                quote! {
+                    /// The document of the operations, compressed with DEFLATE.
+                    const QUERY_DEFLATED: &'static [u8] = #deflated;
+
+                    /// The document of the operations, decompressed at first use with the `miniz_oxide` dependency of the crate.
+                    pub fn query() -> &'static str {
+                        static QUERY: ::std::sync::OnceLock<::std::string::String> = ::std::sync::OnceLock::new();
+                        QUERY.get_or_init(|| {
+                            let inflated = ::miniz_oxide::inflate::decompress_to_vec(QUERY_DEFLATED)
+                                .expect("the compressed document is valid DEFLATE data");
+                            ::std::string::String::from_utf8(inflated)
+                                .expect("the compressed document is valid UTF-8")
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    /// The expression of the document inside the module: `QUERY` or `query()`.
+    pub(crate) fn expression(&self) -> TokenStream {
+        match self {
+            QueryText::Embedded => quote!(QUERY),
+            QueryText::Loaded(_) | QueryText::Compressed => quote!(query()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loaded_queries_are_looked_up_by_hash() {
+        assert_eq!(
+            " embedded ".parse::<QueryText>().unwrap(),
+            QueryText::Embedded
+        );
+        assert!("not a path!".parse::<QueryText>().is_err());
+
+        let loaded = "crate::queries::load".parse::<QueryText>().unwrap();
+        assert_eq!(loaded.expression().to_string(), "query ( )");
+        assert_eq!(
+            loaded.items("{ a }").to_string(),
+            quote! {
+                #[doc = r" The FNV-1a hash of the document of the operations, in hexadecimal, as recorded in the operation lock file."]
+                pub const QUERY_HASH: &'static str = "d5c37144e6ae600e";
+
+                #[doc = r" The document of the operations, from the query loader."]
+                pub fn query() -> &'static str {
+                    crate::queries::load(QUERY_HASH)
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn compressed_queries_inflate_to_the_document() {
+        let compressed = "compressed".parse::<QueryText>().unwrap();
+        assert_eq!(compressed.expression().to_string(), "query ( )");
+
+        let query = "query Hero { hero { name friends { name } } }";
+        let deflated = compressed
+            .items(query)
+            .into_iter()
+            .find_map(|token| match token {
+                ::proc_macro2::TokenTree::Literal(literal) => {
+                    syn::parse_str::<syn::LitByteStr>(&literal.to_string()).ok()
+                }
+                _ => None,
+            })
+            .unwrap()
+            .value();
+        assert_eq!(
+            miniz_oxide::inflate::decompress_to_vec(&deflated).unwrap(),
+            query.as_bytes()
+        );
+    }
+}
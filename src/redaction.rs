@@ -0,0 +1,174 @@
+use field_type::FieldType;
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use query::QueryContext;
+use std::collections::BTreeSet;
+use syn;
+
+/// What `Debug` and the pretty printer show instead of the value of a sensitive field.
+pub(crate) const REDACTED: &str = "[redacted]";
+
+/// The fields whose values are masked, by type name for all the fields of a type, or by `Type.field`.
+#[derive(Debug, Default)]
+pub(crate) struct SensitiveFields(BTreeSet<String>);
+
+impl SensitiveFields {
+    /// Parse a list like `AccessToken, User.email`.
+    pub(crate) fn parse(attribute_value: &str) -> Self {
+        SensitiveFields(
+            attribute_value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        )
+    }
+
+    /// Whether the field of `parent_type` is sensitive, by itself or because of its type.
+    pub(crate) fn contains(
+        &self,
+        parent_type: &str,
+        field_name: &str,
+        field_type: &FieldType,
+    ) -> bool {
+        self.0.contains(&format!("{}.{}", parent_type, field_name))
+            || self.0.contains(field_type.inner_name_str())
+    }
+}
+
+/// The `Debug` implementation of a response struct with sensitive fields, showing `[redacted]` for them, and the definition with `Debug` left out of its derives. Other definitions are returned as they are.
+pub(crate) fn redacted_debug(context: &QueryContext, definition: TokenStream) -> TokenStream {
+    let input: syn::DeriveInput =
+        syn::parse2(definition.clone()).expect("generated type definition");
+    let name = &input.ident;
+    let redacted = context.redacted_fields(&name.to_string());
+    let fields = match input.data {
+        syn::Data::Struct(ref data) if !redacted.is_empty() => &data.fields,
+        _ => return definition,
+    };
+    let attrs: Vec<TokenStream> = input
+        .attrs
+        .iter()
+        .map(|attr| without_derives(attr, &["Debug"]))
+        .collect();
+    if attrs
+        .iter()
+        .zip(&input.attrs)
+        .all(|(attr, original)| attr.to_string() == quote!(#original).to_string())
+    {
+        return definition;
+    }
+
+    let field_names: Vec<&Ident> = fields
+        .iter()
+        .map(|field| field.ident.as_ref().expect("named field"))
+        .collect();
+    let debug_fields = field_names.iter().map(|field_name| {
+        let label = field_name.to_string();
+        if redacted.contains(&label) {
+            quote!(.field(#label, &::std::format_args!(#REDACTED)))
+        } else {
+            quote!(.field(#label, &self.#field_name))
+        }
+    });
+    let fields = fields.iter();
+    let vis = &input.vis;
+    let name_literal = name.to_string();
+
+    quote! {
+        #(#attrs)*
+        #vis struct #name {
+            #(#fields,)*
+        }
+
+        #[allow(deprecated)]
+        impl ::std::fmt::Debug for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.debug_struct(#name_literal)
+                    #(#debug_fields)*
+                    .finish()
+            }
+        }
+    }
+}
+
+/// The attribute, without the `removed` derives if it is a `derive`. Nothing when no derive is left.
+pub(crate) fn without_derives(attr: &syn::Attribute, removed: &[&str]) -> TokenStream {
+    let is_derive = attr.path.segments.len() == 1 && attr.path.segments[0].ident == "derive";
+    let group = match attr.tts.clone().into_iter().next() {
+        Some(TokenTree::Group(ref group)) if is_derive => group.stream(),
+        _ => return quote!(#attr),
+    };
+
+    let mut derives: Vec<TokenStream> = vec![TokenStream::new()];
+    for token in group {
+        match token {
+            TokenTree::Punct(ref punct) if punct.as_char() == ',' => {
+                derives.push(TokenStream::new())
+            }
+            token => derives.last_mut().expect("derive").extend(Some(token)),
+        }
+    }
+    let derives = derives.into_iter().filter(|derive| {
+        !derive.is_empty()
+            && !derive
+                .clone()
+                .into_iter()
+                .last()
+                .map(|token| removed.contains(&token.to_string().as_str()))
+                .unwrap_or(false)
+    });
+    let derives: Vec<TokenStream> = derives.collect();
+    if derives.is_empty() {
+        return quote!();
+    }
+    let derive = Ident::new("derive", Span::call_site());
+
+    quote!(#[#derive(#(#derives),*)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::Schema;
+
+    #[test]
+    fn sensitive_fields_are_redacted_in_debug() {
+        let schema = Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        context.sensitive_fields = SensitiveFields::parse("AccessToken, User.email");
+        let definition = quote! {
+            #[derive(::serde_derive::Deserialize, ::std::fmt::Debug)]
+            pub struct Viewer {
+                pub email: String,
+                pub name: String,
+            }
+        };
+
+        assert!(context
+            .sensitive_fields
+            .contains("User", "email", &FieldType::Named("String")));
+        assert!(context.sensitive_fields.contains(
+            "Session",
+            "token",
+            &FieldType::Named("AccessToken")
+        ));
+        assert!(!context
+            .sensitive_fields
+            .contains("User", "name", &FieldType::Named("String")));
+        assert_eq!(
+            redacted_debug(&context, definition.clone()).to_string(),
+            definition.to_string()
+        );
+
+        context.record_redacted_field("Viewer", "email");
+        let generated = redacted_debug(&context, definition).to_string();
+
+        assert!(generated.starts_with(
+            "# [ derive ( :: serde_derive :: Deserialize ) ] pub struct Viewer { pub email : String , pub name : String , }"
+        ));
+        assert!(generated.contains(
+            "f . debug_struct ( \"Viewer\" ) . field ( \"email\" , & :: std :: format_args ! ( \"[redacted]\" ) ) . field ( \"name\" , & self . name ) . finish ( )"
+        ));
+    }
+}
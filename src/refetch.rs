@@ -0,0 +1,173 @@
+use codegen;
+use failure;
+use field_type::FieldType;
+use fragment_library::spreads;
+use graphql_parser::query::*;
+use operations::OperationType;
+use schema::Schema;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub(crate) const NODE_FIELD: &str = "node";
+
+/// A Relay-style operation refetching a fragment by the id of its object:
+///
+/// ```graphql
+/// query HeroNameRefetchQuery($id: ID!) { node(id: $id) { __typename ...HeroName } }
+/// ```
+pub(crate) struct RefetchQuery {
+    pub(crate) name: String,
+    /// The operation and the fragments it spreads, as sent to the server.
+    pub(crate) query_string: String,
+    pub(crate) document: Document,
+}
+
+/// The refetch operations of the fragments of `document` on the types implementing the interface of the `node(id: ID!)` field of the query type, unless the document already defines an operation with the same name.
+///
+/// Fragments using variables are left out, as their refetch operation cannot declare them.
+pub(crate) fn refetch_queries(
+    schema: &Schema,
+    document: &Document,
+) -> Result<Vec<RefetchQuery>, failure::Error> {
+    let node = match node_interface(schema) {
+        Some(node) => node,
+        None => return Ok(Vec::new()),
+    };
+    let fragments: BTreeMap<&str, &FragmentDefinition> = document
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Fragment(fragment) => Some((fragment.name.as_str(), fragment)),
+            Definition::Operation(_) => None,
+        })
+        .collect();
+    let operation_names: BTreeSet<String> = codegen::all_operations(document)
+        .into_iter()
+        .map(|operation| operation.name)
+        .collect();
+
+    let mut refetch_queries = Vec::new();
+    for fragment in fragments.values() {
+        let TypeCondition::On(ref on) = fragment.type_condition;
+        let name = format!("{}RefetchQuery", fragment.name);
+        if !implements(schema, on, node) || operation_names.contains(&name) {
+            continue;
+        }
+        let spread = spread_fragments(fragment, &fragments);
+        if spread.iter().any(|fragment| uses_variables(fragment)) {
+            continue;
+        }
+
+        let mut refetch = parse_query(&format!(
+            "query {}($id: ID!) {{ {}(id: $id) {{ __typename ...{} }} }}",
+            name, NODE_FIELD, fragment.name
+        ))?;
+        refetch.definitions.extend(
+            spread
+                .into_iter()
+                .map(|fragment| Definition::Fragment(fragment.clone())),
+        );
+        refetch_queries.push(RefetchQuery {
+            name,
+            query_string: ::string_literals::print_query(&refetch),
+            document: refetch,
+        });
+    }
+
+    Ok(refetch_queries)
+}
+
+/// The interface returned by the `node(id: ID!)` field of the query type.
+fn node_interface<'a>(schema: &'a Schema) -> Option<&'a str> {
+    let query_type = schema.root_type(&OperationType::Query)?;
+    let node = schema.field(query_type, NODE_FIELD)?.type_.inner_name_str();
+    let takes_id = schema
+        .field_arguments(query_type, NODE_FIELD)?
+        .iter()
+        .any(|argument| argument.name == "id" && argument.type_ == FieldType::Named("ID"));
+
+    if takes_id && schema.interfaces.contains_key(node) {
+        Some(node)
+    } else {
+        None
+    }
+}
+
+fn implements(schema: &Schema, type_name: &str, node: &str) -> bool {
+    type_name == node
+        || schema
+            .interfaces
+            .get(node)
+            .map(|iface| iface.implemented_by.contains(type_name))
+            .unwrap_or(false)
+        || schema
+            .interfaces
+            .get(type_name)
+            .map(|iface| iface.implements.contains(&node))
+            .unwrap_or(false)
+}
+
+/// The fragment and the fragments it spreads, directly or not.
+fn spread_fragments<'a>(
+    fragment: &'a FragmentDefinition,
+    fragments: &BTreeMap<&str, &'a FragmentDefinition>,
+) -> Vec<&'a FragmentDefinition> {
+    let mut spread = vec![fragment];
+    let mut visited = BTreeSet::new();
+    visited.insert(fragment.name.clone());
+    let mut pending = Vec::new();
+    spreads(&fragment.selection_set, &mut pending);
+
+    while let Some(name) = pending.pop() {
+        if !visited.insert(name.clone()) {
+            continue;
+        }
+        // Unknown fragments are left for code generation to report.
+        if let Some(fragment) = fragments.get(name.as_str()) {
+            spreads(&fragment.selection_set, &mut pending);
+            spread.push(fragment);
+        }
+    }
+
+    spread
+}
+
+fn uses_variables(fragment: &FragmentDefinition) -> bool {
+    directives_use_variables(&fragment.directives)
+        || selection_set_uses_variables(&fragment.selection_set)
+}
+
+fn selection_set_uses_variables(selection_set: &SelectionSet) -> bool {
+    selection_set.items.iter().any(|item| match item {
+        Selection::Field(field) => {
+            field
+                .arguments
+                .iter()
+                .any(|(_, value)| value_uses_variables(value))
+                || directives_use_variables(&field.directives)
+                || selection_set_uses_variables(&field.selection_set)
+        }
+        Selection::InlineFragment(inline) => {
+            directives_use_variables(&inline.directives)
+                || selection_set_uses_variables(&inline.selection_set)
+        }
+        Selection::FragmentSpread(spread) => directives_use_variables(&spread.directives),
+    })
+}
+
+fn directives_use_variables(directives: &[Directive]) -> bool {
+    directives.iter().any(|directive| {
+        directive
+            .arguments
+            .iter()
+            .any(|(_, value)| value_uses_variables(value))
+    })
+}
+
+fn value_uses_variables(value: &Value) -> bool {
+    match value {
+        Value::Variable(_) => true,
+        Value::List(values) => values.iter().any(value_uses_variables),
+        Value::Object(fields) => fields.values().any(value_uses_variables),
+        _ => false,
+    }
+}
@@ -0,0 +1,339 @@
+use failure;
+use reqwest;
+use schema_cache;
+use serde_json;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The name of the file, at the root of the crate, where named schemas are registered.
+pub const REGISTRY_FILE_NAME: &str = "graphql_schemas.json";
+
+lazy_static! {
+    /// The registries read so far, with the contents they were read from. A registry is read again when its file changes.
+    static ref REGISTRY_CACHE: ::CacheMap<(String, SchemaRegistry)> = ::CacheMap::default();
+}
+
+/// Where a registered schema is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaLocation {
+    /// A schema file.
+    Path(PathBuf),
+    /// A schema file served over HTTP. It is fetched once and kept in the target directory.
+    Url(String),
+}
+
+/// Maps schema names to schema files or URLs, for crates that talk to several GraphQL APIs.
+///
+/// The registry is usually read from a `graphql_schemas.json` file at the root of the crate:
+///
+/// ```json
+/// {
+///   "github": "schemas/github.graphql",
+///   "shopify": "https://example.com/shopify/schema.json"
+/// }
+/// ```
+///
+/// Relative paths are resolved against the directory containing the registry file. Values starting with `http://` or `https://` are URLs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaRegistry {
+    schemas: BTreeMap<String, SchemaLocation>,
+}
+
+impl SchemaRegistry {
+    /// Create an empty registry.
+    pub fn new() -> SchemaRegistry {
+        SchemaRegistry::default()
+    }
+
+    /// Read a registry from a JSON file mapping schema names to paths or URLs.
+    pub fn from_json_file(path: &Path) -> Result<SchemaRegistry, failure::Error> {
+        SchemaRegistry::from_json(&::read_file(path)?, path)
+    }
+
+    fn from_json(contents: &str, path: &Path) -> Result<SchemaRegistry, failure::Error> {
+        let raw: BTreeMap<String, String> = serde_json::from_str(contents).map_err(|err| {
+            format_err!(
+                "Could not parse the schema registry at {}: {}",
+                path.display(),
+                err
+            )
+        })?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut registry = SchemaRegistry::new();
+
+        for (name, location) in raw {
+            if location.starts_with("http://") || location.starts_with("https://") {
+                registry.register_url(name, location);
+            } else {
+                registry.register(name, base.join(location));
+            }
+        }
+
+        Ok(registry)
+    }
+
+    /// Register a schema file under `name`. Registering the same name twice replaces the previous schema.
+    pub fn register<N: Into<String>, P: Into<PathBuf>>(&mut self, name: N, path: P) {
+        self.schemas
+            .insert(name.into(), SchemaLocation::Path(path.into()));
+    }
+
+    /// Register a schema served at `url` under `name`. Registering the same name twice replaces the previous schema.
+    pub fn register_url<N: Into<String>, U: Into<String>>(&mut self, name: N, url: U) {
+        self.schemas
+            .insert(name.into(), SchemaLocation::Url(url.into()));
+    }
+
+    /// The location of the schema registered under `name`.
+    pub fn resolve(&self, name: &str) -> Result<&SchemaLocation, failure::Error> {
+        self.schemas.get(name).ok_or_else(|| {
+            format_err!(
+                "No schema named `{}` in the registry. Registered schemas: `{}`.",
+                name,
+                self.schemas
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join("`, `")
+            )
+        })
+    }
+
+    /// The names of all the registered schemas, in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.schemas.keys().map(String::as_str)
+    }
+}
+
+/// Resolve the schema path of the derive to the file the schema is read from.
+///
+/// If `schema_path`, relative to `crate_root`, is a name of the registry file of the crate, the registered schema is returned, fetching it first if it is a URL. The registry file is then added to `tracked_files`, so editing it regenerates the code. Anything else that looks like a name rather than a path — no extension, no directory, and no such file — is an unknown schema name. Otherwise `schema_path` is returned as it is.
+pub fn resolve_schema(
+    schema_path: &Path,
+    crate_root: &Path,
+    tracked_files: &mut Vec<PathBuf>,
+) -> Result<PathBuf, failure::Error> {
+    let name = schema_path.strip_prefix(crate_root).unwrap_or(schema_path);
+    let looks_like_name = name.components().count() == 1
+        && name.extension().is_none()
+        && !crate_root.join(name).exists();
+    let registry_path = crate_root.join(REGISTRY_FILE_NAME);
+
+    if !registry_path.is_file() {
+        if looks_like_name {
+            return Err(format_err!(
+                "No schema file at {}, and no {} at the root of the crate to look up `{}` by name.",
+                schema_path.display(),
+                REGISTRY_FILE_NAME,
+                name.display()
+            ));
+        }
+        return Ok(schema_path.to_path_buf());
+    }
+
+    let contents = ::read_file(&registry_path)?;
+    let location = {
+        let mut lock = REGISTRY_CACHE.lock().expect("registry cache is poisoned");
+        let cached = lock
+            .get(&registry_path)
+            .filter(|(read, _)| *read == contents);
+        let registry = match cached {
+            Some((_, registry)) => registry.clone(),
+            None => {
+                let registry = SchemaRegistry::from_json(&contents, &registry_path)?;
+                lock.insert(registry_path.clone(), (contents, registry.clone()));
+                registry
+            }
+        };
+
+        match name.to_str() {
+            Some(name) if registry.schemas.contains_key(name) || looks_like_name => {
+                registry.resolve(name)?.clone()
+            }
+            _ => return Ok(schema_path.to_path_buf()),
+        }
+    };
+
+    tracked_files.push(registry_path);
+    match location {
+        SchemaLocation::Path(path) => Ok(path),
+        SchemaLocation::Url(url) => fetch_schema(&url),
+    }
+}
+
+/// Where the schema served at `url` is kept once fetched.
+fn fetched_schema_path(url: &str) -> PathBuf {
+    schema_cache::target_dir()
+        .unwrap_or_else(::std::env::temp_dir)
+        .join("graphql_client_schemas")
+        .join(format!(
+            "{:016x}.schema",
+            schema_cache::content_hash(url.as_bytes())
+        ))
+}
+
+/// Fetch the schema served at `url`, unless it was fetched by a previous build. Delete the fetched file, or run `cargo clean`, to fetch it again.
+fn fetch_schema(url: &str) -> Result<PathBuf, failure::Error> {
+    let path = fetched_schema_path(url);
+    if path.is_file() {
+        return Ok(path);
+    }
+
+    let fetch_error =
+        |err: reqwest::Error| format_err!("Could not fetch the schema at {}: {}", url, err);
+    let schema = reqwest::blocking::get(url)
+        .and_then(reqwest::blocking::Response::error_for_status)
+        .and_then(reqwest::blocking::Response::bytes)
+        .map_err(fetch_error)?;
+
+    // Write to a temporary file first so parallel builds never read a partially written schema.
+    if let Some(dir) = path.parent() {
+        ::std::fs::create_dir_all(dir)?;
+    }
+    let tmp_path = path.with_extension(format!("{}.tmp", ::std::process::id()));
+    ::std::fs::write(&tmp_path, &schema)?;
+    ::std::fs::rename(&tmp_path, &path)?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A crate root with the registry `registry`, and a schema file.
+    fn crate_root(test: &str, registry: &str) -> PathBuf {
+        let root = ::std::env::temp_dir().join(format!("graphql_client_registry_{}", test));
+        ::std::fs::create_dir_all(root.join("schemas")).unwrap();
+        ::std::fs::write(
+            root.join("schemas/github.graphql"),
+            "type Query { a: String }",
+        )
+        .unwrap();
+        ::std::fs::write(root.join(REGISTRY_FILE_NAME), registry).unwrap();
+        root
+    }
+
+    #[test]
+    fn resolve_registered_schema() {
+        let mut registry = SchemaRegistry::new();
+        registry.register("github", "schemas/github.graphql");
+        registry.register_url("shopify", "https://example.com/schema.json");
+
+        assert_eq!(
+            registry.resolve("github").unwrap(),
+            &SchemaLocation::Path(PathBuf::from("schemas/github.graphql"))
+        );
+        assert_eq!(
+            registry.resolve("shopify").unwrap(),
+            &SchemaLocation::Url("https://example.com/schema.json".to_string())
+        );
+    }
+
+    #[test]
+    fn resolve_unknown_schema_lists_registered_names() {
+        let mut registry = SchemaRegistry::new();
+        registry.register("github", "schemas/github.graphql");
+        registry.register("shopify", "schemas/shopify.json");
+
+        assert_eq!(
+            format!("{}", registry.resolve("gitlab").unwrap_err()),
+            "No schema named `gitlab` in the registry. Registered schemas: `github`, `shopify`."
+        );
+    }
+
+    #[test]
+    fn resolve_schema_without_registry_file_is_a_path() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut tracked_files = Vec::new();
+        assert_eq!(
+            resolve_schema(
+                &root.join("tests/star_wars_schema.graphql"),
+                &root,
+                &mut tracked_files
+            )
+            .unwrap(),
+            root.join("tests/star_wars_schema.graphql")
+        );
+        assert!(tracked_files.is_empty());
+
+        assert_eq!(
+            format!(
+                "{}",
+                resolve_schema(&root.join("github"), &root, &mut tracked_files).unwrap_err()
+            ),
+            format!(
+                "No schema file at {}, and no graphql_schemas.json at the root of the crate to look up `github` by name.",
+                root.join("github").display()
+            )
+        );
+    }
+
+    #[test]
+    fn resolve_schema_by_name() {
+        let root = crate_root("by_name", r#"{ "github": "schemas/github.graphql" }"#);
+        let mut tracked_files = Vec::new();
+
+        assert_eq!(
+            resolve_schema(&root.join("github"), &root, &mut tracked_files).unwrap(),
+            root.join("schemas/github.graphql")
+        );
+        assert_eq!(tracked_files, vec![root.join(REGISTRY_FILE_NAME)]);
+
+        // Paths are still paths.
+        assert_eq!(
+            resolve_schema(&root.join("schemas/github.graphql"), &root, &mut Vec::new()).unwrap(),
+            root.join("schemas/github.graphql")
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                resolve_schema(&root.join("gitlab"), &root, &mut Vec::new()).unwrap_err()
+            ),
+            "No schema named `gitlab` in the registry. Registered schemas: `github`."
+        );
+    }
+
+    #[test]
+    fn edited_registries_are_read_again() {
+        let root = crate_root("edited", r#"{ "api": "schemas/github.graphql" }"#);
+        assert_eq!(
+            resolve_schema(&root.join("api"), &root, &mut Vec::new()).unwrap(),
+            root.join("schemas/github.graphql")
+        );
+
+        ::std::fs::write(
+            root.join(REGISTRY_FILE_NAME),
+            r#"{ "api": "schemas/gitlab.graphql" }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            resolve_schema(&root.join("api"), &root, &mut Vec::new()).unwrap(),
+            root.join("schemas/gitlab.graphql")
+        );
+    }
+
+    #[test]
+    fn fetched_schemas_are_reused() {
+        let url = "https://example.invalid/graphql_client_registry_test/schema.graphql";
+        let root = crate_root("url", &format!(r#"{{ "remote": "{}" }}"#, url));
+        let fetched = fetched_schema_path(url);
+        ::std::fs::create_dir_all(fetched.parent().unwrap()).unwrap();
+        ::std::fs::write(&fetched, "type Query { a: String }").unwrap();
+
+        assert_eq!(
+            resolve_schema(&root.join("remote"), &root, &mut Vec::new()).unwrap(),
+            fetched
+        );
+
+        ::std::fs::remove_file(&fetched).unwrap();
+        let error = resolve_schema(&root.join("remote"), &root, &mut Vec::new()).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with(&format!("Could not fetch the schema at {}: ", url)),
+            "{}",
+            error
+        );
+    }
+}
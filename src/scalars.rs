@@ -1,22 +1,119 @@
-use proc_macro2;
+use graphql_parser::schema;
+use proc_macro2::{self, Ident, Span};
+use query::QueryContext;
 use std::cell::Cell;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Ord, Eq)]
 pub struct Scalar<'schema> {
     pub name: &'schema str,
     pub description: Option<&'schema str>,
+    /// The URL of the specification of the scalar, from `@specifiedBy(url:)`.
+    pub specified_by: Option<&'schema str>,
     pub is_required: Cell<bool>,
 }
 
 impl<'schema> Scalar<'schema> {
-    // TODO: do something smarter here
-    pub fn to_rust(&self) -> proc_macro2::TokenStream {
-        use proc_macro2::{Ident, Span};
-        let ident = Ident::new(&self.name, Span::call_site());
-        let description = match &self.description {
-            Some(d) => quote!(#[doc = #d]),
-            None => quote!(),
+    /// An alias of the type provided by the caller, or of the Rust type given for its specification URL in `specified_by_overrides`. Big-number scalars use their representation from `big_number_scalars` instead, and the `bytes_scalars` are newtypes around `Vec<u8>`.
+    pub fn to_rust(&self, context: &QueryContext) -> proc_macro2::TokenStream {
+        let ident = Ident::new(self.name, Span::call_site());
+        let description = ::shared::description_attributes(self.description);
+        let specified_by = self.specified_by.map(|url| {
+            let doc = format!("Specified by <{}>.", url);
+            quote!(#[doc = #doc])
+        });
+
+        if context.is_bytes_scalar(self.name) {
+            return ::bytes::bytes_scalar_definition(&ident, quote!(#description #specified_by));
+        }
+
+        if let Some(strategy) = context.big_number_strategy(self.name) {
+            return strategy.scalar_definition(&ident, quote!(#description #specified_by));
+        }
+
+        let ty = self.rust_type(context);
+        quote!(#description #specified_by type #ident = #ty;)
+    }
+
+    /// The type the scalar stands for in the generated code.
+    pub(crate) fn rust_type(&self, context: &QueryContext) -> proc_macro2::TokenStream {
+        let ident = Ident::new(self.name, Span::call_site());
+        if context.is_bytes_scalar(self.name) {
+            return quote!(#ident);
+        }
+        if let Some(strategy) = context.big_number_strategy(self.name) {
+            return strategy.rust_type(&ident);
+        }
+
+        match self
+            .specified_by
+            .and_then(|url| context.specified_by_override(url))
+        {
+            Some(ty) => quote!(#ty),
+            None => quote!(super::#ident),
+        }
+    }
+}
+
+/// The URL in the `@specifiedBy(url:)` directive of a scalar definition.
+pub(crate) fn specified_by_url(directives: &[schema::Directive]) -> Option<&str> {
+    directives
+        .iter()
+        .filter(|directive| directive.name == "specifiedBy")
+        .flat_map(|directive| directive.arguments.iter())
+        .find(|(name, _)| name == "url")
+        .and_then(|(_, value)| match value {
+            schema::Value::String(url) => Some(url.as_str()),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::Schema;
+
+    #[test]
+    fn scalars_specified_by_an_overridden_url_use_the_override() {
+        let schema = Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        context
+            .ingest_specified_by_overrides(
+                "https://tools.ietf.org/html/rfc3339=chrono::DateTime<chrono::Utc>, https://example.com/spec?v=2=u64",
+            )
+            .unwrap();
+        let scalar = |name, specified_by| Scalar {
+            name,
+            description: None,
+            specified_by,
+            is_required: false.into(),
         };
-        quote!(#description type #ident = super::#ident;)
+
+        assert_eq!(
+            scalar("DateTime", Some("https://tools.ietf.org/html/rfc3339"))
+                .to_rust(&context)
+                .to_string(),
+            "# [ doc = \"Specified by <https://tools.ietf.org/html/rfc3339>.\" ] type DateTime = chrono :: DateTime < chrono :: Utc > ;"
+        );
+        assert_eq!(
+            scalar("Version", Some("https://example.com/spec?v=2"))
+                .to_rust(&context)
+                .to_string(),
+            "# [ doc = \"Specified by <https://example.com/spec?v=2>.\" ] type Version = u64 ;"
+        );
+        assert_eq!(
+            scalar("Json", None).to_rust(&context).to_string(),
+            "type Json = super :: Json ;"
+        );
+        assert!(context
+            .ingest_specified_by_overrides("https://example.com=not a type")
+            .is_err());
+
+        context.ingest_bytes_scalars("Bytes, ");
+        let bytes = scalar("Bytes", None);
+        assert_eq!(bytes.rust_type(&context).to_string(), "Bytes");
+        assert!(bytes
+            .to_rust(&context)
+            .to_string()
+            .contains("pub struct Bytes ( pub :: std :: vec :: Vec < u8 > ) ;"));
     }
 }
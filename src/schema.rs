@@ -1,17 +1,25 @@
+use constants::IMPLEMENTS_INTERFACES_DIRECTIVE;
 use deprecation::DeprecationStatus;
+use directives::{DirectiveUsage, GqlDirective};
 use enums::{EnumVariant, GqlEnum};
 use failure;
 use field_type::FieldType;
 use graphql_parser::{self, schema};
 use inputs::GqlInput;
 use interfaces::GqlInterface;
-use objects::{GqlObject, GqlObjectField};
-use scalars::Scalar;
-use std::collections::{BTreeMap, BTreeSet};
+use objects::{parse_deprecation_directives, GqlArgument, GqlObject, GqlObjectField};
+use operations::OperationType;
+use scalars::{specified_by_url, Scalar};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use unions::GqlUnion;
 
+pub use schema_diff::{diff, diff_files, Criticality, SchemaChange};
+
 pub(crate) const DEFAULT_SCALARS: &[&str] = &["ID", "String", "Int", "Float", "Boolean"];
 
+/// The directives every server defines. They are not part of [Schema::directives].
+pub(crate) const BUILTIN_DIRECTIVES: &[&str] = &["skip", "include", "deprecated", "specifiedBy"];
+
 /// Intermediate representation for a parsed GraphQL schema used during code generation.
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct Schema<'schema> {
@@ -21,9 +29,19 @@ pub(crate) struct Schema<'schema> {
     pub(crate) objects: BTreeMap<&'schema str, GqlObject<'schema>>,
     pub(crate) scalars: BTreeMap<&'schema str, Scalar<'schema>>,
     pub(crate) unions: BTreeMap<&'schema str, GqlUnion<'schema>>,
+    /// The custom directives defined by the schema.
+    pub(crate) directives: BTreeMap<&'schema str, GqlDirective<'schema>>,
+    /// The custom directives used in the schema, by schema coordinate, like `User`, `User.name`, `User.avatar(size:)` or `Episode.JEDI`.
+    pub(crate) directive_usages: BTreeMap<String, Vec<DirectiveUsage<'schema>>>,
+    /// The arguments of the object and interface fields, by schema coordinate, like `Query.hero`.
+    pub(crate) field_arguments: BTreeMap<String, Vec<GqlArgument<'schema>>>,
+    /// The position of each field among the fields of its object or interface, so fields are found without scanning them. See [Schema::field].
+    pub(crate) field_positions: HashMap<&'schema str, HashMap<&'schema str, usize>>,
     pub(crate) query_type: Option<&'schema str>,
     pub(crate) mutation_type: Option<&'schema str>,
     pub(crate) subscription_type: Option<&'schema str>,
+    /// Whether the root types are declared, by a `schema` definition or the introspection response. Otherwise they are the objects with the conventional names.
+    pub(crate) root_types_declared: bool,
 }
 
 impl<'schema> Schema<'schema> {
@@ -35,61 +53,54 @@ impl<'schema> Schema<'schema> {
             objects: BTreeMap::new(),
             scalars: BTreeMap::new(),
             unions: BTreeMap::new(),
+            directives: BTreeMap::new(),
+            directive_usages: BTreeMap::new(),
+            field_arguments: BTreeMap::new(),
+            field_positions: HashMap::new(),
             query_type: None,
             mutation_type: None,
             subscription_type: None,
+            root_types_declared: false,
         }
     }
 
-    pub(crate) fn ingest_interface_implementations(
-        &mut self,
-        impls: BTreeMap<&'schema str, Vec<&'schema str>>,
-    ) -> Result<(), failure::Error> {
-        impls
-            .into_iter()
-            .map(|(iface_name, implementors)| {
-                let iface = self
-                    .interfaces
-                    .get_mut(&iface_name)
-                    .ok_or_else(|| format_err!("interface not found: {}", iface_name))?;
-                iface.implemented_by = implementors.iter().cloned().collect();
-                Ok(())
-            })
-            .collect()
+    /// Add the type definitions and object type extensions of an SDL document, like the local types of the `@client` fields. Extensions of types the document does not define add the types.
+    pub(crate) fn ingest_document(&mut self, ast: &'schema graphql_parser::schema::Document) {
+        self.ingest_types(ast, &|_| true);
     }
 
-    pub(crate) fn require(&self, typename_: &str) {
-        DEFAULT_SCALARS
-            .iter()
-            .find(|&&s| s == typename_)
-            .map(|_| ())
-            .or_else(|| {
-                self.enums
-                    .get(typename_)
-                    .map(|enm| enm.is_required.set(true))
-            })
-            .or_else(|| self.inputs.get(typename_).map(|input| input.require(self)))
-            .or_else(|| {
-                self.objects
-                    .get(typename_)
-                    .map(|object| object.require(self))
-            })
-            .or_else(|| {
-                self.scalars
-                    .get(typename_)
-                    .map(|scalar| scalar.is_required.set(true))
-            });
+    /// Add the types of an SDL document like [Schema::ingest_document], but only the `reachable` ones.
+    pub(crate) fn ingest_reachable_document(
+        &mut self,
+        ast: &'schema graphql_parser::schema::Document,
+        reachable: &BTreeSet<String>,
+    ) {
+        self.ingest_types(ast, &|name| reachable.contains(name));
     }
 
-    pub(crate) fn contains_scalar(&self, type_name: &str) -> bool {
-        DEFAULT_SCALARS.iter().any(|s| s == &type_name) || self.scalars.contains_key(type_name)
+    /// The schema with only the `reachable` types, the other type definitions of the parsed schema are never converted. See [TypeGraph](../pruning/struct.TypeGraph.html).
+    pub(crate) fn reachable(
+        parsed_schema: &'schema ParsedSchema,
+        reachable: &BTreeSet<String>,
+    ) -> Schema<'schema> {
+        match parsed_schema {
+            ParsedSchema::GraphQLParser(document) => {
+                let mut schema = Schema::new();
+                schema.ingest_reachable_document(document, reachable);
+                schema
+            }
+            ParsedSchema::Json(response) => {
+                Schema::from_introspection(response, &|name| reachable.contains(name))
+            }
+        }
     }
-}
-
-impl<'schema> ::std::convert::From<&'schema graphql_parser::schema::Document> for Schema<'schema> {
-    fn from(ast: &'schema graphql_parser::schema::Document) -> Schema<'schema> {
-        let mut schema = Schema::new();
 
+    /// Add the types of an SDL document the `keep` predicate holds for, with their arguments and directive usages.
+    fn ingest_types(
+        &mut self,
+        ast: &'schema graphql_parser::schema::Document,
+        keep: &dyn Fn(&str) -> bool,
+    ) {
         // Holds which objects implement which interfaces so we can populate GqlInterface#implemented_by later.
         // It maps interface names to a vec of implementation names.
         let mut interface_implementations: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
@@ -105,13 +116,27 @@ impl<'schema> ::std::convert::From<&'schema graphql_parser::schema::Document> fo
                                 .and_modify(|objects| objects.push(name))
                                 .or_insert_with(|| vec![name]);
                         }
+                        if !keep(&obj.name) {
+                            continue;
+                        }
 
-                        schema
-                            .objects
-                            .insert(&obj.name, GqlObject::from_graphql_parser_object(&obj));
+                        self.record_field_arguments(&obj.name, &obj.fields);
+                        let mut object = GqlObject::from_graphql_parser_object(&obj);
+                        // Federated schemas can extend a type before defining it.
+                        if let Some(extended) = self.objects.remove(obj.name.as_str()) {
+                            let defined: HashSet<&str> =
+                                object.fields.iter().map(|field| field.name).collect();
+                            object.fields.extend(
+                                extended
+                                    .fields
+                                    .into_iter()
+                                    .filter(|field| !defined.contains(field.name)),
+                            );
+                        }
+                        self.objects.insert(&obj.name, object);
                     }
-                    schema::TypeDefinition::Enum(enm) => {
-                        schema.enums.insert(
+                    schema::TypeDefinition::Enum(enm) if keep(&enm.name) => {
+                        self.enums.insert(
                             &enm.name,
                             GqlEnum {
                                 name: &enm.name,
@@ -122,26 +147,28 @@ impl<'schema> ::std::convert::From<&'schema graphql_parser::schema::Document> fo
                                     .map(|v| EnumVariant {
                                         description: v.description.as_ref().map(String::as_str),
                                         name: &v.name,
+                                        deprecation: parse_deprecation_directives(&v.directives),
                                     })
                                     .collect(),
                                 is_required: false.into(),
                             },
                         );
                     }
-                    schema::TypeDefinition::Scalar(scalar) => {
-                        schema.scalars.insert(
+                    schema::TypeDefinition::Scalar(scalar) if keep(&scalar.name) => {
+                        self.scalars.insert(
                             &scalar.name,
                             Scalar {
                                 name: &scalar.name,
                                 description: scalar.description.as_ref().map(String::as_str),
+                                specified_by: specified_by_url(&scalar.directives),
                                 is_required: false.into(),
                             },
                         );
                     }
-                    schema::TypeDefinition::Union(union) => {
+                    schema::TypeDefinition::Union(union) if keep(&union.name) => {
                         let variants: BTreeSet<&str> =
                             union.types.iter().map(|s| s.as_str()).collect();
-                        schema.unions.insert(
+                        self.unions.insert(
                             &union.name,
                             GqlUnion {
                                 name: &union.name,
@@ -151,11 +178,13 @@ impl<'schema> ::std::convert::From<&'schema graphql_parser::schema::Document> fo
                             },
                         );
                     }
-                    schema::TypeDefinition::Interface(interface) => {
+                    schema::TypeDefinition::Interface(interface) if keep(&interface.name) => {
                         let mut iface = GqlInterface::new(
                             &interface.name,
                             interface.description.as_ref().map(|d| d.as_str()),
                         );
+                        iface.implements = ::sdl::implemented_interfaces(&interface.directives);
+                        self.record_field_arguments(&interface.name, &interface.fields);
                         iface
                             .fields
                             .extend(interface.fields.iter().map(|f| GqlObjectField {
@@ -164,26 +193,369 @@ impl<'schema> ::std::convert::From<&'schema graphql_parser::schema::Document> fo
                                 type_: FieldType::from(&f.field_type),
                                 deprecation: DeprecationStatus::Current,
                             }));
-                        schema.interfaces.insert(&interface.name, iface);
+                        self.interfaces.insert(&interface.name, iface);
                     }
-                    schema::TypeDefinition::InputObject(input) => {
-                        schema.inputs.insert(&input.name, GqlInput::from(input));
+                    schema::TypeDefinition::InputObject(input) if keep(&input.name) => {
+                        self.inputs.insert(&input.name, GqlInput::from(input));
                     }
+                    _ => (),
                 },
-                schema::Definition::DirectiveDefinition(_) => (),
+                schema::Definition::DirectiveDefinition(directive) => {
+                    if !BUILTIN_DIRECTIVES.contains(&directive.name.as_str()) {
+                        self.directives.insert(
+                            &directive.name,
+                            GqlDirective::from_graphql_parser_directive(directive),
+                        );
+                    }
+                }
+                schema::Definition::TypeExtension(schema::TypeExtension::Object(extension)) => {
+                    for implementing in &extension.implements_interfaces {
+                        let name = &extension.name;
+                        interface_implementations
+                            .entry(implementing)
+                            .and_modify(|objects| objects.push(name))
+                            .or_insert_with(|| vec![name]);
+                    }
+                    if !keep(&extension.name) {
+                        continue;
+                    }
+
+                    self.record_field_arguments(&extension.name, &extension.fields);
+                    // Federation subgraphs extend the types other subgraphs define.
+                    self.objects
+                        .entry(&extension.name)
+                        .or_insert_with(|| GqlObject::new(&extension.name, None))
+                        .fields
+                        .extend(extension.fields.iter().map(GqlObjectField::from));
+                }
+                schema::Definition::TypeExtension(schema::TypeExtension::Scalar(extension)) => {
+                    if let Some(scalar) = self.scalars.get_mut(extension.name.as_str()) {
+                        if let Some(url) = specified_by_url(&extension.directives) {
+                            scalar.specified_by = Some(url);
+                        }
+                    }
+                }
                 schema::Definition::TypeExtension(_extension) => (),
                 schema::Definition::SchemaDefinition(definition) => {
-                    schema.query_type = definition.query.as_ref().map(|s| s.as_str());
-                    schema.mutation_type = definition.mutation.as_ref().map(|s| s.as_str());
-                    schema.subscription_type = definition.subscription.as_ref().map(|s| s.as_str());
+                    self.query_type = definition.query.as_ref().map(|s| s.as_str());
+                    self.mutation_type = definition.mutation.as_ref().map(|s| s.as_str());
+                    self.subscription_type = definition.subscription.as_ref().map(|s| s.as_str());
+                    self.root_types_declared = true;
                 }
             }
         }
 
-        schema
-            .ingest_interface_implementations(interface_implementations)
+        interface_implementations.retain(|interface, _| keep(interface));
+        self.ingest_federation(ast);
+        self.ingest_interface_implementations(interface_implementations)
             .expect("schema ingestion");
+        self.ingest_directive_usages(ast, keep);
+    }
+
+    /// Record the custom directives used on the types of an SDL document, and on their fields, arguments and values.
+    fn ingest_directive_usages(
+        &mut self,
+        ast: &'schema graphql_parser::schema::Document,
+        keep: &dyn Fn(&str) -> bool,
+    ) {
+        use graphql_parser::schema::{Definition, TypeDefinition, TypeExtension};
+
+        for definition in &ast.definitions {
+            match definition_name(definition) {
+                Some(name) if !keep(name) => continue,
+                _ => (),
+            }
+            match definition {
+                Definition::TypeDefinition(TypeDefinition::Scalar(scalar)) => {
+                    self.record_directive_usages(scalar.name.clone(), &scalar.directives);
+                }
+                Definition::TypeDefinition(TypeDefinition::Object(obj)) => {
+                    self.record_directive_usages(obj.name.clone(), &obj.directives);
+                    self.record_field_directive_usages(&obj.name, &obj.fields);
+                }
+                Definition::TypeExtension(TypeExtension::Object(extension)) => {
+                    self.record_directive_usages(extension.name.clone(), &extension.directives);
+                    self.record_field_directive_usages(&extension.name, &extension.fields);
+                }
+                Definition::TypeDefinition(TypeDefinition::Interface(interface)) => {
+                    self.record_directive_usages(interface.name.clone(), &interface.directives);
+                    self.record_field_directive_usages(&interface.name, &interface.fields);
+                }
+                Definition::TypeDefinition(TypeDefinition::Union(union)) => {
+                    self.record_directive_usages(union.name.clone(), &union.directives);
+                }
+                Definition::TypeDefinition(TypeDefinition::Enum(enm)) => {
+                    self.record_directive_usages(enm.name.clone(), &enm.directives);
+                    for value in &enm.values {
+                        self.record_directive_usages(
+                            format!("{}.{}", enm.name, value.name),
+                            &value.directives,
+                        );
+                    }
+                }
+                Definition::TypeDefinition(TypeDefinition::InputObject(input)) => {
+                    self.record_directive_usages(input.name.clone(), &input.directives);
+                    for field in &input.fields {
+                        self.record_directive_usages(
+                            format!("{}.{}", input.name, field.name),
+                            &field.directives,
+                        );
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn record_field_arguments(
+        &mut self,
+        type_name: &str,
+        fields: &'schema [graphql_parser::schema::Field],
+    ) {
+        for field in fields {
+            self.field_arguments.insert(
+                format!("{}.{}", type_name, field.name),
+                field.arguments.iter().map(GqlArgument::from).collect(),
+            );
+        }
+    }
+
+    /// The arguments of the `field` of an object or interface, if the schema declares that field.
+    pub(crate) fn field_arguments(
+        &self,
+        type_name: &str,
+        field: &str,
+    ) -> Option<&[GqlArgument<'schema>]> {
+        self.field_arguments
+            .get(&format!("{}.{}", type_name, field))
+            .map(Vec::as_slice)
+    }
+
+    fn record_field_directive_usages(
+        &mut self,
+        type_name: &str,
+        fields: &'schema [graphql_parser::schema::Field],
+    ) {
+        for field in fields {
+            self.record_directive_usages(
+                format!("{}.{}", type_name, field.name),
+                &field.directives,
+            );
+            for argument in &field.arguments {
+                self.record_directive_usages(
+                    format!("{}.{}({}:)", type_name, field.name, argument.name),
+                    &argument.directives,
+                );
+            }
+        }
+    }
+
+    fn record_directive_usages(
+        &mut self,
+        coordinate: String,
+        directives: &'schema [graphql_parser::schema::Directive],
+    ) {
+        let usages: Vec<DirectiveUsage<'schema>> = directives
+            .iter()
+            .filter(|directive| {
+                !BUILTIN_DIRECTIVES.contains(&directive.name.as_str())
+                    && directive.name != IMPLEMENTS_INTERFACES_DIRECTIVE
+            })
+            .map(DirectiveUsage::from)
+            .collect();
+
+        if !usages.is_empty() {
+            self.directive_usages
+                .entry(coordinate)
+                .or_default()
+                .extend(usages);
+        }
+    }
+
+    pub(crate) fn ingest_interface_implementations(
+        &mut self,
+        impls: BTreeMap<&'schema str, Vec<&'schema str>>,
+    ) -> Result<(), failure::Error> {
+        impls
+            .into_iter()
+            .try_for_each(|(iface_name, implementors)| {
+                let iface = self
+                    .interfaces
+                    .get_mut(&iface_name)
+                    .ok_or_else(|| format_err!("interface not found: {}", iface_name))?;
+                iface.implemented_by.extend(implementors);
+                Ok::<(), failure::Error>(())
+            })?;
+
+        self.propagate_interface_inheritance();
+        self.index_fields();
+        Ok(())
+    }
+
+    fn index_fields(&mut self) {
+        let objects = self.objects.values().map(|obj| (obj.name, &obj.fields));
+        let interfaces = self
+            .interfaces
+            .values()
+            .map(|iface| (iface.name, &iface.fields));
+        self.field_positions = objects
+            .chain(interfaces)
+            .map(|(name, fields)| {
+                let positions = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(position, field)| (field.name, position))
+                    .collect();
+                (name, positions)
+            })
+            .collect();
+    }
+
+    /// The fields of an object or interface.
+    pub(crate) fn fields_of(&self, type_name: &str) -> Option<&[GqlObjectField<'schema>]> {
+        self.objects
+            .get(type_name)
+            .map(|obj| obj.fields.as_slice())
+            .or_else(|| {
+                self.interfaces
+                    .get(type_name)
+                    .map(|iface| iface.fields.as_slice())
+            })
+    }
+
+    /// The root type of the operations of the type, as declared, or else the object named `Query`, `Mutation` or `Subscription` like in SDL schemas without a `schema` definition. `None` when the schema has no such root type.
+    pub(crate) fn root_type(&self, operation_type: &OperationType) -> Option<&'schema str> {
+        let declared = match *operation_type {
+            OperationType::Query => self.query_type,
+            OperationType::Mutation => self.mutation_type,
+            OperationType::Subscription => self.subscription_type,
+        };
+        if self.root_types_declared {
+            return declared;
+        }
+        declared.or_else(|| {
+            self.objects
+                .get(operation_type.conventional_root_name())
+                .map(|object| object.name)
+        })
+    }
+
+    /// Whether the type is the query, mutation or subscription type of the schema.
+    pub(crate) fn is_root_type(&self, type_name: &str) -> bool {
+        [
+            OperationType::Query,
+            OperationType::Mutation,
+            OperationType::Subscription,
+        ]
+        .iter()
+        .any(|operation_type| self.root_type(operation_type) == Some(type_name))
+    }
 
+    /// A field of an object or interface. Types added after the schema was built are not indexed, their fields are scanned.
+    pub(crate) fn field(
+        &self,
+        type_name: &str,
+        field_name: &str,
+    ) -> Option<&GqlObjectField<'schema>> {
+        let fields = self.fields_of(type_name)?;
+        self.field_positions
+            .get(type_name)
+            .and_then(|positions| positions.get(field_name))
+            .and_then(|position| fields.get(*position))
+            .filter(|field| field.name == field_name)
+            .or_else(|| fields.iter().find(|field| field.name == field_name))
+    }
+
+    /// Interfaces implementing other interfaces inherit the fields they do not redeclare, and their implementors implement the parent interfaces too.
+    fn propagate_interface_inheritance(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let names: Vec<&'schema str> = self.interfaces.keys().cloned().collect();
+
+            for name in names {
+                let (parents, implementors) = {
+                    let iface = &self.interfaces[name];
+                    (iface.implements.clone(), iface.implemented_by.clone())
+                };
+
+                for parent_name in parents {
+                    let inherited_fields: Vec<GqlObjectField<'schema>> =
+                        match self.interfaces.get_mut(parent_name) {
+                            Some(parent) => {
+                                for implementor in &implementors {
+                                    changed |= parent.implemented_by.insert(implementor);
+                                }
+                                parent.fields.clone()
+                            }
+                            None => continue,
+                        };
+
+                    let iface = self.interfaces.get_mut(name).expect("interface");
+                    let own_fields: HashSet<&str> =
+                        iface.fields.iter().map(|own| own.name).collect();
+                    for field in inherited_fields {
+                        if !own_fields.contains(field.name) {
+                            iface.fields.push(field);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn require(&self, typename_: &str) {
+        DEFAULT_SCALARS
+            .iter()
+            .find(|&&s| s == typename_)
+            .map(|_| ())
+            .or_else(|| {
+                self.enums
+                    .get(typename_)
+                    .map(|enm| enm.is_required.set(true))
+            })
+            .or_else(|| self.inputs.get(typename_).map(|input| input.require(self)))
+            .or_else(|| {
+                self.objects
+                    .get(typename_)
+                    .map(|object| object.require(self))
+            })
+            .or_else(|| {
+                self.scalars
+                    .get(typename_)
+                    .map(|scalar| scalar.is_required.set(true))
+            });
+    }
+
+    fn record_introspected_field_arguments(
+        &mut self,
+        type_name: &str,
+        ty: &'schema ::introspection_response::FullType,
+    ) {
+        for field in ty.fields.iter().flatten().filter_map(|f| f.as_ref()) {
+            self.field_arguments.insert(
+                format!("{}.{}", type_name, field.name.as_ref().expect("field name")),
+                field
+                    .args
+                    .iter()
+                    .flatten()
+                    .filter_map(|arg| arg.as_ref())
+                    .map(GqlArgument::from)
+                    .collect(),
+            );
+        }
+    }
+
+    pub(crate) fn contains_scalar(&self, type_name: &str) -> bool {
+        DEFAULT_SCALARS.iter().any(|s| s == &type_name) || self.scalars.contains_key(type_name)
+    }
+}
+
+impl<'schema> ::std::convert::From<&'schema graphql_parser::schema::Document> for Schema<'schema> {
+    fn from(ast: &'schema graphql_parser::schema::Document) -> Schema<'schema> {
+        let mut schema = Schema::new();
+        schema.ingest_document(ast);
         schema
     }
 }
@@ -192,6 +564,16 @@ impl<'schema> ::std::convert::From<&'schema ::introspection_response::Introspect
     for Schema<'schema>
 {
     fn from(src: &'schema ::introspection_response::IntrospectionResponse) -> Self {
+        Schema::from_introspection(src, &|_| true)
+    }
+}
+
+impl<'schema> Schema<'schema> {
+    /// The schema of an introspection response, with the types the `keep` predicate holds for.
+    fn from_introspection(
+        src: &'schema ::introspection_response::IntrospectionResponse,
+        keep: &dyn Fn(&str) -> bool,
+    ) -> Self {
         use introspection_response::__TypeKind;
 
         let mut schema = Schema::new();
@@ -216,6 +598,7 @@ impl<'schema> ::std::convert::From<&'schema ::introspection_response::Introspect
             .as_ref()
             .and_then(|ty| ty.name.as_ref())
             .map(|s| s.as_str());
+        schema.root_types_declared = true;
 
         // Holds which objects implement which interfaces so we can populate GqlInterface#implemented_by later.
         // It maps interface names to a vec of implementation names.
@@ -234,6 +617,31 @@ impl<'schema> ::std::convert::From<&'schema ::introspection_response::Introspect
                 .map(String::as_str)
                 .expect("type definition name");
 
+            if let Some(__TypeKind::OBJECT) = ty.kind {
+                for implementing in ty
+                    .interfaces
+                    .as_ref()
+                    .map(|s| s.as_slice())
+                    .unwrap_or_else(|| &[])
+                    .iter()
+                    .filter_map(|t| t.as_ref())
+                    .map(|t| &t.type_ref.name)
+                {
+                    interface_implementations
+                        .entry(
+                            implementing
+                                .as_ref()
+                                .map(String::as_str)
+                                .expect("interface name"),
+                        )
+                        .and_modify(|objects| objects.push(name))
+                        .or_insert_with(|| vec![name]);
+                }
+            }
+            if !keep(name) {
+                continue;
+            }
+
             match ty.kind {
                 Some(__TypeKind::ENUM) => {
                     let variants: Vec<EnumVariant> = ty
@@ -249,6 +657,11 @@ impl<'schema> ::std::convert::From<&'schema ::introspection_response::Introspect
                                     .as_ref()
                                     .map(String::as_str)
                                     .expect("enum variant name"),
+                                deprecation: if t.is_deprecated.unwrap_or(false) {
+                                    DeprecationStatus::Deprecated(t.deprecation_reason.clone())
+                                } else {
+                                    DeprecationStatus::Current
+                                },
                             })
                         })
                         .filter_map(|t| t)
@@ -268,6 +681,7 @@ impl<'schema> ::std::convert::From<&'schema ::introspection_response::Introspect
                             Scalar {
                                 name,
                                 description: ty.description.as_ref().map(String::as_str),
+                                specified_by: ty.specified_by_url.as_deref(),
                                 is_required: false.into(),
                             },
                         );
@@ -295,26 +709,7 @@ impl<'schema> ::std::convert::From<&'schema ::introspection_response::Introspect
                     );
                 }
                 Some(__TypeKind::OBJECT) => {
-                    for implementing in ty
-                        .interfaces
-                        .as_ref()
-                        .map(|s| s.as_slice())
-                        .unwrap_or_else(|| &[])
-                        .iter()
-                        .filter_map(|t| t.as_ref())
-                        .map(|t| &t.type_ref.name)
-                    {
-                        interface_implementations
-                            .entry(
-                                implementing
-                                    .as_ref()
-                                    .map(String::as_str)
-                                    .expect("interface name"),
-                            )
-                            .and_modify(|objects| objects.push(name))
-                            .or_insert_with(|| vec![name]);
-                    }
-
+                    schema.record_introspected_field_arguments(name, ty);
                     schema
                         .objects
                         .insert(name, GqlObject::from_introspected_schema_json(ty));
@@ -322,6 +717,15 @@ impl<'schema> ::std::convert::From<&'schema ::introspection_response::Introspect
                 Some(__TypeKind::INTERFACE) => {
                     let mut iface =
                         GqlInterface::new(name, ty.description.as_ref().map(|t| t.as_str()));
+                    iface.implements = ty
+                        .interfaces
+                        .as_deref()
+                        .unwrap_or(&[])
+                        .iter()
+                        .filter_map(|t| t.as_ref())
+                        .filter_map(|t| t.type_ref.name.as_deref())
+                        .collect();
+                    schema.record_introspected_field_arguments(name, ty);
                     iface.fields.extend(
                         ty.fields
                             .as_ref()
@@ -344,6 +748,21 @@ impl<'schema> ::std::convert::From<&'schema ::introspection_response::Introspect
             }
         }
 
+        for directive in root
+            .directives
+            .iter()
+            .flat_map(|directives| directives.iter())
+            .filter_map(|directive| directive.as_ref())
+            .filter(|directive| match directive.name {
+                Some(ref name) => !BUILTIN_DIRECTIVES.contains(&name.as_str()),
+                None => false,
+            })
+        {
+            let directive = GqlDirective::from_introspected_schema_json(directive);
+            schema.directives.insert(directive.name, directive);
+        }
+
+        interface_implementations.retain(|interface, _| keep(interface));
         schema
             .ingest_interface_implementations(interface_implementations)
             .expect("schema ingestion");
@@ -352,6 +771,22 @@ impl<'schema> ::std::convert::From<&'schema ::introspection_response::Introspect
     }
 }
 
+/// The name of the type an SDL definition defines or extends.
+fn definition_name(definition: &schema::Definition) -> Option<&str> {
+    use graphql_parser::schema::{Definition, TypeDefinition, TypeExtension};
+
+    match definition {
+        Definition::TypeDefinition(TypeDefinition::Scalar(scalar)) => Some(&scalar.name),
+        Definition::TypeDefinition(TypeDefinition::Object(obj)) => Some(&obj.name),
+        Definition::TypeDefinition(TypeDefinition::Interface(interface)) => Some(&interface.name),
+        Definition::TypeDefinition(TypeDefinition::Union(union)) => Some(&union.name),
+        Definition::TypeDefinition(TypeDefinition::Enum(enm)) => Some(&enm.name),
+        Definition::TypeDefinition(TypeDefinition::InputObject(input)) => Some(&input.name),
+        Definition::TypeExtension(TypeExtension::Object(extension)) => Some(&extension.name),
+        _ => None,
+    }
+}
+
 pub(crate) enum ParsedSchema {
     GraphQLParser(graphql_parser::schema::Document),
     Json(::introspection_response::IntrospectionResponse),
@@ -433,4 +868,122 @@ mod tests {
             })
         )
     }
+
+    #[test]
+    fn interfaces_inherit_from_the_interfaces_they_implement() {
+        let document = ::sdl::parse_schema(
+            "interface Node { id: ID! } interface Resource implements Node { url: String } type Image implements Resource & Node { id: ID! url: String } type Query { node: Node }",
+        )
+        .unwrap();
+        let from_sdl = Schema::from(&document);
+        let introspection = ::conversion::sdl_to_introspection(
+            "interface Node { id: ID! } interface Resource implements Node { url: String } type Image implements Resource { id: ID! url: String } type Query { node: Node }",
+        )
+        .unwrap();
+        let introspection: ::introspection_response::IntrospectionResponse =
+            ::serde_json::from_value(introspection).unwrap();
+        let from_introspection = Schema::from(&introspection);
+
+        for schema in &[from_sdl, from_introspection] {
+            let resource = &schema.interfaces["Resource"];
+            assert_eq!(resource.implements, vec!["Node"]);
+            assert!(resource.fields.iter().any(|field| field.name == "id"));
+            assert_eq!(schema.field("Resource", "id").unwrap().name, "id");
+            assert!(schema.interfaces["Node"].implemented_by.contains("Image"));
+        }
+    }
+
+    #[test]
+    fn fields_are_looked_up_on_objects_and_interfaces() {
+        let gql_schema = include_str!("tests/star_wars_schema.graphql");
+        let gql_schema = graphql_parser::parse_schema(gql_schema).unwrap();
+        let built = Schema::from(&gql_schema);
+
+        let friends = built.field("Droid", "friends").unwrap();
+        assert_eq!(friends.name, "friends");
+        assert_eq!(built.field("Character", "name").unwrap().name, "name");
+        assert_eq!(
+            built.field("Human", TYPENAME_FIELD).unwrap().name,
+            TYPENAME_FIELD
+        );
+        assert!(built.field("Droid", "height").is_none());
+        assert!(built.field("Episode", "name").is_none());
+    }
+
+    #[test]
+    fn custom_directives_are_retained() {
+        let document = ::sdl::parse_schema(
+            r#"
+            directive @key(fields: String!) repeatable on OBJECT
+            directive @auth(role: String) on FIELD_DEFINITION | ARGUMENT_DEFINITION
+            type Product @key(fields: "id") @key(fields: "sku") {
+              id: ID!
+              sku: String
+              price(currency: String @auth): Int @auth(role: "admin") @deprecated
+            }
+            enum Currency { EUR @auth }
+            type Query { product: Product }
+            "#,
+        )
+        .unwrap();
+        let schema = Schema::from(&document);
+
+        assert_eq!(
+            schema.directives.keys().cloned().collect::<Vec<_>>(),
+            vec!["auth", "key"]
+        );
+        let key = &schema.directives["key"];
+        assert!(key.is_repeatable);
+        assert_eq!(key.locations, vec!["OBJECT"]);
+        assert_eq!(key.arguments, vec![("fields", FieldType::Named("String"))]);
+        assert!(!schema.directives["auth"].is_repeatable);
+
+        let usages: Vec<(&str, Vec<&str>)> = schema
+            .directive_usages
+            .iter()
+            .map(|(coordinate, usages)| {
+                (
+                    coordinate.as_str(),
+                    usages.iter().map(|usage| usage.name).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            usages,
+            vec![
+                ("Currency.EUR", vec!["auth"]),
+                ("Product", vec!["key", "key"]),
+                ("Product.price", vec!["auth"]),
+                ("Product.price(currency:)", vec!["auth"]),
+            ]
+        );
+        assert_eq!(
+            schema.directive_usages["Product"][1].arguments,
+            vec![("fields", &schema::Value::String("sku".to_string()))]
+        );
+    }
+
+    #[test]
+    fn root_types_default_to_their_conventional_names() {
+        let conventional = graphql_parser::parse_schema(
+            "type Query { a: String } type Mutation { b: String } type Root { c: String }",
+        )
+        .unwrap();
+        let schema = Schema::from(&conventional);
+        assert_eq!(schema.root_type(&OperationType::Query), Some("Query"));
+        assert_eq!(schema.root_type(&OperationType::Mutation), Some("Mutation"));
+        assert_eq!(schema.root_type(&OperationType::Subscription), None);
+        assert!(schema.is_root_type("Mutation"));
+
+        let declared = graphql_parser::parse_schema(
+            "schema { query: Root } type Query { a: String } type Mutation { b: String } type Root { c: String }",
+        )
+        .unwrap();
+        let schema = Schema::from(&declared);
+        assert_eq!(schema.root_type(&OperationType::Query), Some("Root"));
+        assert_eq!(schema.root_type(&OperationType::Mutation), None);
+        assert!(schema.is_root_type("Root"));
+        assert!(!schema.is_root_type("Query"));
+        assert!(!schema.is_root_type("Mutation"));
+    }
 }
@@ -0,0 +1,727 @@
+//! A disk cache for parsed schemas, SDL documents and introspection responses alike, keyed by a hash of the schema file contents.
+//!
+//! Deserializing a multi-megabyte introspection JSON file is the slowest part of code generation, and proc macros start from a fresh process for every crate. Any failure to read or write the cache is ignored, since we can always fall back to parsing the schema.
+
+use failure;
+use introspection_response::*;
+use schema::ParsedSchema;
+use schema_format::SchemaFormat;
+use serde::de::value::BorrowedStrDeserializer;
+use serde::Deserialize;
+use serde_json;
+use std::path::PathBuf;
+
+/// Bump this when the encoding of the cached types changes.
+const FORMAT_VERSION: u8 = 5;
+const MAGIC: &[u8] = b"GQLC";
+/// Written after the format version, for the kind of schema that follows.
+const SDL_TAG: u8 = 0;
+const INTROSPECTION_TAG: u8 = 1;
+
+/// Look up the schema parsed from `contents` in the disk cache, if it was parsed as `format`.
+pub(crate) fn read(contents: &str, format: SchemaFormat) -> Option<ParsedSchema> {
+    let bytes = ::std::fs::read(cache_path(contents)?).ok()?;
+    match (decode_file(&bytes).ok()?, format) {
+        (parsed @ ParsedSchema::GraphQLParser(_), SchemaFormat::Sdl)
+        | (parsed @ ParsedSchema::Json(_), SchemaFormat::Json) => Some(parsed),
+        _ => None,
+    }
+}
+
+/// Store the schema parsed from `contents` in the disk cache.
+pub(crate) fn write(contents: &str, parsed: &ParsedSchema) {
+    let path = match cache_path(contents) {
+        Some(path) => path,
+        None => return,
+    };
+
+    let mut out = Vec::with_capacity(contents.len() / 4);
+    encode_file(parsed, &mut out);
+
+    // Write to a temporary file first so parallel builds never read a partially written cache.
+    let tmp_path = path.with_extension(format!("{}.tmp", ::std::process::id()));
+    let written = path
+        .parent()
+        .map(|dir| ::std::fs::create_dir_all(dir).is_ok())
+        .unwrap_or(false)
+        && ::std::fs::write(&tmp_path, &out).is_ok()
+        && ::std::fs::rename(&tmp_path, &path).is_ok();
+
+    if !written {
+        let _ = ::std::fs::remove_file(&tmp_path);
+    }
+}
+
+fn encode_file(parsed: &ParsedSchema, out: &mut Vec<u8>) {
+    out.extend_from_slice(MAGIC);
+    out.push(FORMAT_VERSION);
+    match parsed {
+        ParsedSchema::GraphQLParser(document) => {
+            out.push(SDL_TAG);
+            document.encode(out);
+        }
+        ParsedSchema::Json(response) => {
+            out.push(INTROSPECTION_TAG);
+            response.as_schema().schema.encode(out);
+        }
+    }
+}
+
+fn decode_file(mut bytes: &[u8]) -> Result<ParsedSchema, failure::Error> {
+    if !bytes.starts_with(MAGIC) || bytes.get(MAGIC.len()) != Some(&FORMAT_VERSION) {
+        return Err(format_err!("unknown schema cache format"));
+    }
+    bytes = &bytes[MAGIC.len() + 1..];
+
+    let parsed = match take(&mut bytes, 1)?[0] {
+        SDL_TAG => ParsedSchema::GraphQLParser(Decode::decode(&mut bytes)?),
+        INTROSPECTION_TAG => {
+            let schema = Decode::decode(&mut bytes)?;
+            ParsedSchema::Json(IntrospectionResponse::Schema(Schema { schema }))
+        }
+        tag => return Err(format_err!("unknown schema kind in schema cache: {}", tag)),
+    };
+
+    if !bytes.is_empty() {
+        return Err(format_err!("trailing bytes in schema cache"));
+    }
+
+    Ok(parsed)
+}
+
+fn cache_path(contents: &str) -> Option<PathBuf> {
+    let file_name = format!(
+        "{:016x}-{}.bin",
+        content_hash(contents.as_bytes()),
+        env!("CARGO_PKG_VERSION")
+    );
+    Some(target_dir()?.join("graphql_client_schemas").join(file_name))
+}
+
+/// The cargo target directory: `CARGO_TARGET_DIR` if it is set, otherwise the closest `target` directory above the crate being compiled (to account for workspaces).
+pub(crate) fn target_dir() -> Option<PathBuf> {
+    if let Some(dir) = ::std::env::var_os("CARGO_TARGET_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+
+    let manifest_dir = PathBuf::from(::std::env::var_os("CARGO_MANIFEST_DIR")?);
+
+    manifest_dir
+        .ancestors()
+        .map(|dir| dir.join("target"))
+        .find(|dir| dir.is_dir())
+        .or_else(|| Some(manifest_dir.join("target")))
+}
+
+/// FNV-1a. Unlike `DefaultHasher`, its output is stable across Rust releases.
+pub(crate) fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+trait Encode {
+    fn encode(&self, out: &mut Vec<u8>);
+}
+
+trait Decode: Sized {
+    fn decode(input: &mut &[u8]) -> Result<Self, failure::Error>;
+}
+
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], failure::Error> {
+    if input.len() < len {
+        return Err(format_err!("unexpected end of schema cache"));
+    }
+    let (taken, rest) = input.split_at(len);
+    *input = rest;
+    Ok(taken)
+}
+
+impl Encode for u32 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl Decode for u32 {
+    fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(take(input, 4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+}
+
+impl Encode for u64 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl Decode for u64 {
+    fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(take(input, 8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+}
+
+impl Decode for bool {
+    fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+        Ok(take(input, 1)?[0] != 0)
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.len() as u32).encode(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl Decode for String {
+    fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+        let len = u32::decode(input)? as usize;
+        Ok(String::from_utf8(take(input, len)?.to_vec())?)
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.is_some().encode(out);
+        if let Some(inner) = self {
+            inner.encode(out);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+        if bool::decode(input)? {
+            Ok(Some(T::decode(input)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.len() as u32).encode(out);
+        for item in self {
+            item.encode(out);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+        let len = u32::decode(input)? as usize;
+        // Do not trust the length for the allocation, the file could be corrupted.
+        let mut items = Vec::with_capacity(len.min(input.len()));
+        for _ in 0..len {
+            items.push(T::decode(input)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: Encode> Encode for Box<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (**self).encode(out)
+    }
+}
+
+impl<T: Decode> Decode for Box<T> {
+    fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+        Ok(Box::new(T::decode(input)?))
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+        self.1.encode(out);
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+        Ok((A::decode(input)?, B::decode(input)?))
+    }
+}
+
+/// The introspection enums are encoded as their GraphQL names, reusing their serde implementations.
+macro_rules! impl_codec_for_enum {
+    ($name:ident) => {
+        impl Encode for $name {
+            fn encode(&self, out: &mut Vec<u8>) {
+                match serde_json::to_value(self) {
+                    Ok(serde_json::Value::String(s)) => s.encode(out),
+                    _ => unreachable!("introspection enums serialize to strings"),
+                }
+            }
+        }
+
+        impl Decode for $name {
+            fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+                let name = String::decode(input)?;
+                let deserializer = BorrowedStrDeserializer::<serde_json::Error>::new(&name);
+                Ok($name::deserialize(deserializer)?)
+            }
+        }
+    };
+}
+
+macro_rules! impl_codec_for_struct {
+    ($name:ident { $($field:ident),* }) => {
+        impl Encode for $name {
+            fn encode(&self, out: &mut Vec<u8>) {
+                $(self.$field.encode(out);)*
+            }
+        }
+
+        impl Decode for $name {
+            fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+                Ok($name {
+                    $($field: Decode::decode(input)?,)*
+                })
+            }
+        }
+    };
+}
+
+/// Enums whose variants each hold one value are encoded as the tag of the variant, then the value.
+macro_rules! impl_codec_for_variants {
+    ($name:ident { $($tag:literal => $variant:ident),* }) => {
+        impl Encode for $name {
+            fn encode(&self, out: &mut Vec<u8>) {
+                match self {
+                    $($name::$variant(inner) => {
+                        out.push($tag);
+                        inner.encode(out);
+                    })*
+                }
+            }
+        }
+
+        impl Decode for $name {
+            fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+                match take(input, 1)?[0] {
+                    $($tag => Ok($name::$variant(Decode::decode(input)?)),)*
+                    tag => Err(format_err!(
+                        "unknown {} in schema cache: {}",
+                        stringify!($name),
+                        tag
+                    )),
+                }
+            }
+        }
+    };
+}
+
+impl_codec_for_enum!(__TypeKind);
+impl_codec_for_enum!(__DirectiveLocation);
+
+impl_codec_for_struct!(FullType {
+    kind,
+    name,
+    description,
+    fields,
+    input_fields,
+    interfaces,
+    enum_values,
+    possible_types,
+    specified_by_url
+});
+impl_codec_for_struct!(FullTypeFieldsArgs { input_value });
+impl_codec_for_struct!(FullTypeFieldsType { type_ref });
+impl_codec_for_struct!(FullTypeFields {
+    name,
+    description,
+    args,
+    type_,
+    is_deprecated,
+    deprecation_reason
+});
+impl_codec_for_struct!(FullTypeInputFields { input_value });
+impl_codec_for_struct!(FullTypeInterfaces { type_ref });
+impl_codec_for_struct!(FullTypeEnumValues {
+    name,
+    description,
+    is_deprecated,
+    deprecation_reason
+});
+impl_codec_for_struct!(FullTypePossibleTypes { type_ref });
+impl_codec_for_struct!(InputValue {
+    name,
+    description,
+    type_,
+    default_value,
+    is_deprecated,
+    deprecation_reason
+});
+impl_codec_for_struct!(InputValueType { type_ref });
+impl_codec_for_struct!(TypeRef {
+    kind,
+    name,
+    of_type
+});
+impl_codec_for_struct!(RustIntrospectionQuerySchemaQueryType { name });
+impl_codec_for_struct!(RustIntrospectionQuerySchemaMutationType { name });
+impl_codec_for_struct!(RustIntrospectionQuerySchemaSubscriptionType { name });
+impl_codec_for_struct!(RustIntrospectionQuerySchemaTypes { full_type });
+impl_codec_for_struct!(RustIntrospectionQuerySchemaDirectivesArgs { input_value });
+impl_codec_for_struct!(RustIntrospectionQuerySchemaDirectives {
+    name,
+    description,
+    locations,
+    args,
+    is_repeatable
+});
+impl_codec_for_struct!(RustIntrospectionQuerySchema {
+    query_type,
+    mutation_type,
+    subscription_type,
+    types,
+    directives
+});
+
+/// The codecs of the SDL documents, in their own module since graphql-parser reuses names of the introspection types.
+mod sdl {
+    use super::{take, Decode, Encode};
+    use failure;
+    use graphql_parser::schema::*;
+    use graphql_parser::Pos;
+    use std::collections::BTreeMap;
+
+    impl Encode for Pos {
+        fn encode(&self, out: &mut Vec<u8>) {
+            (self.line as u32).encode(out);
+            (self.column as u32).encode(out);
+        }
+    }
+
+    impl Decode for Pos {
+        fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+            Ok(Pos {
+                line: u32::decode(input)? as usize,
+                column: u32::decode(input)? as usize,
+            })
+        }
+    }
+
+    impl Encode for DirectiveLocation {
+        fn encode(&self, out: &mut Vec<u8>) {
+            self.as_str().to_string().encode(out)
+        }
+    }
+
+    impl Decode for DirectiveLocation {
+        fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+            let location = String::decode(input)?;
+            location.parse().map_err(|_| {
+                format_err!("unknown directive location in schema cache: {}", location)
+            })
+        }
+    }
+
+    impl Encode for Value {
+        fn encode(&self, out: &mut Vec<u8>) {
+            match self {
+                Value::Variable(name) => {
+                    out.push(0);
+                    name.encode(out);
+                }
+                Value::Int(number) => {
+                    out.push(1);
+                    (number.as_i64().expect("GraphQL ints are i64") as u64).encode(out);
+                }
+                Value::Float(float) => {
+                    out.push(2);
+                    float.to_bits().encode(out);
+                }
+                Value::String(string) => {
+                    out.push(3);
+                    string.encode(out);
+                }
+                Value::Boolean(boolean) => {
+                    out.push(4);
+                    boolean.encode(out);
+                }
+                Value::Null => out.push(5),
+                Value::Enum(name) => {
+                    out.push(6);
+                    name.encode(out);
+                }
+                Value::List(values) => {
+                    out.push(7);
+                    values.encode(out);
+                }
+                Value::Object(fields) => {
+                    out.push(8);
+                    (fields.len() as u32).encode(out);
+                    for (name, value) in fields {
+                        name.encode(out);
+                        value.encode(out);
+                    }
+                }
+            }
+        }
+    }
+
+    impl Decode for Value {
+        fn decode(input: &mut &[u8]) -> Result<Self, failure::Error> {
+            Ok(match take(input, 1)?[0] {
+                0 => Value::Variable(Decode::decode(input)?),
+                1 => int_value(u64::decode(input)? as i64)?,
+                2 => Value::Float(f64::from_bits(Decode::decode(input)?)),
+                3 => Value::String(Decode::decode(input)?),
+                4 => Value::Boolean(Decode::decode(input)?),
+                5 => Value::Null,
+                6 => Value::Enum(Decode::decode(input)?),
+                7 => Value::List(Decode::decode(input)?),
+                8 => {
+                    let fields: Vec<(String, Value)> = Decode::decode(input)?;
+                    Value::Object(fields.into_iter().collect::<BTreeMap<_, _>>())
+                }
+                tag => return Err(format_err!("unknown Value in schema cache: {}", tag)),
+            })
+        }
+    }
+
+    /// graphql-parser only builds the ints outside of the `i32` range when parsing them.
+    fn int_value(int: i64) -> Result<Value, failure::Error> {
+        if int >= i64::from(i32::MIN) && int <= i64::from(i32::MAX) {
+            return Ok(Value::Int((int as i32).into()));
+        }
+
+        let document = ::graphql_parser::parse_schema(&format!("scalar A @b(c: {})", int))?;
+        match document.definitions.into_iter().next() {
+            Some(Definition::TypeDefinition(TypeDefinition::Scalar(mut scalar))) => scalar
+                .directives
+                .pop()
+                .and_then(|mut directive| directive.arguments.pop())
+                .map(|(_, value)| value)
+                .ok_or_else(|| format_err!("could not rebuild the int {}", int)),
+            _ => Err(format_err!("could not rebuild the int {}", int)),
+        }
+    }
+
+    impl_codec_for_variants!(Type {
+        0 => NamedType,
+        1 => ListType,
+        2 => NonNullType
+    });
+    impl_codec_for_variants!(Definition {
+        0 => SchemaDefinition,
+        1 => TypeDefinition,
+        2 => TypeExtension,
+        3 => DirectiveDefinition
+    });
+    impl_codec_for_variants!(TypeDefinition {
+        0 => Scalar,
+        1 => Object,
+        2 => Interface,
+        3 => Union,
+        4 => Enum,
+        5 => InputObject
+    });
+    impl_codec_for_variants!(TypeExtension {
+        0 => Scalar,
+        1 => Object,
+        2 => Interface,
+        3 => Union,
+        4 => Enum,
+        5 => InputObject
+    });
+
+    impl_codec_for_struct!(Document { definitions });
+    impl_codec_for_struct!(Directive {
+        position,
+        name,
+        arguments
+    });
+    impl_codec_for_struct!(SchemaDefinition {
+        position,
+        directives,
+        query,
+        mutation,
+        subscription
+    });
+    impl_codec_for_struct!(ScalarType {
+        position,
+        description,
+        name,
+        directives
+    });
+    impl_codec_for_struct!(ScalarTypeExtension {
+        position,
+        name,
+        directives
+    });
+    impl_codec_for_struct!(ObjectType {
+        position,
+        description,
+        name,
+        implements_interfaces,
+        directives,
+        fields
+    });
+    impl_codec_for_struct!(ObjectTypeExtension {
+        position,
+        name,
+        implements_interfaces,
+        directives,
+        fields
+    });
+    impl_codec_for_struct!(Field {
+        position,
+        description,
+        name,
+        arguments,
+        field_type,
+        directives
+    });
+    impl_codec_for_struct!(InputValue {
+        position,
+        description,
+        name,
+        value_type,
+        default_value,
+        directives
+    });
+    impl_codec_for_struct!(InterfaceType {
+        position,
+        description,
+        name,
+        directives,
+        fields
+    });
+    impl_codec_for_struct!(InterfaceTypeExtension {
+        position,
+        name,
+        directives,
+        fields
+    });
+    impl_codec_for_struct!(UnionType {
+        position,
+        description,
+        name,
+        directives,
+        types
+    });
+    impl_codec_for_struct!(UnionTypeExtension {
+        position,
+        name,
+        directives,
+        types
+    });
+    impl_codec_for_struct!(EnumType {
+        position,
+        description,
+        name,
+        directives,
+        values
+    });
+    impl_codec_for_struct!(EnumValue {
+        position,
+        description,
+        name,
+        directives
+    });
+    impl_codec_for_struct!(EnumTypeExtension {
+        position,
+        name,
+        directives,
+        values
+    });
+    impl_codec_for_struct!(InputObjectType {
+        position,
+        description,
+        name,
+        directives,
+        fields
+    });
+    impl_codec_for_struct!(InputObjectTypeExtension {
+        position,
+        name,
+        directives,
+        fields
+    });
+    impl_codec_for_struct!(DirectiveDefinition {
+        position,
+        description,
+        name,
+        arguments,
+        locations
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema;
+
+    #[test]
+    fn cached_introspection_schema_round_trips() {
+        let json = ParsedSchema::Json(
+            serde_json::from_str(include_str!("tests/github_schema.json")).unwrap(),
+        );
+        let mut out = Vec::new();
+        encode_file(&json, &mut out);
+
+        let decoded = decode_file(&out).unwrap();
+
+        assert_eq!(schema::Schema::from(&decoded), schema::Schema::from(&json));
+    }
+
+    #[test]
+    fn cached_sdl_schema_round_trips() {
+        let sdl = format!(
+            "{}\n{}",
+            include_str!("tests/star_wars_schema.graphql"),
+            r#"
+            schema { query: Query }
+            "A scalar" scalar Big @limits(min: -9007199254740993, max: 2, ratio: 0.5)
+            directive @limits(min: Int, max: Int, ratio: Float = 1.5) repeatable on SCALAR | FIELD_DEFINITION
+            extend type Query @key(fields: ["id", {nested: null}]) { big(value: Big = 12, flag: Boolean = true): [Big!] }
+            extend enum Episode { OTHER }
+            input Filter { mode: Mode = FAST, name: String = "\u00e9" }
+            "#
+        );
+        let document = ::sdl::parse_schema(&sdl).unwrap();
+        let mut out = Vec::new();
+        encode_file(&ParsedSchema::GraphQLParser(document.clone()), &mut out);
+
+        match decode_file(&out).unwrap() {
+            ParsedSchema::GraphQLParser(decoded) => assert_eq!(decoded, document),
+            ParsedSchema::Json(_) => {
+                panic!("the SDL schema was decoded as an introspection response")
+            }
+        }
+    }
+
+    #[test]
+    fn corrupted_cache_is_rejected() {
+        assert!(decode_file(b"GQLC").is_err());
+        assert!(decode_file(b"nope").is_err());
+
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(FORMAT_VERSION);
+        out.extend_from_slice(&[1, 255, 255, 255]);
+        assert!(decode_file(&out).is_err());
+    }
+
+    #[test]
+    fn content_hash_is_stable() {
+        assert_eq!(content_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(content_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -0,0 +1,588 @@
+use conversion;
+use failure;
+use graphql_parser::schema::*;
+use schema_format::SchemaFormat;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+/// How a schema change affects the clients of the schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Criticality {
+    /// Queries that were valid against the old schema can fail against the new one.
+    Breaking,
+    /// Queries stay valid, but responses can contain values the generated code did not see before (new enum values, new union members...).
+    Dangerous,
+    /// Nothing changes for existing queries.
+    Safe,
+}
+
+impl fmt::Display for Criticality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Criticality::Breaking => "breaking",
+            Criticality::Dangerous => "dangerous",
+            Criticality::Safe => "safe",
+        })
+    }
+}
+
+/// A single difference between two schemas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaChange {
+    /// How the change affects existing queries.
+    pub criticality: Criticality,
+    /// The schema coordinate that changed, like `User`, `User.name` or `Query.users(first:)`.
+    pub path: String,
+    /// A human readable description of the change.
+    pub message: String,
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.criticality, self.message)
+    }
+}
+
+/// Compare two schemas. The changes are sorted by criticality, then by path.
+pub fn diff(old: &Document, new: &Document) -> Vec<SchemaChange> {
+    let mut differ = Differ::default();
+    let old = Definitions::from(old);
+    let new = Definitions::from(new);
+
+    for (operation, old_root, new_root) in &[
+        ("query", &old.query, &new.query),
+        ("mutation", &old.mutation, &new.mutation),
+        ("subscription", &old.subscription, &new.subscription),
+    ] {
+        if old_root != new_root {
+            let criticality = match old_root {
+                Some(_) => Criticality::Breaking,
+                None => Criticality::Safe,
+            };
+            differ.push(
+                criticality,
+                operation.to_string(),
+                format!(
+                    "The {} root type changed from {} to {}",
+                    operation,
+                    old_root.as_ref().map(String::as_str).unwrap_or("none"),
+                    new_root.as_ref().map(String::as_str).unwrap_or("none")
+                ),
+            );
+        }
+    }
+
+    for (name, old_type) in &old.types {
+        match new.types.get(name) {
+            Some(new_type) => differ.diff_types(old_type, new_type),
+            None => differ.push(
+                Criticality::Breaking,
+                name.to_string(),
+                format!("Type `{}` was removed", name),
+            ),
+        }
+    }
+
+    for name in new
+        .types
+        .keys()
+        .filter(|name| !old.types.contains_key(*name))
+    {
+        differ.push(
+            Criticality::Safe,
+            name.to_string(),
+            format!("Type `{}` was added", name),
+        );
+    }
+
+    differ
+        .changes
+        .sort_by(|a, b| (a.criticality, &a.path).cmp(&(b.criticality, &b.path)));
+    differ.changes
+}
+
+/// Compare the schemas at two paths. Like for code generation, they can be either SDL or introspection responses, detected from their contents.
+pub fn diff_files(old: &Path, new: &Path) -> Result<Vec<SchemaChange>, failure::Error> {
+    Ok(diff(&read_document(old)?, &read_document(new)?))
+}
+
+fn read_document(path: &Path) -> Result<Document, failure::Error> {
+    let contents = ::read_file(path)?;
+    let sdl = match SchemaFormat::detect(&contents) {
+        SchemaFormat::Json => conversion::introspection_to_sdl(&contents)?,
+        SchemaFormat::Sdl => contents,
+    };
+    ::sdl::parse_schema(&sdl)
+}
+
+/// The type definitions and root types of a schema document.
+struct Definitions<'doc> {
+    types: BTreeMap<&'doc str, &'doc TypeDefinition>,
+    query: Option<String>,
+    mutation: Option<String>,
+    subscription: Option<String>,
+}
+
+impl<'doc> From<&'doc Document> for Definitions<'doc> {
+    fn from(document: &'doc Document) -> Self {
+        let mut types = BTreeMap::new();
+        let mut schema_definition = None;
+
+        for definition in &document.definitions {
+            match definition {
+                Definition::TypeDefinition(ty) => {
+                    types.insert(type_name(ty), ty);
+                }
+                Definition::SchemaDefinition(definition) => schema_definition = Some(definition),
+                Definition::TypeExtension(_) | Definition::DirectiveDefinition(_) => (),
+            }
+        }
+
+        // Without a schema definition, the root types have their conventional names.
+        let root = |explicit: Option<&Option<String>>, conventional: &str| match explicit {
+            Some(explicit) => explicit.clone(),
+            None if types.contains_key(conventional) => Some(conventional.to_owned()),
+            None => None,
+        };
+
+        Definitions {
+            query: root(schema_definition.map(|s| &s.query), "Query"),
+            mutation: root(schema_definition.map(|s| &s.mutation), "Mutation"),
+            subscription: root(schema_definition.map(|s| &s.subscription), "Subscription"),
+            types,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Differ {
+    changes: Vec<SchemaChange>,
+}
+
+impl Differ {
+    fn push(&mut self, criticality: Criticality, path: String, message: String) {
+        self.changes.push(SchemaChange {
+            criticality,
+            path,
+            message,
+        });
+    }
+
+    fn diff_types(&mut self, old: &TypeDefinition, new: &TypeDefinition) {
+        let name = type_name(old);
+
+        match (old, new) {
+            (TypeDefinition::Scalar(_), TypeDefinition::Scalar(_)) => (),
+            (TypeDefinition::Object(old), TypeDefinition::Object(new)) => {
+                self.diff_fields(name, &old.fields, &new.fields);
+                self.diff_members(
+                    name,
+                    "Interface",
+                    "implemented by object",
+                    &old.implements_interfaces,
+                    &new.implements_interfaces,
+                    Criticality::Safe,
+                );
+            }
+            (TypeDefinition::Interface(old), TypeDefinition::Interface(new)) => {
+                self.diff_fields(name, &old.fields, &new.fields);
+            }
+            (TypeDefinition::Union(old), TypeDefinition::Union(new)) => {
+                self.diff_members(
+                    name,
+                    "Member",
+                    "of union",
+                    &old.types,
+                    &new.types,
+                    Criticality::Dangerous,
+                );
+            }
+            (TypeDefinition::Enum(old), TypeDefinition::Enum(new)) => {
+                let old_values: Vec<String> = old.values.iter().map(|v| v.name.clone()).collect();
+                let new_values: Vec<String> = new.values.iter().map(|v| v.name.clone()).collect();
+                self.diff_members(
+                    name,
+                    "Value",
+                    "of enum",
+                    &old_values,
+                    &new_values,
+                    Criticality::Dangerous,
+                );
+            }
+            (TypeDefinition::InputObject(old), TypeDefinition::InputObject(new)) => {
+                self.diff_input_values(name, "Input field", &old.fields, &new.fields);
+            }
+            _ => self.push(
+                Criticality::Breaking,
+                name.to_owned(),
+                format!(
+                    "Type `{}` changed from {} to {}",
+                    name,
+                    type_kind(old),
+                    type_kind(new)
+                ),
+            ),
+        }
+    }
+
+    fn diff_fields(&mut self, type_name: &str, old: &[Field], new: &[Field]) {
+        for old_field in old {
+            let path = format!("{}.{}", type_name, old_field.name);
+
+            let new_field = match new.iter().find(|f| f.name == old_field.name) {
+                Some(new_field) => new_field,
+                None => {
+                    self.push(
+                        Criticality::Breaking,
+                        path.clone(),
+                        format!("Field `{}` was removed", path),
+                    );
+                    continue;
+                }
+            };
+
+            if old_field.field_type != new_field.field_type {
+                let criticality =
+                    if is_safe_output_change(&old_field.field_type, &new_field.field_type) {
+                        Criticality::Safe
+                    } else {
+                        Criticality::Breaking
+                    };
+                self.push(
+                    criticality,
+                    path.clone(),
+                    format!(
+                        "Field `{}` changed type from `{}` to `{}`",
+                        path, old_field.field_type, new_field.field_type
+                    ),
+                );
+            }
+
+            self.diff_input_values(
+                &path,
+                "Argument",
+                &old_field.arguments,
+                &new_field.arguments,
+            );
+        }
+
+        for new_field in new.iter().filter(|f| !old.iter().any(|o| o.name == f.name)) {
+            let path = format!("{}.{}", type_name, new_field.name);
+            self.push(
+                Criticality::Safe,
+                path.clone(),
+                format!("Field `{}` was added", path),
+            );
+        }
+    }
+
+    /// Arguments and input object fields follow the same rules: clients have to provide the required ones, so adding one breaks them.
+    fn diff_input_values(
+        &mut self,
+        parent: &str,
+        what: &str,
+        old: &[InputValue],
+        new: &[InputValue],
+    ) {
+        let coordinate = |value: &InputValue| {
+            if what == "Argument" {
+                format!("{}({}:)", parent, value.name)
+            } else {
+                format!("{}.{}", parent, value.name)
+            }
+        };
+
+        for old_value in old {
+            let path = coordinate(old_value);
+
+            let new_value = match new.iter().find(|v| v.name == old_value.name) {
+                Some(new_value) => new_value,
+                None => {
+                    self.push(
+                        Criticality::Breaking,
+                        path.clone(),
+                        format!("{} `{}` was removed", what, path),
+                    );
+                    continue;
+                }
+            };
+
+            if old_value.value_type != new_value.value_type {
+                let criticality =
+                    if is_safe_input_change(&old_value.value_type, &new_value.value_type) {
+                        Criticality::Safe
+                    } else {
+                        Criticality::Breaking
+                    };
+                self.push(
+                    criticality,
+                    path.clone(),
+                    format!(
+                        "{} `{}` changed type from `{}` to `{}`",
+                        what, path, old_value.value_type, new_value.value_type
+                    ),
+                );
+            }
+
+            if old_value.default_value != new_value.default_value {
+                let print = |value: &Option<Value>| {
+                    value
+                        .as_ref()
+                        .map(|value| format!("`{}`", value))
+                        .unwrap_or_else(|| "none".to_owned())
+                };
+                self.push(
+                    Criticality::Dangerous,
+                    path.clone(),
+                    format!(
+                        "{} `{}` changed default value from {} to {}",
+                        what,
+                        path,
+                        print(&old_value.default_value),
+                        print(&new_value.default_value)
+                    ),
+                );
+            }
+        }
+
+        for new_value in new.iter().filter(|v| !old.iter().any(|o| o.name == v.name)) {
+            let path = coordinate(new_value);
+            let required = match new_value.value_type {
+                Type::NonNullType(_) => new_value.default_value.is_none(),
+                _ => false,
+            };
+
+            if required {
+                self.push(
+                    Criticality::Breaking,
+                    path.clone(),
+                    format!("Required {} `{}` was added", what.to_lowercase(), path),
+                );
+            } else {
+                self.push(
+                    Criticality::Safe,
+                    path.clone(),
+                    format!("{} `{}` was added", what, path),
+                );
+            }
+        }
+    }
+
+    /// Interfaces of objects, members of unions and values of enums. Removing one is always breaking, adding one is `added_criticality`.
+    fn diff_members(
+        &mut self,
+        type_name: &str,
+        what: &str,
+        relation: &str,
+        old: &[String],
+        new: &[String],
+        added_criticality: Criticality,
+    ) {
+        let old: BTreeSet<&String> = old.iter().collect();
+        let new: BTreeSet<&String> = new.iter().collect();
+
+        for removed in old.difference(&new) {
+            self.push(
+                Criticality::Breaking,
+                format!("{}.{}", type_name, removed),
+                format!(
+                    "{} `{}` {} `{}` was removed",
+                    what, removed, relation, type_name
+                ),
+            );
+        }
+
+        for added in new.difference(&old) {
+            self.push(
+                added_criticality,
+                format!("{}.{}", type_name, added),
+                format!(
+                    "{} `{}` {} `{}` was added",
+                    what, added, relation, type_name
+                ),
+            );
+        }
+    }
+}
+
+/// Responses can only get more precise: a nullable field can become non-null.
+fn is_safe_output_change(old: &Type, new: &Type) -> bool {
+    match (old, new) {
+        (Type::NamedType(old), Type::NamedType(new)) => old == new,
+        (Type::ListType(old), Type::ListType(new)) => is_safe_output_change(old, new),
+        (Type::NonNullType(old), Type::NonNullType(new)) => is_safe_output_change(old, new),
+        (old, Type::NonNullType(new)) => is_safe_output_change(old, new),
+        _ => false,
+    }
+}
+
+/// Inputs can only get more permissive: a non-null argument can become nullable.
+fn is_safe_input_change(old: &Type, new: &Type) -> bool {
+    match (old, new) {
+        (Type::NamedType(old), Type::NamedType(new)) => old == new,
+        (Type::ListType(old), Type::ListType(new)) => is_safe_input_change(old, new),
+        (Type::NonNullType(old), Type::NonNullType(new)) => is_safe_input_change(old, new),
+        (Type::NonNullType(old), new) => is_safe_input_change(old, new),
+        _ => false,
+    }
+}
+
+fn type_name(definition: &TypeDefinition) -> &str {
+    match definition {
+        TypeDefinition::Scalar(ty) => &ty.name,
+        TypeDefinition::Object(ty) => &ty.name,
+        TypeDefinition::Interface(ty) => &ty.name,
+        TypeDefinition::Union(ty) => &ty.name,
+        TypeDefinition::Enum(ty) => &ty.name,
+        TypeDefinition::InputObject(ty) => &ty.name,
+    }
+}
+
+fn type_kind(definition: &TypeDefinition) -> &'static str {
+    match definition {
+        TypeDefinition::Scalar(_) => "a scalar",
+        TypeDefinition::Object(_) => "an object",
+        TypeDefinition::Interface(_) => "an interface",
+        TypeDefinition::Union(_) => "a union",
+        TypeDefinition::Enum(_) => "an enum",
+        TypeDefinition::InputObject(_) => "an input object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(old: &str, new: &str) -> Vec<(Criticality, String)> {
+        diff(&parse_schema(old).unwrap(), &parse_schema(new).unwrap())
+            .into_iter()
+            .map(|change| (change.criticality, change.message))
+            .collect()
+    }
+
+    #[test]
+    fn identical_schemas_have_no_changes() {
+        let schema = include_str!("tests/star_wars_schema.graphql");
+        assert!(changes(schema, schema).is_empty());
+    }
+
+    #[test]
+    fn diff_classifies_field_and_argument_changes() {
+        let old = r#"
+            type Query {
+              users(first: Int!, after: String): [User]
+              viewer: User
+            }
+
+            type User {
+              name: String
+              email: String
+              age: Int
+            }
+        "#;
+        let new = r#"
+            type Query {
+              users(first: Int, after: String, order: Order!): [User]
+              viewer: User!
+            }
+
+            type User {
+              name: String!
+              age: String
+              avatar: String
+            }
+
+            enum Order {
+              ASC
+            }
+        "#;
+
+        assert_eq!(
+            changes(old, new),
+            vec![
+                (
+                    Criticality::Breaking,
+                    "Required argument `Query.users(order:)` was added".to_owned()
+                ),
+                (
+                    Criticality::Breaking,
+                    "Field `User.age` changed type from `Int` to `String`".to_owned()
+                ),
+                (
+                    Criticality::Breaking,
+                    "Field `User.email` was removed".to_owned()
+                ),
+                (Criticality::Safe, "Type `Order` was added".to_owned()),
+                (
+                    Criticality::Safe,
+                    "Argument `Query.users(first:)` changed type from `Int!` to `Int`".to_owned()
+                ),
+                (
+                    Criticality::Safe,
+                    "Field `Query.viewer` changed type from `User` to `User!`".to_owned()
+                ),
+                (
+                    Criticality::Safe,
+                    "Field `User.avatar` was added".to_owned()
+                ),
+                (
+                    Criticality::Safe,
+                    "Field `User.name` changed type from `String` to `String!`".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_classifies_enum_union_and_input_changes() {
+        let old = r#"
+            type Query { a: Int }
+            enum Order { ASC DESC }
+            union Result = A | B
+            type A { a: Int }
+            type B { b: Int }
+            input Filter { name: String, limit: Int = 10 }
+        "#;
+        let new = r#"
+            type Query { a: Int }
+            enum Order { ASC RANDOM }
+            union Result = A | C
+            type A { a: Int }
+            type C { c: Int }
+            input Filter { name: String, limit: Int = 20, tag: String! }
+        "#;
+
+        assert_eq!(
+            changes(old, new),
+            vec![
+                (Criticality::Breaking, "Type `B` was removed".to_owned()),
+                (
+                    Criticality::Breaking,
+                    "Required input field `Filter.tag` was added".to_owned()
+                ),
+                (
+                    Criticality::Breaking,
+                    "Value `DESC` of enum `Order` was removed".to_owned()
+                ),
+                (
+                    Criticality::Breaking,
+                    "Member `B` of union `Result` was removed".to_owned()
+                ),
+                (
+                    Criticality::Dangerous,
+                    "Input field `Filter.limit` changed default value from `10` to `20`".to_owned()
+                ),
+                (
+                    Criticality::Dangerous,
+                    "Value `RANDOM` of enum `Order` was added".to_owned()
+                ),
+                (
+                    Criticality::Dangerous,
+                    "Member `C` of union `Result` was added".to_owned()
+                ),
+                (Criticality::Safe, "Type `C` was added".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,61 @@
+/// How a schema file is written.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SchemaFormat {
+    /// Schema definition language, like `type Query { hero: Character }`.
+    Sdl,
+    /// A JSON introspection response.
+    Json,
+}
+
+impl ::std::str::FromStr for SchemaFormat {
+    type Err = ::failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "sdl" => Ok(SchemaFormat::Sdl),
+            "json" => Ok(SchemaFormat::Json),
+            other => Err(format_err!(
+                "Invalid schema format: `{}`. Valid values are `sdl` and `json`.",
+                other
+            )),
+        }
+    }
+}
+
+impl SchemaFormat {
+    /// The format of a schema, from its contents rather than its extension, so `.graphqls`, `.gql.json` or extension-less files work too. SDL documents start with a keyword, a description or a comment, never with the `{` of a JSON object.
+    pub(crate) fn detect(contents: &str) -> SchemaFormat {
+        if contents
+            .trim_start_matches('\u{feff}')
+            .trim_start()
+            .starts_with('{')
+        {
+            SchemaFormat::Json
+        } else {
+            SchemaFormat::Sdl
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_are_detected_from_the_contents() {
+        assert_eq!(
+            SchemaFormat::detect("\u{feff}\n  {\"data\": {\"__schema\": {}}}"),
+            SchemaFormat::Json
+        );
+        assert_eq!(
+            SchemaFormat::detect("# The schema\ntype Query { hero: String }"),
+            SchemaFormat::Sdl
+        );
+        assert_eq!(
+            SchemaFormat::detect("\"\"\"The root\"\"\"\nschema { query: Query }"),
+            SchemaFormat::Sdl
+        );
+        assert_eq!("json".parse::<SchemaFormat>().unwrap(), SchemaFormat::Json);
+        assert!("yaml".parse::<SchemaFormat>().is_err());
+    }
+}
@@ -0,0 +1,647 @@
+use constants::{IMPLEMENTS_INTERFACES_DIRECTIVE, REPEATABLE_ARGUMENT};
+use failure;
+use graphql_parser::schema::{
+    parse_schema as parse_document, Definition, Directive, DirectiveDefinition, Document, Field,
+    InputValue, Type, TypeDefinition, Value,
+};
+use graphql_parser::Pos;
+
+/// Parse an SDL schema.
+///
+/// graphql_parser does not know about interfaces implementing interfaces, repeatable directives nor schema extensions, so before parsing:
+///
+/// - the `implements` clause of interfaces is turned into an `@implementsInterfaces(interfaces: [...])` directive. See [implemented_interfaces].
+/// - the `repeatable` keyword of directive definitions is turned into an extra `__repeatable` argument. See [is_repeatable].
+/// - schema extensions, like the `extend schema @link(...)` of federation subgraphs, become schema definitions when they declare operation types, and are left out otherwise.
+/// - the string literals it would decode wrongly, block string descriptions among them, are rewritten. See [::string_literals::rewrite_string_literals].
+///
+/// The definitions, fields, arguments and enum values without a description then get the comments right above them, the way SDL was documented before descriptions were strings.
+pub(crate) fn parse_schema(sdl: &str) -> Result<Document, failure::Error> {
+    let rewritten = rewrite_unsupported_syntax(sdl);
+    let rewritten = ::string_literals::rewrite_string_literals(&rewritten);
+    let mut document = parse_document(&rewritten)?;
+    describe_with_comments(&mut document, &rewritten);
+    Ok(document)
+}
+
+/// Print a document parsed by [parse_schema] back to SDL, with the syntax graphql_parser does not support.
+pub(crate) fn print_schema(document: &Document) -> String {
+    let mut document = document.clone();
+    // The extra argument is printed last, to find it easily.
+    for definition in &mut document.definitions {
+        if let Definition::DirectiveDefinition(directive) = definition {
+            if is_repeatable(directive) {
+                directive
+                    .arguments
+                    .retain(|argument| argument.name != REPEATABLE_ARGUMENT);
+                directive.arguments.push(repeatable_argument());
+            }
+        }
+    }
+
+    restore_repeatable_directives(&restore_interface_implementations(&document.to_string()))
+}
+
+/// Whether the directive was defined as `repeatable`.
+pub(crate) fn is_repeatable(directive: &DirectiveDefinition) -> bool {
+    directive
+        .arguments
+        .iter()
+        .any(|argument| argument.name == REPEATABLE_ARGUMENT)
+}
+
+/// The arguments of a directive definition, without the one standing for `repeatable`.
+pub(crate) fn directive_arguments(directive: &DirectiveDefinition) -> Vec<&InputValue> {
+    directive
+        .arguments
+        .iter()
+        .filter(|argument| argument.name != REPEATABLE_ARGUMENT)
+        .collect()
+}
+
+/// The argument standing for the `repeatable` keyword of a directive definition.
+pub(crate) fn repeatable_argument() -> InputValue {
+    InputValue {
+        position: Default::default(),
+        description: None,
+        name: REPEATABLE_ARGUMENT.to_string(),
+        value_type: Type::NamedType("Boolean".to_string()),
+        default_value: None,
+        directives: Vec::new(),
+    }
+}
+
+/// The interfaces implemented by an interface, from the directive added by [parse_schema].
+pub(crate) fn implemented_interfaces(directives: &[Directive]) -> Vec<&str> {
+    directives
+        .iter()
+        .filter(|directive| directive.name == IMPLEMENTS_INTERFACES_DIRECTIVE)
+        .flat_map(|directive| directive.arguments.iter())
+        .flat_map(|(_, value)| match value {
+            Value::List(values) => values.iter().collect(),
+            _ => Vec::new(),
+        })
+        .filter_map(|value| match value {
+            Value::String(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The directive standing for the `implements` clause of an interface.
+pub(crate) fn implements_interfaces_directive(interfaces: Vec<String>) -> Directive {
+    Directive {
+        position: Default::default(),
+        name: IMPLEMENTS_INTERFACES_DIRECTIVE.to_string(),
+        arguments: vec![(
+            "interfaces".to_string(),
+            Value::List(interfaces.into_iter().map(Value::String).collect()),
+        )],
+    }
+}
+
+/// Turn the directives added by [parse_schema] back into `implements` clauses in a printed document.
+pub(crate) fn restore_interface_implementations(printed: &str) -> String {
+    let marker = format!("@{}(interfaces: [", IMPLEMENTS_INTERFACES_DIRECTIVE);
+    let mut restored = String::with_capacity(printed.len());
+    let mut rest = printed;
+
+    while let Some(start) = rest.find(&marker) {
+        let after_marker = &rest[start + marker.len()..];
+        let end = match after_marker.find("])") {
+            Some(end) => end,
+            None => break,
+        };
+        let names: Vec<&str> = after_marker[..end]
+            .split(',')
+            .map(|name| name.trim().trim_matches('"'))
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        restored.push_str(&rest[..start]);
+        restored.push_str("implements ");
+        restored.push_str(&names.join(" & "));
+        rest = &after_marker[end + 2..];
+    }
+
+    restored.push_str(rest);
+    restored
+}
+
+fn restore_repeatable_directives(printed: &str) -> String {
+    printed
+        .replace(
+            &format!(", {}: Boolean)", REPEATABLE_ARGUMENT),
+            ") repeatable",
+        )
+        .replace(
+            &format!("({}: Boolean)", REPEATABLE_ARGUMENT),
+            " repeatable",
+        )
+}
+
+fn describe_with_comments(document: &mut Document, sdl: &str) {
+    let lines: Vec<&str> = sdl.lines().collect();
+    let describe_fields = |fields: &mut Vec<Field>| {
+        for field in fields {
+            describe(&mut field.description, field.position, &lines);
+            for argument in &mut field.arguments {
+                describe(&mut argument.description, argument.position, &lines);
+            }
+        }
+    };
+
+    for definition in &mut document.definitions {
+        match definition {
+            Definition::TypeDefinition(TypeDefinition::Scalar(scalar)) => {
+                describe(&mut scalar.description, scalar.position, &lines)
+            }
+            Definition::TypeDefinition(TypeDefinition::Object(object)) => {
+                describe(&mut object.description, object.position, &lines);
+                describe_fields(&mut object.fields);
+            }
+            Definition::TypeDefinition(TypeDefinition::Interface(interface)) => {
+                describe(&mut interface.description, interface.position, &lines);
+                describe_fields(&mut interface.fields);
+            }
+            Definition::TypeDefinition(TypeDefinition::Union(union)) => {
+                describe(&mut union.description, union.position, &lines)
+            }
+            Definition::TypeDefinition(TypeDefinition::Enum(enm)) => {
+                describe(&mut enm.description, enm.position, &lines);
+                for value in &mut enm.values {
+                    describe(&mut value.description, value.position, &lines);
+                }
+            }
+            Definition::TypeDefinition(TypeDefinition::InputObject(input)) => {
+                describe(&mut input.description, input.position, &lines);
+                for field in &mut input.fields {
+                    describe(&mut field.description, field.position, &lines);
+                }
+            }
+            Definition::DirectiveDefinition(directive) => {
+                describe(&mut directive.description, directive.position, &lines);
+                for argument in &mut directive.arguments {
+                    describe(&mut argument.description, argument.position, &lines);
+                }
+            }
+            Definition::SchemaDefinition(_) | Definition::TypeExtension(_) => (),
+        }
+    }
+}
+
+/// Set a missing description to the comment lines right above the element at `position`, without their common indentation, if it starts its line.
+fn describe(description: &mut Option<String>, position: Pos, lines: &[&str]) {
+    if description.is_some() || position.line < 2 {
+        return;
+    }
+    let starts_line = lines
+        .get(position.line - 1)
+        .map(|line| {
+            line.chars()
+                .take(position.column - 1)
+                .all(char::is_whitespace)
+        })
+        .unwrap_or(false);
+    if !starts_line {
+        return;
+    }
+
+    let mut comments: Vec<&str> = lines[..position.line - 1]
+        .iter()
+        .rev()
+        .map(|line| line.trim())
+        .take_while(|line| line.starts_with('#'))
+        .map(|line| &line[1..])
+        .collect();
+    comments.reverse();
+    let indentation = comments
+        .iter()
+        .filter(|comment| !comment.trim().is_empty())
+        .map(|comment| comment.len() - comment.trim_start().len())
+        .min();
+    if let Some(indentation) = indentation {
+        let comments: Vec<&str> = comments
+            .iter()
+            .map(|comment| comment.get(indentation..).unwrap_or(""))
+            .collect();
+        *description = Some(comments.join("\n").trim_matches('\n').to_owned());
+    }
+}
+
+fn rewrite_unsupported_syntax(sdl: &str) -> String {
+    let chars: Vec<char> = sdl.chars().collect();
+    let mut output = String::with_capacity(sdl.len());
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+        if c == '#' {
+            let end = find_from(&chars, idx, |c| c == '\n').unwrap_or(chars.len());
+            output.extend(&chars[idx..end]);
+            idx = end;
+        } else if c == '"' {
+            let end = string_end(&chars, idx);
+            output.extend(&chars[idx..end]);
+            idx = end;
+        } else if is_name_start(c) {
+            let end = name_end(&chars, idx);
+            let word: String = chars[idx..end].iter().collect();
+            if word == "extend" {
+                if let Some(schema_extension_end) =
+                    rewrite_schema_extension(&chars, end, &mut output)
+                {
+                    idx = schema_extension_end;
+                    continue;
+                }
+            }
+            output.push_str(&word);
+            idx = end;
+
+            if word == "interface" {
+                idx = rewrite_implements_clause(&chars, idx, &mut output);
+            } else if word == "directive" {
+                idx = rewrite_repeatable_keyword(&chars, idx, &mut output);
+            }
+        } else {
+            output.push(c);
+            idx += 1;
+        }
+    }
+
+    output
+}
+
+/// After the `interface` keyword: copy the interface name, and replace the `implements A & B` clause that may follow with the directive.
+fn rewrite_implements_clause(chars: &[char], mut idx: usize, output: &mut String) -> usize {
+    let name_start = skip_ignored(chars, idx);
+    if name_start >= chars.len() || !is_name_start(chars[name_start]) {
+        return idx;
+    }
+    let interface_name_end = name_end(chars, name_start);
+    output.extend(&chars[idx..interface_name_end]);
+    idx = interface_name_end;
+
+    let keyword_start = skip_ignored(chars, idx);
+    if keyword_start >= chars.len() || !is_name_start(chars[keyword_start]) {
+        return idx;
+    }
+    let keyword_end = name_end(chars, keyword_start);
+    if chars[keyword_start..keyword_end].iter().collect::<String>() != "implements" {
+        return idx;
+    }
+
+    let mut interfaces = Vec::new();
+    let mut cursor = skip_ignored(chars, keyword_end);
+    if cursor < chars.len() && chars[cursor] == '&' {
+        cursor = skip_ignored(chars, cursor + 1);
+    }
+    while cursor < chars.len() && is_name_start(chars[cursor]) {
+        let end = name_end(chars, cursor);
+        interfaces.push(chars[cursor..end].iter().collect::<String>());
+        let next = skip_ignored(chars, end);
+        idx = end;
+        if next < chars.len() && chars[next] == '&' {
+            cursor = skip_ignored(chars, next + 1);
+        } else {
+            break;
+        }
+    }
+
+    if interfaces.is_empty() {
+        return idx;
+    }
+
+    let names: Vec<String> = interfaces
+        .iter()
+        .map(|name| format!("\"{}\"", name))
+        .collect();
+    output.push_str(&format!(
+        " @{}(interfaces: [{}])",
+        IMPLEMENTS_INTERFACES_DIRECTIVE,
+        names.join(", ")
+    ));
+    idx
+}
+
+/// After the `directive` keyword: copy the directive name and its arguments, and replace the `repeatable` keyword that may follow with an extra argument.
+fn rewrite_repeatable_keyword(chars: &[char], idx: usize, output: &mut String) -> usize {
+    let at = skip_ignored(chars, idx);
+    if at >= chars.len() || chars[at] != '@' {
+        return idx;
+    }
+    let name_start = skip_ignored(chars, at + 1);
+    if name_start >= chars.len() || !is_name_start(chars[name_start]) {
+        return idx;
+    }
+    let directive_name_end = name_end(chars, name_start);
+
+    let open_paren = skip_ignored(chars, directive_name_end);
+    let close_paren = if open_paren < chars.len() && chars[open_paren] == '(' {
+        match arguments_end(chars, open_paren) {
+            Some(close_paren) => Some(close_paren),
+            None => return idx,
+        }
+    } else {
+        None
+    };
+
+    let keyword_start = skip_ignored(
+        chars,
+        close_paren
+            .map(|close| close + 1)
+            .unwrap_or(directive_name_end),
+    );
+    if keyword_start >= chars.len() || !is_name_start(chars[keyword_start]) {
+        return idx;
+    }
+    let keyword_end = name_end(chars, keyword_start);
+    if chars[keyword_start..keyword_end].iter().collect::<String>() != "repeatable" {
+        return idx;
+    }
+
+    // The extra argument goes first, so comments at the end of the arguments cannot swallow it.
+    let argument = format!("{}: Boolean", REPEATABLE_ARGUMENT);
+    match close_paren {
+        Some(close_paren) => {
+            output.extend(&chars[idx..=open_paren]);
+            output.push_str(&argument);
+            output.push_str(", ");
+            output.extend(&chars[open_paren + 1..=close_paren]);
+        }
+        None => {
+            output.extend(&chars[idx..directive_name_end]);
+            output.push_str(&format!("({})", argument));
+        }
+    }
+    keyword_end
+}
+
+/// After the `extend` keyword: when it starts a schema extension, copy it without the keyword if it declares operation types, skip it otherwise. Returns `None` for other extensions.
+fn rewrite_schema_extension(chars: &[char], idx: usize, output: &mut String) -> Option<usize> {
+    let keyword_start = skip_ignored(chars, idx);
+    if keyword_start >= chars.len() || !is_name_start(chars[keyword_start]) {
+        return None;
+    }
+    let keyword_end = name_end(chars, keyword_start);
+    if chars[keyword_start..keyword_end].iter().collect::<String>() != "schema" {
+        return None;
+    }
+
+    let mut cursor = skip_ignored(chars, keyword_end);
+    while cursor < chars.len() && chars[cursor] == '@' {
+        let name_start = skip_ignored(chars, cursor + 1);
+        let mut directive_end = name_end(chars, name_start);
+        let open_paren = skip_ignored(chars, directive_end);
+        if open_paren < chars.len() && chars[open_paren] == '(' {
+            directive_end = arguments_end(chars, open_paren)? + 1;
+        }
+        cursor = skip_ignored(chars, directive_end);
+    }
+
+    if cursor < chars.len() && chars[cursor] == '{' {
+        output.extend(&chars[keyword_start..cursor]);
+    }
+    Some(cursor)
+}
+
+/// The index of the parenthesis closing the arguments opened at `open_paren`.
+fn arguments_end(chars: &[char], open_paren: usize) -> Option<usize> {
+    let mut idx = open_paren + 1;
+    while idx < chars.len() {
+        match chars[idx] {
+            ')' => return Some(idx),
+            '"' => idx = string_end(chars, idx),
+            '#' => idx = find_from(chars, idx, |c| c == '\n').unwrap_or(chars.len()),
+            _ => idx += 1,
+        }
+    }
+    None
+}
+
+fn is_name_start(c: char) -> bool {
+    c == '_' || c.is_ascii_alphabetic()
+}
+
+fn name_end(chars: &[char], start: usize) -> usize {
+    find_from(chars, start, |c| !(c == '_' || c.is_ascii_alphanumeric())).unwrap_or(chars.len())
+}
+
+fn find_from(chars: &[char], start: usize, predicate: impl Fn(char) -> bool) -> Option<usize> {
+    chars[start..]
+        .iter()
+        .position(|c| predicate(*c))
+        .map(|offset| start + offset)
+}
+
+/// Skip whitespace, commas and comments.
+fn skip_ignored(chars: &[char], mut idx: usize) -> usize {
+    while idx < chars.len() {
+        match chars[idx] {
+            ' ' | '\t' | '\n' | '\r' | ',' | '\u{feff}' => idx += 1,
+            '#' => idx = find_from(chars, idx, |c| c == '\n').unwrap_or(chars.len()),
+            _ => break,
+        }
+    }
+    idx
+}
+
+/// The index after the string or block string starting at `start`.
+fn string_end(chars: &[char], start: usize) -> usize {
+    let is_block = chars[start..].starts_with(&['"', '"', '"']);
+    let mut idx = start + if is_block { 3 } else { 1 };
+
+    while idx < chars.len() {
+        if is_block {
+            if chars[idx..].starts_with(&['\\', '"', '"', '"']) {
+                idx += 4;
+            } else if chars[idx..].starts_with(&['"', '"', '"']) {
+                return idx + 3;
+            } else {
+                idx += 1;
+            }
+        } else {
+            match chars[idx] {
+                '\\' => idx += 2,
+                '"' | '\n' => return idx + 1,
+                _ => idx += 1,
+            }
+        }
+    }
+
+    chars.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interfaces_implementing_interfaces_are_parsed() {
+        let document = parse_schema(
+            r#"
+            # interface Commented implements Node
+            interface Node { id: ID! }
+            """interface Described implements Node"""
+            interface Resource implements Node & Named @deprecated { id: ID! name: String }
+            interface Named implements
+              & Node { id: ID! name: String }
+            type Query { node: Node }
+            "#,
+        )
+        .unwrap();
+
+        let implemented: Vec<Vec<&str>> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                ::graphql_parser::schema::Definition::TypeDefinition(
+                    ::graphql_parser::schema::TypeDefinition::Interface(interface),
+                ) => Some(implemented_interfaces(&interface.directives)),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            implemented,
+            vec![vec![], vec!["Node", "Named"], vec!["Node"]]
+        );
+        assert!(restore_interface_implementations(&document.to_string())
+            .contains("interface Resource implements Node & Named @deprecated {"));
+    }
+
+    #[test]
+    fn repeatable_directives_are_parsed() {
+        let document = parse_schema(
+            r#"
+            directive @tag(name: String # the tag
+            ) repeatable on FIELD_DEFINITION | OBJECT
+            directive @key repeatable on OBJECT
+            directive @auth(role: String) on FIELD_DEFINITION
+            type Query { repeatable: String directive: String }
+            "#,
+        )
+        .unwrap();
+
+        let directives: Vec<(&str, bool, Vec<&str>)> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                Definition::DirectiveDefinition(directive) => Some((
+                    directive.name.as_str(),
+                    is_repeatable(directive),
+                    directive_arguments(directive)
+                        .iter()
+                        .map(|argument| argument.name.as_str())
+                        .collect(),
+                )),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            directives,
+            vec![
+                ("tag", true, vec!["name"]),
+                ("key", true, vec![]),
+                ("auth", false, vec!["role"]),
+            ]
+        );
+
+        let printed = print_schema(&document);
+        assert!(printed
+            .contains("directive @tag(name: String) repeatable on FIELD_DEFINITION | OBJECT"));
+        assert!(printed.contains("directive @key repeatable on OBJECT"));
+        assert!(printed.contains("directive @auth(role: String) on FIELD_DEFINITION"));
+    }
+
+    #[test]
+    fn schema_extensions_are_parsed() {
+        let document = parse_schema(
+            r#"
+            extend schema
+              @link(url: "https://specs.apollo.dev/federation/v2.0", import: ["@key"])
+            extend schema @composeDirective(name: "@custom") { mutation: Mutation }
+            type Query { extend: String }
+            type Mutation { ping: String }
+            "#,
+        )
+        .unwrap();
+
+        let mutations: Vec<Option<&str>> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                Definition::SchemaDefinition(schema) => Some(schema.mutation.as_deref()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(mutations, vec![Some("Mutation")]);
+        assert_eq!(document.definitions.len(), 3);
+    }
+
+    #[test]
+    fn descriptions_are_read_from_block_strings_and_comments() {
+        let document = parse_schema(
+            r#"
+            """  Indented first line.
+
+              Code:
+
+                  query { viewer }
+            """
+            type Query {
+              # Who is
+              #   logged in.
+              viewer: String # not the description of `count`
+              count(
+                # Stop there.
+                limit: Int
+              ): Int
+              "A string description."
+              # The comment is left out.
+              other: String
+            }
+
+            # Not above the definition.
+
+            enum Episode {
+              # The first.
+              NEWHOPE
+            }
+            "#,
+        )
+        .unwrap();
+
+        let (query, episode) = match (&document.definitions[0], &document.definitions[1]) {
+            (
+                Definition::TypeDefinition(TypeDefinition::Object(query)),
+                Definition::TypeDefinition(TypeDefinition::Enum(episode)),
+            ) => (query, episode),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            query.description.as_deref(),
+            Some("  Indented first line.\n\nCode:\n\n    query { viewer }")
+        );
+        let descriptions: Vec<Option<&str>> = query
+            .fields
+            .iter()
+            .map(|field| field.description.as_deref())
+            .collect();
+        assert_eq!(
+            descriptions,
+            vec![
+                Some("Who is\n  logged in."),
+                None,
+                Some("A string description.")
+            ]
+        );
+        assert_eq!(
+            query.fields[1].arguments[0].description.as_deref(),
+            Some("Stop there.")
+        );
+        assert_eq!(episode.description, None);
+        assert_eq!(episode.values[0].description.as_deref(), Some("The first."));
+    }
+}
@@ -1,4 +1,5 @@
 use constants::*;
+use failure;
 use graphql_parser::query::SelectionSet;
 use std::collections::BTreeMap;
 
@@ -8,12 +9,18 @@ pub struct SelectionField<'query> {
     pub alias: Option<&'query str>,
     pub name: &'query str,
     pub fields: Selection<'query>,
+    /// Whether the field is resolved on the client (`@client`).
+    pub is_client: bool,
+    /// Whether the values of the field are masked in `Debug` and pretty-printed output (`@sensitive`).
+    pub is_sensitive: bool,
 }
 
 /// A spread fragment in a selection (e.g. `...MyFragment`).
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SelectionFragmentSpread<'query> {
     pub fragment_name: &'query str,
+    /// Whether the fragment is delivered in a subsequent payload (`@defer`).
+    pub is_deferred: bool,
 }
 
 /// An inline fragment as part of a selection (e.g. `...on MyThing { name }`).
@@ -59,9 +66,9 @@ impl<'query> Selection<'query> {
         self.0
             .iter()
             .filter_map(|f| match f {
-                SelectionItem::FragmentSpread(SelectionFragmentSpread { fragment_name }) => {
-                    Some(fragment_name)
-                }
+                SelectionItem::FragmentSpread(SelectionFragmentSpread {
+                    fragment_name, ..
+                }) => Some(fragment_name),
                 _ => None,
             })
             .filter_map(|fragment_name| {
@@ -73,10 +80,10 @@ impl<'query> Selection<'query> {
     }
 
     // Implementation helper for `selected_variants_on_union`.
-    fn selected_variants_on_union_inner<'s>(
-        &'s self,
-        context: &'s crate::query::QueryContext,
-        selected_variants: &mut BTreeMap<&'s str, Selection<'s>>,
+    fn selected_variants_on_union_inner(
+        &self,
+        context: &crate::query::QueryContext<'query, '_>,
+        selected_variants: &mut BTreeMap<&'query str, Selection<'query>>,
         // the name of the type the selection applies to
         selection_on: &str,
     ) -> Result<(), failure::Error> {
@@ -93,7 +100,9 @@ impl<'query> Selection<'query> {
                             Selection(items)
                         });
                 }
-                SelectionItem::FragmentSpread(SelectionFragmentSpread { fragment_name }) => {
+                SelectionItem::FragmentSpread(SelectionFragmentSpread {
+                    fragment_name, ..
+                }) => {
                     let fragment = context
                         .fragments
                         .get(fragment_name)
@@ -130,12 +139,12 @@ impl<'query> Selection<'query> {
     /// Importantly, it will "flatten" the fragments and handle multiple selections of the same variant.
     ///
     /// The `context` argument is required so we can expand the fragments.
-    pub(crate) fn selected_variants_on_union<'s>(
-        &'s self,
-        context: &'s crate::query::QueryContext,
+    pub(crate) fn selected_variants_on_union(
+        &self,
+        context: &crate::query::QueryContext<'query, '_>,
         // the name of the type the selection applies to
         selection_on: &str,
-    ) -> Result<BTreeMap<&'s str, Selection<'s>>, failure::Error> {
+    ) -> Result<BTreeMap<&'query str, Selection<'query>>, failure::Error> {
         let mut selected_variants = BTreeMap::new();
 
         self.selected_variants_on_union_inner(context, &mut selected_variants, selection_on)?;
@@ -143,12 +152,124 @@ impl<'query> Selection<'query> {
         Ok(selected_variants)
     }
 
+    /// The selection with the inline fragments on `type_name` replaced by their fields, since they always apply to it.
+    pub(crate) fn flatten_inline_fragments_on(&self, type_name: &str) -> Selection<'query> {
+        let mut items = Vec::with_capacity(self.0.len());
+        for item in &self.0 {
+            match item {
+                SelectionItem::InlineFragment(inline) if inline.on == type_name => {
+                    items.extend(inline.fields.flatten_inline_fragments_on(type_name).0)
+                }
+                item => items.push(item.clone()),
+            }
+        }
+        Selection(items)
+    }
+
+    /// The selection without the fields selected by the fragments it spreads too: the fragments are flattened into the same struct, and serde hands each key of the response to a single field. Fields selected directly must select nothing more than in the fragment.
+    pub(crate) fn without_fragment_fields(
+        &self,
+        context: &crate::query::QueryContext,
+        type_name: &str,
+    ) -> Result<Selection<'query>, failure::Error> {
+        let mut fragment_fields = BTreeMap::new();
+        for item in &self.0 {
+            if let SelectionItem::FragmentSpread(spread) = item {
+                collect_fragment_fields(context, spread, &mut fragment_fields, &mut Vec::new());
+            }
+        }
+
+        let mut items = Vec::with_capacity(self.0.len());
+        for item in &self.0 {
+            if let SelectionItem::Field(field) = item {
+                let key = field.alias.unwrap_or(field.name);
+                if let Some((fragment_name, fragment_field)) = fragment_fields.get(key) {
+                    if fragment_field.name != field.name
+                        || !field
+                            .fields
+                            .0
+                            .iter()
+                            .all(|item| fragment_field.fields.0.contains(item))
+                    {
+                        Err(format_err!(
+                            "`{}` is selected on `{}` both directly and in the fragment `{}`, with different fields. Select it in one place.",
+                            key,
+                            type_name,
+                            fragment_name
+                        ))?;
+                    }
+                    continue;
+                }
+            }
+            items.push(item.clone());
+        }
+        Ok(Selection(items))
+    }
+
     #[cfg(test)]
     pub(crate) fn new_empty() -> Selection<'static> {
         Selection(Vec::new())
     }
 }
 
+/// The fields of the fragment and of the fragments it spreads, flattened into the struct spreading it, by response key, with the name of the fragment selecting them. Deferred fragments are missing from the initial payload, their fields are left to the struct.
+fn collect_fragment_fields<'a>(
+    context: &'a crate::query::QueryContext,
+    spread: &SelectionFragmentSpread,
+    fields: &mut BTreeMap<&'a str, (&'a str, &'a SelectionField<'a>)>,
+    spreading: &mut Vec<&'a str>,
+) {
+    let fragment = match context.fragments.get(spread.fragment_name) {
+        // Recursive spreads are reported when the fragments are generated.
+        Some(fragment) if !spread.is_deferred && !spreading.contains(&fragment.name) => fragment,
+        _ => return,
+    };
+
+    spreading.push(fragment.name);
+    for item in &fragment.selection.0 {
+        match item {
+            SelectionItem::Field(field) => {
+                fields
+                    .entry(field.alias.unwrap_or(field.name))
+                    .or_insert((fragment.name, field));
+            }
+            SelectionItem::FragmentSpread(spread) => {
+                collect_fragment_fields(context, spread, fields, spreading)
+            }
+            SelectionItem::InlineFragment(_) => (),
+        }
+    }
+    spreading.pop();
+}
+
+/// Add `item` to `items`, merging fields selected several times under the same response key, as the server does, and leaving out repeated fragment spreads, which would be flattened twice.
+fn push_merged<'query>(items: &mut Vec<SelectionItem<'query>>, item: SelectionItem<'query>) {
+    let existing = items
+        .iter_mut()
+        .find(|existing| match (&**existing, &item) {
+            (SelectionItem::Field(existing), SelectionItem::Field(field)) => {
+                existing.alias.unwrap_or(existing.name) == field.alias.unwrap_or(field.name)
+                    && existing.name == field.name
+            }
+            (SelectionItem::FragmentSpread(existing), SelectionItem::FragmentSpread(spread)) => {
+                existing == spread
+            }
+            _ => false,
+        });
+
+    match (existing, item) {
+        (Some(SelectionItem::Field(existing)), SelectionItem::Field(field)) => {
+            existing.is_client |= field.is_client;
+            existing.is_sensitive |= field.is_sensitive;
+            for item in field.fields.0 {
+                push_merged(&mut existing.fields.0, item);
+            }
+        }
+        (Some(_), _) => (),
+        (None, item) => items.push(item),
+    }
+}
+
 impl<'query> ::std::convert::From<&'query SelectionSet> for Selection<'query> {
     fn from(selection_set: &SelectionSet) -> Selection {
         use graphql_parser::query::Selection;
@@ -161,10 +282,16 @@ impl<'query> ::std::convert::From<&'query SelectionSet> for Selection<'query> {
                     alias: f.alias.as_ref().map(|s| s.as_str()),
                     name: &f.name,
                     fields: (&f.selection_set).into(),
+                    is_client: ::client_fields::is_client_field(&f.directives),
+                    is_sensitive: ::client_fields::is_sensitive_field(&f.directives),
                 }),
                 Selection::FragmentSpread(spread) => {
                     SelectionItem::FragmentSpread(SelectionFragmentSpread {
                         fragment_name: &spread.fragment_name,
+                        is_deferred: ::incremental::has_directive(
+                            &spread.directives,
+                            DEFER_DIRECTIVE,
+                        ),
                     })
                 }
                 Selection::InlineFragment(inline) => {
@@ -178,7 +305,7 @@ impl<'query> ::std::convert::From<&'query SelectionSet> for Selection<'query> {
                     })
                 }
             };
-            items.push(converted);
+            push_merged(&mut items, converted);
         }
 
         Selection(items)
@@ -206,6 +333,7 @@ mod tests {
             .0
             .push(SelectionItem::FragmentSpread(SelectionFragmentSpread {
                 fragment_name: "MyFragment",
+                is_deferred: false,
             }));
 
         let mut fragment_selection = Selection::new_empty();
@@ -215,6 +343,8 @@ mod tests {
                 alias: None,
                 name: "__typename",
                 fields: Selection::new_empty(),
+                is_client: false,
+                is_sensitive: false,
             }));
 
         let schema = ::schema::Schema::new();
@@ -225,6 +355,7 @@ mod tests {
                 name: "MyFragment",
                 on: "something".into(),
                 selection: fragment_selection,
+                directives: &[],
                 is_required: std::cell::Cell::new(false),
             },
         );
@@ -278,19 +409,26 @@ mod tests {
                         alias: None,
                         name: "isCat",
                         fields: Selection(Vec::new()),
+                        is_client: false,
+                        is_sensitive: false,
                     }),
                     SelectionItem::Field(SelectionField {
                         alias: None,
                         name: "isHorse",
                         fields: Selection(Vec::new()),
+                        is_client: false,
+                        is_sensitive: false,
                     }),
                     SelectionItem::FragmentSpread(SelectionFragmentSpread {
                         fragment_name: "Timestamps",
+                        is_deferred: false,
                     }),
                     SelectionItem::Field(SelectionField {
                         alias: None,
                         name: "barks",
                         fields: Selection(Vec::new()),
+                        is_client: false,
+                        is_sensitive: false,
                     }),
                     SelectionItem::InlineFragment(SelectionInlineFragment {
                         on: "Dog",
@@ -298,19 +436,27 @@ mod tests {
                             alias: None,
                             name: "rating",
                             fields: Selection(Vec::new()),
+                            is_client: false,
+                            is_sensitive: false,
                         })]),
                     }),
                     SelectionItem::Field(SelectionField {
                         alias: None,
                         name: "pawsCount",
                         fields: Selection(Vec::new()),
+                        is_client: false,
+                        is_sensitive: false,
                     }),
                     SelectionItem::Field(SelectionField {
                         alias: Some("aliased"),
                         name: "sillyName",
                         fields: Selection(Vec::new()),
+                        is_client: false,
+                        is_sensitive: false,
                     }),
                 ]),
+                is_client: false,
+                is_sensitive: false,
             })])
         );
     }
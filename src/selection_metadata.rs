@@ -0,0 +1,132 @@
+use constants::TYPENAME_FIELD;
+use heck::ShoutySnakeCase;
+use operations::Operation;
+use proc_macro2::{Ident, Span, TokenStream};
+use query::QueryContext;
+use selection::{Selection, SelectionItem};
+
+/// The `SELECTION` constant describing the fields the operation selects, with the struct of its items, so middleware can record which fields are requested without parsing `QUERY`. Names are prefixed with the operation name in modules with several operations.
+pub(crate) fn selection_constant(
+    context: &QueryContext,
+    operation: &Operation,
+    operation_prefix: &str,
+) -> TokenStream {
+    let struct_name = Ident::new(
+        &format!("{}SelectedField", operation_prefix),
+        Span::call_site(),
+    );
+    let const_name = if operation_prefix.is_empty() {
+        Ident::new("SELECTION", Span::call_site())
+    } else {
+        Ident::new(
+            &format!("{}_SELECTION", operation_prefix.to_shouty_snake_case()),
+            Span::call_site(),
+        )
+    };
+    let mut fields = Vec::new();
+    selected_fields(
+        context,
+        operation.root_name(context.schema),
+        &operation.selection,
+        &struct_name,
+        &mut Vec::new(),
+        &mut fields,
+    );
+
+    quote! {
+        /// A field selected by the operation.
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::marker::Copy, ::std::cmp::PartialEq, ::std::cmp::Eq)]
+        pub struct #struct_name {
+            /// The name of the field in the schema.
+            pub name: &'static str,
+            /// The alias of the field, its key in the response instead of its name.
+            pub alias: ::std::option::Option<&'static str>,
+            /// The type the field is selected on: the type of the parent field, or the type condition of a fragment.
+            pub parent_type: &'static str,
+            /// The type of the field in GraphQL syntax, like `[Episode!]`.
+            pub type_: &'static str,
+            /// The fields selected on the value of the field, including the fields of its fragments.
+            pub fields: &'static [#struct_name],
+        }
+
+        /// The fields selected by the operation, in document order, with the fields of the fragments in place of their spreads.
+        pub const #const_name: &'static [#struct_name] = &[#(#fields,)*];
+    }
+}
+
+fn selected_fields<'a>(
+    context: &'a QueryContext,
+    parent_type: &str,
+    selection: &'a Selection,
+    struct_name: &Ident,
+    spreading: &mut Vec<&'a str>,
+    fields: &mut Vec<TokenStream>,
+) {
+    for item in &selection.0 {
+        match item {
+            SelectionItem::Field(field) => {
+                let (type_, field_type) = if field.name == TYPENAME_FIELD {
+                    ("String!".to_string(), "String")
+                } else {
+                    match context.schema.field(parent_type, field.name) {
+                        Some(schema_field) => (
+                            schema_field.type_.to_graphql(),
+                            schema_field.type_.inner_name_str(),
+                        ),
+                        // `@client` fields of a client schema that is not loaded.
+                        None => continue,
+                    }
+                };
+                let mut nested = Vec::new();
+                selected_fields(
+                    context,
+                    field_type,
+                    &field.fields,
+                    struct_name,
+                    spreading,
+                    &mut nested,
+                );
+                let name = field.name;
+                let alias = match field.alias {
+                    Some(alias) => quote!(::std::option::Option::Some(#alias)),
+                    None => quote!(::std::option::Option::None),
+                };
+                fields.push(quote! {
+                    #struct_name {
+                        name: #name,
+                        alias: #alias,
+                        parent_type: #parent_type,
+                        type_: #type_,
+                        fields: &[#(#nested,)*],
+                    }
+                });
+            }
+            SelectionItem::InlineFragment(inline) => selected_fields(
+                context,
+                inline.on,
+                &inline.fields,
+                struct_name,
+                spreading,
+                fields,
+            ),
+            SelectionItem::FragmentSpread(spread) => {
+                // Recursive spreads are reported when the fragments are generated.
+                if spreading.contains(&spread.fragment_name) {
+                    continue;
+                }
+                if let Some(fragment) = context.fragments.get(spread.fragment_name) {
+                    spreading.push(spread.fragment_name);
+                    selected_fields(
+                        context,
+                        fragment.on,
+                        &fragment.selection,
+                        struct_name,
+                        spreading,
+                        fields,
+                    );
+                    spreading.pop();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use failure;
+use field_type::FieldType;
+use proc_macro2::TokenStream;
+use query::QueryContext;
+use std::collections::BTreeMap;
+use syn;
+
+/// The `serde(with)` modules of the fields, by type name or by `Type.field`.
+#[derive(Debug, Default)]
+pub(crate) struct SerdeAdapters(BTreeMap<String, String>);
+
+impl SerdeAdapters {
+    /// Parse a list like `Timestamp=::serde_with::As::<::serde_with::TimestampMilliSeconds>, Order.total=crate::serde_str`. Commas inside angle brackets belong to the module path.
+    pub(crate) fn parse(attribute_value: &str) -> Result<Self, failure::Error> {
+        let mut adapters = BTreeMap::new();
+        for item in split_top_level(attribute_value) {
+            let (target, module) = match item.splitn(2, '=').collect::<Vec<_>>().as_slice() {
+                [target, module] if !target.trim().is_empty() => (target.trim(), module.trim()),
+                _ => {
+                    return Err(format_err!(
+                        "Invalid serde adapter: `{}`. Expected `Scalar=path` or `Type.field=path`.",
+                        item.trim()
+                    ))
+                }
+            };
+            syn::parse_str::<syn::ExprPath>(module).map_err(|err| {
+                format_err!("Invalid serde adapter path for `{}`: {}", target, err)
+            })?;
+            adapters.insert(target.to_owned(), module.to_owned());
+        }
+        Ok(SerdeAdapters(adapters))
+    }
+
+    fn module(
+        &self,
+        parent_type: Option<&str>,
+        field_name: &str,
+        field_type: &FieldType,
+    ) -> Option<&str> {
+        parent_type
+            .and_then(|parent_type| self.0.get(&format!("{}.{}", parent_type, field_name)))
+            .or_else(|| self.0.get(field_type.inner_name_str()))
+            .map(String::as_str)
+    }
+}
+
+fn split_top_level(value: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (index, c) in value.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => (),
+        }
+    }
+    items.push(&value[start..]);
+    items.into_iter().filter(|s| !s.trim().is_empty()).collect()
+}
+
+/// The `#[serde(with)]` attribute of a field of `parent_type`, from the adapter of the field itself or else of its type. Variables have no parent type, only type adapters apply to them.
+pub(crate) fn field_attribute(
+    context: &QueryContext,
+    parent_type: Option<&str>,
+    field_name: &str,
+    field_type: &FieldType,
+) -> TokenStream {
+    match context
+        .serde_adapters
+        .module(parent_type, field_name, field_type)
+    {
+        Some(module) => quote!(#[serde(with = #module)]),
+        None => quote!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::Schema;
+
+    #[test]
+    fn field_adapters_take_precedence_over_scalar_adapters() {
+        let schema = Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        context.serde_adapters = SerdeAdapters::parse(
+            "Timestamp=::serde_with::As::<Option<TimestampMilliSeconds<i64, Flexible>>>, Order.createdAt = crate::serde_iso",
+        )
+        .unwrap();
+        let timestamp = FieldType::Optional(Box::new(FieldType::Named("Timestamp")));
+
+        assert_eq!(
+            field_attribute(&context, Some("Order"), "createdAt", &timestamp).to_string(),
+            "# [ serde ( with = \"crate::serde_iso\" ) ]"
+        );
+        assert_eq!(
+            field_attribute(&context, None, "since", &timestamp).to_string(),
+            "# [ serde ( with = \"::serde_with::As::<Option<TimestampMilliSeconds<i64, Flexible>>>\" ) ]"
+        );
+        assert!(field_attribute(&context, Some("Order"), "id", &FieldType::Named("ID")).is_empty());
+        assert!(SerdeAdapters::parse("Timestamp").is_err());
+        assert!(SerdeAdapters::parse("Timestamp=not a path").is_err());
+    }
+}
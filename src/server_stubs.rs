@@ -0,0 +1,286 @@
+use deprecation::DeprecationStrategy;
+use failure;
+use field_type::FieldType;
+use objects::GqlObjectField;
+use proc_macro2::{Ident, Span, TokenStream};
+use query::QueryContext;
+use schema::Schema;
+use shared::{description_attributes, rust_field_name};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Generate the resolver traits of a server for the schema at `schema_path`, in a module named `module_name`, so a mock or in-process server stays in sync with the clients generated from the same schema.
+///
+/// Each object type gets a `<Type>Resolver` trait with a method per field, taking the arguments of the field and returning its value. The object, interface and union types the fields return are associated types, bound by their own resolver traits: interfaces and unions get one too, telling the name of the object type of the value. The enums, input objects and scalars of the arguments and values are generated as for the clients, with the input objects deserializable.
+pub fn generate_server_stubs(
+    schema_path: &Path,
+    module_name: &str,
+) -> Result<TokenStream, failure::Error> {
+    let parsed_schema = ::load_schema(schema_path, None)?;
+    let schema = Schema::from(&*parsed_schema);
+    // Deprecated fields still have to be resolved.
+    let mut context = QueryContext::new(&schema, DeprecationStrategy::Allow);
+    context.ingest_additional_derives("Deserialize")?;
+
+    let mut traits: Vec<TokenStream> = Vec::new();
+    for object in schema
+        .objects
+        .values()
+        .filter(|object| !is_reserved(object.name))
+    {
+        traits.push(resolver_trait(
+            &context,
+            object.name,
+            object.description,
+            &object.fields,
+            None,
+        ));
+    }
+    for interface in schema.interfaces.values() {
+        let mut implementations: Vec<&str> = interface.implemented_by.iter().cloned().collect();
+        implementations.sort_unstable();
+        traits.push(resolver_trait(
+            &context,
+            interface.name,
+            interface.description,
+            &interface.fields,
+            Some(&implementations),
+        ));
+    }
+    for union in schema.unions.values() {
+        let variants: Vec<&str> = union.variants.iter().cloned().collect();
+        traits.push(resolver_trait(
+            &context,
+            union.name,
+            union.description,
+            &[],
+            Some(&variants),
+        ));
+    }
+
+    // The input objects require the types of their fields as they are generated.
+    let inputs: Vec<TokenStream> = schema
+        .inputs
+        .values()
+        .filter(|input| input.is_required.get())
+        .map(|input| input.to_rust(&context))
+        .collect::<Result<_, _>>()?;
+    let enums = schema
+        .enums
+        .values()
+        .filter(|enm| enm.is_required.get())
+        .map(|enm| enm.to_rust(&context));
+    let scalars = schema
+        .scalars
+        .values()
+        .filter(|scalar| scalar.is_required.get())
+        .map(|scalar| scalar.to_rust(&context));
+
+    let module_name = Ident::new(module_name, Span::call_site());
+    Ok(quote! {
+        pub mod #module_name {
+            #![allow(non_camel_case_types)]
+            #![allow(non_snake_case)]
+            #![allow(dead_code)]
+
+            #[allow(dead_code)]
+            type Boolean = bool;
+            #[allow(dead_code)]
+            type Float = f64;
+            #[allow(dead_code)]
+            type Int = i64;
+            #[allow(dead_code)]
+            type ID = ::std::string::String;
+
+            #(#scalars)*
+
+            #(#enums)*
+
+            #(#inputs)*
+
+            #(#traits)*
+        }
+    })
+}
+
+/// The introspection types, which servers resolve on their own.
+fn is_reserved(name: &str) -> bool {
+    name.starts_with("__")
+}
+
+/// The name of the resolver trait of a type.
+fn resolver_name(type_name: &str) -> Ident {
+    Ident::new(&format!("{}Resolver", type_name), Span::call_site())
+}
+
+/// The resolver trait of an object or interface type, or of a union type without fields. Interfaces and unions have the names of their possible types, and a `typename()` method to pick one.
+fn resolver_trait(
+    context: &QueryContext,
+    type_name: &str,
+    description: Option<&str>,
+    fields: &[GqlObjectField],
+    possible_types: Option<&[&str]>,
+) -> TokenStream {
+    let name = resolver_name(type_name);
+    let summary = format!("Resolves the values of the `{}` type.", type_name);
+    // The description of the type is a paragraph of its own.
+    let description = description.map(|description| {
+        let description = description_attributes(Some(description));
+        quote!(#[doc = ""] #description)
+    });
+    let fields: Vec<&GqlObjectField> = fields
+        .iter()
+        .filter(|field| !is_reserved(field.name))
+        .collect();
+
+    let mut composite_types = BTreeSet::new();
+    let methods: Vec<TokenStream> = fields
+        .iter()
+        .map(|field| {
+            let method_name = Ident::new(&rust_field_name(field.name), Span::call_site());
+            let arguments = context
+                .schema
+                .field_arguments(type_name, field.name)
+                .unwrap_or(&[])
+                .iter()
+                .map(|argument| {
+                    if let Some(input) = context.schema.inputs.get(argument.type_.inner_name_str())
+                    {
+                        input.require(context.schema);
+                    }
+                    let argument_name =
+                        Ident::new(&rust_field_name(argument.name), Span::call_site());
+                    let ty = argument.type_.to_rust(context, "");
+                    quote!(#argument_name: #ty)
+                });
+            let value_type = value_type(context, &field.type_, &mut composite_types);
+            let description = description_attributes(field.description);
+
+            quote! {
+                #description
+                fn #method_name(&self, #(#arguments),*) -> #value_type;
+            }
+        })
+        .collect();
+
+    let associated_types = composite_types.iter().map(|type_name| {
+        let ident = Ident::new(type_name, Span::call_site());
+        let resolver = resolver_name(type_name);
+        let doc = format!("The `{}` values of the fields.", type_name);
+        quote! {
+            #[doc = #doc]
+            type #ident: #resolver;
+        }
+    });
+
+    let typename = possible_types.map(|possible_types| {
+        let doc = format!(
+            "The name of the object type of the value: `{}`.",
+            possible_types.join("`, `")
+        );
+        quote! {
+            #[doc = #doc]
+            fn typename(&self) -> &'static str;
+        }
+    });
+
+    quote! {
+        #[doc = #summary]
+        #description
+        pub trait #name {
+            #(#associated_types)*
+
+            #typename
+
+            #(#methods)*
+        }
+    }
+}
+
+/// The Rust type of the value of a field: the associated type of the trait for object, interface and union types, recorded in `composite_types`, and the type of the client code for the others.
+fn value_type<'schema>(
+    context: &QueryContext,
+    ty: &FieldType<'schema>,
+    composite_types: &mut BTreeSet<&'schema str>,
+) -> TokenStream {
+    match ty {
+        FieldType::Optional(inner) => {
+            let inner = value_type(context, inner, composite_types);
+            quote!(::std::option::Option<#inner>)
+        }
+        FieldType::Vector(inner) => {
+            let inner = value_type(context, inner, composite_types);
+            quote!(::std::vec::Vec<#inner>)
+        }
+        FieldType::Named(name)
+            if context.schema.objects.contains_key(name)
+                || context.schema.interfaces.contains_key(name)
+                || context.schema.unions.contains_key(name) =>
+        {
+            composite_types.insert(name);
+            let ident = Ident::new(name, Span::call_site());
+            quote!(Self::#ident)
+        }
+        FieldType::Named(_) => ty.to_rust(context, ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn objects_get_resolver_traits() {
+        let dir = ::std::env::temp_dir().join("graphql_client_server_stubs");
+        ::std::fs::create_dir_all(&dir).unwrap();
+        let schema_path = dir.join("schema.graphql");
+        ::std::fs::write(
+            &schema_path,
+            r#"
+            enum Episode { NEWHOPE, EMPIRE }
+            input Page { first: Int!, after: String }
+            interface Character { name: String! }
+            "A human."
+            type Human implements Character {
+              name: String!
+              "Friends, most recent first."
+              friends(page: Page, episode: Episode): [Character!]
+              type: Episode
+            }
+            type Query { hero: Character, human(id: ID!): Human }
+            "#,
+        )
+        .unwrap();
+
+        let generated = generate_server_stubs(&schema_path, "server")
+            .unwrap()
+            .to_string();
+
+        assert!(generated.contains(
+            &quote! {
+                #[doc = "Resolves the values of the `Human` type."]
+                #[doc = ""]
+                #[doc = " A human."]
+                pub trait HumanResolver {
+                    #[doc = "The `Character` values of the fields."]
+                    type Character: CharacterResolver;
+
+                    fn name(&self,) -> ::std::string::String;
+                    #[doc = " Friends, most recent first."]
+                    fn friends(&self, page: ::std::option::Option<Page>, episode: ::std::option::Option<Episode>) -> ::std::option::Option<::std::vec::Vec<Self::Character> >;
+                    fn type_(&self,) -> ::std::option::Option<Episode>;
+                }
+            }
+            .to_string()
+        ));
+        assert!(generated.contains(
+            "pub trait CharacterResolver { # [ doc = \"The name of the object type of the value: `Human`.\" ] fn typename ( & self ) -> & \'static str ;"
+        ));
+        assert!(generated.contains(
+            "fn human ( & self , id : ID ) -> :: std :: option :: Option < Self :: Human > ;"
+        ));
+        // The input objects of the arguments can be deserialized.
+        assert!(generated.contains("# [ derive ( :: serde_derive :: Serialize , :: serde_derive :: Deserialize ) ] pub struct Page"));
+        assert!(generated.contains("pub enum Episode"));
+    }
+}
@@ -1,11 +1,14 @@
-use deprecation::{DeprecationStatus, DeprecationStrategy};
+use constants::TYPENAME_FIELD;
+use deprecation::{deprecation_attribute, DeprecationStatus, DeprecationStrategy};
 use failure;
-use heck::{CamelCase, SnakeCase};
+use field_type::FieldType;
+use heck::SnakeCase;
 use itertools::Itertools;
-use objects::GqlObjectField;
 use proc_macro2::{Ident, Span, TokenStream};
-use query::QueryContext;
+use query::{Nested, QueryContext};
+use schema::Schema;
 use selection::*;
+use std::collections::{BTreeMap, BTreeSet};
 
 pub(crate) fn render_object_field(
     field_name: &str,
@@ -14,28 +17,194 @@ pub(crate) fn render_object_field(
     status: &DeprecationStatus,
     strategy: &DeprecationStrategy,
 ) -> TokenStream {
-    #[allow(unused_assignments)]
-    let mut deprecation = quote!();
-    match (status, strategy) {
-        // If the field is deprecated and we are denying usage, don't generate the
-        // field in rust at all and short-circuit.
-        (DeprecationStatus::Deprecated(_), DeprecationStrategy::Deny) => return quote!(),
-        // Everything is allowed so there is nothing to do.
-        (_, DeprecationStrategy::Allow) => deprecation = quote!(),
-        // Current so there is nothing to do.
-        (DeprecationStatus::Current, _) => deprecation = quote!(),
-        // A reason was provided, translate it to a note.
-        (DeprecationStatus::Deprecated(Some(reason)), DeprecationStrategy::Warn) => {
-            deprecation = quote!(#[deprecated(note = #reason)])
+    let deprecation = match deprecation_attribute(status, strategy) {
+        Some(deprecation) => deprecation,
+        None => return quote!(),
+    };
+
+    let description = description_attributes(description);
+
+    let rust_name = rust_field_name(field_name);
+    let rename = ::shared::field_rename_annotation(&field_name, &rust_name);
+    let name_ident = Ident::new(&rust_name, Span::call_site());
+
+    quote!(#description #deprecation #rename pub #name_ident: #field_type)
+}
+
+/// The `#[doc]` attributes of a description from the schema, one per line like `///` comments, so its line breaks are kept.
+///
+/// Its code blocks mostly hold GraphQL, which rustdoc would run as doctests: fenced blocks without a language are marked as `text`, those in Rust as `ignore`, and indented ones are fenced as `text`. The rest of the text is escaped with [escape_markdown].
+pub(crate) fn description_attributes(description: Option<&str>) -> TokenStream {
+    let lines = description.map(doc_lines).unwrap_or_default();
+    let lines = lines.iter().map(|line| {
+        if line.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", line)
         }
-        // No reason provided, just mark as deprecated.
-        (DeprecationStatus::Deprecated(None), DeprecationStrategy::Warn) => {
-            deprecation = quote!(#[deprecated])
+    });
+    quote!(#(#[doc = #lines])*)
+}
+
+fn doc_lines(description: &str) -> Vec<String> {
+    let lines: Vec<&str> = description.lines().collect();
+    let is_blank = |line: &str| line.trim().is_empty();
+    let indentation = |line: &str| line.len() - line.trim_start().len();
+    let mut doc_lines = Vec::with_capacity(lines.len());
+    // The fence opening the fenced code block the line is in.
+    let mut fence: Option<String> = None;
+    let mut idx = 0;
+
+    while idx < lines.len() {
+        let line = lines[idx];
+        let trimmed = line.trim_start();
+        idx += 1;
+
+        if let Some(opening) = fence.take() {
+            let closes = trimmed.starts_with(opening.as_str())
+                && trimmed.trim_end().chars().all(|c| opening.starts_with(c));
+            if !closes {
+                fence = Some(opening);
+            }
+            doc_lines.push(line.to_owned());
+            continue;
         }
-    };
 
-    let description = description.map(|s| quote!(#[doc = #s]));
+        let fence_char = match trimmed.chars().next() {
+            Some(c @ '`') | Some(c @ '~') if indentation(line) < 4 => c,
+            _ => ' ',
+        };
+        let fence_length = trimmed.chars().take_while(|c| *c == fence_char).count();
+        if fence_char != ' ' && fence_length >= 3 {
+            let opening = &trimmed[..fence_length];
+            let info = trimmed[fence_length..].trim();
+            let info = if info.is_empty() {
+                "text".to_owned()
+            } else if is_rust_info(info) {
+                format!("{},ignore", info)
+            } else {
+                info.to_owned()
+            };
+            doc_lines.push(format!("{}{}{}", &line[..indentation(line)], opening, info));
+            fence = Some(opening.to_owned());
+            continue;
+        }
+
+        let follows_blank_line = doc_lines
+            .last()
+            .map(|line: &String| is_blank(line))
+            .unwrap_or(true);
+        if !is_blank(line) && indentation(line) >= 4 && follows_blank_line {
+            let start = idx - 1;
+            while idx < lines.len() && (is_blank(lines[idx]) || indentation(lines[idx]) >= 4) {
+                idx += 1;
+            }
+            let end = (start..idx)
+                .rev()
+                .find(|idx| !is_blank(lines[*idx]))
+                .map(|end| end + 1)
+                .unwrap_or(idx);
+            doc_lines.push("```text".to_owned());
+            doc_lines.extend(
+                lines[start..end]
+                    .iter()
+                    .map(|line| line.get(4..).unwrap_or("").to_owned()),
+            );
+            doc_lines.push("```".to_owned());
+            doc_lines.extend(lines[end..idx].iter().map(|line| (*line).to_owned()));
+            continue;
+        }
+
+        doc_lines.push(escape_markdown(line));
+    }
+
+    doc_lines
+}
+
+/// Escape what rustdoc would misread in a line of text outside code: `*/`, which ends the block comments the attributes are printed as, brackets around text that is not a link, which it resolves as intra-doc links, and a leading `#`, which starts a heading. Code spans are left as they are.
+fn escape_markdown(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut escaped = String::with_capacity(line.len());
+    let indentation = chars.iter().take_while(|c| c.is_whitespace()).count();
+    // The index of the `]` closing an escaped `[`.
+    let mut closing_bracket = None;
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+        match c {
+            '\\' => {
+                escaped.extend(&chars[idx..(idx + 2).min(chars.len())]);
+                idx += 2;
+                continue;
+            }
+            '`' => {
+                let run = chars[idx..].iter().take_while(|c| **c == '`').count();
+                let span_end = (idx + run..chars.len()).find(|end| {
+                    chars[*end..].iter().take_while(|c| **c == '`').count() == run
+                        && chars[*end - 1] != '`'
+                });
+                let end = span_end.map(|end| end + run).unwrap_or(idx + run);
+                escaped.extend(&chars[idx..end]);
+                idx = end;
+                continue;
+            }
+            '#' if idx == indentation => escaped.push('\\'),
+            '*' if chars.get(idx + 1) == Some(&'/') => {
+                escaped.push_str("*\\/");
+                idx += 2;
+                continue;
+            }
+            // The label of a reference link, after its text.
+            '[' if idx > 0 && chars[idx - 1] == ']' => (),
+            '[' => {
+                let end = chars[idx..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .map(|end| idx + end);
+                if let Some(end) = end {
+                    let is_link = match chars.get(end + 1) {
+                        Some('(') | Some('[') => true,
+                        Some(':') => idx == indentation,
+                        _ => false,
+                    };
+                    if !is_link {
+                        escaped.push('\\');
+                        closing_bracket = Some(end);
+                    }
+                }
+            }
+            ']' if closing_bracket == Some(idx) => escaped.push('\\'),
+            _ => (),
+        }
+        escaped.push(c);
+        idx += 1;
+    }
+
+    escaped
+}
+
+/// Whether rustdoc takes a code block with this info string for Rust: it does when it is empty or only made of the attributes of doctests.
+fn is_rust_info(info: &str) -> bool {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|attribute| !attribute.is_empty())
+        .all(|attribute| {
+            attribute.starts_with("edition")
+                || [
+                    "rust",
+                    "should_panic",
+                    "no_run",
+                    "compile_fail",
+                    "test_harness",
+                    "allow_fail",
+                    "standalone_crate",
+                ]
+                .contains(&attribute)
+        })
+}
 
+/// The name of the struct field for a response key: snake case, with an underscore after keywords.
+pub(crate) fn rust_field_name(response_key: &str) -> String {
     // List of keywords based on https://doc.rust-lang.org/grammar.html#keywords
     let reserved = &[
         "abstract", "alignof", "as", "become", "box", "break", "const", "continue", "crate", "do",
@@ -45,59 +214,173 @@ pub(crate) fn render_object_field(
         "true", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
     ];
 
-    if reserved.contains(&field_name) {
-        let name_ident = Ident::new(&format!("{}_", field_name), Span::call_site());
-        return quote! {
-            #description
-            #deprecation
-            #[serde(rename = #field_name)]
-            pub #name_ident: #field_type
-        };
+    let snake_case_name = response_key.to_snake_case();
+    if reserved.contains(&snake_case_name.as_str()) {
+        format!("{}_", snake_case_name)
+    } else {
+        snake_case_name
     }
+}
+
+/// Fields of scalar and enum types cannot have a selection set, fields of object, interface and union types must have one.
+pub(crate) fn check_selection_set(
+    schema: &Schema,
+    parent_type: &str,
+    field_name: &str,
+    field_type: &str,
+    has_selection_set: bool,
+) -> Result<(), String> {
+    let leaf_kind = if schema.contains_scalar(field_type) {
+        "scalar"
+    } else if schema.enums.contains_key(field_type) {
+        "enum"
+    } else if !has_selection_set
+        && (schema.objects.contains_key(field_type)
+            || schema.interfaces.contains_key(field_type)
+            || schema.unions.contains_key(field_type))
+    {
+        return Err(format!(
+            "Field `{}` on `{}` is of type `{}`, it needs a selection set listing the fields to fetch, like `{} {{ ... }}`.",
+            field_name, parent_type, field_type, field_name
+        ));
+    } else {
+        return Ok(());
+    };
 
-    let snake_case_name = field_name.to_snake_case();
-    let rename = ::shared::field_rename_annotation(&field_name, &snake_case_name);
-    let name_ident = Ident::new(&snake_case_name, Span::call_site());
+    if has_selection_set {
+        return Err(format!(
+            "Field `{}` on `{}` is of the {} type `{}`, it cannot have a selection set.",
+            field_name, parent_type, leaf_kind, field_type
+        ));
+    }
 
-    quote!(#description #deprecation #rename pub #name_ident: #field_type)
+    Ok(())
+}
+
+/// Make sure no two response keys of a selection end up as the same struct field, like `fooBar` and `foo_bar`, or `type` and `type_`. `reserved` are the names of the fields the struct has on top of the selected ones.
+fn check_rust_field_names(
+    type_name: &str,
+    selection: &Selection,
+    reserved: &[&str],
+) -> Result<(), failure::Error> {
+    let mut keys_by_rust_name: BTreeMap<String, String> = reserved
+        .iter()
+        .map(|name| (name.to_string(), format!("the generated `{}` field", name)))
+        .collect();
+
+    for item in &selection.0 {
+        let (key, rust_name) = match item {
+            SelectionItem::Field(field) => {
+                let key = field.alias.unwrap_or(field.name);
+                (format!("`{}`", key), rust_field_name(key))
+            }
+            SelectionItem::FragmentSpread(fragment) => (
+                format!("`...{}`", fragment.fragment_name),
+                fragment.fragment_name.to_snake_case(),
+            ),
+            SelectionItem::InlineFragment(_) => continue,
+        };
+
+        match keys_by_rust_name.get(&rust_name) {
+            Some(existing) if *existing != key => {
+                return Err(format_err!(
+                    "{} and {} in the selection on `{}` would both be named `{}` in the generated struct. Use an alias to rename one of them.",
+                    existing,
+                    key,
+                    type_name,
+                    rust_name
+                ))
+            }
+            Some(_) => (),
+            None => {
+                keys_by_rust_name.insert(rust_name, key);
+            }
+        }
+    }
+
+    Ok(())
 }
 
+/// The generated code for each of the selected fields' types, see [nested_selections].
 pub(crate) fn field_impls_for_selection(
-    fields: &[GqlObjectField],
+    type_name: &str,
     context: &QueryContext,
     selection: &Selection,
     prefix: &str,
 ) -> Result<Vec<TokenStream>, failure::Error> {
+    nested_selections(type_name, context, selection, prefix)?
+        .into_iter()
+        .map(|nested| context.expand_nested(prefix, (quote!(), vec![nested])))
+        .collect()
+}
+
+/// The selections of the selected fields, to expand into the fields' types.
+pub(crate) fn nested_selections<'query>(
+    type_name: &str,
+    context: &QueryContext,
+    selection: &Selection<'query>,
+    prefix: &str,
+) -> Result<Vec<Nested<'query>>, failure::Error> {
+    let struct_name = |selected: &SelectionField| {
+        context.field_struct_name(prefix, selected.alias.unwrap_or(selected.name))
+    };
+
     selection
         .0
         .iter()
-        .map(|selected| {
-            if let SelectionItem::Field(selected) = selected {
-                let name = &selected.name;
-                let alias = selected.alias.as_ref().unwrap_or(name);
+        .enumerate()
+        .filter_map(|(index, selected)| match selected {
+            SelectionItem::Field(selected) => Some((index, selected)),
+            _ => None,
+        })
+        .map(|(index, selected)| {
+            let name = &selected.name;
 
-                let ty = fields
-                    .iter()
-                    .find(|f| &f.name == name)
-                    .ok_or_else(|| format_err!("could not find field `{}`", name))?
-                    .type_
-                    .inner_name_str();
-                let prefix = format!("{}{}", prefix.to_camel_case(), alias.to_camel_case());
-                context.maybe_expand_field(&ty, &selected.fields, &prefix)
-            } else {
-                Ok(quote!())
+            // Aliases of the same field with the same selection share the type generated for the first one.
+            let identical = selection.0[..index]
+                .iter()
+                .find_map(|previous| match previous {
+                    SelectionItem::Field(previous)
+                        if !selected.fields.0.is_empty()
+                            && previous.name == selected.name
+                            && previous.is_client == selected.is_client
+                            && previous.fields == selected.fields =>
+                    {
+                        Some(previous)
+                    }
+                    _ => None,
+                });
+            if let Some(identical) = identical {
+                let alias = Ident::new(&struct_name(selected), Span::call_site());
+                let original = Ident::new(&struct_name(identical), Span::call_site());
+                return Ok(Nested::Expanded(quote!(pub type #alias = #original;)));
             }
+
+            let ty = context
+                .schema
+                .field(type_name, name)
+                .ok_or_else(|| format_err!("could not find field `{}`", name))?
+                .type_
+                .inner_name_str();
+            Ok(Nested::Selection {
+                on: ty.to_owned(),
+                selection: selected.fields.clone(),
+                prefix: struct_name(selected),
+                key: selected.alias.unwrap_or(selected.name).to_owned(),
+            })
         })
         .collect()
 }
 
 pub(crate) fn response_fields_for_selection(
     type_name: &str,
-    schema_fields: &[GqlObjectField],
     context: &QueryContext,
     selection: &Selection,
     prefix: &str,
+    reserved_field_names: &[&str],
 ) -> Result<Vec<TokenStream>, failure::Error> {
+    check_rust_field_names(type_name, selection, reserved_field_names)?;
+
     selection
         .0
         .iter()
@@ -106,41 +389,110 @@ pub(crate) fn response_fields_for_selection(
                 let name = &f.name;
                 let alias = f.alias.as_ref().unwrap_or(name);
 
-                let schema_field = &schema_fields
-                    .iter()
-                    .find(|field| &field.name == name)
-                    .ok_or_else(|| {
-                        format_err!(
-                            "Could not find field `{}` on `{}`. Available fields: `{}`.",
-                            *name,
-                            type_name,
-                            schema_fields
-                                .iter()
-                                .map(|ref field| &field.name)
-                                .format("`, `"),
+                let schema_field = context.schema.field(type_name, name).ok_or_else(|| {
+                    format_err!(
+                        "Could not find field `{}` on `{}`. Available fields: `{}`.",
+                        *name,
+                        type_name,
+                        context
+                            .schema
+                            .fields_of(type_name)
+                            .unwrap_or(&[])
+                            .iter()
+                            .map(|field| &field.name)
+                            .format("`, `"),
+                    )
+                })?;
+                check_selection_set(
+                    context.schema,
+                    type_name,
+                    name,
+                    schema_field.type_.inner_name_str(),
+                    !f.fields.0.is_empty(),
+                )
+                .map_err(|message| format_err!("{}", message))?;
+                if let DeprecationStatus::Deprecated(ref reason) = schema_field.deprecation {
+                    context.record_deprecated_field(type_name, name, reason.as_ref());
+                }
+
+                let struct_name = context.field_struct_name(prefix, alias);
+                let ty = match context.nested_indirection {
+                    Some(ref indirection)
+                        if !f.fields.0.is_empty()
+                            && selection_size(context, &f.fields, &mut Vec::new())
+                                >= indirection.min_fields =>
+                    {
+                        schema_field.type_.to_rust_behind(
+                            context,
+                            &struct_name,
+                            indirection.pointer,
                         )
-                    })?;
-                let ty = schema_field.type_.to_rust(
-                    context,
-                    &format!("{}{}", prefix.to_camel_case(), alias.to_camel_case()),
-                );
+                    }
+                    _ => schema_field.type_.to_response_rust(context, &struct_name),
+                };
+                // The server never returns `@client` fields, they are filled in locally.
+                let (ty, field_type) = if f.is_client && !schema_field.type_.is_optional() {
+                    (
+                        quote!(::std::option::Option<#ty>),
+                        FieldType::Optional(Box::new(schema_field.type_.clone())),
+                    )
+                } else {
+                    (ty, schema_field.type_.clone())
+                };
+                let strategy = ::arbitrary::field_attribute(context, &field_type, false);
+                let adapter =
+                    ::serde_adapters::field_attribute(context, Some(type_name), name, &field_type);
+                // Adapters take care of the whole field, leniency included.
+                let (adapter, has_default) = if adapter.is_empty() {
+                    let lenient = ::lenient::field_attribute(context, &field_type);
+                    // Lenient options have their default already.
+                    let has_default = !lenient.is_empty() && field_type.is_optional();
+                    (lenient, has_default)
+                } else {
+                    (adapter, false)
+                };
+                let default =
+                    if context.nullable_defaults && field_type.is_optional() && !has_default {
+                        quote!(#[serde(default)])
+                    } else {
+                        quote!()
+                    };
 
-                Ok(render_object_field(
+                if f.is_sensitive
+                    || context
+                        .sensitive_fields
+                        .contains(type_name, name, &schema_field.type_)
+                {
+                    context.record_redacted_field(prefix, &rust_field_name(alias));
+                }
+
+                let field = render_object_field(
                     alias,
                     &ty,
                     schema_field.description.as_ref().cloned(),
                     &schema_field.deprecation,
-                    &context.deprecation_strategy,
-                ))
+                    context.deprecation_strategy_for(type_name, name),
+                );
+                Ok(if field.is_empty() {
+                    field
+                } else {
+                    quote!(#strategy #adapter #default #field)
+                })
             }
             SelectionItem::FragmentSpread(fragment) => {
                 let field_name =
                     Ident::new(&fragment.fragment_name.to_snake_case(), Span::call_site());
                 context.require_fragment(&fragment.fragment_name);
                 let type_name = Ident::new(&fragment.fragment_name, Span::call_site());
+                // Deferred fragments are missing from the initial payload.
+                let ty = if fragment.is_deferred {
+                    quote!(::std::option::Option<#type_name>)
+                } else {
+                    quote!(#type_name)
+                };
                 Ok(quote! {
                     #[serde(flatten)]
-                    pub #field_name: #type_name
+                    pub #field_name: #ty
                 })
             }
             SelectionItem::InlineFragment(_) => Err(format_err!(
@@ -156,6 +508,150 @@ pub(crate) fn response_fields_for_selection(
         .collect()
 }
 
+/// The number of fields of a selection, counting those of its nested selections and of the fragments it spreads.
+fn selection_size<'a>(
+    context: &'a QueryContext,
+    selection: &'a Selection,
+    spreading: &mut Vec<&'a str>,
+) -> usize {
+    selection
+        .0
+        .iter()
+        .map(|item| match item {
+            SelectionItem::Field(field) => 1 + selection_size(context, &field.fields, spreading),
+            SelectionItem::InlineFragment(inline) => {
+                selection_size(context, &inline.fields, spreading)
+            }
+            SelectionItem::FragmentSpread(spread) => {
+                match context.fragments.get(spread.fragment_name) {
+                    // Recursive spreads are reported when the fragments are generated.
+                    Some(fragment) if !spreading.contains(&spread.fragment_name) => {
+                        spreading.push(spread.fragment_name);
+                        let size = selection_size(context, &fragment.selection, spreading);
+                        spreading.pop();
+                        size
+                    }
+                    _ => 0,
+                }
+            }
+        })
+        .sum()
+}
+
+/// A `cache_key()` method on a response struct selecting the `id` of its object, returning the `__typename` and the `id` identifying the object in a normalized cache. `typename` is the expression for the `__typename`, as interfaces deserialize it into their `on` enum.
+pub(crate) fn cache_key_impl(
+    type_name: &str,
+    context: &QueryContext,
+    selection: &Selection,
+    struct_name: &Ident,
+    typename: &TokenStream,
+) -> TokenStream {
+    let id = match selection.0.iter().find_map(|item| match item {
+        SelectionItem::Field(field) if field.name == "id" && !field.is_client => Some(field),
+        _ => None,
+    }) {
+        Some(id) => id,
+        None => return quote!(),
+    };
+    let schema_field = match context.schema.field(type_name, id.name) {
+        Some(schema_field) => schema_field,
+        None => return quote!(),
+    };
+    let strategy = context.deprecation_strategy_for(type_name, id.name);
+    if deprecation_attribute(&schema_field.deprecation, strategy).is_none() {
+        return quote!();
+    }
+
+    let id_name = Ident::new(
+        &rust_field_name(id.alias.unwrap_or(id.name)),
+        Span::call_site(),
+    );
+    let (id_type, key) = match schema_field.type_ {
+        FieldType::Named(_) => (
+            schema_field.type_.to_rust(context, ""),
+            quote!(::std::option::Option::Some((#typename, &self.#id_name))),
+        ),
+        FieldType::Optional(ref inner) => match **inner {
+            FieldType::Named(_) => (
+                inner.to_rust(context, ""),
+                quote!(self.#id_name.as_ref().map(|id| (#typename, id))),
+            ),
+            _ => return quote!(),
+        },
+        _ => return quote!(),
+    };
+
+    quote! {
+        impl #struct_name {
+            /// The `__typename` and the `id` identifying the object in a normalized cache, or `None` when the `id` is null.
+            pub fn cache_key(&self) -> ::std::option::Option<(&str, &#id_type)> {
+                #key
+            }
+        }
+    }
+}
+
+/// The expression for the selected `__typename` of a response struct, if it is selected.
+pub(crate) fn selected_typename(
+    context: &QueryContext,
+    selection: &Selection,
+) -> Option<TokenStream> {
+    selection.0.iter().find_map(|item| match item {
+        SelectionItem::Field(field) if field.name == TYPENAME_FIELD => {
+            let name = Ident::new(
+                &rust_field_name(field.alias.unwrap_or(field.name)),
+                Span::call_site(),
+            );
+            Some(match context.response_string_type() {
+                Some(_) => quote!(::std::convert::AsRef::<str>::as_ref(&self.#name)),
+                None => quote!(self.#name.as_str()),
+            })
+        }
+        _ => None,
+    })
+}
+
+/// `AsRef` and `From` conversions from a response struct to the fragments spread in its selection, and the `Has<Fragment>` trait implementations, so functions written against a fragment accept any struct containing it. Deferred fragments are optional, they get no conversions.
+pub(crate) fn fragment_conversions(struct_name: &Ident, selection: &Selection) -> TokenStream {
+    let fragment_names: BTreeSet<&str> = selection
+        .0
+        .iter()
+        .filter_map(|item| match item {
+            SelectionItem::FragmentSpread(fragment) if !fragment.is_deferred => {
+                Some(fragment.fragment_name)
+            }
+            _ => None,
+        })
+        .collect();
+
+    let conversions = fragment_names.iter().map(|fragment_name| {
+        let field_name = Ident::new(&fragment_name.to_snake_case(), Span::call_site());
+        let type_name = Ident::new(fragment_name, Span::call_site());
+        let trait_name = ::fragments::carrier_trait_name(fragment_name);
+        quote! {
+            impl #trait_name for #struct_name {
+                fn #field_name(&self) -> &#type_name {
+                    &self.#field_name
+                }
+            }
+
+            impl ::std::convert::AsRef<#type_name> for #struct_name {
+                fn as_ref(&self) -> &#type_name {
+                    &self.#field_name
+                }
+            }
+
+            impl<'a> ::std::convert::From<&'a #struct_name> for &'a #type_name {
+                fn from(value: &'a #struct_name) -> Self {
+                    &value.#field_name
+                }
+            }
+        }
+    });
+
+    quote!(#(#conversions)*)
+}
+
 /// Given the GraphQL schema name for an object/interface/input object field and
 /// the equivalent rust name, produces a serde annotation to map them during
 /// (de)serialization if it is necessary, otherwise an empty TokenStream.
@@ -166,3 +662,156 @@ pub(crate) fn field_rename_annotation(graphql_name: &str, rust_name: &str) -> To
         quote!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn descriptions_keep_their_lines_without_doctests() {
+        let description = "Lines\n\n```\n{ viewer }\n```\n\n```rust\nlet x = 1;\n```\n\n~~~graphql\n{ viewer }\n~~~\n\n    indented\n\n      code\n\nEnd";
+
+        assert_eq!(
+            description_attributes(Some(description)).to_string(),
+            quote! {
+                #[doc = " Lines"]
+                #[doc = ""]
+                #[doc = " ```text"]
+                #[doc = " { viewer }"]
+                #[doc = " ```"]
+                #[doc = ""]
+                #[doc = " ```rust,ignore"]
+                #[doc = " let x = 1;"]
+                #[doc = " ```"]
+                #[doc = ""]
+                #[doc = " ~~~graphql"]
+                #[doc = " { viewer }"]
+                #[doc = " ~~~"]
+                #[doc = ""]
+                #[doc = " ```text"]
+                #[doc = " indented"]
+                #[doc = ""]
+                #[doc = "   code"]
+                #[doc = " ```"]
+                #[doc = ""]
+                #[doc = " End"]
+            }
+            .to_string()
+        );
+        assert!(description_attributes(None).is_empty());
+    }
+
+    #[test]
+    fn descriptions_are_escaped_for_rustdoc() {
+        let description = "# of items, in [0, 100] or `[a]` */\nSee [the spec](https://spec.graphql.org) and [`Node`][node].\n[node]: https://example.com\n```\n# [kept] */\n```";
+
+        assert_eq!(
+            description_attributes(Some(description)).to_string(),
+            quote! {
+                #[doc = " \\# of items, in \\[0, 100\\] or `[a]` *\\/"]
+                #[doc = " See [the spec](https://spec.graphql.org) and [`Node`][node]."]
+                #[doc = " [node]: https://example.com"]
+                #[doc = " ```text"]
+                #[doc = " # [kept] */"]
+                #[doc = " ```"]
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn fragment_conversions_skip_deferred_fragments() {
+        let selection = Selection(vec![
+            SelectionItem::FragmentSpread(SelectionFragmentSpread {
+                fragment_name: "UserFields",
+                is_deferred: false,
+            }),
+            SelectionItem::FragmentSpread(SelectionFragmentSpread {
+                fragment_name: "SlowFields",
+                is_deferred: true,
+            }),
+        ]);
+
+        assert_eq!(
+            fragment_conversions(&Ident::new("Viewer", Span::call_site()), &selection).to_string(),
+            "impl HasUserFields for Viewer { fn user_fields ( & self ) -> & UserFields { & self . user_fields } } \
+             impl :: std :: convert :: AsRef < UserFields > for Viewer { fn as_ref ( & self ) -> & UserFields { & self . user_fields } } \
+             impl < 'a > :: std :: convert :: From < & 'a Viewer > for & 'a UserFields { fn from ( value : & 'a Viewer ) -> Self { & value . user_fields } }"
+        );
+    }
+
+    #[test]
+    fn identical_aliased_selections_share_one_type() {
+        let schema =
+            ::graphql_parser::parse_schema(include_str!("tests/star_wars_schema.graphql")).unwrap();
+        let schema = Schema::from(&schema);
+        let context = QueryContext::new_empty(&schema);
+        let human = |alias, field| {
+            SelectionItem::Field(SelectionField {
+                alias: Some(alias),
+                name: "human",
+                fields: Selection(vec![SelectionItem::Field(SelectionField {
+                    alias: None,
+                    name: field,
+                    fields: Selection(vec![]),
+                    is_client: false,
+                    is_sensitive: false,
+                })]),
+                is_client: false,
+                is_sensitive: false,
+            })
+        };
+        let selection = Selection(vec![
+            human("luke", "name"),
+            human("leia", "name"),
+            human("han", "id"),
+        ]);
+
+        let impls = field_impls_for_selection("Query", &context, &selection, "Dashboard").unwrap();
+
+        assert!(impls[0].to_string().contains("pub struct DashboardLuke"));
+        assert_eq!(
+            impls[1].to_string(),
+            "pub type DashboardLeia = DashboardLuke ;"
+        );
+        assert!(impls[2].to_string().contains("pub struct DashboardHan"));
+    }
+
+    #[test]
+    fn colliding_rust_field_names_are_reported() {
+        let field = |alias, name| {
+            SelectionItem::Field(SelectionField {
+                alias,
+                name,
+                fields: Selection(vec![]),
+                is_client: false,
+                is_sensitive: false,
+            })
+        };
+
+        let selection = Selection(vec![
+            field(None, "name"),
+            field(None, "name"),
+            field(Some("type"), "__typename"),
+        ]);
+        assert!(check_rust_field_names("User", &selection, &[]).is_ok());
+
+        let selection = Selection(vec![field(None, "fooBar"), field(Some("foo_bar"), "other")]);
+        assert_eq!(
+            check_rust_field_names("User", &selection, &[]).unwrap_err().to_string(),
+            "`fooBar` and `foo_bar` in the selection on `User` would both be named `foo_bar` in the generated struct. Use an alias to rename one of them."
+        );
+
+        let selection = Selection(vec![
+            field(Some("type"), "__typename"),
+            field(None, "type_"),
+        ]);
+        assert!(check_rust_field_names("User", &selection, &[])
+            .unwrap_err()
+            .to_string()
+            .starts_with("`type` and `type_` in the selection on `User`"));
+
+        let selection = Selection(vec![field(None, "on")]);
+        assert!(check_rust_field_names("Node", &selection, &["on"]).is_err());
+    }
+}
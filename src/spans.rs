@@ -0,0 +1,19 @@
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+
+/// Give every token of the generated code the span of the derive attribute, so compiler errors and IDE navigation on generated items land on the attribute.
+pub(crate) fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => {
+                let mut respanned = Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                TokenTree::Group(respanned)
+            }
+            mut token => {
+                token.set_span(span);
+                token
+            }
+        })
+        .collect()
+}
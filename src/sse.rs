@@ -0,0 +1,73 @@
+use proc_macro2::{Ident, Span, TokenStream};
+
+/// The event envelope of the GraphQL over Server-Sent Events protocol, for a subscription: `next` events carry a response, `complete` ends the subscription.
+pub(crate) fn sse_event_type(operation_name: &str, multiple_operations: bool) -> TokenStream {
+    let (response_data, event) = if multiple_operations {
+        (
+            format!("{}ResponseData", operation_name),
+            format!("{}SseEvent", operation_name),
+        )
+    } else {
+        ("ResponseData".to_owned(), "SseEvent".to_owned())
+    };
+    let response_data = Ident::new(&response_data, Span::call_site());
+    let event = Ident::new(&event, Span::call_site());
+
+    quote! {
+        /// An event of the GraphQL over Server-Sent Events protocol for this subscription.
+        pub enum #event {
+            /// A `next` event, carrying an execution result.
+            Next(::graphql_client::Response<#response_data>),
+            /// A `complete` event: the subscription is over.
+            Complete,
+        }
+
+        impl #event {
+            /// Parse an event from its `event` and `data` fields. Returns `None` for the events of other protocols.
+            pub fn from_event(event: &str, data: &str) -> ::std::result::Result<::std::option::Option<Self>, ::serde_json::Error> {
+                match event {
+                    "next" => ::serde_json::from_str(data).map(|response| ::std::option::Option::Some(#event::Next(response))),
+                    "complete" => ::std::result::Result::Ok(::std::option::Option::Some(#event::Complete)),
+                    _ => ::std::result::Result::Ok(::std::option::Option::None),
+                }
+            }
+
+            /// Parse a whole message, as delimited by a blank line in the event stream.
+            pub fn parse(message: &str) -> ::std::result::Result<::std::option::Option<Self>, ::serde_json::Error> {
+                let mut event = "message";
+                let mut data = ::std::vec::Vec::new();
+                for line in message.lines() {
+                    let (field, value) = match line.find(':') {
+                        ::std::option::Option::Some(0) => continue,
+                        ::std::option::Option::Some(idx) => (&line[..idx], line[idx + 1..].trim_start_matches(' ')),
+                        ::std::option::Option::None => (line, ""),
+                    };
+                    match field {
+                        "event" => event = value,
+                        "data" => data.push(value),
+                        _ => (),
+                    }
+                }
+                Self::from_event(event, &data.join("\n"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_event_types_are_named_after_the_operation() {
+        let single = sse_event_type("OnMessage", false).to_string();
+        assert!(single.contains("pub enum SseEvent {"));
+        assert!(single.contains("Next ( :: graphql_client :: Response < ResponseData > ) ,"));
+
+        let multiple = sse_event_type("OnMessage", true).to_string();
+        assert!(multiple.contains("pub enum OnMessageSseEvent {"));
+        assert!(
+            multiple.contains("Next ( :: graphql_client :: Response < OnMessageResponseData > ) ,")
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use proc_macro2::{TokenStream, TokenTree};
+use schema::Schema;
+use std::io::Write;
+
+/// Where [GraphQLClientDeriveOptions::codegen_stats](../struct.GraphQLClientDeriveOptions.html) writes the report when it is not a file path.
+pub const STATS_TO_STDERR: &str = "stderr";
+
+/// Statistics about the code generated for one query, to track generated-code bloat.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CodegenStats {
+    /// The name of the generated module.
+    pub module: String,
+    /// The query file the module is generated from.
+    pub query_path: String,
+    /// The structs in the module, including the query structs.
+    pub structs: usize,
+    /// The enums in the module.
+    pub enums: usize,
+    /// Every token of the generated module, including the delimited groups.
+    pub tokens: usize,
+    /// The fragments used by the operations, which get their own struct.
+    pub fragments_required: usize,
+    /// The schema types the operations cannot reach, left out before code generation.
+    pub types_pruned: usize,
+}
+
+impl CodegenStats {
+    pub(crate) fn new(
+        module: String,
+        query_path: String,
+        tokens: &TokenStream,
+        fragments_required: usize,
+        types_pruned: usize,
+    ) -> Self {
+        let mut stats = CodegenStats {
+            module,
+            query_path,
+            structs: 0,
+            enums: 0,
+            tokens: 0,
+            fragments_required,
+            types_pruned,
+        };
+        stats.count(tokens.clone());
+        stats
+    }
+
+    fn count(&mut self, tokens: TokenStream) {
+        for token in tokens {
+            self.tokens += 1;
+            match token {
+                TokenTree::Group(group) => self.count(group.stream()),
+                TokenTree::Ident(ref ident) if ident == "struct" => self.structs += 1,
+                TokenTree::Ident(ref ident) if ident == "enum" => self.enums += 1,
+                _ => (),
+            }
+        }
+    }
+
+    /// Write the report to stderr, or append it to a file as a JSON line. Failing to write only prints a warning, it should not break the build.
+    pub(crate) fn report(&self, destination: &str) {
+        if destination == STATS_TO_STDERR {
+            eprintln!("{}", self);
+            return;
+        }
+
+        let written = ::std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(destination)
+            .and_then(|mut file| {
+                writeln!(
+                    file,
+                    "{}",
+                    ::serde_json::to_string(self).expect("stats serialization")
+                )
+            });
+        if let Err(err) = written {
+            eprintln!("graphql_client: could not write {}: {}", destination, err);
+        }
+    }
+}
+
+impl ::std::fmt::Display for CodegenStats {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(
+            f,
+            "graphql_client: `{}` ({}): {} structs, {} enums, {} tokens, {} fragments required, {} types pruned",
+            self.module,
+            self.query_path,
+            self.structs,
+            self.enums,
+            self.tokens,
+            self.fragments_required,
+            self.types_pruned
+        )
+    }
+}
+
+/// The number of types in a schema, to measure pruning.
+pub(crate) fn type_count(schema: &Schema) -> usize {
+    schema.enums.len()
+        + schema.inputs.len()
+        + schema.interfaces.len()
+        + schema.objects.len()
+        + schema.scalars.len()
+        + schema.unions.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_items_are_counted() {
+        let tokens = quote! {
+            pub mod query {
+                pub struct Variables;
+                pub enum Episode { JEDI, EMPIRE }
+                pub struct ResponseData { pub hero: Option<Hero> }
+            }
+        };
+
+        let stats = CodegenStats::new("query".into(), "query.graphql".into(), &tokens, 1, 20);
+
+        assert_eq!(stats.structs, 2);
+        assert_eq!(stats.enums, 1);
+        assert_eq!(stats.tokens, 26);
+        assert_eq!(
+            stats.to_string(),
+            "graphql_client: `query` (query.graphql): 2 structs, 1 enums, 26 tokens, 1 fragments required, 20 types pruned"
+        );
+    }
+}
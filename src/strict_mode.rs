@@ -0,0 +1,80 @@
+use json_validation::SerdeAttributes;
+use proc_macro2::TokenStream;
+use query::QueryContext;
+use syn;
+
+/// The definition of a response struct with `#[serde(deny_unknown_fields)]`, in strict mode. serde does not support it along with `flatten`, so structs flattening others, and the fragments and variants of interfaces flattened into other structs, are returned as they are.
+pub(crate) fn strict_definition(context: &QueryContext, definition: TokenStream) -> TokenStream {
+    if !context.deny_unknown_fields {
+        return definition;
+    }
+
+    let mut input: syn::DeriveInput =
+        syn::parse2(definition.clone()).expect("generated type definition");
+    let name = input.ident.to_string();
+    let has_flattened_fields = match input.data {
+        syn::Data::Struct(ref data) => data
+            .fields
+            .iter()
+            .any(|field| SerdeAttributes::from_attributes(&field.attrs).flatten),
+        _ => return definition,
+    };
+    if has_flattened_fields
+        || context.fragments.contains_key(name.as_str())
+        || context.is_flattened_struct(&name)
+    {
+        return definition;
+    }
+
+    input
+        .attrs
+        .push(syn::parse_quote!(#[serde(deny_unknown_fields)]));
+    quote!(#input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::Schema;
+
+    #[test]
+    fn structs_deny_unknown_fields_unless_flattened() {
+        let schema = Schema::new();
+        let mut context = QueryContext::new_empty(&schema);
+        let definition = quote! {
+            #[derive(Deserialize)]
+            pub struct Viewer {
+                pub name: String,
+            }
+        };
+
+        assert_eq!(
+            strict_definition(&context, definition.clone()).to_string(),
+            definition.to_string()
+        );
+
+        context.deny_unknown_fields = true;
+        assert_eq!(
+            strict_definition(&context, definition.clone()).to_string(),
+            "# [ derive ( Deserialize ) ] # [ serde ( deny_unknown_fields ) ] pub struct Viewer { pub name : String , }"
+        );
+
+        let flattening = quote! {
+            #[derive(Deserialize)]
+            pub struct Viewer {
+                #[serde(flatten)]
+                pub on: ViewerOn,
+            }
+        };
+        assert_eq!(
+            strict_definition(&context, flattening.clone()).to_string(),
+            flattening.to_string()
+        );
+
+        context.record_flattened_struct("Viewer");
+        assert_eq!(
+            strict_definition(&context, definition.clone()).to_string(),
+            definition.to_string()
+        );
+    }
+}
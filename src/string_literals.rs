@@ -0,0 +1,276 @@
+use conversion::Placeholders;
+use graphql_parser::query::{self, Document, ParseError};
+use std::borrow::Cow;
+
+/// Parse a query document, with the string literals rewritten by [rewrite_string_literals].
+pub(crate) fn parse_query(query: &str) -> Result<Document, ParseError> {
+    query::parse_query(&rewrite_string_literals(query))
+}
+
+/// Print a query document, with the string values graphql_parser would print wrongly escaped by hand: it escapes the control characters and the characters outside the Basic Multilingual Plane with invalid escapes, and prints the strings with line breaks as block strings, losing their indentation and blank lines.
+pub(crate) fn print_query(document: &Document) -> String {
+    let mut document = document.clone();
+    let mut placeholders = Placeholders::default();
+    placeholders.protect_query_document(&mut document);
+    placeholders.restore(document.to_string())
+}
+
+/// graphql_parser decodes `\b` as U+0010, rejects the surrogate pairs escaping the characters outside the Basic Multilingual Plane, and trims the first line of block strings, so the string literals of a document it would get wrong are rewritten before parsing to literals it decodes to the values the spec gives them. The tokens after them keep their line and column, for the positions of errors.
+pub(crate) fn rewrite_string_literals(source: &str) -> Cow<'_, str> {
+    let mut rewritten = String::with_capacity(source.len());
+    let mut rest = source;
+    let mut changed = false;
+
+    while let Some(start) = rest.find(['"', '#']) {
+        rewritten.push_str(&rest[..start]);
+        let from = &rest[start..];
+        // Comments run to the end of the line.
+        if from.starts_with('#') {
+            let end = from.find(['\n', '\r']).unwrap_or(from.len());
+            rewritten.push_str(&from[..end]);
+            rest = &from[end..];
+            continue;
+        }
+
+        let literal = if from.starts_with("\"\"\"") {
+            block_string_end(from).map(|end| (end, Some(block_string_value(&from[3..end - 3]))))
+        } else {
+            string_end(from).map(|end| (end, string_value(&from[1..end - 1])))
+        };
+        match literal {
+            Some((end, Some(value))) => {
+                rewritten.push_str(&in_place(&from[..end], &parser_literal(&value)));
+                rest = &from[end..];
+                changed = true;
+            }
+            Some((end, None)) => {
+                rewritten.push_str(&from[..end]);
+                rest = &from[end..];
+            }
+            // Unterminated strings are reported by the parser.
+            None => {
+                rest = from;
+                break;
+            }
+        }
+    }
+
+    if !changed {
+        return Cow::Borrowed(source);
+    }
+    rewritten.push_str(rest);
+    Cow::Owned(rewritten)
+}
+
+/// The length of the block string at the start of `from`, up to its first closing `"""` not escaped.
+fn block_string_end(from: &str) -> Option<usize> {
+    let tail = &from[3..];
+    tail.match_indices("\"\"\"")
+        .find(|(end, _)| !tail[..*end].ends_with('\\'))
+        .map(|(end, _)| end + 6)
+}
+
+/// The length of the string at the start of `from`, up to its closing quote.
+fn string_end(from: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, c) in from.char_indices().skip(1) {
+        match c {
+            '"' if !escaped => return Some(idx + 1),
+            '\n' | '\r' => return None,
+            _ => (),
+        }
+        escaped = !escaped && c == '\\';
+    }
+    None
+}
+
+/// The value of a block string, from what is between its quotes: the indentation common to its lines after the first removed, without the blank lines at its start and end.
+fn block_string_value(raw: &str) -> String {
+    let raw = raw
+        .replace("\\\"\"\"", "\"\"\"")
+        .replace("\r\n", "\n")
+        .replace('\r', "\n");
+    let is_indentation = |c: char| c == ' ' || c == '\t';
+    let lines: Vec<&str> = raw.split('\n').collect();
+    let common_indent = lines
+        .iter()
+        .skip(1)
+        .map(|line| line.len() - line.trim_start_matches(is_indentation).len())
+        .zip(lines.iter().skip(1))
+        .filter(|(indent, line)| *indent < line.len())
+        .map(|(indent, _)| indent)
+        .min()
+        .unwrap_or(0);
+
+    let lines: Vec<&str> = lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| match idx {
+            0 => line,
+            _ => line.get(common_indent..).unwrap_or(""),
+        })
+        .collect();
+    let is_blank = |line: &&str| line.chars().all(is_indentation);
+    let start = lines
+        .iter()
+        .position(|line| !is_blank(line))
+        .unwrap_or(lines.len());
+    let end = lines
+        .iter()
+        .rposition(|line| !is_blank(line))
+        .map(|idx| idx + 1)
+        .unwrap_or(start);
+    lines[start..end].join("\n")
+}
+
+/// The value of a string, from what is between its quotes, when graphql_parser would decode it wrongly. `None` for the other strings, and for invalid ones, which graphql_parser reports.
+fn string_value(raw: &str) -> Option<String> {
+    let mut chars = raw.chars();
+    let mut value = String::with_capacity(raw.len());
+    let mut misdecoded = false;
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            c @ '"' | c @ '\\' | c @ '/' => value.push(c),
+            'b' => {
+                value.push('\u{8}');
+                misdecoded = true;
+            }
+            'f' => value.push('\u{c}'),
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            'u' => {
+                let code = hex_code(&mut chars)?;
+                let code = match code {
+                    0xD800..=0xDBFF => {
+                        if chars.next()? != '\\' || chars.next()? != 'u' {
+                            return None;
+                        }
+                        let low = hex_code(&mut chars)?;
+                        if !(0xDC00..=0xDFFF).contains(&low) {
+                            return None;
+                        }
+                        misdecoded = true;
+                        0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00)
+                    }
+                    code => code,
+                };
+                value.push(::std::char::from_u32(code)?);
+            }
+            _ => return None,
+        }
+    }
+
+    if misdecoded {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// The code unit of a `\uXXXX` escape, from its four hexadecimal digits.
+fn hex_code(chars: &mut ::std::str::Chars) -> Option<u32> {
+    let digits: String = chars.take(4).collect();
+    if digits.len() != 4 {
+        return None;
+    }
+    u32::from_str_radix(&digits, 16).ok()
+}
+
+/// A string literal graphql_parser decodes to `value`: only quotes, backslashes and line breaks are escaped, the other characters are written as they are.
+fn parser_literal(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// `replacement` followed by the line breaks of `original`, and spaces up to the column `original` ends at, so the tokens after it keep their position.
+fn in_place(original: &str, replacement: &str) -> String {
+    let mut replaced = replacement.to_owned();
+    let line_breaks = original.matches('\n').count();
+    let last_line = original.rsplit('\n').next().unwrap_or(original);
+    let (width, written) = if line_breaks == 0 {
+        (original.chars().count(), replacement.chars().count())
+    } else {
+        (last_line.chars().count(), 0)
+    };
+    replaced.push_str(&"\n".repeat(line_breaks));
+    replaced.push_str(&" ".repeat(width.saturating_sub(written)));
+    replaced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argument(query: &str) -> query::Value {
+        match parse_query(query).unwrap().definitions.remove(0) {
+            query::Definition::Operation(query::OperationDefinition::SelectionSet(
+                selection_set,
+            )) => match selection_set.items[0] {
+                query::Selection::Field(ref field) => field.arguments[0].1.clone(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn string_literals_are_decoded_as_the_spec_says() {
+        assert_eq!(
+            argument(r#"{ f(a: "\b \uD83D\uDE00 \u00e9 é \"") }"#),
+            query::Value::String("\u{8} 😀 é é \"".to_string())
+        );
+        assert_eq!(
+            argument("{ f(a: \"\"\"  first\n      indented\n    code\n\n  \"\"\") }"),
+            query::Value::String("  first\n  indented\ncode".to_string())
+        );
+        assert_eq!(
+            argument(r#"{ f(a: """quotes \""" and \n""") }"#),
+            query::Value::String("quotes \"\"\" and \\n".to_string())
+        );
+        // Strings graphql_parser decodes right, and comments, are left as they are.
+        assert_eq!(
+            rewrite_string_literals("{ f(a: \"\\u00e9\") } # \"\"\"\n"),
+            Cow::Borrowed("{ f(a: \"\\u00e9\") } # \"\"\"\n")
+        );
+    }
+
+    #[test]
+    fn tokens_after_rewritten_literals_keep_their_position() {
+        let query = "{ f(a: \"\"\"\n  x\n  \"\"\", b: 1) }";
+        let rewritten = rewrite_string_literals(query);
+        assert_eq!(rewritten, "{ f(a: \"x\"\n\n     , b: 1) }");
+        assert_eq!(
+            rewritten.lines().last().unwrap().find(','),
+            query.lines().last().unwrap().find(',')
+        );
+    }
+
+    #[test]
+    fn printed_queries_keep_their_string_values() {
+        let query = "{ f(a: \"\"\"\n  indented\n\n    more \\n\n\"\"\", b: \"\\u0001 😀 \\uD83D\\uDE00 \\b\") }";
+        let printed = print_query(&parse_query(query).unwrap());
+
+        assert_eq!(
+            printed,
+            "{\n  f(a: \"indented\\n\\n  more \\\\n\", b: \"\\u0001 😀 😀 \\u0008\")\n}\n"
+        );
+        assert_eq!(argument(&printed), argument(query));
+    }
+}
@@ -0,0 +1,70 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+type Query {
+  node(id: ID!): Node
+  viewer: User
+}
+
+interface Node {
+  id: ID!
+}
+
+type User implements Node {
+  id: ID
+  name: String
+}
+
+type Team implements Node {
+  id: ID!
+}
+"#;
+
+#[test]
+fn structs_selecting_id_and_typename_get_cache_keys() {
+    let generated = generate(
+        "cache_keys",
+        SCHEMA,
+        r#"
+        query Viewer {
+          viewer { kind: __typename id name }
+          node(id: "1") { __typename id ... on User { name } }
+        }
+        "#,
+        GraphQLClientDeriveOptions {
+            operation_name: Some("Viewer".to_string()),
+            struct_name: Some("Viewer".to_string()),
+            module_visibility: syn::parse_str("pub").unwrap(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_behaves(
+        "cache_keys_behavior",
+        &format!("pub struct Viewer;\n{}", generated),
+        r##"
+use cache_keys_behavior::viewer::ResponseData;
+
+#[test]
+fn cache_keys_are_the_typename_and_the_id() {
+    let data: ResponseData = serde_json::from_str(
+        r#"{"viewer": {"kind": "User", "id": "1", "name": "Luke"}, "node": {"__typename": "Team", "id": "2"}}"#,
+    )
+    .unwrap();
+    let id = String::from("1");
+    assert_eq!(data.viewer.unwrap().cache_key(), Some(("User", &id)));
+    let id = String::from("2");
+    assert_eq!(data.node.unwrap().cache_key(), Some(("Team", &id)));
+
+    let data: ResponseData = serde_json::from_str(
+        r#"{"viewer": {"kind": "User", "id": null, "name": "Luke"}, "node": null}"#,
+    )
+    .unwrap();
+    assert_eq!(data.viewer.unwrap().cache_key(), None);
+}
+"##,
+    );
+}
@@ -0,0 +1,37 @@
+use std::path::Path;
+use {generate_module_token_stream, GraphQLClientDeriveOptions};
+
+fn generate(call_hook: &str) -> Result<String, ::failure::Error> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+    generate_module_token_stream(
+        root.join("star_wars_query.graphql"),
+        &root.join("star_wars_schema.graphql"),
+        Some(GraphQLClientDeriveOptions {
+            module_name: Some("star_wars_query".to_string()),
+            call_hook: Some(call_hook.to_string()),
+            ..Default::default()
+        }),
+    )
+    .map(|tokens| tokens.to_string())
+}
+
+#[test]
+fn execute_reports_calls_to_the_call_hook() {
+    let generated = generate("crate::metrics::record_call").unwrap();
+
+    if cfg!(feature = "reqwest") {
+        assert!(generated.contains(
+            "crate :: metrics :: record_call ( body . operation_name , started . elapsed ( ) , :: std :: result :: Result :: is_ok ( & result ) ) ;"
+        ));
+    } else {
+        assert!(!generated.contains("record_call"));
+    }
+}
+
+#[test]
+fn invalid_call_hooks_are_reported() {
+    assert_eq!(
+        generate("record call").unwrap_err().to_string(),
+        "Invalid call hook: `record call`. Expected the path of a function, like `crate::metrics::record_graphql_call`."
+    );
+}
@@ -0,0 +1,42 @@
+use std::path::Path;
+use {generate_module_token_stream, GraphQLClientDeriveOptions};
+
+#[test]
+fn cfg_predicate_gates_the_module_and_the_trait_impls() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+    let generated = generate_module_token_stream(
+        root.join("star_wars_query.graphql"),
+        &root.join("star_wars_schema.graphql"),
+        Some(GraphQLClientDeriveOptions {
+            module_name: Some("star_wars_query".to_string()),
+            cfg: Some(r#"feature = "admin-api""#.to_string()),
+            ..Default::default()
+        }),
+    )
+    .unwrap()
+    .to_string();
+
+    assert!(generated.starts_with(r#"# [ cfg ( feature = "admin-api" ) ] mod star_wars_query"#));
+    assert!(generated.contains(
+        r#"# [ cfg ( feature = "admin-api" ) ] impl :: graphql_client :: GraphQLQuery for StarWarsQuery"#
+    ));
+}
+
+#[test]
+fn invalid_cfg_predicates_are_reported() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+    let result = generate_module_token_stream(
+        root.join("star_wars_query.graphql"),
+        &root.join("star_wars_schema.graphql"),
+        Some(GraphQLClientDeriveOptions {
+            module_name: Some("star_wars_query".to_string()),
+            cfg: Some("feature = \"unclosed".to_string()),
+            ..Default::default()
+        }),
+    );
+
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Invalid cfg predicate: `feature = \"unclosed`"
+    );
+}
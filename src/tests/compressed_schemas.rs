@@ -0,0 +1,24 @@
+use std::path::Path;
+use {generate_module_token_stream, GraphQLClientDeriveOptions};
+
+#[test]
+fn compressed_schemas_are_read_transparently() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+    for schema in &[
+        "star_wars_schema.graphql.gz",
+        "star_wars_schema.graphql.zst",
+    ] {
+        let generated = generate_module_token_stream(
+            root.join("star_wars_query.graphql"),
+            &root.join(schema),
+            Some(GraphQLClientDeriveOptions {
+                module_name: Some("star_wars_query".to_string()),
+                ..Default::default()
+            }),
+        )
+        .unwrap()
+        .to_string();
+
+        assert!(generated.contains("pub enum Episode"));
+    }
+}
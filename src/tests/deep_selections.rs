@@ -0,0 +1,46 @@
+use super::generate;
+use std::thread;
+use GraphQLClientDeriveOptions;
+
+#[test]
+fn deeply_nested_selections_are_generated_up_to_the_limit() {
+    let friends = (0..100).fold("__typename name".to_string(), |inner, _| {
+        format!("__typename friends {{ {} }}", inner)
+    });
+    let query = format!("query DeepQuery {{ hero {{ {} }} }}", friends);
+
+    let generate_with_limit = move |max_selection_depth| {
+        let query = query.clone();
+        // graphql_parser parses nested selection sets recursively, which takes more than the stack of test threads.
+        thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(move || {
+                generate(
+                    "deep_selections",
+                    include_str!("star_wars_schema.graphql"),
+                    &query,
+                    GraphQLClientDeriveOptions {
+                        module_name: Some("deep_query".to_string()),
+                        max_selection_depth,
+                        ..Default::default()
+                    },
+                )
+                .map_err(|err| err.to_string())
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+    };
+
+    let generated = generate_with_limit(Some(102)).unwrap();
+    assert!(generated.contains(&format!(
+        "pub struct DeepQueryHero{} ",
+        "Friends".repeat(100)
+    )));
+
+    let error = generate_with_limit(None).unwrap_err();
+    assert!(error.starts_with(&format!(
+        "The selection on `Character` at `DeepQuery.hero{}` is nested 65 selection sets deep, more than the limit of 64.",
+        ".friends".repeat(63)
+    )));
+}
@@ -0,0 +1,81 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+type Query {
+  viewer: User
+  node: Node
+}
+
+interface Node {
+  id: ID!
+}
+
+type User implements Node {
+  id: ID!
+  name: String
+  friends: [User]
+}
+"#;
+
+const QUERY: &str = r#"
+query Viewer {
+  viewer {
+    name
+    friends { ...Friend }
+  }
+  node {
+    __typename
+    id
+    ... on User { name }
+  }
+}
+
+fragment Friend on User {
+  name
+}
+"#;
+
+#[test]
+fn strict_mode_denies_unknown_fields_where_serde_allows() {
+    let generated = generate(
+        "deny_unknown_fields",
+        SCHEMA,
+        QUERY,
+        GraphQLClientDeriveOptions {
+            operation_name: Some("Viewer".to_string()),
+            struct_name: Some("Viewer".to_string()),
+            deny_unknown_fields: true,
+            module_visibility: syn::parse_str("pub").unwrap(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // Flattening structs, fragments and the variants flattened into interfaces stay lenient.
+    assert_behaves(
+        "deny_unknown_fields_behavior",
+        &format!("pub struct Viewer;\n{}", generated),
+        r##"
+use deny_unknown_fields_behavior::viewer::ResponseData;
+
+fn parse(json: &str) -> Result<ResponseData, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+#[test]
+fn unknown_fields_are_denied_where_serde_allows() {
+    let node = r#"{"__typename": "User", "id": "1", "name": "Leia", "extra": 1}"#;
+    assert!(parse(&format!(
+        r#"{{"viewer": {{"name": "Luke", "friends": [{{"name": "Leia", "extra": 1}}]}}, "node": {}}}"#,
+        node
+    ))
+    .is_ok());
+
+    assert!(parse(r#"{"viewer": null, "node": null, "extra": 1}"#).is_err());
+    assert!(parse(r#"{"viewer": {"name": "Luke", "friends": [], "extra": 1}, "node": null}"#).is_err());
+}
+"##,
+    );
+}
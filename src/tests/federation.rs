@@ -0,0 +1,94 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+const SUBGRAPH: &str = r#"
+extend schema
+  @link(url: "https://specs.apollo.dev/federation/v2.0", import: ["@key", "@external"])
+
+extend type Query {
+  topProducts(first: Int = 5): [Product]
+}
+
+type Product @key(fields: "upc") @key(fields: "sku") {
+  upc: String!
+  sku: String!
+  name: String
+}
+
+extend type User @key(fields: "id organization { id }") {
+  id: ID! @external
+  organization: Organization! @external
+  reviews: [Review]
+}
+
+type Organization {
+  id: ID!
+}
+
+type Review {
+  body: String
+}
+"#;
+
+#[test]
+fn federation_subgraphs_serve_entities() {
+    let generated = generate(
+        "federation",
+        SUBGRAPH,
+        r#"
+        query Entities($representations: [_Any!]!) {
+          _entities(representations: $representations) {
+            __typename
+            ...ProductName
+            ... on User { reviews { body } }
+          }
+          _service { sdl }
+          topProducts { upc }
+        }
+
+        fragment ProductName on Product { name }
+        "#,
+        GraphQLClientDeriveOptions {
+            module_name: Some("entities".to_string()),
+            module_visibility: syn::parse_str("pub").unwrap(),
+            federation_representations: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // `_Any` is left to the crate, like other custom scalars.
+    assert_behaves(
+        "federation_behavior",
+        &format!(
+            "pub struct Entities;\npub type _Any = ::serde_json::Value;\n{}",
+            generated
+        ),
+        r#"
+use federation_behavior::entities::{
+    ProductRepresentation, UserRepresentation, UserRepresentationOrganization,
+};
+
+#[test]
+fn representations_serialize_to_the_typename_and_the_key_fields() {
+    let product = ProductRepresentation {
+        upc: "1".to_string(),
+    };
+    let user = UserRepresentation::new(
+        "2".to_string(),
+        UserRepresentationOrganization { id: "3".to_string() },
+    );
+
+    assert_eq!(
+        serde_json::to_value(&product).unwrap(),
+        serde_json::json!({"__typename": "Product", "upc": "1"})
+    );
+    assert_eq!(
+        serde_json::to_value(&user).unwrap(),
+        serde_json::json!({"__typename": "User", "id": "2", "organization": {"id": "3"}})
+    );
+}
+"#,
+    );
+}
@@ -0,0 +1,107 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+schema {
+  query: Query
+  subscription: Subscription
+}
+
+type Query {
+  viewer: User
+}
+
+type Subscription {
+  userAdded: User
+  userRemoved: User
+}
+
+type User {
+  name: String
+  friends: [User]
+}
+"#;
+
+#[test]
+fn fragment_only_selections_are_flattened_at_every_level() {
+    let generated = generate(
+        "fragment_only_nested",
+        SCHEMA,
+        r#"
+        query Friends { ...Root }
+        fragment Root on Query { viewer { ...Friends } }
+        fragment Friends on User { friends { ...Name } }
+        fragment Name on User { name }
+        "#,
+        GraphQLClientDeriveOptions {
+            operation_name: Some("Friends".to_string()),
+            struct_name: Some("Friends".to_string()),
+            json_validation: true,
+            module_visibility: syn::parse_str("pub").unwrap(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // The validation of structs with flattened fields only does not bind the unused object.
+    assert_behaves(
+        "fragment_only_nested_behavior",
+        &format!(
+            "#![deny(unused_variables)]\npub struct Friends;\n{}",
+            generated
+        ),
+        r##"
+use fragment_only_nested_behavior::friends::ResponseData;
+
+#[test]
+fn fragment_only_selections_are_flattened_at_every_level() {
+    let json: serde_json::Value =
+        serde_json::from_str(r#"{"viewer": {"friends": [{"name": "Leia"}, null]}}"#).unwrap();
+
+    assert!(ResponseData::validate_json(&json).is_ok());
+    let data: ResponseData = serde_json::from_value(json).unwrap();
+    let friends = data.root.viewer.unwrap().friends.friends.unwrap();
+    assert_eq!(friends[0].as_ref().unwrap().name.name.as_deref(), Some("Leia"));
+
+    let invalid = serde_json::json!({"viewer": {"friends": [{"name": 1}]}});
+    let errors = ResponseData::validate_json(&invalid).unwrap_err();
+    assert_eq!(errors[0].path, "/viewer/friends/0/name");
+}
+"##,
+    );
+}
+
+#[test]
+fn subscription_fragments_selecting_several_fields_are_rejected() {
+    let options = || GraphQLClientDeriveOptions {
+        operation_name: Some("Users".to_string()),
+        struct_name: Some("Users".to_string()),
+        ..Default::default()
+    };
+
+    let error = generate(
+        "fragment_only_subscription",
+        SCHEMA,
+        r#"
+        subscription Users { ...UserEvents }
+        fragment UserEvents on Subscription { userAdded { name } userRemoved { name } }
+        "#,
+        options(),
+    )
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Multiple-field queries on the root subscription field are forbidden"));
+
+    assert!(generate(
+        "fragment_only_single_subscription",
+        SCHEMA,
+        r#"
+        subscription Users { ...UserEvents }
+        fragment UserEvents on Subscription { userAdded { name } }
+        "#,
+        options(),
+    )
+    .is_ok());
+}
@@ -12,8 +12,8 @@ fn ast_from_graphql_and_json_produce_the_same_schema() {
     let json: ::introspection_response::IntrospectionResponse =
         serde_json::from_str(SCHEMA_JSON).unwrap();
     let graphql_parser_schema = graphql_parser::parse_schema(SCHEMA_GRAPHQL).unwrap();
-    let json = Schema::from(&json);
-    let gql = Schema::from(&graphql_parser_schema);
+    let mut json = Schema::from(&json);
+    let mut gql = Schema::from(&graphql_parser_schema);
 
     assert_eq!(json.scalars, gql.scalars);
     for (json, gql) in json.objects.iter().zip(gql.objects.iter()) {
@@ -32,6 +32,25 @@ fn ast_from_graphql_and_json_produce_the_same_schema() {
     assert_eq!(json.query_type, gql.query_type);
     assert_eq!(json.mutation_type, gql.mutation_type);
     assert_eq!(json.subscription_type, gql.subscription_type);
+    // The SDL wraps the descriptions of input fields, so they only have the same words. Once compared, they are left out of the comparison of the inputs.
+    fn words(description: Option<&str>) -> Option<Vec<&str>> {
+        description.map(|description| description.split_whitespace().collect())
+    }
+    for (name, gql_input) in gql.inputs.iter_mut() {
+        let json_input = json.inputs.get_mut(name).unwrap();
+        for (field_name, gql_field) in gql_input.fields.iter_mut() {
+            let json_field = json_input.fields.get_mut(field_name).unwrap();
+            assert_eq!(
+                words(json_field.description),
+                words(gql_field.description),
+                "description of {}.{}",
+                name,
+                field_name
+            );
+            json_field.description = None;
+            gql_field.description = None;
+        }
+    }
     for (json, gql) in json.inputs.iter().zip(gql.inputs.iter()) {
         assert_eq!(json, gql);
     }
@@ -0,0 +1,91 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+#[test]
+fn variables_convert_into_graphql_values() {
+    let generated = generate(
+        "graphql_values",
+        r#"
+        scalar Date
+        enum Order { ASC, DESC }
+        input Filter { since: Date, and: Filter, tags: [String!] }
+        type Query { posts(filter: Filter!, order: Order, first: Int): [String!]! }
+        "#,
+        "query Posts($filter: Filter!, $order: Order, $first: Int) { posts(filter: $filter, order: $order, first: $first) }",
+        GraphQLClientDeriveOptions {
+            operation_name: Some("Posts".to_string()),
+            struct_name: Some("Posts".to_string()),
+            graphql_values: true,
+            module_visibility: syn::parse_str("pub").unwrap(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // The variables keep their GraphQL names, and the recursive field is boxed.
+    assert_behaves(
+        "graphql_values_behavior",
+        &format!(
+            "pub struct Posts;\npub type Date = ::std::string::String;\n{}",
+            generated
+        ),
+        r#"
+use graphql_parser::query::Value;
+use graphql_values_behavior::posts::{Filter, Order, Variables};
+
+fn object(fields: Vec<(&str, Value)>) -> Value {
+    Value::Object(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
+
+#[test]
+fn variables_convert_into_graphql_values() {
+    let variables = Variables {
+        filter: Filter {
+            since: Some("2020-01-01".to_string()),
+            and: Box::new(Some(Filter {
+                since: None,
+                and: Box::new(None),
+                tags: None,
+            })),
+            tags: Some(vec!["rust".to_string()]),
+        },
+        order: Some(Order::DESC),
+        first: None,
+    };
+
+    assert_eq!(
+        variables.to_graphql_value(),
+        object(vec![
+            (
+                "filter",
+                object(vec![
+                    ("since", Value::String("2020-01-01".to_string())),
+                    (
+                        "and",
+                        object(vec![
+                            ("since", Value::Null),
+                            ("and", Value::Null),
+                            ("tags", Value::Null),
+                        ]),
+                    ),
+                    ("tags", Value::List(vec![Value::String("rust".to_string())])),
+                ]),
+            ),
+            ("order", Value::Enum("DESC".to_string())),
+            ("first", Value::Null),
+        ])
+    );
+    assert_eq!(
+        Order::Other("RANDOM".to_string()).to_graphql_value(),
+        Value::Enum("RANDOM".to_string())
+    );
+}
+"#,
+    );
+}
@@ -0,0 +1,112 @@
+use super::generate;
+use hooks::{CodegenHooks, GeneratedItem, ItemKind};
+use proc_macro2::{Ident, Span};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use {generate_module_token_stream, GraphQLClientDeriveOptions};
+
+#[derive(Default)]
+struct Recorder {
+    items: Mutex<Vec<(ItemKind, String, String)>>,
+}
+
+impl CodegenHooks for Recorder {
+    fn generated_item(&self, item: &mut GeneratedItem) {
+        self.items.lock().unwrap().push((
+            item.kind,
+            item.graphql_type.to_string(),
+            item.rust_name.to_string(),
+        ));
+        match item.kind {
+            ItemKind::Interface => {
+                let name = Ident::new(item.rust_name, Span::call_site());
+                item.tokens.extend(quote!(impl Marker for #name {}));
+            }
+            ItemKind::Enum => item.tokens = quote!(),
+            _ => (),
+        }
+    }
+}
+
+#[test]
+fn hooks_see_and_change_every_generated_item() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+    let recorder = Arc::new(Recorder::default());
+    let generated = generate_module_token_stream(
+        root.join("star_wars_query.graphql"),
+        &root.join("star_wars_schema.graphql"),
+        Some(GraphQLClientDeriveOptions {
+            module_name: Some("star_wars_query".to_string()),
+            hooks: Some(recorder.clone()),
+            ..Default::default()
+        }),
+    )
+    .unwrap()
+    .to_string();
+
+    let mut items = recorder.items.lock().unwrap().clone();
+    items.sort_by_key(|(_, _, rust_name)| rust_name.clone());
+    let item = |kind, graphql_type: &str, rust_name: &str| {
+        (kind, graphql_type.to_string(), rust_name.to_string())
+    };
+    assert_eq!(
+        items,
+        vec![
+            item(ItemKind::Enum, "Episode", "Episode"),
+            item(ItemKind::Object, "Query", "ResponseData"),
+            item(ItemKind::Interface, "Character", "StarWarsQueryHero"),
+            item(ItemKind::Variables, "StarWarsQuery", "Variables"),
+        ]
+    );
+    assert!(generated.contains("impl Marker for StarWarsQueryHero { }"));
+    assert!(!generated.contains("pub enum Episode"));
+}
+
+#[derive(Default)]
+struct DirectiveRecorder {
+    directives: Mutex<Vec<(String, String)>>,
+}
+
+impl CodegenHooks for DirectiveRecorder {
+    fn generated_item(&self, item: &mut GeneratedItem) {
+        for directive in item.directives {
+            self.directives
+                .lock()
+                .unwrap()
+                .push((item.rust_name.to_string(), directive.name.clone()));
+        }
+    }
+}
+
+#[test]
+fn hooks_see_the_directives_of_operations_and_fragments() {
+    let recorder = Arc::new(DirectiveRecorder::default());
+
+    generate(
+        "hooks_directives",
+        "type Query { viewer: User }\ntype User { name: String }\n",
+        r#"
+        query Viewer @cached(ttl: 60) { viewer { ...Name } }
+        fragment Name on User @tag(name: "profile") { name }
+        "#,
+        GraphQLClientDeriveOptions {
+            module_name: Some("viewer".to_string()),
+            hooks: Some(recorder.clone()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let mut directives = recorder.directives.lock().unwrap().clone();
+    directives.sort();
+    let directive =
+        |rust_name: &str, directive: &str| (rust_name.to_string(), directive.to_string());
+    assert_eq!(
+        directives,
+        vec![
+            directive("Name", "tag"),
+            directive("ResponseData", "cached"),
+            directive("Variables", "cached"),
+        ]
+    );
+}
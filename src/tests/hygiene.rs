@@ -0,0 +1,308 @@
+use super::{assert_compiles, generate};
+use heck::SnakeCase;
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use std::path::Path;
+use syn::LitStr;
+use {generate_module_token_stream, GraphQLClientDeriveOptions};
+
+const PRELUDE_NAMES: &[&str] = &[
+    "Option",
+    "Some",
+    "None",
+    "Result",
+    "Ok",
+    "Err",
+    "Vec",
+    "String",
+    "Box",
+    "Default",
+    "Clone",
+    "Debug",
+    "PartialEq",
+    "From",
+    "Into",
+    "AsRef",
+    "Serialize",
+    "Deserialize",
+    "vec",
+    "format",
+];
+
+const SCHEMA: &str = r#"
+scalar DateTime
+scalar BigInt
+scalar Bytes
+
+enum Episode { NEWHOPE, EMPIRE, JEDI }
+
+input ReviewInput {
+  stars: Int!
+  commentary: String
+  postedAt: DateTime
+  replyTo: ReviewInput
+  replies: [ReviewInput!]
+}
+
+interface Character {
+  id: ID!
+  name: String!
+  friends: [Character]
+}
+
+type Human implements Character {
+  id: ID!
+  name: String!
+  friends: [Character]
+  height: Float
+  credits: BigInt!
+  avatar: Bytes
+  birthday: DateTime
+}
+
+type Droid implements Character {
+  id: ID!
+  name: String!
+  friends: [Character]
+  primaryFunction: String
+}
+
+union SearchResult = Human | Droid
+
+type Review {
+  stars: Int!
+  commentary: String
+}
+
+type Query {
+  hero(episode: Episode): Character
+  search(text: String!): [SearchResult!]!
+}
+
+type Mutation {
+  createReview(episode: Episode!, review: ReviewInput!): Review
+}
+
+type Subscription {
+  reviewAdded(episode: Episode): Review
+}
+"#;
+
+const QUERY: &str = r#"
+query Hero($episode: Episode) {
+  hero(episode: $episode) {
+    __typename
+    id
+    name
+    friends { __typename name }
+    ... on Human { height credits avatar birthday }
+    ... on Droid { primaryFunction }
+  }
+  search(text: "hope") {
+    __typename
+    ... on Human { name }
+  }
+}
+
+mutation CreateReview($episode: Episode!, $review: ReviewInput!) {
+  createReview(episode: $episode, review: $review) { stars commentary }
+}
+
+subscription ReviewAdded($episode: Episode) {
+  reviewAdded(episode: $episode) { stars }
+}
+"#;
+
+/// The tokens of the generated code, with the code in the string literals of the attributes other than docs, like the strategies of proptest.
+fn flatten(tokens: TokenStream, flat: &mut Vec<TokenTree>, in_attribute: bool) {
+    let mut previous: Option<TokenTree> = None;
+    for token in tokens {
+        match &token {
+            TokenTree::Group(group) => {
+                let is_attribute = group.delimiter() == Delimiter::Bracket
+                    && match &previous {
+                        Some(TokenTree::Punct(punct)) => {
+                            punct.as_char() == '#' || punct.as_char() == '!'
+                        }
+                        _ => false,
+                    };
+                let is_doc = match group.stream().into_iter().next() {
+                    Some(TokenTree::Ident(ident)) => ident == "doc",
+                    _ => false,
+                };
+                if !(is_attribute && is_doc) {
+                    flatten(group.stream(), flat, in_attribute || is_attribute);
+                }
+            }
+            TokenTree::Literal(literal) if in_attribute => {
+                if let Ok(code) =
+                    syn::parse2::<LitStr>(quote!(#literal)).map(|literal| literal.value())
+                {
+                    if let Ok(code) = code.parse() {
+                        flatten(code, flat, in_attribute);
+                    }
+                }
+            }
+            token => flat.push(token.clone()),
+        }
+        previous = Some(token);
+    }
+}
+
+/// The names of the prelude used without a path in the generated code.
+fn unqualified_prelude_names(generated: TokenStream) -> Vec<String> {
+    let mut flat = Vec::new();
+    flatten(generated, &mut flat, false);
+    flat.iter()
+        .enumerate()
+        .filter_map(|(index, token)| match token {
+            TokenTree::Ident(ident) if PRELUDE_NAMES.contains(&ident.to_string().as_str()) => {
+                // Associated types like `FromStr::Err` are defined by name.
+                let qualified = index > 0
+                    && match &flat[index - 1] {
+                        TokenTree::Punct(punct) => punct.as_char() == ':',
+                        TokenTree::Ident(ident) => ident == "type",
+                        _ => false,
+                    };
+                if qualified {
+                    None
+                } else {
+                    Some(ident.to_string())
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn generated_code_does_not_depend_on_the_prelude() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+    let generated = generate_module_token_stream(
+        root.join("star_wars_query.graphql"),
+        &root.join("star_wars_schema.graphql"),
+        Some(GraphQLClientDeriveOptions {
+            module_name: Some("star_wars_query".to_string()),
+            additional_derives: Some("Serialize,PartialEq".to_string()),
+            input_builders: true,
+            mocks: true,
+            json_validation: true,
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+
+    assert_eq!(unqualified_prelude_names(generated), Vec::<String>::new());
+}
+
+/// The options of the hygiene tests: every option whose generated code only needs the dependencies of `assert_compiles`.
+fn every_option() -> GraphQLClientDeriveOptions {
+    GraphQLClientDeriveOptions {
+        module_name: Some("queries".to_string()),
+        additional_derives: Some("Serialize,PartialEq".to_string()),
+        big_number_scalars: Some("BigInt=string".to_string()),
+        bytes_scalars: Some("Bytes".to_string()),
+        input_builders: true,
+        borrowed_variables: true,
+        sse_subscriptions: true,
+        get_requests: true,
+        graphql_values: true,
+        cassettes: true,
+        mocks: true,
+        arbitrary: true,
+        default_impls: true,
+        default_variants: Some("Episode=JEDI".to_string()),
+        list_iterators: true,
+        json_validation: true,
+        error_paths: true,
+        partial_data: true,
+        variable_specs: true,
+        query_extensions: true,
+        pretty_print: true,
+        nested_indirection: Some("box".parse().unwrap()),
+        serde_adapters: Some("DateTime=crate::timestamps".to_string()),
+        lenient_scalars: true,
+        nullable_defaults: true,
+        deny_unknown_fields: true,
+        sensitive_fields: Some("Human.birthday".to_string()),
+        union_mode: "non-exhaustive".parse().unwrap(),
+        typename_enums: true,
+        selection_metadata: true,
+        query_text: "compressed".parse().unwrap(),
+        call_hook: Some("crate::record_call".to_string()),
+        ..Default::default()
+    }
+}
+
+/// The items the modules generated with `every_option` expect from the crate using them.
+const CRATE_ITEMS: &str = r#"
+pub struct Hero;
+pub struct CreateReview;
+pub struct ReviewAdded;
+
+pub fn record_call(_operation_name: &'static str, _elapsed: ::std::time::Duration, _succeeded: bool) {}
+
+#[derive(::serde_derive::Serialize, ::serde_derive::Deserialize, ::std::fmt::Debug, ::std::clone::Clone, ::std::cmp::PartialEq, ::std::default::Default)]
+pub struct DateTime(::std::string::String);
+
+pub mod timestamps {
+    pub fn serialize<S: ::serde::Serializer>(
+        value: &::std::option::Option<super::DateTime>,
+        serializer: S,
+    ) -> ::std::result::Result<S::Ok, S::Error> {
+        ::serde::Serialize::serialize(value, serializer)
+    }
+
+    pub fn deserialize<'de, D: ::serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> ::std::result::Result<::std::option::Option<super::DateTime>, D::Error> {
+        ::serde::Deserialize::deserialize(deserializer)
+    }
+}
+"#;
+
+#[test]
+fn generated_code_with_every_option_does_not_depend_on_the_prelude() {
+    let generated = generate("hygiene", SCHEMA, QUERY, every_option())
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    assert_eq!(unqualified_prelude_names(generated), Vec::<String>::new());
+}
+
+#[test]
+fn generated_code_with_every_option_compiles_without_the_prelude() {
+    let modules: Vec<String> = ["Hero", "CreateReview", "ReviewAdded"]
+        .iter()
+        .map(|operation| {
+            let module_name = operation.to_snake_case();
+            generate(
+                &format!("hygiene_{}", module_name),
+                SCHEMA,
+                QUERY,
+                GraphQLClientDeriveOptions {
+                    operation_name: Some(operation.to_string()),
+                    struct_name: Some(operation.to_string()),
+                    module_name: Some(module_name),
+                    ..every_option()
+                },
+            )
+            .unwrap()
+        })
+        .collect();
+
+    assert_compiles(
+        "hygiene_compile",
+        &format!("{}{}", CRATE_ITEMS, modules.join("\n")),
+    );
+}
+
+#[test]
+fn module_with_every_operation_and_option_compiles_without_the_prelude() {
+    let generated = generate("hygiene_every_operation", SCHEMA, QUERY, every_option()).unwrap();
+
+    assert_compiles(
+        "hygiene_every_operation_compile",
+        &format!("{}{}", CRATE_ITEMS, generated),
+    );
+}
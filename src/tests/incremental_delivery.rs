@@ -0,0 +1,90 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+type Query {
+  viewer: User
+}
+
+type User {
+  login: String!
+  bio: String!
+  films: [String!]!
+}
+"#;
+
+fn options() -> GraphQLClientDeriveOptions {
+    GraphQLClientDeriveOptions {
+        operation_name: Some("Profile".to_string()),
+        struct_name: Some("Profile".to_string()),
+        module_visibility: syn::parse_str("pub").unwrap(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn payloads_are_typed_once_merged() {
+    let generated = generate(
+        "incremental_delivery_merged",
+        SCHEMA,
+        r#"
+        query Profile { viewer { login films @stream(initialCount: 1) ...Bio @defer(label: "bio") } }
+        fragment Bio on User { bio }
+        "#,
+        options(),
+    )
+    .unwrap();
+
+    assert_behaves(
+        "incremental_delivery_merged_behavior",
+        &format!("pub struct Profile;\n{}", generated),
+        r##"
+use incremental_delivery_merged_behavior::profile::{IncrementalPatch, IncrementalResponse};
+use serde_json::json;
+
+#[test]
+fn payloads_are_typed_once_merged() {
+    let mut response = IncrementalResponse::new(json!({"viewer": {"login": "octocat", "films": ["A New Hope"]}}));
+    let initial = response.response_data().unwrap().viewer.unwrap();
+    assert_eq!(initial.login, "octocat");
+    assert!(initial.bio.is_none());
+
+    // The payloads are JSON: what they hold depends on their path.
+    let deferred: IncrementalPatch = serde_json::from_str(
+        r#"{"path": ["viewer"], "label": "bio", "data": {"bio": "Hi"}, "hasNext": true}"#,
+    )
+    .unwrap();
+    assert_eq!(deferred.data, Some(json!({"bio": "Hi"})));
+    let streamed: IncrementalPatch = serde_json::from_str(
+        r#"{"path": ["viewer", "films", 1], "items": ["The Empire Strikes Back"], "hasNext": false}"#,
+    )
+    .unwrap();
+    assert_eq!(streamed.items, Some(vec![json!("The Empire Strikes Back")]));
+
+    response.apply(deferred);
+    response.apply(streamed);
+
+    let merged = response.response_data().unwrap().viewer.unwrap();
+    assert_eq!(merged.bio.unwrap().bio, "Hi");
+    assert_eq!(merged.films, vec!["A New Hope", "The Empire Strikes Back"]);
+}
+"##,
+    );
+}
+
+#[test]
+fn deferred_inline_fragments_are_rejected() {
+    let error = generate(
+        "incremental_delivery_inline",
+        SCHEMA,
+        "query Profile { viewer { login ... on User @defer { bio } } }",
+        options(),
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "`@defer` is only supported on fragment spreads: move the fields of `... on User @defer` to a named fragment, and spread it with `...Name @defer`."
+    );
+}
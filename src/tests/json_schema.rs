@@ -0,0 +1,82 @@
+use super::{generate, test_dir};
+use serde_json::{self, Value};
+use std::fs;
+use std::path::Path;
+use GraphQLClientDeriveOptions;
+
+fn read_json(path: &Path) -> Value {
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn json_schemas_are_written_for_each_operation() {
+    let dir = test_dir("json_schema_output");
+
+    generate(
+        "json_schema",
+        include_str!("star_wars_schema.graphql"),
+        r#"
+        query HeroQuery($episode: Episode) {
+          hero(episode: $episode) { __typename name ... on Human { homePlanet } }
+        }
+
+        mutation CreateReview($episode: Episode, $review: ReviewInput!) {
+          createReview(episode: $episode, review: $review) { stars }
+        }
+        "#,
+        GraphQLClientDeriveOptions {
+            module_name: Some("operations".to_string()),
+            json_schema_directory: Some(dir.join("schemas")),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let variables = read_json(&dir.join("schemas/CreateReview.variables.schema.json"));
+    assert_eq!(variables["title"], "CreateReview variables");
+    assert_eq!(variables["required"], json!(["review"]));
+    assert_eq!(
+        variables["properties"]["review"],
+        json!({ "$ref": "#/$defs/ReviewInput" })
+    );
+    assert_eq!(
+        variables["$defs"]["Episode"],
+        json!({
+            "enum": ["NEWHOPE", "EMPIRE", "JEDI"],
+            "description": "The episodes in the Star Wars trilogy",
+        })
+    );
+    assert_eq!(
+        variables["$defs"]["ReviewInput"]["required"],
+        json!(["stars"])
+    );
+    assert_eq!(
+        variables["$defs"]["ReviewInput"]["properties"]["favorite_color"],
+        json!({
+            "anyOf": [{ "$ref": "#/$defs/ColorInput" }, { "type": "null" }],
+            "description": "Favorite color, optional",
+        })
+    );
+
+    let response = read_json(&dir.join("schemas/HeroQuery.response.schema.json"));
+    assert_eq!(response["required"], json!(["hero"]));
+    let heroes = &response["properties"]["hero"]["anyOf"][0]["anyOf"];
+    assert_eq!(
+        heroes[0],
+        json!({
+            "type": "object",
+            "properties": {
+                "__typename": { "const": "Droid" },
+                "name": { "type": "string", "description": "What others call this droid" },
+            },
+            "required": ["__typename", "name"],
+        })
+    );
+    assert_eq!(
+        heroes[1]["properties"]["homePlanet"],
+        json!({
+            "anyOf": [{ "type": "string" }, { "type": "null" }],
+            "description": "The home planet of the human, or null if unknown",
+        })
+    );
+}
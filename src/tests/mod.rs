@@ -1 +1,179 @@
+mod cache_keys;
+mod call_hooks;
+mod cfg_gating;
+mod compressed_schemas;
+mod deep_selections;
+mod deny_unknown_fields;
+mod federation;
+mod fragment_only_selections;
 mod github;
+mod graphql_values;
+mod hooks;
+mod hygiene;
+mod incremental_delivery;
+mod json_schema;
+mod multiple_operations;
+mod namespaces;
+mod nested_indirection;
+mod nullable_defaults;
+mod operation_features;
+mod operation_lock;
+mod overlapping_selections;
+mod query_bodies;
+mod query_files;
+mod refetch_queries;
+mod response_string_type;
+mod root_fragments;
+mod selection_metadata;
+mod sensitive_fields;
+mod spans;
+mod union_modes;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use {generate_module_token_stream, GraphQLClientDeriveOptions};
+
+/// An empty temporary directory for the files of the test `test`. Schemas and queries are cached by path, so every test needs its own.
+fn test_dir(test: &str) -> PathBuf {
+    let dir = ::std::env::temp_dir().join(format!("graphql_client_{}", test));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// The module generated for `query` against `schema`, written to the directory of the test `test`.
+fn generate(
+    test: &str,
+    schema: &str,
+    query: &str,
+    options: GraphQLClientDeriveOptions,
+) -> Result<String, ::failure::Error> {
+    let dir = test_dir(test);
+    fs::write(dir.join("schema.graphql"), schema).unwrap();
+    fs::write(dir.join("query.graphql"), query).unwrap();
+
+    generate_module_token_stream(
+        dir.join("query.graphql"),
+        &dir.join("schema.graphql"),
+        Some(options),
+    )
+    .map(|tokens| tokens.to_string())
+}
+
+/// The dependencies the generated code can use, for the crates of `assert_compiles`.
+const DEPENDENCIES: &str = r#"
+graphql_client = { version = "0.14", default-features = false }
+graphql-parser = "0.2"
+miniz_oxide = "0.8"
+proptest = "1"
+proptest-derive = "0.4"
+serde = { version = "1", features = ["rc"] }
+serde_derive = "1"
+serde_json = "1"
+"#;
+
+/// The dependencies of the executors, for the crates of `assert_compiles` when their features are enabled.
+fn executor_dependencies() -> String {
+    let mut dependencies = String::new();
+    if cfg!(feature = "reqwest") {
+        dependencies.push_str(
+            "reqwest = { version = \"0.12\", default-features = false, features = [\"json\"] }\n",
+        );
+    }
+    if cfg!(feature = "tracing") {
+        dependencies.push_str("tracing = \"0.1\"\n");
+    }
+    dependencies
+}
+
+/// Check `code`, generated modules next to the items they expect from the crate using them, with `cargo check` in a crate without the prelude of the test `test`. Names only the prelude brings in scope fail the test, like trait methods called without their trait and type errors.
+fn assert_compiles(test: &str, code: &str) {
+    assert_compiles_with_features(test, code, &[]);
+}
+
+/// `assert_compiles` for a crate with the cargo `features`, checked with none of them and with all of them enabled.
+fn assert_compiles_with_features(test: &str, code: &str, features: &[&str]) {
+    let dir = write_crate(test, code, features);
+    let all_features = features.join(",");
+    let feature_sets = if features.is_empty() {
+        vec![""]
+    } else {
+        vec!["", all_features.as_str()]
+    };
+    for features in feature_sets {
+        let output = cargo(&dir, &["check", "--quiet", "--features", features]);
+        assert!(
+            output.status.success(),
+            "The generated code does not compile with the features `{}`:\n{}",
+            features,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+/// Run `tests`, the integration tests of a crate made of `code` like the ones of `assert_compiles`, to check how the generated code behaves, like what it deserializes. The modules the tests use must be `pub`.
+fn assert_behaves(test: &str, code: &str, tests: &str) {
+    let dir = write_crate(test, code, &[]);
+    fs::create_dir(dir.join("tests")).unwrap();
+    fs::write(dir.join("tests/behavior.rs"), tests).unwrap();
+
+    let output = cargo(&dir, &["test", "--quiet"]);
+    assert!(
+        output.status.success(),
+        "The tests of the generated code fail:\n{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// A crate named after the test `test`, without the prelude, made of `code` and declaring the cargo `features`.
+fn write_crate(test: &str, code: &str, features: &[&str]) -> PathBuf {
+    let dir = test_dir(test);
+    fs::create_dir(dir.join("src")).unwrap();
+    let declared_features: String = features
+        .iter()
+        .map(|feature| format!("{} = []\n", feature))
+        .collect();
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.0.0\"\nedition = \"2018\"\n\n[workspace]\n\n[features]\n{}\n[dependencies]{}{}",
+            test,
+            declared_features,
+            DEPENDENCIES,
+            executor_dependencies()
+        ),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src/lib.rs"),
+        format!("#![no_implicit_prelude]\n{}", code),
+    )
+    .unwrap();
+    dir
+}
+
+/// Run cargo with `args` in `dir`. The dependencies are built once for all the tests, and downloaded only if they are not available offline, so the tests don't need the network once they are.
+fn cargo(dir: &Path, args: &[&str]) -> Output {
+    let target_dir = env::temp_dir().join("graphql_client_compile_checks");
+    let run = |offline: bool| {
+        let mut command = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+        command
+            .args(args)
+            .current_dir(dir)
+            .env("CARGO_TARGET_DIR", &target_dir);
+        if offline {
+            command.arg("--offline");
+        }
+        command.output().unwrap()
+    };
+
+    let output = run(true);
+    if !output.status.success() && String::from_utf8_lossy(&output.stderr).contains("offline") {
+        run(false)
+    } else {
+        output
+    }
+}
@@ -0,0 +1,56 @@
+use super::{assert_compiles, generate};
+use std::fs;
+use std::path::Path;
+use GraphQLClientDeriveOptions;
+
+const QUERY: &str = r#"
+query A { starship(id: "1") { name } }
+query B { droid(id: "2") { name appearsIn } }
+query Hero($episode: Episode) { hero(episode: $episode) { __typename name appearsIn } }
+"#;
+
+#[test]
+fn operations_share_the_items_they_have_in_common() {
+    let schema = fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/star_wars_schema.graphql"),
+    )
+    .unwrap();
+    let generated = generate(
+        "multiple_operations",
+        &schema,
+        QUERY,
+        GraphQLClientDeriveOptions {
+            module_name: Some("queries".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(generated.matches("pub enum Episode").count(), 1);
+    assert!(generated.contains("pub const HERO_OPERATION_NAME : & 'static str = \"Hero\" ;"));
+
+    // The request bodies are built from the typed variables of each operation.
+    assert_compiles(
+        "multiple_operations_compile",
+        &format!(
+            r#"
+pub struct A;
+pub struct B;
+pub struct Hero;
+
+{}
+
+pub fn query_bodies() -> ::std::vec::Vec<::graphql_client::QueryBody<queries::OperationVariables>> {{
+    let hero = queries::HeroVariables {{
+        episode: ::std::option::Option::Some(queries::Episode::JEDI),
+    }};
+    ::std::vec![
+        <queries::OperationVariables as ::std::convert::From<_>>::from(queries::AVariables).into_query_body(),
+        <queries::OperationVariables as ::std::convert::From<_>>::from(hero).into_query_body(),
+    ]
+}}
+"#,
+            generated
+        ),
+    );
+}
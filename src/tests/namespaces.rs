@@ -0,0 +1,36 @@
+use namespace::Namespace;
+use std::path::Path;
+use {generate_module_token_stream, GraphQLClientDeriveOptions};
+
+#[test]
+fn namespaces_prefix_the_module_and_the_query_struct() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+    let generate = |struct_name: Option<&str>, namespace| {
+        generate_module_token_stream(
+            root.join("star_wars_query.graphql"),
+            &root.join("star_wars_schema.graphql"),
+            Some(GraphQLClientDeriveOptions {
+                module_name: Some("StarWarsQuery".to_string()),
+                struct_name: struct_name.map(String::from),
+                namespace: Some(namespace),
+                ..Default::default()
+            }),
+        )
+    };
+
+    let generated = generate(None, Namespace::Prefix("swapi".to_string()))
+        .unwrap()
+        .to_string();
+    assert!(generated.starts_with("mod swapi_star_wars_query {"));
+    assert!(generated.contains(
+        "impl :: graphql_client :: GraphQLQuery for SwapiStarWarsQuery { type Variables = swapi_star_wars_query :: Variables ;"
+    ));
+
+    let generated = generate(Some("LegacyStarWars"), Namespace::StructName)
+        .unwrap()
+        .to_string();
+    assert!(generated.starts_with("mod legacy_star_wars_star_wars_query {"));
+    assert!(generated.contains("impl :: graphql_client :: GraphQLQuery for LegacyStarWars {"));
+
+    assert!(generate(None, Namespace::StructName).is_err());
+}
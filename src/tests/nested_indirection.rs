@@ -0,0 +1,141 @@
+use super::{assert_behaves, generate};
+use indirection::{Indirection, Pointer};
+use syn;
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+type Query {
+  viewer: User
+}
+
+type User {
+  name: String
+  friends: [User!]!
+  team: Team!
+}
+
+type Team {
+  name: String
+}
+"#;
+
+const QUERY: &str = r#"
+query Viewer {
+  viewer { name friends { name } team { name } }
+}
+"#;
+
+fn options(indirection: Indirection) -> GraphQLClientDeriveOptions {
+    GraphQLClientDeriveOptions {
+        operation_name: Some("Viewer".to_string()),
+        struct_name: Some("Viewer".to_string()),
+        nested_indirection: Some(indirection),
+        module_visibility: syn::parse_str("pub").unwrap(),
+        ..Default::default()
+    }
+}
+
+const VIEWER: &str =
+    r#"{"viewer": {"name": "Luke", "friends": [{"name": "Leia"}], "team": {"name": "Rebels"}}}"#;
+
+/// A crate with the modules generated with each `(module, indirection)`, for `assert_behaves`.
+fn modules(test: &str, indirections: &[(&str, Indirection)]) -> String {
+    indirections
+        .iter()
+        .map(|(module, indirection)| {
+            let generated = generate(
+                &format!("{}_{}", test, module),
+                SCHEMA,
+                QUERY,
+                options(indirection.clone()),
+            )
+            .unwrap();
+            format!(
+                "pub mod {} {{ pub struct Viewer; {} }}\n",
+                module, generated
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn nested_structs_are_held_by_the_pointer() {
+    let code = modules(
+        "nested_indirection",
+        &[
+            (
+                "boxed",
+                Indirection {
+                    pointer: Pointer::Box,
+                    min_fields: 0,
+                },
+            ),
+            (
+                "shared",
+                Indirection {
+                    pointer: Pointer::Arc,
+                    min_fields: 0,
+                },
+            ),
+        ],
+    );
+
+    assert_behaves(
+        "nested_indirection_behavior",
+        &code,
+        &format!(
+            r##"
+use nested_indirection_behavior::{{boxed::viewer as boxed, shared::viewer as shared}};
+use std::sync::Arc;
+
+#[test]
+fn nested_structs_are_held_by_the_pointer() {{
+    let data: boxed::ResponseData = serde_json::from_str(r#"{viewer}"#).unwrap();
+    let viewer: Box<boxed::ViewerViewer> = data.viewer.unwrap();
+    let friends: Vec<boxed::ViewerViewerFriends> = viewer.friends;
+    let team: Box<boxed::ViewerViewerTeam> = viewer.team;
+    assert_eq!(friends[0].name.as_deref(), Some("Leia"));
+    assert_eq!(team.name.as_deref(), Some("Rebels"));
+
+    let data: shared::ResponseData = serde_json::from_str(r#"{viewer}"#).unwrap();
+    let friends: Vec<Arc<shared::ViewerViewerFriends>> = data.viewer.unwrap().friends.clone();
+    assert_eq!(friends[0].name.as_deref(), Some("Leia"));
+}}
+"##,
+            viewer = VIEWER
+        ),
+    );
+}
+
+#[test]
+fn small_selections_stay_inline() {
+    let code = modules(
+        "nested_indirection_min_fields",
+        &[(
+            "boxed",
+            Indirection {
+                pointer: Pointer::Box,
+                min_fields: 4,
+            },
+        )],
+    );
+
+    assert_behaves(
+        "nested_indirection_min_fields_behavior",
+        &code,
+        &format!(
+            r##"
+use nested_indirection_min_fields_behavior::boxed::viewer as boxed;
+
+#[test]
+fn small_selections_stay_inline() {{
+    let data: boxed::ResponseData = serde_json::from_str(r#"{viewer}"#).unwrap();
+    let viewer: Box<boxed::ViewerViewer> = data.viewer.unwrap();
+    let team: boxed::ViewerViewerTeam = viewer.team;
+    assert_eq!(team.name.as_deref(), Some("Rebels"));
+}}
+"##,
+            viewer = VIEWER
+        ),
+    );
+}
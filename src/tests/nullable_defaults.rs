@@ -0,0 +1,118 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+scalar Timestamp
+
+type Query {
+  viewer: User
+}
+
+type User {
+  id: ID!
+  name: String
+  age: Int
+  updatedAt: Timestamp
+}
+"#;
+
+const QUERY: &str = "query Viewer { viewer { id name age updatedAt } }";
+
+fn options(options: GraphQLClientDeriveOptions) -> GraphQLClientDeriveOptions {
+    GraphQLClientDeriveOptions {
+        operation_name: Some("Viewer".to_string()),
+        struct_name: Some("Viewer".to_string()),
+        nullable_defaults: true,
+        module_visibility: syn::parse_str("pub").unwrap(),
+        ..options
+    }
+}
+
+#[test]
+fn nullable_fields_default_to_none() {
+    let generated = generate(
+        "nullable_defaults_plain",
+        SCHEMA,
+        QUERY,
+        options(GraphQLClientDeriveOptions::default()),
+    )
+    .unwrap();
+
+    assert_behaves(
+        "nullable_defaults_plain_behavior",
+        &format!(
+            "pub struct Viewer;\npub type Timestamp = ::std::string::String;\n{}",
+            generated
+        ),
+        r##"
+use nullable_defaults_plain_behavior::viewer::ResponseData;
+
+#[test]
+fn missing_nullable_fields_are_none() {
+    let data: ResponseData = serde_json::from_str("{}").unwrap();
+    assert!(data.viewer.is_none());
+
+    let data: ResponseData = serde_json::from_str(r#"{"viewer": {"id": "1"}}"#).unwrap();
+    let viewer = data.viewer.unwrap();
+    assert_eq!(viewer.id, "1");
+    assert!(viewer.name.is_none() && viewer.age.is_none() && viewer.updated_at.is_none());
+
+    assert!(serde_json::from_str::<ResponseData>(r#"{"viewer": {}}"#).is_err());
+}
+"##,
+    );
+}
+
+#[test]
+fn nullable_defaults_combine_with_adapters_and_leniency() {
+    let generated = generate(
+        "nullable_defaults_adapters",
+        SCHEMA,
+        QUERY,
+        options(GraphQLClientDeriveOptions {
+            serde_adapters: Some("Timestamp=crate::timestamp".to_string()),
+            lenient_scalars: true,
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+
+    // The adapter makes the field required unless it has a default, and the lenient attribute has its own.
+    assert_behaves(
+        "nullable_defaults_adapters_behavior",
+        &format!("{}{}", TIMESTAMP_ADAPTER, generated),
+        r##"
+use nullable_defaults_adapters_behavior::viewer::ResponseData;
+
+#[test]
+fn missing_fields_with_adapters_are_none() {
+    let data: ResponseData = serde_json::from_str(r#"{"viewer": {"id": "1"}}"#).unwrap();
+    let viewer = data.viewer.unwrap();
+    assert!(viewer.age.is_none() && viewer.updated_at.is_none());
+
+    let data: ResponseData =
+        serde_json::from_str(r#"{"viewer": {"id": "1", "age": "42", "updatedAt": 7}}"#).unwrap();
+    let viewer = data.viewer.unwrap();
+    assert_eq!(viewer.age, Some(42));
+    assert_eq!(viewer.updated_at.unwrap().0, 7);
+}
+"##,
+    );
+}
+
+/// A `Timestamp` without serde implementations, read from a number of seconds by its adapter.
+const TIMESTAMP_ADAPTER: &str = r#"
+pub struct Viewer;
+
+pub struct Timestamp(pub u64);
+
+pub mod timestamp {
+    pub fn deserialize<'de, D: ::serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> ::std::result::Result<::std::option::Option<super::Timestamp>, D::Error> {
+        let seconds: ::std::option::Option<u64> = ::serde::Deserialize::deserialize(deserializer)?;
+        ::std::result::Result::Ok(::std::option::Option::map(seconds, super::Timestamp))
+    }
+}
+"#;
@@ -0,0 +1,94 @@
+use super::{assert_compiles_with_features, generate};
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+type Query {
+  viewer: User
+  users: [User]
+}
+
+type User {
+  name: String
+}
+"#;
+
+const QUERY: &str = r#"
+query Viewer {
+  viewer { name }
+}
+
+query Users {
+  users { name }
+}
+"#;
+
+fn options(operation_features: &str) -> GraphQLClientDeriveOptions {
+    GraphQLClientDeriveOptions {
+        module_name: Some("queries".to_string()),
+        operation_features: Some(operation_features.parse().unwrap()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn operations_with_a_feature_are_gated() {
+    let generated = generate(
+        "operation_features_gated",
+        SCHEMA,
+        QUERY,
+        options("Users=users"),
+    )
+    .unwrap();
+
+    assert!(generated.contains(
+        "# [ cfg ( feature = \"users\" ) ] mod users_operation { use super :: * ; pub const USERS_OPERATION_NAME : & 'static str = \"Users\" ;"
+    ));
+    assert!(generated
+        .contains("# [ cfg ( feature = \"users\" ) ] pub use self :: users_operation :: * ;"));
+    assert!(generated.contains(
+        "# [ cfg ( feature = \"users\" ) ] impl :: graphql_client :: GraphQLQuery for Users"
+    ));
+    assert!(generated
+        .contains("pub enum Operations { Viewer , # [ cfg ( feature = \"users\" ) ] Users , }"));
+    // Operations without a feature are always generated.
+    assert!(!generated.contains("mod viewer_operation"));
+    assert!(!generated.contains(
+        "# [ cfg ( feature = \"users\" ) ] impl :: graphql_client :: GraphQLQuery for Viewer"
+    ));
+}
+
+#[test]
+fn features_of_unknown_operations_are_reported() {
+    let err = generate(
+        "operation_features_unknown",
+        SCHEMA,
+        QUERY,
+        options("Posts=posts"),
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "Feature for the operation `Posts`, which is not in the document. Operations: `Viewer`, `Users`."
+    );
+}
+
+#[test]
+fn modules_with_a_gated_operation_compile_with_and_without_the_feature() {
+    let generated = generate(
+        "operation_features_compile",
+        SCHEMA,
+        QUERY,
+        options("Users=users"),
+    )
+    .unwrap();
+
+    assert_compiles_with_features(
+        "operation_features_compile_crate",
+        &format!(
+            "pub struct Viewer;\n#[cfg(feature = \"users\")]\npub struct Users;\n{}",
+            generated
+        ),
+        &["users"],
+    );
+}
@@ -0,0 +1,44 @@
+use super::{generate, test_dir};
+use operation_lock::UPDATE_LOCK_ENV_VAR;
+use std::fs;
+use std::path::Path;
+use GraphQLClientDeriveOptions;
+
+fn options(lock: &Path) -> GraphQLClientDeriveOptions {
+    GraphQLClientDeriveOptions {
+        operation_name: Some("HeroName".to_string()),
+        struct_name: Some("HeroName".to_string()),
+        operation_lock: Some(lock.to_path_buf()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn operations_are_checked_against_the_lock_file() {
+    let lock = test_dir("operation_lock").join("graphql.lock");
+    let schema = include_str!("star_wars_schema.graphql");
+    let query = "query HeroName { hero { __typename name } }";
+
+    let missing = generate("operation_lock_query", schema, query, options(&lock))
+        .unwrap_err()
+        .to_string();
+    assert!(missing.contains("is out of date for hero_name::HeroName."));
+
+    ::std::env::set_var(UPDATE_LOCK_ENV_VAR, "1");
+    let updated = generate("operation_lock_query", schema, query, options(&lock));
+    ::std::env::remove_var(UPDATE_LOCK_ENV_VAR);
+    updated.unwrap();
+    let lock_file = fs::read_to_string(&lock).unwrap();
+    assert!(lock_file.contains("\"hero_name::HeroName\": {"));
+    assert!(lock_file.contains("\"query\": \"query HeroName { hero { __typename name } }\""));
+    generate("operation_lock_query", schema, query, options(&lock)).unwrap();
+
+    // Query files are cached by path, so the changed query is in another file.
+    assert!(generate(
+        "operation_lock_changed",
+        schema,
+        "query HeroName { hero { __typename id } }",
+        options(&lock),
+    )
+    .is_err());
+}
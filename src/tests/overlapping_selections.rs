@@ -0,0 +1,105 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+type Query {
+  viewer: User!
+}
+
+type User {
+  id: ID!
+  name: String
+  friend: User
+}
+"#;
+
+fn options() -> GraphQLClientDeriveOptions {
+    GraphQLClientDeriveOptions {
+        operation_name: Some("Viewer".to_string()),
+        struct_name: Some("Viewer".to_string()),
+        module_visibility: syn::parse_str("pub").unwrap(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn fields_selected_several_times_are_merged() {
+    let generated = generate(
+        "overlapping_repeated",
+        SCHEMA,
+        "query Viewer { viewer { name friend { id } } viewer { friend { name } name } }",
+        options(),
+    )
+    .unwrap();
+
+    assert_behaves(
+        "overlapping_repeated_behavior",
+        &format!("pub struct Viewer;\n{}", generated),
+        r##"
+use overlapping_repeated_behavior::viewer::ResponseData;
+
+#[test]
+fn fields_selected_several_times_are_merged() {
+    let data: ResponseData = serde_json::from_str(
+        r#"{"viewer": {"name": "Luke", "friend": {"id": "2", "name": "Leia"}}}"#,
+    )
+    .unwrap();
+    let friend = data.viewer.friend.unwrap();
+    assert_eq!(data.viewer.name.as_deref(), Some("Luke"));
+    assert_eq!((friend.id.as_str(), friend.name.as_deref()), ("2", Some("Leia")));
+}
+"##,
+    );
+}
+
+#[test]
+fn fields_of_spread_fragments_are_left_to_the_fragments() {
+    let generated = generate(
+        "overlapping_spread",
+        SCHEMA,
+        r#"
+        query Viewer { viewer { id name ...UserParts } }
+        fragment UserParts on User { ...UserId friend { id } }
+        fragment UserId on User { id }
+        "#,
+        options(),
+    )
+    .unwrap();
+
+    // `id` is only a field of `UserId`, reached through `UserParts`.
+    assert_behaves(
+        "overlapping_spread_behavior",
+        &format!("pub struct Viewer;\n{}", generated),
+        r##"
+use overlapping_spread_behavior::viewer::ResponseData;
+
+#[test]
+fn fields_of_spread_fragments_are_left_to_the_fragments() {
+    let data: ResponseData = serde_json::from_str(
+        r#"{"viewer": {"id": "1", "name": "Luke", "friend": {"id": "2"}}}"#,
+    )
+    .unwrap();
+    let parts = &data.viewer.user_parts;
+    assert_eq!(data.viewer.name.as_deref(), Some("Luke"));
+    assert_eq!(parts.user_id.id, "1");
+    assert_eq!(parts.friend.as_ref().unwrap().id, "2");
+}
+"##,
+    );
+
+    let err = generate(
+        "overlapping_conflicting",
+        SCHEMA,
+        r#"
+        query Viewer { viewer { friend { name } ...UserParts } }
+        fragment UserParts on User { friend { id } }
+        "#,
+        options(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`friend` is selected on `User` both directly and in the fragment `UserParts`, with different fields. Select it in one place."
+    );
+}
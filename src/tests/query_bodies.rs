@@ -0,0 +1,278 @@
+use super::{assert_behaves, generate};
+use query_text::QueryText;
+use std::path::Path;
+use syn;
+use {generate_module_token_stream, GraphQLClientDeriveOptions};
+
+/// The module generated for the star wars query with `options`, public so the tests of `assert_behaves` can use it.
+fn star_wars_query(options: GraphQLClientDeriveOptions) -> String {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+    let generated = generate_module_token_stream(
+        root.join("star_wars_query.graphql"),
+        &root.join("star_wars_schema.graphql"),
+        Some(GraphQLClientDeriveOptions {
+            module_name: Some("star_wars_query".to_string()),
+            module_visibility: syn::parse_str("pub").unwrap(),
+            ..options
+        }),
+    )
+    .unwrap()
+    .to_string();
+    format!("pub struct StarWarsQuery;\n{}", generated)
+}
+
+#[test]
+fn query_bodies_carry_extensions() {
+    let without = star_wars_query(GraphQLClientDeriveOptions::default());
+    assert!(!without.contains("Extensions"));
+
+    // `build_query` builds the body of the runtime, without extensions.
+    assert_behaves(
+        "query_bodies_extensions_behavior",
+        &star_wars_query(GraphQLClientDeriveOptions {
+            query_extensions: true,
+            ..Default::default()
+        }),
+        r#"
+use graphql_client::GraphQLQuery;
+use query_bodies_extensions_behavior::star_wars_query::{Episode, Variables, QUERY};
+use query_bodies_extensions_behavior::StarWarsQuery;
+use serde_json::json;
+
+fn variables() -> Variables {
+    Variables {
+        episode_for_hero: Episode::NEWHOPE,
+    }
+}
+
+#[test]
+fn query_bodies_carry_extensions() {
+    let body = StarWarsQuery::build_query_with_extensions(
+        variables(),
+        json!({"persistedQuery": {"version": 1}}),
+    );
+    assert_eq!(
+        serde_json::to_value(&body).unwrap(),
+        json!({
+            "variables": {"episodeForHero": "NEWHOPE"},
+            "query": QUERY,
+            "operationName": "StarWarsQuery",
+            "extensions": {"persistedQuery": {"version": 1}},
+        })
+    );
+
+    assert_eq!(
+        serde_json::to_value(&StarWarsQuery::build_query(variables())).unwrap(),
+        json!({
+            "variables": {"episodeForHero": "NEWHOPE"},
+            "query": QUERY,
+            "operationName": "StarWarsQuery",
+        })
+    );
+}
+"#,
+    );
+}
+
+#[test]
+fn queries_get_a_get_query_string() {
+    assert_behaves(
+        "query_bodies_get_requests_behavior",
+        &star_wars_query(GraphQLClientDeriveOptions {
+            get_requests: true,
+            ..Default::default()
+        }),
+        r#"
+use query_bodies_get_requests_behavior::star_wars_query::{Episode, Variables};
+
+#[test]
+fn queries_get_a_get_query_string() {
+    let variables = Variables {
+        episode_for_hero: Episode::JEDI,
+    };
+
+    let query_string = variables.to_get_query_string().unwrap();
+    assert!(query_string.starts_with("query=query%20StarWarsQuery%28%24episodeForHero%3A%20Episode%21%29%20%7B"), "{}", query_string);
+    assert!(query_string.ends_with("&operationName=StarWarsQuery&variables=%7B%22episodeForHero%22%3A%22JEDI%22%7D"), "{}", query_string);
+}
+"#,
+    );
+}
+
+#[test]
+fn string_literals_keep_their_values_in_the_query() {
+    let schema = "type Query { echo(text: String): String, draft: String }";
+    let options = || GraphQLClientDeriveOptions {
+        operation_name: Some("Echo".to_string()),
+        struct_name: Some("Echo".to_string()),
+        module_visibility: syn::parse_str("pub").unwrap(),
+        ..Default::default()
+    };
+
+    // The query is sent as it is written...
+    let written = "query Echo { echo(text: \"\"\"\n  é\n    😀\n\"\"\") escaped: echo(text: \"\\uD83D\\uDE00\\b\") }";
+    let as_written = generate("query_bodies_written", schema, written, options()).unwrap();
+
+    // ...and printed with the same string values without its `@client` fields.
+    let printed = generate(
+        "query_bodies_printed",
+        schema,
+        &written.replace(" }", " draft @client }"),
+        options(),
+    )
+    .unwrap();
+
+    assert_behaves(
+        "query_bodies_string_literals_behavior",
+        &format!(
+            "pub mod written {{ pub struct Echo; {} }}\npub mod printed {{ pub struct Echo; {} }}",
+            as_written, printed
+        ),
+        &format!(
+            r#"
+use query_bodies_string_literals_behavior::{{printed, written}};
+
+#[test]
+fn string_literals_keep_their_values_in_the_query() {{
+    assert_eq!(written::echo::QUERY, {:?});
+    assert_eq!(
+        printed::echo::QUERY,
+        "query Echo {{\n  echo(text: \"é\\n  😀\")\n  escaped: echo(text: \"😀\\u0008\")\n}}\n"
+    );
+}}
+"#,
+            written
+        ),
+    );
+}
+
+#[test]
+fn loaded_queries_are_not_embedded() {
+    let generated = star_wars_query(GraphQLClientDeriveOptions {
+        get_requests: true,
+        query_text: "crate::queries::load".parse().unwrap(),
+        ..Default::default()
+    });
+    assert!(!generated.contains("pub const QUERY :"));
+
+    assert_behaves(
+        "query_bodies_loaded_behavior",
+        &format!(
+            r#"
+pub mod queries {{
+    pub fn load(hash: &'static str) -> &'static str {{
+        ::std::assert_eq!(hash, super::star_wars_query::QUERY_HASH);
+        "query StarWarsQuery($episodeForHero: Episode!) {{ hero(episode: $episodeForHero) {{ name }} }}"
+    }}
+}}
+{}"#,
+            generated
+        ),
+        r#"
+use graphql_client::GraphQLQuery;
+use query_bodies_loaded_behavior::star_wars_query::{query, Episode, Variables};
+use query_bodies_loaded_behavior::StarWarsQuery;
+
+#[test]
+fn loaded_queries_are_sent() {
+    let variables = Variables {
+        episode_for_hero: Episode::EMPIRE,
+    };
+
+    assert!(query().starts_with("query StarWarsQuery($episodeForHero: Episode!)"));
+    assert!(variables
+        .to_get_query_string()
+        .unwrap()
+        .starts_with("query=query%20StarWarsQuery"));
+    assert_eq!(StarWarsQuery::build_query(variables).query, query());
+}
+"#,
+    );
+}
+
+#[test]
+fn compressed_queries_are_not_available_in_no_std_code() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+    let error = generate_module_token_stream(
+        root.join("star_wars_query.graphql"),
+        &root.join("star_wars_schema.graphql"),
+        Some(GraphQLClientDeriveOptions {
+            module_name: Some("star_wars_query".to_string()),
+            query_text: QueryText::Compressed,
+            no_std: true,
+            ..Default::default()
+        }),
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "The compressed query text is decompressed with `std`, it is not available in no_std code"
+    );
+}
+
+#[test]
+fn cassettes_record_responses_with_their_request() {
+    assert_behaves(
+        "query_bodies_cassettes_behavior",
+        &star_wars_query(GraphQLClientDeriveOptions {
+            cassettes: true,
+            ..Default::default()
+        }),
+        r##"
+use query_bodies_cassettes_behavior::star_wars_query::{Cassette, Episode, Variables};
+
+fn variables(episode_for_hero: Episode) -> Variables {
+    Variables { episode_for_hero }
+}
+
+#[test]
+fn cassettes_record_responses_with_their_request() {
+    let response = r#"{"data": {"hero": {"__typename": "Droid", "name": "R2-D2"}}}"#;
+    let cassette = Cassette::record(&variables(Episode::NEWHOPE), response).unwrap();
+
+    assert_eq!(cassette.request_hash.len(), 16);
+    assert!(cassette.matches(&variables(Episode::NEWHOPE)));
+    assert!(!cassette.matches(&variables(Episode::JEDI)));
+    let cassettes = [cassette];
+    let found = Cassette::find(&cassettes, &variables(Episode::NEWHOPE)).unwrap();
+    let replayed = found.replay().unwrap();
+    assert_eq!(replayed.data.unwrap().hero.unwrap().name, "R2-D2");
+
+    assert!(Cassette::record(&variables(Episode::NEWHOPE), r#"{"data": {"hero": 1}}"#).is_err());
+}
+"##,
+    );
+}
+
+#[test]
+fn variable_specs_are_generated_with_their_option() {
+    let schema = "type Query { echo(text: String): String }";
+    let query = "query Echo($text: String) { echo(text: $text) }";
+    let options = |variable_specs| GraphQLClientDeriveOptions {
+        operation_name: Some("Echo".to_string()),
+        struct_name: Some("Echo".to_string()),
+        variable_specs,
+        ..Default::default()
+    };
+
+    let without = generate("query_bodies_without_specs", schema, query, options(false)).unwrap();
+    assert!(!without.contains("VARIABLES"));
+    assert!(!without.contains("VariableSpec"));
+
+    let with = generate("query_bodies_with_specs", schema, query, options(true)).unwrap();
+    assert_behaves(
+        "query_bodies_variable_specs_behavior",
+        &format!("pub struct Echo;\n{}", with),
+        r#"
+use query_bodies_variable_specs_behavior::Echo;
+
+#[test]
+fn variables_are_described() {
+    let text = &Echo::VARIABLES[0];
+    assert_eq!(Echo::VARIABLES.len(), 1);
+    assert_eq!((text.name, text.type_, text.has_default), ("text", "String", false));
+}
+"#,
+    );
+}
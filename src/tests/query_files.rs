@@ -0,0 +1,114 @@
+use super::{assert_behaves, generate, test_dir};
+use std::fs;
+use std::path::Path;
+use syn;
+use GraphQLClientDeriveOptions;
+
+/// The constant including `path`, for cargo to rebuild the generated code when it changes.
+fn tracked(path: &Path) -> String {
+    format!(
+        "include_bytes ! ( {:?} )",
+        path.canonicalize().unwrap().to_str().unwrap()
+    )
+}
+
+#[test]
+fn fragments_can_live_in_additional_query_files() {
+    let dir = test_dir("query_files_fragments");
+    fs::write(
+        dir.join("hero_name.graphql"),
+        "fragment HeroName on Character { __typename name }",
+    )
+    .unwrap();
+
+    let generate = |additional_query_paths: Vec<String>| {
+        generate(
+            "query_files",
+            include_str!("star_wars_schema.graphql"),
+            "query HeroQuery { hero { __typename ...HeroName } }",
+            GraphQLClientDeriveOptions {
+                module_name: Some("hero_query".to_string()),
+                additional_query_paths,
+                module_visibility: syn::parse_str("pub").unwrap(),
+                ..Default::default()
+            },
+        )
+    };
+
+    let pattern = dir.join("*.graphql").display().to_string();
+    let generated = generate(vec![pattern]).unwrap();
+    // Matched files are tracked, so editing them regenerates the code.
+    assert!(generated.contains(&tracked(&dir.join("hero_name.graphql"))));
+    assert_behaves(
+        "query_files_behavior",
+        &format!("pub struct HeroQuery;\n{}", generated),
+        r##"
+use query_files_behavior::hero_query::{ResponseData, QUERY};
+
+#[test]
+fn fragments_of_additional_query_files_are_generated_and_sent() {
+    let data: ResponseData =
+        serde_json::from_str(r#"{"hero": {"__typename": "Droid", "name": "R2-D2"}}"#).unwrap();
+    assert_eq!(data.hero.unwrap().hero_name.name, "R2-D2");
+    assert!(QUERY.contains("fragment HeroName on Character { __typename name }"));
+}
+"##,
+    );
+
+    let missing = dir.join("*.gql").display().to_string();
+    assert!(generate(vec![missing]).is_err());
+}
+
+#[test]
+fn only_the_spread_fragments_of_the_library_are_included() {
+    let dir = test_dir("fragment_library_fragments");
+    fs::create_dir_all(dir.join("characters")).unwrap();
+    fs::write(
+        dir.join("characters/hero_name.graphql"),
+        "fragment HeroName on Character { __typename name ...CharacterId }",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("character_id.graphql"),
+        "fragment CharacterId on Character { __typename id }\n\
+         fragment Unused on Character { __typename name }",
+    )
+    .unwrap();
+
+    let generated = generate(
+        "fragment_library",
+        include_str!("star_wars_schema.graphql"),
+        "query HeroQuery { hero { __typename ...HeroName } }",
+        GraphQLClientDeriveOptions {
+            module_name: Some("hero_query".to_string()),
+            fragments_path: Some(dir.join("**/*.graphql").display().to_string()),
+            module_visibility: syn::parse_str("pub").unwrap(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert!(!generated.contains("Unused"));
+    assert!(generated.contains(&tracked(&dir.join("characters/hero_name.graphql"))));
+    assert!(generated.contains(&tracked(&dir.join("character_id.graphql"))));
+    assert_behaves(
+        "fragment_library_behavior",
+        &format!("pub struct HeroQuery;\n{}", generated),
+        r##"
+use fragment_library_behavior::hero_query::{ResponseData, QUERY};
+
+#[test]
+fn the_spread_fragments_are_generated_and_sent() {
+    let data: ResponseData = serde_json::from_str(
+        r#"{"hero": {"__typename": "Droid", "name": "R2-D2", "id": "2001"}}"#,
+    )
+    .unwrap();
+    let hero_name = data.hero.unwrap().hero_name;
+    assert_eq!(hero_name.name, "R2-D2");
+    assert_eq!(hero_name.character_id.id, "2001");
+    assert!(QUERY.contains("fragment CharacterId on Character"));
+    assert!(!QUERY.contains("fragment Unused"));
+}
+"##,
+    );
+}
@@ -0,0 +1,74 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+type Query {
+  node(id: ID!): Node
+  viewer: User
+}
+
+interface Node {
+  id: ID!
+}
+
+type User implements Node {
+  id: ID!
+  name: String
+  avatar(size: Int): String
+  friends: [User]
+}
+"#;
+
+#[test]
+fn fragments_on_nodes_get_refetch_queries() {
+    let generated = generate(
+        "refetch_queries",
+        SCHEMA,
+        r#"
+        query Viewer($size: Int) { viewer { ...UserCard ...UserAvatar } }
+        fragment UserCard on User { id friends { ...FriendName } }
+        fragment FriendName on User { name }
+        fragment UserAvatar on User { avatar(size: $size) }
+        "#,
+        GraphQLClientDeriveOptions {
+            operation_name: Some("Viewer".to_string()),
+            struct_name: Some("Viewer".to_string()),
+            module_visibility: syn::parse_str("pub").unwrap(),
+            refetch_queries: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert!(!generated.contains("UserAvatarRefetchQuery"));
+    assert_behaves(
+        "refetch_queries_behavior",
+        &format!("pub struct Viewer;\n{}", generated),
+        r##"
+use graphql_client::GraphQLQuery;
+use refetch_queries_behavior::{user_card_refetch_query, FriendNameRefetchQuery, UserCardRefetchQuery};
+
+#[test]
+fn fragments_on_nodes_get_refetch_queries() {
+    let body = UserCardRefetchQuery::build_query(user_card_refetch_query::Variables {
+        id: "1".to_string(),
+    });
+    assert_eq!(body.operation_name, "UserCardRefetchQuery");
+    assert_eq!(
+        body.query,
+        "query UserCardRefetchQuery($id: ID!) {\n  node(id: $id) {\n    __typename\n    ...UserCard\n  }\n}\n\n\
+         fragment UserCard on User {\n  id\n  friends {\n    ...FriendName\n  }\n}\n\n\
+         fragment FriendName on User {\n  name\n}\n"
+    );
+    assert_eq!(serde_json::to_value(&body.variables).unwrap(), serde_json::json!({"id": "1"}));
+
+    let data: <FriendNameRefetchQuery as GraphQLQuery>::ResponseData = serde_json::from_str(
+        r#"{"node": {"__typename": "User", "name": "Leia"}}"#,
+    )
+    .unwrap();
+    assert!(data.node.is_some());
+}
+"##,
+    );
+}
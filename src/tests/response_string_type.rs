@@ -0,0 +1,80 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+type Query {
+  viewer: User
+}
+
+type User {
+  id: ID!
+  name: String!
+  nicknames: [String]
+}
+"#;
+
+const QUERY: &str = "query Viewer { viewer { __typename id name nicknames } }";
+
+fn options(response_string_type: &str) -> GraphQLClientDeriveOptions {
+    GraphQLClientDeriveOptions {
+        operation_name: Some("Viewer".to_string()),
+        struct_name: Some("Viewer".to_string()),
+        response_string_type: Some(response_string_type.to_string()),
+        module_visibility: syn::parse_str("pub").unwrap(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn response_strings_use_the_string_type() {
+    let generated = generate(
+        "response_string_type_cow",
+        SCHEMA,
+        QUERY,
+        options("::std::borrow::Cow<'static, str>"),
+    )
+    .unwrap();
+
+    // IDs keep their own type.
+    assert_behaves(
+        "response_string_type_cow_behavior",
+        &format!("pub struct Viewer;\n{}", generated),
+        r##"
+use response_string_type_cow_behavior::viewer::ResponseData;
+use std::borrow::Cow;
+
+#[test]
+fn response_strings_use_the_string_type() {
+    let data: ResponseData = serde_json::from_str(
+        r#"{"viewer": {"__typename": "User", "id": "1", "name": "Luke", "nicknames": ["Red Five", null]}}"#,
+    )
+    .unwrap();
+    let viewer = data.viewer.unwrap();
+
+    let name: Cow<'static, str> = viewer.name.clone();
+    let nicknames: Option<Vec<Option<Cow<'static, str>>>> = viewer.nicknames.clone();
+    let id: String = viewer.id.clone();
+    assert_eq!(name, "Luke");
+    assert_eq!(nicknames, Some(vec![Some(Cow::Borrowed("Red Five")), None]));
+    assert_eq!(id, "1");
+    assert_eq!(viewer.cache_key(), Some(("User", &id)));
+}
+"##,
+    );
+}
+
+#[test]
+fn invalid_string_types_are_reported() {
+    let err = generate(
+        "response_string_type_invalid",
+        SCHEMA,
+        QUERY,
+        options("Cow<'static"),
+    )
+    .unwrap_err();
+
+    assert!(err
+        .to_string()
+        .starts_with("Invalid response string type `Cow<'static`"));
+}
@@ -0,0 +1,117 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+schema {
+  query: QueryRoot
+  mutation: MutationRoot
+}
+
+type QueryRoot {
+  viewer: User
+  version: String
+}
+
+type MutationRoot {
+  rename(name: String!): User
+}
+
+type User {
+  name: String
+}
+"#;
+
+fn options(operation_name: &str) -> GraphQLClientDeriveOptions {
+    GraphQLClientDeriveOptions {
+        operation_name: Some(operation_name.to_string()),
+        struct_name: Some(operation_name.to_string()),
+        module_visibility: syn::parse_str("pub").unwrap(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn inline_fragments_on_the_root_type_are_flattened() {
+    let generated = generate(
+        "root_fragments_inline",
+        SCHEMA,
+        r#"
+        query Version { ... on QueryRoot { version ... on QueryRoot { viewer { name } } } }
+        "#,
+        options("Version"),
+    )
+    .unwrap();
+
+    assert_behaves(
+        "root_fragments_inline_behavior",
+        &format!("pub struct Version;\n{}", generated),
+        r##"
+use root_fragments_inline_behavior::version::ResponseData;
+
+#[test]
+fn inline_fragments_on_the_root_type_are_flattened() {
+    let data: ResponseData =
+        serde_json::from_str(r#"{"version": "1.0", "viewer": {"name": "Luke"}}"#).unwrap();
+    assert_eq!(data.version.as_deref(), Some("1.0"));
+    assert_eq!(data.viewer.unwrap().name.as_deref(), Some("Luke"));
+}
+"##,
+    );
+}
+
+#[test]
+fn fragments_on_the_root_types_are_spread_in_the_response_data() {
+    let generated = generate(
+        "root_fragments_spread",
+        SCHEMA,
+        r#"
+        mutation Rename { ...RenameFields }
+        fragment RenameFields on MutationRoot { ... on MutationRoot { rename(name: "Leia") { name } } }
+        "#,
+        options("Rename"),
+    )
+    .unwrap();
+
+    assert_behaves(
+        "root_fragments_spread_behavior",
+        &format!("pub struct Rename;\n{}", generated),
+        r##"
+use root_fragments_spread_behavior::rename::ResponseData;
+
+#[test]
+fn fragments_on_the_root_types_are_spread_in_the_response_data() {
+    let data: ResponseData = serde_json::from_str(r#"{"rename": {"name": "Leia"}}"#).unwrap();
+    let renamed = data.rename_fields.rename.unwrap();
+    assert_eq!(renamed.name.as_deref(), Some("Leia"));
+}
+"##,
+    );
+}
+
+#[test]
+fn operations_without_a_root_type_are_reported() {
+    let err = generate(
+        "root_fragments_missing_root",
+        SCHEMA,
+        "subscription Renamed { renamed { name } }",
+        options("Renamed"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The schema has no subscription type; operation `Renamed` is a subscription."
+    );
+
+    let diagnostics = ::validation::validate(
+        "subscription Renamed { renamed { name } }",
+        &::std::env::temp_dir().join("graphql_client_root_fragments_missing_root/schema.graphql"),
+    );
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec!["1:1: The schema has no subscription type; operation `Renamed` is a subscription."]
+    );
+}
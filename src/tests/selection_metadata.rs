@@ -0,0 +1,59 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+#[test]
+fn selection_constant_describes_the_selected_fields() {
+    let generated = generate(
+        "selection_metadata",
+        include_str!("star_wars_schema.graphql"),
+        r#"
+        query Heroes { hero { __typename heroName: name ... on Droid { ...Function } } }
+        fragment Function on Droid { primaryFunction }
+        "#,
+        GraphQLClientDeriveOptions {
+            operation_name: Some("Heroes".to_string()),
+            struct_name: Some("Heroes".to_string()),
+            module_visibility: syn::parse_str("pub").unwrap(),
+            selection_metadata: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_behaves(
+        "selection_metadata_behavior",
+        &format!("pub struct Heroes;\n{}", generated),
+        r#"
+use selection_metadata_behavior::heroes::{SelectedField, SELECTION};
+
+fn describe(fields: &[SelectedField]) -> Vec<String> {
+    fields
+        .iter()
+        .map(|field| {
+            format!(
+                "{}{}: {} on {} {:?}",
+                field.alias.map(|alias| format!("{}: ", alias)).unwrap_or_default(),
+                field.name,
+                field.type_,
+                field.parent_type,
+                describe(field.fields)
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn selection_constant_describes_the_selected_fields() {
+    assert_eq!(
+        describe(SELECTION),
+        [
+            "hero: Character on Query [\"__typename: String! on Character []\", \
+             \"heroName: name: String! on Character []\", \
+             \"primaryFunction: String on Droid []\"]"
+        ]
+    );
+}
+"#,
+    );
+}
@@ -0,0 +1,99 @@
+use super::{assert_behaves, generate};
+use syn;
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+scalar AccessToken
+
+type Query {
+  viewer: User
+}
+
+type User {
+  name: String!
+  email: String
+  token: AccessToken!
+}
+"#;
+
+fn options(sensitive_fields: Option<&str>) -> GraphQLClientDeriveOptions {
+    GraphQLClientDeriveOptions {
+        operation_name: Some("Viewer".to_string()),
+        struct_name: Some("Viewer".to_string()),
+        additional_derives: Some("Debug".to_string()),
+        sensitive_fields: sensitive_fields.map(String::from),
+        pretty_print: true,
+        module_visibility: syn::parse_str("pub").unwrap(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn sensitive_directive_masks_the_field_and_is_not_sent() {
+    let generated = generate(
+        "sensitive_fields_directive",
+        SCHEMA,
+        "query Viewer { viewer { name email @sensitive } }",
+        options(None),
+    )
+    .unwrap();
+
+    assert_behaves(
+        "sensitive_fields_directive_behavior",
+        &format!("pub struct Viewer;\n{}", generated),
+        r##"
+use sensitive_fields_directive_behavior::viewer::{ResponseData, QUERY};
+
+#[test]
+fn the_field_is_masked_and_not_sent() {
+    let data: ResponseData = serde_json::from_str(
+        r#"{"viewer": {"name": "Luke", "email": "luke@example.com"}}"#,
+    )
+    .unwrap();
+
+    let debug = format!("{:?}", data);
+    let pretty = data.to_string();
+    assert!(debug.contains(r#"name: "Luke", email: [redacted]"#), "{}", debug);
+    assert!(pretty.contains("email: [redacted]\n"), "{}", pretty);
+    assert!(!debug.contains("luke@") && !pretty.contains("luke@"));
+    assert!(!QUERY.contains("@sensitive"));
+}
+"##,
+    );
+}
+
+#[test]
+fn sensitive_fields_option_masks_types_and_fields() {
+    let generated = generate(
+        "sensitive_fields_option",
+        SCHEMA,
+        "query Viewer { viewer { name email token } }",
+        options(Some("AccessToken, User.email")),
+    )
+    .unwrap();
+
+    // The response data keeps its derived `Debug`.
+    assert_behaves(
+        "sensitive_fields_option_behavior",
+        &format!(
+            "pub struct Viewer;\n#[derive(::serde_derive::Deserialize, ::std::fmt::Debug)]\npub struct AccessToken(::std::string::String);\n{}",
+            generated
+        ),
+        r##"
+use sensitive_fields_option_behavior::viewer::ResponseData;
+
+#[test]
+fn types_and_fields_are_masked() {
+    let data: ResponseData = serde_json::from_str(
+        r#"{"viewer": {"name": "Luke", "email": "luke@example.com", "token": "secret"}}"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        format!("{:?}", data),
+        r#"ResponseData { viewer: Some(ViewerViewer { name: "Luke", email: [redacted], token: [redacted] }) }"#
+    );
+}
+"##,
+    );
+}
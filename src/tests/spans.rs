@@ -0,0 +1,45 @@
+use super::generate;
+use proc_macro2::Span;
+use spans::respan;
+use std::path::Path;
+use {generate_module_token_stream, GraphQLClientDeriveOptions};
+
+#[test]
+fn generated_items_keep_their_code_with_the_attribute_span() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+    let generated = generate_module_token_stream(
+        root.join("star_wars_query.graphql"),
+        &root.join("star_wars_schema.graphql"),
+        Some(GraphQLClientDeriveOptions {
+            module_name: Some("star_wars_query".to_string()),
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+
+    assert_eq!(
+        respan(generated.clone(), Span::call_site()).to_string(),
+        generated.to_string()
+    );
+}
+
+#[test]
+fn argument_diagnostics_point_at_the_query_file() {
+    let error = generate(
+        "spans",
+        include_str!("star_wars_schema.graphql"),
+        "query StarWarsQuery {\n  hero(episode: JEDI, planet: 3) { name }\n}\n",
+        GraphQLClientDeriveOptions {
+            module_name: Some("star_wars_query".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap_err()
+    .to_string();
+
+    let path = ::std::env::temp_dir()
+        .join("graphql_client_spans/query.graphql")
+        .display()
+        .to_string();
+    assert!(error.starts_with(&format!("Invalid arguments in {}:\n{}:2:", path, path)));
+}
@@ -0,0 +1,191 @@
+use super::{assert_behaves, generate};
+use syn;
+use union_mode::UnionMode;
+use GraphQLClientDeriveOptions;
+
+const SCHEMA: &str = r#"
+type Query {
+  search: [SearchResult!]!
+}
+
+union SearchResult = User | Team
+
+type User {
+  name: String
+}
+
+type Team {
+  name: String
+}
+"#;
+
+const INTERFACE_SCHEMA: &str = r#"
+type Query {
+  nodes: [Node!]!
+}
+
+interface Node {
+  id: ID!
+}
+
+type User implements Node {
+  id: ID!
+  name: String
+}
+
+type Team implements Node {
+  id: ID!
+}
+"#;
+
+const QUERY: &str = "query Search { search { __typename ... on User { name } } }";
+
+fn options(union_mode: UnionMode) -> GraphQLClientDeriveOptions {
+    GraphQLClientDeriveOptions {
+        operation_name: Some("Search".to_string()),
+        struct_name: Some("Search".to_string()),
+        union_mode,
+        module_visibility: syn::parse_str("pub").unwrap(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn exhaustive_unions_have_no_fallback() {
+    let generated = generate(
+        "union_modes_exhaustive",
+        SCHEMA,
+        QUERY,
+        options(UnionMode::Exhaustive),
+    )
+    .unwrap();
+
+    assert_behaves(
+        "union_modes_exhaustive_behavior",
+        &format!("pub struct Search;\n{}", generated),
+        r##"
+use union_modes_exhaustive_behavior::search::{ResponseData, SearchSearch};
+
+#[test]
+fn unknown_members_are_rejected() {
+    let data: ResponseData = serde_json::from_str(
+        r#"{"search": [{"__typename": "User", "name": "Luke"}, {"__typename": "Team"}]}"#,
+    )
+    .unwrap();
+    match (&data.search[0], &data.search[1]) {
+        (SearchSearch::User(user), SearchSearch::Team) => assert_eq!(user.name.as_deref(), Some("Luke")),
+        _ => panic!("unexpected members"),
+    }
+
+    assert!(serde_json::from_str::<ResponseData>(r#"{"search": [{"__typename": "Bot"}]}"#).is_err());
+}
+"##,
+    );
+}
+
+#[test]
+fn non_exhaustive_unions_fall_back_to_unknown() {
+    let generated = generate(
+        "union_modes_non_exhaustive",
+        SCHEMA,
+        QUERY,
+        options(UnionMode::NonExhaustive),
+    )
+    .unwrap();
+
+    assert_behaves(
+        "union_modes_non_exhaustive_behavior",
+        &format!("pub struct Search;\n{}", generated),
+        r##"
+use union_modes_non_exhaustive_behavior::search::{ResponseData, SearchSearch};
+
+#[test]
+fn unknown_members_fall_back_to_unknown() {
+    let data: ResponseData = serde_json::from_str(
+        r#"{"search": [{"__typename": "User", "name": "Luke"}, {"__typename": "Team"}, {"__typename": "Bot", "name": "R2"}]}"#,
+    )
+    .unwrap();
+
+    match &data.search[0] {
+        SearchSearch::User(user) => assert_eq!(user.name.as_deref(), Some("Luke")),
+        _ => panic!("expected a user"),
+    }
+    assert!(matches!(data.search[1], SearchSearch::Team));
+    match &data.search[2] {
+        SearchSearch::Unknown { typename } => assert_eq!(typename, "Bot"),
+        _ => panic!("expected an unknown member"),
+    }
+}
+"##,
+    );
+
+    let conflicting = SCHEMA
+        .replace("User | Team", "User | Unknown")
+        .replace("type Team", "type Unknown");
+    let err = generate(
+        "union_modes_conflict",
+        &conflicting,
+        QUERY,
+        options(UnionMode::NonExhaustive),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The member Unknown of SearchResult conflicts with the fallback variant of non-exhaustive unions"
+    );
+}
+
+#[test]
+fn non_exhaustive_interfaces_fall_back_to_unknown() {
+    let query = "query Nodes { nodes { id __typename ... on User { name } } }";
+    let nodes = r#"{"nodes": [{"__typename": "User", "id": "1", "name": "Luke"}, {"__typename": "Team", "id": "2"}, {"__typename": "Bot", "id": "3"}]}"#;
+    let exhaustive = generate(
+        "union_modes_exhaustive_interface",
+        INTERFACE_SCHEMA,
+        query,
+        options(UnionMode::Exhaustive),
+    )
+    .unwrap();
+    let generated = generate(
+        "union_modes_non_exhaustive_interface",
+        INTERFACE_SCHEMA,
+        query,
+        options(UnionMode::NonExhaustive),
+    )
+    .unwrap();
+
+    assert_behaves(
+        "union_modes_interface_behavior",
+        &format!(
+            "pub struct Search;\npub mod exhaustive {{ pub struct Search; {} }}\n{}",
+            exhaustive, generated
+        ),
+        &format!(
+            r##"
+use union_modes_interface_behavior::exhaustive::search as exhaustive;
+use union_modes_interface_behavior::search::{{NodesNodesOn, ResponseData}};
+
+const NODES: &str = r#"{}"#;
+
+#[test]
+fn unknown_implementations_fall_back_to_unknown() {{
+    assert!(serde_json::from_str::<exhaustive::ResponseData>(NODES).is_err());
+
+    let data: ResponseData = serde_json::from_str(NODES).unwrap();
+    let ids: Vec<&str> = data.nodes.iter().map(|node| node.id.as_str()).collect();
+    assert_eq!(ids, ["1", "2", "3"]);
+    match &data.nodes[0].on {{
+        NodesNodesOn::User(user) => assert_eq!(user.name.as_deref(), Some("Luke")),
+        _ => panic!("expected a user"),
+    }}
+    assert!(matches!(data.nodes[1].on, NodesNodesOn::Team));
+    match &data.nodes[2].on {{
+        NodesNodesOn::Unknown {{ typename }} => assert_eq!(typename, "Bot"),
+        _ => panic!("expected an unknown implementation"),
+    }}
+}}
+"##,
+            nodes
+        ),
+    );
+}
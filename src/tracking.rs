@@ -0,0 +1,47 @@
+use failure;
+use proc_macro2::{Ident, Span, TokenStream};
+use std::path::PathBuf;
+
+/// Constants including the bytes of each file, so cargo rebuilds the generated code when one of them changes. Paths are made absolute, since `include_bytes!` resolves relative paths from the source file of the derive.
+pub(crate) fn tracked_files(mut paths: Vec<PathBuf>) -> Result<TokenStream, failure::Error> {
+    paths.sort();
+    paths.dedup();
+
+    let mut constants = TokenStream::new();
+    for (index, path) in paths.iter().enumerate() {
+        let absolute = path
+            .canonicalize()
+            .map_err(|err| format_err!("Could not resolve {}: {}", path.display(), err))?;
+        let absolute = absolute
+            .to_str()
+            .ok_or_else(|| format_err!("The path of {} is not valid UTF-8", absolute.display()))?;
+        let name = Ident::new(&format!("__TRACKED_FILE_{}", index), Span::call_site());
+        constants.extend(quote! {
+            const #name: &'static [u8] = ::std::include_bytes!(#absolute);
+        });
+    }
+    Ok(constants)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_included_once_by_absolute_path() {
+        let query =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/tests/star_wars_query.graphql");
+        let tracked = tracked_files(vec![query.clone(), query.clone()])
+            .unwrap()
+            .to_string();
+
+        assert_eq!(
+            tracked,
+            format!(
+                "const __TRACKED_FILE_0 : & 'static [ u8 ] = :: std :: include_bytes ! ( {:?} ) ;",
+                query.canonicalize().unwrap().to_str().unwrap()
+            )
+        );
+        assert!(tracked_files(vec![PathBuf::from("missing.graphql")]).is_err());
+    }
+}
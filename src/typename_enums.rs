@@ -0,0 +1,144 @@
+use failure;
+use graphql_parser::query;
+use proc_macro2::{Ident, Span, TokenStream};
+use schema::Schema;
+use std::collections::BTreeSet;
+use visitor::{self, FieldInfo, FragmentInfo, QueryVisitor, TypeKind};
+
+/// Collects the interfaces and unions the document selects or has fragments on.
+struct AbstractTypes<'a, 'schema: 'a> {
+    schema: &'a Schema<'schema>,
+    names: BTreeSet<String>,
+}
+
+impl<'a, 'schema> AbstractTypes<'a, 'schema> {
+    fn insert_if_abstract(&mut self, type_name: &str) {
+        if self.schema.interfaces.contains_key(type_name)
+            || self.schema.unions.contains_key(type_name)
+        {
+            self.names.insert(type_name.to_owned());
+        }
+    }
+}
+
+impl<'a, 'schema> QueryVisitor for AbstractTypes<'a, 'schema> {
+    fn enter_field(&mut self, field: &FieldInfo) {
+        if let Some(TypeKind::Interface) | Some(TypeKind::Union) = field.kind {
+            self.names.insert(field.named_type.to_owned());
+        }
+    }
+
+    fn enter_fragment(&mut self, fragment: &FragmentInfo) {
+        if let Some(on) = fragment.type_condition {
+            self.insert_if_abstract(on);
+        }
+    }
+
+    fn enter_inline_fragment(&mut self, fragment: &FragmentInfo) {
+        if let Some(on) = fragment.type_condition {
+            self.insert_if_abstract(on);
+        }
+    }
+}
+
+/// A `{Type}Typename` enum of the possible `__typename` values of each interface and union used in the document, with `as_str()` and `FromStr`, so code branching on type names needs no string literals. Generated once per module. The enums list every possible type the schema declares, since objects the document never selects on can still be returned.
+pub(crate) fn typename_enums(
+    schema: &Schema,
+    query: &query::Document,
+) -> Result<TokenStream, failure::Error> {
+    let mut abstract_types = AbstractTypes {
+        schema,
+        names: BTreeSet::new(),
+    };
+    visitor::visit_document(query, schema, &mut abstract_types)?;
+
+    let enums = abstract_types.names.iter().map(|type_name| {
+        let possible_types: BTreeSet<&str> = match schema.unions.get(type_name.as_str()) {
+            Some(union) => union.variants.iter().cloned().collect(),
+            None => schema.interfaces[type_name.as_str()]
+                .implemented_by
+                .iter()
+                .cloned()
+                .collect(),
+        };
+        typename_enum(type_name, &possible_types)
+    });
+
+    Ok(quote!(#(#enums)*))
+}
+
+fn typename_enum(type_name: &str, possible_types: &BTreeSet<&str>) -> TokenStream {
+    let name = Ident::new(&format!("{}Typename", type_name), Span::call_site());
+    let doc = format!("The possible `__typename` values of `{}`.", type_name);
+    let variants: Vec<Ident> = possible_types
+        .iter()
+        .map(|possible_type| Ident::new(possible_type, Span::call_site()))
+        .collect();
+    let variant_list = &variants;
+    let as_str_arms = variants
+        .iter()
+        .zip(possible_types)
+        .map(|(variant, typename)| quote!(#name::#variant => #typename,));
+    let from_str_arms = variants.iter().zip(possible_types).map(
+        |(variant, typename)| quote!(#typename => ::std::result::Result::Ok(#name::#variant),),
+    );
+
+    quote! {
+        #[doc = #doc]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::marker::Copy, ::std::cmp::PartialEq, ::std::cmp::Eq, ::std::hash::Hash)]
+        pub enum #name {
+            #(#variant_list,)*
+        }
+
+        impl #name {
+            /// The `__typename` of the type.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    #(#as_str_arms)*
+                }
+            }
+        }
+
+        impl ::std::str::FromStr for #name {
+            /// The unknown `__typename`.
+            type Err = ::std::string::String;
+
+            fn from_str(typename: &str) -> ::std::result::Result<Self, Self::Err> {
+                match typename {
+                    #(#from_str_arms)*
+                    _ => ::std::result::Result::Err(::std::string::ToString::to_string(typename)),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphql_parser::{parse_query, parse_schema};
+
+    #[test]
+    fn abstract_types_of_the_document_get_typename_enums() {
+        let schema = parse_schema(
+            r#"
+            type Query { node: Node search: [SearchResult] }
+            interface Node { id: ID! }
+            union SearchResult = User | Team
+            type User implements Node { id: ID! }
+            type Team implements Node { id: ID! }
+            "#,
+        )
+        .unwrap();
+        let schema = Schema::from(&schema);
+        let query = parse_query("{ node { ... on User { id } } }").unwrap();
+
+        let generated = typename_enums(&schema, &query).unwrap().to_string();
+
+        assert!(generated.contains("pub enum NodeTypename { Team , User , }"));
+        assert!(generated.contains(
+            "match self { NodeTypename :: Team => \"Team\" , NodeTypename :: User => \"User\" , }"
+        ));
+        assert!(!generated.contains("SearchResultTypename"));
+    }
+}
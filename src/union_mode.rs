@@ -0,0 +1,38 @@
+/// How the enums generated for unions and interfaces deal with members added to the schema after code generation.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum UnionMode {
+    /// One variant per member of the union, without a fallback (default). A response with a new member fails to deserialize, and regenerating the code breaks the matches that do not handle it, for strict clients.
+    #[default]
+    Exhaustive,
+    /// An `Unknown { typename }` variant for the members missing from the schema, on a `#[non_exhaustive]` enum, so responses with new members still deserialize and downstream matches keep compiling when the schema grows. The enums are (de)serialized through `serde_json`.
+    NonExhaustive,
+}
+
+impl ::std::str::FromStr for UnionMode {
+    type Err = ::failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "exhaustive" => Ok(UnionMode::Exhaustive),
+            "non-exhaustive" => Ok(UnionMode::NonExhaustive),
+            other => Err(format_err!(
+                "Invalid union mode: `{}`. Valid values are `exhaustive` and `non-exhaustive`.",
+                other
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn union_modes_parse() {
+        assert_eq!(
+            " non-exhaustive ".parse::<UnionMode>().unwrap(),
+            UnionMode::NonExhaustive
+        );
+        assert!("open".parse::<UnionMode>().is_err());
+    }
+}
@@ -1,9 +1,13 @@
 use failure;
+use hooks::ItemKind;
+use json_validation::SerdeAttributes;
 use proc_macro2::{Ident, Span, TokenStream};
-use query::QueryContext;
+use query::{Expansion, Nested, QueryContext};
 use selection::Selection;
 use std::cell::Cell;
 use std::collections::BTreeSet;
+use syn;
+use union_mode::UnionMode;
 
 /// A GraphQL union (simplified schema representation).
 ///
@@ -23,28 +27,149 @@ enum UnionError {
     UnknownType { ty: String },
     #[fail(display = "Missing __typename in selection for {}", union_name)]
     MissingTypename { union_name: String },
+    #[fail(
+        display = "The member {} of {} conflicts with the fallback variant of non-exhaustive unions",
+        ty, union_name
+    )]
+    FallbackConflict { ty: String, union_name: String },
 }
 
-type UnionVariantResult<'selection> =
-    Result<(Vec<TokenStream>, Vec<TokenStream>, Vec<&'selection str>), failure::Error>;
+/// The variant of non-exhaustive union and interface enums for the members missing from the schema.
+pub(crate) const FALLBACK_VARIANT: &str = "Unknown";
+
+/// The fallback variant of non-exhaustive union and interface enums, holding the `__typename` of the member.
+pub(crate) fn fallback_variant() -> TokenStream {
+    let fallback = Ident::new(FALLBACK_VARIANT, Span::call_site());
+    quote! {
+        /// A member added to the schema after the code was generated. The fields shared by the members of an interface are on the struct holding the enum.
+        #fallback { typename: ::std::string::String }
+    }
+}
+
+/// The definition of a union or interface enum tagged with `__typename` and ending with the fallback variant, with the serde derives left out, and its `Deserialize` and `Serialize` implementations. serde only falls back to unit variants, so the variant is looked up by hand: known members are deserialized like the derive does, the others keep their `__typename`.
+pub(crate) fn fallback_enum(definition: &TokenStream) -> TokenStream {
+    let input: syn::DeriveInput =
+        syn::parse2(definition.clone()).expect("generated type definition");
+    let name = &input.ident;
+    let vis = &input.vis;
+    let tag = SerdeAttributes::from_attributes(&input.attrs)
+        .tag
+        .expect("tagged union enum");
+    let variants = match input.data {
+        syn::Data::Enum(ref data) => &data.variants,
+        _ => panic!("union enum"),
+    };
+    let is_serde = |attr: &syn::Attribute| attr.path.is_ident("serde");
+    let derives_serialize = input
+        .attrs
+        .iter()
+        .any(|attr| attr.path.is_ident("derive") && attr.tts.to_string().contains("Serialize"));
+    let attrs = input
+        .attrs
+        .iter()
+        .filter(|attr| !is_serde(attr))
+        .map(|attr| ::redaction::without_derives(attr, &["Serialize", "Deserialize"]));
+    let definition_variants = variants.iter().map(|variant| {
+        let mut variant = variant.clone();
+        variant.attrs.retain(|attr| !is_serde(attr));
+        variant
+    });
+
+    let deserialize_arms = variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+        let typename = variant_name.to_string();
+        match variant.fields {
+            syn::Fields::Unit => {
+                quote!(#typename => ::std::result::Result::Ok(#name::#variant_name),)
+            }
+            syn::Fields::Unnamed(_) => quote! {
+                #typename => ::serde_json::from_value(::serde_json::Value::Object(object))
+                    .map(#name::#variant_name)
+                    .map_err(<D::Error as ::serde::de::Error>::custom),
+            },
+            syn::Fields::Named(_) => {
+                quote!(_ => ::std::result::Result::Ok(#name::#variant_name { typename }),)
+            }
+        }
+    });
+    let serialize = if derives_serialize {
+        let serialize_arms = variants.iter().map(|variant| {
+            let variant_name = &variant.ident;
+            let typename = variant_name.to_string();
+            match variant.fields {
+                syn::Fields::Unit => quote!(#name::#variant_name => (#typename, ::serde_json::Map::new()),),
+                syn::Fields::Unnamed(_) => quote! {
+                    #name::#variant_name(ref fields) => match ::serde_json::to_value(fields).map_err(<S::Error as ::serde::ser::Error>::custom)? {
+                        ::serde_json::Value::Object(object) => (#typename, object),
+                        _ => (#typename, ::serde_json::Map::new()),
+                    },
+                },
+                syn::Fields::Named(_) => quote!(#name::#variant_name { ref typename } => (typename.as_str(), ::serde_json::Map::new()),),
+            }
+        });
+        quote! {
+            #[allow(deprecated)]
+            impl ::serde::Serialize for #name {
+                fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+                    let (typename, mut object) = match *self {
+                        #(#serialize_arms)*
+                    };
+                    object.insert(
+                        <::std::string::String as ::std::convert::From<&str>>::from(#tag),
+                        ::serde_json::Value::String(<::std::string::String as ::std::convert::From<&str>>::from(typename)),
+                    );
+                    ::serde::Serialize::serialize(&object, serializer)
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    quote! {
+        #(#attrs)*
+        #vis enum #name {
+            #(#definition_variants,)*
+        }
+
+        #[allow(deprecated)]
+        impl<'de> ::serde::Deserialize<'de> for #name {
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+                let mut object = <::serde_json::Map<::std::string::String, ::serde_json::Value> as ::serde::Deserialize>::deserialize(deserializer)?;
+                let typename = match object.remove(#tag) {
+                    ::std::option::Option::Some(::serde_json::Value::String(typename)) => typename,
+                    _ => return ::std::result::Result::Err(<D::Error as ::serde::de::Error>::missing_field(#tag)),
+                };
+                match typename.as_str() {
+                    #(#deserialize_arms)*
+                }
+            }
+        }
+
+        #serialize
+    }
+}
+
+type UnionVariantResult<'query> =
+    Result<(Vec<TokenStream>, Vec<Nested<'query>>, Vec<&'query str>), failure::Error>;
 
 /// Returns a triple.
 ///
 /// - The first element is the union variants to be inserted directly into the `enum` declaration.
-/// - The second is the structs for each variant's sub-selection
+/// - The second is the selection of each variant, to expand into its struct
 /// - The last one contains which fields have been selected on the union, so we can make the enum exhaustive by complementing with those missing.
-pub(crate) fn union_variants<'selection>(
-    selection: &'selection Selection,
-    context: &'selection QueryContext<'selection, 'selection>,
+pub(crate) fn union_variants<'query>(
+    selection: &Selection<'query>,
+    context: &QueryContext<'query, '_>,
     prefix: &str,
     selection_on: &str,
-) -> UnionVariantResult<'selection> {
+) -> UnionVariantResult<'query> {
     let selection = selection.selected_variants_on_union(context, selection_on)?;
     let mut used_variants: Vec<&str> = selection.keys().cloned().collect();
-    let mut children_definitions = Vec::with_capacity(selection.len());
+    let mut children = Vec::with_capacity(selection.len());
     let mut variants = Vec::with_capacity(selection.len());
 
-    for (on, fields) in selection.iter() {
+    for (on, fields) in selection {
         let variant_name = Ident::new(&on, Span::call_site());
         used_variants.push(on);
 
@@ -52,43 +177,36 @@ pub(crate) fn union_variants<'selection>(
 
         let variant_type = Ident::new(&new_prefix, Span::call_site());
 
-        let field_object_type = context
-            .schema
-            .objects
-            .get(on)
-            .map(|_f| context.maybe_expand_field(&on, fields, &new_prefix));
-        let field_interface = context
-            .schema
-            .interfaces
-            .get(on)
-            .map(|_f| context.maybe_expand_field(&on, fields, &new_prefix));
-        let field_union_type = context
-            .schema
-            .unions
-            .get(on)
-            .map(|_f| context.maybe_expand_field(&on, fields, &new_prefix));
-
-        match field_object_type.or(field_interface).or(field_union_type) {
-            Some(tokens) => children_definitions.push(tokens?),
-            None => Err(UnionError::UnknownType { ty: on.to_string() })?,
-        };
+        let schema = context.schema;
+        if !schema.objects.contains_key(on)
+            && !schema.interfaces.contains_key(on)
+            && !schema.unions.contains_key(on)
+        {
+            Err(UnionError::UnknownType { ty: on.to_string() })?;
+        }
+        children.push(Nested::Selection {
+            on: on.to_owned(),
+            selection: fields,
+            prefix: new_prefix,
+            key: format!("... on {}", on),
+        });
 
         variants.push(quote! {
             #variant_name(#variant_type)
         })
     }
 
-    Ok((variants, children_definitions, used_variants))
+    Ok((variants, children, used_variants))
 }
 
 impl<'schema> GqlUnion<'schema> {
-    /// Returns the code to deserialize this union in the response given the query selection.
-    pub(crate) fn response_for_selection(
+    /// The enum for the selection, and the selections on its variants. See [QueryContext::expand_nested].
+    pub(crate) fn expand_selection<'query>(
         &self,
-        query_context: &QueryContext,
-        selection: &Selection,
+        query_context: &QueryContext<'query, '_>,
+        selection: &Selection<'query>,
         prefix: &str,
-    ) -> Result<TokenStream, failure::Error> {
+    ) -> Expansion<'query> {
         let typename_field = selection.extract_typename(query_context);
 
         if typename_field.is_none() {
@@ -100,28 +218,64 @@ impl<'schema> GqlUnion<'schema> {
         let struct_name = Ident::new(prefix, Span::call_site());
         let derives = query_context.response_derives();
 
-        let (mut variants, children_definitions, used_variants) =
+        let (mut variants, children, used_variants) =
             union_variants(selection, query_context, prefix, &self.name)?;
 
         variants.extend(
             self.variants
                 .iter()
-                .filter(|v| used_variants.iter().find(|a| a == v).is_none())
+                .filter(|v| !used_variants.contains(v))
                 .map(|v| {
                     let v = Ident::new(v, Span::call_site());
                     quote!(#v)
                 }),
         );
 
-        Ok(quote! {
-            #(#children_definitions)*
+        let non_exhaustive = match query_context.union_mode {
+            UnionMode::Exhaustive => quote!(),
+            UnionMode::NonExhaustive => {
+                if self.variants.contains(FALLBACK_VARIANT) {
+                    Err(UnionError::FallbackConflict {
+                        ty: FALLBACK_VARIANT.to_owned(),
+                        union_name: self.name.to_owned(),
+                    })?;
+                }
+                variants.push(fallback_variant());
+                quote!(#[non_exhaustive])
+            }
+        };
 
+        let definition = quote! {
             #derives
             #[serde(tag = "__typename")]
+            #non_exhaustive
             pub enum #struct_name {
                 #(#variants),*
             }
-        })
+        };
+        let mock = ::mocks::mock_impl(query_context, &definition);
+        let default = ::defaults::default_impl(query_context, &definition, self.name);
+        let validation = ::json_validation::validation_impl(query_context, &definition);
+        let pretty_print = ::pretty_print::pretty_print_impl(query_context, &definition);
+        let definition = match query_context.union_mode {
+            UnionMode::Exhaustive => definition,
+            UnionMode::NonExhaustive => fallback_enum(&definition),
+        };
+
+        let tokens = quote! {
+            #definition
+
+            #mock
+
+            #default
+
+            #validation
+
+
+            #pretty_print
+        };
+        let tokens = query_context.hooked(ItemKind::Union, self.name, prefix, tokens);
+        Ok((tokens, children))
     }
 }
 
@@ -143,6 +297,8 @@ mod tests {
                     alias: None,
                     name: "firstName",
                     fields: Selection(vec![]),
+                    is_client: false,
+                    is_sensitive: false,
                 })]),
             }),
             SelectionItem::InlineFragment(SelectionInlineFragment {
@@ -151,6 +307,8 @@ mod tests {
                     alias: None,
                     name: "title",
                     fields: Selection(vec![]),
+                    is_client: false,
+                    is_sensitive: false,
                 })]),
             }),
         ];
@@ -219,7 +377,9 @@ mod tests {
         );
         let context = QueryContext::new_empty(&schema);
 
-        let result = union.response_for_selection(&context, &selection, &prefix);
+        let result = union
+            .expand_selection(&context, &selection, &prefix)
+            .and_then(|expansion| context.expand_nested(&prefix, expansion));
 
         assert!(result.is_err());
 
@@ -236,6 +396,8 @@ mod tests {
                 alias: None,
                 name: "__typename",
                 fields: Selection(vec![]),
+                is_client: false,
+                is_sensitive: false,
             }),
             SelectionItem::InlineFragment(SelectionInlineFragment {
                 on: "User",
@@ -243,6 +405,8 @@ mod tests {
                     alias: None,
                     name: "firstName",
                     fields: Selection(vec![]),
+                    is_client: false,
+                    is_sensitive: false,
                 })]),
             }),
             SelectionItem::InlineFragment(SelectionInlineFragment {
@@ -251,6 +415,8 @@ mod tests {
                     alias: None,
                     name: "title",
                     fields: Selection(vec![]),
+                    is_client: false,
+                    is_sensitive: false,
                 })]),
             }),
         ];
@@ -265,7 +431,9 @@ mod tests {
             is_required: false.into(),
         };
 
-        let result = union.response_for_selection(&context, &selection, &prefix);
+        let result = union
+            .expand_selection(&context, &selection, &prefix)
+            .and_then(|expansion| context.expand_nested(&prefix, expansion));
 
         assert!(result.is_err());
 
@@ -336,7 +504,9 @@ mod tests {
 
         let context = QueryContext::new_empty(&schema);
 
-        let result = union.response_for_selection(&context, &selection, &prefix);
+        let result = union
+            .expand_selection(&context, &selection, &prefix)
+            .and_then(|expansion| context.expand_nested(&prefix, expansion));
 
         println!("{:?}", result);
 
@@ -345,11 +515,11 @@ mod tests {
         assert_eq!(
             result.unwrap().to_string(),
             vec![
-                "# [ derive ( Deserialize ) ] ",
-                "pub struct MeowOnOrganization { pub title : String , } ",
-                "# [ derive ( Deserialize ) ] ",
-                "pub struct MeowOnUser { # [ serde ( rename = \"firstName\" ) ] pub first_name : String , } ",
-                "# [ derive ( Deserialize ) ] ",
+                "# [ derive ( :: serde_derive :: Deserialize ) ] ",
+                "pub struct MeowOnOrganization { pub title : :: std :: string :: String , } ",
+                "# [ derive ( :: serde_derive :: Deserialize ) ] ",
+                "pub struct MeowOnUser { # [ serde ( rename = \"firstName\" ) ] pub first_name : :: std :: string :: String , } ",
+                "# [ derive ( :: serde_derive :: Deserialize ) ] ",
                 "# [ serde ( tag = \"__typename\" ) ] ",
                 "pub enum Meow { Organization ( MeowOnOrganization ) , User ( MeowOnUser ) }",
             ].into_iter()
@@ -0,0 +1,1098 @@
+use client_fields::{is_client_field, strip_client_fields};
+use codegen;
+use constants::*;
+use deprecation::DeprecationStrategy;
+use field_type::FieldType;
+use graphql_parser::query;
+use graphql_parser::Pos;
+use itertools::Itertools;
+use objects::GqlObjectField;
+use operations::Operation;
+use schema::Schema;
+use shared::check_selection_set;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use GraphQLClientDeriveOptions;
+
+/// A position in a query document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// One-based line number.
+    pub line: usize,
+    /// One-based column number.
+    pub column: usize,
+}
+
+impl From<Pos> for Position {
+    fn from(pos: Pos) -> Position {
+        Position {
+            line: pos.line,
+            column: pos.column,
+        }
+    }
+}
+
+/// A problem found in a query document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Where the problem is, when it can be attributed to a specific part of the query.
+    pub position: Option<Position>,
+    /// The description of the problem.
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn new<M: Into<String>>(position: Pos, message: M) -> Diagnostic {
+        Diagnostic {
+            position: Some(position.into()),
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn without_position<M: Into<String>>(message: M) -> Diagnostic {
+        Diagnostic {
+            position: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl ::std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{}:{}: {}", position.line, position.column, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Check the query document `query` against the schema at `schema_path`, and report all the problems that would prevent code generation.
+///
+/// An empty result means the derive will succeed for this query.
+pub fn validate(query: &str, schema_path: &Path) -> Vec<Diagnostic> {
+    let query = match ::string_literals::parse_query(query) {
+        Ok(query) => query,
+        Err(err) => return vec![Diagnostic::without_position(err.to_string())],
+    };
+    let parsed_schema = match ::load_schema(schema_path, None) {
+        Ok(parsed_schema) => parsed_schema,
+        Err(err) => return vec![Diagnostic::without_position(err.to_string())],
+    };
+    let schema = Schema::from(&*parsed_schema);
+
+    validate_document(&query, &schema)
+}
+
+pub(crate) fn validate_document(query: &query::Document, schema: &Schema) -> Vec<Diagnostic> {
+    let mut validator = Validator::new(query, schema);
+
+    for definition in &query.definitions {
+        match definition {
+            query::Definition::Operation(operation) => validator.validate_operation(operation),
+            query::Definition::Fragment(fragment) => validator.validate_fragment(fragment),
+        }
+    }
+
+    // Everything we know how to check is fine, now make sure code generation itself succeeds.
+    if validator.diagnostics.is_empty() {
+        let query = strip_client_fields(query);
+        let operations = codegen::all_operations(&query);
+        let multiple_operations = operations.len() > 1;
+
+        for operation in &operations {
+            if let Err(err) = codegen::response_for_query(
+                schema,
+                &query,
+                operation,
+                &GraphQLClientDeriveOptions {
+                    deprecation_strategy: Some(DeprecationStrategy::Allow),
+                    ..Default::default()
+                },
+                multiple_operations,
+            ) {
+                validator
+                    .diagnostics
+                    .push(Diagnostic::without_position(format!(
+                        "{}: {}",
+                        operation.name, err
+                    )));
+            }
+        }
+    }
+
+    validator.diagnostics
+}
+
+struct Validator<'query, 'schema: 'query> {
+    schema: &'query Schema<'schema>,
+    fragments: BTreeMap<&'query str, &'query query::FragmentDefinition>,
+    /// The name and variables of the operation being validated. Fragments can be used by several operations, their variables are checked with each operation by [argument_diagnostics].
+    variables: Option<(&'query str, &'query [query::VariableDefinition])>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// The problems with the field arguments of the operations in a query document, including those of the fragments they use. Code generation rejects them, rather than letting the server do it at runtime.
+pub(crate) fn argument_diagnostics(query: &query::Document, schema: &Schema) -> Vec<Diagnostic> {
+    let mut validator = Validator::new(query, schema);
+
+    for definition in &query.definitions {
+        if let query::Definition::Operation(operation) = definition {
+            validator.check_operation_arguments(operation);
+        }
+    }
+
+    validator.diagnostics
+}
+
+impl<'query, 'schema> Validator<'query, 'schema> {
+    fn new(query: &'query query::Document, schema: &'query Schema<'schema>) -> Self {
+        Validator {
+            schema,
+            fragments: query
+                .definitions
+                .iter()
+                .filter_map(|definition| match definition {
+                    query::Definition::Fragment(fragment) => {
+                        Some((fragment.name.as_str(), fragment))
+                    }
+                    query::Definition::Operation(_) => None,
+                })
+                .collect(),
+            variables: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn error<M: Into<String>>(&mut self, position: Pos, message: M) {
+        self.diagnostics.push(Diagnostic::new(position, message));
+    }
+
+    fn validate_operation(&mut self, operation: &'query query::OperationDefinition) {
+        let (position, name, variables, directives, selection_set) = match operation {
+            query::OperationDefinition::Query(q) => (
+                q.position,
+                &q.name,
+                &q.variable_definitions,
+                (&q.directives, "QUERY"),
+                &q.selection_set,
+            ),
+            query::OperationDefinition::Mutation(m) => (
+                m.position,
+                &m.name,
+                &m.variable_definitions,
+                (&m.directives, "MUTATION"),
+                &m.selection_set,
+            ),
+            query::OperationDefinition::Subscription(s) => (
+                s.position,
+                &s.name,
+                &s.variable_definitions,
+                (&s.directives, "SUBSCRIPTION"),
+                &s.selection_set,
+            ),
+            query::OperationDefinition::SelectionSet(selection_set) => {
+                self.error(selection_set.span.0, SELECTION_SET_AT_ROOT.trim());
+                return;
+            }
+        };
+
+        if name.is_none() {
+            self.error(position, "Operations in queries must be named.");
+            return;
+        }
+
+        self.validate_directives(directives.0, directives.1);
+
+        let operation = Operation::from(operation);
+
+        for variable in variables {
+            let type_name = operation
+                .variables
+                .iter()
+                .find(|v| v.name == variable.name)
+                .map(|v| v.ty.inner_name_str())
+                .expect("variable is part of the operation");
+
+            if !self.schema.contains_scalar(type_name)
+                && !self.schema.enums.contains_key(type_name)
+                && !self.schema.inputs.contains_key(type_name)
+            {
+                self.error(
+                    variable.position,
+                    format!(
+                        "Unknown type `{}` for variable `${}`. Variables must be scalars, enums or input objects.",
+                        type_name, variable.name
+                    ),
+                );
+            }
+        }
+
+        if operation.is_subscription() && selection_set.items.len() > 1 {
+            self.error(
+                position,
+                MULTIPLE_SUBSCRIPTION_FIELDS_ERROR
+                    .trim()
+                    .lines()
+                    .next()
+                    .expect("error message"),
+            );
+        }
+
+        let root_name = match operation.checked_root_name(self.schema) {
+            Ok(root_name) => root_name,
+            Err(err) => {
+                self.error(position, err.to_string());
+                return;
+            }
+        };
+
+        self.variables = name
+            .as_ref()
+            .map(|name| (name.as_str(), variables.as_slice()));
+        self.validate_selection_set(root_name, position, selection_set, true);
+        self.variables = None;
+    }
+
+    fn validate_fragment(&mut self, fragment: &'query query::FragmentDefinition) {
+        let query::TypeCondition::On(ref on) = fragment.type_condition;
+
+        self.validate_directives(&fragment.directives, "FRAGMENT_DEFINITION");
+
+        if self.fields_of(on).is_none() {
+            self.error(
+                fragment.position,
+                format!(
+                    "Fragment {} is defined on unknown type: {}",
+                    fragment.name, on
+                ),
+            );
+            return;
+        }
+
+        self.validate_selection_set(on, fragment.position, &fragment.selection_set, true);
+    }
+
+    /// The fields of an object, interface or union (which has no fields of its own). `None` if the type is not one of those.
+    fn fields_of(&self, type_name: &str) -> Option<&'query [GqlObjectField<'schema>]> {
+        let schema = self.schema;
+        schema
+            .fields_of(type_name)
+            .or_else(|| schema.unions.get(type_name).map(|_| &[][..]))
+    }
+
+    fn validate_selection_set(
+        &mut self,
+        on: &str,
+        position: Pos,
+        selection_set: &'query query::SelectionSet,
+        requires_typename: bool,
+    ) {
+        let fields = self.fields_of(on).unwrap_or(&[]);
+
+        let is_abstract =
+            self.schema.interfaces.contains_key(on) || self.schema.unions.contains_key(on);
+        if requires_typename
+            && is_abstract
+            && !self.selects_typename(selection_set, &mut BTreeSet::new())
+        {
+            self.error(
+                position,
+                format!("Missing __typename in selection on `{}`", on),
+            );
+        }
+
+        for item in &selection_set.items {
+            match item {
+                query::Selection::Field(field) => {
+                    self.validate_directives(&field.directives, "FIELD");
+
+                    // `@client` fields are resolved locally, the server schema does not know them.
+                    if field.name == TYPENAME_FIELD || is_client_field(&field.directives) {
+                        continue;
+                    }
+
+                    let schema_field = match self.schema.field(on, &field.name) {
+                        Some(schema_field) => schema_field,
+                        None => {
+                            self.error(
+                                field.position,
+                                format!(
+                                    "Could not find field `{}` on `{}`. Available fields: `{}`.",
+                                    field.name,
+                                    on,
+                                    fields.iter().map(|f| f.name).format("`, `")
+                                ),
+                            );
+                            continue;
+                        }
+                    };
+
+                    self.validate_arguments(on, field);
+
+                    let field_type = schema_field.type_.inner_name_str();
+                    if let Err(message) = check_selection_set(
+                        self.schema,
+                        on,
+                        &field.name,
+                        field_type,
+                        !field.selection_set.items.is_empty(),
+                    ) {
+                        self.error(field.position, message);
+                        continue;
+                    }
+
+                    if self.fields_of(field_type).is_some() {
+                        self.validate_selection_set(
+                            field_type,
+                            field.position,
+                            &field.selection_set,
+                            true,
+                        );
+                    }
+                }
+                query::Selection::FragmentSpread(spread) => {
+                    self.validate_directives(&spread.directives, "FRAGMENT_SPREAD");
+
+                    if !self.fragments.contains_key(spread.fragment_name.as_str()) {
+                        self.error(
+                            spread.position,
+                            format!("Unknown fragment: {}", spread.fragment_name),
+                        );
+                    }
+                }
+                query::Selection::InlineFragment(inline_fragment) => {
+                    self.validate_directives(&inline_fragment.directives, "INLINE_FRAGMENT");
+
+                    let on = match inline_fragment.type_condition {
+                        Some(query::TypeCondition::On(ref on)) => on.as_str(),
+                        None => on,
+                    };
+
+                    if self.fields_of(on).is_none() {
+                        self.error(
+                            inline_fragment.position,
+                            format!("Inline fragment on unknown type: {}", on),
+                        );
+                        continue;
+                    }
+
+                    // __typename is selected on the enclosing selection.
+                    self.validate_selection_set(
+                        on,
+                        inline_fragment.position,
+                        &inline_fragment.selection_set,
+                        false,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check the uses of the custom directives defined by the schema, at `location` (like `FIELD`). Directives unknown to the schema are left to code generation, like `@client`.
+    fn validate_directives(&mut self, directives: &'query [query::Directive], location: &str) {
+        for (index, directive) in directives.iter().enumerate() {
+            let definition = match self.schema.directives.get(directive.name.as_str()) {
+                Some(definition) => definition,
+                None => continue,
+            };
+
+            if !definition.locations.contains(&location) {
+                self.error(
+                    directive.position,
+                    format!(
+                        "Directive `@{}` cannot be used on {}. Allowed locations: {}.",
+                        directive.name,
+                        location,
+                        definition.locations.iter().format(", ")
+                    ),
+                );
+            }
+
+            if !definition.is_repeatable
+                && directives[..index]
+                    .iter()
+                    .any(|previous| previous.name == directive.name)
+            {
+                self.error(
+                    directive.position,
+                    format!(
+                        "Directive `@{}` is not repeatable, but it is used more than once here.",
+                        directive.name
+                    ),
+                );
+            }
+        }
+
+        self.check_directive_arguments(directives);
+    }
+
+    /// Check the argument values of directives against the arguments of `@skip`, `@include` and the custom directives. The variables used by other directives, like `@client`, must still be defined.
+    fn check_directive_arguments(&mut self, directives: &'query [query::Directive]) {
+        let schema = self.schema;
+
+        for directive in directives {
+            let definition = schema.directives.get(directive.name.as_str());
+
+            for (argument, value) in &directive.arguments {
+                let check = match (directive.name.as_str(), definition) {
+                    ("skip", _) | ("include", _) if argument == "if" => {
+                        self.check_value(value, &FieldType::Named("Boolean"), false)
+                    }
+                    (_, Some(definition)) => {
+                        match definition
+                            .arguments
+                            .iter()
+                            .find(|(name, _)| name == argument)
+                        {
+                            Some((_, type_)) => self.check_value(value, type_, false),
+                            None => {
+                                self.error(
+                                    directive.position,
+                                    format!(
+                                        "Unknown argument `{}` on directive `@{}`.",
+                                        argument, directive.name
+                                    ),
+                                );
+                                continue;
+                            }
+                        }
+                    }
+                    (_, None) => self.check_variables_are_defined(value),
+                };
+
+                if let Err(message) = check {
+                    self.error(
+                        directive.position,
+                        format!(
+                            "Argument `{}` of directive `@{}`: {}",
+                            argument, directive.name, message
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_operation_arguments(&mut self, operation: &'query query::OperationDefinition) {
+        let (name, variables, directives, selection_set) = match operation {
+            query::OperationDefinition::Query(q) => (
+                &q.name,
+                &q.variable_definitions,
+                &q.directives,
+                &q.selection_set,
+            ),
+            query::OperationDefinition::Mutation(m) => (
+                &m.name,
+                &m.variable_definitions,
+                &m.directives,
+                &m.selection_set,
+            ),
+            query::OperationDefinition::Subscription(s) => (
+                &s.name,
+                &s.variable_definitions,
+                &s.directives,
+                &s.selection_set,
+            ),
+            query::OperationDefinition::SelectionSet(_) => return,
+        };
+        let root_name = Operation::from(operation).root_name(self.schema);
+
+        self.variables = Some((name.as_deref().unwrap_or(""), variables.as_slice()));
+        self.check_directive_arguments(directives);
+        self.check_selection_set_arguments(root_name, selection_set, &mut BTreeSet::new());
+        self.variables = None;
+    }
+
+    fn check_selection_set_arguments(
+        &mut self,
+        on: &str,
+        selection_set: &'query query::SelectionSet,
+        visited_fragments: &mut BTreeSet<&'query str>,
+    ) {
+        for item in &selection_set.items {
+            match item {
+                query::Selection::Field(field) => {
+                    self.check_directive_arguments(&field.directives);
+                    if is_client_field(&field.directives) {
+                        continue;
+                    }
+                    self.validate_arguments(on, field);
+
+                    let field_type = self
+                        .schema
+                        .field(on, &field.name)
+                        .map(|f| f.type_.inner_name_str());
+                    if let Some(field_type) = field_type {
+                        self.check_selection_set_arguments(
+                            field_type,
+                            &field.selection_set,
+                            visited_fragments,
+                        );
+                    }
+                }
+                query::Selection::FragmentSpread(spread) => {
+                    self.check_directive_arguments(&spread.directives);
+                    if !visited_fragments.insert(spread.fragment_name.as_str()) {
+                        continue;
+                    }
+                    if let Some(fragment) =
+                        self.fragments.get(spread.fragment_name.as_str()).cloned()
+                    {
+                        let query::TypeCondition::On(ref on) = fragment.type_condition;
+                        self.check_directive_arguments(&fragment.directives);
+                        self.check_selection_set_arguments(
+                            on,
+                            &fragment.selection_set,
+                            visited_fragments,
+                        );
+                    }
+                }
+                query::Selection::InlineFragment(inline_fragment) => {
+                    self.check_directive_arguments(&inline_fragment.directives);
+                    let on = match inline_fragment.type_condition {
+                        Some(query::TypeCondition::On(ref on)) => on.as_str(),
+                        None => on,
+                    };
+                    self.check_selection_set_arguments(
+                        on,
+                        &inline_fragment.selection_set,
+                        visited_fragments,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check the arguments of a field against the arguments of `on.field` in the schema: they must be defined, have values of the right types, and the required ones must be there.
+    fn validate_arguments(&mut self, on: &str, field: &'query query::Field) {
+        let schema = self.schema;
+        let definitions = match schema.field_arguments(on, &field.name) {
+            Some(definitions) => definitions,
+            None => {
+                for (name, value) in &field.arguments {
+                    if let Err(message) = self.check_variables_are_defined(value) {
+                        self.error(
+                            field.position,
+                            format!(
+                                "Argument `{}` of `{}.{}`: {}",
+                                name, on, field.name, message
+                            ),
+                        );
+                    }
+                }
+                return;
+            }
+        };
+
+        for (name, value) in &field.arguments {
+            let check = match definitions.iter().find(|argument| argument.name == name) {
+                Some(argument) => self.check_value(value, &argument.type_, argument.has_default),
+                None if definitions.is_empty() => {
+                    Err("the field does not take any arguments.".to_string())
+                }
+                None => Err(format!(
+                    "it is not defined by the field. Available arguments: `{}`.",
+                    definitions
+                        .iter()
+                        .map(|argument| argument.name)
+                        .format("`, `")
+                )),
+            };
+
+            if let Err(message) = check {
+                self.error(
+                    field.position,
+                    format!(
+                        "Argument `{}` of `{}.{}`: {}",
+                        name, on, field.name, message
+                    ),
+                );
+            }
+        }
+
+        for argument in definitions {
+            if !argument.type_.is_optional()
+                && !argument.has_default
+                && field
+                    .arguments
+                    .iter()
+                    .all(|(name, _)| name != argument.name)
+            {
+                self.error(
+                    field.position,
+                    format!(
+                        "Missing required argument `{}` of type `{}` on `{}.{}`.",
+                        argument.name,
+                        argument.type_.to_graphql(),
+                        on,
+                        field.name
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Check that a value can be passed where the schema expects `expected`. `has_default` is whether the argument or input field has a default value, which lets nullable variables be used for non-null types.
+    fn check_value(
+        &self,
+        value: &query::Value,
+        expected: &FieldType,
+        has_default: bool,
+    ) -> Result<(), String> {
+        match (value, expected) {
+            (query::Value::Variable(name), _) => self.check_variable(name, expected, has_default),
+            (query::Value::Null, FieldType::Optional(_)) => Ok(()),
+            (query::Value::Null, _) => Err(format!(
+                "expected a value of type `{}`, found null.",
+                expected.to_graphql()
+            )),
+            (_, FieldType::Optional(inner)) => self.check_value(value, inner, false),
+            (query::Value::List(items), FieldType::Vector(inner)) => items
+                .iter()
+                .try_for_each(|item| self.check_value(item, inner, false)),
+            // A single value is coerced to a list with one item.
+            (_, FieldType::Vector(inner)) => self.check_value(value, inner, false),
+            (_, FieldType::Named(name)) => self.check_named_value(value, name),
+        }
+    }
+
+    fn check_named_value(&self, value: &query::Value, type_name: &str) -> Result<(), String> {
+        let matches = match (type_name, value) {
+            ("Int", query::Value::Int(_))
+            | ("Float", query::Value::Int(_))
+            | ("Float", query::Value::Float(_))
+            | ("String", query::Value::String(_))
+            | ("Boolean", query::Value::Boolean(_))
+            | ("ID", query::Value::String(_))
+            | ("ID", query::Value::Int(_)) => true,
+            ("Int", _) | ("Float", _) | ("String", _) | ("Boolean", _) | ("ID", _) => false,
+            _ => {
+                if let Some(enm) = self.schema.enums.get(type_name) {
+                    match value {
+                        query::Value::Enum(variant) => {
+                            if enm.variants.iter().any(|v| v.name == variant) {
+                                return Ok(());
+                            }
+                            return Err(format!(
+                                "`{}` is not a value of the enum `{}`. Possible values: `{}`.",
+                                variant,
+                                type_name,
+                                enm.variants.iter().map(|v| v.name).format("`, `")
+                            ));
+                        }
+                        _ => false,
+                    }
+                } else if let Some(input) = self.schema.inputs.get(type_name) {
+                    match value {
+                        query::Value::Object(fields) => {
+                            return self.check_input_object(fields, input);
+                        }
+                        _ => false,
+                    }
+                } else {
+                    // Custom scalars can be written as any literal.
+                    return self.check_variables_are_defined(value);
+                }
+            }
+        };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(format!("expected `{}`, found `{}`.", type_name, value))
+        }
+    }
+
+    fn check_input_object(
+        &self,
+        fields: &BTreeMap<String, query::Value>,
+        input: &::inputs::GqlInput,
+    ) -> Result<(), String> {
+        for (name, value) in fields {
+            match input.fields.get(name.as_str()) {
+                Some(field) => self.check_value(
+                    value,
+                    &field.type_,
+                    input.default_values.contains_key(name.as_str()),
+                )?,
+                None => {
+                    return Err(format!(
+                        "the input `{}` has no field `{}`.",
+                        input.name, name
+                    ))
+                }
+            }
+        }
+
+        let missing: BTreeSet<&str> = input
+            .fields
+            .values()
+            .filter(|field| {
+                !field.type_.is_optional()
+                    && !input.default_values.contains_key(field.name)
+                    && !fields.contains_key(field.name)
+            })
+            .map(|field| field.name)
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "missing required fields of the input `{}`: `{}`.",
+                input.name,
+                missing.iter().format("`, `")
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The definition of a variable of the operation being validated. `Ok(None)` outside of operations.
+    fn variable_definition(
+        &self,
+        name: &str,
+    ) -> Result<Option<&'query query::VariableDefinition>, String> {
+        let (operation, variables) = match self.variables {
+            Some(variables) => variables,
+            None => return Ok(None),
+        };
+
+        match variables.iter().find(|v| v.name == name) {
+            Some(definition) => Ok(Some(definition)),
+            None => Err(format!(
+                "the variable `${}` is not defined by the operation `{}`.",
+                name, operation
+            )),
+        }
+    }
+
+    /// Check that the variables used in a value, at any depth, are defined by the operation.
+    fn check_variables_are_defined(&self, value: &query::Value) -> Result<(), String> {
+        match value {
+            query::Value::Variable(name) => self.variable_definition(name).map(|_| ()),
+            query::Value::List(items) => items
+                .iter()
+                .try_for_each(|item| self.check_variables_are_defined(item)),
+            query::Value::Object(fields) => fields
+                .values()
+                .try_for_each(|value| self.check_variables_are_defined(value)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that a variable is defined by the operation, and that its type is allowed where `expected` is.
+    fn check_variable(
+        &self,
+        name: &str,
+        expected: &FieldType,
+        location_has_default: bool,
+    ) -> Result<(), String> {
+        let definition = match self.variable_definition(name)? {
+            Some(definition) => definition,
+            None => return Ok(()),
+        };
+
+        let variable_type = FieldType::from(&definition.var_type);
+        // Variables of unknown types are reported with the variable definitions.
+        let type_name = variable_type.inner_name_str();
+        if !self.schema.contains_scalar(type_name)
+            && !self.schema.enums.contains_key(type_name)
+            && !self.schema.inputs.contains_key(type_name)
+        {
+            return Ok(());
+        }
+
+        let variable_has_default = match definition.default_value {
+            Some(query::Value::Null) | None => false,
+            Some(_) => true,
+        };
+        // A nullable variable can be used for a non-null type if a default value takes over when it is null.
+        let variable_type = match variable_type {
+            FieldType::Optional(inner)
+                if !expected.is_optional() && (variable_has_default || location_has_default) =>
+            {
+                *inner
+            }
+            other => other,
+        };
+
+        if types_are_compatible(&variable_type, expected) {
+            Ok(())
+        } else {
+            Err(format!(
+                "the variable `${}` of type `{}` cannot be used where `{}` is expected.",
+                name,
+                definition.var_type,
+                expected.to_graphql()
+            ))
+        }
+    }
+
+    fn selects_typename(
+        &self,
+        selection_set: &'query query::SelectionSet,
+        visited_fragments: &mut BTreeSet<&'query str>,
+    ) -> bool {
+        selection_set.items.iter().any(|item| match item {
+            query::Selection::Field(field) => field.name == TYPENAME_FIELD,
+            query::Selection::FragmentSpread(spread) => {
+                visited_fragments.insert(spread.fragment_name.as_str())
+                    && self
+                        .fragments
+                        .get(spread.fragment_name.as_str())
+                        .map(|fragment| {
+                            self.selects_typename(&fragment.selection_set, visited_fragments)
+                        })
+                        .unwrap_or(false)
+            }
+            query::Selection::InlineFragment(_) => false,
+        })
+    }
+}
+
+/// Whether a variable of type `variable` can be used where `location` is expected: same named type and list wrapping, and non-null where `location` is.
+fn types_are_compatible(variable: &FieldType, location: &FieldType) -> bool {
+    match (variable, location) {
+        (FieldType::Optional(variable), FieldType::Optional(location)) => {
+            types_are_compatible(variable, location)
+        }
+        (FieldType::Optional(_), _) => false,
+        (variable, FieldType::Optional(location)) => types_are_compatible(variable, location),
+        (FieldType::Vector(variable), FieldType::Vector(location)) => {
+            types_are_compatible(variable, location)
+        }
+        (FieldType::Named(variable), FieldType::Named(location)) => variable == location,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphql_parser;
+
+    fn validate_star_wars(query: &str) -> Vec<String> {
+        let schema =
+            graphql_parser::parse_schema(include_str!("tests/star_wars_schema.graphql")).unwrap();
+        let schema = Schema::from(&schema);
+        let query = graphql_parser::parse_query(query).unwrap();
+
+        validate_document(&query, &schema)
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn valid_query_has_no_diagnostics() {
+        let diagnostics = validate_star_wars(
+            "query Hero($episode: Episode) { hero(episode: $episode) { __typename name } }",
+        );
+
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn client_fields_are_not_validated_against_the_schema() {
+        let diagnostics =
+            validate_star_wars("query Hero { hero { __typename name isFavorite @client } }");
+
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn all_problems_are_reported_with_positions() {
+        let diagnostics = validate_star_wars(
+            r#"
+query Hero($episode: Planet) {
+  hero(episode: $episode) {
+    name
+    height
+  }
+  droid(id: "2001") {
+    ...DroidFields
+    model
+  }
+}
+"#,
+        );
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                "2:12: Unknown type `Planet` for variable `$episode`. Variables must be scalars, enums or input objects.",
+                "3:3: Missing __typename in selection on `Character`",
+                "5:5: Could not find field `height` on `Character`. Available fields: `id`, `name`, `friends`, `friendsConnection`, `appearsIn`.",
+                "8:8: Unknown fragment: DroidFields",
+                "9:5: Could not find field `model` on `Droid`. Available fields: `__typename`, `id`, `name`, `friends`, `friendsConnection`, `appearsIn`, `primaryFunction`.",
+            ]
+        );
+    }
+
+    #[test]
+    fn fragments_on_unknown_types_are_reported() {
+        let diagnostics = validate_star_wars(
+            "query Hero { hero { __typename ...Vehicle } }\nfragment Vehicle on Vehicle { wheels }",
+        );
+
+        assert_eq!(
+            diagnostics,
+            vec!["2:1: Fragment Vehicle is defined on unknown type: Vehicle"]
+        );
+    }
+
+    #[test]
+    fn custom_directives_are_checked_against_their_definitions() {
+        let schema = ::sdl::parse_schema(
+            r#"
+            directive @cached(ttl: Int) repeatable on FIELD
+            directive @tag(name: String) on FIELD | FRAGMENT_SPREAD
+            directive @auth on FIELD_DEFINITION
+            type Query { viewer: String @auth }
+            "#,
+        )
+        .unwrap();
+        let schema = Schema::from(&schema);
+        let query = graphql_parser::parse_query(
+            r#"
+query Viewer @tag(name: "a") {
+  viewer @cached(ttl: 1) @cached(ttl: 2) @tag(name: "b") @tag(name: "c") @unknown
+  again: viewer @auth @cached(seconds: 3)
+}
+"#,
+        )
+        .unwrap();
+
+        let diagnostics: Vec<String> = validate_document(&query, &schema)
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect();
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                "2:14: Directive `@tag` cannot be used on QUERY. Allowed locations: FIELD, FRAGMENT_SPREAD.",
+                "3:58: Directive `@tag` is not repeatable, but it is used more than once here.",
+                "4:17: Directive `@auth` cannot be used on FIELD. Allowed locations: FIELD_DEFINITION.",
+                "4:23: Unknown argument `seconds` on directive `@cached`.",
+            ]
+        );
+    }
+
+    #[test]
+    fn selection_sets_must_match_the_field_types() {
+        let diagnostics = validate_star_wars(
+            "query Hero {\n  hero { __typename name { length } appearsIn { value } friends }\n  human(id: \"1\") { mass }\n}",
+        );
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                "2:21: Field `name` on `Character` is of the scalar type `String`, it cannot have a selection set.",
+                "2:37: Field `appearsIn` on `Character` is of the enum type `Episode`, it cannot have a selection set.",
+                "2:57: Field `friends` on `Character` is of type `Character`, it needs a selection set listing the fields to fetch, like `friends { ... }`.",
+            ]
+        );
+    }
+
+    #[test]
+    fn arguments_are_checked_against_the_schema() {
+        let diagnostics = validate_star_wars(
+            r#"query Arguments($episode: Episode, $id: ID, $stars: Int = 5, $ids: [ID!]!) {
+  hero(episode: "JEDI", unknown: 1) { __typename name }
+  reviews(episode: $episode) { stars }
+  human { name }
+  droid(id: $id) { name }
+  character(id: 12) { __typename friendsConnection(first: 1.5) { totalCount } }
+  starship(id: $ids) { name }
+}
+
+mutation Review($stars: Int = 5) {
+  createReview(episode: EMPIRE, review: {stars: $stars, commentary: null, favorite_color: {red: 1, green: 2}}) { stars }
+  other: createReview(review: {stars: 1, rating: 2}) { stars }
+}"#,
+        );
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                "2:3: Argument `episode` of `Query.hero`: expected `Episode`, found `\"JEDI\"`.",
+                "2:3: Argument `unknown` of `Query.hero`: it is not defined by the field. Available arguments: `episode`.",
+                "3:3: Argument `episode` of `Query.reviews`: the variable `$episode` of type `Episode` cannot be used where `Episode!` is expected.",
+                "4:3: Missing required argument `id` of type `ID!` on `Query.human`.",
+                "5:3: Argument `id` of `Query.droid`: the variable `$id` of type `ID` cannot be used where `ID!` is expected.",
+                "6:34: Argument `first` of `Character.friendsConnection`: expected `Int`, found `1.5`.",
+                "7:3: Argument `id` of `Query.starship`: the variable `$ids` of type `[ID!]!` cannot be used where `ID!` is expected.",
+                "11:3: Argument `review` of `Mutation.createReview`: missing required fields of the input `ColorInput`: `blue`.",
+                "12:3: Argument `review` of `Mutation.createReview`: the input `ReviewInput` has no field `rating`.",
+            ]
+        );
+    }
+
+    #[test]
+    fn argument_diagnostics_follow_fragments() {
+        let schema =
+            graphql_parser::parse_schema(include_str!("tests/star_wars_schema.graphql")).unwrap();
+        let schema = Schema::from(&schema);
+        let query = graphql_parser::parse_query(
+            "query Reviews($episode: Episode) { ...Reviews }\nfragment Reviews on Query { reviews(episode: $episode) { stars } }",
+        )
+        .unwrap();
+
+        let diagnostics: Vec<String> = argument_diagnostics(&query, &schema)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec!["2:29: Argument `episode` of `Query.reviews`: the variable `$episode` of type `Episode` cannot be used where `Episode!` is expected."]
+        );
+    }
+
+    #[test]
+    fn undefined_and_mistyped_variables_are_reported() {
+        let diagnostics = validate_star_wars(
+            r#"query Hero($withName: Boolean) {
+  hero(episode: $episode) { __typename name @include(if: $withName) id @skip(if: $hidden) }
+}
+
+mutation Review {
+  createReview(review: {stars: $stars}) { stars }
+}"#,
+        );
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                "2:3: Argument `episode` of `Query.hero`: the variable `$episode` is not defined by the operation `Hero`.",
+                "2:45: Argument `if` of directive `@include`: the variable `$withName` of type `Boolean` cannot be used where `Boolean!` is expected.",
+                "2:72: Argument `if` of directive `@skip`: the variable `$hidden` is not defined by the operation `Hero`.",
+                "6:3: Argument `review` of `Mutation.createReview`: the variable `$stars` is not defined by the operation `Review`.",
+            ]
+        );
+
+        let schema =
+            graphql_parser::parse_schema(include_str!("tests/star_wars_schema.graphql")).unwrap();
+        let schema = Schema::from(&schema);
+        let query = graphql_parser::parse_query(
+            "query Droid { ...DroidFields }\nfragment DroidFields on Query { droid(id: $id) { name } }",
+        )
+        .unwrap();
+        let diagnostics: Vec<String> = argument_diagnostics(&query, &schema)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec!["2:33: Argument `id` of `Query.droid`: the variable `$id` is not defined by the operation `Droid`."]
+        );
+    }
+
+    #[test]
+    fn validate_reports_parse_errors() {
+        let schema_path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/star_wars_schema.graphql");
+        let diagnostics = validate("query Hero { hero { ", &schema_path);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].position, None);
+    }
+}
@@ -1,5 +1,6 @@
 use field_type::FieldType;
 use graphql_parser;
+use heck::ShoutySnakeCase;
 use proc_macro2::{Ident, Span, TokenStream};
 use query::QueryContext;
 use std::collections::BTreeMap;
@@ -36,6 +37,56 @@ impl<'query> Variable<'query> {
     }
 }
 
+/// The names of the struct describing a variable and of the `VARIABLES` constant, prefixed with the operation name in modules with several operations.
+pub(crate) fn variable_specs_names(operation_prefix: &str) -> (Ident, Ident) {
+    let struct_name = Ident::new(
+        &format!("{}VariableSpec", operation_prefix),
+        Span::call_site(),
+    );
+    let const_name = if operation_prefix.is_empty() {
+        Ident::new("VARIABLES", Span::call_site())
+    } else {
+        Ident::new(
+            &format!("{}_VARIABLES", operation_prefix.to_shouty_snake_case()),
+            Span::call_site(),
+        )
+    };
+    (struct_name, const_name)
+}
+
+/// The `VARIABLES` constant describing the variables of the operation in declaration order, so middleware can validate or log variables, and forms can be built from them, without knowing the operation.
+pub(crate) fn variable_specs(variables: &[Variable], operation_prefix: &str) -> TokenStream {
+    let (struct_name, const_name) = variable_specs_names(operation_prefix);
+    let specs = variables.iter().map(|variable| {
+        let name = variable.name;
+        let type_ = variable.ty.to_graphql();
+        let has_default = variable.default.is_some();
+        quote! {
+            #struct_name {
+                name: #name,
+                type_: #type_,
+                has_default: #has_default,
+            }
+        }
+    });
+
+    quote! {
+        /// A variable of the operation.
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::marker::Copy, ::std::cmp::PartialEq, ::std::cmp::Eq)]
+        pub struct #struct_name {
+            /// The name of the variable, without the `$`.
+            pub name: &'static str,
+            /// The type of the variable in GraphQL syntax, like `[Episode!]`.
+            pub type_: &'static str,
+            /// Whether the variable has a default value in the query.
+            pub has_default: bool,
+        }
+
+        /// The variables of the operation, in declaration order.
+        pub const #const_name: &'static [#struct_name] = &[#(#specs,)*];
+    }
+}
+
 impl<'query> ::std::convert::From<&'query graphql_parser::query::VariableDefinition>
     for Variable<'query>
 {
@@ -64,7 +115,7 @@ fn graphql_parser_value_to_literal(
                 quote!(false)
             }
         }
-        Value::String(s) => quote!(#s.to_string()),
+        Value::String(s) => quote!(::std::string::ToString::to_string(#s)),
         Value::Variable(_) => panic!("variable in variable"),
         Value::Null => panic!("null as default value"),
         Value::Float(f) => quote!(#f),
@@ -78,7 +129,7 @@ fn graphql_parser_value_to_literal(
                 .iter()
                 .map(|val| graphql_parser_value_to_literal(val, context, ty, false));
             quote! {
-                vec![
+                ::std::vec![
                     #(#elements,)*
                 ]
             }
@@ -87,7 +138,7 @@ fn graphql_parser_value_to_literal(
     };
 
     if is_optional {
-        quote!(Some(#inner))
+        quote!(::std::option::Option::Some(#inner))
     } else {
         inner
     }
@@ -121,7 +172,7 @@ fn render_object_literal(
                     );
                     quote!(#field_name: #value)
                 }
-                None => quote!(#field_name: None),
+                None => quote!(#field_name: ::std::option::Option::None),
             }
         })
         .collect();
@@ -130,3 +181,30 @@ fn render_object_literal(
         #(#fields,)*
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codegen::all_operations;
+    use graphql_parser;
+
+    #[test]
+    fn variable_specs_describe_each_variable() {
+        let query = graphql_parser::parse_query(
+            "query Heroes($episode: Episode = JEDI, $ids: [ID!]!) { hero { name } }",
+        )
+        .unwrap();
+        let operations = all_operations(&query);
+
+        let generated = variable_specs(&operations[0].variables, "").to_string();
+
+        assert!(generated.contains(
+            "pub const VARIABLES : & 'static [ VariableSpec ] = & [ \
+             VariableSpec { name : \"episode\" , type_ : \"Episode\" , has_default : true , } , \
+             VariableSpec { name : \"ids\" , type_ : \"[ID!]!\" , has_default : false , } , ] ;"
+        ));
+        assert!(variable_specs(&operations[0].variables, "Heroes")
+            .to_string()
+            .contains("pub const HEROES_VARIABLES : & 'static [ HeroesVariableSpec ]"));
+    }
+}
@@ -0,0 +1,487 @@
+use client_fields::is_client_field;
+use constants::TYPENAME_FIELD;
+use deprecation::DeprecationStatus;
+use failure;
+use field_type::FieldType;
+use graphql_parser::query;
+use operations::OperationType;
+use schema::Schema;
+use std::collections::BTreeMap;
+use std::path::Path;
+use validation::Position;
+
+/// The kinds of types of a schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeKind {
+    /// A built-in or custom scalar.
+    Scalar,
+    /// An object type.
+    Object,
+    /// An interface.
+    Interface,
+    /// A union.
+    Union,
+    /// An enum.
+    Enum,
+    /// An input object.
+    InputObject,
+}
+
+/// The kinds of operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    /// A query, including the anonymous `{ ... }` shorthand.
+    Query,
+    /// A mutation.
+    Mutation,
+    /// A subscription.
+    Subscription,
+}
+
+/// An operation of the query document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationInfo<'a> {
+    /// The name of the operation, `None` for anonymous operations.
+    pub name: Option<&'a str>,
+    /// Whether it is a query, a mutation or a subscription.
+    pub kind: OperationKind,
+    /// The root type the selection is on, like `Query`.
+    pub root_type: &'a str,
+    /// Where the operation starts in the query document.
+    pub position: Position,
+}
+
+/// A variable of an operation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableInfo<'a> {
+    /// The name of the variable, without the `$`.
+    pub name: &'a str,
+    /// The type in GraphQL syntax, like `[ID!]!`.
+    pub graphql_type: String,
+    /// The named type inside the list and non-null wrappers, like `ID`.
+    pub named_type: &'a str,
+    /// The kind of the named type, `None` if the schema does not define it.
+    pub kind: Option<TypeKind>,
+    /// Whether the variable has a default value.
+    pub has_default: bool,
+    /// Where the variable is defined.
+    pub position: Position,
+}
+
+/// A selected field, resolved against the schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInfo<'a> {
+    /// The type the field is selected on.
+    pub parent_type: &'a str,
+    /// The name of the field in the schema.
+    pub name: &'a str,
+    /// The alias of the field, if it has one.
+    pub alias: Option<&'a str>,
+    /// The type of the field in GraphQL syntax, like `[Character]`.
+    pub graphql_type: String,
+    /// The named type inside the list and non-null wrappers, like `Character`.
+    pub named_type: &'a str,
+    /// The kind of the named type, `None` if the schema does not define it.
+    pub kind: Option<TypeKind>,
+    /// The description of the field in the schema.
+    pub description: Option<&'a str>,
+    /// Whether the field is deprecated.
+    pub deprecation: &'a DeprecationStatus,
+    /// Whether the field is resolved on the client (`@client`).
+    pub is_client: bool,
+    /// The response keys from the operation or fragment to the field, the field's included.
+    pub path: Vec<&'a str>,
+    /// Where the field is selected.
+    pub position: Position,
+}
+
+impl<'a> FieldInfo<'a> {
+    /// The key of the field in the response: its alias, or its name.
+    pub fn response_key(&self) -> &'a str {
+        self.alias.unwrap_or(self.name)
+    }
+}
+
+/// A fragment definition, inline fragment or fragment spread.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FragmentInfo<'a> {
+    /// The name of the fragment, `None` for inline fragments.
+    pub name: Option<&'a str>,
+    /// The type the fragment applies to, `None` for inline fragments without a type condition and spreads of unknown fragments.
+    pub type_condition: Option<&'a str>,
+    /// Where the fragment is defined or spread.
+    pub position: Position,
+}
+
+/// Callbacks for the parts of a query document, in document order. Every method does nothing by default, so visitors implement those they need.
+///
+/// The selections of spread fragments are visited with the fragment definitions rather than at each spread.
+pub trait QueryVisitor {
+    /// Called before the variables and selections of an operation.
+    fn enter_operation(&mut self, _operation: &OperationInfo) {}
+    /// Called after the variables and selections of an operation.
+    fn leave_operation(&mut self, _operation: &OperationInfo) {}
+    /// Called for each variable of an operation.
+    fn visit_variable(&mut self, _variable: &VariableInfo) {}
+    /// Called before the selections of a fragment definition.
+    fn enter_fragment(&mut self, _fragment: &FragmentInfo) {}
+    /// Called after the selections of a fragment definition.
+    fn leave_fragment(&mut self, _fragment: &FragmentInfo) {}
+    /// Called before the selections of a field.
+    fn enter_field(&mut self, _field: &FieldInfo) {}
+    /// Called after the selections of a field.
+    fn leave_field(&mut self, _field: &FieldInfo) {}
+    /// Called before the selections of an inline fragment.
+    fn enter_inline_fragment(&mut self, _fragment: &FragmentInfo) {}
+    /// Called after the selections of an inline fragment.
+    fn leave_inline_fragment(&mut self, _fragment: &FragmentInfo) {}
+    /// Called for each fragment spread.
+    fn visit_fragment_spread(&mut self, _spread: &FragmentInfo) {}
+}
+
+/// Walk the query document `query` with `visitor`, resolving the selected fields against the schema at `schema_path`.
+///
+/// Fields the schema does not define are an error, except `@client` fields, which are visited without their selections when no client schema defines them. Run [validation::validate](../validation/fn.validate.html) first for a report of every problem.
+pub fn visit(
+    query: &str,
+    schema_path: &Path,
+    visitor: &mut dyn QueryVisitor,
+) -> Result<(), failure::Error> {
+    let query = ::string_literals::parse_query(query)?;
+    let parsed_schema = ::load_schema(schema_path, None)?;
+    let schema = Schema::from(&*parsed_schema);
+
+    visit_document(&query, &schema, visitor)
+}
+
+pub(crate) fn visit_document(
+    query: &query::Document,
+    schema: &Schema,
+    visitor: &mut dyn QueryVisitor,
+) -> Result<(), failure::Error> {
+    let fragments: BTreeMap<&str, &str> = query
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            query::Definition::Fragment(fragment) => {
+                let query::TypeCondition::On(ref on) = fragment.type_condition;
+                Some((fragment.name.as_str(), on.as_str()))
+            }
+            query::Definition::Operation(_) => None,
+        })
+        .collect();
+
+    for definition in &query.definitions {
+        match definition {
+            query::Definition::Operation(operation) => {
+                visit_operation(operation, schema, &fragments, visitor)?
+            }
+            query::Definition::Fragment(fragment) => {
+                let query::TypeCondition::On(ref on) = fragment.type_condition;
+                let info = FragmentInfo {
+                    name: Some(&fragment.name),
+                    type_condition: Some(on),
+                    position: fragment.position.into(),
+                };
+                visitor.enter_fragment(&info);
+                visit_selection_set(
+                    on,
+                    &fragment.selection_set,
+                    &mut Vec::new(),
+                    schema,
+                    &fragments,
+                    visitor,
+                )?;
+                visitor.leave_fragment(&info);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn visit_operation(
+    operation: &query::OperationDefinition,
+    schema: &Schema,
+    fragments: &BTreeMap<&str, &str>,
+    visitor: &mut dyn QueryVisitor,
+) -> Result<(), failure::Error> {
+    use graphql_parser::query::OperationDefinition::*;
+
+    let (name, kind, variables, selection_set, position) = match operation {
+        SelectionSet(selection_set) => (
+            None,
+            OperationKind::Query,
+            &[][..],
+            selection_set,
+            selection_set.span.0,
+        ),
+        Query(query) => (
+            query.name.as_ref(),
+            OperationKind::Query,
+            &query.variable_definitions[..],
+            &query.selection_set,
+            query.position,
+        ),
+        Mutation(mutation) => (
+            mutation.name.as_ref(),
+            OperationKind::Mutation,
+            &mutation.variable_definitions[..],
+            &mutation.selection_set,
+            mutation.position,
+        ),
+        Subscription(subscription) => (
+            subscription.name.as_ref(),
+            OperationKind::Subscription,
+            &subscription.variable_definitions[..],
+            &subscription.selection_set,
+            subscription.position,
+        ),
+    };
+    let operation_type = match kind {
+        OperationKind::Query => OperationType::Query,
+        OperationKind::Mutation => OperationType::Mutation,
+        OperationKind::Subscription => OperationType::Subscription,
+    };
+    let root_type = schema
+        .root_type(&operation_type)
+        .unwrap_or_else(|| operation_type.conventional_root_name());
+    let info = OperationInfo {
+        name: name.map(String::as_str),
+        kind,
+        root_type,
+        position: position.into(),
+    };
+
+    visitor.enter_operation(&info);
+    for variable in variables {
+        let var_type = FieldType::from(&variable.var_type);
+        let named_type = var_type.inner_name_str();
+        visitor.visit_variable(&VariableInfo {
+            name: &variable.name,
+            graphql_type: var_type.to_graphql(),
+            named_type,
+            kind: type_kind(schema, named_type),
+            has_default: variable.default_value.is_some(),
+            position: variable.position.into(),
+        });
+    }
+    visit_selection_set(
+        root_type,
+        selection_set,
+        &mut Vec::new(),
+        schema,
+        fragments,
+        visitor,
+    )?;
+    visitor.leave_operation(&info);
+    Ok(())
+}
+
+fn visit_selection_set<'query>(
+    parent_type: &str,
+    selection_set: &'query query::SelectionSet,
+    path: &mut Vec<&'query str>,
+    schema: &Schema,
+    fragments: &BTreeMap<&str, &str>,
+    visitor: &mut dyn QueryVisitor,
+) -> Result<(), failure::Error> {
+    for item in &selection_set.items {
+        match item {
+            query::Selection::Field(field) => {
+                let is_client = is_client_field(&field.directives);
+                let typename;
+                let schema_field = if field.name == TYPENAME_FIELD {
+                    typename = ::constants::typename_field();
+                    &typename
+                } else {
+                    match schema.field(parent_type, &field.name) {
+                        Some(schema_field) => schema_field,
+                        None if is_client => continue,
+                        None => {
+                            return Err(format_err!(
+                                "Could not find field `{}` on `{}`",
+                                field.name,
+                                parent_type
+                            ))
+                        }
+                    }
+                };
+                let named_type = schema_field.type_.inner_name_str();
+
+                path.push(field.alias.as_ref().unwrap_or(&field.name));
+                let info = FieldInfo {
+                    parent_type,
+                    name: &field.name,
+                    alias: field.alias.as_deref(),
+                    graphql_type: schema_field.type_.to_graphql(),
+                    named_type,
+                    kind: type_kind(schema, named_type),
+                    description: schema_field.description,
+                    deprecation: &schema_field.deprecation,
+                    is_client,
+                    path: path.clone(),
+                    position: field.position.into(),
+                };
+                visitor.enter_field(&info);
+                visit_selection_set(
+                    named_type,
+                    &field.selection_set,
+                    path,
+                    schema,
+                    fragments,
+                    visitor,
+                )?;
+                visitor.leave_field(&info);
+                path.pop();
+            }
+            query::Selection::InlineFragment(inline) => {
+                let type_condition = inline.type_condition.as_ref().map(|condition| {
+                    let query::TypeCondition::On(ref on) = condition;
+                    on.as_str()
+                });
+                let info = FragmentInfo {
+                    name: None,
+                    type_condition,
+                    position: inline.position.into(),
+                };
+                visitor.enter_inline_fragment(&info);
+                visit_selection_set(
+                    type_condition.unwrap_or(parent_type),
+                    &inline.selection_set,
+                    path,
+                    schema,
+                    fragments,
+                    visitor,
+                )?;
+                visitor.leave_inline_fragment(&info);
+            }
+            query::Selection::FragmentSpread(spread) => {
+                visitor.visit_fragment_spread(&FragmentInfo {
+                    name: Some(&spread.fragment_name),
+                    type_condition: fragments.get(spread.fragment_name.as_str()).cloned(),
+                    position: spread.position.into(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn type_kind(schema: &Schema, type_name: &str) -> Option<TypeKind> {
+    if schema.contains_scalar(type_name) {
+        Some(TypeKind::Scalar)
+    } else if schema.objects.contains_key(type_name) {
+        Some(TypeKind::Object)
+    } else if schema.interfaces.contains_key(type_name) {
+        Some(TypeKind::Interface)
+    } else if schema.unions.contains_key(type_name) {
+        Some(TypeKind::Union)
+    } else if schema.enums.contains_key(type_name) {
+        Some(TypeKind::Enum)
+    } else if schema.inputs.contains_key(type_name) {
+        Some(TypeKind::InputObject)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+    }
+
+    impl QueryVisitor for Recorder {
+        fn enter_operation(&mut self, operation: &OperationInfo) {
+            self.events.push(format!(
+                "operation {:?} on {}",
+                operation.name, operation.root_type
+            ));
+        }
+
+        fn visit_variable(&mut self, variable: &VariableInfo) {
+            self.events.push(format!(
+                "${}: {} ({:?})",
+                variable.name, variable.graphql_type, variable.kind
+            ));
+        }
+
+        fn enter_fragment(&mut self, fragment: &FragmentInfo) {
+            self.events.push(format!(
+                "fragment {:?} on {:?}",
+                fragment.name, fragment.type_condition
+            ));
+        }
+
+        fn enter_field(&mut self, field: &FieldInfo) {
+            self.events.push(format!(
+                "{} {}.{}: {} ({:?})",
+                field.path.join("."),
+                field.parent_type,
+                field.name,
+                field.graphql_type,
+                field.kind
+            ));
+        }
+
+        fn enter_inline_fragment(&mut self, fragment: &FragmentInfo) {
+            self.events
+                .push(format!("... on {:?}", fragment.type_condition));
+        }
+
+        fn visit_fragment_spread(&mut self, spread: &FragmentInfo) {
+            self.events.push(format!(
+                "...{:?} on {:?}",
+                spread.name, spread.type_condition
+            ));
+        }
+    }
+
+    #[test]
+    fn fields_are_visited_with_their_schema_types() {
+        let schema =
+            graphql_parser::parse_schema(include_str!("tests/star_wars_schema.graphql")).unwrap();
+        let schema = Schema::from(&schema);
+        let query = graphql_parser::parse_query(
+            r#"
+            query Heroes($episode: Episode) {
+              hero(episode: $episode) {
+                __typename
+                friends { name }
+                ... on Droid { primaryFunction }
+                ...HeroId
+              }
+            }
+
+            fragment HeroId on Character { id }
+            "#,
+        )
+        .unwrap();
+
+        let mut recorder = Recorder::default();
+        visit_document(&query, &schema, &mut recorder).unwrap();
+
+        assert_eq!(
+            recorder.events,
+            vec![
+                "operation Some(\"Heroes\") on Query",
+                "$episode: Episode (Some(Enum))",
+                "hero Query.hero: Character (Some(Interface))",
+                "hero.__typename Character.__typename: String! (Some(Scalar))",
+                "hero.friends Character.friends: [Character] (Some(Interface))",
+                "hero.friends.name Character.name: String! (Some(Scalar))",
+                "... on Some(\"Droid\")",
+                "hero.primaryFunction Droid.primaryFunction: String (Some(Scalar))",
+                "...Some(\"HeroId\") on Some(\"Character\")",
+                "fragment Some(\"HeroId\") on Some(\"Character\")",
+                "id Character.id: ID! (Some(Scalar))",
+            ]
+        );
+
+        let query = graphql_parser::parse_query("{ hero { height } }").unwrap();
+        assert!(visit_document(&query, &schema, &mut Recorder::default()).is_err());
+    }
+}